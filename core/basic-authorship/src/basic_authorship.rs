@@ -231,8 +231,11 @@ impl<Block, C, A> Proposer<Block, C, A>	where
 					match block_builder.push_extrinsic(pending.data.clone()) {
 						Ok(()) => {
 							debug!("[{:?}] Pushed to the block.", pending.hash);
+							is_first = false;
 						}
 						Err(error::Error::ApplyExtrinsicFailed(ApplyError::FullBlock)) => {
+							// The block is full either by length or by weight. A transaction that
+							// doesn't even fit in a block without any other transactions never will.
 							if is_first {
 								debug!("[{:?}] Invalid transaction: FullBlock on empty block", pending.hash);
 								unqueue_invalid.push(pending.hash.clone());
@@ -252,8 +255,6 @@ impl<Block, C, A> Proposer<Block, C, A>	where
 							unqueue_invalid.push(pending.hash.clone());
 						}
 					}
-
-					is_first = false;
 				}
 
 				self.transaction_pool.remove_invalid(&unqueue_invalid);
@@ -306,6 +306,15 @@ fn fill_transaction_pool_configuration<F: ServiceFactory>(
 	options.transaction_pool.future.count = params.pool_limit / factor;
 	options.transaction_pool.future.total_bytes = params.pool_kbytes * 1024 / factor;
 
+	// per-sender limits
+	options.transaction_pool.per_sender.ready = params.pool_sender_limit;
+	options.transaction_pool.per_sender.future = params.pool_sender_future_limit;
+
+	// replacement policy
+	options.transaction_pool.min_priority_bump = params.pool_replacement_bump;
+
+	options.transaction_pool_journal = params.pool_journal;
+
 	Ok(())
 }
 
@@ -375,6 +384,11 @@ where
 	let mut config = service::Configuration::default_with_spec(spec.clone());
 	if cli.interactive_password {
 		config.password = input_keystore_password()?
+	} else if let Some(ref path) = cli.password_filename {
+		config.password = fs::read_to_string(path)
+			.map_err(|e| format!("Unable to read password file: {:?}", e))?
+			.trim_end_matches(|c| c == '\r' || c == '\n')
+			.into();
 	}
 
 	config.impl_name = impl_name;
@@ -415,6 +429,7 @@ where
 			s.parse().map_err(|_| error::Error::Input("Invalid pruning mode specified".to_string()))?
 		),
 	};
+	config.transaction_index = !cli.no_transaction_index;
 
 	let role =
 		if cli.light {
@@ -425,6 +440,9 @@ where
 			service::Roles::FULL
 		};
 
+	config.warp_sync = cli.warp_sync;
+	config.fast_sync = cli.fast_sync;
+
 	let exec = cli.execution_strategies;
 	config.execution_strategies = ExecutionStrategies {
 		syncing: exec.syncing_execution.into(),
@@ -443,6 +461,7 @@ where
 
 	config.roles = role;
 	config.disable_grandpa = cli.no_grandpa;
+	config.sealing = cli.sealing.map(Into::into);
 
 	let is_dev = cli.shared_params.dev;
 
@@ -482,7 +501,20 @@ where
 	config.rpc_ws = Some(
 		parse_address(&format!("{}:{}", ws_interface, 9944), cli.ws_port)?
 	);
+	config.rpc_ipc = cli.ipc_path;
+	config.rpc_ipc_max_subscriptions = cli.ipc_max_subscriptions;
+	config.rpc_ipc_rate_limit = cli.ipc_rate_limit;
 	config.rpc_ws_max_connections = cli.ws_max_connections;
+	config.rpc_ws_max_subscriptions = cli.ws_max_subscriptions;
+	config.rpc_http_rate_limit = cli.rpc_rate_limit;
+	config.rpc_ws_rate_limit = cli.ws_rate_limit;
+	config.rpc_methods = cli.rpc_methods.into();
+	if let Some(port) = cli.prometheus_port {
+		let prometheus_interface: &str = if cli.prometheus_external { "0.0.0.0" } else { "127.0.0.1" };
+		config.prometheus_endpoint = Some(
+			parse_address(&format!("{}:{}", prometheus_interface, 9615), Some(port))?
+		);
+	}
 	config.rpc_cors = cli.rpc_cors.unwrap_or_else(|| if is_dev {
 		log::warn!("Running in --dev mode, RPC CORS has been disabled.");
 		Cors::All
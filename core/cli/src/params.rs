@@ -128,6 +128,42 @@ pub struct NetworkConfigurationParams {
 	pub node_key_params: NodeKeyParams
 }
 
+arg_enum! {
+	/// Which RPC methods to expose
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum RpcMethods {
+		Safe,
+		Unsafe,
+	}
+}
+
+impl Into<service::RpcMethods> for RpcMethods {
+	fn into(self) -> service::RpcMethods {
+		match self {
+			RpcMethods::Safe => service::RpcMethods::Safe,
+			RpcMethods::Unsafe => service::RpcMethods::Unsafe,
+		}
+	}
+}
+
+arg_enum! {
+	/// How to author blocks on development chains
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum Sealing {
+		Manual,
+		Instant,
+	}
+}
+
+impl Into<service::Sealing> for Sealing {
+	fn into(self) -> service::Sealing {
+		match self {
+			Sealing::Manual => service::Sealing::Manual,
+			Sealing::Instant => service::Sealing::Instant,
+		}
+	}
+}
+
 arg_enum! {
 	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 	pub enum NodeKeyType {
@@ -220,6 +256,18 @@ pub struct TransactionPoolParams {
 	/// Maximum number of kilobytes of all transactions stored in the pool.
 	#[structopt(long = "pool-kbytes", value_name = "COUNT", default_value="10240")]
 	pub pool_kbytes: usize,
+	/// Maximum number of ready transactions from a single sender.
+	#[structopt(long = "pool-sender-limit", value_name = "COUNT", default_value = "64")]
+	pub pool_sender_limit: usize,
+	/// Maximum number of future transactions from a single sender.
+	#[structopt(long = "pool-sender-future-limit", value_name = "COUNT", default_value = "16")]
+	pub pool_sender_future_limit: usize,
+	/// Minimal priority increase (in percent) required to replace a transaction in the pool.
+	#[structopt(long = "pool-replacement-bump", value_name = "PERCENT", default_value = "10")]
+	pub pool_replacement_bump: u64,
+	/// Persist pending transactions in the database and restore them on restart.
+	#[structopt(long = "pool-journal")]
+	pub pool_journal: bool,
 }
 
 /// Execution strategies parameters.
@@ -305,10 +353,30 @@ pub struct RunCmd {
 	#[structopt(long = "no-grandpa")]
 	pub no_grandpa: bool,
 
+	/// Author blocks on request instead of running the consensus engines, for development
+	/// chains with a single node. `Manual` authors a block on each `engine_createBlock` RPC
+	/// call, `Instant` as soon as transactions enter the pool.
+	#[structopt(
+		long = "sealing",
+		value_name = "MODE",
+		raw(possible_values = "&Sealing::variants()", case_insensitive = "true")
+	)]
+	pub sealing: Option<Sealing>,
+
 	/// Experimental: Run in light client mode
 	#[structopt(long = "light")]
 	pub light: bool,
 
+	/// Warp sync to the latest finalized block using GRANDPA authority set change proofs,
+	/// when starting with an empty database
+	#[structopt(long = "warp-sync")]
+	pub warp_sync: bool,
+
+	/// Download the verified state of a recent block instead of executing the history,
+	/// when starting with an empty database
+	#[structopt(long = "fast-sync", conflicts_with = "warp-sync")]
+	pub fast_sync: bool,
+
 	/// Limit the memory the database cache can use
 	#[structopt(long = "db-cache", value_name = "MiB")]
 	pub database_cache_size: Option<u32>,
@@ -333,10 +401,59 @@ pub struct RunCmd {
 	#[structopt(long = "ws-port", value_name = "PORT")]
 	pub ws_port: Option<u16>,
 
+	/// Specify the path of the IPC RPC server socket. The server is disabled if not given
+	#[structopt(long = "ipc-path", value_name = "PATH", parse(from_os_str))]
+	pub ipc_path: Option<PathBuf>,
+
+	/// Maximum number of subscriptions each IPC RPC connection may hold. Default is 1024.
+	#[structopt(long = "ipc-max-subscriptions", value_name = "COUNT")]
+	pub ipc_max_subscriptions: Option<usize>,
+
+	/// Maximum number of RPC calls per second accepted on each IPC RPC connection.
+	/// Default is unlimited.
+	#[structopt(long = "ipc-rate-limit", value_name = "CALLS")]
+	pub ipc_rate_limit: Option<u32>,
+
+	/// Specify Prometheus metrics server TCP port. The server is disabled if not given
+	#[structopt(long = "prometheus-port", value_name = "PORT")]
+	pub prometheus_port: Option<u16>,
+
+	/// Listen to all Prometheus metrics server interfaces (default is local)
+	#[structopt(long = "prometheus-external")]
+	pub prometheus_external: bool,
+
 	/// Maximum number of WS RPC server connections.
 	#[structopt(long = "ws-max-connections", value_name = "COUNT")]
 	pub ws_max_connections: Option<usize>,
 
+	/// Maximum number of subscriptions each WS RPC connection may hold. Default is 1024.
+	#[structopt(long = "ws-max-subscriptions", value_name = "COUNT")]
+	pub ws_max_subscriptions: Option<usize>,
+
+	/// Maximum number of RPC calls per second accepted by the HTTP RPC server.
+	/// Default is unlimited.
+	#[structopt(long = "rpc-rate-limit", value_name = "CALLS")]
+	pub rpc_rate_limit: Option<u32>,
+
+	/// Maximum number of RPC calls per second accepted on each WS RPC connection.
+	/// Default is unlimited.
+	#[structopt(long = "ws-rate-limit", value_name = "CALLS")]
+	pub ws_rate_limit: Option<u32>,
+
+	/// Which RPC methods to expose. `Safe` exposes unsafe methods, e.g. the ones managing
+	/// reserved peers, only on servers listening on a local interface. `Unsafe` exposes them
+	/// on every interface.
+	#[structopt(
+		long = "rpc-methods",
+		value_name = "METHODS",
+		raw(
+			possible_values = "&RpcMethods::variants()",
+			case_insensitive = "true",
+			default_value = r#""Safe""#
+		)
+	)]
+	pub rpc_methods: RpcMethods,
+
 	/// Specify browser Origins allowed to access the HTTP & WS RPC servers.
 	/// It's a comma-separated list of origins (protocol://domain or special `null` value).
 	/// Value of `all` will disable origin validation.
@@ -349,6 +466,11 @@ pub struct RunCmd {
 	#[structopt(long = "pruning", value_name = "PRUNING_MODE")]
 	pub pruning: Option<String>,
 
+	/// Don't maintain the index of extrinsics by hash, which is required by
+	/// `chain_getExtrinsicByHash`. Blocks imported while the index is disabled are not indexed.
+	#[structopt(long = "no-transaction-index")]
+	pub no_transaction_index: bool,
+
 	/// The human-readable name for this node, as reported to the telemetry server, if enabled
 	#[structopt(long = "name", value_name = "NAME")]
 	pub name: Option<String>,
@@ -403,6 +525,15 @@ pub struct RunCmd {
 	/// Interactive password for validator key.
 	#[structopt(short = "i")]
 	pub interactive_password: bool,
+
+	/// File containing the password of the keystore.
+	#[structopt(
+		long = "password-filename",
+		value_name = "PATH",
+		parse(from_os_str),
+		conflicts_with = "interactive_password"
+	)]
+	pub password_filename: Option<PathBuf>,
 }
 
 /// Stores all required Cli values for a keyring test account.
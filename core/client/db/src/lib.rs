@@ -25,15 +25,19 @@
 //! Finality implies canonicality but not vice-versa.
 
 pub mod light;
+pub mod offchain;
 
 mod cache;
+mod metrics;
 mod storage_cache;
+mod transaction_index;
 mod utils;
 
 use std::sync::Arc;
 use std::path::PathBuf;
 use std::io;
 use std::collections::HashMap;
+use std::time::Instant;
 
 use client::backend::NewBlockState;
 use client::blockchain::HeaderBackend;
@@ -60,7 +64,7 @@ use client::children;
 use state_db::StateDb;
 use consensus_common::well_known_cache_keys;
 use crate::storage_cache::{CachingState, SharedCache, new_shared_cache};
-use log::{trace, debug, warn};
+use log::{trace, debug, info, warn};
 pub use state_db::PruningMode;
 
 #[cfg(feature = "test-helpers")]
@@ -68,6 +72,7 @@ use client::in_mem::Backend as InMemoryBackend;
 
 const CANONICALIZATION_DELAY: u64 = 4096;
 const MIN_BLOCKS_TO_KEEP_CHANGES_TRIES_FOR: u32 = 32768;
+const TRANSACTION_INDEX_BUILD_BATCH: usize = 1024;
 
 /// DB-backed patricia trie state, transaction type is an overlay of changes to commit.
 pub type DbState = state_machine::TrieBackend<Arc<state_machine::Storage<Blake2Hasher>>, Blake2Hasher>;
@@ -82,6 +87,11 @@ pub struct DatabaseSettings {
 	pub path: PathBuf,
 	/// Pruning mode.
 	pub pruning: PruningMode,
+	/// Maintain an index of the extrinsics of canonical blocks by extrinsic hash.
+	///
+	/// When enabled on an existing database, the blocks already imported are indexed in
+	/// batches along with the next block imports.
+	pub transaction_index: bool,
 }
 
 /// Create an instance of db-backed client.
@@ -112,6 +122,10 @@ mod columns {
 	pub const JUSTIFICATION: Option<u32> = Some(6);
 	pub const CHANGES_TRIE: Option<u32> = Some(7);
 	pub const AUX: Option<u32> = Some(8);
+	/// Offchain workers local storage
+	pub const OFFCHAIN: Option<u32> = Some(9);
+	/// maps extrinsic hashes to the canonical block that includes them and their index in it.
+	pub const TRANSACTION_INDEX: Option<u32> = Some(10);
 }
 
 struct PendingBlock<Block: BlockT> {
@@ -260,6 +274,10 @@ impl<Block: BlockT> client::blockchain::Backend<Block> for BlockchainDb<Block> {
 	fn children(&self, parent_hash: Block::Hash) -> Result<Vec<Block::Hash>, client::error::Error> {
 		children::read_children(&*self.db, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash)
 	}
+
+	fn transaction_index(&self, hash: Block::Hash) -> Result<Option<(Block::Hash, u32)>, client::error::Error> {
+		transaction_index::read::<Block>(&*self.db, columns::TRANSACTION_INDEX, hash)
+	}
 }
 
 impl<Block: BlockT> client::blockchain::ProvideCache<Block> for BlockchainDb<Block> {
@@ -278,6 +296,8 @@ pub struct BlockImportOperation<Block: BlockT, H: Hasher> {
 	aux_ops: Vec<(Vec<u8>, Option<Vec<u8>>)>,
 	finalized_blocks: Vec<(BlockId<Block>, Option<Justification>)>,
 	set_head: Option<BlockId<Block>>,
+	imported_state: bool,
+	started: Instant,
 }
 
 impl<Block: BlockT, H: Hasher> BlockImportOperation<Block, H> {
@@ -352,6 +372,12 @@ where Block: BlockT<Hash=H256>,
 		Ok(root)
 	}
 
+	fn import_state(&mut self, top: StorageOverlay, children: ChildrenStorageOverlay) -> Result<H256, client::error::Error> {
+		let root = self.reset_storage(top, children)?;
+		self.imported_state = true;
+		Ok(root)
+	}
+
 	fn update_changes_trie(&mut self, update: MemoryDB<Blake2Hasher>) -> Result<(), client::error::Error> {
 		self.changes_trie_updates = update;
 		Ok(())
@@ -554,6 +580,18 @@ where
 	}
 }
 
+/// Progress of building the transaction index, persisted under `meta_keys::TRANSACTION_INDEX`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TransactionIndexState {
+	/// Entries left from a previous time the index was enabled are being deleted.
+	Clearing,
+	/// The canonical blocks `next..=end` are still being indexed, later ones are indexed as
+	/// they are imported.
+	Building { next: u64, end: u64 },
+	/// The index covers the canonical chain.
+	Built,
+}
+
 /// Disk backend. Keeps data in a key-value store. In archive mode, trie nodes are kept from all blocks.
 /// Otherwise, trie nodes are kept only from some recent blocks.
 pub struct Backend<Block: BlockT> {
@@ -562,9 +600,12 @@ pub struct Backend<Block: BlockT> {
 	/// None<*> means that the value hasn't been cached yet. Some(*) means that the value (either None or
 	/// Some(*)) has been cached and is valid.
 	changes_trie_config: Mutex<Option<Option<ChangesTrieConfiguration>>>,
+	offchain_storage: offchain::LocalStorage,
 	blockchain: BlockchainDb<Block>,
 	canonicalization_delay: u64,
 	shared_cache: SharedCache<Block, Blake2Hasher>,
+	/// `None` if the transaction index is disabled.
+	transaction_index: Option<Mutex<TransactionIndexState>>,
 }
 
 impl<Block: BlockT<Hash=H256>> Backend<Block> {
@@ -578,14 +619,26 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 	#[cfg(feature = "kvdb-rocksdb")]
 	fn new_inner(config: DatabaseSettings, canonicalization_delay: u64) -> Result<Self, client::error::Error> {
 		let db = crate::utils::open_database(&config, columns::META, "full")?;
-		Backend::from_kvdb(db as Arc<_>, config.pruning, canonicalization_delay, config.state_cache_size)
+		Backend::from_kvdb(
+			db as Arc<_>,
+			config.pruning,
+			canonicalization_delay,
+			config.state_cache_size,
+			config.transaction_index,
+		)
 	}
 
 	#[cfg(not(feature = "kvdb-rocksdb"))]
 	fn new_inner(config: DatabaseSettings, canonicalization_delay: u64) -> Result<Self, client::error::Error> {
 		log::warn!("Running without the RocksDB feature. The database will NOT be saved.");
 		let db = Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
-		Backend::from_kvdb(db as Arc<_>, config.pruning, canonicalization_delay, config.state_cache_size)
+		Backend::from_kvdb(
+			db as Arc<_>,
+			config.pruning,
+			canonicalization_delay,
+			config.state_cache_size,
+			config.transaction_index,
+		)
 	}
 
 	#[cfg(any(test, feature = "test-helpers"))]
@@ -599,11 +652,19 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			PruningMode::keep_blocks(keep_blocks),
 			canonicalization_delay,
 			16777216,
+			true,
 		).expect("failed to create test-db")
 	}
 
-	fn from_kvdb(db: Arc<KeyValueDB>, pruning: PruningMode, canonicalization_delay: u64, state_cache_size: usize) -> Result<Self, client::error::Error> {
+	fn from_kvdb(
+		db: Arc<KeyValueDB>,
+		pruning: PruningMode,
+		canonicalization_delay: u64,
+		state_cache_size: usize,
+		transaction_index: bool,
+	) -> Result<Self, client::error::Error> {
 		let is_archive_pruning = pruning.is_archive();
+		let transaction_index = Self::open_transaction_index(&*db, transaction_index)?;
 		let blockchain = BlockchainDb::new(db.clone())?;
 		let meta = blockchain.meta.clone();
 		let map_e = |e: state_db::Error<io::Error>| ::client::error::Error::from(format!("State database error: {:?}", e));
@@ -612,6 +673,7 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			db: db.clone(),
 			state_db,
 		};
+		let offchain_storage = offchain::LocalStorage::new(db.clone());
 		let changes_tries_storage = DbChangesTrieStorage {
 			db,
 			meta,
@@ -619,14 +681,111 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			_phantom: Default::default(),
 		};
 
-		Ok(Backend {
+		let backend = Backend {
 			storage: Arc::new(storage_db),
 			changes_tries_storage,
 			changes_trie_config: Mutex::new(None),
+			offchain_storage,
 			blockchain,
 			canonicalization_delay,
 			shared_cache: new_shared_cache(state_cache_size),
-		})
+			transaction_index: transaction_index.map(Mutex::new),
+		};
+
+		Ok(backend)
+	}
+
+	/// Read the progress of the transaction index if it is enabled.
+	///
+	/// The index isn't maintained while it is disabled, so it is rebuilt from the canonical
+	/// chain when it gets enabled again.
+	fn open_transaction_index(
+		db: &KeyValueDB,
+		enabled: bool,
+	) -> Result<Option<TransactionIndexState>, client::error::Error> {
+		let progress = db.get(columns::META, meta_keys::TRANSACTION_INDEX).map_err(db_err)?;
+		if !enabled {
+			if progress.is_some() {
+				let mut transaction = DBTransaction::new();
+				transaction.delete(columns::META, meta_keys::TRANSACTION_INDEX);
+				db.write(transaction).map_err(db_err)?;
+			}
+			return Ok(None);
+		}
+
+		Ok(Some(match progress {
+			None => TransactionIndexState::Clearing,
+			Some(ref progress) if progress.is_empty() => TransactionIndexState::Built,
+			Some(progress) => match <(u64, u64)>::decode(&mut &progress[..]) {
+				Some((next, end)) => TransactionIndexState::Building { next, end },
+				None => return Err(client::error::Error::Backend("Error decoding transaction index progress".into())),
+			},
+		}))
+	}
+
+	/// Take the next step of building the transaction index if it isn't built yet, returning
+	/// the state of the index once `transaction` is written.
+	///
+	/// Stale entries are deleted and the canonical chain is indexed in batches, one batch per
+	/// commit, so that enabling the index stalls neither opening the database nor block import.
+	fn advance_transaction_index(
+		&self,
+		transaction: &mut DBTransaction,
+		index_update: &mut transaction_index::Update<Block>,
+	) -> Result<Option<TransactionIndexState>, client::error::Error> {
+		let mut state = match self.transaction_index {
+			Some(ref state) => *state.lock(),
+			None => return Ok(None),
+		};
+		let db = &*self.storage.db;
+
+		if state == TransactionIndexState::Clearing {
+			// entries left from a previous time the index was enabled may point to retracted blocks.
+			let stale = db.iter(columns::TRANSACTION_INDEX)
+				.take(TRANSACTION_INDEX_BUILD_BATCH)
+				.map(|(key, _)| key)
+				.collect::<Vec<_>>();
+			if !stale.is_empty() {
+				for key in stale {
+					transaction.delete(columns::TRANSACTION_INDEX, &key);
+				}
+				return Ok(Some(state));
+			}
+
+			let end = self.blockchain.meta.read().best_number.saturated_into::<u64>();
+			info!("Building the transaction index of blocks #0 to #{}", end);
+			state = TransactionIndexState::Building { next: 0, end };
+		}
+
+		if let TransactionIndexState::Building { next, end } = state {
+			let last = ::std::cmp::min(next + TRANSACTION_INDEX_BUILD_BATCH as u64 - 1, end);
+			for number in next..=last {
+				let hash = match self.blockchain.hash(number.saturated_into())? {
+					Some(hash) => hash,
+					None => continue,
+				};
+				// blocks imported along with their state have no ancestor bodies.
+				if let Some(body) = ::client::blockchain::Backend::body(&self.blockchain, BlockId::Hash(hash))? {
+					index_update.insert(db, hash, &body)?;
+				}
+			}
+
+			state = if last >= end {
+				transaction.put(columns::META, meta_keys::TRANSACTION_INDEX, &[]);
+				TransactionIndexState::Built
+			} else {
+				transaction.put(columns::META, meta_keys::TRANSACTION_INDEX, &(last + 1, end).encode());
+				TransactionIndexState::Building { next: last + 1, end }
+			};
+		}
+
+		Ok(Some(state))
+	}
+
+	/// Whether canonical blocks are indexed as they are imported.
+	fn indexes_transactions(&self) -> bool {
+		self.transaction_index.as_ref()
+			.map_or(false, |state| *state.lock() != TransactionIndexState::Clearing)
 	}
 
 	/// Returns in-memory blockchain that contains the same set of blocks that the self.
@@ -704,7 +863,14 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 	/// In the case where the new best block is a block to be imported, `route_to`
 	/// should be the parent of `best_to`. In the case where we set an existing block
 	/// to be best, `route_to` should equal to `best_to`.
-	fn set_head_with_transaction(&self, transaction: &mut DBTransaction, route_to: Block::Hash, best_to: (NumberFor<Block>, Block::Hash)) -> Result<(Vec<Block::Hash>, Vec<Block::Hash>), client::error::Error> {
+	fn set_head_with_transaction(
+		&self,
+		transaction: &mut DBTransaction,
+		index_update: &mut transaction_index::Update<Block>,
+		index_transactions: bool,
+		route_to: Block::Hash,
+		best_to: (NumberFor<Block>, Block::Hash),
+	) -> Result<(Vec<Block::Hash>, Vec<Block::Hash>), client::error::Error> {
 		let mut enacted = Vec::default();
 		let mut retracted = Vec::default();
 
@@ -736,6 +902,11 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 					columns::KEY_LOOKUP,
 					r.number
 				);
+				if index_transactions {
+					let body = ::client::blockchain::Backend::body(&self.blockchain, BlockId::Hash(r.hash))?
+						.unwrap_or_default();
+					index_update.remove(&*self.storage.db, r.hash, &body)?;
+				}
 			}
 
 			// canonicalize: set the number lookup to map to this block's hash.
@@ -747,6 +918,11 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 					e.number,
 					e.hash
 				);
+				if index_transactions {
+					let body = ::client::blockchain::Backend::body(&self.blockchain, BlockId::Hash(e.hash))?
+						.unwrap_or_default();
+					index_update.insert(&*self.storage.db, e.hash, &body)?;
+				}
 			}
 		}
 
@@ -843,6 +1019,9 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 		-> Result<(), client::error::Error>
 	{
 		let mut transaction = DBTransaction::new();
+		let mut index_update = transaction_index::Update::<Block>::new(columns::TRANSACTION_INDEX);
+		let index_state = self.advance_transaction_index(&mut transaction, &mut index_update)?;
+		let index_transactions = index_state.map_or(false, |state| state != TransactionIndexState::Clearing);
 		let mut finalization_displaced_leaves = None;
 
 		operation.apply_aux(&mut transaction);
@@ -875,8 +1054,20 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			// blocks are keyed by number + hash.
 			let lookup_key = utils::number_and_hash_to_lookup_key(number, hash);
 
-			let (enacted, retracted) = if pending_block.leaf_state.is_best() {
-				self.set_head_with_transaction(&mut transaction, parent_hash, (number, hash))?
+			let (enacted, retracted) = if operation.imported_state {
+				// the ancestry of a block with imported state is unknown, so it
+				// becomes the new head without a tree route.
+				transaction.put(columns::META, meta_keys::BEST_BLOCK, &lookup_key);
+				utils::insert_number_to_key_mapping(&mut transaction, columns::KEY_LOOKUP, number, hash);
+				(Default::default(), Default::default())
+			} else if pending_block.leaf_state.is_best() {
+				self.set_head_with_transaction(
+					&mut transaction,
+					&mut index_update,
+					index_transactions,
+					parent_hash,
+					(number, hash),
+				)?
 			} else {
 				(Default::default(), Default::default())
 			};
@@ -890,6 +1081,10 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 
 			transaction.put(columns::HEADER, &lookup_key, &pending_block.header.encode());
 			if let Some(body) = pending_block.body {
+				let is_canonical = operation.imported_state || pending_block.leaf_state.is_best();
+				if index_transactions && is_canonical {
+					index_update.insert(&*self.storage.db, hash, &body)?;
+				}
 				transaction.put(columns::BODY, &lookup_key, &body.encode());
 			}
 			if let Some(justification) = pending_block.justification {
@@ -910,12 +1105,15 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 				}
 			}
 			let number_u64 = number.saturated_into::<u64>();
-			let commit = self.storage.state_db.insert_block(&hash, number_u64, &pending_block.header.parent_hash(), changeset)
-				.map_err(|e: state_db::Error<io::Error>| client::error::Error::from(format!("State database error: {:?}", e)))?;
+			let commit = if operation.imported_state {
+				self.storage.state_db.insert_base_block(&hash, number_u64, &parent_hash, changeset)
+			} else {
+				self.storage.state_db.insert_block(&hash, number_u64, &parent_hash, changeset)
+			}.map_err(|e: state_db::Error<io::Error>| client::error::Error::from(format!("State database error: {:?}", e)))?;
 			apply_state_commit(&mut transaction, commit);
 
 			// Check if need to finalize. Genesis is always finalized instantly.
-			let finalized = number_u64 == 0 || pending_block.leaf_state.is_final();
+			let finalized = number_u64 == 0 || operation.imported_state || pending_block.leaf_state.is_final();
 
 			let header = &pending_block.header;
 			let is_best = pending_block.leaf_state.is_best();
@@ -923,7 +1121,20 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 
 			self.changes_tries_storage.commit(&mut transaction, changes_trie_updates);
 
-			if finalized {
+			if operation.imported_state {
+				// there is no parent state to read the changes trie configuration from,
+				// and no older changes tries to prune.
+				let commit = self.storage.state_db.canonicalize_block(&hash)
+					.map_err(|e: state_db::Error<io::Error>| client::error::Error::from(format!("State database error: {:?}", e)))?;
+				apply_state_commit(&mut transaction, commit);
+				transaction.put(columns::META, meta_keys::FINALIZED_BLOCK, &lookup_key);
+
+				let new_displaced = self.blockchain.leaves.write().finalize_height(number);
+				match finalization_displaced_leaves {
+					Some(ref mut displaced) => displaced.merge(new_displaced),
+					None => finalization_displaced_leaves = Some(new_displaced),
+				}
+			} else if finalized {
 				// TODO: ensure best chain contains this block.
 				self.ensure_sequential_finalization(header, Some(last_finalized_hash))?;
 				self.note_finalized(
@@ -947,9 +1158,11 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 				displaced_leaf
 			};
 
-			let mut children = children::read_children(&*self.storage.db, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash)?;
-			children.push(hash);
-			children::write_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash, children);
+			if !operation.imported_state {
+				let mut children = children::read_children(&*self.storage.db, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash)?;
+				children.push(hash);
+				children::write_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash, children);
+			}
 
 			meta_updates.push((hash, number, pending_block.leaf_state.is_best(), finalized));
 
@@ -965,6 +1178,8 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 
 				self.set_head_with_transaction(
 					&mut transaction,
+					&mut index_update,
+					index_transactions,
 					hash.clone(),
 					(number.clone(), hash.clone())
 				)?;
@@ -973,8 +1188,15 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			}
 		}
 
+		index_update.apply(&mut transaction);
 		let write_result = self.storage.db.write(transaction).map_err(db_err);
 
+		if write_result.is_ok() {
+			if let (Some(state), Some(current)) = (index_state, self.transaction_index.as_ref()) {
+				*current.lock() = state;
+			}
+		}
+
 		if let Some((number, hash, enacted, retracted, displaced_leaf, is_best)) = imported {
 			if let Err(e) = write_result {
 				let mut leaves = self.blockchain.leaves.write();
@@ -1092,6 +1314,7 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 	type Blockchain = BlockchainDb<Block>;
 	type State = CachingState<Blake2Hasher, DbState, Block>;
 	type ChangesTrieStorage = DbChangesTrieStorage<Block>;
+	type OffchainStorage = offchain::LocalStorage;
 
 	fn begin_operation(&self) -> Result<Self::BlockImportOperation, client::error::Error> {
 		let old_state = self.state_at(BlockId::Hash(Default::default()))?;
@@ -1104,6 +1327,8 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 			aux_ops: Vec::new(),
 			finalized_blocks: Vec::new(),
 			set_head: None,
+			imported_state: false,
+			started: Instant::now(),
 		})
 	}
 
@@ -1115,9 +1340,13 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 	fn commit_operation(&self, operation: Self::BlockImportOperation)
 		-> Result<(), client::error::Error>
 	{
+		let started = operation.pending_block.as_ref().map(|_| operation.started);
 		match self.try_commit_operation(operation) {
 			Ok(_) => {
 				self.storage.state_db.apply_pending();
+				if let Some(started) = started {
+					metrics::BLOCK_IMPORT_TIME.observe_since(started);
+				}
 				Ok(())
 			},
 			e @ Err(_) => {
@@ -1164,6 +1393,10 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 		Some(&self.changes_tries_storage)
 	}
 
+	fn offchain_storage(&self) -> Option<Self::OffchainStorage> {
+		Some(self.offchain_storage.clone())
+	}
+
 	fn revert(&self, n: NumberFor<Block>) -> Result<NumberFor<Block>, client::error::Error> {
 		let mut best = self.blockchain.info()?.best_number;
 		let finalized = self.blockchain.info()?.finalized_number;
@@ -1189,6 +1422,13 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 					let key = utils::number_and_hash_to_lookup_key(best.clone(), &hash);
 					transaction.put(columns::META, meta_keys::BEST_BLOCK, &key);
 					transaction.delete(columns::KEY_LOOKUP, removed.hash().as_ref());
+					if self.indexes_transactions() {
+						let body = ::client::blockchain::Backend::body(&self.blockchain, BlockId::Hash(removed.hash()))?
+							.unwrap_or_default();
+						let mut index_update = transaction_index::Update::<Block>::new(columns::TRANSACTION_INDEX);
+						index_update.remove(&*self.storage.db, removed.hash(), &body)?;
+						index_update.apply(&mut transaction);
+					}
 					children::remove_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, hash);
 					self.storage.db.write(transaction).map_err(db_err)?;
 					self.blockchain.update_meta(hash, best, true, false);
@@ -1367,7 +1607,7 @@ mod tests {
 			db.storage.db.clone()
 		};
 
-		let backend = Backend::<Block>::from_kvdb(backing, PruningMode::keep_blocks(1), 0, 16777216).unwrap();
+		let backend = Backend::<Block>::from_kvdb(backing, PruningMode::keep_blocks(1), 0, 16777216, true).unwrap();
 		assert_eq!(backend.blockchain().info().unwrap().best_number, 9);
 		for i in 0..10 {
 			assert!(backend.blockchain().hash(i).unwrap().is_some())
@@ -2005,4 +2245,103 @@ mod tests {
 			backend.commit_operation(op).unwrap_err();
 		}
 	}
+
+	fn insert_block_with_body(
+		backend: &Backend<Block>,
+		number: u64,
+		parent_hash: H256,
+		body: Vec<u64>,
+		leaf_state: NewBlockState,
+	) -> H256 {
+		let body: Vec<ExtrinsicWrapper<u64>> = body.into_iter().map(Into::into).collect();
+		let header = Header {
+			number,
+			parent_hash,
+			state_root: BlakeTwo256::trie_root::<_, &[u8], &[u8]>(Vec::new()),
+			digest: Default::default(),
+			extrinsics_root: BlakeTwo256::hash_of(&body),
+		};
+		let header_hash = header.hash();
+
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, BlockId::Hash(parent_hash)).unwrap();
+		op.set_block_data(header, Some(body), None, leaf_state).unwrap();
+		backend.commit_operation(op).unwrap();
+
+		header_hash
+	}
+
+	fn indexed_block(backend: &Backend<Block>, xt: u64) -> Option<(H256, u32)> {
+		backend.blockchain().transaction_index(BlakeTwo256::hash_of(&ExtrinsicWrapper::from(xt))).unwrap()
+	}
+
+	#[test]
+	fn transaction_index_follows_canonical_chain() {
+		let backend = Backend::<Block>::new_test(10, 10);
+		let block0 = insert_block_with_body(&backend, 0, Default::default(), vec![], NewBlockState::Best);
+		let block1 = insert_block_with_body(&backend, 1, block0, vec![1, 2], NewBlockState::Best);
+		let block2a = insert_block_with_body(&backend, 2, block1, vec![3, 1], NewBlockState::Best);
+		let block2b = insert_block_with_body(&backend, 2, block1, vec![4, 2], NewBlockState::Normal);
+
+		assert_eq!(indexed_block(&backend, 1), Some((block1, 0)));
+		assert_eq!(indexed_block(&backend, 2), Some((block1, 1)));
+		assert_eq!(indexed_block(&backend, 3), Some((block2a, 0)));
+		assert_eq!(indexed_block(&backend, 4), None);
+
+		// reorg to the fork
+		let mut op = backend.begin_operation().unwrap();
+		op.mark_head(BlockId::Hash(block2b)).unwrap();
+		backend.commit_operation(op).unwrap();
+
+		assert_eq!(indexed_block(&backend, 1), Some((block1, 0)));
+		assert_eq!(indexed_block(&backend, 2), Some((block1, 1)));
+		assert_eq!(indexed_block(&backend, 3), None);
+		assert_eq!(indexed_block(&backend, 4), Some((block2b, 0)));
+
+		// revert the fork
+		assert_eq!(backend.revert(1).unwrap(), 1);
+
+		assert_eq!(indexed_block(&backend, 2), Some((block1, 1)));
+		assert_eq!(indexed_block(&backend, 4), None);
+	}
+
+	#[test]
+	fn transaction_index_is_built_when_enabled() {
+		let db = Arc::new(::kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let open = |transaction_index| Backend::<Block>::from_kvdb(
+			db.clone(),
+			PruningMode::keep_blocks(10),
+			10,
+			16777216,
+			transaction_index,
+		).unwrap();
+
+		let backend = open(false);
+		let block0 = insert_block_with_body(&backend, 0, Default::default(), vec![], NewBlockState::Best);
+		let block1 = insert_block_with_body(&backend, 1, block0, vec![1], NewBlockState::Best);
+		assert_eq!(indexed_block(&backend, 1), None);
+
+		// the index is built along with the next commit rather than when opening the database.
+		let backend = open(true);
+		assert_eq!(indexed_block(&backend, 1), None);
+		let block2 = insert_block_with_body(&backend, 2, block1, vec![2], NewBlockState::Best);
+		assert_eq!(indexed_block(&backend, 1), Some((block1, 0)));
+		assert_eq!(indexed_block(&backend, 2), Some((block2, 0)));
+
+		// blocks imported while the index is disabled are indexed once it is enabled again.
+		let backend = open(false);
+		let block3 = insert_block_with_body(&backend, 3, block2, vec![3], NewBlockState::Best);
+
+		// the stale entries are deleted first, then the canonical chain is indexed again.
+		let backend = open(true);
+		let block4 = insert_block_with_body(&backend, 4, block3, vec![4], NewBlockState::Best);
+		assert_eq!(indexed_block(&backend, 2), None);
+		assert_eq!(indexed_block(&backend, 4), None);
+
+		let block5 = insert_block_with_body(&backend, 5, block4, vec![5], NewBlockState::Best);
+		assert_eq!(indexed_block(&backend, 2), Some((block2, 0)));
+		assert_eq!(indexed_block(&backend, 3), Some((block3, 0)));
+		assert_eq!(indexed_block(&backend, 4), Some((block4, 0)));
+		assert_eq!(indexed_block(&backend, 5), Some((block5, 0)));
+	}
 }
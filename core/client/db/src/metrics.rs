@@ -0,0 +1,45 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Database metrics.
+
+use std::sync::Arc;
+use lazy_static::lazy_static;
+use substrate_metrics::{Counter, Histogram, register_counter, register_histogram, DEFAULT_TIME_BUCKETS};
+
+lazy_static! {
+	/// Time between beginning and committing an operation that imports a block.
+	pub static ref BLOCK_IMPORT_TIME: Arc<Histogram> = register_histogram(
+		"substrate_block_import_time_seconds",
+		"Time taken to execute and commit an imported block",
+		DEFAULT_TIME_BUCKETS,
+	);
+	/// State reads served by the cache local to a state.
+	pub static ref STATE_CACHE_LOCAL_HITS: Arc<Counter> = register_counter(
+		"substrate_state_cache_local_hits_total",
+		"State reads served by the local state cache",
+	);
+	/// State reads served by the shared state cache.
+	pub static ref STATE_CACHE_SHARED_HITS: Arc<Counter> = register_counter(
+		"substrate_state_cache_shared_hits_total",
+		"State reads served by the shared state cache",
+	);
+	/// State reads that had to query the database.
+	pub static ref STATE_CACHE_MISSES: Arc<Counter> = register_counter(
+		"substrate_state_cache_misses_total",
+		"State reads that missed the state cache",
+	);
+}
@@ -0,0 +1,180 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RocksDB-based offchain workers local storage.
+
+use std::{
+	collections::HashMap,
+	sync::Arc,
+};
+
+use crate::columns;
+use kvdb::KeyValueDB;
+use log::error;
+use parking_lot::Mutex;
+
+/// Offchain local storage
+#[derive(Clone)]
+pub struct LocalStorage {
+	db: Arc<KeyValueDB>,
+	locks: Arc<Mutex<HashMap<Vec<u8>, Arc<Mutex<()>>>>>,
+}
+
+impl std::fmt::Debug for LocalStorage {
+	fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+		fmt.debug_struct("LocalStorage")
+			.finish()
+	}
+}
+
+impl LocalStorage {
+	/// Create new offchain storage for tests (backed by memorydb)
+	#[cfg(any(test, feature = "test-helpers"))]
+	pub fn new_test() -> Self {
+		let db = Arc::new(::kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		Self::new(db as _)
+	}
+
+	/// Create offchain local storage with given `KeyValueDB` backend.
+	pub fn new(db: Arc<KeyValueDB>) -> Self {
+		Self {
+			db,
+			locks: Default::default(),
+		}
+	}
+}
+
+impl LocalStorage {
+	/// Run `f` while holding the lock of the given key, so that the writes to a key are
+	/// serialized with the compare-and-set operations on it.
+	fn with_key_lock<R>(&self, key: &[u8], f: impl FnOnce() -> R) -> R {
+		let key_lock = {
+			let mut locks = self.locks.lock();
+			locks.entry(key.to_vec()).or_default().clone()
+		};
+
+		let result = {
+			let _key_guard = key_lock.lock();
+			f()
+		};
+
+		// clean the lock map if we're the only entry
+		let mut locks = self.locks.lock();
+		{
+			drop(key_lock);
+			let key_lock = locks.get_mut(key);
+			if key_lock.and_then(Arc::get_mut).is_some() {
+				locks.remove(key);
+			}
+		}
+		result
+	}
+
+	fn write(&self, key: &[u8], value: &[u8]) {
+		let mut tx = self.db.transaction();
+		tx.put(columns::OFFCHAIN, key, value);
+
+		if let Err(e) = self.db.write(tx) {
+			error!("Error setting on local storage: {}", e)
+		}
+	}
+}
+
+impl primitives::offchain::OffchainStorage for LocalStorage {
+	fn set(&mut self, prefix: &[u8], key: &[u8], value: &[u8]) {
+		let key: Vec<u8> = prefix.iter().chain(key).cloned().collect();
+		self.with_key_lock(&key, || self.write(&key, value))
+	}
+
+	fn get(&self, prefix: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+		let key: Vec<u8> = prefix.iter().chain(key).cloned().collect();
+		self.db.get(columns::OFFCHAIN, &key)
+			.ok()
+			.and_then(|x| x)
+			.map(|v| v.to_vec())
+	}
+
+	fn compare_and_set(
+		&mut self,
+		prefix: &[u8],
+		item_key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool {
+		let key: Vec<u8> = prefix.iter().chain(item_key).cloned().collect();
+		self.with_key_lock(&key, || {
+			let current = self.db.get(columns::OFFCHAIN, &key)
+				.ok()
+				.and_then(|x| x);
+			let is_set = current.as_ref().map(|v| &**v) == old_value;
+
+			if is_set {
+				self.write(&key, new_value)
+			}
+			is_set
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::offchain::OffchainStorage;
+
+	#[test]
+	fn should_compare_and_set_and_clear_the_locks_map() {
+		let mut storage = LocalStorage::new_test();
+		let prefix = b"prefix";
+		let key = b"key";
+		let value = b"value";
+
+		storage.set(prefix, key, value);
+		assert_eq!(storage.get(prefix, key), Some(value.to_vec()));
+
+		assert_eq!(storage.compare_and_set(prefix, key, Some(&value[..]), b"asd"), true);
+		assert_eq!(storage.get(prefix, key), Some(b"asd".to_vec()));
+		assert!(storage.locks.lock().is_empty(), "Locks map should be empty!");
+	}
+
+	#[test]
+	fn should_not_set_when_old_value_does_not_match() {
+		let mut storage = LocalStorage::new_test();
+		let prefix = b"prefix";
+		let key = b"key";
+
+		assert_eq!(storage.compare_and_set(prefix, key, Some(&b"value"[..]), b"asd"), false);
+		assert_eq!(storage.get(prefix, key), None);
+
+		storage.set(prefix, key, b"other");
+		assert_eq!(storage.compare_and_set(prefix, key, Some(&b"value"[..]), b"asd"), false);
+		assert_eq!(storage.compare_and_set(prefix, key, None, b"asd"), false);
+		assert_eq!(storage.get(prefix, key), Some(b"other".to_vec()));
+		assert!(storage.locks.lock().is_empty(), "Locks map should be empty!");
+	}
+
+	#[test]
+	fn should_compare_and_set_absent_value() {
+		let mut storage = LocalStorage::new_test();
+		let prefix = b"prefix";
+		let key = b"key";
+
+		assert_eq!(storage.compare_and_set(prefix, key, None, b"value"), true);
+		assert_eq!(storage.get(prefix, key), Some(b"value".to_vec()));
+		assert_eq!(storage.compare_and_set(prefix, key, None, b"asd"), false);
+		assert_eq!(storage.get(prefix, key), Some(b"value".to_vec()));
+		assert!(storage.locks.lock().is_empty(), "Locks map should be empty!");
+	}
+}
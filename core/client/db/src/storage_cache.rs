@@ -24,6 +24,7 @@ use hash_db::Hasher;
 use runtime_primitives::traits::{Block, Header};
 use state_machine::{backend::Backend as StateBackend, TrieBackend};
 use log::trace;
+use crate::metrics::{STATE_CACHE_LOCAL_HITS, STATE_CACHE_SHARED_HITS, STATE_CACHE_MISSES};
 
 const STATE_CACHE_BLOCKS: usize = 12;
 
@@ -323,16 +324,19 @@ impl<H: Hasher, S: StateBackend<H>, B:Block> StateBackend<H> for CachingState<H,
 		let local_cache = self.local_cache.upgradable_read();
 		if let Some(entry) = local_cache.storage.get(key).cloned() {
 			trace!("Found in local cache: {:?}", key);
+			STATE_CACHE_LOCAL_HITS.inc();
 			return Ok(entry)
 		}
 		let mut cache = self.shared_cache.lock();
 		if Self::is_allowed(key, &self.parent_hash, &cache.modifications) {
 			if let Some(entry) = cache.storage.get_mut(key).map(|a| a.clone()) {
 				trace!("Found in shared cache: {:?}", key);
+				STATE_CACHE_SHARED_HITS.inc();
 				return Ok(entry)
 			}
 		}
 		trace!("Cache miss: {:?}", key);
+		STATE_CACHE_MISSES.inc();
 		let value = self.state.storage(key)?;
 		RwLockUpgradableReadGuard::upgrade(local_cache).storage.insert(key.to_vec(), value.clone());
 		Ok(value)
@@ -342,16 +346,19 @@ impl<H: Hasher, S: StateBackend<H>, B:Block> StateBackend<H> for CachingState<H,
 		let local_cache = self.local_cache.upgradable_read();
 		if let Some(entry) = local_cache.hashes.get(key).cloned() {
 			trace!("Found hash in local cache: {:?}", key);
+			STATE_CACHE_LOCAL_HITS.inc();
 			return Ok(entry)
 		}
 		let mut cache = self.shared_cache.lock();
 		if Self::is_allowed(key, &self.parent_hash, &cache.modifications) {
 			if let Some(entry) = cache.hashes.get_mut(key).map(|a| a.clone()) {
 				trace!("Found hash in shared cache: {:?}", key);
+				STATE_CACHE_SHARED_HITS.inc();
 				return Ok(entry)
 			}
 		}
 		trace!("Cache hash miss: {:?}", key);
+		STATE_CACHE_MISSES.inc();
 		let hash = self.state.storage_hash(key)?;
 		RwLockUpgradableReadGuard::upgrade(local_cache).hashes.insert(key.to_vec(), hash.clone());
 		Ok(hash)
@@ -405,6 +412,24 @@ impl<H: Hasher, S: StateBackend<H>, B:Block> StateBackend<H> for CachingState<H,
 		self.state.child_keys(child_key, prefix)
 	}
 
+	fn paged_pairs(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error> {
+		self.state.paged_pairs(prefix, start_key, count)
+	}
+
+	fn paged_keys(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<Vec<u8>>, Self::Error> {
+		self.state.paged_keys(prefix, start_key, count)
+	}
+
 	fn try_into_trie_backend(self) -> Option<TrieBackend<Self::TrieBackendStorage, H>> {
 		self.state.try_into_trie_backend()
 	}
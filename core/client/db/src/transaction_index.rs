@@ -0,0 +1,131 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+//! Index of the extrinsics of canonical blocks, keyed by extrinsic hash.
+//!
+//! Each entry lists all canonical blocks including the extrinsic, in the order they became
+//! canonical, along with the index of the extrinsic in the block.
+
+use std::collections::{HashMap, hash_map::Entry};
+
+use kvdb::{KeyValueDB, DBTransaction};
+use parity_codec::{Encode, Decode};
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, Hash as HashT};
+use crate::utils::db_err;
+
+fn extrinsic_hash<Block: BlockT>(extrinsic: &Block::Extrinsic) -> Block::Hash {
+	<<Block::Header as HeaderT>::Hashing as HashT>::hash_of(extrinsic)
+}
+
+fn read_entries<Block: BlockT>(
+	db: &KeyValueDB,
+	column: Option<u32>,
+	hash: Block::Hash,
+) -> Result<Vec<(Block::Hash, u32)>, client::error::Error> {
+	match db.get(column, hash.as_ref()).map_err(db_err)? {
+		Some(entries) => match Decode::decode(&mut &entries[..]) {
+			Some(entries) => Ok(entries),
+			None => Err(client::error::Error::Backend("Error decoding transaction index entry".into())),
+		},
+		None => Ok(Vec::new()),
+	}
+}
+
+/// Returns the hash of the canonical block that includes the extrinsic with given hash and the
+/// index of the extrinsic in that block. If several canonical blocks include the extrinsic, the
+/// first one to become canonical is returned.
+pub fn read<Block: BlockT>(
+	db: &KeyValueDB,
+	column: Option<u32>,
+	hash: Block::Hash,
+) -> Result<Option<(Block::Hash, u32)>, client::error::Error> {
+	Ok(read_entries::<Block>(db, column, hash)?.into_iter().next())
+}
+
+/// Changes to the index, written to a database transaction at once.
+///
+/// All changes made within a transaction must go through the same `Update`, so that an extrinsic
+/// included in both a retracted and an enacted block ends up with the right entries.
+pub struct Update<Block: BlockT> {
+	column: Option<u32>,
+	entries: HashMap<Block::Hash, Vec<(Block::Hash, u32)>>,
+}
+
+impl<Block: BlockT> Update<Block> {
+	/// Create an empty update of the index stored in `column`.
+	pub fn new(column: Option<u32>) -> Self {
+		Update {
+			column,
+			entries: HashMap::new(),
+		}
+	}
+
+	fn entries(
+		&mut self,
+		db: &KeyValueDB,
+		hash: Block::Hash,
+	) -> Result<&mut Vec<(Block::Hash, u32)>, client::error::Error> {
+		Ok(match self.entries.entry(hash) {
+			Entry::Occupied(entry) => entry.into_mut(),
+			Entry::Vacant(entry) => entry.insert(read_entries::<Block>(db, self.column, hash)?),
+		})
+	}
+
+	/// Add index entries for the extrinsics of a block that becomes canonical.
+	pub fn insert(
+		&mut self,
+		db: &KeyValueDB,
+		block_hash: Block::Hash,
+		body: &[Block::Extrinsic],
+	) -> Result<(), client::error::Error> {
+		for (index, extrinsic) in body.iter().enumerate() {
+			let entry = (block_hash, index as u32);
+			let entries = self.entries(db, extrinsic_hash::<Block>(extrinsic))?;
+			if !entries.contains(&entry) {
+				entries.push(entry);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Remove the index entries for the extrinsics of a block that is no longer canonical.
+	///
+	/// Entries of other blocks including the same extrinsics, e.g. an ancestor, are kept.
+	pub fn remove(
+		&mut self,
+		db: &KeyValueDB,
+		block_hash: Block::Hash,
+		body: &[Block::Extrinsic],
+	) -> Result<(), client::error::Error> {
+		for extrinsic in body {
+			self.entries(db, extrinsic_hash::<Block>(extrinsic))?
+				.retain(|(indexed_block, _)| *indexed_block != block_hash);
+		}
+
+		Ok(())
+	}
+
+	/// Write the changes to the given database transaction.
+	pub fn apply(self, transaction: &mut DBTransaction) {
+		for (hash, entries) in self.entries {
+			if entries.is_empty() {
+				transaction.delete(self.column, hash.as_ref());
+			} else {
+				transaction.put(self.column, hash.as_ref(), &entries.encode());
+			}
+		}
+	}
+}
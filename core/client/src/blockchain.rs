@@ -90,6 +90,13 @@ pub trait Backend<Block: BlockT>: HeaderBackend<Block> {
 
 	/// Return hashes of all blocks that are children of the block with `parent_hash`.
 	fn children(&self, parent_hash: Block::Hash) -> Result<Vec<Block::Hash>>;
+
+	/// Get the hash of the canonical block that includes the extrinsic with given hash, along
+	/// with the index of the extrinsic in that block. Returns `None` if the extrinsic is not
+	/// found or if the backend doesn't index extrinsics.
+	fn transaction_index(&self, _hash: Block::Hash) -> Result<Option<(Block::Hash, u32)>> {
+		Ok(None)
+	}
 }
 
 /// Provides access to the optional cache.
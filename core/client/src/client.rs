@@ -25,7 +25,7 @@ use futures::sync::mpsc;
 use parking_lot::{Mutex, RwLock};
 use primitives::NativeOrEncoded;
 use runtime_primitives::{
-	Justification,
+	Justification, StorageOverlay, ChildrenStorageOverlay,
 	generic::{BlockId, SignedBlock},
 };
 use consensus::{
@@ -53,9 +53,10 @@ use primitives::storage::well_known_keys;
 use parity_codec::{Encode, Decode};
 use state_machine::{
 	DBValue, Backend as StateBackend, CodeExecutor, ChangesTrieAnchorBlockId,
-	ExecutionStrategy, ExecutionManager, prove_read, prove_child_read,
-	ChangesTrieRootsStorage, ChangesTrieStorage,
+	ExecutionStrategy, ExecutionManager, prove_read, prove_read_keys, prove_child_read,
+	prove_child_read_keys, ChangesTrieRootsStorage, ChangesTrieStorage,
 	key_changes, key_changes_proof, OverlayedChanges, NeverOffchainExt,
+	prove_range_read, StorageRange, StorageTrace,
 };
 use hash_db::Hasher;
 
@@ -134,7 +135,15 @@ pub struct Client<B, E, Block, RA> where Block: BlockT {
 /// Client import operation, a wrapper for the backend.
 pub struct ClientImportOperation<Block: BlockT, H: Hasher<Out=Block::Hash>, B: backend::Backend<Block, H>> {
 	op: B::BlockImportOperation,
-	notify_imported: Option<(Block::Hash, BlockOrigin, Block::Header, bool, Option<Vec<(Vec<u8>, Option<Vec<u8>>)>>)>,
+	notify_imported: Option<(
+		Block::Hash,
+		BlockOrigin,
+		Block::Header,
+		bool,
+		Vec<Block::Hash>,
+		Vec<Block::Hash>,
+		Option<Vec<(Vec<u8>, Option<Vec<u8>>)>>,
+	)>,
 	notify_finalized: Vec<Block::Hash>,
 }
 
@@ -201,6 +210,12 @@ pub struct BlockImportNotification<Block: BlockT> {
 	pub header: Block::Header,
 	/// Is this the new best block.
 	pub is_new_best: bool,
+	/// Blocks that were part of the previous best chain and are no longer
+	/// part of the new one (only populated when `is_new_best` causes a reorg).
+	pub retracted: Vec<Block::Hash>,
+	/// Ancestors of the imported block that became part of the best chain
+	/// together with it, in ascending order (only populated on a reorg).
+	pub enacted: Vec<Block::Hash>,
 }
 
 /// Summary of a finalized block.
@@ -359,6 +374,43 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		Ok(keys)
 	}
 
+	/// Given a `BlockId` and a key prefix, return at most `count` matching storage keys in that
+	/// block, in key order. If `start_key` is given, only keys strictly greater than it are returned.
+	pub fn storage_keys_paged(
+		&self,
+		id: &BlockId<Block>,
+		key_prefix: &StorageKey,
+		start_key: Option<&StorageKey>,
+		count: usize,
+	) -> error::Result<Vec<StorageKey>> {
+		let keys = self.state_at(id)?
+			.paged_keys(&key_prefix.0, start_key.map(|key| &key.0[..]), count)
+			.map_err(|e| error::Error::from_state(Box::new(e)))?
+			.into_iter()
+			.map(StorageKey)
+			.collect();
+		Ok(keys)
+	}
+
+	/// Given a `BlockId` and a key prefix, return at most `count` matching storage entries in
+	/// that block, in key order. If `start_key` is given, only keys strictly greater than it
+	/// are returned.
+	pub fn storage_pairs_paged(
+		&self,
+		id: &BlockId<Block>,
+		key_prefix: &StorageKey,
+		start_key: Option<&StorageKey>,
+		count: usize,
+	) -> error::Result<Vec<(StorageKey, StorageData)>> {
+		let pairs = self.state_at(id)?
+			.paged_pairs(&key_prefix.0, start_key.map(|key| &key.0[..]), count)
+			.map_err(|e| error::Error::from_state(Box::new(e)))?
+			.into_iter()
+			.map(|(key, value)| (StorageKey(key), StorageData(value)))
+			.collect();
+		Ok(pairs)
+	}
+
 	/// Given a `BlockId` and a key, return the value under the key in that block.
 	pub fn storage(&self, id: &BlockId<Block>, key: &StorageKey) -> error::Result<Option<StorageData>> {
 		Ok(self.state_at(id)?
@@ -418,6 +470,28 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 				.map_err(Into::into))
 	}
 
+	/// Reads storage values at a given block for all given keys, returning a single read proof.
+	pub fn read_proof_keys(&self, id: &BlockId<Block>, keys: &[Vec<u8>]) -> error::Result<Vec<Vec<u8>>> {
+		self.state_at(id)
+			.and_then(|state| prove_read_keys(state, keys)
+				.map_err(Into::into))
+	}
+
+	/// Reads a storage range of at most `max_entries` top-level and child trie entries at a given
+	/// block, starting after `start` (and `child_start` in the child trie at `start`, if given),
+	/// returning range proof.
+	pub fn read_range_proof(
+		&self,
+		id: &BlockId<Block>,
+		start: &[u8],
+		child_start: Option<&[u8]>,
+		max_entries: usize,
+	) -> error::Result<(StorageRange, Vec<Vec<u8>>)> {
+		self.state_at(id)
+			.and_then(|state| prove_range_read(state, start, child_start, max_entries)
+				.map_err(Into::into))
+	}
+
 	/// Reads child storage value at a given block + storage_key + key, returning
 	/// read proof.
 	pub fn read_child_proof(
@@ -432,6 +506,19 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 				.map_err(Into::into))
 	}
 
+	/// Reads child storage values at a given block + storage_key for all given keys, returning
+	/// a single read proof.
+	pub fn read_child_proof_keys(
+		&self,
+		id: &BlockId<Block>,
+		storage_key: &[u8],
+		keys: &[Vec<u8>],
+	) -> error::Result<Vec<Vec<u8>>> {
+		self.state_at(id)
+			.and_then(|state| prove_child_read_keys(state, storage_key, keys)
+				.map_err(Into::into))
+	}
+
 	/// Execute a call to a contract on top of state in a block of given hash
 	/// AND returning execution proof.
 	///
@@ -771,6 +858,46 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		result
 	}
 
+	/// Import a block together with its complete state, without executing it or importing
+	/// its ancestors. The block becomes the new best and finalized block.
+	///
+	/// This is used to start a full node from a recent state, e.g. after warp sync.
+	pub fn import_state(
+		&self,
+		header: Block::Header,
+		justification: Option<Justification>,
+		top: StorageOverlay,
+		children: ChildrenStorageOverlay,
+	) -> error::Result<()> {
+		let hash = header.hash();
+		if let blockchain::BlockStatus::InChain = self.backend.blockchain().status(BlockId::Hash(hash))? {
+			return Ok(());
+		}
+
+		self.lock_import_and_run(|operation| {
+			operation.op.set_block_data(header.clone(), None, justification, crate::backend::NewBlockState::Final)?;
+			let state_root = operation.op.import_state(top, children)?;
+			if state_root != *header.state_root() {
+				return Err(error::Error::InvalidStateRoot(format!("{}", hash)));
+			}
+
+			info!("Imported state of block #{} ({})", header.number(), hash);
+
+			operation.notify_finalized.push(hash);
+			operation.notify_imported = Some((
+				hash,
+				BlockOrigin::NetworkInitialSync,
+				header,
+				true,
+				Vec::new(),
+				Vec::new(),
+				None,
+			));
+
+			Ok(())
+		})
+	}
+
 	/// Set a block as best block.
 	pub fn set_head(
 		&self,
@@ -914,6 +1041,22 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			crate::backend::NewBlockState::Normal
 		};
 
+		// if the new best block is not built on top of the previous best,
+		// collect the blocks that are leaving and joining the best chain.
+		let (retracted, enacted) = if make_notifications && is_new_best && parent_hash != last_best {
+			let route_from_best = crate::blockchain::tree_route(
+				self.backend.blockchain(),
+				BlockId::Hash(last_best),
+				BlockId::Hash(parent_hash),
+			)?;
+			(
+				route_from_best.retracted().iter().map(|e| e.hash.clone()).collect(),
+				route_from_best.enacted().iter().map(|e| e.hash.clone()).collect(),
+			)
+		} else {
+			(Vec::new(), Vec::new())
+		};
+
 		trace!("Imported {}, (#{}), best={}, origin={:?}", hash, import_headers.post().number(), is_new_best, origin);
 
 		operation.op.set_block_data(
@@ -941,7 +1084,15 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 				operation.notify_finalized.push(hash);
 			}
 
-			operation.notify_imported = Some((hash, origin, import_headers.into_post(), is_new_best, storage_changes));
+			operation.notify_imported = Some((
+				hash,
+				origin,
+				import_headers.into_post(),
+				is_new_best,
+				retracted,
+				enacted,
+				storage_changes,
+			));
 		}
 
 		Ok(ImportResult::imported())
@@ -1099,9 +1250,17 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 
 	fn notify_imported(
 		&self,
-		notify_import: (Block::Hash, BlockOrigin, Block::Header, bool, Option<Vec<(Vec<u8>, Option<Vec<u8>>)>>),
+		notify_import: (
+			Block::Hash,
+			BlockOrigin,
+			Block::Header,
+			bool,
+			Vec<Block::Hash>,
+			Vec<Block::Hash>,
+			Option<Vec<(Vec<u8>, Option<Vec<u8>>)>>,
+		),
 	) -> error::Result<()> {
-		let (hash, origin, header, is_new_best, storage_changes) = notify_import;
+		let (hash, origin, header, is_new_best, retracted, enacted, storage_changes) = notify_import;
 
 		if let Some(storage_changes) = storage_changes {
 			// TODO [ToDr] How to handle re-orgs? Should we re-emit all storage changes?
@@ -1114,6 +1273,8 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			origin,
 			header,
 			is_new_best,
+			retracted,
+			enacted,
 		};
 
 		self.import_notification_sinks.lock()
@@ -1226,6 +1387,12 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		self.backend.blockchain().justification(*id)
 	}
 
+	/// Get the hash of the canonical block that includes the extrinsic with given hash, along
+	/// with the index of the extrinsic in that block.
+	pub fn transaction_index(&self, hash: &Block::Hash) -> error::Result<Option<(Block::Hash, u32)>> {
+		self.backend.blockchain().transaction_index(*hash)
+	}
+
 	/// Get full block by id.
 	pub fn block(&self, id: &BlockId<Block>)
 		-> error::Result<Option<SignedBlock<Block>>>
@@ -1237,6 +1404,36 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		})
 	}
 
+	/// Re-execute the block with given id on the state of its parent, recording the storage
+	/// accesses made by the runtime. Only accesses to keys (or child tries) starting with
+	/// `key_prefix` are recorded, if given, until the recorded keys and values reach `max_size`
+	/// bytes.
+	pub fn trace_block(
+		&self,
+		id: &BlockId<Block>,
+		key_prefix: Option<&[u8]>,
+		max_size: usize,
+	) -> error::Result<StorageTrace> {
+		let block = self.block(id)?
+			.ok_or_else(|| Error::UnknownBlock(format!("Unknown block {:?}", id)))?
+			.block;
+		let parent_state = self.state_at(&BlockId::Hash(*block.header().parent_hash()))?;
+
+		let mut overlay = OverlayedChanges::default();
+		overlay.enable_tracing(key_prefix.map(<[u8]>::to_vec), max_size);
+		self.executor.call_at_state::<_, _, _, NeverNativeValue, fn() -> _>(
+			&parent_state,
+			&mut overlay,
+			"Core_execute_block",
+			&block.encode(),
+			state_machine::native_else_wasm(),
+			None,
+			NeverOffchainExt::new(),
+		)?;
+
+		Ok(overlay.take_trace())
+	}
+
 	/// Gets the uncles of the block with `target_hash` going back `max_generation` ancestors.
 	pub fn uncles(&self, target_hash: Block::Hash, max_generation: NumberFor<Block>) -> error::Result<Vec<Block::Hash>> {
 		let load_header = |id: Block::Hash| -> error::Result<Block::Header> {
@@ -2453,4 +2650,45 @@ pub(crate) mod tests {
 			None,
 		);
 	}
+
+	#[test]
+	fn import_state_without_ancestors() {
+		use test_client::blockchain::Backend;
+		use state_machine::Backend as StateBackend;
+
+		let source = test_client::new();
+		for _ in 0..3 {
+			let block = source.new_block(Default::default()).unwrap().bake().unwrap();
+			source.import(BlockOrigin::Own, block).unwrap();
+		}
+
+		let header = source.header(&BlockId::Number(3)).unwrap().unwrap();
+		let top: StorageOverlay = source.state_at(&BlockId::Number(3)).unwrap()
+			.pairs()
+			.into_iter()
+			.collect();
+
+		let client = test_client::new();
+		let mut invalid = top.clone();
+		invalid.insert(b"invalid".to_vec(), b"value".to_vec());
+		assert!(client.import_state(header.clone(), None, invalid, Default::default()).is_err());
+		assert_eq!(client.info().unwrap().chain.best_number, 0);
+
+		let justification = vec![1, 2, 3];
+		client.import_state(header.clone(), Some(justification.clone()), top, Default::default()).unwrap();
+
+		let info = client.info().unwrap().chain;
+		assert_eq!(info.best_hash, header.hash());
+		assert_eq!(info.finalized_hash, header.hash());
+		assert_eq!(client.header(&BlockId::Number(2)).unwrap(), None);
+
+		#[allow(deprecated)]
+		let blockchain = client.backend().blockchain();
+		assert_eq!(blockchain.justification(BlockId::Hash(header.hash())).unwrap(), Some(justification));
+
+		// the chain continues from the imported block.
+		let block = client.new_block(Default::default()).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, block.clone()).unwrap();
+		assert_eq!(client.info().unwrap().chain.best_hash, block.hash());
+	}
 }
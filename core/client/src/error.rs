@@ -94,6 +94,9 @@ pub enum Error {
 	/// Hash that is required for building CHT is missing.
 	#[display(fmt = "Failed to get hash of block for building CHT")]
 	MissingHashRequiredForCHT,
+	/// Imported state does not match the state root of the block.
+	#[display(fmt = "Imported state does not match the state root of block {}", _0)]
+	InvalidStateRoot(String),
 	/// A convenience variant for String
 	#[display(fmt = "{}", _0)]
 	Msg(String),
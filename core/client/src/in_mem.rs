@@ -19,7 +19,7 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use parking_lot::RwLock;
-use primitives::{ChangesTrieConfiguration, storage::well_known_keys};
+use primitives::{ChangesTrieConfiguration, storage::well_known_keys, offchain::InMemOffchainStorage};
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::{
 	Block as BlockT, Header as HeaderT, Zero,
@@ -179,6 +179,29 @@ impl<Block: BlockT> Blockchain<Block> {
 		Ok(())
 	}
 
+	/// Insert a block with unknown ancestry, making it the new best and finalized block.
+	///
+	/// This is used for blocks that are imported together with their state.
+	pub fn insert_base(
+		&self,
+		hash: Block::Hash,
+		header: <Block as BlockT>::Header,
+		justification: Option<Justification>,
+		body: Option<Vec<<Block as BlockT>::Extrinsic>>,
+	) -> crate::error::Result<()> {
+		let number = header.number().clone();
+		let mut storage = self.storage.write();
+		storage.leaves.import(hash.clone(), number.clone(), header.parent_hash().clone());
+		storage.blocks.insert(hash.clone(), StoredBlock::new(header, body, justification));
+		storage.hashes.insert(number.clone(), hash.clone());
+		storage.best_hash = hash.clone();
+		storage.best_number = number.clone();
+		storage.finalized_hash = hash;
+		storage.finalized_number = number;
+
+		Ok(())
+	}
+
 	/// Get total number of blocks.
 	pub fn blocks_count(&self) -> usize {
 		self.storage.read().blocks.len()
@@ -448,6 +471,7 @@ pub struct BlockImportOperation<Block: BlockT, H: Hasher> {
 	aux: Vec<(Vec<u8>, Option<Vec<u8>>)>,
 	finalized_blocks: Vec<(BlockId<Block>, Option<Justification>)>,
 	set_head: Option<BlockId<Block>>,
+	imported_state: bool,
 }
 
 impl<Block, H> backend::BlockImportOperation<Block, H> for BlockImportOperation<Block, H>
@@ -508,6 +532,12 @@ where
 		Ok(root)
 	}
 
+	fn import_state(&mut self, top: StorageOverlay, children: ChildrenStorageOverlay) -> error::Result<H::Out> {
+		let root = self.reset_storage(top, children)?;
+		self.imported_state = true;
+		Ok(root)
+	}
+
 	fn insert_aux<I>(&mut self, ops: I) -> error::Result<()>
 		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
 	{
@@ -541,6 +571,7 @@ where
 	states: RwLock<HashMap<Block::Hash, InMemory<H>>>,
 	changes_trie_storage: ChangesTrieStorage<Block, H>,
 	blockchain: Blockchain<Block>,
+	offchain_storage: InMemOffchainStorage,
 }
 
 impl<Block, H> Backend<Block, H>
@@ -555,6 +586,7 @@ where
 			states: RwLock::new(HashMap::new()),
 			changes_trie_storage: ChangesTrieStorage(InMemoryChangesTrieStorage::new()),
 			blockchain: Blockchain::new(),
+			offchain_storage: Default::default(),
 		}
 	}
 }
@@ -590,6 +622,7 @@ where
 	type Blockchain = Blockchain<Block>;
 	type State = InMemory<H>;
 	type ChangesTrieStorage = ChangesTrieStorage<Block, H>;
+	type OffchainStorage = InMemOffchainStorage;
 
 	fn begin_operation(&self) -> error::Result<Self::BlockImportOperation> {
 		let old_state = self.state_at(BlockId::Hash(Default::default()))?;
@@ -602,6 +635,7 @@ where
 			aux: Default::default(),
 			finalized_blocks: Default::default(),
 			set_head: None,
+			imported_state: false,
 		})
 	}
 
@@ -637,7 +671,11 @@ where
 				}
 			}
 
-			self.blockchain.insert(hash, header, justification, body, pending_block.state)?;
+			if operation.imported_state {
+				self.blockchain.insert_base(hash, header, justification, body)?;
+			} else {
+				self.blockchain.insert(hash, header, justification, body, pending_block.state)?;
+			}
 		}
 
 		if !operation.aux.is_empty() {
@@ -667,6 +705,10 @@ where
 		Some(&self.changes_trie_storage)
 	}
 
+	fn offchain_storage(&self) -> Option<Self::OffchainStorage> {
+		Some(self.offchain_storage.clone())
+	}
+
 	fn state_at(&self, block: BlockId<Block>) -> error::Result<Self::State> {
 		match block {
 			BlockId::Hash(h) if h == Default::default() => {
@@ -64,7 +64,10 @@ pub use crate::client::{
 #[cfg(feature = "std")]
 pub use crate::notifications::{StorageEventStream, StorageChangeSet};
 #[cfg(feature = "std")]
-pub use state_machine::{ExecutionStrategy, NeverOffchainExt};
+pub use state_machine::{
+	ExecutionStrategy, NeverOffchainExt, StorageRange, StorageAccess, StorageAccessKind, StorageTrace,
+	read_range_proof_check,
+};
 #[cfg(feature = "std")]
 pub use crate::leaves::LeafSet;
 
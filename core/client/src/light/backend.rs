@@ -22,6 +22,7 @@ use std::sync::{Arc, Weak};
 use futures::{Future, IntoFuture};
 use parking_lot::RwLock;
 
+use primitives::offchain::InMemOffchainStorage;
 use runtime_primitives::{generic::BlockId, Justification, StorageOverlay, ChildrenStorageOverlay};
 use state_machine::{Backend as StateBackend, TrieBackend, backend::InMemory as InMemoryState};
 use runtime_primitives::traits::{Block as BlockT, NumberFor, Zero, Header};
@@ -113,6 +114,7 @@ impl<S, F, Block, H> ClientBackend<Block, H> for Backend<S, F, H> where
 	type Blockchain = Blockchain<S, F>;
 	type State = OnDemandOrGenesisState<Block, S, F, H>;
 	type ChangesTrieStorage = in_mem::ChangesTrieStorage<Block, H>;
+	type OffchainStorage = InMemOffchainStorage;
 
 	fn begin_operation(&self) -> ClientResult<Self::BlockImportOperation> {
 		Ok(ImportOperation {
@@ -190,6 +192,10 @@ impl<S, F, Block, H> ClientBackend<Block, H> for Backend<S, F, H> where
 		None
 	}
 
+	fn offchain_storage(&self) -> Option<Self::OffchainStorage> {
+		None
+	}
+
 	fn state_at(&self, block: BlockId<Block>) -> ClientResult<Self::State> {
 		let block_number = self.blockchain.expect_block_number_from_id(&block)?;
 
@@ -297,6 +303,10 @@ where
 		Ok(storage_root)
 	}
 
+	fn import_state(&mut self, _top: StorageOverlay, _children: ChildrenStorageOverlay) -> ClientResult<H::Out> {
+		Err(ClientError::NotAvailableOnLightClient)
+	}
+
 	fn insert_aux<I>(&mut self, ops: I) -> ClientResult<()>
 		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
 	{
@@ -482,6 +492,34 @@ where
 		}
 	}
 
+	fn paged_pairs(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> ClientResult<Vec<(Vec<u8>, Vec<u8>)>> {
+		match *self {
+			OnDemandOrGenesisState::OnDemand(ref state) =>
+				StateBackend::<H>::paged_pairs(state, prefix, start_key, count),
+			OnDemandOrGenesisState::Genesis(ref state) =>
+				Ok(state.paged_pairs(prefix, start_key, count).expect(IN_MEMORY_EXPECT_PROOF)),
+		}
+	}
+
+	fn paged_keys(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> ClientResult<Vec<Vec<u8>>> {
+		match *self {
+			OnDemandOrGenesisState::OnDemand(ref state) =>
+				StateBackend::<H>::paged_keys(state, prefix, start_key, count),
+			OnDemandOrGenesisState::Genesis(ref state) =>
+				Ok(state.paged_keys(prefix, start_key, count).expect(IN_MEMORY_EXPECT_PROOF)),
+		}
+	}
+
 	fn try_into_trie_backend(self) -> Option<TrieBackend<Self::TrieBackendStorage, H>> {
 		match self {
 			OnDemandOrGenesisState::OnDemand(state) => state.try_into_trie_backend(),
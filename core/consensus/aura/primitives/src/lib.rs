@@ -18,12 +18,76 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use rstd::prelude::*;
+use parity_codec::{Encode, Decode, Compact};
 use substrate_client::decl_runtime_apis;
 use runtime_primitives::ConsensusEngineId;
+use runtime_primitives::generic::DigestItem;
+use runtime_primitives::traits::{Hash as HashT, Verify};
 
 /// The `ConsensusEngineId` of AuRa.
 pub const AURA_ENGINE_ID: ConsensusEngineId = [b'a', b'u', b'r', b'a'];
 
+/// Proof that an authority has sealed two different headers for the same slot.
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct EquivocationReport<AuthorityId, Signature> {
+	/// The equivocating authority.
+	pub offender: AuthorityId,
+	/// The slot both headers have been authored in.
+	pub slot: u64,
+	/// The first SCALE-encoded header, without its seal.
+	pub first_header: Vec<u8>,
+	/// The seal of the first header.
+	pub first_signature: Signature,
+	/// The second SCALE-encoded header, without its seal.
+	pub second_header: Vec<u8>,
+	/// The seal of the second header.
+	pub second_signature: Signature,
+}
+
+impl<AuthorityId, Signature> EquivocationReport<AuthorityId, Signature> where
+	AuthorityId: Decode,
+	Signature: Verify<Signer = AuthorityId>,
+{
+	/// Check that the headers are different, were authored in the reported
+	/// slot and are both sealed by the offender.
+	///
+	/// `Hashing` is the hashing algorithm of the chain's headers.
+	pub fn check<Hashing: HashT>(&self) -> bool where Hashing::Output: Decode {
+		let check_header = |header: &[u8], signature: &Signature| {
+			pre_header_slot::<Hashing::Output, AuthorityId>(header) == Some(self.slot)
+				&& signature.verify(Hashing::hash(header).as_ref(), &self.offender)
+		};
+
+		self.first_header != self.second_header
+			&& check_header(&self.first_header, &self.first_signature)
+			&& check_header(&self.second_header, &self.second_signature)
+	}
+}
+
+/// Extract the slot number from the AuRa pre-runtime digest of a SCALE-encoded
+/// header without seal.
+///
+/// Only the digest items known to `generic::DigestItem` are decoded, which makes
+/// this usable with runtimes that don't know about AuRa's pre-runtime digest.
+pub fn pre_header_slot<Hash: Decode, AuthorityId: Decode>(pre_header: &[u8]) -> Option<u64> {
+	// `generic::Header` is encoded as the parent hash, the compact block number,
+	// the state root, the extrinsics root and finally the digest items.
+	let (_, _, _, _, logs): (Hash, Compact<u128>, Hash, Hash, Vec<DigestItem<Hash, AuthorityId, ()>>) =
+		Decode::decode(&mut &pre_header[..])?;
+
+	let mut slots = logs.iter().filter_map(|log| match log {
+		DigestItem::PreRuntime(AURA_ENGINE_ID, data) => Some(u64::decode(&mut &data[..])),
+		_ => None,
+	});
+
+	match (slots.next(), slots.next()) {
+		(Some(slot), None) => slot,
+		_ => None,
+	}
+}
+
 decl_runtime_apis! {
 	/// API necessary for block authorship with aura.
 	pub trait AuraApi {
@@ -34,4 +98,72 @@ decl_runtime_apis! {
 		/// Dynamic slot duration may be supported in the future.
 		fn slot_duration() -> u64;
 	}
+
+	/// API for reporting equivocations of aura authorities.
+	pub trait AuraEquivocationApi {
+		/// Construct an unsigned extrinsic reporting the given SCALE-encoded
+		/// `EquivocationReport`. Returns `None` if the report can't be decoded.
+		fn construct_equivocation_report_extrinsic(report: Vec<u8>) -> Option<Block::Extrinsic>;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::{Pair, H256, sr25519};
+	use runtime_primitives::generic;
+	use runtime_primitives::traits::{BlakeTwo256, Header as HeaderT};
+
+	type Header = generic::Header<u64, BlakeTwo256, DigestItem<H256, sr25519::Public, sr25519::Signature>>;
+
+	fn pre_header(number: u64, slot: u64) -> Header {
+		let mut header = Header::new(
+			number,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		header.digest_mut().push(DigestItem::AuthoritiesChange(vec![sr25519::Public::from_raw([1; 32])]));
+		header.digest_mut().push(DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode()));
+		header
+	}
+
+	fn report(pair: &sr25519::Pair, first: Header, second: Header, slot: u64) -> EquivocationReport<sr25519::Public, sr25519::Signature> {
+		EquivocationReport {
+			offender: pair.public(),
+			slot,
+			first_signature: pair.sign(first.hash().as_ref()),
+			first_header: first.encode(),
+			second_signature: pair.sign(second.hash().as_ref()),
+			second_header: second.encode(),
+		}
+	}
+
+	#[test]
+	fn extracts_slot_from_pre_header() {
+		assert_eq!(pre_header_slot::<H256, sr25519::Public>(&pre_header(1, 42).encode()), Some(42));
+
+		let mut header = pre_header(1, 42);
+		header.digest_mut().pop();
+		assert_eq!(pre_header_slot::<H256, sr25519::Public>(&header.encode()), None);
+	}
+
+	#[test]
+	fn checks_equivocation_reports() {
+		let pair = sr25519::Pair::from_seed(&[1; 32]);
+		let other = sr25519::Pair::from_seed(&[2; 32]);
+
+		assert!(report(&pair, pre_header(1, 42), pre_header(2, 42), 42).check::<BlakeTwo256>());
+
+		// same header twice
+		assert!(!report(&pair, pre_header(1, 42), pre_header(1, 42), 42).check::<BlakeTwo256>());
+		// different slots
+		assert!(!report(&pair, pre_header(1, 42), pre_header(2, 43), 42).check::<BlakeTwo256>());
+
+		// sealed by someone else
+		let mut bad = report(&pair, pre_header(1, 42), pre_header(2, 42), 42);
+		bad.second_signature = other.sign(BlakeTwo256::hash(&bad.second_header).as_ref());
+		assert!(!bad.check::<BlakeTwo256>());
+	}
 }
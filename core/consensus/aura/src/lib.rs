@@ -51,7 +51,7 @@ use runtime_primitives::traits::{
 	Zero, Member,
 };
 
-use primitives::Pair;
+use primitives::{Pair, crypto::Sign};
 use inherents::{InherentDataProviders, InherentData};
 use authorities::AuthoritiesApi;
 
@@ -65,7 +65,11 @@ use srml_aura::{
 };
 use substrate_telemetry::{telemetry, CONSENSUS_TRACE, CONSENSUS_DEBUG, CONSENSUS_WARN, CONSENSUS_INFO};
 
-use slots::{CheckedHeader, SlotWorker, SlotInfo, SlotCompatible, slot_now, check_equivocation};
+use slots::{
+	CheckedHeader, SlotWorker, SlotInfo, SlotCompatible, EquivocationProof, slot_now, check_equivocation,
+	note_equivocation_report, equivocation_reports, remove_equivocation_report,
+};
+use transaction_pool::txpool::{self, ChainApi, Pool, IntoPoolError};
 
 pub use aura_primitives::*;
 pub use consensus_common::{SyncOracle, ExtraVerification};
@@ -127,7 +131,7 @@ impl SlotCompatible for AuraSlotCompatible {
 /// Start the aura worker. The returned future should be run in a tokio runtime.
 pub fn start_aura<B, C, SC, E, I, P, SO, Error, OnExit, H>(
 	slot_duration: SlotDuration,
-	local_key: Arc<P>,
+	local_key: Arc<dyn Sign<P>>,
 	client: Arc<C>,
 	select_chain: SC,
 	block_import: Arc<I>,
@@ -181,7 +185,7 @@ struct AuraWorker<C, E, I, P, SO> {
 	client: Arc<C>,
 	block_import: Arc<I>,
 	env: Arc<E>,
-	local_key: Arc<P>,
+	local_key: Arc<dyn Sign<P>>,
 	sync_oracle: SO,
 	inherent_data_providers: InherentDataProviders,
 	force_authoring: bool,
@@ -220,8 +224,8 @@ impl<H, B, C, E, I, P, Error, SO> SlotWorker<B> for AuraWorker<C, E, I, P, SO> w
 		chain_head: B::Header,
 		slot_info: SlotInfo,
 	) -> Self::OnSlot {
-		let pair = self.local_key.clone();
-		let public_key = self.local_key.public();
+		let local_key = self.local_key.clone();
+		let public_key = self.local_key.signing_key();
 		let client = self.client.clone();
 		let block_import = self.block_import.clone();
 		let env = self.env.clone();
@@ -326,7 +330,16 @@ impl<H, B, C, E, I, P, Error, SO> SlotWorker<B> for AuraWorker<C, E, I, P, SO> w
 			// sign the pre-sealed hash of the block and then
 			// add it to a digest item.
 			let header_hash = header.hash();
-			let signature = pair.sign(header_hash.as_ref());
+			let signature = match local_key.sign_message(header_hash.as_ref()) {
+				Some(signature) => signature,
+				None => {
+					warn!(target: "aura", "Unable to sign block {:?}: authority key not available", header_hash);
+					telemetry!(CONSENSUS_WARN; "aura.unable_signing_block";
+						"hash" => ?header_hash
+					);
+					return
+				}
+			};
 			let signature_digest_item = <DigestItemFor<B> as CompatibleDigestItem<P>>::aura_seal(signature);
 
 			let import_block: ImportBlock<B> = ImportBlock {
@@ -402,7 +415,7 @@ fn check_header<C, B: Block, P: Pair>(
 	authorities: &[AuthorityId<P>],
 ) -> Result<CheckedHeader<B::Header, (u64, DigestItemFor<B>)>, String> where
 	DigestItemFor<B>: CompatibleDigestItem<P>,
-	P::Signature: Decode,
+	P::Signature: Clone + Encode + Decode,
 	C: client::backend::AuxStore,
 	P::Public: AsRef<P::Public> + Encode + Decode + PartialEq + Clone,
 {
@@ -431,11 +444,16 @@ fn check_header<C, B: Block, P: Pair>(
 		let pre_hash = header.hash();
 
 		if P::verify(&sig, pre_hash.as_ref(), expected_author) {
+			// headers are kept sealed, so that an equivocation can be proven to the runtime.
+			// they are compared without their seal, which is randomized for sr25519.
+			let mut sealed_header = header.clone();
+			sealed_header.digest_mut().push(seal.clone());
+
 			if let Some(equivocation_proof) = check_equivocation(
 				client,
 				slot_now,
 				slot_num,
-				&header,
+				&sealed_header,
 				expected_author,
 			).map_err(|e| e.to_string())? {
 				info!(
@@ -444,6 +462,12 @@ fn check_header<C, B: Block, P: Pair>(
 					equivocation_proof.fst_header().hash(),
 					equivocation_proof.snd_header().hash(),
 				);
+
+				match equivocation_report::<B::Header, P>(&equivocation_proof, expected_author) {
+					Some(report) => note_equivocation_report(client, EQUIVOCATION_REPORTS_KEY, report.encode())
+						.map_err(|e| e.to_string())?,
+					None => warn!(target: "aura", "Unable to create report of equivocation at slot {}", slot_num),
+				}
 			}
 
 			Ok(CheckedHeader::Checked(header, (slot_num, seal)))
@@ -453,6 +477,88 @@ fn check_header<C, B: Block, P: Pair>(
 	}
 }
 
+const EQUIVOCATION_REPORTS_KEY: &[u8] = b"aura_equivocation_reports";
+
+/// Create a report of the equivocation that can be checked by the runtime.
+///
+/// Returns `None` if any of the headers isn't sealed.
+fn equivocation_report<H, P>(
+	proof: &EquivocationProof<H>,
+	offender: &AuthorityId<P>,
+) -> Option<EquivocationReport<AuthorityId<P>, P::Signature>> where
+	H: Header,
+	<H::Digest as Digest>::Item: CompatibleDigestItem<P>,
+	P: Pair,
+	P::Public: Clone,
+	P::Signature: Clone,
+{
+	let unseal = |header: &H| {
+		let mut header = header.clone();
+		let seal = header.digest_mut().pop()?;
+		let signature = seal.as_aura_seal()?.clone();
+		Some((header.encode(), signature))
+	};
+
+	let (first_header, first_signature) = unseal(proof.fst_header())?;
+	let (second_header, second_signature) = unseal(proof.snd_header())?;
+
+	Some(EquivocationReport {
+		offender: offender.clone(),
+		slot: proof.slot(),
+		first_header,
+		first_signature,
+		second_header,
+		second_signature,
+	})
+}
+
+/// Submit the reports of equivocations that were detected during block import
+/// to the transaction pool.
+///
+/// The report extrinsics are constructed by the runtime at the given block. A
+/// report is kept until the runtime doesn't consider it valid anymore, i.e. it
+/// has been included in the chain or the offender isn't an authority anymore.
+/// This should be called regularly, e.g. on every imported block.
+pub fn submit_equivocation_reports<B, C, A>(
+	client: &C,
+	transaction_pool: &Pool<A>,
+	at: &BlockId<B>,
+) -> CResult<()> where
+	B: Block,
+	C: ProvideRuntimeApi + AuxStore,
+	C::Api: AuraEquivocationApi<B>,
+	A: ChainApi<Block = B>,
+{
+	let reports = equivocation_reports(client, EQUIVOCATION_REPORTS_KEY)?;
+	if reports.is_empty() {
+		return Ok(());
+	}
+
+	let runtime_api = client.runtime_api();
+	for report in reports {
+		let extrinsic = match runtime_api.construct_equivocation_report_extrinsic(at, report.clone())? {
+			Some(extrinsic) => extrinsic,
+			None => {
+				warn!(target: "aura", "Runtime at {:?} rejected an equivocation report", at);
+				remove_equivocation_report(client, EQUIVOCATION_REPORTS_KEY, &report)?;
+				continue;
+			},
+		};
+
+		match transaction_pool.submit_one(at, extrinsic).map_err(IntoPoolError::into_pool_error) {
+			Ok(hash) => info!(target: "aura", "Submitted equivocation report: {:?}", hash),
+			Err(Ok(txpool::error::Error::InvalidTransaction(_))) => {
+				debug!(target: "aura", "Equivocation report is not valid anymore at {:?}", at);
+				remove_equivocation_report(client, EQUIVOCATION_REPORTS_KEY, &report)?;
+			},
+			Err(Ok(txpool::error::Error::AlreadyImported(_))) => {},
+			Err(e) => warn!(target: "aura", "Failed to submit equivocation report: {:?}", e),
+		}
+	}
+
+	Ok(())
+}
+
 /// A verifier for Aura blocks.
 pub struct AuraVerifier<C, E, P> {
 	client: Arc<C>,
@@ -533,7 +639,7 @@ impl<B: Block, C, E, P> Verifier<B> for AuraVerifier<C, E, P> where
 	E: ExtraVerification<B>,
 	P: Pair + Send + Sync + 'static,
 	P::Public: Send + Sync + Hash + Eq + Clone + Decode + Encode + Debug + AsRef<P::Public> + 'static,
-	P::Signature: Encode + Decode,
+	P::Signature: Clone + Encode + Decode,
 	Self: Authorities<B>,
 {
 	fn verify(
@@ -725,7 +831,7 @@ pub fn import_queue<B, C, E, P>(
 	E: 'static + ExtraVerification<B>,
 	P: Pair + Send + Sync + 'static,
 	P::Public: Clone + Eq + Send + Sync + Hash + Debug + Encode + Decode + AsRef<P::Public>,
-	P::Signature: Encode + Decode,
+	P::Signature: Clone + Encode + Decode,
 {
 	register_aura_inherent_data_provider(&inherent_data_providers, slot_duration.get())?;
 	initialize_authorities_cache(&*client)?;
@@ -908,7 +1014,7 @@ mod tests {
 
 			let aura = start_aura::<_, _, _, _, _, sr25519::Pair, _, _, _, _>(
 				slot_duration,
-				Arc::new(key.clone().into()),
+				Arc::new(key.pair()),
 				client.clone(),
 				select_chain,
 				client,
@@ -939,6 +1045,76 @@ mod tests {
 		runtime.block_on(wait_for.select(drive_to_completion).map_err(|_| ())).unwrap();
 	}
 
+	#[test]
+	fn equivocations_are_reported_and_slashed() {
+		use test_client::{TestClient as _, runtime::TestAPI};
+
+		let client = Arc::new(test_client::new());
+		let genesis = BlockId::Number(0);
+		let authorities = authorities(&*client, &genesis).unwrap();
+
+		// Bob is the author of slot 1.
+		let offender = Keyring::Bob.pair();
+		let sealed_header = |extrinsics_root| {
+			let mut header = <TestBlock as BlockT>::Header::new(
+				1,
+				extrinsics_root,
+				Default::default(),
+				client.genesis_hash(),
+				Default::default(),
+			);
+			header.digest_mut().push(
+				<DigestItemFor<TestBlock> as CompatibleDigestItem<sr25519::Pair>>::aura_pre_digest(1)
+			);
+			let signature = offender.sign(header.hash().as_ref());
+			header.digest_mut().push(
+				<DigestItemFor<TestBlock> as CompatibleDigestItem<sr25519::Pair>>::aura_seal(signature)
+			);
+			header
+		};
+
+		let check = |header: <TestBlock as BlockT>::Header| {
+			let hash = header.hash();
+			match check_header::<_, TestBlock, sr25519::Pair>(&*client, 1, header, hash, &authorities) {
+				Ok(CheckedHeader::Checked(..)) => {},
+				_ => panic!("header is valid"),
+			}
+		};
+		let reports = || equivocation_reports(&*client, EQUIVOCATION_REPORTS_KEY).unwrap();
+
+		// sealing the same block twice gives different seals, but is no equivocation.
+		check(sealed_header([1; 32].into()));
+		check(sealed_header([1; 32].into()));
+		assert!(reports().is_empty());
+
+		// two different blocks are authored for the same slot.
+		check(sealed_header([2; 32].into()));
+		assert_eq!(reports().len(), 1);
+
+		// the report gets submitted to the pool and is kept until it is included.
+		let pool = Pool::new(Default::default(), transaction_pool::ChainApi::new(client.clone()));
+		submit_equivocation_reports(&*client, &pool, &genesis).unwrap();
+		assert_eq!(pool.status().ready, 1);
+		submit_equivocation_reports(&*client, &pool, &genesis).unwrap();
+		assert_eq!(pool.status().ready, 1);
+		assert_eq!(reports().len(), 1);
+
+		// and the offender is slashed once it is included in a block.
+		assert_eq!(client.runtime_api().balance_of(&genesis, Keyring::Bob.into()).unwrap(), 1000);
+		let mut builder = client.new_block(Default::default()).unwrap();
+		for tx in pool.ready() {
+			builder.push(tx.data.clone()).unwrap();
+		}
+		let block = builder.bake().unwrap();
+		let best = BlockId::Hash(block.header().hash());
+		client.import(BlockOrigin::Own, block).unwrap();
+		assert_eq!(client.runtime_api().balance_of(&best, Keyring::Bob.into()).unwrap(), 0);
+
+		// the same offence can't be reported twice, so the report is dropped.
+		submit_equivocation_reports(&*client, &pool, &best).unwrap();
+		assert!(reports().is_empty());
+	}
+
 	#[test]
 	fn authorities_call_works() {
 		let client = test_client::new();
@@ -18,16 +18,158 @@
 #![deny(warnings, unsafe_code, missing_docs)]
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use rstd::vec::Vec;
 use runtime_primitives::ConsensusEngineId;
+use runtime_primitives::generic::DigestItem;
+use runtime_primitives::traits::{Hash as HashT, Verify};
 use substrate_client::decl_runtime_apis;
 
-use parity_codec::{Encode, Decode};
+use parity_codec::{Encode, Decode, Compact};
 
 /// The `ConsensusEngineId` of BABE.
 pub const BABE_ENGINE_ID: ConsensusEngineId = [b'b', b'a', b'b', b'e'];
 
+/// Length of a VRF output, the same as in `schnorrkel`.
+pub const VRF_OUTPUT_LENGTH: usize = 32;
+
+/// Length of a VRF proof, the same as in `schnorrkel`.
+pub const VRF_PROOF_LENGTH: usize = 64;
+
+/// The type of the BABE authority keys.
+pub type AuthorityId = primitives::sr25519::Public;
+
+/// The type of the seals of BABE blocks.
+pub type AuthoritySignature = primitives::sr25519::Signature;
+
+/// Randomness of an epoch, committed to by the VRF of the primary slot claims.
+pub type Randomness = [u8; VRF_OUTPUT_LENGTH];
+
+/// A BABE pre-runtime digest, in a form the runtime can decode without `schnorrkel`.
+///
+/// Blocks are either authored in a primary slot, claimed with a VRF output below the
+/// threshold, or in a secondary slot, assigned to one authority in a round-robin fashion
+/// when secondary slots are enabled.
+#[derive(Clone, Encode, Decode)]
+pub enum RawBabePreDigest {
+	/// A primary slot claim.
+	Primary {
+		/// VRF output.
+		vrf_output: [u8; VRF_OUTPUT_LENGTH],
+		/// VRF proof.
+		proof: [u8; VRF_PROOF_LENGTH],
+		/// Authority claiming the slot.
+		author: AuthorityId,
+		/// Slot number.
+		slot_num: u64,
+	},
+	/// A secondary slot claim.
+	Secondary {
+		/// Authority claiming the slot.
+		author: AuthorityId,
+		/// Slot number.
+		slot_num: u64,
+	},
+}
+
+impl RawBabePreDigest {
+	/// Returns the slot number of the pre digest.
+	pub fn slot_num(&self) -> u64 {
+		match *self {
+			RawBabePreDigest::Primary { slot_num, .. } => slot_num,
+			RawBabePreDigest::Secondary { slot_num, .. } => slot_num,
+		}
+	}
+
+	/// Returns the author of the pre digest.
+	pub fn author(&self) -> &AuthorityId {
+		match *self {
+			RawBabePreDigest::Primary { ref author, .. } => author,
+			RawBabePreDigest::Secondary { ref author, .. } => author,
+		}
+	}
+
+	/// Returns the VRF output of the pre digest, `None` for secondary slots.
+	pub fn vrf_output(&self) -> Option<&[u8; VRF_OUTPUT_LENGTH]> {
+		match *self {
+			RawBabePreDigest::Primary { ref vrf_output, .. } => Some(vrf_output),
+			RawBabePreDigest::Secondary { .. } => None,
+		}
+	}
+}
+
+/// Proof that an authority has sealed two different headers for the same slot.
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct EquivocationReport {
+	/// The equivocating authority.
+	pub offender: AuthorityId,
+	/// The slot both headers have been authored in.
+	pub slot: u64,
+	/// The first SCALE-encoded header, without its seal.
+	pub first_header: Vec<u8>,
+	/// The seal of the first header.
+	pub first_signature: AuthoritySignature,
+	/// The second SCALE-encoded header, without its seal.
+	pub second_header: Vec<u8>,
+	/// The seal of the second header.
+	pub second_signature: AuthoritySignature,
+}
+
+impl EquivocationReport {
+	/// Check that the headers are different, were authored by the offender in
+	/// the reported slot and are both sealed by the offender.
+	///
+	/// `Hashing` is the hashing algorithm of the chain's headers.
+	pub fn check<Hashing: HashT>(&self) -> bool where Hashing::Output: Decode {
+		let check_header = |header: &[u8], signature: &AuthoritySignature| {
+			match pre_header_digest::<Hashing::Output>(header) {
+				Some(pre_digest) => pre_digest.slot_num() == self.slot
+					&& *pre_digest.author() == self.offender
+					&& signature.verify(Hashing::hash(header).as_ref(), &self.offender),
+				None => false,
+			}
+		};
+
+		self.first_header != self.second_header
+			&& check_header(&self.first_header, &self.first_signature)
+			&& check_header(&self.second_header, &self.second_signature)
+	}
+}
+
+/// Extract the BABE pre-runtime digest of a SCALE-encoded header without seal.
+///
+/// Only the digest items known to `generic::DigestItem` are decoded, which makes
+/// this usable with runtimes that don't know about BABE's pre-runtime digest.
+pub fn pre_header_digest<Hash: Decode>(pre_header: &[u8]) -> Option<RawBabePreDigest> {
+	// `generic::Header` is encoded as the parent hash, the compact block number,
+	// the state root, the extrinsics root and finally the digest items.
+	let (_, _, _, _, logs): (Hash, Compact<u128>, Hash, Hash, Vec<DigestItem<Hash, AuthorityId, ()>>) =
+		Decode::decode(&mut &pre_header[..])?;
+
+	let mut pre_digests = logs.iter().filter_map(|log| match log {
+		DigestItem::PreRuntime(BABE_ENGINE_ID, data) => Some(RawBabePreDigest::decode(&mut &data[..])),
+		_ => None,
+	});
+
+	match (pre_digests.next(), pre_digests.next()) {
+		(Some(pre_digest), None) => pre_digest,
+		_ => None,
+	}
+}
+
+/// Data of the epoch following the current one, known as soon as the current one starts.
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct NextEpochDescriptor {
+	/// The authorities of the next epoch.
+	pub authorities: Vec<AuthorityId>,
+	/// The randomness of the next epoch.
+	pub randomness: Randomness,
+}
+
 /// Configuration data used by the BABE consensus engine.
-#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug, Encode, Decode)]
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct BabeConfiguration {
 	/// The slot duration in milliseconds for BABE. Currently, only
 	/// the value provided by this type at genesis will be used.
@@ -46,6 +188,18 @@ pub struct BabeConfiguration {
 	///
 	/// Dynamic thresholds may be supported in the future.
 	pub threshold: u64,
+
+	/// The length of an epoch, in slots.
+	pub epoch_length: u64,
+
+	/// The authorities of the first epoch.
+	pub genesis_authorities: Vec<AuthorityId>,
+
+	/// The randomness of the first epoch.
+	pub randomness: Randomness,
+
+	/// Whether secondary slots are claimed when no authority claims the primary slot.
+	pub secondary_slots: bool,
 }
 
 #[cfg(feature = "std")]
@@ -69,5 +223,81 @@ decl_runtime_apis! {
 		///
 		/// Dynamic configuration may be supported in the future.
 		fn startup_data() -> BabeConfiguration;
+
+		/// Return the data of the epoch following the current one.
+		fn next_epoch() -> NextEpochDescriptor;
+	}
+
+	/// API for reporting equivocations of BABE authorities.
+	pub trait BabeEquivocationApi {
+		/// Construct an unsigned extrinsic reporting the given SCALE-encoded
+		/// `EquivocationReport`. Returns `None` if the report can't be decoded.
+		fn construct_equivocation_report_extrinsic(report: Vec<u8>) -> Option<Block::Extrinsic>;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::{Pair, H256, sr25519};
+	use runtime_primitives::generic;
+	use runtime_primitives::traits::{BlakeTwo256, Header as HeaderT};
+
+	type Header = generic::Header<u64, BlakeTwo256, DigestItem<H256, AuthorityId, AuthoritySignature>>;
+
+	fn pre_header(number: u64, author: &sr25519::Pair, slot_num: u64) -> Header {
+		let mut header = Header::new(
+			number,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		let pre_digest = RawBabePreDigest::Secondary { author: author.public(), slot_num };
+		header.digest_mut().push(DigestItem::PreRuntime(BABE_ENGINE_ID, pre_digest.encode()));
+		header
+	}
+
+	fn report(pair: &sr25519::Pair, first: Header, second: Header, slot: u64) -> EquivocationReport {
+		EquivocationReport {
+			offender: pair.public(),
+			slot,
+			first_signature: pair.sign(first.hash().as_ref()),
+			first_header: first.encode(),
+			second_signature: pair.sign(second.hash().as_ref()),
+			second_header: second.encode(),
+		}
+	}
+
+	#[test]
+	fn extracts_pre_digest_from_pre_header() {
+		let pair = sr25519::Pair::from_seed(&[1; 32]);
+		let pre_digest = pre_header_digest::<H256>(&pre_header(1, &pair, 42).encode()).unwrap();
+		assert_eq!(pre_digest.slot_num(), 42);
+		assert_eq!(*pre_digest.author(), pair.public());
+
+		let mut header = pre_header(1, &pair, 42);
+		header.digest_mut().pop();
+		assert!(pre_header_digest::<H256>(&header.encode()).is_none());
+	}
+
+	#[test]
+	fn checks_equivocation_reports() {
+		let pair = sr25519::Pair::from_seed(&[1; 32]);
+		let other = sr25519::Pair::from_seed(&[2; 32]);
+
+		assert!(report(&pair, pre_header(1, &pair, 42), pre_header(2, &pair, 42), 42).check::<BlakeTwo256>());
+
+		// same header twice
+		assert!(!report(&pair, pre_header(1, &pair, 42), pre_header(1, &pair, 42), 42).check::<BlakeTwo256>());
+		// different slots
+		assert!(!report(&pair, pre_header(1, &pair, 42), pre_header(2, &pair, 43), 42).check::<BlakeTwo256>());
+		// slot claimed by someone else
+		assert!(!report(&pair, pre_header(1, &pair, 42), pre_header(2, &other, 42), 42).check::<BlakeTwo256>());
+
+		// sealed by someone else
+		let mut bad = report(&pair, pre_header(1, &pair, 42), pre_header(2, &pair, 42), 42);
+		bad.second_signature = other.sign(BlakeTwo256::hash(&bad.second_header).as_ref());
+		assert!(!bad.check::<BlakeTwo256>());
 	}
 }
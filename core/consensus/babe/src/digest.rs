@@ -16,61 +16,98 @@
 
 //! Private mplementation details of BABE digests.
 use primitives::sr25519::{Public, Signature};
-use babe_primitives::BABE_ENGINE_ID;
+use babe_primitives::{BABE_ENGINE_ID, RawBabePreDigest};
 use runtime_primitives::generic::DigestItem;
 use std::fmt::Debug;
 use parity_codec::{Decode, Encode, Input};
 use log::info;
-use schnorrkel::{
-	vrf::{VRFProof, VRFOutput, VRF_OUTPUT_LENGTH, VRF_PROOF_LENGTH},
-	PUBLIC_KEY_LENGTH,
-};
+use schnorrkel::vrf::{VRFProof, VRFOutput};
 
-/// A BABE pre-digest.  It includes:
+/// A BABE pre-digest. Primary slot claims include:
 ///
 /// * The public key of the author.
 /// * The VRF proof.
 /// * The VRF output.
 /// * The slot number.
+///
+/// Secondary slot claims only include the author and the slot number.
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct BabePreDigest {
-	pub(super) vrf_output: VRFOutput,
-	pub(super) proof: VRFProof,
-	pub(super) author: Public,
-	pub(super) slot_num: u64,
+pub enum BabePreDigest {
+	/// A primary slot claim.
+	Primary {
+		/// VRF output.
+		vrf_output: VRFOutput,
+		/// VRF proof.
+		proof: VRFProof,
+		/// Authority claiming the slot.
+		author: Public,
+		/// Slot number.
+		slot_num: u64,
+	},
+	/// A secondary slot claim.
+	Secondary {
+		/// Authority claiming the slot.
+		author: Public,
+		/// Slot number.
+		slot_num: u64,
+	},
+}
+
+impl BabePreDigest {
+	/// Returns the slot number of the pre digest.
+	pub fn slot_num(&self) -> u64 {
+		match *self {
+			BabePreDigest::Primary { slot_num, .. } => slot_num,
+			BabePreDigest::Secondary { slot_num, .. } => slot_num,
+		}
+	}
+
+	/// Returns the author of the block.
+	pub fn author(&self) -> &Public {
+		match *self {
+			BabePreDigest::Primary { ref author, .. } => author,
+			BabePreDigest::Secondary { ref author, .. } => author,
+		}
+	}
 }
 
 /// The prefix used by BABE for its VRF keys.
 pub const BABE_VRF_PREFIX: &'static [u8] = b"substrate-babe-vrf";
 
-type TmpDecode = (
-	[u8; VRF_OUTPUT_LENGTH],
-	[u8; VRF_PROOF_LENGTH],
-	[u8; PUBLIC_KEY_LENGTH],
-	u64,
-);
-
 impl Encode for BabePreDigest {
 	fn encode(&self) -> Vec<u8> {
-		let tmp: TmpDecode = (
-			*self.vrf_output.as_bytes(),
-			self.proof.to_bytes(),
-			self.author.0,
-			self.slot_num,
-		);
-		parity_codec::Encode::encode(&tmp)
+		let raw = match self {
+			BabePreDigest::Primary { vrf_output, proof, author, slot_num } => RawBabePreDigest::Primary {
+				vrf_output: *vrf_output.as_bytes(),
+				proof: proof.to_bytes(),
+				author: author.clone(),
+				slot_num: *slot_num,
+			},
+			BabePreDigest::Secondary { author, slot_num } => RawBabePreDigest::Secondary {
+				author: author.clone(),
+				slot_num: *slot_num,
+			},
+		};
+		parity_codec::Encode::encode(&raw)
 	}
 }
 
 impl Decode for BabePreDigest {
 	fn decode<R: Input>(i: &mut R) -> Option<Self> {
-		let (output, proof, public_key, slot_num): TmpDecode = Decode::decode(i)?;
-		Some(BabePreDigest {
-			proof: VRFProof::from_bytes(&proof).ok()?,
-			vrf_output: VRFOutput::from_bytes(&output).ok()?,
-			author: Public(public_key),
-			slot_num,
-		})
+		let raw: RawBabePreDigest = Decode::decode(i)?;
+		let pre_digest = match raw {
+			RawBabePreDigest::Primary { vrf_output, proof, author, slot_num } => BabePreDigest::Primary {
+				proof: VRFProof::from_bytes(&proof).ok()?,
+				vrf_output: VRFOutput::from_bytes(&vrf_output).ok()?,
+				author,
+				slot_num,
+			},
+			RawBabePreDigest::Secondary { author, slot_num } => BabePreDigest::Secondary {
+				author,
+				slot_num,
+			},
+		};
+		Some(pre_digest)
 	}
 }
 
@@ -0,0 +1,249 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tracking of the BABE epochs on the client side.
+//!
+//! The first block of every epoch is recorded in a fork tree, along with the data of its
+//! epoch and of the following one, as announced by the runtime. Blocks are verified and
+//! authored against the epoch found on their own chain, so that competing forks each use
+//! their own epoch data.
+//!
+//! Epochs without any block are skipped: the first block after them starts an epoch with
+//! the data announced for the next epoch, the same way the runtime does.
+
+use std::sync::Arc;
+
+use babe_primitives::{AuthorityId, BabeConfiguration, NextEpochDescriptor, Randomness};
+use client::{backend::AuxStore, blockchain::HeaderBackend, error::Result as ClientResult};
+use fork_tree::ForkTree;
+use parity_codec::{Decode, Encode};
+use parking_lot::Mutex;
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{Block as BlockT, NumberFor, Zero};
+
+const EPOCH_CHANGES_KEY: &[u8] = b"babe_epoch_changes";
+
+/// BABE epoch data.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Epoch {
+	/// The index of the epoch.
+	pub epoch_index: u64,
+	/// The first slot of the epoch.
+	pub start_slot: u64,
+	/// The length of the epoch, in slots.
+	pub duration: u64,
+	/// The authorities of the epoch.
+	pub authorities: Vec<AuthorityId>,
+	/// The randomness of the epoch.
+	pub randomness: Randomness,
+	/// Whether secondary slots are claimed when no authority claims the primary slot.
+	pub secondary_slots: bool,
+}
+
+impl Epoch {
+	/// The first epoch, started by the first block of the chain at `slot_num`.
+	pub fn genesis(config: &BabeConfiguration, slot_num: u64) -> Self {
+		Epoch {
+			epoch_index: 0,
+			start_slot: slot_num,
+			duration: config.epoch_length,
+			authorities: config.genesis_authorities.clone(),
+			randomness: config.randomness,
+			secondary_slots: config.secondary_slots,
+		}
+	}
+
+	/// The slot following the last slot of the epoch.
+	pub fn end_slot(&self) -> u64 {
+		self.start_slot + self.duration
+	}
+
+	/// The epoch following this one, as described by the runtime.
+	pub fn increment(&self, descriptor: NextEpochDescriptor) -> Epoch {
+		Epoch {
+			epoch_index: self.epoch_index + 1,
+			start_slot: self.end_slot(),
+			duration: self.duration,
+			authorities: descriptor.authorities,
+			randomness: descriptor.randomness,
+			secondary_slots: self.secondary_slots,
+		}
+	}
+
+	/// Skip the epochs without blocks before `slot_num`, keeping the same data.
+	fn skip_to(mut self, slot_num: u64) -> Epoch {
+		if slot_num >= self.end_slot() {
+			let skipped = (slot_num - self.start_slot) / self.duration;
+			self.epoch_index += skipped;
+			self.start_slot += skipped * self.duration;
+		}
+
+		self
+	}
+}
+
+/// An epoch change: the epoch started by a block, and the epoch announced after it.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct EpochChange {
+	/// The epoch started by the block.
+	pub epoch: Epoch,
+	/// The next epoch.
+	pub next: Epoch,
+}
+
+/// The epoch of a new block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockEpoch {
+	/// The epoch the block is in.
+	pub epoch: Epoch,
+	/// Whether the block is the first of its epoch.
+	pub is_change: bool,
+}
+
+type EpochChanges<B> = ForkTree<<B as BlockT>::Hash, NumberFor<B>, EpochChange>;
+
+/// Returns a function checking whether the second hash is a descendent of the first one.
+/// Both blocks must have been imported.
+fn is_descendent_of<'a, B, C>(client: &'a C)
+	-> impl Fn(&B::Hash, &B::Hash) -> ClientResult<bool> + 'a
+where
+	B: BlockT,
+	C: HeaderBackend<B>,
+{
+	move |base, hash| {
+		if base == hash { return Ok(false); }
+
+		let tree_route = client::blockchain::tree_route(
+			client,
+			BlockId::Hash(*hash),
+			BlockId::Hash(*base),
+		)?;
+
+		Ok(tree_route.common_block().hash == *base)
+	}
+}
+
+/// The epoch changes of all the forks, shared between the block import, the verifier and
+/// the worker.
+pub struct SharedEpochChanges<B: BlockT> {
+	inner: Arc<Mutex<EpochChanges<B>>>,
+}
+
+impl<B: BlockT> Clone for SharedEpochChanges<B> {
+	fn clone(&self) -> Self {
+		SharedEpochChanges { inner: self.inner.clone() }
+	}
+}
+
+impl<B: BlockT> SharedEpochChanges<B> {
+	/// Load the epoch changes from the auxiliary database.
+	pub fn load<C: AuxStore>(client: &C) -> ClientResult<Self> {
+		let epoch_changes = match client.get_aux(EPOCH_CHANGES_KEY)? {
+			Some(bytes) => EpochChanges::<B>::decode(&mut &bytes[..])
+				.ok_or_else(|| client::error::Error::Backend("Corrupted BABE epoch changes".into()))?,
+			None => EpochChanges::<B>::new(),
+		};
+
+		Ok(SharedEpochChanges { inner: Arc::new(Mutex::new(epoch_changes)) })
+	}
+
+	/// Find the epoch of a block authored at `slot_num` on top of the given parent.
+	pub fn epoch_for_child_of<C: HeaderBackend<B>>(
+		&self,
+		client: &C,
+		config: &BabeConfiguration,
+		parent_hash: &B::Hash,
+		parent_number: NumberFor<B>,
+		slot_num: u64,
+	) -> Result<BlockEpoch, String> {
+		if parent_number.is_zero() {
+			return Ok(BlockEpoch { epoch: Epoch::genesis(config, slot_num), is_change: true });
+		}
+
+		let epoch_changes = self.inner.lock();
+		let change = epoch_changes.find_ancestor_or_self(
+			parent_hash,
+			&parent_number,
+			&is_descendent_of(client),
+		)
+			.map_err(|e| format!("Error searching the epoch of {:?}: {:?}", parent_hash, e))?
+			.map(|(_, _, change)| change)
+			.ok_or_else(|| format!("No epoch data for the descendents of {:?}", parent_hash))?;
+
+		if slot_num < change.epoch.end_slot() {
+			Ok(BlockEpoch { epoch: change.epoch.clone(), is_change: false })
+		} else {
+			Ok(BlockEpoch { epoch: change.next.clone().skip_to(slot_num), is_change: true })
+		}
+	}
+
+	/// Record the epoch change of an imported block, and persist the epoch changes.
+	///
+	/// The changes older than the epoch of the last finalized block, and the ones of the
+	/// forks which can't be finalized anymore, are pruned first.
+	pub fn note_epoch_change<C: HeaderBackend<B> + AuxStore>(
+		&self,
+		client: &C,
+		hash: B::Hash,
+		number: NumberFor<B>,
+		change: EpochChange,
+	) -> Result<(), String> {
+		let info = client.info().map_err(|e| format!("Error fetching the finalized block: {:?}", e))?;
+
+		let mut epoch_changes = self.inner.lock();
+		if !info.finalized_number.is_zero() {
+			epoch_changes.prune(&info.finalized_hash, &info.finalized_number, &is_descendent_of(client))
+				.map_err(|e| format!("Error pruning the epoch changes at {:?}: {:?}", info.finalized_hash, e))?;
+		}
+
+		epoch_changes.import(hash, number, change, &is_descendent_of(client))
+			.map_err(|e| format!("Error recording the epoch change of {:?}: {:?}", hash, e))?;
+
+		epoch_changes.using_encoded(|s| client.insert_aux(&[(EPOCH_CHANGES_KEY, s)], &[]))
+			.map_err(|e| format!("Error writing the epoch changes: {:?}", e))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn epoch() -> Epoch {
+		Epoch {
+			epoch_index: 3,
+			start_slot: 100,
+			duration: 10,
+			authorities: Vec::new(),
+			randomness: [1; 32],
+			secondary_slots: true,
+		}
+	}
+
+	#[test]
+	fn next_epoch_starts_after_current_one() {
+		let next = epoch().increment(NextEpochDescriptor { authorities: Vec::new(), randomness: [2; 32] });
+		assert_eq!((next.epoch_index, next.start_slot, next.randomness), (4, 110, [2; 32]));
+	}
+
+	#[test]
+	fn skipped_epochs_keep_announced_data() {
+		assert_eq!(epoch().skip_to(109), epoch());
+
+		let skipped = epoch().skip_to(135);
+		assert_eq!((skipped.epoch_index, skipped.start_slot), (6, 130));
+		assert_eq!(skipped.randomness, [1; 32]);
+	}
+}
@@ -27,17 +27,20 @@
 #![deny(warnings)]
 extern crate core;
 mod digest;
+mod epoch_changes;
 use digest::CompatibleDigestItem;
 pub use digest::{BabePreDigest, BABE_VRF_PREFIX};
+pub use epoch_changes::{Epoch, EpochChange, SharedEpochChanges};
+use epoch_changes::BlockEpoch;
 pub use babe_primitives::*;
 pub use consensus_common::SyncOracle;
 use consensus_common::ExtraVerification;
 use runtime_primitives::{generic, generic::BlockId, Justification};
 use runtime_primitives::traits::{
 	Block, Header, Digest, DigestItemFor, DigestItem, ProvideRuntimeApi, AuthorityIdFor,
-	SimpleBitOps,
+	SimpleBitOps, One,
 };
-use std::{sync::Arc, u64, fmt::{Debug, Display}};
+use std::{sync::Arc, u64, fmt::{Debug, Display}, collections::HashMap};
 use runtime_support::serde::{Serialize, Deserialize};
 use parity_codec::{Decode, Encode};
 use primitives::{
@@ -55,29 +58,34 @@ use substrate_telemetry::{
 };
 use schnorrkel::{
 	keys::Keypair,
-	vrf::{
-		VRFProof, VRFProofBatchable, VRFInOut,
-	},
+	vrf::VRFInOut,
 };
 use authorities::AuthoritiesApi;
 use consensus_common::{
 	self, Authorities, BlockImport, Environment, Proposer,
-	ForkChoiceStrategy, ImportBlock, BlockOrigin, Error as ConsensusError,
+	ForkChoiceStrategy, ImportBlock, ImportResult, BlockOrigin, Error as ConsensusError,
 };
 use srml_babe::{
 	BabeInherentData,
 	timestamp::{TimestampInherentData, InherentType as TimestampInherent}
 };
 use consensus_common::{SelectChain, well_known_cache_keys};
-use consensus_common::import_queue::{Verifier, BasicQueue};
+use consensus_common::import_queue::{
+	Verifier, BasicQueue, SharedBlockImport, SharedJustificationImport, SharedFinalityProofImport,
+	SharedFinalityProofRequestBuilder,
+};
 use client::{
 	block_builder::api::BlockBuilder as BlockBuilderApi,
-	blockchain::ProvideCache,
+	blockchain::{ProvideCache, HeaderBackend},
 	runtime_api::ApiExt,
 	error::Result as CResult,
 	backend::AuxStore,
 };
-use slots::{CheckedHeader, check_equivocation};
+use slots::{
+	CheckedHeader, EquivocationProof, check_equivocation, note_equivocation_report,
+	equivocation_reports, remove_equivocation_report,
+};
+use transaction_pool::txpool::{self, ChainApi, Pool, IntoPoolError};
 use futures::{Future, IntoFuture, future};
 use tokio::timer::Timeout;
 use log::{error, warn, debug, info, trace};
@@ -117,6 +125,15 @@ impl Config {
 	pub fn threshold(&self) -> u64 {
 		self.0.threshold
 	}
+
+	/// Retrieve the length of the epochs, in slots.
+	pub fn epoch_length(&self) -> u64 {
+		self.0.epoch_length
+	}
+
+	fn babe_config(&self) -> BabeConfiguration {
+		self.0.get()
+	}
 }
 
 struct BabeSlotCompatible;
@@ -134,7 +151,7 @@ impl SlotCompatible for BabeSlotCompatible {
 }
 
 /// Parameters for BABE.
-pub struct BabeParams<C, E, I, SO, SC, OnExit> {
+pub struct BabeParams<B: Block, C, E, I, SO, SC, OnExit> {
 
 	/// The configuration for BABE.  Includes the slot duration, threshold, and
 	/// other parameters.
@@ -166,6 +183,9 @@ pub struct BabeParams<C, E, I, SO, SC, OnExit> {
 
 	/// Force authoring of blocks even if we are offline
 	pub force_authoring: bool,
+
+	/// The epoch changes, shared with the block import.
+	pub epoch_changes: SharedEpochChanges<B>,
 }
 
 /// Start the babe worker. The returned future should be run in a tokio runtime.
@@ -180,12 +200,13 @@ pub fn start_babe<B, C, SC, E, I, SO, Error, OnExit, H>(BabeParams {
 	on_exit,
 	inherent_data_providers,
 	force_authoring,
-}: BabeParams<C, E, I, SO, SC, OnExit>) -> Result<
+	epoch_changes,
+}: BabeParams<B, C, E, I, SO, SC, OnExit>) -> Result<
 	impl Future<Item=(), Error=()>,
 	consensus_common::Error,
 > where
 	B: Block<Header=H>,
-	C: ProvideRuntimeApi + ProvideCache<B>,
+	C: ProvideRuntimeApi + ProvideCache<B> + HeaderBackend<B>,
 	C::Api: AuthoritiesApi<B>,
 	SC: SelectChain<B>,
 	generic::DigestItem<B::Hash, Public, Signature>: DigestItem<Hash=B::Hash>,
@@ -210,7 +231,8 @@ pub fn start_babe<B, C, SC, E, I, SO, Error, OnExit, H>(BabeParams {
 		inherent_data_providers: inherent_data_providers.clone(),
 		sync_oracle: sync_oracle.clone(),
 		force_authoring,
-		threshold: config.threshold(),
+		config: config.babe_config(),
+		epoch_changes,
 	};
 	slots::start_slot_worker::<_, _, _, _, _, BabeSlotCompatible, _>(
 		config.0,
@@ -222,7 +244,7 @@ pub fn start_babe<B, C, SC, E, I, SO, Error, OnExit, H>(BabeParams {
 	)
 }
 
-struct BabeWorker<C, E, I, SO> {
+struct BabeWorker<B: Block, C, E, I, SO> {
 	client: Arc<C>,
 	block_import: Arc<I>,
 	env: Arc<E>,
@@ -230,12 +252,13 @@ struct BabeWorker<C, E, I, SO> {
 	sync_oracle: SO,
 	inherent_data_providers: InherentDataProviders,
 	force_authoring: bool,
-	threshold: u64,
+	config: BabeConfiguration,
+	epoch_changes: SharedEpochChanges<B>,
 }
 
-impl<Hash, H, B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<C, E, I, SO> where
+impl<Hash, H, B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<B, C, E, I, SO> where
 	B: Block<Header=H, Hash=Hash>,
-	C: ProvideRuntimeApi + ProvideCache<B>,
+	C: ProvideRuntimeApi + ProvideCache<B> + HeaderBackend<B>,
 	C::Api: AuthoritiesApi<B>,
 	E: Environment<B, Error=Error>,
 	E::Proposer: Proposer<B, Error=Error>,
@@ -273,21 +296,28 @@ impl<Hash, H, B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<C, E, I, SO> w
 		let (timestamp, slot_num, slot_duration) =
 			(slot_info.timestamp, slot_info.number, slot_info.duration);
 
-		let authorities = match authorities(client.as_ref(), &BlockId::Hash(chain_head.hash())) {
-			Ok(authorities) => authorities,
+		let epoch = match self.epoch_changes.epoch_for_child_of(
+			client.as_ref(),
+			&self.config,
+			&chain_head.hash(),
+			*chain_head.number(),
+			slot_num,
+		) {
+			Ok(BlockEpoch { epoch, .. }) => epoch,
 			Err(e) => {
 				error!(
 					target: "babe",
-					"Unable to fetch authorities at block {:?}: {:?}",
+					"Unable to fetch epoch data at block {:?}: {:?}",
 					chain_head.hash(),
 					e
 				);
-				telemetry!(CONSENSUS_WARN; "babe.unable_fetching_authorities";
+				telemetry!(CONSENSUS_WARN; "babe.unable_fetching_epoch_data";
 					"slot" => ?chain_head.hash(), "err" => ?e
 				);
 				return Box::new(future::ok(()));
 			}
 		};
+		let authorities = &epoch.authorities;
 
 		if !self.force_authoring && self.sync_oracle.is_offline() && authorities.len() > 1 {
 			debug!(target: "babe", "Skipping proposal slot. Waiting for the network.");
@@ -297,17 +327,11 @@ impl<Hash, H, B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<C, E, I, SO> w
 			return Box::new(future::ok(()));
 		}
 
-		// FIXME replace the dummy empty slices with real data
-		// https://github.com/paritytech/substrate/issues/2435
-		// https://github.com/paritytech/substrate/issues/2436
-		let proposal_work = if let Some((inout, proof, _batchable_proof)) = claim_slot(
-			&[0u8; 0],
+		let proposal_work = if let Some(inherent_digest) = claim_slot(
 			slot_info.number,
-			&[0u8; 0],
-			0,
-			&authorities,
+			&epoch,
 			&pair,
-			self.threshold,
+			self.config.threshold,
 		) {
 			debug!(
 				target: "babe", "Starting authorship at slot {}; timestamp = {}",
@@ -319,7 +343,7 @@ impl<Hash, H, B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<C, E, I, SO> w
 			);
 
 			// we are the slot author. make a block and sign it.
-			let proposer = match env.init(&chain_head, &authorities) {
+			let proposer = match env.init(&chain_head, authorities) {
 				Ok(p) => p,
 				Err(e) => {
 					warn!(target: "babe", "Unable to author block in slot {:?}: {:?}", slot_num, e);
@@ -330,13 +354,6 @@ impl<Hash, H, B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<C, E, I, SO> w
 				}
 			};
 
-			let inherent_digest = BabePreDigest {
-				proof,
-				vrf_output: inout.to_output(),
-				author: pair.public(),
-				slot_num,
-			};
-
 			// deadline our production to approx. the end of the slot
 			let remaining_duration = slot_info.remaining_duration();
 			Timeout::new(
@@ -465,7 +482,7 @@ fn check_header<B: Block + Sized, C: AuxStore>(
 	slot_now: u64,
 	mut header: B::Header,
 	hash: B::Hash,
-	authorities: &[Public],
+	epoch: &Epoch,
 	threshold: u64,
 ) -> Result<CheckedHeader<B::Header, (DigestItemFor<B>, DigestItemFor<B>)>, String>
 	where DigestItemFor<B>: CompatibleDigestItem,
@@ -481,23 +498,31 @@ fn check_header<B: Block + Sized, C: AuxStore>(
 	})?;
 
 	let pre_digest = find_pre_digest::<B>(&header)?;
-	let BabePreDigest { slot_num, ref author, ref proof, ref vrf_output } = pre_digest;
+	let slot_num = pre_digest.slot_num();
+	let author = pre_digest.author().clone();
 
 	if slot_num > slot_now {
 		header.digest_mut().push(seal);
-		Ok(CheckedHeader::Deferred(header, slot_num))
-	} else if !authorities.contains(&author) {
-		Err(babe_err!("Slot author not found"))
-	} else {
-		let pre_hash = header.hash();
+		return Ok(CheckedHeader::Deferred(header, slot_num));
+	}
 
-		if sr25519::Pair::verify(&sig, pre_hash, author) {
+	if !epoch.authorities.contains(&author) {
+		return Err(babe_err!("Slot author not found"));
+	}
+
+	let pre_hash = header.hash();
+	if !sr25519::Pair::verify(&sig, pre_hash, &author) {
+		return Err(babe_err!("Bad signature on {:?}", hash));
+	}
+
+	match pre_digest {
+		BabePreDigest::Primary { ref vrf_output, ref proof, .. } => {
 			let (inout, _batchable_proof) = {
 				let transcript = make_transcript(
-					Default::default(),
+					&epoch.randomness,
 					slot_num,
 					Default::default(),
-					0,
+					epoch.epoch_index,
 				);
 				schnorrkel::PublicKey::from_bytes(author.as_slice()).and_then(|p| {
 					p.vrf_verify(transcript, vrf_output, proof)
@@ -506,45 +531,147 @@ fn check_header<B: Block + Sized, C: AuxStore>(
 				})?
 			};
 
+			let threshold = threshold / epoch.authorities.len() as u64;
 			if !check(&inout, threshold) {
 				return Err(babe_err!("VRF verification of block by author {:?} failed: \
 									  threshold {} exceeded", author, threshold));
 			}
+		}
+		BabePreDigest::Secondary { .. } => {
+			if !epoch.secondary_slots {
+				return Err(babe_err!("Secondary slot claimed by {:?} while secondary slots \
+									  are disabled", author));
+			}
 
-			if let Some(equivocation_proof) = check_equivocation(
-				client,
-				slot_now,
-				slot_num,
-				&header,
-				author,
-			).map_err(|e| e.to_string())? {
-				info!(
-					"Slot author {:?} is equivocating at slot {} with headers {:?} and {:?}",
-					author,
-					slot_num,
-					equivocation_proof.fst_header().hash(),
-					equivocation_proof.snd_header().hash(),
-				);
+			let expected_author = secondary_slot_author(slot_num, &epoch.authorities);
+			if expected_author != Some(&author) {
+				return Err(babe_err!("Secondary slot {} claimed by {:?}, expected {:?}",
+									 slot_num, author, expected_author));
 			}
+		}
+	}
 
-			let pre_digest = CompatibleDigestItem::babe_pre_digest(pre_digest);
-			Ok(CheckedHeader::Checked(header, (pre_digest, seal)))
-		} else {
-			Err(babe_err!("Bad signature on {:?}", hash))
+	// headers are kept sealed, so that an equivocation can be proven to the runtime.
+	// they are compared without their seal, which is randomized for sr25519.
+	let mut sealed_header = header.clone();
+	sealed_header.digest_mut().push(seal.clone());
+
+	if let Some(equivocation_proof) = check_equivocation(
+		client,
+		slot_now,
+		slot_num,
+		&sealed_header,
+		&author,
+	).map_err(|e| e.to_string())? {
+		info!(
+			"Slot author {:?} is equivocating at slot {} with headers {:?} and {:?}",
+			author,
+			slot_num,
+			equivocation_proof.fst_header().hash(),
+			equivocation_proof.snd_header().hash(),
+		);
+
+		match equivocation_report::<B::Header>(&equivocation_proof, &author) {
+			Some(report) => note_equivocation_report(client, EQUIVOCATION_REPORTS_KEY, report.encode())
+				.map_err(|e| e.to_string())?,
+			None => warn!(target: "babe", "Unable to create report of equivocation at slot {}", slot_num),
 		}
 	}
+
+	let pre_digest = CompatibleDigestItem::babe_pre_digest(pre_digest);
+	Ok(CheckedHeader::Checked(header, (pre_digest, seal)))
+}
+
+const EQUIVOCATION_REPORTS_KEY: &[u8] = b"babe_equivocation_reports";
+
+/// Create a report of the equivocation that can be checked by the runtime.
+///
+/// Returns `None` if any of the headers isn't sealed.
+fn equivocation_report<H>(
+	proof: &EquivocationProof<H>,
+	offender: &AuthorityId,
+) -> Option<EquivocationReport> where
+	H: Header,
+	<H::Digest as Digest>::Item: CompatibleDigestItem,
+{
+	let unseal = |header: &H| {
+		let mut header = header.clone();
+		let seal = header.digest_mut().pop()?;
+		let signature = seal.as_babe_seal()?;
+		Some((header.encode(), signature))
+	};
+
+	let (first_header, first_signature) = unseal(proof.fst_header())?;
+	let (second_header, second_signature) = unseal(proof.snd_header())?;
+
+	Some(EquivocationReport {
+		offender: offender.clone(),
+		slot: proof.slot(),
+		first_header,
+		first_signature,
+		second_header,
+		second_signature,
+	})
+}
+
+/// Submit the reports of equivocations that were detected during block import
+/// to the transaction pool.
+///
+/// The report extrinsics are constructed by the runtime at the given block. A
+/// report is kept until the runtime doesn't consider it valid anymore, i.e. it
+/// has been included in the chain or the offender isn't an authority anymore.
+/// This should be called regularly, e.g. on every imported block.
+pub fn submit_equivocation_reports<B, C, A>(
+	client: &C,
+	transaction_pool: &Pool<A>,
+	at: &BlockId<B>,
+) -> CResult<()> where
+	B: Block,
+	C: ProvideRuntimeApi + AuxStore,
+	C::Api: BabeEquivocationApi<B>,
+	A: ChainApi<Block = B>,
+{
+	let reports = equivocation_reports(client, EQUIVOCATION_REPORTS_KEY)?;
+	if reports.is_empty() {
+		return Ok(());
+	}
+
+	let runtime_api = client.runtime_api();
+	for report in reports {
+		let extrinsic = match runtime_api.construct_equivocation_report_extrinsic(at, report.clone())? {
+			Some(extrinsic) => extrinsic,
+			None => {
+				warn!(target: "babe", "Runtime at {:?} rejected an equivocation report", at);
+				remove_equivocation_report(client, EQUIVOCATION_REPORTS_KEY, &report)?;
+				continue;
+			},
+		};
+
+		match transaction_pool.submit_one(at, extrinsic).map_err(IntoPoolError::into_pool_error) {
+			Ok(hash) => info!(target: "babe", "Submitted equivocation report: {:?}", hash),
+			Err(Ok(txpool::error::Error::InvalidTransaction(_))) => {
+				debug!(target: "babe", "Equivocation report is not valid anymore at {:?}", at);
+				remove_equivocation_report(client, EQUIVOCATION_REPORTS_KEY, &report)?;
+			},
+			Err(Ok(txpool::error::Error::AlreadyImported(_))) => {},
+			Err(e) => warn!(target: "babe", "Failed to submit equivocation report: {:?}", e),
+		}
+	}
+
+	Ok(())
 }
 
 /// A verifier for Babe blocks.
-pub struct BabeVerifier<C, E> {
+pub struct BabeVerifier<B: Block, C, E> {
 	client: Arc<C>,
 	extra: E,
 	inherent_data_providers: inherents::InherentDataProviders,
-	threshold: u64,
+	config: BabeConfiguration,
+	epoch_changes: SharedEpochChanges<B>,
 }
 
-impl<C, E> BabeVerifier<C, E> {
-	fn check_inherents<B: Block>(
+impl<B: Block, C, E> BabeVerifier<B, C, E> {
+	fn check_inherents(
 		&self,
 		block: B,
 		block_id: BlockId<B>,
@@ -580,8 +707,8 @@ impl<B: Block> ExtraVerification<B> for NothingExtra {
 	}
 }
 
-impl<B: Block, C, E> Verifier<B> for BabeVerifier<C, E> where
-	C: ProvideRuntimeApi + Send + Sync + AuxStore,
+impl<B: Block, C, E> Verifier<B> for BabeVerifier<B, C, E> where
+	C: ProvideRuntimeApi + HeaderBackend<B> + Send + Sync + AuxStore,
 	C::Api: BlockBuilderApi<B>,
 	DigestItemFor<B>: CompatibleDigestItem + DigestItem<AuthorityId=Public>,
 	E: ExtraVerification<B>,
@@ -612,8 +739,14 @@ impl<B: Block, C, E> Verifier<B> for BabeVerifier<C, E> where
 			.map_err(|e| format!("Could not extract timestamp and slot: {:?}", e))?;
 		let hash = header.hash();
 		let parent_hash = *header.parent_hash();
-		let authorities = self.authorities(&BlockId::Hash(parent_hash))
-			.map_err(|e| format!("Could not fetch authorities at {:?}: {:?}", parent_hash, e))?;
+		let slot_num = find_pre_digest::<B>(&header)?.slot_num();
+		let BlockEpoch { epoch, .. } = self.epoch_changes.epoch_for_child_of(
+			self.client.as_ref(),
+			&self.config,
+			&parent_hash,
+			*header.number() - One::one(),
+			slot_num,
+		)?;
 
 		let extra_verification = self.extra.verify(
 			&header,
@@ -628,14 +761,11 @@ impl<B: Block, C, E> Verifier<B> for BabeVerifier<C, E> where
 			slot_now + 1,
 			header,
 			hash,
-			&authorities[..],
-			self.threshold,
+			&epoch,
+			self.config.threshold,
 		)?;
 		match checked_header {
-			CheckedHeader::Checked(pre_header, (pre_digest, seal)) => {
-				let BabePreDigest { slot_num, .. } = pre_digest.as_babe_pre_digest()
-					.expect("check_header always returns a pre-digest digest item; qed");
-
+			CheckedHeader::Checked(pre_header, (_, seal)) => {
 				// if the body is passed through, we need to use the runtime
 				// to check that the internally-set timestamp in the inherents
 				// actually matches the slot set in the seal.
@@ -690,7 +820,7 @@ impl<B: Block, C, E> Verifier<B> for BabeVerifier<C, E> where
 	}
 }
 
-impl<B, C, E> Authorities<B> for BabeVerifier<C, E> where
+impl<B, C, E> Authorities<B> for BabeVerifier<B, C, E> where
 	B: Block,
 	C: ProvideRuntimeApi + ProvideCache<B>,
 	C::Api: AuthoritiesApi<B>,
@@ -743,6 +873,130 @@ fn register_babe_inherent_data_provider(
 	}
 }
 
+/// A block import recording the epoch changes of the imported blocks, wrapping the
+/// actual block import.
+pub struct BabeBlockImport<B: Block, I, C> {
+	inner: Arc<I>,
+	client: Arc<C>,
+	config: BabeConfiguration,
+	epoch_changes: SharedEpochChanges<B>,
+}
+
+impl<B, I, C> BlockImport<B> for BabeBlockImport<B, I, C> where
+	B: Block,
+	I: BlockImport<B, Error=ConsensusError>,
+	C: ProvideRuntimeApi + HeaderBackend<B> + AuxStore,
+	C::Api: BabeApi<B>,
+	DigestItemFor<B>: CompatibleDigestItem,
+{
+	type Error = ConsensusError;
+
+	fn check_block(
+		&self,
+		hash: B::Hash,
+		parent_hash: B::Hash,
+	) -> Result<ImportResult, Self::Error> {
+		self.inner.check_block(hash, parent_hash)
+	}
+
+	fn import_block(
+		&self,
+		block: ImportBlock<B>,
+		new_cache: HashMap<well_known_cache_keys::Id, Vec<u8>>,
+	) -> Result<ImportResult, Self::Error> {
+		let hash = block.post_header().hash();
+		let number = *block.header.number();
+		let slot_num = find_pre_digest::<B>(&block.header)
+			.map_err(ConsensusError::ClientImport)?
+			.slot_num();
+
+		let BlockEpoch { epoch, is_change } = self.epoch_changes.epoch_for_child_of(
+			self.client.as_ref(),
+			&self.config,
+			block.header.parent_hash(),
+			number - One::one(),
+			slot_num,
+		).map_err(ConsensusError::ClientImport)?;
+
+		let import_result = self.inner.import_block(block, new_cache)?;
+
+		// the runtime announces the next epoch in the first block of each epoch.
+		if let (ImportResult::Imported(_), true) = (&import_result, is_change) {
+			let next_epoch = self.client.runtime_api().next_epoch(&BlockId::Hash(hash))
+				.map_err(|e| ConsensusError::ClientImport(format!("{:?}", e)))?;
+
+			self.epoch_changes.note_epoch_change(
+				self.client.as_ref(),
+				hash,
+				number,
+				EpochChange { next: epoch.increment(next_epoch), epoch },
+			).map_err(ConsensusError::ClientImport)?;
+		}
+
+		Ok(import_result)
+	}
+}
+
+/// Create a BABE block import wrapping `inner`, along with the epoch changes it records,
+/// to be shared with the import queue and the worker.
+pub fn block_import<B, I, C>(
+	config: &Config,
+	inner: Arc<I>,
+	client: Arc<C>,
+) -> CResult<(BabeBlockImport<B, I, C>, SharedEpochChanges<B>)> where
+	B: Block,
+	C: AuxStore,
+{
+	let epoch_changes = SharedEpochChanges::load(&*client)?;
+	let import = BabeBlockImport {
+		inner,
+		client,
+		config: config.babe_config(),
+		epoch_changes: epoch_changes.clone(),
+	};
+
+	Ok((import, epoch_changes))
+}
+
+/// Start an import queue for the BABE consensus algorithm. The epoch changes must be the
+/// ones recorded by `block_import`.
+pub fn import_queue<B, C, E>(
+	config: Config,
+	block_import: SharedBlockImport<B>,
+	justification_import: Option<SharedJustificationImport<B>>,
+	finality_proof_import: Option<SharedFinalityProofImport<B>>,
+	finality_proof_request_builder: Option<SharedFinalityProofRequestBuilder<B>>,
+	client: Arc<C>,
+	epoch_changes: SharedEpochChanges<B>,
+	extra: E,
+	inherent_data_providers: InherentDataProviders,
+) -> Result<BabeImportQueue<B>, consensus_common::Error> where
+	B: Block,
+	C: 'static + ProvideRuntimeApi + ProvideCache<B> + HeaderBackend<B> + Send + Sync + AuxStore,
+	C::Api: BlockBuilderApi<B> + AuthoritiesApi<B>,
+	DigestItemFor<B>: CompatibleDigestItem + DigestItem<AuthorityId=Public>,
+	E: 'static + ExtraVerification<B>,
+{
+	register_babe_inherent_data_provider(&inherent_data_providers, config.get())?;
+
+	let verifier = Arc::new(
+		BabeVerifier {
+			client,
+			extra,
+			inherent_data_providers,
+			config: config.babe_config(),
+			epoch_changes,
+		}
+	);
+	Ok(BasicQueue::new(
+		verifier,
+		block_import,
+		justification_import,
+		finality_proof_import,
+		finality_proof_request_builder,
+	))
+}
+
 fn get_keypair(q: &sr25519::Pair) -> &Keypair {
 	q.as_ref()
 }
@@ -770,31 +1024,59 @@ fn check(inout: &VRFInOut, threshold: u64) -> bool {
 ///
 /// This hashes the slot number, epoch, genesis hash, and chain randomness into
 /// the VRF.  If the VRF produces a value less than `threshold`, it is our turn,
-/// so it returns `Some(_)`.  Otherwise, it returns `None`.
+/// so it returns a primary claim.  Otherwise, if secondary slots are enabled and
+/// it is our turn in the round-robin, it returns a secondary claim.
 fn claim_slot(
-	randomness: &[u8],
 	slot_number: u64,
-	genesis_hash: &[u8],
-	epoch: u64,
-	authorities: &[sr25519::Public],
+	epoch: &Epoch,
 	key: &sr25519::Pair,
 	threshold: u64,
-) -> Option<(VRFInOut, VRFProof, VRFProofBatchable)> {
-	if !authorities.contains(&key.public()) { return None }
+) -> Option<BabePreDigest> {
+	let public = key.public();
+	if !epoch.authorities.contains(&public) { return None }
 	let transcript = make_transcript(
-		randomness,
+		&epoch.randomness,
 		slot_number,
-		genesis_hash,
-		epoch,
+		Default::default(),
+		epoch.epoch_index,
 	);
 
 	// Compute the threshold we will use.
 	//
 	// We already checked that authorities contains `key.public()`, so it can’t
 	// be empty.  Therefore, this division is safe.
-	let threshold = threshold / authorities.len() as u64;
+	let threshold = threshold / epoch.authorities.len() as u64;
+
+	let primary = get_keypair(key).vrf_sign_n_check(transcript, |inout| check(inout, threshold))
+		.map(|(inout, proof, _batchable_proof)| BabePreDigest::Primary {
+			vrf_output: inout.to_output(),
+			proof,
+			author: public.clone(),
+			slot_num: slot_number,
+		});
+
+	primary.or_else(|| {
+		if !epoch.secondary_slots {
+			return None
+		}
 
-	get_keypair(key).vrf_sign_n_check(transcript, |inout| check(inout, threshold))
+		secondary_slot_author(slot_number, &epoch.authorities)
+			.filter(|author| **author == public)
+			.map(|_| BabePreDigest::Secondary { author: public.clone(), slot_num: slot_number })
+	})
+}
+
+/// The authority allowed to author a block in the given slot when it has no
+/// primary claim, in a round-robin fashion.
+fn secondary_slot_author(
+	slot_number: u64,
+	authorities: &[sr25519::Public],
+) -> Option<&sr25519::Public> {
+	if authorities.is_empty() {
+		return None
+	}
+
+	authorities.get((slot_number % authorities.len() as u64) as usize)
 }
 
 #[cfg(test)]
@@ -858,15 +1140,20 @@ mod tests {
 	const SLOT_DURATION: u64 = 1;
 	const TEST_ROUTING_INTERVAL: Duration = Duration::from_millis(50);
 
+	type TestBlockImport = BabeBlockImport<TestBlock, PeersFullClient, PeersFullClient>;
+	type PeerData = Mutex<Option<(Arc<TestBlockImport>, SharedEpochChanges<TestBlock>)>>;
+
 	pub struct BabeTestNet {
-		peers: Vec<Arc<Peer<(), DummySpecialization>>>,
+		peers: Vec<Arc<Peer<PeerData, DummySpecialization>>>,
 		started: bool,
+		// block import created along with the verifier of the peer being added.
+		pending_import: Mutex<Option<(Arc<TestBlockImport>, SharedEpochChanges<TestBlock>)>>,
 	}
 
 	impl TestNetFactory for BabeTestNet {
 		type Specialization = DummySpecialization;
-		type Verifier = BabeVerifier<PeersFullClient, NothingExtra>;
-		type PeerData = ();
+		type Verifier = BabeVerifier<TestBlock, PeersFullClient, NothingExtra>;
+		type PeerData = PeerData;
 
 		/// Create new test network with peers and given config.
 		fn from_config(_config: &ProtocolConfig) -> Self {
@@ -874,6 +1161,7 @@ mod tests {
 			BabeTestNet {
 				peers: Vec::new(),
 				started: false,
+				pending_import: Mutex::new(None),
 			}
 		}
 
@@ -892,14 +1180,34 @@ mod tests {
 			trace!(target: "babe", "Provider registered");
 
 			assert_eq!(config.get(), SLOT_DURATION);
+			let (block_import, epoch_changes) = block_import(&config, client.clone(), client.clone())
+				.expect("creates block import");
+			*self.pending_import.lock() = Some((Arc::new(block_import), epoch_changes.clone()));
+
 			Arc::new(BabeVerifier {
 				client,
 				extra: NothingExtra,
 				inherent_data_providers,
-				threshold: config.threshold(),
+				config: config.babe_config(),
+				epoch_changes,
 			})
 		}
 
+		fn make_block_import(&self, _client: PeersClient)
+			-> (
+				SharedBlockImport<TestBlock>,
+				Option<SharedJustificationImport<TestBlock>>,
+				Option<SharedFinalityProofImport<TestBlock>>,
+				Option<SharedFinalityProofRequestBuilder<TestBlock>>,
+				PeerData,
+			)
+		{
+			let (block_import, epoch_changes) = self.pending_import.lock().take()
+				.expect("the verifier is made before the block import; qed");
+
+			(block_import.clone(), None, None, None, Mutex::new(Some((block_import, epoch_changes))))
+		}
+
 		fn uses_tokio(&self) -> bool {
 			true
 		}
@@ -958,6 +1266,8 @@ mod tests {
 		let mut runtime = current_thread::Runtime::new().unwrap();
 		for (peer_id, key) in peers {
 			let client = net.lock().peer(*peer_id).client().as_full().unwrap();
+			let (block_import, epoch_changes) = net.lock().peer(*peer_id).data.lock().take()
+				.expect("block import set up by the test net");
 			let environ = Arc::new(DummyFactory(client.clone()));
 			import_notifications.push(
 				client.import_notification_stream()
@@ -980,7 +1290,7 @@ mod tests {
 			let babe = start_babe(BabeParams {
 				config,
 				local_key: Arc::new(key.clone().into()),
-				block_import: client.clone(),
+				block_import,
 				select_chain,
 				client,
 				env: environ.clone(),
@@ -988,6 +1298,7 @@ mod tests {
 				on_exit: futures::empty(),
 				inherent_data_providers,
 				force_authoring: false,
+				epoch_changes,
 			}).expect("Starts babe");
 
 			runtime.spawn(babe);
@@ -1036,14 +1347,25 @@ mod tests {
 		assert!(bad_seal.as_babe_seal().is_some())
 	}
 
+	fn test_epoch(authorities: Vec<Public>, secondary_slots: bool) -> Epoch {
+		Epoch {
+			epoch_index: 0,
+			start_slot: 0,
+			duration: 100,
+			authorities,
+			randomness: [0; 32],
+			secondary_slots,
+		}
+	}
+
 	#[test]
 	fn can_author_block() {
 		drop(env_logger::try_init());
-		let randomness = &[];
 		let pair = sr25519::Pair::generate();
+		let epoch = test_epoch(vec![pair.public()], false);
 		let mut i = 0;
 		loop {
-			match claim_slot(randomness, i, &[], 0, &[pair.public()], &pair, u64::MAX / 10) {
+			match claim_slot(i, &epoch, &pair, u64::MAX / 10) {
 				None => i += 1,
 				Some(s) => {
 					debug!(target: "babe", "Authored block {:?}", s);
@@ -1053,6 +1375,99 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn secondary_slots_are_claimed_by_one_authority() {
+		drop(env_logger::try_init());
+		let pairs: Vec<_> = (0..3).map(|_| sr25519::Pair::generate()).collect();
+		let epoch = test_epoch(pairs.iter().map(|p| p.public()).collect(), true);
+
+		for slot in 0..20 {
+			// with a zero threshold, no authority can claim the primary slot.
+			let claims: Vec<_> = pairs.iter()
+				.filter_map(|pair| claim_slot(slot, &epoch, pair, 0))
+				.collect();
+
+			assert_eq!(claims.len(), 1);
+			match claims[0] {
+				BabePreDigest::Secondary { ref author, slot_num } => {
+					assert_eq!(slot_num, slot);
+					assert_eq!(Some(author), secondary_slot_author(slot, &epoch.authorities));
+				}
+				BabePreDigest::Primary { .. } => panic!("primary slot claimed with a zero threshold"),
+			}
+		}
+
+		let epoch = test_epoch(epoch.authorities, false);
+		assert!(pairs.iter().all(|pair| claim_slot(0, &epoch, pair, 0).is_none()));
+	}
+
+	#[test]
+	fn equivocations_are_reported_and_slashed() {
+		use test_client::{TestClient as _, runtime::TestAPI};
+
+		drop(env_logger::try_init());
+		let client = Arc::new(test_client::new());
+		let genesis = BlockId::Number(0);
+		let epoch = test_epoch(authorities(&*client, &genesis).unwrap(), true);
+
+		// the author of the secondary slot 1.
+		let author = secondary_slot_author(1, &epoch.authorities).unwrap().clone();
+		let offender = Keyring::from_public(&author).unwrap();
+		let sealed_header = |extrinsics_root| {
+			let mut header = <TestBlock as BlockT>::Header::new(
+				1,
+				extrinsics_root,
+				Default::default(),
+				client.genesis_hash(),
+				Default::default(),
+			);
+			let pre_digest = BabePreDigest::Secondary { author: author.clone(), slot_num: 1 };
+			header.digest_mut().push(Item::babe_pre_digest(pre_digest));
+			let signature = offender.pair().sign(header.hash().as_ref());
+			header.digest_mut().push(Item::babe_seal(signature));
+			header
+		};
+
+		let check = |header: <TestBlock as BlockT>::Header| {
+			let hash = header.hash();
+			match check_header::<TestBlock, _>(&*client, 1, header, hash, &epoch, 0) {
+				Ok(CheckedHeader::Checked(..)) => {},
+				_ => panic!("header is valid"),
+			}
+		};
+		let reports = || equivocation_reports(&*client, EQUIVOCATION_REPORTS_KEY).unwrap();
+
+		// sealing the same block twice gives different seals, but is no equivocation.
+		check(sealed_header([1; 32].into()));
+		check(sealed_header([1; 32].into()));
+		assert!(reports().is_empty());
+
+		// two different blocks are authored for the same slot.
+		check(sealed_header([2; 32].into()));
+		assert_eq!(reports().len(), 1);
+
+		// the report gets submitted to the pool and is kept until it is included.
+		let pool = Pool::new(Default::default(), transaction_pool::ChainApi::new(client.clone()));
+		submit_equivocation_reports(&*client, &pool, &genesis).unwrap();
+		assert_eq!(pool.status().ready, 1);
+		assert_eq!(reports().len(), 1);
+
+		// and the offender is slashed once it is included in a block.
+		assert_eq!(client.runtime_api().balance_of(&genesis, offender.into()).unwrap(), 1000);
+		let mut builder = client.new_block(Default::default()).unwrap();
+		for tx in pool.ready() {
+			builder.push(tx.data.clone()).unwrap();
+		}
+		let block = builder.bake().unwrap();
+		let best = BlockId::Hash(block.header().hash());
+		client.import(BlockOrigin::Own, block).unwrap();
+		assert_eq!(client.runtime_api().balance_of(&best, offender.into()).unwrap(), 0);
+
+		// the same offence can't be reported twice, so the report is dropped.
+		submit_equivocation_reports(&*client, &pool, &best).unwrap();
+		assert!(reports().is_empty());
+	}
+
 	#[test]
 	fn authorities_call_works() {
 		drop(env_logger::try_init());
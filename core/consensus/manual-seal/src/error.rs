@@ -0,0 +1,53 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Error types of the manual seal engine.
+
+use consensus_common::Error as ConsensusError;
+
+/// Result type alias.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Manual seal errors.
+#[derive(Debug, derive_more::Display)]
+pub enum Error {
+	/// Consensus error.
+	Consensus(ConsensusError),
+	/// Unable to build the block.
+	#[display(fmt="Unable to build block: {}", _0)]
+	BlockBuilding(String),
+	/// Unable to import the block.
+	#[display(fmt="Unable to import block: {}", _0)]
+	BlockImport(String),
+	/// The engine isn't running anymore.
+	#[display(fmt="Sealing engine is not running")]
+	EngineStopped,
+}
+
+impl From<ConsensusError> for Error {
+	fn from(e: ConsensusError) -> Self {
+		Error::Consensus(e)
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Consensus(ref err) => Some(err),
+			_ => None,
+		}
+	}
+}
@@ -0,0 +1,264 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Manual and instant sealing engine for development chains.
+//!
+//! Instead of waiting for slots, the engine authors a block whenever it is told to: either
+//! through the `engine_createBlock` RPC (manual seal) or as soon as a transaction enters
+//! the transaction pool (instant seal). Blocks are built with the given proposer, usually
+//! `basic_authorship::ProposerFactory`, and can be finalized right away on import.
+//!
+//! There is no verification of the authored blocks, so the engine must only be used on
+//! chains with a single node, e.g. in integration tests.
+
+#![warn(missing_docs)]
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use consensus_common::{
+	BlockImport, BlockOrigin, Environment, ForkChoiceStrategy, ImportBlock, ImportResult,
+	Proposer, SelectChain, Error as ConsensusError,
+};
+use futures::{Future, IntoFuture, Stream, future};
+use inherents::InherentDataProviders;
+use log::{info, warn};
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
+use transaction_pool::txpool::{self, Pool};
+
+mod error;
+pub mod rpc;
+
+pub use self::error::{Error, Result};
+pub use self::rpc::{EngineCommand, ManualSeal, ManualSealApi};
+
+/// Maximal time spent building a block.
+const MAX_PROPOSAL_DURATION: Duration = Duration::from_secs(10);
+
+/// Author a block on top of the best block and import it.
+fn seal_new_block<B, E, I, SC>(
+	block_import: Arc<I>,
+	env: &E,
+	select_chain: &SC,
+	inherent_data_providers: &InherentDataProviders,
+	finalize: bool,
+) -> Box<dyn Future<Item=B::Hash, Error=Error> + Send> where
+	B: BlockT,
+	E: Environment<B>,
+	E::Error: std::fmt::Debug,
+	<<E::Proposer as Proposer<B>>::Create as IntoFuture>::Future: Send + 'static,
+	I: BlockImport<B> + Send + Sync + 'static,
+	SC: SelectChain<B>,
+{
+	let proposal = select_chain.best_chain()
+		.map_err(Error::from)
+		.and_then(|parent| {
+			let proposer = env.init(&parent, &[])
+				.map_err(|e| Error::BlockBuilding(format!("{:?}", e)))?;
+			let inherent_data = inherent_data_providers.create_inherent_data()
+				.map_err(|e| ConsensusError::InherentData(e.into_owned()))?;
+
+			Ok(proposer.propose(inherent_data, Default::default(), MAX_PROPOSAL_DURATION))
+		});
+
+	let proposal = match proposal {
+		Ok(proposal) => proposal.into_future(),
+		Err(e) => return Box::new(future::err(e)),
+	};
+
+	Box::new(proposal
+		.map_err(|e| Error::BlockBuilding(format!("{:?}", e)))
+		.and_then(move |block| {
+			let (header, body) = block.deconstruct();
+			let hash = header.hash();
+			let number = *header.number();
+
+			let import_block = ImportBlock {
+				origin: BlockOrigin::Own,
+				header,
+				justification: None,
+				post_digests: Vec::new(),
+				body: Some(body),
+				finalized: finalize,
+				auxiliary: Vec::new(),
+				fork_choice: ForkChoiceStrategy::LongestChain,
+			};
+
+			match block_import.import_block(import_block, Default::default()) {
+				Ok(ImportResult::Imported(_)) => {
+					info!("Sealed block #{} ({}){}", number, hash, if finalize { ", finalized" } else { "" });
+					Ok(hash)
+				},
+				Ok(result) => Err(Error::BlockImport(format!("{:?}", result))),
+				Err(e) => Err(Error::BlockImport(format!("{:?}", e))),
+			}
+		}))
+}
+
+/// Start the manual seal engine: a block is authored on top of the best block for each
+/// command received through `commands_stream`, e.g. from the `ManualSeal` RPC handler.
+///
+/// The returned future resolves once the stream ends.
+pub fn run_manual_seal<B, E, I, SC, CS>(
+	block_import: Arc<I>,
+	env: E,
+	select_chain: SC,
+	commands_stream: CS,
+	inherent_data_providers: InherentDataProviders,
+) -> impl Future<Item=(), Error=()> where
+	B: BlockT,
+	E: Environment<B>,
+	E::Error: std::fmt::Debug,
+	<<E::Proposer as Proposer<B>>::Create as IntoFuture>::Future: Send + 'static,
+	I: BlockImport<B> + Send + Sync + 'static,
+	SC: SelectChain<B>,
+	CS: Stream<Item=EngineCommand<B::Hash>, Error=()>,
+{
+	commands_stream.for_each(move |command| {
+		match command {
+			EngineCommand::SealNewBlock { finalize, sender } => {
+				seal_new_block(
+					block_import.clone(),
+					&env,
+					&select_chain,
+					&inherent_data_providers,
+					finalize,
+				).then(move |result| {
+					match sender {
+						Some(sender) => { let _ = sender.send(result); },
+						None => if let Err(e) = result {
+							warn!("Unable to seal new block: {}", e);
+						},
+					}
+
+					Ok(())
+				})
+			},
+		}
+	})
+}
+
+/// Start the instant seal engine: a block is authored on top of the best block as soon as
+/// a transaction is imported into the pool, and finalized on import if `finalize` is set.
+///
+/// The returned future never resolves.
+pub fn run_instant_seal<B, E, I, SC, A>(
+	block_import: Arc<I>,
+	env: E,
+	select_chain: SC,
+	pool: Arc<Pool<A>>,
+	inherent_data_providers: InherentDataProviders,
+	finalize: bool,
+) -> impl Future<Item=(), Error=()> where
+	B: BlockT,
+	E: Environment<B>,
+	E::Error: std::fmt::Debug,
+	<<E::Proposer as Proposer<B>>::Create as IntoFuture>::Future: Send + 'static,
+	I: BlockImport<B> + Send + Sync + 'static,
+	SC: SelectChain<B>,
+	A: txpool::ChainApi<Block=B>,
+{
+	let commands_stream = pool.import_notification_stream()
+		// several transactions imported at once end up in the same block.
+		.filter(move |_| pool.status().ready > 0)
+		.map(move |_| EngineCommand::SealNewBlock { finalize, sender: None });
+
+	run_manual_seal(block_import, env, select_chain, commands_stream, inherent_data_providers)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use basic_authorship::ProposerFactory;
+	use client::{BlockchainEvents, LongestChain};
+	use futures::sync::{mpsc, oneshot};
+	use runtime_primitives::generic::BlockId;
+	use test_client::{self, runtime::{Extrinsic, Transfer}, AccountKeyring};
+	use tokio::runtime::Runtime;
+
+	fn extrinsic(nonce: u64) -> Extrinsic {
+		Transfer {
+			amount: Default::default(),
+			nonce,
+			from: AccountKeyring::Alice.into(),
+			to: Default::default(),
+		}.into_signed_tx()
+	}
+
+	#[test]
+	fn manual_seal_authors_blocks_on_request() {
+		let mut runtime = Runtime::new().unwrap();
+		let client = Arc::new(test_client::new());
+		#[allow(deprecated)]
+		let select_chain = LongestChain::new(client.backend().clone(), client.import_lock());
+		let pool = Arc::new(Pool::new(Default::default(), transaction_pool::ChainApi::new(client.clone())));
+		let env = ProposerFactory { client: client.clone(), transaction_pool: pool.clone() };
+		let (commands, commands_stream) = mpsc::unbounded();
+
+		runtime.spawn(run_manual_seal(
+			client.clone(),
+			env,
+			select_chain,
+			commands_stream,
+			InherentDataProviders::new(),
+		));
+
+		pool.submit_one(&BlockId::number(0), extrinsic(0)).unwrap();
+		let (sender, receiver) = oneshot::channel();
+		commands.unbounded_send(EngineCommand::SealNewBlock { finalize: false, sender: Some(sender) }).unwrap();
+		let hash = runtime.block_on(receiver).unwrap().unwrap();
+
+		let info = client.info().unwrap().chain;
+		assert_eq!((info.best_number, info.best_hash), (1, hash));
+		assert_eq!(info.finalized_number, 0);
+		assert_eq!(client.body(&BlockId::Hash(hash)).unwrap().unwrap(), vec![extrinsic(0)]);
+
+		let (sender, receiver) = oneshot::channel();
+		commands.unbounded_send(EngineCommand::SealNewBlock { finalize: true, sender: Some(sender) }).unwrap();
+		let hash = runtime.block_on(receiver).unwrap().unwrap();
+
+		let info = client.info().unwrap().chain;
+		assert_eq!((info.best_number, info.finalized_hash), (2, hash));
+	}
+
+	#[test]
+	fn instant_seal_authors_blocks_on_transactions() {
+		let mut runtime = Runtime::new().unwrap();
+		let client = Arc::new(test_client::new());
+		#[allow(deprecated)]
+		let select_chain = LongestChain::new(client.backend().clone(), client.import_lock());
+		let pool = Arc::new(Pool::new(Default::default(), transaction_pool::ChainApi::new(client.clone())));
+		let env = ProposerFactory { client: client.clone(), transaction_pool: pool.clone() };
+		let imported = client.import_notification_stream();
+
+		runtime.spawn(run_instant_seal(
+			client.clone(),
+			env,
+			select_chain,
+			pool.clone(),
+			InherentDataProviders::new(),
+			true,
+		));
+
+		pool.submit_one(&BlockId::number(0), extrinsic(0)).unwrap();
+		let (notification, _) = runtime.block_on(imported.into_future()).map_err(|_| ()).unwrap();
+		let hash = notification.unwrap().hash;
+
+		let info = client.info().unwrap().chain;
+		assert_eq!((info.best_number, info.finalized_hash), (1, hash));
+		assert_eq!(client.body(&BlockId::Hash(hash)).unwrap().unwrap(), vec![extrinsic(0)]);
+	}
+}
@@ -0,0 +1,94 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC interface of the manual seal engine.
+
+use futures::{Future, future, sync::{mpsc, oneshot}};
+use jsonrpc_core::{BoxFuture, Error as RpcError, ErrorCode};
+use jsonrpc_derive::rpc;
+
+use crate::error::Error;
+
+/// Base code for all manual seal errors.
+const BASE_ERROR: i64 = 8000;
+
+/// Command sent to the sealing engine.
+pub enum EngineCommand<Hash> {
+	/// Author a new block on top of the best block and import it, finalizing it if
+	/// `finalize` is set. The hash of the new block is sent back through `sender`.
+	SealNewBlock {
+		/// Whether the block must be finalized on import.
+		finalize: bool,
+		/// Channel to send the result through.
+		sender: Option<oneshot::Sender<Result<Hash, Error>>>,
+	},
+}
+
+/// Manual seal API
+#[rpc]
+pub trait ManualSealApi<Hash> {
+	/// Author a new block on top of the best block and returns its hash. The block is
+	/// finalized on import if `finalize` is set.
+	#[rpc(name = "engine_createBlock")]
+	fn create_block(&self, finalize: bool) -> BoxFuture<Hash>;
+}
+
+/// Manual seal API, forwarding the calls to the sealing engine.
+pub struct ManualSeal<Hash> {
+	/// Sender of the engine commands.
+	commands: mpsc::UnboundedSender<EngineCommand<Hash>>,
+}
+
+impl<Hash> ManualSeal<Hash> {
+	/// Create new Manual seal API RPC handler, sending the commands to the engine fed
+	/// by the receiving end of `commands`.
+	pub fn new(commands: mpsc::UnboundedSender<EngineCommand<Hash>>) -> Self {
+		Self { commands }
+	}
+}
+
+impl<Hash: Send + 'static> ManualSealApi<Hash> for ManualSeal<Hash> {
+	fn create_block(&self, finalize: bool) -> BoxFuture<Hash> {
+		let (sender, receiver) = oneshot::channel();
+		let command = EngineCommand::SealNewBlock { finalize, sender: Some(sender) };
+		if self.commands.unbounded_send(command).is_err() {
+			return Box::new(future::err(Error::EngineStopped.into()));
+		}
+
+		Box::new(receiver.then(|result| match result {
+			Ok(Ok(hash)) => Ok(hash),
+			Ok(Err(e)) => Err(e.into()),
+			// the engine dropped the command without answering.
+			Err(_) => Err(Error::EngineStopped.into()),
+		}))
+	}
+}
+
+impl From<Error> for RpcError {
+	fn from(e: Error) -> Self {
+		let code = match e {
+			Error::Consensus(_) | Error::BlockBuilding(_) => BASE_ERROR + 1,
+			Error::BlockImport(_) => BASE_ERROR + 2,
+			Error::EngineStopped => BASE_ERROR + 3,
+		};
+
+		RpcError {
+			code: ErrorCode::ServerError(code),
+			message: e.to_string(),
+			data: None,
+		}
+	}
+}
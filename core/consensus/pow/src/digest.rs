@@ -0,0 +1,55 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! PoW digests
+//!
+//! The seal is opaque to the runtime, so it is stored in an `Other` digest item, prefixed
+//! with the PoW engine id.
+
+use parity_codec::{Encode, Decode};
+use runtime_primitives::ConsensusEngineId;
+use runtime_primitives::generic::DigestItem;
+
+use crate::{Seal, POW_ENGINE_ID};
+
+/// A digest item which is usable with PoW consensus.
+pub trait CompatibleDigestItem: Sized {
+	/// Construct a digest item which contains the seal.
+	fn pow_seal(seal: Seal) -> Self;
+
+	/// If this item is a PoW seal, return the seal.
+	fn as_pow_seal(&self) -> Option<Seal>;
+}
+
+impl<Hash, AuthorityId, SealSignature> CompatibleDigestItem for DigestItem<Hash, AuthorityId, SealSignature> {
+	fn pow_seal(seal: Seal) -> Self {
+		DigestItem::Other((POW_ENGINE_ID, seal).encode())
+	}
+
+	fn as_pow_seal(&self) -> Option<Seal> {
+		match self {
+			DigestItem::Other(ref buffer) => {
+				let (id, seal): (ConsensusEngineId, Seal) = Decode::decode(&mut &buffer[..])?;
+				if id == POW_ENGINE_ID {
+					Some(seal)
+				} else {
+					None
+				}
+			},
+			_ => None,
+		}
+	}
+}
@@ -0,0 +1,586 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Proof-of-work consensus.
+//!
+//! Unlike the other engines, blocks aren't authored by a set of authorities: anyone can
+//! author a block by finding a seal satisfying the difficulty required by the chain. The
+//! difficulty adjustment, the seal format and its verification are left to a
+//! `PowAlgorithm`.
+//!
+//! The difficulty and the total difficulty of the chain are stored in the auxiliary
+//! database for every imported block, and the chain with the highest total difficulty is
+//! the best chain.
+
+#![warn(missing_docs)]
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use client::{
+	backend::AuxStore, blockchain::HeaderBackend,
+	block_builder::api::BlockBuilder as BlockBuilderApi,
+};
+use consensus_common::{
+	BlockImport, BlockOrigin, Environment, ForkChoiceStrategy, ImportBlock, Proposer,
+	SelectChain, SyncOracle,
+};
+use consensus_common::import_queue::{
+	BasicQueue, SharedBlockImport, SharedJustificationImport, Verifier,
+};
+use futures::{Future, IntoFuture};
+use inherents::InherentDataProviders;
+use log::{debug, error, info};
+use parity_codec::{Encode, Decode};
+use runtime_primitives::{ConsensusEngineId, Justification};
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{
+	AuthorityIdFor, Block as BlockT, Digest, DigestItemFor, Header as HeaderT, ProvideRuntimeApi, Zero,
+};
+
+mod digest;
+pub mod sha256;
+
+pub use self::digest::CompatibleDigestItem;
+
+/// The PoW engine id.
+pub const POW_ENGINE_ID: ConsensusEngineId = *b"pow_";
+
+/// Prefix of the auxiliary storage keys of the PoW data.
+const POW_AUX_PREFIX: [u8; 4] = *b"PoW:";
+
+/// Maximal time spent building a block.
+const MAX_PROPOSAL_DURATION: Duration = Duration::from_secs(10);
+
+/// Block difficulty.
+pub type Difficulty = u128;
+
+/// Block seal, opaque to everything but the `PowAlgorithm`.
+pub type Seal = Vec<u8>;
+
+/// Auxiliary storage key of the PoW data of a block.
+fn aux_key<H: Encode>(hash: &H) -> Vec<u8> {
+	POW_AUX_PREFIX.iter().cloned().chain(hash.encode()).collect()
+}
+
+/// PoW data stored in the auxiliary database for every imported block.
+#[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PowAux {
+	/// Difficulty of the block.
+	pub difficulty: Difficulty,
+	/// Sum of the difficulties of the block and all its ancestors.
+	pub total_difficulty: Difficulty,
+}
+
+impl PowAux {
+	/// Read the PoW data of a block. Blocks imported without PoW data, e.g. the genesis
+	/// block, have a difficulty of zero.
+	pub fn read<C: AuxStore, H: Encode>(client: &C, hash: &H) -> Result<Self, String> {
+		Self::load(client, hash).map(Option::unwrap_or_default)
+	}
+
+	/// Read the PoW data of a block, if any was stored.
+	fn load<C: AuxStore, H: Encode>(client: &C, hash: &H) -> Result<Option<Self>, String> {
+		let key = aux_key(hash);
+
+		match client.get_aux(&key).map_err(|e| format!("{:?}", e))? {
+			Some(bytes) => PowAux::decode(&mut &bytes[..])
+				.map(Some)
+				.ok_or_else(|| "Could not decode PoW aux data".to_string()),
+			None => Ok(None),
+		}
+	}
+}
+
+/// Algorithm used for proof of work.
+pub trait PowAlgorithm<B: BlockT> {
+	/// Get the difficulty required for the next block on top of `parent`.
+	fn difficulty(&self, parent: &BlockId<B>) -> Result<Difficulty, String>;
+
+	/// Verify that the seal is valid against the given pre-hash, i.e. the hash of the
+	/// header without the seal, and difficulty.
+	fn verify(
+		&self,
+		parent: &BlockId<B>,
+		pre_hash: &B::Hash,
+		seal: &Seal,
+		difficulty: Difficulty,
+	) -> Result<bool, String>;
+
+	/// Try to find a seal for the given pre-hash and difficulty, making at most `round`
+	/// attempts. Returns `None` if no seal was found.
+	fn mine(
+		&self,
+		parent: &BlockId<B>,
+		pre_hash: &B::Hash,
+		difficulty: Difficulty,
+		round: u32,
+	) -> Result<Option<Seal>, String>;
+}
+
+/// Compute the PoW data of a new block on top of `parent_hash`, and whether it becomes the
+/// best block. Only the genesis block may lack PoW data.
+fn import_data<B, C>(
+	client: &C,
+	parent_hash: &B::Hash,
+	difficulty: Difficulty,
+) -> Result<(PowAux, ForkChoiceStrategy), String> where
+	B: BlockT,
+	C: HeaderBackend<B> + AuxStore,
+{
+	let parent_aux = match PowAux::load(client, parent_hash)? {
+		Some(aux) => aux,
+		None => {
+			let parent_header = client.header(BlockId::Hash(*parent_hash))
+				.map_err(|e| format!("{:?}", e))?
+				.ok_or_else(|| format!("Parent {:?} not found", parent_hash))?;
+			if !parent_header.number().is_zero() {
+				return Err(format!("Missing PoW data of parent {:?}", parent_hash));
+			}
+
+			PowAux::default()
+		},
+	};
+	let best_hash = client.info().map_err(|e| format!("{:?}", e))?.best_hash;
+	let best_aux = PowAux::read(client, &best_hash)?;
+
+	let aux = PowAux {
+		difficulty,
+		total_difficulty: parent_aux.total_difficulty.saturating_add(difficulty),
+	};
+	let fork_choice = ForkChoiceStrategy::Custom(aux.total_difficulty > best_aux.total_difficulty);
+
+	Ok((aux, fork_choice))
+}
+
+/// Build the block to import from a sealed header, storing its PoW data.
+fn import_block<B: BlockT>(
+	origin: BlockOrigin,
+	header: B::Header,
+	seal: DigestItemFor<B>,
+	justification: Option<Justification>,
+	body: Option<Vec<B::Extrinsic>>,
+	aux: PowAux,
+	fork_choice: ForkChoiceStrategy,
+) -> ImportBlock<B> {
+	let mut block = ImportBlock {
+		origin,
+		header,
+		justification,
+		post_digests: vec![seal],
+		body,
+		finalized: false,
+		auxiliary: Vec::new(),
+		fork_choice,
+	};
+
+	let key = aux_key(&block.post_header().hash());
+	block.auxiliary.push((key, Some(aux.encode())));
+	block
+}
+
+/// A verifier for PoW blocks.
+pub struct PowVerifier<C, A> {
+	client: Arc<C>,
+	algorithm: A,
+	inherent_data_providers: InherentDataProviders,
+}
+
+impl<C, A> PowVerifier<C, A> {
+	/// Create a new PoW verifier.
+	pub fn new(client: Arc<C>, algorithm: A, inherent_data_providers: InherentDataProviders) -> Self {
+		PowVerifier { client, algorithm, inherent_data_providers }
+	}
+
+	fn check_inherents<B: BlockT>(&self, block: B, block_id: BlockId<B>) -> Result<(), String> where
+		C: ProvideRuntimeApi,
+		C::Api: BlockBuilderApi<B>,
+	{
+		let inherent_data = self.inherent_data_providers.create_inherent_data().map_err(String::from)?;
+		let inherent_res = self.client.runtime_api().check_inherents(
+			&block_id,
+			block,
+			inherent_data,
+		).map_err(|e| format!("{:?}", e))?;
+
+		if inherent_res.ok() {
+			Ok(())
+		} else {
+			inherent_res
+				.into_errors()
+				.try_for_each(|(i, e)| Err(self.inherent_data_providers.error_to_string(&i, &e)))
+		}
+	}
+}
+
+impl<B, C, A> Verifier<B> for PowVerifier<C, A> where
+	B: BlockT,
+	C: ProvideRuntimeApi + HeaderBackend<B> + AuxStore + Send + Sync,
+	C::Api: BlockBuilderApi<B>,
+	A: PowAlgorithm<B> + Send + Sync,
+	DigestItemFor<B>: CompatibleDigestItem,
+{
+	fn verify(
+		&self,
+		origin: BlockOrigin,
+		mut header: B::Header,
+		justification: Option<Justification>,
+		mut body: Option<Vec<B::Extrinsic>>,
+	) -> Result<(ImportBlock<B>, Option<Vec<AuthorityIdFor<B>>>), String> {
+		let hash = header.hash();
+		let seal_item = header.digest_mut().pop()
+			.ok_or_else(|| format!("Header {:?} is unsealed", hash))?;
+		let seal = seal_item.as_pow_seal()
+			.ok_or_else(|| format!("Header {:?} has a bad seal", hash))?;
+
+		let pre_hash = header.hash();
+		let parent_hash = *header.parent_hash();
+		let parent = BlockId::Hash(parent_hash);
+		let difficulty = self.algorithm.difficulty(&parent)?;
+
+		if !self.algorithm.verify(&parent, &pre_hash, &seal, difficulty)? {
+			return Err(format!("Header {:?} has an invalid seal", hash));
+		}
+
+		if let Some(inner_body) = body.take() {
+			let block = B::new(header.clone(), inner_body);
+			self.check_inherents(block.clone(), parent)?;
+
+			let (_, inner_body) = block.deconstruct();
+			body = Some(inner_body);
+		}
+
+		let (aux, fork_choice) = import_data(&*self.client, &parent_hash, difficulty)?;
+		debug!(target: "pow", "Checked {:?} with difficulty {}; importing.", hash, difficulty);
+
+		let import_block = import_block(origin, header, seal_item, justification, body, aux, fork_choice);
+		Ok((import_block, None))
+	}
+}
+
+/// The PoW import queue type.
+pub type PowImportQueue<B> = BasicQueue<B>;
+
+/// Start an import queue for PoW blocks.
+pub fn import_queue<B, C, A>(
+	block_import: SharedBlockImport<B>,
+	justification_import: Option<SharedJustificationImport<B>>,
+	client: Arc<C>,
+	algorithm: A,
+	inherent_data_providers: InherentDataProviders,
+) -> PowImportQueue<B> where
+	B: BlockT,
+	C: ProvideRuntimeApi + HeaderBackend<B> + AuxStore + Send + Sync + 'static,
+	C::Api: BlockBuilderApi<B>,
+	A: PowAlgorithm<B> + Send + Sync + 'static,
+	DigestItemFor<B>: CompatibleDigestItem,
+{
+	let verifier = Arc::new(PowVerifier::new(client, algorithm, inherent_data_providers));
+
+	BasicQueue::new(verifier, block_import, justification_import, None, None)
+}
+
+/// Handle of a mining worker started with `start_mine`. Dropping the handle stops the
+/// worker and waits for its thread to finish the current round.
+pub struct MiningHandle {
+	exit: Arc<AtomicBool>,
+	thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Drop for MiningHandle {
+	fn drop(&mut self) {
+		self.exit.store(true, Ordering::SeqCst);
+		if let Some(thread) = self.thread.take() {
+			if thread.join().is_err() {
+				error!(target: "pow", "Mining worker panicked");
+			}
+		}
+	}
+}
+
+/// Start the mining worker on a new thread. The worker mines on top of the best block of
+/// `select_chain`, making `round` attempts at a time before checking whether the best
+/// block changed, and imports the mined blocks through `block_import`. The worker runs
+/// until the returned handle is dropped.
+pub fn start_mine<B, C, I, A, E, SC, SO>(
+	block_import: Arc<I>,
+	client: Arc<C>,
+	algorithm: A,
+	env: E,
+	select_chain: SC,
+	sync_oracle: SO,
+	inherent_data_providers: InherentDataProviders,
+	round: u32,
+) -> MiningHandle where
+	B: BlockT,
+	C: HeaderBackend<B> + AuxStore + Send + Sync + 'static,
+	I: BlockImport<B> + Send + Sync + 'static,
+	A: PowAlgorithm<B> + Send + 'static,
+	E: Environment<B> + Send + 'static,
+	E::Error: std::fmt::Debug,
+	SC: SelectChain<B> + 'static,
+	SO: SyncOracle + Send + 'static,
+	DigestItemFor<B>: CompatibleDigestItem,
+{
+	let exit = Arc::new(AtomicBool::new(false));
+	let worker_exit = exit.clone();
+
+	let thread = thread::spawn(move || {
+		while !worker_exit.load(Ordering::SeqCst) {
+			let result = mine_one(
+				&*block_import,
+				&*client,
+				&algorithm,
+				&env,
+				&select_chain,
+				&sync_oracle,
+				&inherent_data_providers,
+				round,
+				&worker_exit,
+			);
+
+			if let Err(e) = result {
+				error!(target: "pow", "Mining block failed with {}. Sleeping for 1 second before restarting...", e);
+				thread::sleep(Duration::from_secs(1));
+			}
+		}
+	});
+
+	MiningHandle { exit, thread: Some(thread) }
+}
+
+/// Mine a block on top of the best block and import it. Returns early, without importing
+/// anything, if the best block changes or `exit` is set in the meantime.
+fn mine_one<B, C, I, A, E, SC, SO>(
+	block_import: &I,
+	client: &C,
+	algorithm: &A,
+	env: &E,
+	select_chain: &SC,
+	sync_oracle: &SO,
+	inherent_data_providers: &InherentDataProviders,
+	round: u32,
+	exit: &AtomicBool,
+) -> Result<(), String> where
+	B: BlockT,
+	C: HeaderBackend<B> + AuxStore,
+	I: BlockImport<B>,
+	A: PowAlgorithm<B>,
+	E: Environment<B>,
+	E::Error: std::fmt::Debug,
+	SC: SelectChain<B>,
+	SO: SyncOracle,
+	DigestItemFor<B>: CompatibleDigestItem,
+{
+	if sync_oracle.is_major_syncing() {
+		debug!(target: "pow", "Skipping proposal due to sync.");
+		thread::sleep(Duration::from_secs(1));
+		return Ok(());
+	}
+
+	let best_hash = |select_chain: &SC| select_chain.best_chain()
+		.map(|header| header.hash())
+		.map_err(|e| format!("Fetching best header failed: {:?}", e));
+
+	let best_header = select_chain.best_chain()
+		.map_err(|e| format!("Fetching best header failed: {:?}", e))?;
+	let parent_hash = best_header.hash();
+	let parent = BlockId::Hash(parent_hash);
+
+	let proposer = env.init(&best_header, &[]).map_err(|e| format!("{:?}", e))?;
+	let inherent_data = inherent_data_providers.create_inherent_data().map_err(String::from)?;
+	let block = proposer.propose(inherent_data, Default::default(), MAX_PROPOSAL_DURATION)
+		.into_future()
+		.wait()
+		.map_err(|e| format!("Block proposal failed: {:?}", e))?;
+
+	let (header, body) = block.deconstruct();
+	let pre_hash = header.hash();
+	let difficulty = algorithm.difficulty(&parent)?;
+
+	let seal = loop {
+		if let Some(seal) = algorithm.mine(&parent, &pre_hash, difficulty, round)? {
+			break seal;
+		}
+
+		if exit.load(Ordering::SeqCst) {
+			return Ok(());
+		}
+
+		if best_hash(select_chain)? != parent_hash {
+			debug!(target: "pow", "Best block changed, restarting mining.");
+			return Ok(());
+		}
+	};
+
+	let (aux, fork_choice) = import_data(client, &parent_hash, difficulty)?;
+	let number = *header.number();
+	let import_block = import_block(
+		BlockOrigin::Own,
+		header,
+		<DigestItemFor<B> as CompatibleDigestItem>::pow_seal(seal),
+		None,
+		Some(body),
+		aux,
+		fork_choice,
+	);
+	let hash = import_block.post_header().hash();
+
+	block_import.import_block(import_block, HashMap::new())
+		.map_err(|e| format!("Error with block built on {:?}: {:?}", parent_hash, e))?;
+	info!(target: "pow", "Imported mined block #{} ({})", number, hash);
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use basic_authorship::ProposerFactory;
+	use client::{BlockchainEvents, LongestChain};
+	use consensus_common::NoNetwork;
+	use futures::Stream;
+	use sha256::Sha256Algorithm;
+	use test_client::{self, BlockBuilderExt, AccountKeyring, TestClient as _};
+	use test_client::runtime::{Block, Transfer};
+	use transaction_pool::txpool::Pool;
+
+	type TestClient = client::Client<test_client::Backend, test_client::Executor, Block, test_client::runtime::RuntimeApi>;
+
+	/// Build a block with a transfer of `amount` on top of `parent`, and seal it.
+	fn sealed_block(client: &TestClient, parent: BlockId<Block>, nonce: u64, amount: u64, difficulty: Difficulty) -> Block {
+		let mut builder = client.new_block_at(&parent, Default::default()).unwrap();
+		builder.push_transfer(Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+			amount,
+			nonce,
+		}).unwrap();
+		let (mut header, body) = builder.bake().unwrap().deconstruct();
+
+		let algorithm = Sha256Algorithm::new(difficulty);
+		let pre_hash = header.hash();
+		let seal = loop {
+			if let Some(seal) = algorithm.mine(&parent, &pre_hash, difficulty, 1000).unwrap() {
+				break seal;
+			}
+		};
+		header.digest_mut().push(CompatibleDigestItem::pow_seal(seal));
+
+		Block::new(header, body)
+	}
+
+	fn verify_and_import(client: &Arc<TestClient>, block: Block, difficulty: Difficulty) -> Result<(), String> {
+		let verifier = PowVerifier::new(client.clone(), Sha256Algorithm::new(difficulty), InherentDataProviders::new());
+		let (header, body) = block.deconstruct();
+		let (import_block, _) = verifier.verify(BlockOrigin::NetworkBroadcast, header, None, Some(body))?;
+		client.import_block(import_block, HashMap::new()).unwrap();
+
+		Ok(())
+	}
+
+	#[test]
+	fn verifier_rejects_invalid_seals() {
+		let client = Arc::new(test_client::new());
+		let block = sealed_block(&client, BlockId::number(0), 0, 1, 1000);
+
+		let (mut header, body) = block.clone().deconstruct();
+		header.digest_mut().pop();
+		assert!(verify_and_import(&client, Block::new(header.clone(), body.clone()), 1000).is_err());
+
+		header.digest_mut().push(CompatibleDigestItem::pow_seal(vec![1, 2, 3]));
+		assert!(verify_and_import(&client, Block::new(header, body), 1000).is_err());
+
+		verify_and_import(&client, block, 1000).unwrap();
+		assert_eq!(client.info().unwrap().chain.best_number, 1);
+	}
+
+	#[test]
+	fn best_chain_has_highest_total_difficulty() {
+		let client = Arc::new(test_client::new());
+
+		let a1 = sealed_block(&client, BlockId::number(0), 0, 1, 10);
+		verify_and_import(&client, a1.clone(), 10).unwrap();
+		assert_eq!(client.info().unwrap().chain.best_hash, a1.header().hash());
+		assert_eq!(
+			PowAux::read(&*client, &a1.header().hash()).unwrap(),
+			PowAux { difficulty: 10, total_difficulty: 10 },
+		);
+
+		// a shorter fork with a higher total difficulty becomes the best chain...
+		let b1 = sealed_block(&client, BlockId::number(0), 0, 2, 20);
+		verify_and_import(&client, b1.clone(), 20).unwrap();
+		assert_eq!(client.info().unwrap().chain.best_hash, b1.header().hash());
+
+		// ...and extending the former best chain without exceeding it doesn't switch back.
+		let a2 = sealed_block(&client, BlockId::Hash(a1.header().hash()), 1, 1, 5);
+		verify_and_import(&client, a2.clone(), 5).unwrap();
+		assert_eq!(client.info().unwrap().chain.best_hash, b1.header().hash());
+		assert_eq!(PowAux::read(&*client, &a2.header().hash()).unwrap().total_difficulty, 15);
+	}
+
+	#[test]
+	fn miner_imports_blocks() {
+		let client = Arc::new(test_client::new());
+		#[allow(deprecated)]
+		let select_chain = LongestChain::new(client.backend().clone(), client.import_lock());
+		let pool = Arc::new(Pool::new(Default::default(), transaction_pool::ChainApi::new(client.clone())));
+		let env = ProposerFactory { client: client.clone(), transaction_pool: pool };
+		let imported = client.import_notification_stream();
+
+		let handle = start_mine(
+			client.clone(),
+			client.clone(),
+			Sha256Algorithm::new(100),
+			env,
+			select_chain,
+			NoNetwork,
+			InherentDataProviders::new(),
+			1000,
+		);
+
+		let (notification, _) = imported.into_future().wait().map_err(|_| ()).unwrap();
+		let notification = notification.unwrap();
+		assert_eq!(*notification.header.number(), 1);
+		assert_eq!(
+			PowAux::read(&*client, &notification.hash).unwrap(),
+			PowAux { difficulty: 100, total_difficulty: 100 },
+		);
+
+		// dropping the handle stops the worker and joins its thread.
+		drop(handle);
+	}
+
+	#[test]
+	fn import_requires_parent_pow_data() {
+		let client = Arc::new(test_client::new());
+
+		// a block imported without going through the verifier has no PoW data.
+		let a1 = client.new_block(Default::default()).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, a1.clone()).unwrap();
+		assert!(PowAux::load(&*client, &a1.header().hash()).unwrap().is_none());
+
+		let a2 = sealed_block(&client, BlockId::Hash(a1.header().hash()), 0, 1, 10);
+		assert!(verify_and_import(&client, a2, 10).is_err());
+
+		// the genesis block is the only exception.
+		let b1 = sealed_block(&client, BlockId::number(0), 0, 1, 10);
+		verify_and_import(&client, b1, 10).unwrap();
+	}
+}
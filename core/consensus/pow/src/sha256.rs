@@ -0,0 +1,115 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A simple SHA-256 based PoW algorithm with a fixed difficulty, for tests and development
+//! chains.
+//!
+//! The seal is a nonce. It is valid if the first 16 bytes of the SHA-256 hash of the
+//! pre-hash and the nonce, read as a big endian number, times the difficulty don't
+//! overflow a `u128`.
+
+use parity_codec::{Encode, Decode};
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::Block as BlockT;
+use sha2::{Digest, Sha256};
+
+use crate::{Difficulty, PowAlgorithm, Seal};
+
+/// SHA-256 PoW algorithm with a fixed difficulty.
+#[derive(Debug, Clone)]
+pub struct Sha256Algorithm {
+	difficulty: Difficulty,
+}
+
+impl Sha256Algorithm {
+	/// Create a new algorithm requiring the given difficulty for every block.
+	pub fn new(difficulty: Difficulty) -> Self {
+		Sha256Algorithm { difficulty }
+	}
+}
+
+fn work<H: Encode>(pre_hash: &H, nonce: u64) -> u128 {
+	let mut hasher = Sha256::new();
+	pre_hash.using_encoded(|encoded| hasher.input(encoded));
+	nonce.using_encoded(|encoded| hasher.input(encoded));
+	let hash = hasher.result();
+
+	let mut high = [0u8; 16];
+	high.copy_from_slice(&hash[..16]);
+	u128::from_be_bytes(high)
+}
+
+fn meets_difficulty(work: u128, difficulty: Difficulty) -> bool {
+	work.checked_mul(difficulty).is_some()
+}
+
+impl<B: BlockT> PowAlgorithm<B> for Sha256Algorithm {
+	fn difficulty(&self, _parent: &BlockId<B>) -> Result<Difficulty, String> {
+		Ok(self.difficulty)
+	}
+
+	fn verify(
+		&self,
+		_parent: &BlockId<B>,
+		pre_hash: &B::Hash,
+		seal: &Seal,
+		difficulty: Difficulty,
+	) -> Result<bool, String> {
+		let nonce = u64::decode(&mut &seal[..]).ok_or_else(|| "Seal is not a nonce".to_string())?;
+		Ok(meets_difficulty(work(pre_hash, nonce), difficulty))
+	}
+
+	fn mine(
+		&self,
+		_parent: &BlockId<B>,
+		pre_hash: &B::Hash,
+		difficulty: Difficulty,
+		round: u32,
+	) -> Result<Option<Seal>, String> {
+		// start at a random nonce so that successive calls try different nonces.
+		let start: u64 = rand::random();
+		let seal = (0..u64::from(round))
+			.map(|i| start.wrapping_add(i))
+			.find(|nonce| meets_difficulty(work(pre_hash, *nonce), difficulty))
+			.map(|nonce| nonce.encode());
+
+		Ok(seal)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use test_client::runtime::{Block, H256};
+
+	#[test]
+	fn mined_seals_are_verified() {
+		let algorithm = Sha256Algorithm::new(1000);
+		let parent = BlockId::<Block>::number(0);
+		let pre_hash = H256::random();
+
+		let difficulty = PowAlgorithm::<Block>::difficulty(&algorithm, &parent).unwrap();
+		let seal = (0..100)
+			.filter_map(|_| algorithm.mine(&parent, &pre_hash, difficulty, 1000).unwrap())
+			.next()
+			.expect("finds a seal in 100_000 attempts");
+
+		assert!(algorithm.verify(&parent, &pre_hash, &seal, difficulty).unwrap());
+		// the seal doesn't hold for another block.
+		assert!(!(0..10).all(|_| algorithm.verify(&parent, &H256::random(), &seal, difficulty).unwrap()));
+		assert!(algorithm.verify(&parent, &pre_hash, &vec![1, 2], difficulty).is_err());
+	}
+}
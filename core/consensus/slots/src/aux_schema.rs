@@ -19,7 +19,7 @@
 use codec::{Encode, Decode};
 use client::backend::AuxStore;
 use client::error::{Result as ClientResult, Error as ClientError};
-use runtime_primitives::traits::Header;
+use runtime_primitives::traits::{Header, Digest};
 
 const SLOT_HEADER_MAP_KEY: &[u8] = b"slot_header_map";
 const SLOT_HEADER_START: &[u8] = b"slot_header_start";
@@ -69,8 +69,19 @@ impl<H> EquivocationProof<H> {
 	}
 }
 
+/// The hash of the header without its seal, i.e. its last digest item.
+fn pre_hash<H: Header>(header: &H) -> H::Hash {
+	let mut header = header.clone();
+	header.digest_mut().pop();
+	header.hash()
+}
+
 /// Checks if the header is an equivocation and returns the proof in that case.
 ///
+/// The header is expected to be sealed, with the seal as its last digest item.
+/// Headers that only differ in their seal are not an equivocation, since seals
+/// may be randomized (e.g. sr25519 signatures).
+///
 /// Note: it detects equivocations only when slot_now - slot <= MAX_SLOT_CAPACITY.
 pub fn check_equivocation<C, H, P>(
 	backend: &C,
@@ -106,8 +117,8 @@ pub fn check_equivocation<C, H, P>(
 		// A proof of equivocation consists of two headers:
 		// 1) signed by the same voter,
 		if prev_signer == signer {
-			// 2) with different hash
-			if header.hash() != prev_header.hash() {
+			// 2) with different pre-header hash
+			if pre_hash(header) != pre_hash(prev_header) {
 				return Ok(Some(EquivocationProof {
 					slot, // 3) and mentioning the same slot.
 					fst_header: prev_header.clone(),
@@ -149,14 +160,55 @@ pub fn check_equivocation<C, H, P>(
 	Ok(None)
 }
 
+/// Store a SCALE-encoded equivocation report under the given key, until it is
+/// removed with `remove_equivocation_report`.
+pub fn note_equivocation_report<C: AuxStore>(
+	backend: &C,
+	key: &[u8],
+	report: Vec<u8>,
+) -> ClientResult<()> {
+	let mut reports = equivocation_reports(backend, key)?;
+	if reports.contains(&report) {
+		return Ok(())
+	}
+
+	reports.push(report);
+	reports.using_encoded(|s| backend.insert_aux(&[(key, s)], &[]))
+}
+
+/// Get the equivocation reports stored under the given key.
+pub fn equivocation_reports<C: AuxStore>(backend: &C, key: &[u8]) -> ClientResult<Vec<Vec<u8>>> {
+	Ok(load_decode(backend, key)?.unwrap_or_else(Vec::new))
+}
+
+/// Remove an equivocation report stored under the given key, e.g. once it has
+/// been included in the chain.
+pub fn remove_equivocation_report<C: AuxStore>(
+	backend: &C,
+	key: &[u8],
+	report: &[u8],
+) -> ClientResult<()> {
+	let mut reports = equivocation_reports(backend, key)?;
+	reports.retain(|r| &r[..] != report);
+
+	if reports.is_empty() {
+		backend.insert_aux(&[], &[key])
+	} else {
+		reports.using_encoded(|s| backend.insert_aux(&[(key, s)], &[]))
+	}
+}
+
 #[cfg(test)]
 mod test {
 	use primitives::{sr25519, Pair};
 	use primitives::hash::H256;
-	use runtime_primitives::testing::{Header as HeaderTest, Digest as DigestTest};
+	use runtime_primitives::testing::{Header as HeaderTest, Digest as DigestTest, DigestItem};
 	use test_client;
 
-	use super::{MAX_SLOT_CAPACITY, PRUNING_BOUND, check_equivocation};
+	use super::{
+		MAX_SLOT_CAPACITY, PRUNING_BOUND, check_equivocation, note_equivocation_report,
+		equivocation_reports, remove_equivocation_report,
+	};
 
 	fn create_header(number: u64) -> HeaderTest {
 		// so that different headers for the same number get different hashes
@@ -262,4 +314,41 @@ mod test {
 			).unwrap().is_none(),
 		);
 	}
+
+	#[test]
+	fn resealed_header_is_no_equivocation() {
+		let client = test_client::new();
+		let public = sr25519::Pair::generate().public();
+
+		let sealed = |seal: u8| {
+			let mut header = create_header(1);
+			header.parent_hash = Default::default();
+			header.digest.logs.push(DigestItem::Other(vec![seal]));
+			header
+		};
+
+		assert!(check_equivocation(&client, 2, 2, &sealed(1), &public).unwrap().is_none());
+		assert!(check_equivocation(&client, 2, 2, &sealed(2), &public).unwrap().is_none());
+
+		let mut header = sealed(3);
+		header.number = 2;
+		assert!(check_equivocation(&client, 2, 2, &header, &public).unwrap().is_some());
+	}
+
+	#[test]
+	fn equivocation_reports_are_kept_until_removed() {
+		let client = test_client::new();
+		let key = b"test_equivocation_reports";
+
+		note_equivocation_report(&client, key, vec![1]).unwrap();
+		note_equivocation_report(&client, key, vec![2]).unwrap();
+		note_equivocation_report(&client, key, vec![1]).unwrap();
+		assert_eq!(equivocation_reports(&client, key).unwrap(), vec![vec![1], vec![2]]);
+
+		remove_equivocation_report(&client, key, &[1]).unwrap();
+		assert_eq!(equivocation_reports(&client, key).unwrap(), vec![vec![2]]);
+
+		remove_equivocation_report(&client, key, &[2]).unwrap();
+		assert!(equivocation_reports(&client, key).unwrap().is_empty());
+	}
 }
@@ -26,7 +26,10 @@ mod slots;
 mod aux_schema;
 
 pub use slots::{slot_now, SlotInfo, Slots};
-pub use aux_schema::{check_equivocation, MAX_SLOT_CAPACITY, PRUNING_BOUND};
+pub use aux_schema::{
+	check_equivocation, note_equivocation_report, equivocation_reports, remove_equivocation_report,
+	EquivocationProof, MAX_SLOT_CAPACITY, PRUNING_BOUND,
+};
 
 use codec::{Decode, Encode};
 use consensus_common::{SyncOracle, SelectChain};
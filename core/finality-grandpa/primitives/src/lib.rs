@@ -23,11 +23,12 @@ extern crate alloc;
 
 use parity_codec::{Encode, Decode};
 use substrate_primitives::ed25519;
-use sr_primitives::traits::{DigestFor, NumberFor};
+use sr_primitives::traits::{DigestFor, NumberFor, Verify};
 use client::decl_runtime_apis;
 use rstd::vec::Vec;
 
 use ed25519::Public as AuthorityId;
+use ed25519::Signature as AuthoritySignature;
 
 /// A scheduled change of authority set.
 #[cfg_attr(feature = "std", derive(Debug, PartialEq))]
@@ -39,6 +40,67 @@ pub struct ScheduledChange<N> {
 	pub delay: N,
 }
 
+/// A vote cast by a GRANDPA authority that is part of an equivocation.
+///
+/// This is encoded exactly like the prevote and precommit variants of
+/// `finality_grandpa::Message`, which is what the authorities sign.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+pub enum EquivocatedVote<H, N> {
+	/// A prevote for the given block hash and number.
+	Prevote(H, N),
+	/// A precommit for the given block hash and number.
+	Precommit(H, N),
+}
+
+impl<H, N> EquivocatedVote<H, N> {
+	/// Whether both votes are of the same kind, i.e. both prevotes or both precommits.
+	pub fn is_same_kind(&self, other: &Self) -> bool {
+		match (self, other) {
+			(EquivocatedVote::Prevote(..), EquivocatedVote::Prevote(..)) => true,
+			(EquivocatedVote::Precommit(..), EquivocatedVote::Precommit(..)) => true,
+			_ => false,
+		}
+	}
+}
+
+/// Proof that a GRANDPA authority has cast two different votes of the same
+/// kind in a single round.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+pub struct EquivocationProof<H, N, Id = AuthorityId, Signature = AuthoritySignature> {
+	/// The id of the authority set the votes were cast in.
+	pub set_id: u64,
+	/// The round the votes were cast in.
+	pub round: u64,
+	/// The equivocating authority.
+	pub offender: Id,
+	/// The first vote and its signature.
+	pub first: (EquivocatedVote<H, N>, Signature),
+	/// The second vote and its signature.
+	pub second: (EquivocatedVote<H, N>, Signature),
+}
+
+impl<H, N, Id, Signature> EquivocationProof<H, N, Id, Signature> where
+	H: Encode + PartialEq,
+	N: Encode + PartialEq,
+	Signature: Verify<Signer = Id>,
+{
+	/// Check that both votes are of the same kind, are different and were
+	/// both signed by the offender in the reported round and set.
+	pub fn check(&self) -> bool {
+		let check_vote = |(vote, signature): &(EquivocatedVote<H, N>, Signature)| {
+			let payload = (vote, self.round, self.set_id).encode();
+			signature.verify(&payload[..], &self.offender)
+		};
+
+		self.first.0.is_same_kind(&self.second.0)
+			&& self.first.0 != self.second.0
+			&& check_vote(&self.first)
+			&& check_vote(&self.second)
+	}
+}
+
 /// WASM function call to check for pending changes.
 pub const PENDING_CHANGE_CALL: &str = "grandpa_pending_change";
 /// WASM function call to get current GRANDPA authorities.
@@ -109,4 +171,74 @@ decl_runtime_apis! {
 		/// is finalized by the authorities from block B-1.
 		fn grandpa_authorities() -> Vec<(AuthorityId, u64)>;
 	}
+
+	/// API for reporting equivocations of GRANDPA authorities.
+	pub trait GrandpaEquivocationApi {
+		/// Construct an unsigned extrinsic reporting the given SCALE-encoded
+		/// `EquivocationProof`. Returns `None` if the proof can't be decoded.
+		fn construct_equivocation_report_extrinsic(report: Vec<u8>) -> Option<Block::Extrinsic>;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_primitives::{Pair, H256};
+
+	type Proof = EquivocationProof<H256, u64>;
+
+	fn signed(pair: &ed25519::Pair, vote: EquivocatedVote<H256, u64>, round: u64, set_id: u64)
+		-> (EquivocatedVote<H256, u64>, AuthoritySignature)
+	{
+		let signature = pair.sign(&(&vote, round, set_id).encode()[..]);
+		(vote, signature)
+	}
+
+	#[test]
+	fn checks_equivocation_proofs() {
+		let pair = ed25519::Pair::from_seed([1; 32]);
+		let other = ed25519::Pair::from_seed([2; 32]);
+		let a = H256::repeat_byte(1);
+		let b = H256::repeat_byte(2);
+
+		let proof = Proof {
+			set_id: 1,
+			round: 5,
+			offender: pair.public(),
+			first: signed(&pair, EquivocatedVote::Prevote(a, 1), 5, 1),
+			second: signed(&pair, EquivocatedVote::Prevote(b, 1), 5, 1),
+		};
+		assert!(proof.check());
+
+		// identical votes
+		assert!(!Proof { second: proof.first.clone(), ..proof.clone() }.check());
+
+		// prevote and precommit in the same round
+		assert!(!Proof {
+			second: signed(&pair, EquivocatedVote::Precommit(b, 1), 5, 1),
+			..proof.clone()
+		}.check());
+
+		// signed for a different round
+		assert!(!Proof {
+			second: signed(&pair, EquivocatedVote::Prevote(b, 1), 6, 1),
+			..proof.clone()
+		}.check());
+
+		// signed by a different authority
+		assert!(!Proof {
+			second: signed(&other, EquivocatedVote::Prevote(b, 1), 5, 1),
+			..proof.clone()
+		}.check());
+	}
+
+	#[test]
+	fn votes_are_encoded_like_grandpa_messages() {
+		// `finality_grandpa::Message::Precommit(Precommit { target_hash, target_number })`
+		let mut expected = vec![1u8];
+		expected.extend(H256::repeat_byte(3).encode());
+		expected.extend(7u64.encode());
+
+		assert_eq!(EquivocatedVote::Precommit(H256::repeat_byte(3), 7u64).encode(), expected);
+	}
 }
@@ -0,0 +1,47 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! GRANDPA RPC errors.
+
+/// GRANDPA RPC Result type.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// GRANDPA RPC errors.
+#[derive(Debug, derive_more::Display)]
+pub enum Error {
+	/// The GRANDPA voter isn't running.
+	#[display(fmt="GRANDPA voter is not running")]
+	VoterNotRunning,
+}
+
+impl std::error::Error for Error {}
+
+/// Base code for all GRANDPA errors.
+const BASE_ERROR: i64 = 7000;
+/// The GRANDPA voter isn't running.
+const VOTER_NOT_RUNNING: i64 = BASE_ERROR + 1;
+
+impl From<Error> for jsonrpc_core::Error {
+	fn from(e: Error) -> Self {
+		match e {
+			Error::VoterNotRunning => jsonrpc_core::Error {
+				code: jsonrpc_core::ErrorCode::ServerError(VOTER_NOT_RUNNING),
+				message: "GRANDPA voter is not running.".into(),
+				data: None,
+			},
+		}
+	}
+}
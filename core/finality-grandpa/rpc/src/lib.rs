@@ -0,0 +1,122 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Substrate GRANDPA API.
+//!
+//! Only available on nodes running the GRANDPA finality gadget, which provides the state
+//! of the current round and the justifications of the blocks it finalizes.
+
+#![warn(missing_docs)]
+
+use std::sync::Arc;
+
+use futures::{Future, Sink, Stream};
+use grandpa::JustificationSubscribers;
+use jsonrpc_core::Result as RpcResult;
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+use log::warn;
+use primitives::Bytes;
+use substrate_rpc::Subscriptions;
+
+pub use grandpa::round_state::{RoundState, RoundStateProvider, Tally};
+
+mod error;
+#[cfg(test)]
+mod tests;
+
+use self::error::{Error, Result};
+
+/// Substrate GRANDPA API
+#[rpc]
+pub trait GrandpaApi {
+	/// RPC metadata
+	type Metadata;
+
+	/// Returns the state of the current round: the set id, the round number and the
+	/// prevotes and precommits received so far, along with the voters missing.
+	#[rpc(name = "grandpa_roundState")]
+	fn round_state(&self) -> Result<RoundState>;
+
+	/// Justification subscription. Streams the encoded justification of the blocks finalized
+	/// by the voter.
+	#[pubsub(
+		subscription = "grandpa_justifications",
+		subscribe,
+		name = "grandpa_subscribeJustifications"
+	)]
+	fn subscribe_justifications(&self, metadata: Self::Metadata, subscriber: Subscriber<Bytes>);
+
+	/// Unsubscribe from justification subscription.
+	#[pubsub(
+		subscription = "grandpa_justifications",
+		unsubscribe,
+		name = "grandpa_unsubscribeJustifications"
+	)]
+	fn unsubscribe_justifications(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool>;
+}
+
+/// GRANDPA API with subscriptions support.
+pub struct Grandpa {
+	/// Provider of the current round state.
+	round_state: Arc<dyn RoundStateProvider>,
+	/// Subscribers to the justifications of the finalized blocks.
+	justifications: JustificationSubscribers,
+	/// Current subscriptions.
+	subscriptions: Subscriptions,
+}
+
+impl Grandpa {
+	/// Create new GRANDPA API RPC handler.
+	pub fn new(
+		round_state: Arc<dyn RoundStateProvider>,
+		justifications: JustificationSubscribers,
+		subscriptions: Subscriptions,
+	) -> Self {
+		Self {
+			round_state,
+			justifications,
+			subscriptions,
+		}
+	}
+}
+
+impl GrandpaApi for Grandpa {
+	type Metadata = substrate_rpc::metadata::Metadata;
+
+	fn round_state(&self) -> Result<RoundState> {
+		self.round_state.round_state().ok_or(Error::VoterNotRunning)
+	}
+
+	fn subscribe_justifications(&self, metadata: Self::Metadata, subscriber: Subscriber<Bytes>) {
+		let justifications = self.justifications.subscribe();
+		self.subscriptions.add(&metadata, subscriber, |sink| {
+			let stream = justifications
+				.map(|justification| Ok(justification.into()))
+				.map_err(|_| warn!("Justification stream error"));
+
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(stream)
+				// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_justifications(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
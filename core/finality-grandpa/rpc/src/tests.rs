@@ -0,0 +1,101 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+use std::sync::Mutex;
+use primitives::ed25519;
+
+/// A provider returning the round state it has been given.
+#[derive(Default)]
+struct TestRoundState(Mutex<Option<RoundState>>);
+
+impl RoundStateProvider for TestRoundState {
+	fn round_state(&self) -> Option<RoundState> {
+		self.0.lock().unwrap().clone()
+	}
+}
+
+fn voter(n: u8) -> ed25519::Public {
+	ed25519::Public::from_raw([n; 32])
+}
+
+fn round_state() -> RoundState {
+	RoundState {
+		set_id: 1,
+		round: 42,
+		total_weight: 3,
+		threshold_weight: 3,
+		prevotes: Tally {
+			current_weight: 2,
+			missing: vec![voter(3)],
+		},
+		precommits: Tally {
+			current_weight: 0,
+			missing: vec![voter(1), voter(2), voter(3)],
+		},
+	}
+}
+
+#[test]
+fn should_return_round_state() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let provider = Arc::new(TestRoundState::default());
+
+	let api = Grandpa::new(
+		provider.clone(),
+		Default::default(),
+		Subscriptions::new(core.executor()),
+	);
+
+	match api.round_state() {
+		Err(Error::VoterNotRunning) => {},
+		_ => panic!("no round state before the voter starts"),
+	}
+
+	*provider.0.lock().unwrap() = Some(round_state());
+	assert_eq!(api.round_state().unwrap(), round_state());
+}
+
+#[test]
+fn should_notify_about_justifications() {
+	let mut core = tokio::runtime::Runtime::new().unwrap();
+	let remote = core.executor();
+	let (subscriber, id, transport) = Subscriber::new_test("test");
+	let justifications = JustificationSubscribers::default();
+
+	{
+		let api = Grandpa::new(
+			Arc::new(TestRoundState::default()),
+			justifications.clone(),
+			Subscriptions::new(remote),
+		);
+
+		api.subscribe_justifications(Default::default(), subscriber);
+
+		// assert id assigned
+		assert_eq!(core.block_on(id), Ok(Ok(SubscriptionId::Number(1))));
+
+		justifications.notify(&[1, 2, 3]);
+	}
+	drop(justifications);
+
+	// assert justification sent to transport
+	let (notification, next) = core.block_on(transport.into_future()).unwrap();
+	assert!(notification.unwrap().contains("\"result\":\"0x010203\""));
+	// no more notifications on this channel
+	assert_eq!(core.block_on(next.into_future()).unwrap().0, None);
+}
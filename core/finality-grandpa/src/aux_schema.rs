@@ -30,14 +30,15 @@ use substrate_telemetry::{telemetry, CONSENSUS_INFO};
 use crate::authorities::{AuthoritySet, SharedAuthoritySet, PendingChange, DelayKind};
 use crate::consensus_changes::{SharedConsensusChanges, ConsensusChanges};
 use crate::environment::{CompletedRound, CompletedRounds, HasVoted, SharedVoterSetState, VoterSetState};
-use crate::NewAuthoritySet;
+use crate::{EquivocationProof, NewAuthoritySet};
 
 use substrate_primitives::ed25519::Public as AuthorityId;
 
 const VERSION_KEY: &[u8] = b"grandpa_schema_version";
-const SET_STATE_KEY: &[u8] = b"grandpa_completed_round";
+pub(crate) const SET_STATE_KEY: &[u8] = b"grandpa_completed_round";
 const AUTHORITY_SET_KEY: &[u8] = b"grandpa_voters";
 const CONSENSUS_CHANGES_KEY: &[u8] = b"grandpa_consensus_changes";
+const EQUIVOCATIONS_KEY: &[u8] = b"grandpa_equivocations";
 
 const CURRENT_VERSION: u32 = 2;
 
@@ -427,6 +428,55 @@ pub(crate) fn update_consensus_changes<H, N, F, R>(
 	write_aux(&[(CONSENSUS_CHANGES_KEY, set.encode().as_slice())])
 }
 
+/// Note an equivocation proof that should be reported on-chain. Only one
+/// proof is kept per offender, round and set.
+pub(crate) fn note_equivocation<Block: BlockT, B: AuxStore>(
+	backend: &B,
+	proof: EquivocationProof<Block>,
+) -> ClientResult<()> {
+	let mut proofs: Vec<EquivocationProof<Block>> = load_decode(backend, EQUIVOCATIONS_KEY)?
+		.unwrap_or_default();
+
+	let known = proofs.iter().any(|p|
+		p.set_id == proof.set_id && p.round == proof.round && p.offender == proof.offender
+	);
+	if known {
+		return Ok(());
+	}
+
+	proofs.push(proof);
+	backend.insert_aux(&[(EQUIVOCATIONS_KEY, proofs.encode().as_slice())], &[])
+}
+
+/// Load all equivocation proofs which haven't been included on-chain yet.
+pub(crate) fn equivocations<Block: BlockT, B: AuxStore>(
+	backend: &B,
+) -> ClientResult<Vec<EquivocationProof<Block>>> {
+	Ok(load_decode(backend, EQUIVOCATIONS_KEY)?.unwrap_or_default())
+}
+
+/// Remove the equivocation proof for the same offender, round and set as the
+/// given one, e.g. once it was included on-chain.
+pub(crate) fn remove_equivocation<Block: BlockT, B: AuxStore>(
+	backend: &B,
+	proof: &EquivocationProof<Block>,
+) -> ClientResult<()> {
+	let mut proofs = equivocations::<Block, _>(backend)?;
+	let len = proofs.len();
+	proofs.retain(|p|
+		p.set_id != proof.set_id || p.round != proof.round || p.offender != proof.offender
+	);
+	if proofs.len() == len {
+		return Ok(());
+	}
+
+	if proofs.is_empty() {
+		backend.insert_aux(&[], &[EQUIVOCATIONS_KEY])
+	} else {
+		backend.insert_aux(&[(EQUIVOCATIONS_KEY, proofs.encode().as_slice())], &[])
+	}
+}
+
 #[cfg(test)]
 pub(crate) fn load_authorities<B: AuxStore, H: Decode, N: Decode>(backend: &B)
 	-> Option<AuthoritySet<H, N>> {
@@ -603,4 +653,40 @@ mod test {
 			},
 		);
 	}
+
+	#[test]
+	fn equivocations_are_noted_once() {
+		use fg_primitives::EquivocatedVote;
+
+		let client = test_client::new();
+		let proof = |round, offender| EquivocationProof::<test_client::runtime::Block> {
+			set_id: 1,
+			round,
+			offender: AuthorityId([offender; 32]),
+			first: (EquivocatedVote::Prevote(H256::random(), 1), Default::default()),
+			second: (EquivocatedVote::Prevote(H256::random(), 1), Default::default()),
+		};
+
+		note_equivocation(&client, proof(1, 1)).unwrap();
+		note_equivocation(&client, proof(1, 2)).unwrap();
+		note_equivocation(&client, proof(2, 1)).unwrap();
+		// same offender, round and set
+		note_equivocation(&client, proof(1, 1)).unwrap();
+
+		let noted = || equivocations::<test_client::runtime::Block, _>(&client).unwrap()
+			.into_iter()
+			.map(|p| (p.round, p.offender))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			noted(),
+			vec![(1, AuthorityId([1; 32])), (1, AuthorityId([2; 32])), (2, AuthorityId([1; 32]))],
+		);
+
+		// proofs are kept until they are removed explicitly.
+		remove_equivocation(&client, &proof(1, 2)).unwrap();
+		assert_eq!(noted(), vec![(1, AuthorityId([1; 32])), (2, AuthorityId([1; 32]))]);
+		remove_equivocation(&client, &proof(1, 1)).unwrap();
+		remove_equivocation(&client, &proof(2, 1)).unwrap();
+		assert!(noted().is_empty());
+	}
 }
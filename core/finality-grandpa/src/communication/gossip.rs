@@ -43,6 +43,12 @@
 //! from our peers who are not necessarily voters, we have to account the benefit
 //! based on what they might have seen.
 //!
+//! The first prevote and precommit of every voter of the current set in the
+//! recent rounds is remembered, so that a voter casting a second, different vote
+//! of the same kind is caught. Votes of authorities outside of the current voter
+//! set are discarded. The resulting equivocation proofs can be taken from the
+//! validator in order to be reported on-chain.
+//!
 //! #### Propose
 //!
 //! This is a broadcast by a known voter of the last-round estimate.
@@ -58,6 +64,16 @@
 //! Sending a commit is polite when it may finalize something that the receiving peer
 //! was not aware of.
 //!
+//! #### Catch Up
+//!
+//! A voter whose neighbor is at least two rounds ahead in the same voter set sends it
+//! a catch up request with its current round. The neighbor answers with the prevotes
+//! and precommits of the last round it completed, if it is more recent. Only one
+//! catch up request is pending at a time, and it is impolite to send a catch up that
+//! wasn't requested.
+//!
+//! Neither catch up requests nor catch ups are repropagated.
+//!
 //! ## Expiration
 //!
 //! We keep some amount of recent rounds' messages, but do not accept new ones from rounds
@@ -77,13 +93,18 @@ use log::{trace, debug, warn};
 use futures::prelude::*;
 use futures::sync::mpsc;
 
-use crate::{CompactCommit, SignedMessage};
-use super::{cost, benefit, Round, SetId};
+use crate::{CompactCommit, EquivocationProof, Message, SignedMessage};
+use super::{cost, benefit, CatchUp, Round, SetId};
+use grandpa::voter_set::VoterSet;
+use substrate_primitives::ed25519::{Public as AuthorityId, Signature as AuthoritySignature};
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, VecDeque, hash_map::Entry};
+use std::mem::Discriminant;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 const REBROADCAST_AFTER: Duration = Duration::from_secs(60 * 5);
+const CATCH_UP_REQUEST_TIMEOUT: Duration = Duration::from_secs(45);
 
 /// An outcome of examining a message.
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -227,6 +248,10 @@ pub(super) enum GossipMessage<Block: BlockT> {
 	Commit(FullCommitMessage<Block>),
 	/// A neighbor packet. Not repropagated.
 	Neighbor(VersionedNeighborPacket<NumberFor<Block>>),
+	/// A catch up request. Not repropagated.
+	CatchUpRequest(CatchUpRequestMessage),
+	/// The votes of a completed round, in response to a catch up request. Not repropagated.
+	CatchUp(FullCatchUpMessage<Block>),
 }
 
 impl<Block: BlockT> From<NeighborPacket<NumberFor<Block>>> for GossipMessage<Block> {
@@ -257,6 +282,24 @@ pub(super) struct FullCommitMessage<Block: BlockT> {
 	pub(super) message: CompactCommit<Block>,
 }
 
+/// A request for the votes of a round more recent than the given one.
+#[derive(Debug, Encode, Decode, Clone)]
+pub(super) struct CatchUpRequestMessage {
+	/// The round the requester is at.
+	pub(super) round: Round,
+	/// The voter set ID the requester is at.
+	pub(super) set_id: SetId,
+}
+
+/// Network level catch up message with topic information.
+#[derive(Debug, Encode, Decode)]
+pub(super) struct FullCatchUpMessage<Block: BlockT> {
+	/// The voter set ID the votes are from.
+	pub(super) set_id: SetId,
+	/// The votes of the completed round.
+	pub(super) message: CatchUp<Block>,
+}
+
 /// V1 neighbor packet. Neighbor packets are sent from nodes to their peers
 /// and are not repropagated. These contain information about the node's state.
 #[derive(Debug, Encode, Decode, Clone)]
@@ -297,6 +340,10 @@ pub(super) enum Misbehavior {
 		blocks_loaded: i32,
 		equivocations_caught: i32,
 	},
+	// Bad catch up message
+	BadCatchUpMessage {
+		signatures_checked: i32,
+	},
 	// A message received that's from the future relative to our view.
 	// always misbehavior.
 	FutureMessage,
@@ -318,6 +365,8 @@ impl Misbehavior {
 
 				(benefit as i32).saturating_add(cost as i32)
 			},
+			BadCatchUpMessage { signatures_checked } =>
+				cost::PER_SIGNATURE_CHECKED.saturating_mul(signatures_checked),
 			FutureMessage => cost::FUTURE_MESSAGE,
 		}
 	}
@@ -325,12 +374,15 @@ impl Misbehavior {
 
 struct PeerInfo<N> {
 	view: View<N>,
+	// when we last answered a catch up request of the peer.
+	last_catch_up_answer: Option<Instant>,
 }
 
 impl<N> PeerInfo<N> {
 	fn new() -> Self {
 		PeerInfo {
 			view: View::default(),
+			last_catch_up_answer: None,
 		}
 	}
 }
@@ -405,6 +457,10 @@ impl<N: Ord> Peers<N> {
 	fn peer<'a>(&'a self, who: &PeerId) -> Option<&'a PeerInfo<N>> {
 		self.inner.get(who)
 	}
+
+	fn peer_mut<'a>(&'a mut self, who: &PeerId) -> Option<&'a mut PeerInfo<N>> {
+		self.inner.get_mut(who)
+	}
 }
 
 #[derive(Debug)]
@@ -417,12 +473,23 @@ pub(super) enum Action<H>  {
 	Discard(i32),
 }
 
+/// The first vote of each kind seen from a voter in a round.
+type SeenVotes<Block> = HashMap<
+	(Round, SetId, AuthorityId, Discriminant<Message<Block>>),
+	(Message<Block>, AuthoritySignature),
+>;
+
 struct Inner<Block: BlockT> {
 	local_view: View<NumberFor<Block>>,
 	peers: Peers<NumberFor<Block>>,
 	live_topics: KeepTopics<Block>,
 	config: crate::Config,
 	next_rebroadcast: Instant,
+	voters: Option<(SetId, Arc<VoterSet<AuthorityId>>)>,
+	seen_votes: SeenVotes<Block>,
+	equivocations: Vec<EquivocationProof<Block>>,
+	latest_catch_up: Option<FullCatchUpMessage<Block>>,
+	pending_catch_up: Option<(PeerId, Instant)>,
 }
 
 type MaybeMessage<Block> = Option<(Vec<PeerId>, NeighborPacket<NumberFor<Block>>)>;
@@ -435,6 +502,11 @@ impl<Block: BlockT> Inner<Block> {
 			live_topics: KeepTopics::new(),
 			next_rebroadcast: Instant::now() + REBROADCAST_AFTER,
 			config,
+			voters: None,
+			seen_votes: HashMap::new(),
+			equivocations: Vec::new(),
+			latest_catch_up: None,
+			pending_catch_up: None,
 		}
 	}
 
@@ -451,21 +523,99 @@ impl<Block: BlockT> Inner<Block> {
 		self.local_view.set_id = set_id;
 
 		self.live_topics.push(round, set_id);
+		self.prune_seen_votes();
 		self.multicast_neighbor_packet()
 	}
 
-	/// Note that a voter set with given ID has started. Does nothing if the last
-	/// call to the function was with the same `set_id`.
-	fn note_set(&mut self, set_id: SetId) -> MaybeMessage<Block> {
+	/// Note that a voter set with given ID has started. Does nothing but noting
+	/// the voters if the last call to the function was with the same `set_id`.
+	fn note_set(&mut self, set_id: SetId, voters: Arc<VoterSet<AuthorityId>>) -> MaybeMessage<Block> {
+		self.voters = Some((set_id, voters));
 		if self.local_view.set_id == set_id {
 			return None;
 		}
 
 		self.local_view.update_set(set_id);
 		self.live_topics.push(Round(0), set_id);
+		self.prune_seen_votes();
 		self.multicast_neighbor_packet()
 	}
 
+	/// Forget the votes of rounds that we don't accept messages for anymore.
+	fn prune_seen_votes(&mut self) {
+		let local_view = &self.local_view;
+		self.seen_votes.retain(|&(round, set_id, _, _), _|
+			local_view.consider_vote(round, set_id) == Consider::Accept
+		);
+	}
+
+	/// Whether the given authority is known to be a voter of the given set.
+	/// `None` if the voters of the set aren't known.
+	fn is_voter(&self, set_id: SetId, id: &AuthorityId) -> Option<bool> {
+		match self.voters {
+			Some((voters_set_id, ref voters)) if voters_set_id == set_id => Some(voters.contains_key(id)),
+			_ => None,
+		}
+	}
+
+	/// Note a signature-checked vote, collecting an equivocation proof if the
+	/// voter already cast a different vote of the same kind in the round.
+	///
+	/// Only votes of known voters are recorded, so that the memory used can't
+	/// be inflated by votes of arbitrary keys.
+	fn note_vote(&mut self, full: &VoteOrPrecommitMessage<Block>) {
+		if let grandpa::Message::PrimaryPropose(_) = full.message.message {
+			return;
+		}
+		if self.is_voter(full.set_id, &full.message.id) != Some(true) {
+			return;
+		}
+
+		let key = (
+			full.round,
+			full.set_id,
+			full.message.id.clone(),
+			std::mem::discriminant(&full.message.message),
+		);
+		let vote = (full.message.message.clone(), full.message.signature.clone());
+
+		let first = match self.seen_votes.entry(key) {
+			Entry::Vacant(entry) => {
+				entry.insert(vote);
+				return;
+			},
+			Entry::Occupied(ref entry) if entry.get().0 == vote.0 => return,
+			Entry::Occupied(entry) => entry.get().clone(),
+		};
+
+		debug!(target: "afg", "Caught equivocation of {} in round {:?}", full.message.id, full.round);
+		telemetry!(CONSENSUS_DEBUG; "afg.equivocation_caught";
+			"voter" => ?full.message.id,
+			"round" => ?full.round,
+		);
+
+		let proof = crate::equivocation_proof::<Block>(
+			full.set_id.0,
+			full.round.0,
+			full.message.id.clone(),
+			first,
+			vote,
+		);
+		self.equivocations.extend(proof);
+	}
+
+	/// Note that a round has been completed, keeping its votes to answer catch
+	/// up requests.
+	fn note_completed_round(&mut self, set_id: SetId, catch_up: CatchUp<Block>) {
+		let is_older = self.latest_catch_up.as_ref().map_or(false, |latest|
+			(latest.set_id, latest.message.round_number) >= (set_id, catch_up.round_number)
+		);
+
+		if !is_older {
+			self.latest_catch_up = Some(FullCatchUpMessage { set_id, message: catch_up });
+		}
+	}
+
 	/// Note that we've imported a commit finalizing a given block.
 	fn note_commit_finalized(&mut self, finalized: NumberFor<Block>) -> MaybeMessage<Block> {
 		if self.local_view.last_commit.as_ref() < Some(&finalized) {
@@ -489,7 +639,7 @@ impl<Block: BlockT> Inner<Block> {
 		cost::PAST_REJECTION
 	}
 
-	fn validate_round_message(&self, who: &PeerId, full: &VoteOrPrecommitMessage<Block>)
+	fn validate_round_message(&mut self, who: &PeerId, full: &VoteOrPrecommitMessage<Block>)
 		-> Action<Block::Hash>
 	{
 		match self.consider_vote(full.round, full.set_id) {
@@ -499,6 +649,12 @@ impl<Block: BlockT> Inner<Block> {
 			Consider::Accept => {},
 		}
 
+		if self.is_voter(full.set_id, &full.message.id) == Some(false) {
+			debug!(target: "afg", "Message from unknown voter: {}", full.message.id);
+			telemetry!(CONSENSUS_DEBUG; "afg.unknown_voter"; "voter" => ?full.message.id);
+			return Action::Discard(cost::UNKNOWN_VOTER);
+		}
+
 		if let Err(()) = super::check_message_sig::<Block>(
 			&full.message.message,
 			&full.message.id,
@@ -511,6 +667,8 @@ impl<Block: BlockT> Inner<Block> {
 			return Action::Discard(cost::BAD_SIGNATURE);
 		}
 
+		self.note_vote(full);
+
 		let topic = super::round_topic::<Block>(full.round.0, full.set_id.0);
 		Action::Keep(topic, benefit::ROUND_MESSAGE)
 	}
@@ -546,8 +704,131 @@ impl<Block: BlockT> Inner<Block> {
 		Action::ProcessAndDiscard(topic, benefit::BASIC_VALIDATED_COMMIT)
 	}
 
+	fn validate_catch_up_message(&mut self, who: &PeerId, full: &FullCatchUpMessage<Block>)
+		-> Action<Block::Hash>
+	{
+		let requested = self.pending_catch_up.as_ref().map_or(false, |(peer, _)| peer == who);
+		if !requested {
+			return Action::Discard(cost::UNSOLICITED_CATCH_UP);
+		}
+
+		self.pending_catch_up = None;
+
+		if full.set_id < self.local_view.set_id || full.message.round_number <= self.local_view.round.0 {
+			return Action::Discard(self.cost_past_rejection(who, Round(full.message.round_number), full.set_id));
+		}
+
+		if full.set_id > self.local_view.set_id {
+			return Action::Discard(Misbehavior::FutureMessage.cost());
+		}
+
+		if full.message.prevotes.is_empty() || full.message.precommits.is_empty() {
+			debug!(target: "afg", "Malformed catch up");
+			telemetry!(CONSENSUS_DEBUG; "afg.malformed_catch_up";
+				"prevotes_len" => ?full.message.prevotes.len(),
+				"precommits_len" => ?full.message.precommits.len(),
+			);
+			return Action::Discard(cost::MALFORMED_CATCH_UP);
+		}
+
+		// the votes are checked against the voter set once received by the voter.
+		let topic = super::global_topic::<Block>(full.set_id.0);
+		Action::ProcessAndDiscard(topic, benefit::BASIC_VALIDATED_CATCH_UP)
+	}
+
+	fn handle_catch_up_request(&mut self, who: &PeerId, request: CatchUpRequestMessage)
+		-> (Option<GossipMessage<Block>>, Action<Block::Hash>)
+	{
+		if request.set_id < self.local_view.set_id {
+			return (None, Action::Discard(self.cost_past_rejection(who, request.round, request.set_id)));
+		}
+
+		if request.set_id > self.local_view.set_id {
+			return (None, Action::Discard(Misbehavior::FutureMessage.cost()));
+		}
+
+		// only answer peers whose view, as announced in their neighbor packets, is the
+		// one of the request and far enough behind ours that they'd reject our votes.
+		let local_view = &self.local_view;
+		let peer = match self.peers.peer_mut(who) {
+			Some(peer) => peer,
+			None => return (None, Action::Discard(cost::OUT_OF_SCOPE_CATCH_UP_REQUEST)),
+		};
+
+		let is_behind = peer.view.set_id == request.set_id
+			&& peer.view.round == request.round
+			&& request.round.0.saturating_add(1) < local_view.round.0;
+		if !is_behind {
+			return (None, Action::Discard(cost::OUT_OF_SCOPE_CATCH_UP_REQUEST));
+		}
+
+		// a catch up carries all the votes of a round, answer a peer at most once per
+		// request timeout.
+		let now = Instant::now();
+		if let Some(answered_at) = peer.last_catch_up_answer {
+			if now < answered_at + CATCH_UP_REQUEST_TIMEOUT {
+				return (None, Action::Discard(cost::CATCH_UP_REQUEST_TOO_SOON));
+			}
+		}
+
+		let reply = match self.latest_catch_up {
+			Some(ref latest) if latest.set_id == request.set_id
+				&& latest.message.round_number > request.round.0 =>
+			{
+				trace!(target: "afg", "Answering catch up request of {} with round {}",
+					who, latest.message.round_number);
+
+				Some(GossipMessage::CatchUp(FullCatchUpMessage {
+					set_id: latest.set_id,
+					message: latest.message.clone(),
+				}))
+			},
+			_ => None,
+		};
+
+		if reply.is_some() {
+			peer.last_catch_up_answer = Some(now);
+		}
+
+		// always discard, it's only meant for us.
+		(reply, Action::Discard(0))
+	}
+
+	// request a catch up from a peer at least two rounds ahead of us in the same set,
+	// since we'd reject its votes as coming from the future. observers never note
+	// a round, so they don't request catch ups.
+	fn catch_up_request(&mut self, who: &PeerId) -> Option<GossipMessage<Block>> {
+		let (peer_round, peer_set_id) = match self.peers.peer(who) {
+			None => return None,
+			Some(peer) => (peer.view.round, peer.view.set_id),
+		};
+
+		if self.local_view.round.0 == 0
+			|| peer_set_id != self.local_view.set_id
+			|| peer_round.0 <= self.local_view.round.0.saturating_add(1)
+		{
+			return None;
+		}
+
+		let now = Instant::now();
+		if let Some((_, requested_at)) = self.pending_catch_up {
+			if now < requested_at + CATCH_UP_REQUEST_TIMEOUT {
+				return None;
+			}
+		}
+
+		debug!(target: "afg", "Voter {} requesting catch up from {} at round {:?}",
+			self.config.name(), who, peer_round);
+
+		self.pending_catch_up = Some((who.clone(), now));
+		Some(GossipMessage::CatchUpRequest(CatchUpRequestMessage {
+			round: self.local_view.round,
+			set_id: self.local_view.set_id,
+		}))
+	}
+
 	fn import_neighbor_message(&mut self, who: &PeerId, update: NeighborPacket<NumberFor<Block>>)
-		-> (Vec<Block::Hash>, Action<Block::Hash>)
+		-> (Vec<Block::Hash>, Option<GossipMessage<Block>>, Action<Block::Hash>)
 	{
 		let (cb, topics) = match self.peers.update_peer_state(who, update) {
 			Ok(view) => (100i32, view.map(|view| neighbor_topics::<Block>(view))),
@@ -555,9 +836,10 @@ impl<Block: BlockT> Inner<Block> {
 		};
 
 		let neighbor_topics = topics.unwrap_or_default();
+		let catch_up_request = self.catch_up_request(who);
 
 		// always discard, it's valid for one hop.
-		(neighbor_topics, Action::Discard(cb))
+		(neighbor_topics, catch_up_request, Action::Discard(cb))
 	}
 
 	fn multicast_neighbor_packet(&self) -> MaybeMessage<Block> {
@@ -600,11 +882,11 @@ impl<Block: BlockT> GossipValidator<Block> {
 		}
 	}
 
-	/// Note that a voter set with given ID has started.
-	pub(super) fn note_set<F>(&self, set_id: SetId, send_neighbor: F)
+	/// Note that a voter set with given ID and voters has started.
+	pub(super) fn note_set<F>(&self, set_id: SetId, voters: Arc<VoterSet<AuthorityId>>, send_neighbor: F)
 		where F: FnOnce(Vec<PeerId>, NeighborPacket<NumberFor<Block>>)
 	{
-		let maybe_msg = self.inner.write().note_set(set_id);
+		let maybe_msg = self.inner.write().note_set(set_id, voters);
 		if let Some((to, msg)) = maybe_msg {
 			send_neighbor(to, msg);
 		}
@@ -620,28 +902,49 @@ impl<Block: BlockT> GossipValidator<Block> {
 		}
 	}
 
+	/// Note that a round has been completed, keeping its votes to answer catch
+	/// up requests.
+	pub(super) fn note_completed_round(&self, set_id: SetId, catch_up: CatchUp<Block>) {
+		self.inner.write().note_completed_round(set_id, catch_up);
+	}
+
+	/// Take the equivocation proofs collected from gossiped votes.
+	pub(super) fn take_equivocations(&self) -> Vec<EquivocationProof<Block>> {
+		std::mem::replace(&mut self.inner.write().equivocations, Vec::new())
+	}
+
 	fn report(&self, who: PeerId, cost_benefit: i32) {
 		let _ = self.report_sender.unbounded_send(PeerReport { who, cost_benefit });
 	}
 
 	pub(super) fn do_validate(&self, who: &PeerId, mut data: &[u8])
-		-> (Action<Block::Hash>, Vec<Block::Hash>)
+		-> (Action<Block::Hash>, Vec<Block::Hash>, Option<GossipMessage<Block>>)
 	{
 		let mut broadcast_topics = Vec::new();
+		let mut peer_reply = None;
 		let action = {
 			match GossipMessage::<Block>::decode(&mut data) {
 				Some(GossipMessage::VoteOrPrecommit(ref message))
 					=> self.inner.write().validate_round_message(who, message),
 				Some(GossipMessage::Commit(ref message)) => self.inner.write().validate_commit_message(who, message),
 				Some(GossipMessage::Neighbor(update)) => {
-					let (topics, action) = self.inner.write().import_neighbor_message(
+					let (topics, catch_up_request, action) = self.inner.write().import_neighbor_message(
 						who,
 						update.into_neighbor_packet(),
 					);
 
 					broadcast_topics = topics;
+					peer_reply = catch_up_request;
 					action
 				}
+				Some(GossipMessage::CatchUpRequest(request)) => {
+					let (catch_up, action) = self.inner.write().handle_catch_up_request(who, request);
+
+					peer_reply = catch_up;
+					action
+				}
+				Some(GossipMessage::CatchUp(ref message))
+					=> self.inner.write().validate_catch_up_message(who, message),
 				None => {
 					debug!(target: "afg", "Error decoding message");
 					telemetry!(CONSENSUS_DEBUG; "afg.err_decoding_msg"; "" => "");
@@ -652,7 +955,7 @@ impl<Block: BlockT> GossipValidator<Block> {
 			}
 		};
 
-		(action, broadcast_topics)
+		(action, broadcast_topics, peer_reply)
 	}
 }
 
@@ -680,13 +983,17 @@ impl<Block: BlockT> network_gossip::Validator<Block> for GossipValidator<Block>
 	fn validate(&self, context: &mut ValidatorContext<Block>, who: &PeerId, data: &[u8])
 		-> network_gossip::ValidationResult<Block::Hash>
 	{
-		let (action, broadcast_topics) = self.do_validate(who, data);
+		let (action, broadcast_topics, peer_reply) = self.do_validate(who, data);
 
 		// not with lock held!
 		for topic in broadcast_topics {
 			context.send_topic(who, topic, false);
 		}
 
+		if let Some(msg) = peer_reply {
+			context.send_message(who, msg.encode());
+		}
+
 		match action {
 			Action::Keep(topic, cb) => {
 				self.report(who.clone(), cb);
@@ -758,6 +1065,8 @@ impl<Block: BlockT> network_gossip::Validator<Block> for GossipValidator<Block>
 					&& Some(full.message.target_number) > peer_best_commit
 				}
 				Some(GossipMessage::Neighbor(_)) => false,
+				Some(GossipMessage::CatchUpRequest(_)) => false,
+				Some(GossipMessage::CatchUp(_)) => false,
 				Some(GossipMessage::VoteOrPrecommit(_)) => false, // should not be the case.
 			}
 		})
@@ -1025,4 +1334,205 @@ mod tests {
 			}
 		}
 	}
+
+	#[test]
+	fn equivocations_are_caught() {
+		use keyring::AuthorityKeyring;
+		use substrate_primitives::{Pair, H256};
+
+		let (val, _) = GossipValidator::<Block>::new(config());
+		let voters = Arc::new(vec![(AuthorityKeyring::Alice.pair().public(), 1)].into_iter()
+			.collect::<VoterSet<AuthorityId>>());
+		val.note_set(SetId(0), voters, |_, _| {});
+		val.note_round(Round(1), SetId(0), |_, _| {});
+
+		let peer = PeerId::random();
+		let vote_by = |pair: &substrate_primitives::ed25519::Pair, target_hash: H256, precommit: bool| {
+			let message = if precommit {
+				grandpa::Message::Precommit(grandpa::Precommit { target_hash, target_number: 1 })
+			} else {
+				grandpa::Message::Prevote(grandpa::Prevote { target_hash, target_number: 1 })
+			};
+			let signature = pair.sign(&super::super::localized_payload(1, 0, &message)[..]);
+
+			GossipMessage::<Block>::VoteOrPrecommit(VoteOrPrecommitMessage {
+				round: Round(1),
+				set_id: SetId(0),
+				message: SignedMessage::<Block> { message, signature, id: pair.public() },
+			}).encode()
+		};
+		let pair = AuthorityKeyring::Alice.pair();
+		let vote = |target_hash: H256, precommit: bool| vote_by(&pair, target_hash, precommit);
+
+		// the same vote twice and votes of different kinds are fine.
+		val.do_validate(&peer, &vote(H256::repeat_byte(1), false));
+		val.do_validate(&peer, &vote(H256::repeat_byte(1), false));
+		val.do_validate(&peer, &vote(H256::repeat_byte(2), true));
+		assert!(val.take_equivocations().is_empty());
+
+		// a second, different prevote is an equivocation.
+		val.do_validate(&peer, &vote(H256::repeat_byte(2), false));
+		let equivocations = val.take_equivocations();
+		assert_eq!(equivocations.len(), 1);
+		assert_eq!(equivocations[0].offender, pair.public());
+		assert_eq!((equivocations[0].set_id, equivocations[0].round), (0, 1));
+		assert!(equivocations[0].check());
+		assert!(val.take_equivocations().is_empty());
+
+		// votes of authorities outside of the voter set are discarded and not recorded.
+		let bob = AuthorityKeyring::Bob.pair();
+		match val.do_validate(&peer, &vote_by(&bob, H256::repeat_byte(1), false)).0 {
+			Action::Discard(c) => assert_eq!(c, cost::UNKNOWN_VOTER),
+			other => panic!("Expected the vote to be discarded, got {:?}", other),
+		}
+		val.do_validate(&peer, &vote_by(&bob, H256::repeat_byte(2), false));
+		assert!(val.take_equivocations().is_empty());
+		assert!(val.inner.read().seen_votes.keys().all(|(_, _, id, _)| id == &pair.public()));
+
+		// votes are forgotten once the round is not live anymore.
+		val.note_round(Round(3), SetId(0), |_, _| {});
+		assert!(val.inner.read().seen_votes.is_empty());
+	}
+
+	fn catch_up(round_number: u64) -> CatchUp<Block> {
+		use keyring::AuthorityKeyring;
+		use substrate_primitives::{Pair, H256};
+
+		let pair = AuthorityKeyring::Alice.pair();
+		let vote = |message: Message<Block>| {
+			let signature = pair.sign(&super::super::localized_payload(round_number, 0, &message)[..]);
+			SignedMessage::<Block> { message, signature, id: pair.public() }
+		};
+		let target_hash = H256::repeat_byte(1);
+
+		CatchUp {
+			round_number,
+			prevotes: vec![vote(grandpa::Message::Prevote(grandpa::Prevote { target_hash, target_number: 1 }))],
+			precommits: vec![vote(grandpa::Message::Precommit(grandpa::Precommit { target_hash, target_number: 1 }))],
+			base_hash: H256::default(),
+			base_number: 0,
+		}
+	}
+
+	fn neighbor(round: u64) -> Vec<u8> {
+		GossipMessage::<Block>::from(NeighborPacket {
+			round: Round(round),
+			set_id: SetId(0),
+			commit_finalized_height: 1,
+		}).encode()
+	}
+
+	#[test]
+	fn catch_up_is_requested_from_peers_ahead() {
+		let (val, _) = GossipValidator::<Block>::new(config());
+		val.note_round(Round(1), SetId(0), |_, _| {});
+
+		let peer = PeerId::random();
+		let other_peer = PeerId::random();
+		val.inner.write().peers.new_peer(peer.clone());
+		val.inner.write().peers.new_peer(other_peer.clone());
+
+		// votes of a peer one round ahead are still accepted.
+		let (_, _, reply) = val.do_validate(&peer, &neighbor(2));
+		assert!(reply.is_none());
+
+		// a peer two rounds ahead is asked for a catch up.
+		let (_, _, reply) = val.do_validate(&peer, &neighbor(3));
+		match reply {
+			Some(GossipMessage::CatchUpRequest(request)) => {
+				assert_eq!(request.round, Round(1));
+				assert_eq!(request.set_id, SetId(0));
+			},
+			other => panic!("Expected a catch up request, got {:?}", other),
+		}
+
+		// only one request is pending at a time.
+		let (_, _, reply) = val.do_validate(&other_peer, &neighbor(5));
+		assert!(reply.is_none());
+	}
+
+	#[test]
+	fn catch_up_requests_are_answered_with_later_rounds() {
+		let (val, _) = GossipValidator::<Block>::new(config());
+		val.note_round(Round(5), SetId(0), |_, _| {});
+		val.note_completed_round(SetId(0), catch_up(4));
+
+		let peer = PeerId::random();
+		val.inner.write().peers.new_peer(peer.clone());
+		let request = |round, set_id| GossipMessage::<Block>::CatchUpRequest(CatchUpRequestMessage {
+			round: Round(round),
+			set_id: SetId(set_id),
+		}).encode();
+		let assert_discarded = |action, expected| match action {
+			Action::Discard(cost) => assert_eq!(cost, expected),
+			other => panic!("Expected the request to be discarded, got {:?}", other),
+		};
+
+		// the peer didn't announce being at the round of the request.
+		let (action, _, reply) = val.do_validate(&peer, &request(1, 0));
+		assert!(reply.is_none());
+		assert_discarded(action, cost::OUT_OF_SCOPE_CATCH_UP_REQUEST);
+
+		val.do_validate(&peer, &neighbor(1));
+		let (_, _, reply) = val.do_validate(&peer, &request(1, 0));
+		match reply {
+			Some(GossipMessage::CatchUp(full)) => {
+				assert_eq!(full.set_id, SetId(0));
+				assert_eq!(full.message, catch_up(4));
+			},
+			other => panic!("Expected a catch up, got {:?}", other),
+		}
+
+		// the peer is answered at most once per request timeout.
+		let (action, _, reply) = val.do_validate(&peer, &request(1, 0));
+		assert!(reply.is_none());
+		assert_discarded(action, cost::CATCH_UP_REQUEST_TOO_SOON);
+
+		// a peer one round behind still accepts our votes.
+		let close_peer = PeerId::random();
+		val.inner.write().peers.new_peer(close_peer.clone());
+		val.do_validate(&close_peer, &neighbor(4));
+		let (action, _, reply) = val.do_validate(&close_peer, &request(4, 0));
+		assert!(reply.is_none());
+		assert_discarded(action, cost::OUT_OF_SCOPE_CATCH_UP_REQUEST);
+
+		// the peer is in another set.
+		let (action, _, reply) = val.do_validate(&close_peer, &request(1, 1));
+		assert!(reply.is_none());
+		assert_discarded(action, cost::FUTURE_MESSAGE);
+	}
+
+	#[test]
+	fn only_requested_catch_ups_are_processed() {
+		let (val, _) = GossipValidator::<Block>::new(config());
+		val.note_round(Round(1), SetId(0), |_, _| {});
+
+		let peer = PeerId::random();
+		val.inner.write().peers.new_peer(peer.clone());
+
+		let catch_up = GossipMessage::<Block>::CatchUp(FullCatchUpMessage {
+			set_id: SetId(0),
+			message: catch_up(4),
+		}).encode();
+
+		match val.do_validate(&peer, &catch_up).0 {
+			Action::Discard(cost) => assert_eq!(cost, cost::UNSOLICITED_CATCH_UP),
+			other => panic!("Expected the catch up to be discarded, got {:?}", other),
+		}
+
+		let (_, _, reply) = val.do_validate(&peer, &neighbor(5));
+		assert!(reply.is_some());
+
+		match val.do_validate(&peer, &catch_up).0 {
+			Action::ProcessAndDiscard(topic, _) =>
+				assert_eq!(topic, crate::communication::global_topic::<Block>(0)),
+			other => panic!("Expected the catch up to be processed, got {:?}", other),
+		}
+
+		// the request has been answered already.
+		match val.do_validate(&peer, &catch_up).0 {
+			Action::Discard(cost) => assert_eq!(cost, cost::UNSOLICITED_CATCH_UP),
+			other => panic!("Expected the catch up to be discarded, got {:?}", other),
+		}
+	}
 }
@@ -26,24 +26,28 @@
 //! For instance, it is _impolite_ to send the same message more than once.
 //! In the future, there will be a fallback for allowing sending the same message
 //! under certain conditions that are used to un-stick the protocol.
+//!
+//! Voters which fall behind can catch up by requesting the votes of a later
+//! round that was completed by one of their peers.
 
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use grandpa::voter_set::VoterSet;
 use grandpa::Message::{Prevote, Precommit, PrimaryPropose};
 use futures::prelude::*;
 use futures::sync::{oneshot, mpsc};
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use parity_codec::{Encode, Decode};
-use substrate_primitives::{ed25519, Pair};
+use substrate_primitives::{ed25519, Pair, crypto::Sign};
 use substrate_telemetry::{telemetry, CONSENSUS_DEBUG, CONSENSUS_INFO};
 use runtime_primitives::ConsensusEngineId;
-use runtime_primitives::traits::{Block as BlockT, Hash as HashT, Header as HeaderT};
+use runtime_primitives::traits::{Block as BlockT, Hash as HashT, Header as HeaderT, NumberFor};
 use network::{consensus_gossip as network_gossip, NetworkService};
 use network_gossip::ConsensusMessage;
 
 use crate::{Error, Message, SignedMessage, Commit, CompactCommit};
-use crate::environment::HasVoted;
+use crate::environment::{CompletedRound, HasVoted};
 use gossip::{
 	GossipMessage, FullCommitMessage, VoteOrPrecommitMessage, GossipValidator
 };
@@ -70,12 +74,18 @@ mod cost {
 	pub(super) const PER_SIGNATURE_CHECKED: i32 = -25;
 	pub(super) const PER_BLOCK_LOADED: i32 = -10;
 	pub(super) const INVALID_COMMIT: i32 = -5000;
+	pub(super) const MALFORMED_CATCH_UP: i32 = -1000;
+	pub(super) const UNSOLICITED_CATCH_UP: i32 = -500;
+	pub(super) const OUT_OF_SCOPE_CATCH_UP_REQUEST: i32 = -200;
+	pub(super) const CATCH_UP_REQUEST_TOO_SOON: i32 = -500;
+	pub(super) const UNKNOWN_VOTER: i32 = -150;
 }
 
 // benefit scalars for reporting peers.
 mod benefit {
 	pub(super) const ROUND_MESSAGE: i32 = 100;
 	pub(super) const BASIC_VALIDATED_COMMIT: i32 = 100;
+	pub(super) const BASIC_VALIDATED_CATCH_UP: i32 = 200;
 	pub(super) const PER_EQUIVOCATION: i32 = 10;
 }
 
@@ -212,6 +222,45 @@ impl Stream for NetworkStream {
 	}
 }
 
+/// The votes of a completed round, used to let lagging voters catch up with
+/// the rest of the voter set.
+#[derive(Debug, Clone, Encode, Decode, PartialEq)]
+pub(crate) struct CatchUp<Block: BlockT> {
+	/// The round the votes are from.
+	pub(crate) round_number: u64,
+	/// The prevotes cast in the round.
+	pub(crate) prevotes: Vec<SignedMessage<Block>>,
+	/// The precommits cast in the round.
+	pub(crate) precommits: Vec<SignedMessage<Block>>,
+	/// The hash of the base block of the round.
+	pub(crate) base_hash: Block::Hash,
+	/// The number of the base block of the round.
+	pub(crate) base_number: NumberFor<Block>,
+}
+
+impl<Block: BlockT> CatchUp<Block> {
+	/// Build a catch up out of the votes of a completed round.
+	pub(crate) fn from_completed_round(round: &CompletedRound<Block>) -> Self {
+		let mut prevotes = Vec::new();
+		let mut precommits = Vec::new();
+		for signed in &round.votes {
+			match signed.message {
+				Prevote(_) => prevotes.push(signed.clone()),
+				Precommit(_) => precommits.push(signed.clone()),
+				PrimaryPropose(_) => {},
+			}
+		}
+
+		CatchUp {
+			round_number: round.number,
+			prevotes,
+			precommits,
+			base_hash: round.base.0.clone(),
+			base_number: round.base.1,
+		}
+	}
+}
+
 /// The result of processing a commit.
 pub(crate) enum CommitProcessingOutcome {
 	Good,
@@ -245,6 +294,12 @@ impl<B: BlockT, N: Network<B>> NetworkBridge<B, N> {
 		service.register_validator(validator.clone());
 
 		if let Some((set_id, set_state)) = set_state {
+			// keep the votes of the last completed round around to help lagging
+			// peers catch up.
+			if let Some(catch_up) = catch_up_for::<B>(set_state.completed_rounds().last()) {
+				validator.note_completed_round(SetId(set_id), catch_up);
+			}
+
 			// register all previous votes with the gossip service so that they're
 			// available to peers potentially stuck on a previous round.
 			for round in set_state.completed_rounds().iter() {
@@ -295,13 +350,62 @@ impl<B: BlockT, N: Network<B>> NetworkBridge<B, N> {
 		(bridge, startup_work)
 	}
 
+	/// Take the equivocation proofs that were collected from gossiped votes.
+	pub(crate) fn take_equivocations(&self) -> Vec<crate::EquivocationProof<B>> {
+		self.validator.take_equivocations()
+	}
+
+	/// Note that a round has been completed. Its votes are used to answer the
+	/// catch up requests of lagging peers.
+	pub(crate) fn note_completed_round(&self, set_id: SetId, round: &CompletedRound<B>) {
+		if let Some(catch_up) = catch_up_for::<B>(round) {
+			self.validator.note_completed_round(set_id, catch_up);
+		}
+	}
+
+	/// Get a stream of the catch up messages received for a given set ID, in
+	/// response to our catch up requests. The votes are signature-checked and
+	/// carry enough weight, but it's up to the voter to check that they prove
+	/// their round completable.
+	pub(crate) fn catch_up_messages(
+		&self,
+		set_id: SetId,
+		voters: Arc<VoterSet<AuthorityId>>,
+	) -> impl Stream<Item=CatchUp<B>,Error=Error> {
+		let service = self.service.clone();
+		let topic = global_topic::<B>(set_id.0);
+
+		self.service.messages_for(topic)
+			.filter_map(move |notification| {
+				let msg = match GossipMessage::<B>::decode(&mut &notification.message[..]) {
+					Some(GossipMessage::CatchUp(msg)) => msg,
+					_ => return None,
+				};
+
+				if msg.set_id != set_id {
+					return None;
+				}
+
+				match check_catch_up::<B>(&msg.message, &*voters, set_id) {
+					Ok(()) => Some(msg.message),
+					Err(cost) => {
+						if let Some(who) = notification.sender {
+							service.report(who, cost);
+						}
+						None
+					}
+				}
+			})
+			.map_err(|()| Error::Network(format!("Failed to receive message on unbounded stream")))
+	}
+
 	/// Get the round messages for a round in a given set ID. These are signature-checked.
 	pub(crate) fn round_communication(
 		&self,
 		round: Round,
 		set_id: SetId,
 		voters: Arc<VoterSet<AuthorityId>>,
-		local_key: Option<Arc<ed25519::Pair>>,
+		local_key: Option<Arc<dyn Sign<ed25519::Pair>>>,
 		has_voted: HasVoted<B>,
 	) -> (
 		impl Stream<Item=SignedMessage<B>,Error=Error>,
@@ -316,11 +420,10 @@ impl<B: BlockT, N: Network<B>> NetworkBridge<B, N> {
 			),
 		);
 
-		let locals = local_key.and_then(|pair| {
-			let public = pair.public();
-			let id = AuthorityId(public.0);
+		let locals = local_key.and_then(|key| {
+			let id = key.signing_key();
 			if voters.contains_key(&id) {
-				Some((pair, id))
+				Some((key, id))
 			} else {
 				None
 			}
@@ -410,6 +513,7 @@ impl<B: BlockT, N: Network<B>> NetworkBridge<B, N> {
 	) {
 		self.validator.note_set(
 			set_id,
+			voters.clone(),
 			|to, neighbor| self.service.send_message(to, GossipMessage::<B>::from(neighbor).encode()),
 		);
 
@@ -558,7 +662,7 @@ pub(crate) fn check_message_sig<Block: BlockT>(
 struct OutgoingMessages<Block: BlockT, N: Network<Block>> {
 	round: u64,
 	set_id: u64,
-	locals: Option<(Arc<ed25519::Pair>, AuthorityId)>,
+	locals: Option<(Arc<dyn Sign<ed25519::Pair>>, AuthorityId)>,
 	sender: mpsc::UnboundedSender<SignedMessage<Block>>,
 	network: N,
 	has_voted: HasVoted<Block>,
@@ -587,9 +691,20 @@ impl<Block: BlockT, N: Network<Block>> Sink for OutgoingMessages<Block, N>
 		}
 
 		// when locals exist, sign messages on import
-		if let Some((ref pair, ref local_id)) = self.locals {
+		if let Some((ref key, ref local_id)) = self.locals {
 			let encoded = localized_payload(self.round, self.set_id, &msg);
-			let signature = pair.sign(&encoded[..]);
+			let signature = match key.sign_message(&encoded[..]) {
+				Some(signature) => signature,
+				None => {
+					warn!(
+						target: "afg",
+						"Unable to sign vote in round {} in set {}: authority key not available",
+						self.round,
+						self.set_id,
+					);
+					return Ok(AsyncSink::Ready);
+				},
+			};
 
 			let target_hash = msg.target().0.clone();
 			let signed = SignedMessage::<Block> {
@@ -698,6 +813,99 @@ fn check_compact_commit<Block: BlockT>(
 	Ok(())
 }
 
+// the genesis round of a set has no votes to catch up with.
+fn catch_up_for<Block: BlockT>(round: &CompletedRound<Block>) -> Option<CatchUp<Block>> {
+	if round.number == 0 {
+		None
+	} else {
+		Some(CatchUp::from_completed_round(round))
+	}
+}
+
+// checks the votes of one kind in a catch up message.
+fn check_catch_up_votes<Block: BlockT>(
+	votes: &[SignedMessage<Block>],
+	is_expected_kind: impl Fn(&Message<Block>) -> bool,
+	voters: &VoterSet<AuthorityId>,
+) -> Result<(), i32> {
+	// 4f + 1 = equivocations from f voters.
+	let f = voters.total_weight() - voters.threshold();
+	let full_threshold = voters.total_weight() + f;
+
+	// check total weight is not too high, and that the distinct voters reach
+	// the threshold.
+	let mut total_weight = 0;
+	let mut voters_weight = 0;
+	let mut seen = HashSet::new();
+	for signed in votes {
+		if !is_expected_kind(&signed.message) {
+			return Err(cost::MALFORMED_CATCH_UP);
+		}
+
+		if let Some(weight) = voters.info(&signed.id).map(|info| info.weight()) {
+			total_weight += weight;
+			if total_weight > full_threshold {
+				return Err(cost::MALFORMED_CATCH_UP);
+			}
+
+			if seen.insert(signed.id.clone()) {
+				voters_weight += weight;
+			}
+		} else {
+			debug!(target: "afg", "Skipping catch up containing unknown voter {}", signed.id);
+			return Err(cost::MALFORMED_CATCH_UP);
+		}
+	}
+
+	if voters_weight < voters.threshold() {
+		return Err(cost::MALFORMED_CATCH_UP);
+	}
+
+	Ok(())
+}
+
+// checks a catch up. the prevotes and the precommits must each be signed by
+// voters reaching the threshold.
+pub(crate) fn check_catch_up<Block: BlockT>(
+	msg: &CatchUp<Block>,
+	voters: &VoterSet<AuthorityId>,
+	set_id: SetId,
+) -> Result<(), i32> {
+	check_catch_up_votes::<Block>(
+		&msg.prevotes,
+		|message| if let Prevote(_) = message { true } else { false },
+		voters,
+	)?;
+	check_catch_up_votes::<Block>(
+		&msg.precommits,
+		|message| if let Precommit(_) = message { true } else { false },
+		voters,
+	)?;
+
+	// check signatures on all contained votes.
+	for (i, signed) in msg.prevotes.iter().chain(&msg.precommits).enumerate() {
+		use crate::communication::gossip::Misbehavior;
+
+		if let Err(()) = check_message_sig::<Block>(
+			&signed.message,
+			&signed.id,
+			&signed.signature,
+			msg.round_number,
+			set_id.0,
+		) {
+			debug!(target: "afg", "Bad catch up message signature {}", signed.id);
+			telemetry!(CONSENSUS_DEBUG; "afg.bad_catch_up_msg_signature"; "id" => ?signed.id);
+			let cost = Misbehavior::BadCatchUpMessage {
+				signatures_checked: i as i32,
+			}.cost();
+
+			return Err(cost);
+		}
+	}
+
+	Ok(())
+}
+
 /// An output sink for commit messages.
 struct CommitsOut<Block: BlockT, N: Network<Block>> {
 	network: N,
@@ -36,11 +36,11 @@ use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::{
 	Block as BlockT, Header as HeaderT, NumberFor, One, Zero, BlockNumberToHash,
 };
-use substrate_primitives::{Blake2Hasher, ed25519, H256, Pair};
+use substrate_primitives::{Blake2Hasher, ed25519, H256};
 use substrate_telemetry::{telemetry, CONSENSUS_INFO};
 
 use crate::{
-	CommandOrError, Commit, Config, Error, Network, Precommit, Prevote,
+	CommandOrError, Commit, Config, Error, EquivocationProof, Network, Precommit, Prevote,
 	PrimaryPropose, SignedMessage, NewAuthoritySet, VoterCommand,
 };
 
@@ -49,6 +49,8 @@ use consensus_common::SelectChain;
 use crate::authorities::SharedAuthoritySet;
 use crate::consensus_changes::SharedConsensusChanges;
 use crate::justification::GrandpaJustification;
+use crate::notification::JustificationSubscribers;
+use crate::round_state::SharedRoundState;
 use crate::until_imported::UntilVoteTargetImported;
 
 use ed25519::Public as AuthorityId;
@@ -278,6 +280,8 @@ pub(crate) struct Environment<B, E, Block: BlockT, N: Network<Block>, RA, SC> {
 	pub(crate) network: crate::communication::NetworkBridge<Block, N>,
 	pub(crate) set_id: u64,
 	pub(crate) voter_set_state: SharedVoterSetState<Block>,
+	pub(crate) round_state: SharedRoundState,
+	pub(crate) justification_subscribers: JustificationSubscribers,
 }
 
 impl<B, E, Block: BlockT, N: Network<Block>, RA, SC> Environment<B, E, Block, N, RA, SC> {
@@ -424,6 +428,23 @@ pub(crate) fn ancestry<B, Block: BlockT<Hash=H256>, E, RA>(
 	Ok(tree_route.retracted().iter().skip(1).map(|e| e.hash).collect())
 }
 
+impl<B, E, Block: BlockT<Hash=H256>, N, RA, SC> Environment<B, E, Block, N, RA, SC> where
+	B: Backend<Block, Blake2Hasher>,
+	E: CallExecutor<Block, Blake2Hasher>,
+	N: Network<Block>,
+{
+	/// Store equivocation proofs in the aux DB, so that they can be reported on-chain.
+	fn note_equivocations(&self, proofs: impl IntoIterator<Item=EquivocationProof<Block>>) {
+		for proof in proofs {
+			#[allow(deprecated)]
+			let result = crate::aux_schema::note_equivocation(&**self.inner.backend(), proof);
+			if let Err(e) = result {
+				warn!(target: "afg", "Failed to store equivocation proof: {:?}", e);
+			}
+		}
+	}
+}
+
 impl<B, E, Block: BlockT<Hash=H256>, N, RA, SC>
 	voter::Environment<Block::Hash, NumberFor<Block>>
 for Environment<B, E, Block, N, RA, SC>
@@ -457,12 +478,16 @@ where
 		&self,
 		round: u64
 	) -> voter::RoundData<Self::Id, Self::Timer, Self::In, Self::Out> {
+		crate::metrics::report_round(self.set_id, round);
+
 		let now = Instant::now();
 		let prevote_timer = Delay::new(now + self.config.gossip_duration * 2);
 		let precommit_timer = Delay::new(now + self.config.gossip_duration * 4);
 
 		let local_key = self.config.local_key.as_ref()
-			.filter(|pair| self.voters.contains_key(&pair.public().into()));
+			.filter(|key| self.voters.contains_key(&key.signing_key()));
+
+		self.round_state.start_round(self.set_id, round, self.voters.clone());
 
 		let (incoming, outgoing) = self.network.round_communication(
 			crate::communication::Round(round),
@@ -474,17 +499,21 @@ where
 
 		// schedule incoming messages from the network to be held until
 		// corresponding blocks are imported.
+		let round_state = self.round_state.clone();
+		let set_id = self.set_id;
 		let incoming = Box::new(UntilVoteTargetImported::new(
 			self.inner.import_notification_stream(),
 			self.inner.clone(),
 			incoming,
-		).map_err(Into::into));
+		)
+			.inspect(move |signed| round_state.note_vote(set_id, round, &signed.id, &signed.message))
+			.map_err(Into::into));
 
 		// schedule network message cleanup when sink drops.
 		let outgoing = Box::new(outgoing.sink_map_err(Into::into));
 
 		voter::RoundData {
-			voter_id: self.config.local_key.as_ref().map(|pair| pair.public().clone()),
+			voter_id: self.config.local_key.as_ref().map(|key| key.signing_key()),
 			prevote_timer: Box::new(prevote_timer.map_err(|e| Error::Timer(e).into())),
 			precommit_timer: Box::new(precommit_timer.map_err(|e| Error::Timer(e).into())),
 			incoming,
@@ -494,7 +523,7 @@ where
 
 	fn proposed(&self, _round: u64, propose: PrimaryPropose<Block>) -> Result<(), Self::Error> {
 		let local_id = self.config.local_key.as_ref()
-			.map(|pair| pair.public().into())
+			.map(|key| key.signing_key())
 			.filter(|id| self.voters.contains_key(&id));
 
 		let local_id = match local_id {
@@ -533,7 +562,7 @@ where
 
 	fn prevoted(&self, _round: u64, prevote: Prevote<Block>) -> Result<(), Self::Error> {
 		let local_id = self.config.local_key.as_ref()
-			.map(|pair| pair.public().into())
+			.map(|key| key.signing_key())
 			.filter(|id| self.voters.contains_key(&id));
 
 		let local_id = match local_id {
@@ -575,7 +604,7 @@ where
 
 	fn precommitted(&self, _round: u64, precommit: Precommit<Block>) -> Result<(), Self::Error> {
 		let local_id = self.config.local_key.as_ref()
-			.map(|pair| pair.public().into())
+			.map(|key| key.signing_key())
 			.filter(|id| self.voters.contains_key(&id));
 
 		let local_id = match local_id {
@@ -629,16 +658,18 @@ where
 			state.finalized.as_ref().map(|e| e.1),
 		);
 
+		let completed_round = CompletedRound {
+			number: round,
+			state: state.clone(),
+			base,
+			votes,
+		};
+
 		self.update_voter_set_state(|voter_set_state| {
 			let mut completed_rounds = voter_set_state.completed_rounds();
 
 			// NOTE: the Environment assumes that rounds are *always* completed in-order.
-			if !completed_rounds.push(CompletedRound {
-				number: round,
-				state: state.clone(),
-				base,
-				votes,
-			}) {
+			if !completed_rounds.push(completed_round.clone()) {
 				let msg = "Voter completed round that is older than the last completed round.";
 				return Err(Error::Safety(msg.to_string()));
 			};
@@ -654,6 +685,12 @@ where
 			Ok(Some(set_state))
 		})?;
 
+		// the votes of the round can help lagging peers catch up.
+		self.network.note_completed_round(crate::communication::SetId(self.set_id), &completed_round);
+
+		// equivocations in rounds we didn't vote in are only caught by the gossip validator.
+		self.note_equivocations(self.network.take_equivocations());
+
 		Ok(())
 	}
 
@@ -680,6 +717,7 @@ where
 			&*self.inner,
 			&self.authority_set,
 			&self.consensus_changes,
+			Some(&self.justification_subscribers),
 			Some(self.config.justification_period.into()),
 			hash,
 			number,
@@ -699,20 +737,36 @@ where
 
 	fn prevote_equivocation(
 		&self,
-		_round: u64,
+		round: u64,
 		equivocation: ::grandpa::Equivocation<Self::Id, Prevote<Block>, Self::Signature>
 	) {
 		warn!(target: "afg", "Detected prevote equivocation in the finality worker: {:?}", equivocation);
-		// nothing yet; this could craft misbehavior reports of some kind.
+
+		let Equivocation { identity, first, second, .. } = equivocation;
+		self.note_equivocations(crate::equivocation_proof::<Block>(
+			self.set_id,
+			round,
+			identity,
+			(::grandpa::Message::Prevote(first.0), first.1),
+			(::grandpa::Message::Prevote(second.0), second.1),
+		));
 	}
 
 	fn precommit_equivocation(
 		&self,
-		_round: u64,
+		round: u64,
 		equivocation: Equivocation<Self::Id, Precommit<Block>, Self::Signature>
 	) {
 		warn!(target: "afg", "Detected precommit equivocation in the finality worker: {:?}", equivocation);
-		// nothing yet
+
+		let Equivocation { identity, first, second, .. } = equivocation;
+		self.note_equivocations(crate::equivocation_proof::<Block>(
+			self.set_id,
+			round,
+			identity,
+			(::grandpa::Message::Precommit(first.0), first.1),
+			(::grandpa::Message::Precommit(second.0), second.1),
+		));
 	}
 }
 
@@ -735,12 +789,14 @@ impl<Block: BlockT> From<GrandpaJustification<Block>> for JustificationOrCommit<
 
 /// Finalize the given block and apply any authority set changes. If an
 /// authority set change is enacted then a justification is created (if not
-/// given) and stored with the block when finalizing it.
+/// given) and stored with the block when finalizing it. The justification is
+/// sent to the given subscribers, if any, once the block is finalized.
 /// This method assumes that the block being finalized has already been imported.
 pub(crate) fn finalize_block<B, Block: BlockT<Hash=H256>, E, RA>(
 	client: &Client<B, E, Block, RA>,
 	authority_set: &SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
 	consensus_changes: &SharedConsensusChanges<Block::Hash, NumberFor<Block>>,
+	justification_subscribers: Option<&JustificationSubscribers>,
 	justification_period: Option<NumberFor<Block>>,
 	hash: Block::Hash,
 	number: NumberFor<Block>,
@@ -760,6 +816,9 @@ pub(crate) fn finalize_block<B, Block: BlockT<Hash=H256>, E, RA>(
 	let mut old_consensus_changes = None;
 
 	let mut consensus_changes = consensus_changes.lock();
+	// the justification to notify once the block is finalized.
+	let mut notified_justification = None;
+	let justification_subscribers = justification_subscribers.filter(|s| s.is_subscribed());
 	let canon_at_height = |canon_number| {
 		// "true" because the block is finalized
 		canonical_at_height(client, (hash, number), true, canon_number)
@@ -799,7 +858,13 @@ pub(crate) fn finalize_block<B, Block: BlockT<Hash=H256>, E, RA>(
 		// justifications for transition blocks which will be requested by
 		// syncing clients.
 		let justification = match justification_or_commit {
-			JustificationOrCommit::Justification(justification) => Some(justification.encode()),
+			JustificationOrCommit::Justification(justification) => {
+				let justification = justification.encode();
+				if justification_subscribers.is_some() {
+					notified_justification = Some(justification.clone());
+				}
+				Some(justification)
+			},
 			JustificationOrCommit::Commit((round_number, commit)) => {
 				let mut justification_required =
 					// justification is always required when block that enacts new authorities
@@ -819,14 +884,17 @@ pub(crate) fn finalize_block<B, Block: BlockT<Hash=H256>, E, RA>(
 					}
 				}
 
-				if justification_required {
+				if justification_required || justification_subscribers.is_some() {
 					let justification = GrandpaJustification::from_commit(
 						client,
 						round_number,
 						commit,
-					)?;
+					)?.encode();
 
-					Some(justification.encode())
+					if justification_subscribers.is_some() {
+						notified_justification = Some(justification.clone());
+					}
+					if justification_required { Some(justification) } else { None }
 				} else {
 					None
 				}
@@ -888,6 +956,12 @@ pub(crate) fn finalize_block<B, Block: BlockT<Hash=H256>, E, RA>(
 		Ok(new_authorities.map(VoterCommand::ChangeAuthorities))
 	});
 
+	if update_res.is_ok() {
+		if let (Some(subscribers), Some(justification)) = (justification_subscribers, notified_justification) {
+			subscribers.notify(&justification);
+		}
+	}
+
 	match update_res {
 		Ok(Some(command)) => Err(CommandOrError::VoterCommand(command)),
 		Ok(None) => Ok(()),
@@ -40,7 +40,7 @@ use log::{trace, warn};
 use client::{
 	backend::Backend, blockchain::Backend as BlockchainBackend, CallExecutor, Client,
 	error::{Error as ClientError, Result as ClientResult},
-	light::fetcher::{FetchChecker, RemoteCallRequest},
+	light::{call_executor::check_execution_proof, fetcher::{FetchChecker, RemoteCallRequest}},
 	ExecutionStrategy, NeverOffchainExt,
 };
 use parity_codec::{Encode, Decode};
@@ -51,6 +51,7 @@ use runtime_primitives::traits::{
 };
 use substrate_primitives::{ed25519, H256, Blake2Hasher};
 use ed25519::Public as AuthorityId;
+use state_machine::CodeExecutor;
 use substrate_telemetry::{telemetry, CONSENSUS_INFO};
 
 use crate::justification::GrandpaJustification;
@@ -129,6 +130,45 @@ impl<Block: BlockT> AuthoritySetForFinalityChecker<Block> for Arc<FetchChecker<B
 	}
 }
 
+/// Code executor-based implementation of AuthoritySetForFinalityChecker.
+///
+/// Used by full nodes, which have no `FetchChecker` at hand.
+pub struct ExecutorAuthoritySetChecker<E> {
+	executor: E,
+}
+
+impl<E> ExecutorAuthoritySetChecker<E> {
+	/// Create new authority set checker using given code executor.
+	pub fn new(executor: E) -> Self {
+		ExecutorAuthoritySetChecker { executor }
+	}
+}
+
+impl<Block: BlockT<Hash=H256>, E: CodeExecutor<Blake2Hasher>> AuthoritySetForFinalityChecker<Block>
+	for ExecutorAuthoritySetChecker<E>
+{
+	fn check_authorities_proof(
+		&self,
+		hash: Block::Hash,
+		header: Block::Header,
+		proof: Vec<Vec<u8>>,
+	) -> ClientResult<Vec<(AuthorityId, u64)>> {
+		let request = RemoteCallRequest {
+			block: hash,
+			header,
+			method: "GrandpaApi_grandpa_authorities".into(),
+			call_data: vec![],
+			retry_count: None,
+		};
+
+		check_execution_proof::<_, _, Blake2Hasher>(&self.executor, &request, proof)
+			.and_then(|authorities| Decode::decode(&mut &authorities[..])
+				.ok_or_else(|| ClientError::CallResultDecode(
+					"failed to decode GRANDPA authorities set proof".into(),
+				)))
+	}
+}
+
 /// Finality proof provider for serving network requests.
 pub struct FinalityProofProvider<B, E, Block: BlockT<Hash=H256>, RA> {
 	client: Arc<Client<B, E, Block, RA>>,
@@ -598,7 +638,7 @@ pub(crate) mod tests {
 		}
 	}
 
-	struct ClosureAuthoritySetForFinalityChecker<Closure>(pub Closure);
+	pub(crate) struct ClosureAuthoritySetForFinalityChecker<Closure>(pub Closure);
 
 	impl<Closure> AuthoritySetForFinalityChecker<Block> for ClosureAuthoritySetForFinalityChecker<Closure>
 		where
@@ -572,6 +572,7 @@ where
 			&self.authority_set,
 			&self.consensus_changes,
 			None,
+			None,
 			hash,
 			number,
 			justification.into(),
@@ -57,27 +57,29 @@ use futures::prelude::*;
 use log::{debug, info, warn};
 use futures::sync::mpsc;
 use client::{
-	BlockchainEvents, CallExecutor, Client, backend::Backend,
+	BlockchainEvents, CallExecutor, Client, backend::{AuxStore, Backend},
 	error::Error as ClientError,
 };
 use client::blockchain::HeaderBackend;
 use parity_codec::Encode;
 use runtime_primitives::traits::{
-	NumberFor, Block as BlockT, DigestFor, ProvideRuntimeApi,
+	NumberFor, Block as BlockT, DigestFor, ProvideRuntimeApi, One,
 };
-use fg_primitives::GrandpaApi;
+use fg_primitives::{GrandpaApi, GrandpaEquivocationApi};
 use inherents::InherentDataProviders;
 use runtime_primitives::generic::BlockId;
 use consensus_common::SelectChain;
-use substrate_primitives::{ed25519, H256, Pair, Blake2Hasher};
+use substrate_primitives::{ed25519, H256, Blake2Hasher, crypto::Sign};
 use substrate_telemetry::{telemetry, CONSENSUS_INFO, CONSENSUS_DEBUG, CONSENSUS_WARN};
 use serde_json;
+use transaction_pool::txpool::{self, ChainApi, Pool, IntoPoolError};
 
 use srml_finality_tracker;
 
 use grandpa::Error as GrandpaError;
 use grandpa::{voter, round::State as RoundState, BlockNumberOps, voter_set::VoterSet};
 
+use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
 use std::time::Duration;
@@ -93,17 +95,24 @@ mod finality_proof;
 mod import;
 mod justification;
 mod light_import;
+mod metrics;
+mod notification;
 mod observer;
+pub mod round_state;
 mod until_imported;
+mod warp_proof;
 
 #[cfg(feature="service-integration")]
 mod service_integration;
 #[cfg(feature="service-integration")]
 pub use service_integration::{LinkHalfForService, BlockImportForService, BlockImportForLightService};
 pub use communication::Network;
-pub use finality_proof::FinalityProofProvider;
+pub use finality_proof::{FinalityProofProvider, ExecutorAuthoritySetChecker};
 pub use light_import::light_block_import;
 pub use observer::run_grandpa_observer;
+pub use notification::JustificationSubscribers;
+pub use round_state::SharedRoundState;
+pub use warp_proof::WarpSyncProofProvider;
 
 use aux_schema::PersistentData;
 use environment::{CompletedRound, CompletedRounds, Environment, HasVoted, SharedVoterSetState, VoterSetState};
@@ -148,6 +157,35 @@ pub type CompactCommit<Block> = grandpa::CompactCommit<
 	AuthorityId
 >;
 
+/// A proof of an equivocation of a GRANDPA authority for this chain's block type.
+pub type EquivocationProof<Block> = fg_primitives::EquivocationProof<<Block as BlockT>::Hash, NumberFor<Block>>;
+
+/// Build a proof that the given authority cast both signed messages in the
+/// given round and set. Returns `None` if the messages aren't votes of the same kind.
+fn equivocation_proof<Block: BlockT>(
+	set_id: u64,
+	round: u64,
+	offender: AuthorityId,
+	first: (Message<Block>, AuthoritySignature),
+	second: (Message<Block>, AuthoritySignature),
+) -> Option<EquivocationProof<Block>> {
+	use fg_primitives::EquivocatedVote;
+
+	let vote = |message: Message<Block>| match message {
+		grandpa::Message::Prevote(p) => Some(EquivocatedVote::Prevote(p.target_hash, p.target_number)),
+		grandpa::Message::Precommit(p) => Some(EquivocatedVote::Precommit(p.target_hash, p.target_number)),
+		grandpa::Message::PrimaryPropose(_) => None,
+	};
+
+	let first = (vote(first.0)?, first.1);
+	let second = (vote(second.0)?, second.1);
+	if !first.0.is_same_kind(&second.0) {
+		return None;
+	}
+
+	Some(EquivocationProof::<Block> { set_id, round, offender, first, second })
+}
+
 /// Configuration for the GRANDPA service.
 #[derive(Clone)]
 pub struct Config {
@@ -158,7 +196,7 @@ pub struct Config {
 	/// justification generation.
 	pub justification_period: u32,
 	/// The local signing key.
-	pub local_key: Option<Arc<ed25519::Pair>>,
+	pub local_key: Option<Arc<dyn Sign<ed25519::Pair>>>,
 	/// Some local identifier of the voter.
 	pub name: Option<String>,
 }
@@ -227,13 +265,24 @@ pub(crate) struct NewAuthoritySet<H, N> {
 	pub(crate) authorities: Vec<(AuthorityId, u64)>,
 }
 
+/// A round that was proven completable by a catch up, along with its votes.
+#[derive(Debug)]
+pub(crate) struct CaughtUpRound<H, N> {
+	pub(crate) number: u64,
+	pub(crate) state: RoundState<H, N>,
+	pub(crate) base: (H, N),
+	pub(crate) votes: Vec<grandpa::SignedMessage<H, N, AuthoritySignature, AuthorityId>>,
+}
+
 /// Commands issued to the voter.
 #[derive(Debug)]
 pub(crate) enum VoterCommand<H, N> {
 	/// Pause the voter for given reason.
 	Pause(String),
 	/// New authorities.
-	ChangeAuthorities(NewAuthoritySet<H, N>)
+	ChangeAuthorities(NewAuthoritySet<H, N>),
+	/// Restart the voter after the given round.
+	CatchUp(CaughtUpRound<H, N>),
 }
 
 impl<H, N> fmt::Display for VoterCommand<H, N> {
@@ -241,6 +290,7 @@ impl<H, N> fmt::Display for VoterCommand<H, N> {
 		match *self {
 			VoterCommand::Pause(ref reason) => write!(f, "Pausing voter: {}", reason),
 			VoterCommand::ChangeAuthorities(_) => write!(f, "Changing authorities"),
+			VoterCommand::CatchUp(ref round) => write!(f, "Catching up to round {}", round.number),
 		}
 	}
 }
@@ -294,6 +344,21 @@ pub struct LinkHalf<B, E, Block: BlockT<Hash=H256>, RA, SC> {
 	select_chain: SC,
 	persistent_data: PersistentData<Block>,
 	voter_commands_rx: mpsc::UnboundedReceiver<VoterCommand<Block::Hash, NumberFor<Block>>>,
+	round_state: SharedRoundState,
+	justification_subscribers: JustificationSubscribers,
+}
+
+impl<B, E, Block: BlockT<Hash=H256>, RA, SC> LinkHalf<B, E, Block, RA, SC> {
+	/// Get the state of the round the voter is in, e.g. to serve it over RPC.
+	pub fn round_state(&self) -> SharedRoundState {
+		self.round_state.clone()
+	}
+
+	/// Get the subscribers to the justifications of the blocks finalized by the voter or the
+	/// observer.
+	pub fn justification_subscribers(&self) -> JustificationSubscribers {
+		self.justification_subscribers.clone()
+	}
 }
 
 /// Make block importer and link half necessary to tie the background voter
@@ -350,12 +415,14 @@ where
 			select_chain,
 			persistent_data,
 			voter_commands_rx,
+			round_state: SharedRoundState::default(),
+			justification_subscribers: JustificationSubscribers::default(),
 		},
 	))
 }
 
 fn global_communication<Block: BlockT<Hash=H256>, B, E, N, RA>(
-	local_key: Option<&Arc<ed25519::Pair>>,
+	local_key: Option<&Arc<dyn Sign<ed25519::Pair>>>,
 	set_id: u64,
 	voters: &Arc<VoterSet<AuthorityId>>,
 	client: &Arc<Client<B, E, Block, RA>>,
@@ -378,7 +445,7 @@ fn global_communication<Block: BlockT<Hash=H256>, B, E, N, RA>(
 {
 
 	let is_voter = local_key
-		.map(|pair| voters.contains_key(&pair.public().into()))
+		.map(|key| voters.contains_key(&key.signing_key()))
 		.unwrap_or(false);
 
 	// verification stream
@@ -417,6 +484,167 @@ fn global_communication<Block: BlockT<Hash=H256>, B, E, N, RA>(
 	(global_in, global_out)
 }
 
+/// Check that the votes of a catch up prove its round completable, returning
+/// the state of the round if so.
+///
+/// The ghost of a set of votes is computed the same way for prevotes and
+/// precommits, so both are validated as the precommits of a commit for the
+/// round base. The block finalized by the precommits must be an ancestor of
+/// (or equal to) the prevote ghost, and the round must be completable with
+/// the estimate computed from the precommits.
+fn validate_catch_up<Block: BlockT<Hash=H256>, C>(
+	catch_up: communication::CatchUp<Block>,
+	voters: &VoterSet<AuthorityId>,
+	chain: &C,
+) -> Option<CaughtUpRound<Block::Hash, NumberFor<Block>>> where
+	C: grandpa::Chain<Block::Hash, NumberFor<Block>>,
+	NumberFor<Block>: BlockNumberOps,
+{
+	let base = (catch_up.base_hash, catch_up.base_number);
+	let ghost = |votes: &[SignedMessage<Block>]| {
+		let precommits = votes.iter().map(|signed| {
+			let (target_hash, target_number) = signed.message.target();
+			grandpa::SignedPrecommit {
+				precommit: grandpa::Precommit { target_hash: target_hash.clone(), target_number },
+				signature: signed.signature.clone(),
+				id: signed.id.clone(),
+			}
+		}).collect();
+
+		let commit = Commit::<Block> {
+			target_hash: base.0,
+			target_number: base.1,
+			precommits,
+		};
+
+		grandpa::validate_commit(&commit, voters, chain)
+			.ok()
+			.and_then(|result| result.ghost().map(|ghost| ghost.clone()))
+	};
+
+	let prevote_ghost = ghost(&catch_up.prevotes)?;
+	let precommit_ghost = ghost(&catch_up.precommits)?;
+
+	// honest voters never precommit beyond the prevote ghost, so a
+	// supermajority of precommits can't finalize a block off its chain.
+	if !chain.is_equal_or_descendent_of(precommit_ghost.0, prevote_ghost.0) {
+		debug!(target: "afg", "Catch up for round {} finalizes a block beyond the prevote ghost", catch_up.round_number);
+		return None;
+	}
+
+	let estimate = match completed_estimate(
+		&catch_up.precommits,
+		&prevote_ghost,
+		&precommit_ghost,
+		voters,
+		chain,
+	) {
+		Some(estimate) => estimate,
+		None => {
+			debug!(target: "afg", "Catch up for round {} doesn't prove it completable", catch_up.round_number);
+			return None;
+		}
+	};
+
+	let state = RoundState {
+		prevote_ghost: Some(prevote_ghost),
+		finalized: Some(precommit_ghost),
+		estimate: Some(estimate),
+		completable: true,
+	};
+
+	let mut votes = catch_up.prevotes;
+	votes.extend(catch_up.precommits);
+
+	Some(CaughtUpRound {
+		number: catch_up.round_number,
+		state,
+		base,
+		votes,
+	})
+}
+
+/// Compute the estimate of a round from its precommits the same way the voter
+/// does, returning it only if the round is completable.
+///
+/// The estimate is the highest block on the chain from `finalized` to the
+/// prevote ghost which could still get a supermajority of precommits. If the
+/// estimate is the prevote ghost itself, the round is only completable when
+/// its descendants can't get a supermajority of precommits anymore.
+fn completed_estimate<Block: BlockT<Hash=H256>, C>(
+	precommits: &[SignedMessage<Block>],
+	prevote_ghost: &(Block::Hash, NumberFor<Block>),
+	finalized: &(Block::Hash, NumberFor<Block>),
+	voters: &VoterSet<AuthorityId>,
+	chain: &C,
+) -> Option<(Block::Hash, NumberFor<Block>)> where
+	C: grandpa::Chain<Block::Hash, NumberFor<Block>>,
+	NumberFor<Block>: BlockNumberOps,
+{
+	// the distinct targets of each voter, voters precommitting for more than
+	// one target are equivocators.
+	let mut targets: HashMap<&AuthorityId, Vec<Block::Hash>> = HashMap::new();
+	for signed in precommits {
+		if voters.info(&signed.id).is_none() {
+			continue;
+		}
+
+		let target = *signed.message.target().0;
+		let voted = targets.entry(&signed.id).or_insert_with(Vec::new);
+		if !voted.contains(&target) {
+			voted.push(target);
+		}
+	}
+
+	let weight = |id: &AuthorityId| voters.info(id).map_or(0, |info| info.weight());
+	let total = voters.total_weight();
+	let threshold = voters.threshold();
+	let current: u64 = targets.keys().map(|id| weight(id)).sum();
+	let equivocated: u64 = targets.iter()
+		.filter(|(_, voted)| voted.len() > 1)
+		.map(|(id, _)| weight(id))
+		.sum();
+	let tolerated = (total - threshold).saturating_sub(equivocated);
+
+	// the weight which could still end up precommitting for `block` (or
+	// strictly for its descendants). equivocators count for every block.
+	let possible = |block: Block::Hash, strict: bool| {
+		let precommitted = equivocated + targets.iter()
+			.filter(|(_, voted)| voted.len() == 1)
+			.filter(|(_, voted)| {
+				(!strict || voted[0] != block) && chain.is_equal_or_descendent_of(block, voted[0])
+			})
+			.map(|(id, _)| weight(id))
+			.sum::<u64>();
+
+		precommitted + (total - current) + ::std::cmp::min(current - precommitted, tolerated)
+	};
+
+	let mut chain_to_ghost = vec![prevote_ghost.0];
+	if finalized.0 != prevote_ghost.0 {
+		chain_to_ghost.extend(chain.ancestry(finalized.0, prevote_ghost.0).ok()?);
+		chain_to_ghost.push(finalized.0);
+	}
+
+	let mut number = prevote_ghost.1;
+	let mut estimate = None;
+	for hash in chain_to_ghost {
+		if possible(hash, false) >= threshold {
+			estimate = Some((hash, number));
+			break;
+		}
+
+		number = number - One::one();
+	}
+
+	let estimate = estimate?;
+	if estimate.0 == prevote_ghost.0 && possible(prevote_ghost.0, true) >= threshold {
+		return None;
+	}
+
+	Some(estimate)
+}
+
 /// Register the finality tracker inherent data provider (which is used by
 /// GRANDPA), if not registered already.
 fn register_finality_tracker_inherent_data_provider<B, E, Block: BlockT<Hash=H256>, RA>(
@@ -496,6 +724,8 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 		select_chain,
 		persistent_data,
 		voter_commands_rx,
+		round_state,
+		justification_subscribers,
 	} = link;
 
 	let PersistentData { authority_set, set_state, consensus_changes } = persistent_data;
@@ -542,12 +772,14 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 		authority_set: authority_set.clone(),
 		consensus_changes: consensus_changes.clone(),
 		voter_set_state: set_state.clone(),
+		round_state: round_state.clone(),
+		justification_subscribers: justification_subscribers.clone(),
 	});
 
 	initial_environment.update_voter_set_state(|voter_set_state| {
 		match voter_set_state {
 			VoterSetState::Live { current_round: HasVoted::Yes(id, _), completed_rounds } => {
-				let local_id = config.local_key.clone().map(|pair| pair.public());
+				let local_id = config.local_key.as_ref().map(|key| key.signing_key());
 				let has_voted = match local_id {
 					Some(local_id) => if *id == local_id {
 						// keep the previous votes
@@ -597,25 +829,49 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 					&network,
 				);
 
+				let catch_ups = network.catch_up_messages(
+					communication::SetId(env.set_id),
+					env.voters.clone(),
+				);
+
 				let voters = (*env.voters).clone();
 
 				let last_completed_round = completed_rounds.last();
 
-				Some(voter::Voter::new(
+				Some((voter::Voter::new(
 					env.clone(),
 					voters,
 					global_comms,
 					last_completed_round.number,
 					last_completed_round.state.clone(),
 					last_finalized,
-				))
+				), catch_ups))
 			},
 			VoterSetState::Paused { .. } => None,
 		};
 
 		// needs to be combined with another future otherwise it can deadlock.
+		let voter_env = env.clone();
 		let poll_voter = future::poll_fn(move || match maybe_voter {
-			Some(ref mut voter) => voter.poll(),
+			Some((ref mut voter, ref mut catch_ups)) => {
+				while let Async::Ready(Some(catch_up)) = catch_ups.poll()? {
+					let last_completed_round = voter_env.voter_set_state.read()
+						.completed_rounds()
+						.last()
+						.number;
+
+					if catch_up.round_number <= last_completed_round {
+						continue;
+					}
+
+					if let Some(round) = validate_catch_up(catch_up, &voter_env.voters, &*voter_env) {
+						// restart the voter after the caught up round.
+						return Err(VoterCommand::CatchUp(round).into());
+					}
+				}
+
+				voter.poll()
+			},
 			None => Ok(Async::NotReady),
 		});
 
@@ -625,6 +881,8 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 		let select_chain = select_chain.clone();
 		let authority_set = authority_set.clone();
 		let consensus_changes = consensus_changes.clone();
+		let round_state = round_state.clone();
+		let justification_subscribers = justification_subscribers.clone();
 
 		let handle_voter_command = move |command: VoterCommand<_, _>, voter_commands_rx| {
 			match command {
@@ -658,6 +916,7 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 					aux_schema::write_voter_set_state(&**client.backend(), &set_state)?;
 
 					let set_state: SharedVoterSetState<_> = set_state.into();
+					round_state.reset();
 
 					let env = Arc::new(Environment {
 						inner: client,
@@ -669,12 +928,15 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 						authority_set,
 						consensus_changes,
 						voter_set_state: set_state,
+						round_state,
+						justification_subscribers,
 					});
 
 					Ok(FutureLoop::Continue((env, voter_commands_rx)))
 				}
 				VoterCommand::Pause(reason) => {
 					info!(target: "afg", "Pausing old validator set: {}", reason);
+					env.round_state.reset();
 
 					// not racing because old voter is shut down.
 					env.update_voter_set_state(|voter_set_state| {
@@ -686,6 +948,41 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 						Ok(Some(set_state))
 					})?;
 
+					Ok(FutureLoop::Continue((env, voter_commands_rx)))
+				},
+				VoterCommand::CatchUp(round) => {
+					info!(target: "afg", "Catching up to round {} in set {}", round.number, env.set_id);
+					telemetry!(CONSENSUS_INFO; "afg.voter_command_catch_up";
+						"round" => ?round.number,
+						"set_id" => ?env.set_id,
+					);
+
+					let completed_round = CompletedRound {
+						number: round.number,
+						state: round.state,
+						base: round.base,
+						votes: round.votes,
+					};
+
+					// not racing because old voter is shut down.
+					env.update_voter_set_state(|voter_set_state| {
+						let mut completed_rounds = voter_set_state.completed_rounds();
+						if !completed_rounds.push(completed_round.clone()) {
+							return Ok(None);
+						}
+
+						let set_state = VoterSetState::Live {
+							completed_rounds,
+							current_round: HasVoted::No,
+						};
+
+						#[allow(deprecated)]
+						aux_schema::write_voter_set_state(&**client.backend(), &set_state)?;
+						Ok(Some(set_state))
+					})?;
+
+					network.note_completed_round(communication::SetId(env.set_id), &completed_round);
+
 					Ok(FutureLoop::Continue((env, voter_commands_rx)))
 				},
 			}
@@ -748,3 +1045,50 @@ pub fn run_grandpa<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 {
 	run_grandpa_voter(grandpa_params)
 }
+
+/// Submit the equivocations of GRANDPA authorities that were caught by the
+/// voter or the gossip validator to the transaction pool.
+///
+/// The report extrinsics are constructed by the runtime at the given block. A
+/// proof is kept until the runtime doesn't consider it valid anymore, i.e. it
+/// has been included in the chain or the offender's set isn't current anymore.
+/// This should be called regularly, e.g. on every imported block.
+pub fn submit_equivocation_reports<Block, C, A>(
+	client: &C,
+	transaction_pool: &Pool<A>,
+	at: &BlockId<Block>,
+) -> ::client::error::Result<()> where
+	Block: BlockT,
+	C: ProvideRuntimeApi + AuxStore,
+	C::Api: GrandpaEquivocationApi<Block>,
+	A: ChainApi<Block = Block>,
+{
+	let proofs = aux_schema::equivocations::<Block, _>(client)?;
+	if proofs.is_empty() {
+		return Ok(());
+	}
+
+	let runtime_api = client.runtime_api();
+	for proof in proofs {
+		let extrinsic = match runtime_api.construct_equivocation_report_extrinsic(at, proof.encode())? {
+			Some(extrinsic) => extrinsic,
+			None => {
+				warn!(target: "afg", "Runtime at {:?} rejected an equivocation report", at);
+				aux_schema::remove_equivocation(client, &proof)?;
+				continue;
+			},
+		};
+
+		match transaction_pool.submit_one(at, extrinsic).map_err(IntoPoolError::into_pool_error) {
+			Ok(hash) => info!(target: "afg", "Submitted equivocation report: {:?}", hash),
+			Err(Ok(txpool::error::Error::InvalidTransaction(_))) => {
+				debug!(target: "afg", "Equivocation report is not valid anymore at {:?}", at);
+				aux_schema::remove_equivocation(client, &proof)?;
+			},
+			Err(Ok(txpool::error::Error::AlreadyImported(_))) => {},
+			Err(e) => warn!(target: "afg", "Failed to submit equivocation report: {:?}", e),
+		}
+	}
+
+	Ok(())
+}
@@ -0,0 +1,38 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! GRANDPA voter metrics.
+
+use std::sync::Arc;
+use lazy_static::lazy_static;
+use substrate_metrics::{Gauge, register_gauge};
+
+lazy_static! {
+	static ref ROUND: Arc<Gauge> = register_gauge(
+		"substrate_finality_grandpa_round",
+		"Current GRANDPA round",
+	);
+	static ref SET_ID: Arc<Gauge> = register_gauge(
+		"substrate_finality_grandpa_set_id",
+		"Current GRANDPA authority set id",
+	);
+}
+
+/// Export the round the voter has just started and the authority set it belongs to.
+pub(crate) fn report_round(set_id: u64, round: u64) {
+	SET_ID.set(set_id);
+	ROUND.set(round);
+}
@@ -0,0 +1,75 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Notification of the justifications of the blocks finalized by the voter or the observer.
+//!
+//! Justifications are only stored with some of the finalized blocks. Subscribers get the
+//! justification of every block finalized through a commit, built from the commit.
+
+use std::sync::Arc;
+
+use futures::sync::mpsc;
+use parking_lot::Mutex;
+
+/// Subscribers to the encoded justifications of the finalized blocks.
+#[derive(Clone, Default)]
+pub struct JustificationSubscribers {
+	subscribers: Arc<Mutex<Vec<mpsc::UnboundedSender<Vec<u8>>>>>,
+}
+
+impl JustificationSubscribers {
+	/// Subscribe to the justifications of the blocks finalized from now on.
+	pub fn subscribe(&self) -> mpsc::UnboundedReceiver<Vec<u8>> {
+		let (sink, stream) = mpsc::unbounded();
+		self.subscribers.lock().push(sink);
+		stream
+	}
+
+	/// Returns true if anyone is subscribed, i.e. justifications need to be built.
+	pub(crate) fn is_subscribed(&self) -> bool {
+		let mut subscribers = self.subscribers.lock();
+		subscribers.retain(|sink| !sink.is_closed());
+		!subscribers.is_empty()
+	}
+
+	/// Send the justification to all subscribers, dropping the ones that are gone.
+	pub fn notify(&self, justification: &[u8]) {
+		self.subscribers.lock()
+			.retain(|sink| sink.unbounded_send(justification.to_vec()).is_ok());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::{Future, Stream};
+
+	#[test]
+	fn notifies_current_subscribers() {
+		let subscribers = JustificationSubscribers::default();
+		assert!(!subscribers.is_subscribed());
+
+		let stream = subscribers.subscribe();
+		let dropped = subscribers.subscribe();
+		drop(dropped);
+		assert!(subscribers.is_subscribed());
+
+		subscribers.notify(&[1, 2, 3]);
+		drop(subscribers);
+
+		assert_eq!(stream.collect().wait(), Ok(vec![vec![1, 2, 3]]));
+	}
+}
@@ -37,8 +37,10 @@ use crate::authorities::SharedAuthoritySet;
 use crate::communication::NetworkBridge;
 use crate::consensus_changes::SharedConsensusChanges;
 use crate::environment::{CompletedRound, CompletedRounds, HasVoted};
+use crate::notification::JustificationSubscribers;
+use crate::round_state::SharedRoundState;
 
-struct ObserverChain<'a, Block: BlockT, B, E, RA>(&'a Client<B, E, Block, RA>);
+pub(crate) struct ObserverChain<'a, Block: BlockT, B, E, RA>(pub(crate) &'a Client<B, E, Block, RA>);
 
 impl<'a, Block: BlockT<Hash=H256>, B, E, RA> grandpa::Chain<Block::Hash, NumberFor<Block>>
 	for ObserverChain<'a, Block, B, E, RA> where
@@ -60,6 +62,8 @@ fn grandpa_observer<B, E, Block: BlockT<Hash=H256>, RA, S>(
 	client: &Arc<Client<B, E, Block, RA>>,
 	authority_set: &SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
 	consensus_changes: &SharedConsensusChanges<Block::Hash, NumberFor<Block>>,
+	round_state: &SharedRoundState,
+	justification_subscribers: &JustificationSubscribers,
 	voters: &Arc<VoterSet<AuthorityId>>,
 	last_finalized_number: NumberFor<Block>,
 	commits: S,
@@ -75,6 +79,8 @@ fn grandpa_observer<B, E, Block: BlockT<Hash=H256>, RA, S>(
 {
 	let authority_set = authority_set.clone();
 	let consensus_changes = consensus_changes.clone();
+	let round_state = round_state.clone();
+	let justification_subscribers = justification_subscribers.clone();
 	let client = client.clone();
 	let voters = voters.clone();
 
@@ -109,11 +115,14 @@ fn grandpa_observer<B, E, Block: BlockT<Hash=H256>, RA, S>(
 			let finalized_hash = commit.target_hash;
 			let finalized_number = commit.target_number;
 
+			round_state.note_commit(authority_set.set_id(), round, voters.clone(), &commit.precommits);
+
 			// commit is valid, finalize the block it targets
 			match environment::finalize_block(
 				&client,
 				&authority_set,
 				&consensus_changes,
+				Some(&justification_subscribers),
 				None,
 				finalized_hash,
 				finalized_number,
@@ -163,6 +172,8 @@ pub fn run_grandpa_observer<B, E, Block: BlockT<Hash=H256>, N, RA, SC>(
 		select_chain: _,
 		persistent_data,
 		voter_commands_rx,
+		round_state,
+		justification_subscribers,
 	} = link;
 
 	let PersistentData { authority_set, consensus_changes, set_state } = persistent_data;
@@ -203,17 +214,22 @@ pub fn run_grandpa_observer<B, E, Block: BlockT<Hash=H256>, N, RA, SC>(
 			&client,
 			&authority_set,
 			&consensus_changes,
+			&round_state,
+			&justification_subscribers,
 			&voters,
 			last_finalized_number,
 			global_in,
 		);
 
+		let round_state = round_state.clone();
+
 		let handle_voter_command = move |command, voter_commands_rx| {
 			// the observer doesn't use the voter set state, but we need to
 			// update it on-disk in case we restart as validator in the future.
 			let set_state = match command {
 				VoterCommand::Pause(reason) => {
 					info!(target: "afg", "Pausing old validator set: {}", reason);
+					round_state.reset();
 
 					let completed_rounds = set_state.read().completed_rounds();
 					let set_state = VoterSetState::Paused { completed_rounds };
@@ -224,6 +240,8 @@ pub fn run_grandpa_observer<B, E, Block: BlockT<Hash=H256>, N, RA, SC>(
 					set_state
 				},
 				VoterCommand::ChangeAuthorities(new) => {
+					round_state.reset();
+
 					// start the new authority set using the block where the
 					// set changed (not where the signal happened!) as the base.
 					let genesis_state = RoundState::genesis((new.canon_hash, new.canon_number));
@@ -244,6 +262,10 @@ pub fn run_grandpa_observer<B, E, Block: BlockT<Hash=H256>, N, RA, SC>(
 
 					set_state
 				},
+				VoterCommand::CatchUp(_) => {
+					// only issued by voters, the observer doesn't track rounds.
+					return Ok(FutureLoop::Continue((authority_set, consensus_changes, set_state, voter_commands_rx)));
+				},
 			};
 
 			Ok(FutureLoop::Continue((authority_set, consensus_changes, set_state.into(), voter_commands_rx)))
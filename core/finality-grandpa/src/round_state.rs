@@ -0,0 +1,262 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tracking of the votes received in the current round, e.g. to serve them over RPC.
+//!
+//! The environment notes every round the voter starts and every vote it receives. Only the
+//! latest round is kept: votes for rounds still running in the background are ignored.
+//! Observers don't take part in rounds, they note the precommits of the commits they import.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use grandpa::{Message, voter_set::VoterSet};
+use parking_lot::RwLock;
+use serde::{Serialize, Deserialize};
+use substrate_primitives::ed25519;
+
+use ed25519::Public as AuthorityId;
+
+/// Votes of one kind (prevotes or precommits) received in the current round.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tally {
+	/// Total weight of the voters that have voted.
+	pub current_weight: u64,
+	/// Voters that haven't voted yet.
+	pub missing: Vec<AuthorityId>,
+}
+
+/// State of the current GRANDPA round.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundState {
+	/// Id of the current authority set.
+	pub set_id: u64,
+	/// Number of the current round.
+	pub round: u64,
+	/// Total weight of the authority set.
+	pub total_weight: u64,
+	/// Weight that votes must reach to be supermajority.
+	pub threshold_weight: u64,
+	/// Prevotes received in the round.
+	pub prevotes: Tally,
+	/// Precommits received in the round.
+	pub precommits: Tally,
+}
+
+/// Something that provides the state of the current GRANDPA round.
+pub trait RoundStateProvider: Send + Sync {
+	/// Returns the state of the current round, `None` if the voter isn't running.
+	fn round_state(&self) -> Option<RoundState>;
+}
+
+struct CurrentRound {
+	set_id: u64,
+	round: u64,
+	voters: Arc<VoterSet<AuthorityId>>,
+	prevotes: HashSet<AuthorityId>,
+	precommits: HashSet<AuthorityId>,
+}
+
+impl CurrentRound {
+	fn tally(&self, voted: &HashSet<AuthorityId>) -> Tally {
+		let mut current_weight = 0;
+		let mut missing = Vec::new();
+		for (id, weight) in self.voters.voters() {
+			if voted.contains(id) {
+				current_weight += weight;
+			} else {
+				missing.push(id.clone());
+			}
+		}
+
+		Tally { current_weight, missing }
+	}
+}
+
+/// State of the round the voter is currently in, shared between the voter and the RPC.
+#[derive(Clone, Default)]
+pub struct SharedRoundState {
+	inner: Arc<RwLock<Option<CurrentRound>>>,
+}
+
+impl SharedRoundState {
+	/// Note that the voter started a round. Ignored if the voter is already in a later round.
+	pub(crate) fn start_round(&self, set_id: u64, round: u64, voters: Arc<VoterSet<AuthorityId>>) {
+		let mut inner = self.inner.write();
+		if inner.as_ref().map_or(false, |current| (current.set_id, current.round) >= (set_id, round)) {
+			return;
+		}
+
+		*inner = Some(CurrentRound {
+			set_id,
+			round,
+			voters,
+			prevotes: HashSet::new(),
+			precommits: HashSet::new(),
+		});
+	}
+
+	/// Note that the voter stopped, e.g. because it is paused or the authority set changed. The
+	/// next round started replaces the current one, whatever its number.
+	pub(crate) fn reset(&self) {
+		*self.inner.write() = None;
+	}
+
+	/// Note a commit imported by an observer: the round it concludes becomes the current one,
+	/// with the precommits of the commit.
+	pub(crate) fn note_commit<H: Clone, N: Clone>(
+		&self,
+		set_id: u64,
+		round: u64,
+		voters: Arc<VoterSet<AuthorityId>>,
+		precommits: &[grandpa::SignedPrecommit<H, N, ed25519::Signature, AuthorityId>],
+	) {
+		self.start_round(set_id, round, voters);
+		for precommit in precommits {
+			self.note_vote(set_id, round, &precommit.id, &Message::Precommit(precommit.precommit.clone()));
+		}
+	}
+
+	/// Note a vote received in a round. Ignored unless it is the current round.
+	pub(crate) fn note_vote<H, N>(&self, set_id: u64, round: u64, id: &AuthorityId, message: &Message<H, N>) {
+		let mut inner = self.inner.write();
+		let current = match *inner {
+			Some(ref mut current) if current.set_id == set_id && current.round == round => current,
+			_ => return,
+		};
+
+		if !current.voters.contains_key(id) {
+			return;
+		}
+
+		match message {
+			Message::Prevote(_) => { current.prevotes.insert(id.clone()); },
+			Message::Precommit(_) => { current.precommits.insert(id.clone()); },
+			Message::PrimaryPropose(_) => {},
+		}
+	}
+}
+
+impl RoundStateProvider for SharedRoundState {
+	fn round_state(&self) -> Option<RoundState> {
+		self.inner.read().as_ref().map(|current| RoundState {
+			set_id: current.set_id,
+			round: current.round,
+			total_weight: current.voters.total_weight(),
+			threshold_weight: current.voters.threshold(),
+			prevotes: current.tally(&current.prevotes),
+			precommits: current.tally(&current.precommits),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_primitives::H256;
+	use grandpa::{Prevote, Precommit};
+
+	fn voters() -> Arc<VoterSet<AuthorityId>> {
+		let voters = (1..4).map(|n| (ed25519::Public::from_raw([n; 32]), 1)).collect();
+		Arc::new(voters)
+	}
+
+	#[test]
+	fn tallies_votes_of_current_round() {
+		let state = SharedRoundState::default();
+		assert!(state.round_state().is_none());
+
+		let voters = voters();
+		let alice = voters.voters()[0].0.clone();
+		let bob = voters.voters()[1].0.clone();
+		let prevote = Message::Prevote(Prevote::new(H256::zero(), 1u64));
+		let precommit = Message::Precommit(Precommit::new(H256::zero(), 1u64));
+
+		state.start_round(0, 1, voters.clone());
+		state.note_vote(0, 1, &alice, &prevote);
+		state.note_vote(0, 1, &bob, &prevote);
+		state.note_vote(0, 1, &alice, &precommit);
+		// duplicates and votes of other rounds aren't counted.
+		state.note_vote(0, 1, &alice, &prevote);
+		state.note_vote(0, 0, &bob, &precommit);
+
+		let round_state = state.round_state().unwrap();
+		assert_eq!(round_state.set_id, 0);
+		assert_eq!(round_state.round, 1);
+		assert_eq!(round_state.threshold_weight, 3);
+		assert_eq!(round_state.prevotes.current_weight, 2);
+		assert_eq!(round_state.prevotes.missing, vec![voters.voters()[2].0.clone()]);
+		assert_eq!(round_state.precommits.current_weight, 1);
+		assert_eq!(round_state.precommits.missing.len(), 2);
+
+		// rounds started in the background don't replace the current one.
+		state.start_round(0, 0, voters.clone());
+		assert_eq!(state.round_state().unwrap().round, 1);
+
+		state.start_round(1, 1, voters);
+		let round_state = state.round_state().unwrap();
+		assert_eq!((round_state.set_id, round_state.round), (1, 1));
+		assert_eq!(round_state.prevotes.current_weight, 0);
+	}
+
+	#[test]
+	fn reset_clears_current_round() {
+		let state = SharedRoundState::default();
+		state.start_round(0, 5, voters());
+		state.reset();
+		assert!(state.round_state().is_none());
+
+		// a restarted voter may start at an earlier round.
+		state.start_round(0, 2, voters());
+		assert_eq!(state.round_state().unwrap().round, 2);
+	}
+
+	#[test]
+	fn notes_precommits_of_observed_commits() {
+		let state = SharedRoundState::default();
+		let voters = voters();
+		let precommits: Vec<_> = voters.voters()[..2].iter()
+			.map(|(id, _)| grandpa::SignedPrecommit {
+				precommit: Precommit::new(H256::zero(), 1u64),
+				signature: Default::default(),
+				id: id.clone(),
+			})
+			.collect();
+
+		state.note_commit(0, 3, voters.clone(), &precommits);
+		let round_state = state.round_state().unwrap();
+		assert_eq!(round_state.round, 3);
+		assert_eq!(round_state.precommits.current_weight, 2);
+		assert_eq!(round_state.prevotes.current_weight, 0);
+	}
+
+	#[test]
+	fn should_serialize_round_state() {
+		let state = SharedRoundState::default();
+		let voters = voters();
+		state.start_round(1, 42, voters.clone());
+		state.note_vote(1, 42, &voters.voters()[0].0, &Message::Prevote(Prevote::new(H256::zero(), 1u64)));
+
+		let serialized = serde_json::to_value(state.round_state().unwrap()).unwrap();
+		assert_eq!(serialized["setId"], 1);
+		assert_eq!(serialized["round"], 42);
+		assert_eq!(serialized["thresholdWeight"], 3);
+		assert_eq!(serialized["prevotes"]["currentWeight"], 1);
+		assert_eq!(serialized["precommits"]["missing"].as_array().unwrap().len(), 3);
+	}
+}
@@ -39,7 +39,7 @@ use std::result;
 use parity_codec::Decode;
 use runtime_primitives::traits::{ApiRef, ProvideRuntimeApi, Header as HeaderT};
 use runtime_primitives::generic::BlockId;
-use substrate_primitives::{NativeOrEncoded, ExecutionContext, ed25519::Public as AuthorityId};
+use substrate_primitives::{NativeOrEncoded, ExecutionContext, Pair, ed25519::Public as AuthorityId};
 
 use authorities::AuthoritySet;
 use finality_proof::{FinalityProofProvider, AuthoritySetForFinalityProver, AuthoritySetForFinalityChecker};
@@ -99,6 +99,8 @@ impl TestNetFactory for GrandpaTestNet {
 		// the authority role ensures gossip hits all nodes here.
 		ProtocolConfig {
 			roles: Roles::AUTHORITY,
+			warp_sync: false,
+			fast_sync: false,
 		}
 	}
 
@@ -509,7 +511,7 @@ fn run_to_completion_with<F>(
 			config: Config {
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_period: 32,
-				local_key: Some(Arc::new(key.clone().into())),
+				local_key: Some(Arc::new(key.pair())),
 				name: Some(format!("peer#{}", peer_id)),
 			},
 			link: link,
@@ -573,6 +575,40 @@ fn finalize_3_voters_no_observers() {
 		"Extra justification for block#1");
 }
 
+#[test]
+fn finalized_blocks_are_notified_with_justification() {
+	let _ = env_logger::try_init();
+	let peers = &[AuthorityKeyring::Alice, AuthorityKeyring::Bob, AuthorityKeyring::Charlie];
+	let voters = make_ids(peers);
+
+	let mut net = GrandpaTestNet::new(TestApi::new(voters), 3);
+	net.peer(0).push_blocks(20, false);
+	net.sync();
+
+	let justifications = net.peers[0].data.lock().as_ref()
+		.expect("link initialized at startup; qed")
+		.justification_subscribers()
+		.subscribe();
+
+	let net = Arc::new(Mutex::new(net));
+	run_to_completion(20, net.clone(), peers);
+
+	// the voter is dropped with the runtime, closing the stream.
+	let justifications = justifications.collect().wait().unwrap();
+	assert!(!justifications.is_empty());
+
+	let mut last_finalized = 0;
+	for justification in justifications {
+		let justification = crate::justification::GrandpaJustification::<Block>::decode(&mut &justification[..])
+			.expect("justifications are encoded; qed");
+		assert!(justification.commit.target_number > last_finalized);
+		last_finalized = justification.commit.target_number;
+	}
+
+	// the justifications are sent without being stored.
+	assert!(net.lock().peer(0).client().justification(&BlockId::Number(20)).unwrap().is_none());
+}
+
 #[test]
 fn finalize_3_voters_1_full_observer() {
 	let peers = &[AuthorityKeyring::Alice, AuthorityKeyring::Bob, AuthorityKeyring::Charlie];
@@ -588,7 +624,7 @@ fn finalize_3_voters_1_full_observer() {
 	let mut runtime = current_thread::Runtime::new().unwrap();
 	let all_peers = peers.iter()
 		.cloned()
-		.map(|key| Some(Arc::new(key.into())))
+		.map(|key| Some(Arc::new(key.pair()) as Arc<dyn Sign<ed25519::Pair>>))
 		.chain(::std::iter::once(None));
 
 	for (peer_id, local_key) in all_peers.enumerate() {
@@ -747,7 +783,7 @@ fn transition_3_voters_twice_1_full_observer() {
 		.cloned()
 		.collect::<HashSet<_>>() // deduplicate
 		.into_iter()
-		.map(|key| Some(Arc::new(key.into())))
+		.map(|key| Some(Arc::new(key.pair()) as Arc<dyn Sign<ed25519::Pair>>))
 		.enumerate();
 
 	for (peer_id, local_key) in all_peers {
@@ -1196,7 +1232,7 @@ fn voter_persists_its_votes() {
 				config: Config {
 					gossip_duration: TEST_GOSSIP_DURATION,
 					justification_period: 32,
-					local_key: Some(Arc::new(peers[0].clone().into())),
+					local_key: Some(Arc::new(peers[0].pair())),
 					name: Some(format!("peer#{}", 0)),
 				},
 				link: link,
@@ -1247,7 +1283,7 @@ fn voter_persists_its_votes() {
 		let config = Config {
 			gossip_duration: TEST_GOSSIP_DURATION,
 			justification_period: 32,
-			local_key: Some(Arc::new(peers[1].clone().into())),
+			local_key: Some(Arc::new(peers[1].pair())),
 			name: Some(format!("peer#{}", 1)),
 		};
 		let routing = MessageRouting::new(net.clone(), 1);
@@ -1486,3 +1522,294 @@ fn empty_finality_proof_is_returned_to_light_client_when_authority_set_is_differ
 		if FORCE_CHANGE { 0 } else { 10 },
 	);
 }
+
+fn catch_up_vote(key: AuthorityKeyring, round: u64, message: Message<Block>) -> SignedMessage<Block> {
+	let pair = key.pair();
+	let signature = pair.sign(&(&message, round, 0u64).encode()[..]);
+	SignedMessage::<Block> { message, signature, id: pair.public() }
+}
+
+fn catch_up_prevote(key: AuthorityKeyring, round: u64, target: (Hash, BlockNumber)) -> SignedMessage<Block> {
+	catch_up_vote(key, round, grandpa::Message::Prevote(grandpa::Prevote {
+		target_hash: target.0,
+		target_number: target.1,
+	}))
+}
+
+fn catch_up_precommit(key: AuthorityKeyring, round: u64, target: (Hash, BlockNumber)) -> SignedMessage<Block> {
+	catch_up_vote(key, round, grandpa::Message::Precommit(grandpa::Precommit {
+		target_hash: target.0,
+		target_number: target.1,
+	}))
+}
+
+#[test]
+fn catch_up_proves_round_completable() {
+	let peers = &[AuthorityKeyring::Alice, AuthorityKeyring::Bob, AuthorityKeyring::Charlie, AuthorityKeyring::Dave];
+	let voters: VoterSet<AuthorityId> = make_ids(peers).into_iter().collect();
+
+	let mut net = GrandpaTestNet::new(TestApi::new(make_ids(peers)), 1);
+	net.peer(0).push_blocks(10, false);
+
+	let client = net.peer(0).client().as_full().expect("only full clients are used in test");
+	let hash = |number| client.backend().blockchain().hash(number).unwrap().unwrap();
+	let base = (hash(0), 0);
+	let target = (hash(5), 5);
+
+	let catch_up = communication::CatchUp::<Block> {
+		round_number: 3,
+		prevotes: peers[..3].iter().map(|key| catch_up_prevote(*key, 3, target)).collect(),
+		precommits: peers[..3].iter().map(|key| catch_up_precommit(*key, 3, target)).collect(),
+		base_hash: base.0,
+		base_number: base.1,
+	};
+
+	assert!(communication::check_catch_up(&catch_up, &voters, communication::SetId(0)).is_ok());
+
+	let round = validate_catch_up(catch_up, &voters, &observer::ObserverChain(&*client))
+		.expect("the votes of the catch up prove the round completable");
+
+	assert_eq!(round.number, 3);
+	assert_eq!(round.base, base);
+	assert_eq!(round.votes.len(), 6);
+	assert_eq!(round.state, RoundState {
+		prevote_ghost: Some(target),
+		finalized: Some(target),
+		estimate: Some(target),
+		completable: true,
+	});
+}
+
+#[test]
+fn catch_up_is_checked_against_voter_set() {
+	let peers = &[AuthorityKeyring::Alice, AuthorityKeyring::Bob, AuthorityKeyring::Charlie, AuthorityKeyring::Dave];
+	let voters: VoterSet<AuthorityId> = make_ids(peers).into_iter().collect();
+	let target = (Hash::repeat_byte(5), 5);
+
+	let catch_up = |round, signers: &[AuthorityKeyring]| communication::CatchUp::<Block> {
+		round_number: 3,
+		prevotes: signers.iter().map(|key| catch_up_prevote(*key, round, target)).collect(),
+		precommits: signers.iter().map(|key| catch_up_precommit(*key, round, target)).collect(),
+		base_hash: Hash::default(),
+		base_number: 0,
+	};
+	let check = |catch_up: communication::CatchUp<Block>| communication::check_catch_up::<Block>(&catch_up, &voters, communication::SetId(0));
+
+	assert!(check(catch_up(3, &peers[..3])).is_ok());
+
+	// the votes of two voters don't reach the threshold.
+	assert!(check(catch_up(3, &peers[..2])).is_err());
+
+	// the same voter twice doesn't count twice.
+	let duplicated = [AuthorityKeyring::Alice, AuthorityKeyring::Bob, AuthorityKeyring::Bob];
+	assert!(check(catch_up(3, &duplicated[..])).is_err());
+
+	// votes from outside the voter set.
+	let unknown = [AuthorityKeyring::Alice, AuthorityKeyring::Bob, AuthorityKeyring::Eve];
+	assert!(check(catch_up(3, &unknown[..])).is_err());
+
+	// votes signed for another round.
+	assert!(check(catch_up(2, &peers[..3])).is_err());
+
+	// precommits given as prevotes.
+	let mut swapped = catch_up(3, &peers[..3]);
+	swapped.prevotes = swapped.precommits.clone();
+	assert!(check(swapped).is_err());
+}
+
+#[test]
+fn catch_up_estimate_can_be_above_finalized_block() {
+	let peers = &[AuthorityKeyring::Alice, AuthorityKeyring::Bob, AuthorityKeyring::Charlie, AuthorityKeyring::Dave];
+	let voters: VoterSet<AuthorityId> = make_ids(peers).into_iter().collect();
+
+	let mut net = GrandpaTestNet::new(TestApi::new(make_ids(peers)), 1);
+	net.peer(0).push_blocks(10, false);
+
+	let client = net.peer(0).client().as_full().expect("only full clients are used in test");
+	let hash = |number| client.backend().blockchain().hash(number).unwrap().unwrap();
+
+	// everyone prevoted #5 but charlie precommitted #3, so only #3 is
+	// finalized. #5 could still get dave's precommit, which makes it the
+	// estimate, but nothing above it can get a supermajority anymore.
+	let catch_up = communication::CatchUp::<Block> {
+		round_number: 3,
+		prevotes: peers[..3].iter().map(|key| catch_up_prevote(*key, 3, (hash(5), 5))).collect(),
+		precommits: vec![
+			catch_up_precommit(AuthorityKeyring::Alice, 3, (hash(5), 5)),
+			catch_up_precommit(AuthorityKeyring::Bob, 3, (hash(5), 5)),
+			catch_up_precommit(AuthorityKeyring::Charlie, 3, (hash(3), 3)),
+		],
+		base_hash: hash(0),
+		base_number: 0,
+	};
+
+	assert!(communication::check_catch_up(&catch_up, &voters, communication::SetId(0)).is_ok());
+
+	let round = validate_catch_up(catch_up, &voters, &observer::ObserverChain(&*client))
+		.expect("the votes of the catch up prove the round completable");
+
+	assert_eq!(round.state, RoundState {
+		prevote_ghost: Some((hash(5), 5)),
+		finalized: Some((hash(3), 3)),
+		estimate: Some((hash(5), 5)),
+		completable: true,
+	});
+}
+
+#[test]
+fn catch_up_with_diverging_ghosts_is_not_completable() {
+	let peers = &[AuthorityKeyring::Alice, AuthorityKeyring::Bob, AuthorityKeyring::Charlie, AuthorityKeyring::Dave];
+	let voters: VoterSet<AuthorityId> = make_ids(peers).into_iter().collect();
+
+	let mut net = GrandpaTestNet::new(TestApi::new(make_ids(peers)), 1);
+	net.peer(0).push_blocks(10, false);
+
+	let client = net.peer(0).client().as_full().expect("only full clients are used in test");
+	let hash = |number| client.backend().blockchain().hash(number).unwrap().unwrap();
+
+	// everyone prevoted #5 but the precommits finalize #6. honest voters
+	// never precommit beyond the prevote ghost, so these votes can't come
+	// from a round the voter would complete.
+	let catch_up = communication::CatchUp::<Block> {
+		round_number: 3,
+		prevotes: peers[..3].iter().map(|key| catch_up_prevote(*key, 3, (hash(5), 5))).collect(),
+		precommits: vec![
+			catch_up_precommit(AuthorityKeyring::Alice, 3, (hash(6), 6)),
+			catch_up_precommit(AuthorityKeyring::Bob, 3, (hash(6), 6)),
+			catch_up_precommit(AuthorityKeyring::Charlie, 3, (hash(7), 7)),
+		],
+		base_hash: hash(0),
+		base_number: 0,
+	};
+
+	assert!(communication::check_catch_up(&catch_up, &voters, communication::SetId(0)).is_ok());
+	assert!(validate_catch_up(catch_up, &voters, &observer::ObserverChain(&*client)).is_none());
+}
+
+#[test]
+fn catch_up_with_open_precommits_is_not_completable() {
+	let peers = &[AuthorityKeyring::Alice, AuthorityKeyring::Bob, AuthorityKeyring::Charlie, AuthorityKeyring::Dave];
+	let voters: VoterSet<AuthorityId> = make_ids(peers).into_iter().collect();
+
+	let mut net = GrandpaTestNet::new(TestApi::new(make_ids(peers)), 1);
+	net.peer(0).push_blocks(10, false);
+
+	let client = net.peer(0).client().as_full().expect("only full clients are used in test");
+	let hash = |number| client.backend().blockchain().hash(number).unwrap().unwrap();
+
+	// the ghosts agree on #5, but alice precommitted #6. with dave's missing
+	// precommit and one tolerated equivocation #6 could still get a
+	// supermajority, so the estimate may still move.
+	let catch_up = communication::CatchUp::<Block> {
+		round_number: 3,
+		prevotes: peers[..3].iter().map(|key| catch_up_prevote(*key, 3, (hash(5), 5))).collect(),
+		precommits: vec![
+			catch_up_precommit(AuthorityKeyring::Alice, 3, (hash(6), 6)),
+			catch_up_precommit(AuthorityKeyring::Bob, 3, (hash(5), 5)),
+			catch_up_precommit(AuthorityKeyring::Charlie, 3, (hash(5), 5)),
+		],
+		base_hash: hash(0),
+		base_number: 0,
+	};
+
+	assert!(communication::check_catch_up(&catch_up, &voters, communication::SetId(0)).is_ok());
+	assert!(validate_catch_up(catch_up, &voters, &observer::ObserverChain(&*client)).is_none());
+}
+
+#[test]
+fn lagging_voter_catches_up_and_resumes_voting() {
+	use tokio::timer::Interval;
+
+	let _ = env_logger::try_init();
+
+	let peers = &[AuthorityKeyring::Alice, AuthorityKeyring::Bob, AuthorityKeyring::Charlie, AuthorityKeyring::Dave];
+	let voters = make_ids(peers);
+
+	let mut net = GrandpaTestNet::new(TestApi::new(voters), 4);
+	net.peer(0).push_blocks(20, false);
+	net.sync();
+
+	let net = Arc::new(Mutex::new(net));
+	let mut runtime = current_thread::Runtime::new().unwrap();
+
+	let start_voter = |runtime: &mut current_thread::Runtime, peer_id: usize| {
+		let link = net.lock().peers[peer_id].data.lock().take().expect("link initialized at startup; qed");
+		let grandpa_params = GrandpaParams {
+			config: Config {
+				gossip_duration: TEST_GOSSIP_DURATION,
+				justification_period: 32,
+				local_key: Some(Arc::new(peers[peer_id].pair())),
+				name: Some(format!("peer#{}", peer_id)),
+			},
+			link: link,
+			network: MessageRouting::new(net.clone(), peer_id),
+			inherent_data_providers: InherentDataProviders::new(),
+			on_exit: Exit,
+			telemetry_on_connect: None,
+		};
+
+		runtime.spawn(run_grandpa_voter(grandpa_params).expect("all in order with client and network"));
+	};
+
+	// the last round completed by the voter of the given peer and whether it
+	// has already voted in the round after it.
+	let voter_progress = |peer_id: usize| {
+		let client = net.lock().peer(peer_id).client().as_full().expect("only full clients are used in test");
+		move || {
+			let state = aux_schema::load_decode::<_, VoterSetState<Block>>(
+				&**client.backend(),
+				aux_schema::SET_STATE_KEY,
+			).unwrap();
+
+			match state {
+				Some(VoterSetState::Live { completed_rounds, current_round }) => {
+					let voted = match current_round {
+						HasVoted::Yes(..) => true,
+						HasVoted::No => false,
+					};
+					(completed_rounds.last().number, voted)
+				},
+				_ => (0, false),
+			}
+		}
+	};
+
+	let drive = |net: Arc<Mutex<GrandpaTestNet>>| Interval::new_interval(TEST_ROUTING_INTERVAL)
+		.for_each(move |_| {
+			net.lock().send_import_notifications();
+			net.lock().send_finality_notifications();
+			net.lock().sync_without_disconnects();
+			Ok(())
+		})
+		.map(|_| ())
+		.map_err(|_| ());
+
+	let wait_until = |condition: Box<Fn() -> bool>| Interval::new_interval(TEST_ROUTING_INTERVAL)
+		.map_err(|_| ())
+		.take_while(move |_| Ok(!condition()))
+		.for_each(|_| Ok(()));
+
+	// alice, bob and charlie are enough to complete rounds without dave.
+	for peer_id in 0..3 {
+		start_voter(&mut runtime, peer_id);
+	}
+
+	let alice_progress = voter_progress(0);
+	runtime.block_on(
+		wait_until(Box::new(move || alice_progress().0 >= 5)).select(drive(net.clone())).map_err(|_| ())
+	).unwrap();
+
+	// dave starts at the first round, several rounds behind everyone else. it
+	// can only complete rounds by catching up to the others, and once caught
+	// up it takes part in the following rounds.
+	assert_eq!(voter_progress(3)(), (0, false));
+	start_voter(&mut runtime, 3);
+
+	let dave_progress = voter_progress(3);
+	runtime.block_on(
+		wait_until(Box::new(move || {
+			let (round, voted) = dave_progress();
+			round >= 5 && voted
+		})).select(drive(net.clone())).map_err(|_| ())
+	).unwrap();
+}
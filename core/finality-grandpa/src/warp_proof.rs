@@ -0,0 +1,606 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! GRANDPA warp sync proof generation and check.
+//!
+//! Warp sync proof allows a node that only knows the genesis authorities to jump to a recent
+//! finalized block. The proof is the ordered set of fragments, where:
+//! - every fragment but the last one contains the justification of the block that enacts
+//!   a new GRANDPA authorities set, along with the proof of GRANDPA::authorities() at that block;
+//! - the last fragment contains the justification of the latest justified block known to the
+//!   prover, or of the block that enacts a new authorities set if the proof is incomplete.
+//!
+//! Every justification is signed by the authorities set proven by the previous fragment (or by
+//! the set known to the caller for the first fragment). The header of the last fragment is then
+//! used as the base for the state download.
+
+use std::sync::Arc;
+use log::trace;
+
+use client::{
+	backend::Backend, blockchain::Backend as BlockchainBackend, CallExecutor, Client,
+	error::{Error as ClientError, Result as ClientResult},
+};
+use parity_codec::{Encode, Decode};
+use grandpa::BlockNumberOps;
+use runtime_primitives::{Justification, generic::BlockId};
+use runtime_primitives::traits::{NumberFor, Block as BlockT, Header as HeaderT, One, Zero};
+use substrate_primitives::{ed25519, H256, Blake2Hasher};
+use ed25519::Public as AuthorityId;
+use network::WarpSyncProgress;
+
+use crate::finality_proof::{
+	AuthoritySetForFinalityChecker, AuthoritySetForFinalityProver, ProvableJustification,
+};
+use crate::justification::GrandpaJustification;
+
+/// Maximum number of authorities set changes that we want to prove in a single proof.
+const MAX_FRAGMENTS_IN_PROOF: usize = 8;
+
+/// Single fragment of the warp sync proof.
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct WarpSyncFragment<Header: HeaderT> {
+	/// The header of the justified block.
+	pub header: Header,
+	/// Justification of the block.
+	pub justification: Vec<u8>,
+	/// Optional proof of execution of GRANDPA::authorities() at the block, if the block
+	/// enacts new authorities set.
+	pub authorities_proof: Option<Vec<Vec<u8>>>,
+}
+
+/// Warp sync proof.
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct WarpSyncProof<Header: HeaderT> {
+	/// Ordered proof fragments.
+	pub fragments: Vec<WarpSyncFragment<Header>>,
+	/// True if the last fragment justifies the latest justified block known to the prover.
+	pub is_finished: bool,
+}
+
+/// The state of the warp sync proof verification, passed around as opaque data.
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct WarpSyncState<Number> {
+	/// Id of the authorities set that finalizes blocks after `last_number`.
+	pub set_id: u64,
+	/// Authorities set that finalizes blocks after `last_number`.
+	pub authorities: Vec<(AuthorityId, u64)>,
+	/// The number of the last proven block.
+	pub last_number: Number,
+}
+
+/// Justification that could be used in warp sync proof.
+pub(crate) trait WarpSyncJustification<Block: BlockT>: ProvableJustification<Block::Header> {
+	/// Hash and number of the block this justification finalizes.
+	fn target(&self) -> (Block::Hash, NumberFor<Block>);
+}
+
+impl<Block: BlockT<Hash=H256>> WarpSyncJustification<Block> for GrandpaJustification<Block>
+	where
+		NumberFor<Block>: BlockNumberOps,
+{
+	fn target(&self) -> (Block::Hash, NumberFor<Block>) {
+		(self.commit.target_hash, self.commit.target_number)
+	}
+}
+
+/// Warp sync proof provider for serving and checking network requests.
+pub struct WarpSyncProofProvider<B, E, Block: BlockT<Hash=H256>, RA> {
+	client: Arc<Client<B, E, Block, RA>>,
+	authority_provider: Arc<AuthoritySetForFinalityProver<Block>>,
+	authority_checker: Arc<AuthoritySetForFinalityChecker<Block>>,
+}
+
+impl<B, E, Block: BlockT<Hash=H256>, RA> WarpSyncProofProvider<B, E, Block, RA>
+	where
+		B: Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+		E: CallExecutor<Block, Blake2Hasher> + 'static + Clone + Send + Sync,
+		RA: Send + Sync,
+{
+	/// Create new warp sync proof provider using:
+	///
+	/// - client for accessing blockchain data;
+	/// - authority_provider for calling and proving runtime methods;
+	/// - authority_checker for checking proofs of runtime methods execution.
+	pub fn new(
+		client: Arc<Client<B, E, Block, RA>>,
+		authority_provider: Arc<AuthoritySetForFinalityProver<Block>>,
+		authority_checker: Arc<AuthoritySetForFinalityChecker<Block>>,
+	) -> Self {
+		WarpSyncProofProvider { client, authority_provider, authority_checker }
+	}
+}
+
+impl<B, E, Block, RA> network::WarpSyncProvider<Block> for WarpSyncProofProvider<B, E, Block, RA>
+	where
+		Block: BlockT<Hash=H256>,
+		NumberFor<Block>: BlockNumberOps,
+		B: Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+		E: CallExecutor<Block, Blake2Hasher> + 'static + Clone + Send + Sync,
+		RA: Send + Sync,
+{
+	fn generate(&self, begin: Block::Hash) -> Result<Option<Vec<u8>>, ClientError> {
+		prove_warp_sync(
+			#[allow(deprecated)]
+			&*self.client.backend().blockchain(),
+			&*self.authority_provider,
+			begin,
+		)
+	}
+
+	fn initial_authorities(&self) -> Result<Vec<u8>, ClientError> {
+		let authorities = self.authority_provider.authorities(&BlockId::Number(Zero::zero()))?;
+		Ok(WarpSyncState::<NumberFor<Block>> {
+			set_id: 0,
+			authorities,
+			last_number: Zero::zero(),
+		}.encode())
+	}
+
+	fn verify(
+		&self,
+		proof: &[u8],
+		authorities: Vec<u8>,
+	) -> Result<(Vec<u8>, WarpSyncProgress<Block>), ClientError> {
+		check_warp_sync_proof::<Block, GrandpaJustification<Block>>(
+			&authorities,
+			&*self.authority_checker,
+			proof,
+		)
+	}
+
+	fn verify_justification(
+		&self,
+		header: &Block::Header,
+		justification: &Justification,
+		authorities: &[u8],
+	) -> Result<(), ClientError> {
+		check_justification::<Block, GrandpaJustification<Block>>(authorities, header, justification)
+	}
+}
+
+/// Prepare warp sync proof for the finalized blocks after `begin`.
+///
+/// Returns None if there are no justified blocks after `begin`.
+pub(crate) fn prove_warp_sync<Block: BlockT<Hash=H256>, B: BlockchainBackend<Block>>(
+	blockchain: &B,
+	authorities_provider: &AuthoritySetForFinalityProver<Block>,
+	begin: Block::Hash,
+) -> ClientResult<Option<Vec<u8>>> {
+	let begin_id = BlockId::Hash(begin);
+	let begin_number = blockchain.expect_block_number_from_id(&begin_id)?;
+
+	// early-return if we sure that there are no blocks finalized AFTER begin block
+	let info = blockchain.info()?;
+	if info.finalized_number <= begin_number {
+		trace!(
+			target: "finality",
+			"Requested warp sync proof starting at #{} while we only have finalized #{}. Returning empty proof.",
+			begin_number,
+			info.finalized_number,
+		);
+
+		return Ok(None);
+	}
+
+	// early-return if we sure that the block is NOT a part of canonical chain
+	let canonical_begin = blockchain.expect_block_hash_from_id(&BlockId::Number(begin_number))?;
+	if begin != canonical_begin {
+		return Err(ClientError::Backend(
+			format!("Cannot generate warp sync proof for non-canonical block: {}", begin),
+		));
+	}
+
+	// iterate justifications && prove all authorities set changes
+	let mut current_authorities = authorities_provider.authorities(&begin_id)?;
+	let mut current_number = begin_number + One::one();
+	let mut fragments = Vec::new();
+	let mut latest_fragment = None;
+	let mut is_finished = true;
+	loop {
+		let current_id = BlockId::Number(current_number);
+		if let Some(justification) = blockchain.justification(current_id)? {
+			let header = blockchain.expect_header(current_id)?;
+
+			// check if the current block enacts new GRANDPA authorities set
+			let new_authorities = authorities_provider.authorities(&current_id)?;
+			if new_authorities != current_authorities {
+				current_authorities = new_authorities;
+				fragments.push(WarpSyncFragment {
+					header,
+					justification,
+					authorities_proof: Some(authorities_provider.prove_authorities(&current_id)?),
+				});
+				latest_fragment = None;
+
+				if fragments.len() == MAX_FRAGMENTS_IN_PROOF {
+					is_finished = current_number == info.finalized_number;
+					break;
+				}
+			} else {
+				latest_fragment = Some(WarpSyncFragment {
+					header,
+					justification,
+					authorities_proof: None,
+				});
+			}
+		}
+
+		// append the latest justification - it finalizes the target block
+		if current_number == info.finalized_number {
+			fragments.extend(latest_fragment.take());
+			break;
+		}
+
+		current_number = current_number + One::one();
+	}
+
+	if fragments.is_empty() {
+		trace!(
+			target: "finality",
+			"No justifications found when making warp sync proof starting at {}. Returning empty proof.",
+			begin,
+		);
+
+		return Ok(None);
+	}
+
+	trace!(
+		target: "finality",
+		"Built warp sync proof starting at {} of {} fragments.",
+		begin,
+		fragments.len(),
+	);
+
+	Ok(Some(WarpSyncProof { fragments, is_finished }.encode()))
+}
+
+/// Check warp sync proof against the given encoded verification state.
+///
+/// Returns the updated verification state along with the last proven block.
+pub(crate) fn check_warp_sync_proof<Block: BlockT<Hash=H256>, J>(
+	state: &[u8],
+	authorities_provider: &AuthoritySetForFinalityChecker<Block>,
+	remote_proof: &[u8],
+) -> ClientResult<(Vec<u8>, WarpSyncProgress<Block>)>
+	where
+		J: WarpSyncJustification<Block>,
+{
+	let mut state = WarpSyncState::<NumberFor<Block>>::decode(&mut &state[..])
+		.ok_or_else(|| ClientError::Backend("failed to decode warp sync state".into()))?;
+	let proof = WarpSyncProof::<Block::Header>::decode(&mut &remote_proof[..])
+		.ok_or_else(|| ClientError::BadJustification("failed to decode warp sync proof".into()))?;
+
+	// empty proof can't prove anything
+	if proof.fragments.is_empty() {
+		return Err(ClientError::BadJustification("empty warp sync proof".into()));
+	}
+
+	let last_fragment_index = proof.fragments.len() - 1;
+	let mut last_fragment = None;
+	for (fragment_index, fragment) in proof.fragments.into_iter().enumerate() {
+		// only the last fragment may justify a block that doesn't change authorities set
+		if fragment_index != last_fragment_index && fragment.authorities_proof.is_none() {
+			return Err(ClientError::BadJustification("redundant warp sync proof".into()));
+		}
+
+		let number = *fragment.header.number();
+		if number <= state.last_number {
+			return Err(ClientError::BadJustification("warp sync proof fragments are not ascending".into()));
+		}
+
+		// verify justification using the current authorities set
+		let hash = fragment.header.hash();
+		let justification = J::decode_and_verify(&fragment.justification, state.set_id, &state.authorities)?;
+		if justification.target() != (hash, number) {
+			return Err(ClientError::BadJustification("invalid commit target in warp sync proof".into()));
+		}
+
+		// and now verify new authorities proof (if provided)
+		if let Some(authorities_proof) = fragment.authorities_proof {
+			state.authorities = authorities_provider.check_authorities_proof(
+				hash,
+				fragment.header.clone(),
+				authorities_proof,
+			)?;
+			state.set_id = state.set_id + 1;
+		}
+
+		state.last_number = number;
+		last_fragment = Some((fragment.header, fragment.justification));
+	}
+
+	let (header, justification) = last_fragment.expect("at least one loop iteration is guaranteed
+			because proof is not empty; qed");
+	let progress = if proof.is_finished {
+		WarpSyncProgress::Complete(header, justification)
+	} else {
+		WarpSyncProgress::Partial(header, justification)
+	};
+
+	Ok((state.encode(), progress))
+}
+
+/// Check that the justification finalizes the given header, against the authorities set of the
+/// given encoded verification state.
+pub(crate) fn check_justification<Block: BlockT<Hash=H256>, J>(
+	state: &[u8],
+	header: &Block::Header,
+	justification: &Justification,
+) -> ClientResult<()>
+	where
+		J: WarpSyncJustification<Block>,
+{
+	let state = WarpSyncState::<NumberFor<Block>>::decode(&mut &state[..])
+		.ok_or_else(|| ClientError::Backend("failed to decode warp sync state".into()))?;
+	let justification = J::decode_and_verify(justification, state.set_id, &state.authorities)?;
+	if justification.target() != (header.hash(), *header.number()) {
+		return Err(ClientError::BadJustification("invalid commit target".into()));
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use test_client::runtime::{Block, Header};
+	use test_client::client::backend::NewBlockState;
+	use test_client::client::in_mem::Blockchain as InMemoryBlockchain;
+	use crate::finality_proof::tests::ClosureAuthoritySetForFinalityChecker;
+	use super::*;
+
+	#[derive(Debug, PartialEq, Encode, Decode)]
+	struct TestJustification(bool, H256, u64);
+
+	impl ProvableJustification<Header> for TestJustification {
+		fn verify(&self, _set_id: u64, _authorities: &[(AuthorityId, u64)]) -> ClientResult<()> {
+			if self.0 {
+				Ok(())
+			} else {
+				Err(ClientError::BadJustification("test".into()))
+			}
+		}
+	}
+
+	impl WarpSyncJustification<Block> for TestJustification {
+		fn target(&self) -> (H256, u64) {
+			(self.1, self.2)
+		}
+	}
+
+	fn header(number: u64) -> Header {
+		let parent_hash = match number {
+			0 => Default::default(),
+			_ => header(number - 1).hash(),
+		};
+		Header::new(number, H256::from_low_u64_be(0), H256::from_low_u64_be(0), parent_hash, Default::default())
+	}
+
+	fn justification(number: u64) -> Vec<u8> {
+		TestJustification(true, header(number).hash(), number).encode()
+	}
+
+	fn authorities(set: u8) -> Vec<(AuthorityId, u64)> {
+		vec![(AuthorityId::from_raw([set; 32]), 1u64)]
+	}
+
+	/// Blockchain where the authorities set changes at blocks 2 and 4, and blocks 0..=5 are finalized.
+	fn test_blockchain() -> InMemoryBlockchain<Block> {
+		let blockchain = InMemoryBlockchain::<Block>::new();
+		blockchain.insert(header(0).hash(), header(0), None, None, NewBlockState::Final).unwrap();
+		blockchain.insert(header(1).hash(), header(1), None, None, NewBlockState::Final).unwrap();
+		blockchain.insert(header(2).hash(), header(2), Some(justification(2)), None, NewBlockState::Final).unwrap();
+		blockchain.insert(header(3).hash(), header(3), Some(justification(3)), None, NewBlockState::Final).unwrap();
+		blockchain.insert(header(4).hash(), header(4), Some(justification(4)), None, NewBlockState::Final).unwrap();
+		blockchain.insert(header(5).hash(), header(5), None, None, NewBlockState::Final).unwrap();
+		blockchain
+	}
+
+	fn set_at(block: BlockId<Block>) -> u8 {
+		match block {
+			BlockId::Number(number) if number >= 4 => 2,
+			BlockId::Number(number) if number >= 2 => 1,
+			BlockId::Number(_) => 0,
+			BlockId::Hash(hash) => (0..7).rev().find(|n| header(*n).hash() == hash)
+				.map(|n| set_at(BlockId::Number(n)))
+				.unwrap(),
+		}
+	}
+
+	fn initial_state() -> Vec<u8> {
+		WarpSyncState { set_id: 0, authorities: authorities(0), last_number: 0u64 }.encode()
+	}
+
+	#[test]
+	fn warp_sync_proof_is_none_if_nothing_is_justified() {
+		let blockchain = test_blockchain();
+
+		let proof = prove_warp_sync(
+			&blockchain,
+			&(
+				|block| Ok(authorities(set_at(block))),
+				|_| unreachable!("there are no justified blocks => ProveAuthorities won't be called"),
+			),
+			header(4).hash(),
+		).unwrap();
+		assert_eq!(proof, None);
+	}
+
+	#[test]
+	fn warp_sync_proof_contains_all_authorities_set_changes() {
+		let blockchain = test_blockchain();
+
+		let proof = prove_warp_sync(
+			&blockchain,
+			&(
+				|block| Ok(authorities(set_at(block))),
+				|block| Ok(vec![vec![set_at(block)]]),
+			),
+			header(0).hash(),
+		).unwrap().unwrap();
+		assert_eq!(WarpSyncProof::<Header>::decode(&mut &proof[..]).unwrap(), WarpSyncProof {
+			fragments: vec![
+				WarpSyncFragment {
+					header: header(2),
+					justification: justification(2),
+					authorities_proof: Some(vec![vec![1]]),
+				},
+				WarpSyncFragment {
+					header: header(4),
+					justification: justification(4),
+					authorities_proof: Some(vec![vec![2]]),
+				},
+			],
+			is_finished: true,
+		});
+	}
+
+	#[test]
+	fn warp_sync_proof_ends_with_latest_justification() {
+		let blockchain = test_blockchain();
+		blockchain.insert(header(6).hash(), header(6), Some(justification(6)), None, NewBlockState::Final).unwrap();
+
+		let proof = prove_warp_sync(
+			&blockchain,
+			&(
+				|block| Ok(authorities(set_at(block))),
+				|_| unreachable!("authorities didn't change => ProveAuthorities won't be called"),
+			),
+			header(4).hash(),
+		).unwrap().unwrap();
+		assert_eq!(WarpSyncProof::<Header>::decode(&mut &proof[..]).unwrap(), WarpSyncProof {
+			fragments: vec![WarpSyncFragment {
+				header: header(6),
+				justification: justification(6),
+				authorities_proof: None,
+			}],
+			is_finished: true,
+		});
+	}
+
+	#[test]
+	fn warp_sync_proof_check_works() {
+		let proof = WarpSyncProof {
+			fragments: vec![
+				WarpSyncFragment {
+					header: header(2),
+					justification: justification(2),
+					authorities_proof: Some(vec![vec![1]]),
+				},
+				WarpSyncFragment {
+					header: header(4),
+					justification: justification(4),
+					authorities_proof: Some(vec![vec![2]]),
+				},
+			],
+			is_finished: false,
+		};
+
+		let (state, progress) = check_warp_sync_proof::<Block, TestJustification>(
+			&initial_state(),
+			&ClosureAuthoritySetForFinalityChecker(|_, _, proof: Vec<Vec<u8>>| Ok(authorities(proof[0][0]))),
+			&proof.encode(),
+		).unwrap();
+		assert_eq!(
+			WarpSyncState::<u64>::decode(&mut &state[..]).unwrap(),
+			WarpSyncState { set_id: 2, authorities: authorities(2), last_number: 4 },
+		);
+		match progress {
+			WarpSyncProgress::Partial(last_header, last_justification) => {
+				assert_eq!(last_header, header(4));
+				assert_eq!(last_justification, justification(4));
+			},
+			WarpSyncProgress::Complete(..) => panic!("proof is not finished"),
+		}
+	}
+
+	#[test]
+	fn warp_sync_proof_check_fails_when_fragment_is_redundant() {
+		let proof = WarpSyncProof {
+			fragments: vec![
+				WarpSyncFragment {
+					header: header(3),
+					justification: justification(3),
+					authorities_proof: None,
+				},
+				WarpSyncFragment {
+					header: header(4),
+					justification: justification(4),
+					authorities_proof: Some(vec![vec![2]]),
+				},
+			],
+			is_finished: true,
+		};
+
+		check_warp_sync_proof::<Block, TestJustification>(
+			&initial_state(),
+			&ClosureAuthoritySetForFinalityChecker(|_, _, _| unreachable!("returns before checking authorities")),
+			&proof.encode(),
+		).unwrap_err();
+	}
+
+	#[test]
+	fn warp_sync_proof_check_fails_when_justification_targets_other_block() {
+		let proof = WarpSyncProof {
+			fragments: vec![WarpSyncFragment {
+				header: header(4),
+				justification: justification(3),
+				authorities_proof: None,
+			}],
+			is_finished: true,
+		};
+
+		check_warp_sync_proof::<Block, TestJustification>(
+			&initial_state(),
+			&ClosureAuthoritySetForFinalityChecker(|_, _, _| unreachable!("returns before checking authorities")),
+			&proof.encode(),
+		).unwrap_err();
+	}
+
+	#[test]
+	fn warp_sync_proof_check_fails_when_fragments_are_not_ascending() {
+		let proof = WarpSyncProof {
+			fragments: vec![WarpSyncFragment {
+				header: header(2),
+				justification: justification(2),
+				authorities_proof: None,
+			}],
+			is_finished: true,
+		};
+		let state = WarpSyncState { set_id: 1, authorities: authorities(1), last_number: 2u64 }.encode();
+
+		check_warp_sync_proof::<Block, TestJustification>(
+			&state,
+			&ClosureAuthoritySetForFinalityChecker(|_, _, _| unreachable!("returns before checking authorities")),
+			&proof.encode(),
+		).unwrap_err();
+	}
+
+	#[test]
+	fn justification_check_works() {
+		check_justification::<Block, TestJustification>(&initial_state(), &header(3), &justification(3))
+			.unwrap();
+
+		// justification of another block
+		check_justification::<Block, TestJustification>(&initial_state(), &header(4), &justification(3))
+			.unwrap_err();
+
+		// invalid justification
+		let invalid = TestJustification(false, header(3).hash(), 3).encode();
+		check_justification::<Block, TestJustification>(&initial_state(), &header(3), &invalid)
+			.unwrap_err();
+	}
+}
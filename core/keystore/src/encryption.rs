@@ -0,0 +1,129 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate. If not, see <http://www.gnu.org/licenses/>.
+
+//! Password based encryption of the key files.
+//!
+//! Keys are encrypted with AES-256 in CTR mode and authenticated with HMAC-SHA256, both keys
+//! being derived from the password with PBKDF2-HMAC-SHA256 and a random salt.
+
+use aes_ctr::Aes256Ctr;
+use aes_ctr::stream_cipher::{NewStreamCipher, SyncStreamCipher};
+use hmac::{Hmac, Mac};
+use rand::{Rng, rngs::OsRng};
+use serde::{Serialize, Deserialize};
+use sha2::Sha256;
+
+use crate::{Error, Result};
+
+/// Number of PBKDF2 rounds.
+const KDF_ROUNDS: usize = 10240;
+/// Length of the salt.
+const SALT_LEN: usize = 32;
+/// Length of the initialization vector.
+const IV_LEN: usize = 16;
+/// Length of each of the derived keys.
+const KEY_LEN: usize = 32;
+
+/// Encrypted data, along with what's needed to decrypt it. Byte strings are hex encoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Encrypted {
+	salt: String,
+	iv: String,
+	ciphertext: String,
+	mac: String,
+}
+
+/// Derive the encryption and authentication keys from the password.
+fn derive_keys(password: &str, salt: &[u8]) -> ([u8; KEY_LEN], [u8; KEY_LEN]) {
+	let mut derived = [0u8; KEY_LEN * 2];
+	pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, KDF_ROUNDS, &mut derived);
+
+	let mut encryption_key = [0u8; KEY_LEN];
+	let mut mac_key = [0u8; KEY_LEN];
+	encryption_key.copy_from_slice(&derived[..KEY_LEN]);
+	mac_key.copy_from_slice(&derived[KEY_LEN..]);
+	(encryption_key, mac_key)
+}
+
+fn mac(key: &[u8], iv: &[u8], ciphertext: &[u8]) -> Hmac<Sha256> {
+	let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts keys of any length; qed");
+	mac.input(iv);
+	mac.input(ciphertext);
+	mac
+}
+
+fn apply_keystream(key: &[u8], iv: &[u8], data: &mut [u8]) {
+	Aes256Ctr::new_var(key, iv)
+		.expect("key and iv have the lengths expected by AES-256-CTR; qed")
+		.apply_keystream(data);
+}
+
+/// Encrypt the given data with the password.
+pub fn encrypt(password: &str, plain: &[u8]) -> Encrypted {
+	let mut rng = OsRng::new().expect("OS random generator is available; qed");
+	let mut salt = [0u8; SALT_LEN];
+	let mut iv = [0u8; IV_LEN];
+	rng.fill(&mut salt[..]);
+	rng.fill(&mut iv[..]);
+
+	let (encryption_key, mac_key) = derive_keys(password, &salt);
+	let mut ciphertext = plain.to_vec();
+	apply_keystream(&encryption_key, &iv, &mut ciphertext);
+	let mac = mac(&mac_key, &iv, &ciphertext).result().code();
+
+	Encrypted {
+		salt: hex::encode(&salt[..]),
+		iv: hex::encode(&iv[..]),
+		ciphertext: hex::encode(&ciphertext),
+		mac: hex::encode(&mac),
+	}
+}
+
+/// Decrypt the given data with the password.
+///
+/// Returns `Error::InvalidPassword` if the data wasn't encrypted with this password.
+pub fn decrypt(password: &str, encrypted: &Encrypted) -> Result<Vec<u8>> {
+	let decode = |data: &str| hex::decode(data).map_err(|_| Error::InvalidKeyFile);
+	let salt = decode(&encrypted.salt)?;
+	let iv = decode(&encrypted.iv)?;
+	let mut data = decode(&encrypted.ciphertext)?;
+	let expected_mac = decode(&encrypted.mac)?;
+	if iv.len() != IV_LEN {
+		return Err(Error::InvalidKeyFile);
+	}
+
+	let (encryption_key, mac_key) = derive_keys(password, &salt);
+	mac(&mac_key, &iv, &data).verify(&expected_mac).map_err(|_| Error::InvalidPassword)?;
+	apply_keystream(&encryption_key, &iv, &mut data);
+	Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn encryption_roundtrip() {
+		let encrypted = encrypt("password", b"secret phrase");
+		assert!(!encrypted.ciphertext.contains(&hex::encode(b"secret phrase")));
+		assert_eq!(decrypt("password", &encrypted).unwrap(), b"secret phrase".to_vec());
+
+		match decrypt("wrong password", &encrypted) {
+			Err(Error::InvalidPassword) => {},
+			other => panic!("Unexpected result {:?}", other),
+		}
+	}
+}
@@ -14,16 +14,29 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate. If not, see <http://www.gnu.org/licenses/>.
 
-//! Keystore (and session key management) for ed25519 based chains like Polkadot.
+//! Keystore (and session key management) for ed25519 and sr25519 based chains like Polkadot.
+//!
+//! Keys are tagged by a `KeyTypeId` describing their purpose, e.g. the consensus engine using
+//! them. Key files are encrypted with the keystore password, and the decrypted keys are cached
+//! in memory.
 
 #![warn(missing_docs)]
 
 use std::collections::HashMap;
+use std::marker::PhantomData;
 use std::path::PathBuf;
 use std::fs::{self, File};
 use std::io::{self, Write};
+use std::sync::Arc;
 
-use substrate_primitives::{ed25519::{Pair, Public}, Pair as PairT};
+use parking_lot::RwLock;
+use serde::{Serialize, Deserialize};
+use substrate_primitives::{
+	ed25519, sr25519, Pair as PairT,
+	crypto::{KeyTypeId, UncheckedFrom, Sign, key_types},
+};
+
+mod encryption;
 
 /// Keystore error.
 #[derive(Debug, derive_more::Display, derive_more::From)]
@@ -41,6 +54,15 @@ pub enum Error {
 	/// Invalid seed
 	#[display(fmt="Invalid seed")]
 	InvalidSeed,
+	/// Malformed key file.
+	#[display(fmt="Invalid key file")]
+	InvalidKeyFile,
+	/// Key of the given crypto scheme, type and public key is not in the store.
+	#[display(fmt="Key not found")]
+	KeyNotFound,
+	/// Keys can't be stored on disk without a password.
+	#[display(fmt="A password is required to store keys on disk")]
+	EmptyPassword,
 }
 
 /// Keystore Result
@@ -56,72 +78,230 @@ impl std::error::Error for Error {
 	}
 }
 
+/// Crypto scheme of a stored key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CryptoKind {
+	/// Ed25519 key.
+	Ed25519,
+	/// Sr25519 (Schnorrkel) key.
+	Sr25519,
+}
+
+/// A key pair that can be held by the store.
+pub trait StorePair: PairT {
+	/// Crypto scheme of the pair.
+	const CRYPTO: CryptoKind;
+
+	/// Raw bytes of a public key.
+	fn public_to_raw(public: &Self::Public) -> Vec<u8>;
+
+	/// Public key from its raw bytes, `None` if the length is invalid.
+	fn public_from_raw(data: &[u8]) -> Option<Self::Public>;
+
+	/// Signature from its raw bytes, `None` if the length is invalid.
+	fn signature_from_raw(data: &[u8]) -> Option<Self::Signature>;
+}
+
+impl StorePair for ed25519::Pair {
+	const CRYPTO: CryptoKind = CryptoKind::Ed25519;
+
+	fn public_to_raw(public: &ed25519::Public) -> Vec<u8> {
+		public.as_ref().to_vec()
+	}
+
+	fn public_from_raw(data: &[u8]) -> Option<ed25519::Public> {
+		raw_public(data).map(ed25519::Public::unchecked_from)
+	}
+
+	fn signature_from_raw(data: &[u8]) -> Option<ed25519::Signature> {
+		if data.len() == 64 { Some(ed25519::Signature::from_slice(data)) } else { None }
+	}
+}
+
+impl StorePair for sr25519::Pair {
+	const CRYPTO: CryptoKind = CryptoKind::Sr25519;
+
+	fn public_to_raw(public: &sr25519::Public) -> Vec<u8> {
+		public.as_ref().to_vec()
+	}
+
+	fn public_from_raw(data: &[u8]) -> Option<sr25519::Public> {
+		raw_public(data).map(sr25519::Public::unchecked_from)
+	}
+
+	fn signature_from_raw(data: &[u8]) -> Option<sr25519::Signature> {
+		if data.len() == 64 { Some(sr25519::Signature::from_slice(data)) } else { None }
+	}
+}
+
+fn raw_public(data: &[u8]) -> Option<[u8; 32]> {
+	if data.len() != 32 {
+		return None;
+	}
+	let mut raw = [0u8; 32];
+	raw.copy_from_slice(data);
+	Some(raw)
+}
+
+/// Recover the key pair from its secret, checking it matches the raw public key.
+fn pair_from_secret<P: StorePair>(secret: &str, raw_public: &[u8]) -> Result<P> {
+	let pair = P::from_string(secret, None).map_err(|_| Error::InvalidPhrase)?;
+	if P::public_to_raw(&pair.public()) != raw_public {
+		return Err(Error::InvalidPassword);
+	}
+	Ok(pair)
+}
+
+/// Content of a key file. Only the secret phrase (or URI) of the key is stored, encrypted.
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+	crypto: CryptoKind,
+	secret: encryption::Encrypted,
+}
+
+/// Identifier of a key in the store: its type and raw public key.
+type KeyId = (KeyTypeId, Vec<u8>);
+
+/// Key pair of any of the supported crypto schemes.
+enum AnyPair {
+	Ed25519(ed25519::Pair),
+	Sr25519(sr25519::Pair),
+}
+
+/// A key held in memory: its secret and the key pair recovered from it.
+struct Key {
+	secret: String,
+	pair: AnyPair,
+}
+
+impl Key {
+	/// Recover the key pair of the given crypto scheme from its secret, checking it matches the
+	/// raw public key.
+	fn new(crypto: CryptoKind, secret: String, raw_public: &[u8]) -> Result<Self> {
+		let pair = match crypto {
+			CryptoKind::Ed25519 => AnyPair::Ed25519(pair_from_secret(&secret, raw_public)?),
+			CryptoKind::Sr25519 => AnyPair::Sr25519(pair_from_secret(&secret, raw_public)?),
+		};
+		Ok(Key { secret, pair })
+	}
+
+	fn crypto(&self) -> CryptoKind {
+		match self.pair {
+			AnyPair::Ed25519(_) => CryptoKind::Ed25519,
+			AnyPair::Sr25519(_) => CryptoKind::Sr25519,
+		}
+	}
+}
+
 /// Key store.
+///
+/// Keys are either persisted on disk, encrypted with the password, or only held in memory, e.g.
+/// the development keys inserted from a seed.
 pub struct Store {
-	path: PathBuf,
-	additional: HashMap<Public, Pair>,
+	path: Option<PathBuf>,
+	password: String,
+	/// Keys only held in memory.
+	additional: RwLock<HashMap<KeyId, Arc<Key>>>,
+	/// Keys of the key files, decrypted on first use.
+	cache: RwLock<HashMap<KeyId, Arc<Key>>>,
 }
 
 impl Store {
-	/// Create a new store at the given path.
-	pub fn open(path: PathBuf) -> Result<Self> {
+	/// Create a new store at the given path, encrypting the keys with the given password.
+	///
+	/// The key files written by previous versions of the store, holding the plain phrase of an
+	/// ed25519 key, are encrypted and moved to the `ED25519` key type.
+	pub fn open(path: PathBuf, password: &str) -> Result<Self> {
+		if password.is_empty() {
+			return Err(Error::EmptyPassword);
+		}
+
 		fs::create_dir_all(&path)?;
-		Ok(Store { path, additional: HashMap::new() })
+		let store = Store {
+			path: Some(path),
+			password: password.into(),
+			additional: Default::default(),
+			cache: Default::default(),
+		};
+		store.migrate_legacy_keys()?;
+		Ok(store)
+	}
+
+	/// Create a new store keeping all keys in memory.
+	pub fn new_in_memory() -> Self {
+		Store {
+			path: None,
+			password: String::new(),
+			additional: Default::default(),
+			cache: Default::default(),
+		}
 	}
 
-	/// Generate a new key, placing it into the store.
-	pub fn generate(&self, password: &str) -> Result<Pair> {
-		let (pair, phrase) = Pair::generate_with_phrase(Some(password));
-		let mut file = File::create(self.key_file_path(&pair.public()))?;
-		::serde_json::to_writer(&file, &phrase)?;
-		file.flush()?;
+	/// Generate a new key of the given type, placing it into the store.
+	pub fn generate_by_type<P: StorePair>(&self, key_type: KeyTypeId) -> Result<P> {
+		let (pair, phrase) = P::generate_with_phrase(None);
+		self.insert_secret::<P>(key_type, &pair.public(), phrase)?;
 		Ok(pair)
 	}
 
-	/// Create a new key from seed. Do not place it into the store.
-	pub fn generate_from_seed(&mut self, seed: &str) -> Result<Pair> {
-		let pair = Pair::from_string(seed, None)
-			.ok().ok_or(Error::InvalidSeed)?;
-		self.additional.insert(pair.public(), pair.clone());
+	/// Insert a key of the given type from its secret URI (e.g. a phrase), placing it into the
+	/// store.
+	pub fn insert_by_type<P: StorePair>(&self, key_type: KeyTypeId, suri: &str) -> Result<P> {
+		let pair = P::from_string(suri, None).map_err(|_| Error::InvalidSeed)?;
+		self.insert_secret::<P>(key_type, &pair.public(), suri.into())?;
 		Ok(pair)
 	}
 
-	/// Load a key file with given public key.
-	pub fn load(&self, public: &Public, password: &str) -> Result<Pair> {
-		if let Some(pair) = self.additional.get(public) {
-			return Ok(pair.clone());
-		}
-		let path = self.key_file_path(public);
-		let file = File::open(path)?;
+	/// Create a new key of the given type from seed. Do not place it into the store, it's only
+	/// held in memory.
+	pub fn generate_from_seed_by_type<P: StorePair>(&self, key_type: KeyTypeId, seed: &str) -> Result<P> {
+		let pair = P::from_string(seed, None).map_err(|_| Error::InvalidSeed)?;
+		let raw_public = P::public_to_raw(&pair.public());
+		let key = Key::new(P::CRYPTO, seed.into(), &raw_public)?;
+		self.additional.write().insert((key_type, raw_public), Arc::new(key));
+		Ok(pair)
+	}
 
-		let phrase: String = ::serde_json::from_reader(&file)?;
-		let pair = Pair::from_phrase(&phrase, Some(password))
-			.ok().ok_or(Error::InvalidPhrase)?;
-		if &pair.public() != public {
-			return Err(Error::InvalidPassword);
+	/// Load the key of the given type with given public key.
+	pub fn key_pair_by_type<P: StorePair>(&self, public: &P::Public, key_type: KeyTypeId) -> Result<P> {
+		let raw_public = P::public_to_raw(public);
+		let key = self.key(key_type, &raw_public)?;
+		if key.crypto() != P::CRYPTO {
+			return Err(Error::KeyNotFound);
 		}
-		Ok(pair)
+		pair_from_secret(&key.secret, &raw_public)
 	}
 
-	/// Get public keys of all stored keys.
-	pub fn contents(&self) -> Result<Vec<Public>> {
-		let mut public_keys: Vec<Public> = self.additional.keys().cloned().collect();
-		for entry in fs::read_dir(&self.path)? {
-			let entry = entry?;
-			let path = entry.path();
+	/// Get the public keys of all stored keys of the given type and crypto scheme.
+	pub fn public_keys_by_type<P: StorePair>(&self, key_type: KeyTypeId) -> Result<Vec<P::Public>> {
+		let mut public_keys: Vec<P::Public> = self.additional.read().iter()
+			.filter(|((ty, _), key)| *ty == key_type && key.crypto() == P::CRYPTO)
+			.filter_map(|((_, public), _)| P::public_from_raw(public))
+			.collect();
+
+		let path = match self.path {
+			Some(ref path) => path,
+			None => return Ok(public_keys),
+		};
+		let prefix = hex::encode(&key_type[..]);
+		for entry in fs::read_dir(path)? {
+			let path = entry?.path();
 
 			// skip directories and non-unicode file names (hex is unicode)
 			if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-				if name.len() != 64 { continue }
-
-				match hex::decode(name) {
-					Ok(ref hex) if hex.len() == 32 => {
-						let mut buf = [0; 32];
-						buf.copy_from_slice(&hex[..]);
+				if !name.starts_with(&prefix) { continue }
 
-						public_keys.push(Public(buf));
-					}
+				let public = match hex::decode(&name[prefix.len()..]).ok().and_then(|raw| P::public_from_raw(&raw)) {
+					Some(public) => public,
+					None => continue,
+				};
+				let key_file: KeyFile = match File::open(&path).map(|file| serde_json::from_reader(&file)) {
+					Ok(Ok(key_file)) => key_file,
 					_ => continue,
+				};
+				if key_file.crypto == P::CRYPTO {
+					public_keys.push(public);
 				}
 			}
 		}
@@ -129,10 +309,153 @@ impl Store {
 		Ok(public_keys)
 	}
 
-	fn key_file_path(&self, public: &Public) -> PathBuf {
-		let mut buf = self.path.clone();
-		buf.push(hex::encode(public.as_slice()));
-		buf
+	/// Sign the message with the key of the given type and raw public key, without handing out
+	/// the key pair. Returns the raw signature.
+	pub fn sign_with(&self, key_type: KeyTypeId, public: &[u8], message: &[u8]) -> Result<Vec<u8>> {
+		Ok(match self.key(key_type, public)?.pair {
+			AnyPair::Ed25519(ref pair) => pair.sign(message).0.to_vec(),
+			AnyPair::Sr25519(ref pair) => pair.sign(message).0.to_vec(),
+		})
+	}
+
+	/// Sign the message with the key of the given type and public key, without handing out the
+	/// key pair.
+	pub fn sign_by_type<P: StorePair>(&self, key_type: KeyTypeId, public: &P::Public, message: &[u8]) -> Result<P::Signature> {
+		let raw_public = P::public_to_raw(public);
+		if self.key(key_type, &raw_public)?.crypto() != P::CRYPTO {
+			return Err(Error::KeyNotFound);
+		}
+		let signature = self.sign_with(key_type, &raw_public, message)?;
+		Ok(P::signature_from_raw(&signature).expect("signatures of the key crypto scheme are returned; qed"))
+	}
+
+	/// Encrypt the data with the key of the given type and raw public key. Only the holder of the
+	/// key can decrypt it.
+	pub fn encrypt_with(&self, key_type: KeyTypeId, public: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+		let key = self.key(key_type, public)?;
+		Ok(serde_json::to_vec(&encryption::encrypt(&key.secret, data))?)
+	}
+
+	/// Decrypt data encrypted by `encrypt_with` with the same key.
+	pub fn decrypt_with(&self, key_type: KeyTypeId, public: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+		let key = self.key(key_type, public)?;
+		let encrypted = serde_json::from_slice(data)?;
+		encryption::decrypt(&key.secret, &encrypted)
+	}
+
+	/// Returns true if the store holds a key of the given type with given raw public key.
+	pub fn has_key(&self, key_type: KeyTypeId, public: &[u8]) -> bool {
+		self.key(key_type, public).is_ok()
+	}
+
+	/// Persist the secret of a key, or keep it in memory for in-memory stores.
+	fn insert_secret<P: StorePair>(&self, key_type: KeyTypeId, public: &P::Public, secret: String) -> Result<()> {
+		let raw_public = P::public_to_raw(public);
+		match self.key_file_path(key_type, &raw_public) {
+			Some(path) => {
+				let key_file = KeyFile {
+					crypto: P::CRYPTO,
+					secret: encryption::encrypt(&self.password, secret.as_bytes()),
+				};
+				let mut file = File::create(path)?;
+				serde_json::to_writer(&file, &key_file)?;
+				file.flush()?;
+			},
+			None => {
+				let key = Key::new(P::CRYPTO, secret, &raw_public)?;
+				self.additional.write().insert((key_type, raw_public), Arc::new(key));
+			},
+		}
+		Ok(())
+	}
+
+	/// Get the key with given type and raw public key, decrypting its key file on first use.
+	fn key(&self, key_type: KeyTypeId, public: &[u8]) -> Result<Arc<Key>> {
+		let id = (key_type, public.to_vec());
+		if let Some(key) = self.additional.read().get(&id) {
+			return Ok(key.clone());
+		}
+		if let Some(key) = self.cache.read().get(&id) {
+			return Ok(key.clone());
+		}
+
+		let path = self.key_file_path(key_type, public).ok_or(Error::KeyNotFound)?;
+		let file = match File::open(path) {
+			Ok(file) => file,
+			Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Err(Error::KeyNotFound),
+			Err(e) => return Err(e.into()),
+		};
+		let key_file: KeyFile = serde_json::from_reader(&file)?;
+		let secret = encryption::decrypt(&self.password, &key_file.secret)?;
+		let secret = String::from_utf8(secret).map_err(|_| Error::InvalidPhrase)?;
+		let key = Arc::new(Key::new(key_file.crypto, secret, public)?);
+		self.cache.write().insert(id, key.clone());
+		Ok(key)
+	}
+
+	/// Encrypt the key files written by previous versions of the store.
+	///
+	/// They are named after the hex encoded ed25519 public key and hold the plain phrase of the
+	/// key, the store password being the BIP39 password of the phrase. Keys generated without
+	/// password are recovered as well. Any legacy key file that can't be recovered is an error.
+	fn migrate_legacy_keys(&self) -> Result<()> {
+		let path = match self.path {
+			Some(ref path) => path,
+			None => return Ok(()),
+		};
+
+		for entry in fs::read_dir(path)? {
+			let path = entry?.path();
+			let public = match path.file_name().and_then(|n| n.to_str()) {
+				Some(name) if name.len() == 64 => match hex::decode(name) {
+					Ok(raw) => ed25519::Public::unchecked_from(raw_public(&raw).expect("64 hex chars are 32 bytes; qed")),
+					Err(_) => continue,
+				},
+				_ => continue,
+			};
+
+			let phrase: String = serde_json::from_reader(File::open(&path)?).map_err(|_| Error::InvalidKeyFile)?;
+			let secret = [format!("{}///{}", phrase, self.password), phrase.clone()].iter()
+				.find(|secret| pair_from_secret::<ed25519::Pair>(secret, public.as_ref()).is_ok())
+				.cloned()
+				.ok_or(Error::InvalidPassword)?;
+			self.insert_secret::<ed25519::Pair>(key_types::ED25519, &public, secret)?;
+			fs::remove_file(&path)?;
+		}
+		Ok(())
+	}
+
+	fn key_file_path(&self, key_type: KeyTypeId, public: &[u8]) -> Option<PathBuf> {
+		self.path.as_ref().map(|path| {
+			let mut buf = path.clone();
+			buf.push(hex::encode(&key_type[..]) + &hex::encode(public));
+			buf
+		})
+	}
+}
+
+/// A key of the store, signing on behalf of its holder without exposing the key pair.
+pub struct LocalKey<P: StorePair> {
+	store: Arc<Store>,
+	key_type: KeyTypeId,
+	public: P::Public,
+	_marker: PhantomData<fn() -> P>,
+}
+
+impl<P: StorePair> LocalKey<P> {
+	/// The key of the given store with given type and public key.
+	pub fn new(store: Arc<Store>, key_type: KeyTypeId, public: P::Public) -> Self {
+		LocalKey { store, key_type, public, _marker: PhantomData }
+	}
+}
+
+impl<P: StorePair> Sign<P> for LocalKey<P> where P::Public: Clone + Send + Sync {
+	fn signing_key(&self) -> P::Public {
+		self.public.clone()
+	}
+
+	fn sign_message(&self, message: &[u8]) -> Option<P::Signature> {
+		self.store.sign_by_type::<P>(self.key_type, &self.public, message).ok()
 	}
 }
 
@@ -140,31 +463,165 @@ impl Store {
 mod tests {
 	use super::*;
 	use tempdir::TempDir;
-	use substrate_primitives::crypto::Ss58Codec;
+	use substrate_primitives::crypto::{Ss58Codec, key_types};
 
 	#[test]
 	fn basic_store() {
 		let temp_dir = TempDir::new("keystore").unwrap();
-		let store = Store::open(temp_dir.path().to_owned()).unwrap();
-
-		assert!(store.contents().unwrap().is_empty());
+		let store = Store::open(temp_dir.path().to_owned(), "thepassword").unwrap();
 
-		let key = store.generate("thepassword").unwrap();
-		let key2 = store.load(&key.public(), "thepassword").unwrap();
+		assert!(store.public_keys_by_type::<ed25519::Pair>(key_types::ED25519).unwrap().is_empty());
 
-		assert!(store.load(&key.public(), "notthepassword").is_err());
+		let key: ed25519::Pair = store.generate_by_type(key_types::ED25519).unwrap();
+		let key2 = store.key_pair_by_type::<ed25519::Pair>(&key.public(), key_types::ED25519).unwrap();
 
 		assert_eq!(key.public(), key2.public());
 
-		assert_eq!(store.contents().unwrap()[0], key.public());
+		assert_eq!(store.public_keys_by_type::<ed25519::Pair>(key_types::ED25519).unwrap()[0], key.public());
+		assert!(store.public_keys_by_type::<ed25519::Pair>(key_types::GRANDPA).unwrap().is_empty());
+		assert!(store.public_keys_by_type::<sr25519::Pair>(key_types::ED25519).unwrap().is_empty());
+	}
+
+	#[test]
+	fn keys_are_encrypted_with_the_password() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let store = Store::open(temp_dir.path().to_owned(), "thepassword").unwrap();
+		let phrase = "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+		let key: sr25519::Pair = store.insert_by_type(key_types::BABE, phrase).unwrap();
+
+		for entry in fs::read_dir(temp_dir.path()).unwrap() {
+			let content = fs::read_to_string(entry.unwrap().path()).unwrap();
+			assert!(!content.contains("bottom"));
+		}
+
+		let store = Store::open(temp_dir.path().to_owned(), "notthepassword").unwrap();
+		match store.key_pair_by_type::<sr25519::Pair>(&key.public(), key_types::BABE) {
+			Err(Error::InvalidPassword) => {},
+			_ => panic!("key must not be readable with another password"),
+		}
+	}
+
+	#[test]
+	fn in_memory_store() {
+		let store = Store::new_in_memory();
+
+		let ed_key: ed25519::Pair = store.generate_by_type(key_types::GRANDPA).unwrap();
+		let sr_key: sr25519::Pair = store.generate_by_type(key_types::BABE).unwrap();
+
+		assert_eq!(store.public_keys_by_type::<ed25519::Pair>(key_types::GRANDPA).unwrap(), vec![ed_key.public()]);
+		assert_eq!(store.public_keys_by_type::<sr25519::Pair>(key_types::BABE).unwrap(), vec![sr_key.public()]);
+		assert!(store.has_key(key_types::BABE, sr_key.public().as_ref()));
+		assert!(!store.has_key(key_types::GRANDPA, sr_key.public().as_ref()));
+	}
+
+	#[test]
+	fn sign_with_public_key() {
+		let store = Store::new_in_memory();
+		let ed_key: ed25519::Pair = store.generate_by_type(key_types::GRANDPA).unwrap();
+		let sr_key: sr25519::Pair = store.generate_by_type(key_types::BABE).unwrap();
+
+		let signature = store.sign_with(key_types::GRANDPA, ed_key.public().as_ref(), b"message").unwrap();
+		assert!(ed25519::Pair::verify_weak(&signature, b"message", ed_key.public()));
+
+		let signature = store.sign_with(key_types::BABE, sr_key.public().as_ref(), b"message").unwrap();
+		assert!(sr25519::Pair::verify_weak(&signature, b"message", sr_key.public()));
+
+		match store.sign_with(key_types::BABE, ed_key.public().as_ref(), b"message") {
+			Err(Error::KeyNotFound) => {},
+			_ => panic!("key of another type must not be used"),
+		}
+	}
+
+	#[test]
+	fn encrypt_with_public_key() {
+		let store = Store::new_in_memory();
+		let key: sr25519::Pair = store.generate_by_type(key_types::OFFCHAIN).unwrap();
+		let other: sr25519::Pair = store.generate_by_type(key_types::OFFCHAIN).unwrap();
+
+		let encrypted = store.encrypt_with(key_types::OFFCHAIN, key.public().as_ref(), b"message").unwrap();
+		assert_eq!(store.decrypt_with(key_types::OFFCHAIN, key.public().as_ref(), &encrypted).unwrap(), b"message");
+		match store.decrypt_with(key_types::OFFCHAIN, other.public().as_ref(), &encrypted) {
+			Err(Error::InvalidPassword) => {},
+			_ => panic!("data must not be decrypted with another key"),
+		}
 	}
 
 	#[test]
 	fn test_generate_from_seed() {
 		let temp_dir = TempDir::new("keystore").unwrap();
-		let mut store = Store::open(temp_dir.path().to_owned()).unwrap();
+		let store = Store::open(temp_dir.path().to_owned(), "thepassword").unwrap();
 
-		let pair = store.generate_from_seed("0x3d97c819d68f9bafa7d6e79cb991eebcd77d966c5334c0b94d9e1fa7ad0869dc").unwrap();
+		let pair: ed25519::Pair = store.generate_from_seed_by_type(
+			key_types::ED25519,
+			"0x3d97c819d68f9bafa7d6e79cb991eebcd77d966c5334c0b94d9e1fa7ad0869dc",
+		).unwrap();
 		assert_eq!("5DKUrgFqCPV8iAXx9sjy1nyBygQCeiUYRFWurZGhnrn3HJCA", pair.public().to_ss58check());
+		assert_eq!(store.public_keys_by_type::<ed25519::Pair>(key_types::ED25519).unwrap(), vec![pair.public()]);
+		assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+	}
+
+	#[test]
+	fn store_requires_password() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		match Store::open(temp_dir.path().to_owned(), "") {
+			Err(Error::EmptyPassword) => {},
+			_ => panic!("keys must not be stored without password"),
+		}
+	}
+
+	#[test]
+	fn sign_by_type_with_local_key() {
+		let store = Arc::new(Store::new_in_memory());
+		let pair: ed25519::Pair = store.generate_by_type(key_types::GRANDPA).unwrap();
+
+		let key = LocalKey::<ed25519::Pair>::new(store.clone(), key_types::GRANDPA, pair.public());
+		assert_eq!(key.signing_key(), pair.public());
+		let signature = key.sign_message(b"message").unwrap();
+		assert!(ed25519::Pair::verify(&signature, b"message", &pair.public()));
+
+		let key = LocalKey::<sr25519::Pair>::new(store, key_types::GRANDPA, Default::default());
+		assert!(key.sign_message(b"message").is_none());
+	}
+
+	#[test]
+	fn legacy_key_files_are_migrated() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let phrase = "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+		let with_password = ed25519::Pair::from_phrase(phrase, Some("thepassword")).unwrap();
+		let without_password = ed25519::Pair::from_phrase(phrase, None).unwrap();
+		for pair in &[&with_password, &without_password] {
+			let path = temp_dir.path().join(hex::encode(pair.public().as_ref()));
+			fs::write(path, serde_json::to_string(phrase).unwrap()).unwrap();
+		}
+
+		let store = Store::open(temp_dir.path().to_owned(), "thepassword").unwrap();
+		let mut migrated = store.public_keys_by_type::<ed25519::Pair>(key_types::ED25519).unwrap();
+		migrated.sort();
+		let mut expected = vec![with_password.public(), without_password.public()];
+		expected.sort();
+		assert_eq!(migrated, expected);
+		for entry in fs::read_dir(temp_dir.path()).unwrap() {
+			let content = fs::read_to_string(entry.unwrap().path()).unwrap();
+			assert!(!content.contains("bottom"));
+		}
+
+		let store = Store::open(temp_dir.path().to_owned(), "thepassword").unwrap();
+		let key = store.key_pair_by_type::<ed25519::Pair>(&with_password.public(), key_types::ED25519).unwrap();
+		assert_eq!(key.public(), with_password.public());
+	}
+
+	#[test]
+	fn legacy_key_files_with_another_password_are_rejected() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let phrase = "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+		let pair = ed25519::Pair::from_phrase(phrase, Some("otherpassword")).unwrap();
+		let path = temp_dir.path().join(hex::encode(pair.public().as_ref()));
+		fs::write(&path, serde_json::to_string(phrase).unwrap()).unwrap();
+
+		match Store::open(temp_dir.path().to_owned(), "thepassword") {
+			Err(Error::InvalidPassword) => {},
+			_ => panic!("legacy keys must not be dropped"),
+		}
+		assert!(path.exists());
 	}
 }
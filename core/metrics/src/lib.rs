@@ -0,0 +1,392 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prometheus-compatible metrics.
+//!
+//! Metrics are registered in a global registry with the `register_*` functions and may be
+//! updated anywhere in the Substrate codebase. Registering a metric under a name that is
+//! already taken returns the existing metric, so components that are instantiated more than
+//! once share their metrics.
+//!
+//! The registry is exposed in the Prometheus text format on the `/metrics` path of the HTTP
+//! server started with `init_prometheus`.
+
+#![warn(missing_docs)]
+
+mod server;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use lazy_static::lazy_static;
+use log::warn;
+use parking_lot::Mutex;
+
+pub use server::{init_prometheus, Error};
+
+/// Default buckets of histograms measuring durations, in seconds.
+pub const DEFAULT_TIME_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+lazy_static! {
+	static ref REGISTRY: Registry = Registry::default();
+}
+
+/// A value that only goes up.
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+	/// Increment the counter by one.
+	pub fn inc(&self) {
+		self.inc_by(1);
+	}
+
+	/// Increment the counter by the given amount.
+	pub fn inc_by(&self, value: u64) {
+		self.0.fetch_add(value, Ordering::Relaxed);
+	}
+
+	/// Current value of the counter.
+	pub fn get(&self) -> u64 {
+		self.0.load(Ordering::Relaxed)
+	}
+}
+
+/// A value that can go up and down.
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicU64);
+
+impl Gauge {
+	/// Set the gauge to the given value.
+	pub fn set(&self, value: u64) {
+		self.0.store(value, Ordering::Relaxed);
+	}
+
+	/// Current value of the gauge.
+	pub fn get(&self) -> u64 {
+		self.0.load(Ordering::Relaxed)
+	}
+}
+
+/// Counters partitioned by the value of a single label.
+#[derive(Debug)]
+pub struct CounterVec {
+	label: &'static str,
+	counters: Mutex<BTreeMap<String, u64>>,
+}
+
+impl CounterVec {
+	fn new(label: &'static str) -> Self {
+		CounterVec {
+			label,
+			counters: Mutex::new(BTreeMap::new()),
+		}
+	}
+
+	/// Increment the counter with the given label value by one.
+	pub fn inc(&self, label_value: &str) {
+		let mut counters = self.counters.lock();
+		match counters.get_mut(label_value) {
+			Some(counter) => *counter += 1,
+			None => { counters.insert(label_value.to_owned(), 1); },
+		}
+	}
+
+	/// Current value of the counter with the given label value.
+	pub fn get(&self, label_value: &str) -> u64 {
+		self.counters.lock().get(label_value).cloned().unwrap_or(0)
+	}
+}
+
+/// Distribution of observed values over a set of buckets.
+#[derive(Debug)]
+pub struct Histogram {
+	/// Upper bounds of the buckets, in ascending order.
+	bounds: Vec<f64>,
+	data: Mutex<HistogramData>,
+}
+
+#[derive(Debug)]
+struct HistogramData {
+	/// Number of observations per bucket, not cumulative.
+	buckets: Vec<u64>,
+	sum: f64,
+	count: u64,
+}
+
+impl Histogram {
+	fn new(bounds: &[f64]) -> Self {
+		Histogram {
+			bounds: bounds.to_vec(),
+			data: Mutex::new(HistogramData {
+				buckets: vec![0; bounds.len()],
+				sum: 0.0,
+				count: 0,
+			}),
+		}
+	}
+
+	/// Record an observed value.
+	pub fn observe(&self, value: f64) {
+		let mut data = self.data.lock();
+		if let Some(index) = self.bounds.iter().position(|bound| value <= *bound) {
+			data.buckets[index] += 1;
+		}
+		data.sum += value;
+		data.count += 1;
+	}
+
+	/// Record an observed duration, in seconds.
+	pub fn observe_duration(&self, duration: Duration) {
+		self.observe(duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1_000_000_000.0);
+	}
+
+	/// Record the time elapsed since the given instant, in seconds.
+	pub fn observe_since(&self, start: Instant) {
+		self.observe_duration(start.elapsed());
+	}
+
+	/// Number of observed values.
+	pub fn count(&self) -> u64 {
+		self.data.lock().count
+	}
+}
+
+#[derive(Debug, Clone)]
+enum Metric {
+	Counter(Arc<Counter>),
+	Gauge(Arc<Gauge>),
+	CounterVec(Arc<CounterVec>),
+	Histogram(Arc<Histogram>),
+}
+
+impl Metric {
+	fn kind(&self) -> &'static str {
+		match self {
+			Metric::Counter(_) | Metric::CounterVec(_) => "counter",
+			Metric::Gauge(_) => "gauge",
+			Metric::Histogram(_) => "histogram",
+		}
+	}
+
+	fn encode(&self, name: &str, out: &mut String) {
+		// writing to a `String` never fails.
+		match self {
+			Metric::Counter(counter) => { let _ = writeln!(out, "{} {}", name, counter.get()); },
+			Metric::Gauge(gauge) => { let _ = writeln!(out, "{} {}", name, gauge.get()); },
+			Metric::CounterVec(counters) => for (value, count) in counters.counters.lock().iter() {
+				let _ = writeln!(out, "{}{{{}=\"{}\"}} {}", name, counters.label, escape_label_value(value), count);
+			},
+			Metric::Histogram(histogram) => {
+				let data = histogram.data.lock();
+				let mut cumulative = 0;
+				for (bound, count) in histogram.bounds.iter().zip(data.buckets.iter()) {
+					cumulative += count;
+					let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative);
+				}
+				let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, data.count);
+				let _ = writeln!(out, "{}_sum {}", name, data.sum);
+				let _ = writeln!(out, "{}_count {}", name, data.count);
+			},
+		}
+	}
+}
+
+fn escape_label_value(value: &str) -> String {
+	value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+struct Entry {
+	name: &'static str,
+	help: &'static str,
+	metric: Metric,
+}
+
+/// A set of named metrics.
+#[derive(Default)]
+pub struct Registry {
+	entries: Mutex<Vec<Entry>>,
+}
+
+impl Registry {
+	// Register the metric built by `make` unless a metric with the same name exists, in which
+	// case the existing one is returned if it has the expected type.
+	fn register<T>(
+		&self,
+		name: &'static str,
+		help: &'static str,
+		make: impl FnOnce() -> Metric,
+		extract: impl Fn(&Metric) -> Option<Arc<T>>,
+	) -> Arc<T> {
+		let mut entries = self.entries.lock();
+		if let Some(entry) = entries.iter().find(|entry| entry.name == name) {
+			if let Some(metric) = extract(&entry.metric) {
+				return metric;
+			}
+
+			// the new metric is still usable, it just isn't exported.
+			warn!(target: "metrics", "Metric {} is already registered as a {}", name, entry.metric.kind());
+			return extract(&make()).expect("`make` builds the metric `extract` expects; qed");
+		}
+
+		let metric = make();
+		let result = extract(&metric).expect("`make` builds the metric `extract` expects; qed");
+		entries.push(Entry { name, help, metric });
+		result
+	}
+
+	/// Register a counter.
+	pub fn register_counter(&self, name: &'static str, help: &'static str) -> Arc<Counter> {
+		self.register(
+			name,
+			help,
+			|| Metric::Counter(Default::default()),
+			|metric| match metric { Metric::Counter(counter) => Some(counter.clone()), _ => None },
+		)
+	}
+
+	/// Register a gauge.
+	pub fn register_gauge(&self, name: &'static str, help: &'static str) -> Arc<Gauge> {
+		self.register(
+			name,
+			help,
+			|| Metric::Gauge(Default::default()),
+			|metric| match metric { Metric::Gauge(gauge) => Some(gauge.clone()), _ => None },
+		)
+	}
+
+	/// Register counters partitioned by the value of the given label.
+	pub fn register_counter_vec(
+		&self,
+		name: &'static str,
+		help: &'static str,
+		label: &'static str,
+	) -> Arc<CounterVec> {
+		self.register(
+			name,
+			help,
+			|| Metric::CounterVec(Arc::new(CounterVec::new(label))),
+			|metric| match metric { Metric::CounterVec(counters) => Some(counters.clone()), _ => None },
+		)
+	}
+
+	/// Register a histogram with the given bucket upper bounds, in ascending order.
+	pub fn register_histogram(
+		&self,
+		name: &'static str,
+		help: &'static str,
+		buckets: &[f64],
+	) -> Arc<Histogram> {
+		self.register(
+			name,
+			help,
+			|| Metric::Histogram(Arc::new(Histogram::new(buckets))),
+			|metric| match metric { Metric::Histogram(histogram) => Some(histogram.clone()), _ => None },
+		)
+	}
+
+	/// Encode all registered metrics in the Prometheus text format.
+	pub fn encode(&self) -> String {
+		let mut out = String::new();
+		for entry in self.entries.lock().iter() {
+			let _ = writeln!(out, "# HELP {} {}", entry.name, entry.help);
+			let _ = writeln!(out, "# TYPE {} {}", entry.name, entry.metric.kind());
+			entry.metric.encode(entry.name, &mut out);
+		}
+		out
+	}
+}
+
+/// Register a counter in the global registry.
+pub fn register_counter(name: &'static str, help: &'static str) -> Arc<Counter> {
+	REGISTRY.register_counter(name, help)
+}
+
+/// Register a gauge in the global registry.
+pub fn register_gauge(name: &'static str, help: &'static str) -> Arc<Gauge> {
+	REGISTRY.register_gauge(name, help)
+}
+
+/// Register counters partitioned by the value of the given label in the global registry.
+pub fn register_counter_vec(name: &'static str, help: &'static str, label: &'static str) -> Arc<CounterVec> {
+	REGISTRY.register_counter_vec(name, help, label)
+}
+
+/// Register a histogram in the global registry.
+pub fn register_histogram(name: &'static str, help: &'static str, buckets: &[f64]) -> Arc<Histogram> {
+	REGISTRY.register_histogram(name, help, buckets)
+}
+
+/// Encode all metrics of the global registry in the Prometheus text format.
+pub fn encode() -> String {
+	REGISTRY.encode()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn metrics_are_encoded() {
+		let registry = Registry::default();
+		registry.register_counter("test_counter", "A counter").inc_by(3);
+		registry.register_gauge("test_gauge", "A gauge").set(42);
+		let calls = registry.register_counter_vec("test_calls", "Calls", "method");
+		calls.inc("b");
+		calls.inc("a\"");
+		calls.inc("b");
+		let histogram = registry.register_histogram("test_histogram", "A histogram", &[0.5, 1.0]);
+		histogram.observe(0.25);
+		histogram.observe(0.75);
+		histogram.observe(2.0);
+
+		assert_eq!(registry.encode(), "\
+# HELP test_counter A counter
+# TYPE test_counter counter
+test_counter 3
+# HELP test_gauge A gauge
+# TYPE test_gauge gauge
+test_gauge 42
+# HELP test_calls Calls
+# TYPE test_calls counter
+test_calls{method=\"a\\\"\"} 1
+test_calls{method=\"b\"} 2
+# HELP test_histogram A histogram
+# TYPE test_histogram histogram
+test_histogram_bucket{le=\"0.5\"} 1
+test_histogram_bucket{le=\"1\"} 2
+test_histogram_bucket{le=\"+Inf\"} 3
+test_histogram_sum 3
+test_histogram_count 3
+");
+	}
+
+	#[test]
+	fn registering_twice_returns_existing_metric() {
+		let registry = Registry::default();
+		registry.register_counter("test_counter", "A counter").inc();
+		let counter = registry.register_counter("test_counter", "A counter");
+		assert_eq!(counter.get(), 1);
+
+		// a metric of a different type isn't exported.
+		let gauge = registry.register_gauge("test_counter", "A gauge");
+		gauge.set(5);
+		assert_eq!(registry.encode().matches("# TYPE").count(), 1);
+		assert!(registry.encode().contains("test_counter 1"));
+	}
+}
@@ -0,0 +1,54 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! HTTP server exposing the global registry.
+
+use std::net::SocketAddr;
+use futures::Future;
+use hyper::{Body, Method, Request, Response, Server, StatusCode, header::CONTENT_TYPE};
+use hyper::service::service_fn_ok;
+use log::{info, warn};
+
+/// Content type of the Prometheus text format.
+const TEXT_FORMAT: &str = "text/plain; version=0.0.4";
+
+/// Metrics server error.
+pub type Error = hyper::Error;
+
+fn handle(request: Request<Body>) -> Response<Body> {
+	if request.method() != Method::GET || request.uri().path() != "/metrics" {
+		return Response::builder()
+			.status(StatusCode::NOT_FOUND)
+			.body(Body::from("Not found."))
+			.expect("response with a valid status code is always valid; qed");
+	}
+
+	Response::builder()
+		.status(StatusCode::OK)
+		.header(CONTENT_TYPE, TEXT_FORMAT)
+		.body(Body::from(crate::encode()))
+		.expect("response with a valid status code and header is always valid; qed")
+}
+
+/// Bind the metrics server to the given address. The returned future serves `/metrics`
+/// requests and must be spawned on a tokio runtime.
+pub fn init_prometheus(address: SocketAddr) -> Result<impl Future<Item=(), Error=()>, Error> {
+	let server = Server::try_bind(&address)?
+		.serve(|| service_fn_ok(handle));
+	info!("Prometheus metrics server started at {}", server.local_addr());
+
+	Ok(server.map_err(|e| warn!("Prometheus metrics server error: {:?}", e)))
+}
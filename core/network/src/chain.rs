@@ -16,13 +16,13 @@
 
 //! Blockchain access trait
 
-use client::{self, Client as SubstrateClient, ClientInfo, BlockStatus, CallExecutor};
+use client::{self, Client as SubstrateClient, ClientInfo, BlockStatus, CallExecutor, StorageRange};
 use client::error::Error;
 use client::light::fetcher::ChangesProof;
 use consensus::{BlockImport, Error as ConsensusError};
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
 use runtime_primitives::generic::{BlockId};
-use runtime_primitives::Justification;
+use runtime_primitives::{Justification, StorageOverlay, ChildrenStorageOverlay};
 use primitives::{H256, Blake2Hasher, storage::StorageKey};
 
 /// Local client abstraction for the network.
@@ -66,6 +66,37 @@ pub trait Client<Block: BlockT>: Send + Sync {
 
 	/// Returns `true` if the given `block` is a descendent of `base`.
 	fn is_descendent_of(&self, base: &Block::Hash, block: &Block::Hash) -> Result<bool, Error>;
+
+	/// Get a storage range of the given block: at most `max_entries` top-level and child trie
+	/// entries, starting after `start` (and `child_start` in the child trie at `start`, if given),
+	/// and the proof of the range.
+	fn read_range_proof(
+		&self,
+		block: &Block::Hash,
+		start: &[u8],
+		child_start: Option<&[u8]>,
+		max_entries: usize,
+	) -> Result<(StorageRange, Vec<Vec<u8>>), Error>;
+
+	/// Check a storage range proof, generated by `read_range_proof`, against the given state root.
+	/// Returns the range read from the proof.
+	fn check_read_range_proof(
+		&self,
+		state_root: Block::Hash,
+		proof: Vec<Vec<u8>>,
+		start: &[u8],
+		child_start: Option<&[u8]>,
+		max_entries: usize,
+	) -> Result<StorageRange, Error>;
+
+	/// Import a finalized block together with its complete state, without importing its ancestors.
+	fn import_state(
+		&self,
+		header: Block::Header,
+		justification: Option<Justification>,
+		top: StorageOverlay,
+		children: ChildrenStorageOverlay,
+	) -> Result<(), Error>;
 }
 
 /// Finality proof provider.
@@ -74,6 +105,42 @@ pub trait FinalityProofProvider<Block: BlockT>: Send + Sync {
 	fn prove_finality(&self, for_block: Block::Hash, request: &[u8]) -> Result<Option<Vec<u8>>, Error>;
 }
 
+/// Result of verifying a warp sync proof.
+#[derive(Debug)]
+pub enum WarpSyncProgress<Block: BlockT> {
+	/// The proof is valid, but more proofs are required. Contains the last finalized block
+	/// proven so far.
+	Partial(Block::Header, Justification),
+	/// The proof is valid and reaches the latest finalized block known to the prover.
+	Complete(Block::Header, Justification),
+}
+
+/// Warp sync proof provider and verifier.
+///
+/// A warp sync proof allows a node to jump from a block finalized by a known set of authorities
+/// to a recent finalized block, without importing the blocks in between.
+pub trait WarpSyncProvider<Block: BlockT>: Send + Sync {
+	/// Generate a proof starting at the given block. Returns `None` if there's nothing newer to
+	/// prove.
+	fn generate(&self, begin: Block::Hash) -> Result<Option<Vec<u8>>, Error>;
+
+	/// Encoded authority set that finalizes the genesis block.
+	fn initial_authorities(&self) -> Result<Vec<u8>, Error>;
+
+	/// Verify a proof against the given encoded authority set. Returns the encoded authority
+	/// set that finalizes the last proven block.
+	fn verify(&self, proof: &[u8], authorities: Vec<u8>) -> Result<(Vec<u8>, WarpSyncProgress<Block>), Error>;
+
+	/// Verify that the justification finalizes the given header, against the given encoded
+	/// authority set.
+	fn verify_justification(
+		&self,
+		header: &Block::Header,
+		justification: &Justification,
+		authorities: &[u8],
+	) -> Result<(), Error>;
+}
+
 impl<B, E, Block, RA> Client<Block> for SubstrateClient<B, E, Block, RA> where
 	B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
 	E: CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
@@ -142,4 +209,37 @@ impl<B, E, Block, RA> Client<Block> for SubstrateClient<B, E, Block, RA> where
 
 		Ok(tree_route.common_block().hash == *base)
 	}
+
+	fn read_range_proof(
+		&self,
+		block: &Block::Hash,
+		start: &[u8],
+		child_start: Option<&[u8]>,
+		max_entries: usize,
+	) -> Result<(StorageRange, Vec<Vec<u8>>), Error> {
+		(self as &SubstrateClient<B, E, Block, RA>)
+			.read_range_proof(&BlockId::Hash(*block), start, child_start, max_entries)
+	}
+
+	fn check_read_range_proof(
+		&self,
+		state_root: Block::Hash,
+		proof: Vec<Vec<u8>>,
+		start: &[u8],
+		child_start: Option<&[u8]>,
+		max_entries: usize,
+	) -> Result<StorageRange, Error> {
+		client::read_range_proof_check::<Blake2Hasher>(state_root, proof, start, child_start, max_entries)
+			.map_err(Into::into)
+	}
+
+	fn import_state(
+		&self,
+		header: Block::Header,
+		justification: Option<Justification>,
+		top: StorageOverlay,
+		children: ChildrenStorageOverlay,
+	) -> Result<(), Error> {
+		(self as &SubstrateClient<B, E, Block, RA>).import_state(header, justification, top, children)
+	}
 }
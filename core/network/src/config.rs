@@ -21,7 +21,7 @@ pub use network_libp2p::{NonReservedPeerMode, NetworkConfiguration, NodeKeyConfi
 
 use bitflags::bitflags;
 use consensus::import_queue::ImportQueue;
-use crate::chain::{Client, FinalityProofProvider};
+use crate::chain::{Client, FinalityProofProvider, WarpSyncProvider};
 use parity_codec;
 use crate::on_demand_layer::OnDemand;
 use runtime_primitives::traits::{Block as BlockT};
@@ -38,6 +38,14 @@ pub struct Params<B: BlockT, S, H: ExHashT> {
 	pub chain: Arc<Client<B>>,
 	/// Finality proof provider.
 	pub finality_proof_provider: Option<Arc<FinalityProofProvider<B>>>,
+	/// Warp sync proof provider.
+	pub warp_sync_provider: Option<Arc<WarpSyncProvider<B>>>,
+	/// Warp sync to a recent finalized block when starting from genesis. Requires a warp sync
+	/// proof provider.
+	pub warp_sync: bool,
+	/// Download the state of the best block of a peer, checked against range proofs, when starting
+	/// from genesis instead of executing the history.
+	pub fast_sync: bool,
 	/// On-demand service reference.
 	pub on_demand: Option<Arc<OnDemand<B>>>,
 	/// Transaction pool.
@@ -41,7 +41,7 @@ pub mod specialization;
 #[cfg(any(test, feature = "test-helpers"))]
 pub mod test;
 
-pub use chain::{Client as ClientHandle, FinalityProofProvider};
+pub use chain::{Client as ClientHandle, FinalityProofProvider, WarpSyncProvider, WarpSyncProgress};
 pub use service::{
 	NetworkService, NetworkWorker, FetchFuture, TransactionPool, ManageNetwork,
 	NetworkMsg, SyncProvider, ExHashT, ReportHandle,
@@ -53,7 +53,7 @@ pub use network_libp2p::{
 	ProtocolId, Multiaddr,
 	NetworkState, NetworkStatePeer, NetworkStateNotConnectedPeer, NetworkStatePeerEndpoint,
 	NodeKeyConfig, Secret, Secp256k1Secret, Ed25519Secret,
-	build_multiaddr, PeerId, PublicKey
+	build_multiaddr, parse_str_addr, PeerId, PublicKey
 };
 pub use message::{generic as generic_message, RequestId, Status as StatusMessage};
 pub use error::Error;
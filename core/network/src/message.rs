@@ -25,6 +25,8 @@ pub use self::generic::{
 	RemoteChangesRequest, RemoteChangesResponse,
 	FinalityProofRequest, FinalityProofResponse,
 	FromBlock, RemoteReadChildRequest,
+	WarpSyncRequest, WarpSyncResponse,
+	StateRequest, StateResponse,
 };
 
 /// A unique ID of a request.
@@ -205,6 +207,14 @@ pub mod generic {
 		FinalityProofRequest(FinalityProofRequest<Hash>),
 		/// Finality proof reponse.
 		FinalityProofResponse(FinalityProofResponse<Hash>),
+		/// Warp sync proof request.
+		WarpSyncRequest(WarpSyncRequest<Hash>),
+		/// Warp sync proof response.
+		WarpSyncResponse(WarpSyncResponse),
+		/// State chunk request.
+		StateRequest(StateRequest<Hash>),
+		/// State chunk response.
+		StateResponse(StateResponse),
 		/// Chain-specific message.
 		#[codec(index = "255")]
 		ChainSpecific(Vec<u8>),
@@ -386,4 +396,54 @@ pub mod generic {
 		/// Finality proof (if available).
 		pub proof: Option<Vec<u8>>,
 	}
+
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	/// Warp sync proof request.
+	pub struct WarpSyncRequest<H> {
+		/// Unique request id.
+		pub id: RequestId,
+		/// Hash of the last block known to the requester to be finalized by a known authority set.
+		pub begin: H,
+	}
+
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	/// Warp sync proof response.
+	pub struct WarpSyncResponse {
+		/// Id of a request this response was made for.
+		pub id: RequestId,
+		/// Encoded warp sync proof (if available).
+		pub proof: Option<Vec<u8>>,
+	}
+
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	/// State chunk request.
+	pub struct StateRequest<H> {
+		/// Unique request id.
+		pub id: RequestId,
+		/// Hash of the block to request state for.
+		pub block: H,
+		/// Only return keys strictly greater than this one. Empty to start from the first key.
+		pub start: Vec<u8>,
+		/// If set, `start` is the storage key of a partially downloaded child trie, and only its
+		/// keys strictly greater than this one are returned before continuing after `start`.
+		pub child_start: Option<Vec<u8>>,
+	}
+
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	/// State chunk response.
+	pub struct StateResponse {
+		/// Id of a request this response was made for.
+		pub id: RequestId,
+		/// Top-level storage key/value pairs, in key order.
+		pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+		/// Child trie key/value pairs, for the child tries whose root keys are included in
+		/// `entries` or for the child trie the request was started in.
+		pub children: Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)>,
+		/// Whether the last child trie in `children` is incomplete.
+		pub partial_child: bool,
+		/// Whether this is the last chunk of the state.
+		pub complete: bool,
+		/// Proof of `entries` and `children`, checked against the state root of the block.
+		pub proof: Vec<Vec<u8>>,
+	}
 }
@@ -41,7 +41,7 @@ use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use std::{cmp, num::NonZeroUsize, time};
 use log::{trace, debug, warn, error};
-use crate::chain::{Client, FinalityProofProvider};
+use crate::chain::{Client, FinalityProofProvider, WarpSyncProvider};
 use client::light::fetcher::{FetchChecker, ChangesProof};
 use crate::{error, util::LruHashSet};
 
@@ -52,12 +52,14 @@ const TICK_TIMEOUT: time::Duration = time::Duration::from_millis(1100);
 const PROPAGATE_TIMEOUT: time::Duration = time::Duration::from_millis(2900);
 
 /// Current protocol version.
-pub(crate) const CURRENT_VERSION: u32 = 3;
+pub(crate) const CURRENT_VERSION: u32 = 4;
 /// Lowest version we support
 pub(crate) const MIN_VERSION: u32 = 2;
 
 // Maximum allowed entries in `BlockResponse`
 const MAX_BLOCK_DATA_RESPONSE: u32 = 128;
+// Maximum allowed top-level and child trie entries in `StateResponse`
+const MAX_STATE_RESPONSE_ENTRIES: usize = 4096;
 /// When light node connects to the full node and the full node is behind light node
 /// for at least `LIGHT_MAXIMAL_BLOCKS_DIFFERENCE` blocks, we consider it unuseful
 /// and disconnect to free connection slot.
@@ -93,6 +95,10 @@ pub struct Protocol<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> {
 	context_data: ContextData<B, H>,
 	// Connected peers pending Status message.
 	handshaking_peers: HashMap<PeerId, HandshakingPeer>,
+	/// Used to serve warp sync requests and to verify warp sync proofs.
+	warp_sync_provider: Option<Arc<dyn WarpSyncProvider<B>>>,
+	/// Number of connected peers, exported as a metric.
+	peers_count: Arc<metrics::Gauge>,
 }
 
 /// A peer from whom we have received a Status message.
@@ -360,6 +366,24 @@ impl<'a, B: BlockT + 'a, H: ExHashT + 'a> SyncContext<B> for ProtocolContext<'a,
 			GenericMessage::BlockRequest(request)
 		)
 	}
+
+	fn send_warp_sync_request(&mut self, who: PeerId, request: message::WarpSyncRequest<B::Hash>) {
+		send_message(
+			&mut self.context_data.peers,
+			self.network_out,
+			who,
+			GenericMessage::WarpSyncRequest(request)
+		)
+	}
+
+	fn send_state_request(&mut self, who: PeerId, request: message::StateRequest<B::Hash>) {
+		send_message(
+			&mut self.context_data.peers,
+			self.network_out,
+			who,
+			GenericMessage::StateRequest(request)
+		)
+	}
 }
 
 /// Data necessary to create a context.
@@ -374,12 +398,18 @@ struct ContextData<B: BlockT, H: ExHashT> {
 pub struct ProtocolConfig {
 	/// Assigned roles.
 	pub roles: Roles,
+	/// Warp sync to a recent finalized block when starting from genesis.
+	pub warp_sync: bool,
+	/// Download the state of a recent block when starting from genesis.
+	pub fast_sync: bool,
 }
 
 impl Default for ProtocolConfig {
 	fn default() -> ProtocolConfig {
 		ProtocolConfig {
 			roles: Roles::FULL,
+			warp_sync: false,
+			fast_sync: false,
 		}
 	}
 }
@@ -390,10 +420,17 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		config: ProtocolConfig,
 		chain: Arc<Client<B>>,
 		checker: Arc<dyn FetchChecker<B>>,
+		warp_sync_provider: Option<Arc<dyn WarpSyncProvider<B>>>,
 		specialization: S,
 	) -> error::Result<Protocol<B, S, H>> {
 		let info = chain.info()?;
-		let sync = ChainSync::new(config.roles, &info);
+		let sync = ChainSync::new(
+			config.roles,
+			&info,
+			warp_sync_provider.clone(),
+			config.warp_sync,
+			config.fast_sync,
+		);
 		Ok(Protocol {
 			tick_timeout: tokio_timer::Interval::new_interval(TICK_TIMEOUT),
 			propagate_timeout: tokio_timer::Interval::new_interval(PROPAGATE_TIMEOUT),
@@ -408,6 +445,8 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			specialization: specialization,
 			consensus_gossip: ConsensusGossip::new(),
 			handshaking_peers: HashMap::new(),
+			warp_sync_provider,
+			peers_count: metrics::register_gauge("substrate_network_peers", "Number of connected peers"),
 		})
 	}
 
@@ -546,6 +585,14 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 				self.on_finality_proof_request(network_out, who, request, finality_proof_provider),
 			GenericMessage::FinalityProofResponse(response) =>
 				return self.on_finality_proof_response(network_out, who, response),
+			GenericMessage::WarpSyncRequest(request) =>
+				self.on_warp_sync_request(network_out, who, request),
+			GenericMessage::WarpSyncResponse(response) =>
+				self.on_warp_sync_response(network_out, who, response),
+			GenericMessage::StateRequest(request) =>
+				self.on_state_request(network_out, who, request),
+			GenericMessage::StateResponse(response) =>
+				self.on_state_response(network_out, who, response),
 			GenericMessage::Consensus(msg) => {
 				if self.context_data.peers.get(&who).map_or(false, |peer| peer.info.protocol_version > 2) {
 					self.consensus_gossip.on_incoming(
@@ -628,6 +675,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			self.handshaking_peers.remove(&peer);
 			self.context_data.peers.remove(&peer)
 		};
+		self.peers_count.set(self.context_data.peers.len() as u64);
 		if let Some(peer_data) = removed {
 			let mut context = ProtocolContext::new(&mut self.context_data, network_out);
 			if peer_data.info.protocol_version > 2 {
@@ -895,6 +943,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 				obsolete_requests: HashMap::new(),
 			};
 			self.context_data.peers.insert(who.clone(), peer);
+			self.peers_count.set(self.context_data.peers.len() as u64);
 
 			debug!(target: "sync", "Connected {}", who);
 			status.version
@@ -1443,6 +1492,111 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		}
 	}
 
+	fn on_warp_sync_request(
+		&mut self,
+		network_out: &mut dyn NetworkOut<B>,
+		who: PeerId,
+		request: message::WarpSyncRequest<B::Hash>,
+	) {
+		trace!(target: "sync", "Warp sync request from {} starting at {}", who, request.begin);
+		let proof = self.warp_sync_provider.as_ref()
+			.ok_or_else(|| String::from("Warp sync provider is not configured"))
+			.and_then(|provider| provider.generate(request.begin).map_err(|e| e.to_string()));
+		let proof = match proof {
+			Ok(proof) => proof,
+			Err(error) => {
+				trace!(target: "sync", "Warp sync request from {} starting at {} failed with: {}",
+					who,
+					request.begin,
+					error
+				);
+				None
+			},
+		};
+		self.send_message(
+			network_out,
+			who,
+			GenericMessage::WarpSyncResponse(message::WarpSyncResponse {
+				id: request.id,
+				proof,
+			}),
+		);
+	}
+
+	fn on_warp_sync_response(
+		&mut self,
+		network_out: &mut dyn NetworkOut<B>,
+		who: PeerId,
+		response: message::WarpSyncResponse,
+	) {
+		trace!(target: "sync", "Warp sync response from {}", who);
+		self.sync.on_warp_sync_data(
+			&mut ProtocolContext::new(&mut self.context_data, network_out),
+			who,
+			response,
+		);
+	}
+
+	fn on_state_request(
+		&mut self,
+		network_out: &mut dyn NetworkOut<B>,
+		who: PeerId,
+		request: message::StateRequest<B::Hash>,
+	) {
+		trace!(target: "sync", "State request from {} for {}", who, request.block);
+		let range = self.context_data.chain.read_range_proof(
+			&request.block,
+			&request.start,
+			request.child_start.as_ref().map(|key| &key[..]),
+			MAX_STATE_RESPONSE_ENTRIES,
+		);
+		let (range, proof) = match range {
+			Ok(range) => range,
+			Err(error) => {
+				trace!(target: "sync", "State request from {} for {} failed with: {}",
+					who,
+					request.block,
+					error
+				);
+				(Default::default(), Vec::new())
+			},
+		};
+		self.send_message(
+			network_out,
+			who,
+			GenericMessage::StateResponse(message::StateResponse {
+				id: request.id,
+				entries: range.entries,
+				children: range.children,
+				partial_child: range.partial_child,
+				complete: range.complete,
+				proof,
+			}),
+		);
+	}
+
+	fn on_state_response(
+		&mut self,
+		network_out: &mut dyn NetworkOut<B>,
+		who: PeerId,
+		response: message::StateResponse,
+	) {
+		trace!(target: "sync", "State response from {} ({} entries)", who, response.entries.len());
+		let mut context = ProtocolContext::new(&mut self.context_data, network_out);
+		if let Some(state) = self.sync.on_state_data(&mut context, who, response) {
+			let result = context.client().import_state(
+				state.header,
+				state.justification,
+				state.top,
+				state.children,
+			);
+			if let Err(ref e) = result {
+				warn!("Error importing downloaded state: {:?}", e);
+			}
+			self.sync.on_state_import_result(&mut context, result.is_ok());
+		}
+	}
+
 	fn on_remote_body_response(
 		&mut self,
 		mut network_out: &mut dyn NetworkOut<B>,
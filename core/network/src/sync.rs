@@ -33,6 +33,7 @@
 use std::cmp::max;
 use std::ops::Range;
 use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
 use log::{debug, trace, warn, info};
 use crate::protocol::PeerInfo as ProtocolPeerInfo;
 use network_libp2p::PeerId;
@@ -41,6 +42,9 @@ use consensus::{BlockOrigin, import_queue::{IncomingBlock, SharedFinalityProofRe
 use client::error::Error as ClientError;
 use crate::blocks::BlockCollection;
 use crate::sync::extra_requests::ExtraRequestsAggregator;
+use crate::sync::state::{FastSync, StateSync};
+use crate::sync::warp::WarpSync;
+use crate::chain::WarpSyncProvider;
 use runtime_primitives::traits::{
 	Block as BlockT, Header as HeaderT, NumberFor, Zero, One,
 	CheckedSub, SaturatedConversion
@@ -51,6 +55,10 @@ use crate::config::Roles;
 use std::collections::HashSet;
 
 mod extra_requests;
+mod state;
+mod warp;
+
+pub(crate) use self::state::ImportState;
 
 // Maximum blocks to request in a single packet.
 const MAX_BLOCKS_TO_REQUEST: usize = 128;
@@ -68,6 +76,10 @@ const BLOCKCHAIN_STATUS_READ_ERROR_REPUTATION_CHANGE: i32 = -(1 << 16);
 const ANCESTRY_BLOCK_ERROR_REPUTATION_CHANGE: i32 = -(1 << 9);
 /// Reputation change when a peer sent us a status message with a different genesis than us.
 const GENESIS_MISMATCH_REPUTATION_CHANGE: i32 = i32::min_value() + 1;
+/// Reputation change when a peer sent us an invalid warp sync proof, target header or state.
+const BAD_STATE_SYNC_DATA_REPUTATION_CHANGE: i32 = -(1 << 12);
+/// Lowest protocol version that supports warp sync and state requests.
+const STATE_SYNC_MIN_VERSION: u32 = 4;
 
 /// Context for a network-specific handler.
 pub trait Context<B: BlockT> {
@@ -89,6 +101,12 @@ pub trait Context<B: BlockT> {
 
 	/// Request a block from a peer.
 	fn send_block_request(&mut self, who: PeerId, request: message::BlockRequest<B>);
+
+	/// Request a warp sync proof from a peer.
+	fn send_warp_sync_request(&mut self, who: PeerId, request: message::WarpSyncRequest<B::Hash>);
+
+	/// Request a state chunk from a peer.
+	fn send_state_request(&mut self, who: PeerId, request: message::StateRequest<B::Hash>);
 }
 
 #[derive(Debug)]
@@ -127,6 +145,9 @@ pub(crate) enum PeerSyncState<B: BlockT> {
 	DownloadingStale(B::Hash),
 	DownloadingJustification(B::Hash),
 	DownloadingFinalityProof(B::Hash),
+	DownloadingWarpProof(B::Hash),
+	DownloadingFastSyncHeader(B::Hash),
+	DownloadingState(B::Hash),
 }
 
 /// Relay chain sync strategy.
@@ -141,6 +162,9 @@ pub struct ChainSync<B: BlockT> {
 	extra_requests: ExtraRequestsAggregator<B>,
 	queue_blocks: HashSet<B::Hash>,
 	best_importing_number: NumberFor<B>,
+	warp_sync: Option<WarpSync<B>>,
+	fast_sync: Option<FastSync<B>>,
+	state_sync: Option<StateSync<B>>,
 }
 
 /// Reported sync state.
@@ -181,15 +205,51 @@ impl<B: BlockT> Status<B> {
 
 impl<B: BlockT> ChainSync<B> {
 	/// Create a new instance. Pass the initial known state of the chain.
+	///
+	/// If `warp_sync` is set and our best block is the genesis, the chain is first warp synced to
+	/// a recent finalized block. Otherwise, if `fast_sync` is set, the state of the best block of
+	/// a peer is downloaded first. Both require a warp sync provider to verify the finality of
+	/// the target block.
 	pub(crate) fn new(
 		role: Roles,
 		info: &ClientInfo<B>,
+		warp_sync_provider: Option<Arc<dyn WarpSyncProvider<B>>>,
+		warp_sync: bool,
+		fast_sync: bool,
 	) -> Self {
 		let mut required_block_attributes = message::BlockAttributes::HEADER | message::BlockAttributes::JUSTIFICATION;
 		if role.is_full() {
 			required_block_attributes |= message::BlockAttributes::BODY;
 		}
 
+		let from_genesis = role.is_full() && info.chain.best_number.is_zero();
+		if from_genesis && (warp_sync || fast_sync) && warp_sync_provider.is_none() {
+			warn!(target: "sync", "State sync requires a warp sync provider, using regular sync");
+		}
+
+		let warp_sync = match warp_sync_provider {
+			Some(ref provider) if warp_sync && from_genesis =>
+				match WarpSync::new(provider.clone(), info.chain.genesis_hash) {
+					Ok(warp_sync) => Some(warp_sync),
+					Err(e) => {
+						warn!(target: "sync", "Unable to start warp sync: {:?}", e);
+						None
+					},
+				},
+			_ => None,
+		};
+		let fast_sync = match warp_sync_provider {
+			Some(provider) if fast_sync && warp_sync.is_none() && from_genesis =>
+				match FastSync::new(provider, info.chain.finalized_hash) {
+					Ok(fast_sync) => Some(fast_sync),
+					Err(e) => {
+						warn!(target: "sync", "Unable to start fast sync: {:?}", e);
+						None
+					},
+				},
+			_ => None,
+		};
+
 		ChainSync {
 			genesis_hash: info.chain.genesis_hash,
 			peers: HashMap::new(),
@@ -201,6 +261,9 @@ impl<B: BlockT> ChainSync<B> {
 			required_block_attributes,
 			queue_blocks: Default::default(),
 			best_importing_number: Zero::zero(),
+			warp_sync,
+			fast_sync,
+			state_sync: None,
 		}
 	}
 
@@ -209,6 +272,9 @@ impl<B: BlockT> ChainSync<B> {
 	}
 
 	fn state(&self, best_seen: &Option<NumberFor<B>>) -> SyncState {
+		if self.is_state_syncing() {
+			return SyncState::Downloading;
+		}
 		match best_seen {
 			&Some(n) if n > self.best_queued_number && n - self.best_queued_number > 5.into() => SyncState::Downloading,
 			_ => SyncState::Idle,
@@ -314,6 +380,8 @@ impl<B: BlockT> ChainSync<B> {
 					});
 				}
 			}
+
+			self.dispatch_state_sync(protocol);
 		}
 	}
 
@@ -445,7 +513,31 @@ impl<B: BlockT> ChainSync<B> {
 						vec![]
 					}
 				},
-				PeerSyncState::Available | PeerSyncState::DownloadingJustification(..) | PeerSyncState::DownloadingFinalityProof(..) => Vec::new(),
+				PeerSyncState::Available
+					| PeerSyncState::DownloadingJustification(..)
+					| PeerSyncState::DownloadingFinalityProof(..)
+					| PeerSyncState::DownloadingWarpProof(..)
+					| PeerSyncState::DownloadingState(..) => Vec::new(),
+				PeerSyncState::DownloadingFastSyncHeader(hash) => {
+					peer.state = PeerSyncState::Available;
+					if let Some(ref mut fast_sync) = self.fast_sync {
+						match blocks.into_iter().next() {
+							Some(block) => match fast_sync.on_header(hash, block.header, block.justification) {
+								Ok(state_sync) => self.state_sync = Some(state_sync),
+								Err(()) => {
+									debug!(target: "sync", "Invalid fast sync target header provided by {}", who);
+									protocol.report_peer(who.clone(), BAD_STATE_SYNC_DATA_REPUTATION_CHANGE);
+									fast_sync.note_unusable(who.clone());
+								},
+							},
+							None => {
+								trace!(target: "sync", "Peer {} is unable to provide the fast sync target header", who);
+								fast_sync.note_unusable(who.clone());
+							},
+						}
+					}
+					Vec::new()
+				},
 			}
 		} else {
 			Vec::new()
@@ -560,6 +652,171 @@ impl<B: BlockT> ChainSync<B> {
 		None
 	}
 
+	/// Handle a warp sync proof response.
+	pub(crate) fn on_warp_sync_data(
+		&mut self,
+		protocol: &mut Context<B>,
+		who: PeerId,
+		response: message::WarpSyncResponse,
+	) {
+		if let Some(ref mut peer) = self.peers.get_mut(&who) {
+			if let PeerSyncState::DownloadingWarpProof(_) = peer.state {
+				peer.state = PeerSyncState::Available;
+
+				if let Some(ref mut warp_sync) = self.warp_sync {
+					let has_proof = response.proof.is_some();
+					match warp_sync.on_warp_proof(response.proof) {
+						Ok(Some((header, justification))) =>
+							self.state_sync = Some(StateSync::new(header, Some(justification))),
+						Ok(None) => {},
+						Err(()) => {
+							if has_proof {
+								debug!(target: "sync", "Bad warp sync proof provided by {}", who);
+								protocol.report_peer(who.clone(), BAD_STATE_SYNC_DATA_REPUTATION_CHANGE);
+							} else {
+								trace!(target: "sync", "Peer {} is unable to provide a warp sync proof", who);
+							}
+							warp_sync.note_unusable(who);
+						},
+					}
+				}
+			}
+		}
+
+		self.maintain_sync(protocol);
+	}
+
+	/// Handle a state range response.
+	///
+	/// Returns `Some` once the complete state of the warp sync or fast sync target is downloaded.
+	/// It must be imported and the result reported with `on_state_import_result`.
+	#[must_use]
+	pub(crate) fn on_state_data(
+		&mut self,
+		protocol: &mut Context<B>,
+		who: PeerId,
+		response: message::StateResponse,
+	) -> Option<ImportState<B>> {
+		if let Some(ref mut peer) = self.peers.get_mut(&who) {
+			if let PeerSyncState::DownloadingState(_) = peer.state {
+				peer.state = PeerSyncState::Available;
+
+				let result = match self.state_sync {
+					Some(ref mut state_sync) => state_sync.on_response(protocol.client(), response),
+					None => Ok(false),
+				};
+				match result {
+					Ok(true) => {
+						trace!(target: "sync", "State download complete");
+						return self.state_sync.take().map(StateSync::into_import_state);
+					},
+					Ok(false) => {},
+					Err(()) => {
+						debug!(target: "sync", "Bad state range provided by {}", who);
+						protocol.report_peer(who.clone(), BAD_STATE_SYNC_DATA_REPUTATION_CHANGE);
+						self.note_state_sync_unusable(who);
+					},
+				}
+			}
+		}
+
+		self.maintain_sync(protocol);
+		None
+	}
+
+	/// Call this when the state downloaded by the warp sync or the fast sync has been imported,
+	/// with or without errors.
+	pub(crate) fn on_state_import_result(&mut self, protocol: &mut Context<B>, success: bool) {
+		if success {
+			info!("State sync complete, continuing with regular sync");
+			self.warp_sync = None;
+			self.fast_sync = None;
+			self.restart(protocol);
+			return;
+		}
+
+		self.state_sync = None;
+		let restarted = self.warp_sync.as_mut().map(|warp_sync| warp_sync.restart());
+		if let Some(Err(e)) = restarted {
+			warn!(target: "sync", "Unable to restart warp sync: {:?}", e);
+			self.warp_sync = None;
+		}
+		self.maintain_sync(protocol);
+	}
+
+	// Returns true while the warp sync or the fast sync is in progress.
+	fn is_state_syncing(&self) -> bool {
+		self.warp_sync.is_some() || self.fast_sync.is_some()
+	}
+
+	// Note that the given peer is unable to serve warp sync or fast sync requests.
+	fn note_state_sync_unusable(&mut self, who: PeerId) {
+		if let Some(ref mut warp_sync) = self.warp_sync {
+			warp_sync.note_unusable(who);
+		} else if let Some(ref mut fast_sync) = self.fast_sync {
+			fast_sync.note_unusable(who);
+		}
+	}
+
+	// Issue the next warp sync or fast sync request, if there's no request in flight.
+	fn dispatch_state_sync(&mut self, protocol: &mut Context<B>) {
+		if !self.is_state_syncing() {
+			return;
+		}
+
+		let is_downloading = self.peers.values().any(|peer| match peer.state {
+			PeerSyncState::DownloadingWarpProof(_)
+				| PeerSyncState::DownloadingFastSyncHeader(_)
+				| PeerSyncState::DownloadingState(_) => true,
+			_ => false,
+		});
+		if is_downloading {
+			return;
+		}
+
+		let (warp_sync, fast_sync) = (&self.warp_sync, &self.fast_sync);
+		let is_usable = |who: &PeerId| match (warp_sync, fast_sync) {
+			(Some(warp_sync), _) => !warp_sync.is_unusable(who),
+			(None, Some(fast_sync)) => !fast_sync.is_unusable(who),
+			(None, None) => false,
+		};
+		let who = self.peers.iter()
+			.filter(|(_, peer)| peer.state == PeerSyncState::Available && !peer.best_number.is_zero())
+			.map(|(who, _)| who)
+			.find(|who| is_usable(who) && protocol.peer_info(who)
+				.map_or(false, |info| info.protocol_version >= STATE_SYNC_MIN_VERSION))
+			.cloned();
+		let who = match who {
+			Some(who) => who,
+			None => return,
+		};
+
+		let peer = self.peers.get_mut(&who).expect("peer is selected from `self.peers` above; qed");
+		if let Some(ref state_sync) = self.state_sync {
+			let (block, start, child_start) = state_sync.next_request();
+			trace!(target: "sync", "Requesting state of {} from {}", block, who);
+			peer.state = PeerSyncState::DownloadingState(block);
+			protocol.send_state_request(who, message::generic::StateRequest { id: 0, block, start, child_start });
+		} else if let Some(ref warp_sync) = self.warp_sync {
+			let begin = warp_sync.next_request();
+			trace!(target: "sync", "Requesting warp sync proof from {} starting at {}", who, begin);
+			peer.state = PeerSyncState::DownloadingWarpProof(begin);
+			protocol.send_warp_sync_request(who, message::generic::WarpSyncRequest { id: 0, begin });
+		} else if self.fast_sync.is_some() {
+			let hash = peer.best_hash;
+			trace!(target: "sync", "Requesting fast sync target header {} from {}", hash, who);
+			peer.state = PeerSyncState::DownloadingFastSyncHeader(hash);
+			protocol.send_block_request(who, message::generic::BlockRequest {
+				id: 0,
+				fields: message::BlockAttributes::HEADER | message::BlockAttributes::JUSTIFICATION,
+				from: message::FromBlock::Hash(hash),
+				to: None,
+				direction: message::Direction::Ascending,
+				max: Some(1),
+			});
+		}
+	}
+
 	/// A batch of blocks have been processed, with or without errors.
 	/// Call this when a batch of blocks have been processed by the import queue, with or without
 	/// errors.
@@ -578,12 +835,14 @@ impl<B: BlockT> ChainSync<B> {
 		for peer in peers {
 			self.download_new(protocol, peer);
 		}
+		self.dispatch_state_sync(protocol);
 		self.extra_requests.dispatch(&mut self.peers, protocol);
 	}
 
 	/// Called periodically to perform any time-based actions. Must be called at a regular
 	/// interval.
 	pub fn tick(&mut self, protocol: &mut Context<B>) {
+		self.dispatch_state_sync(protocol);
 		self.extra_requests.dispatch(&mut self.peers, protocol);
 	}
 
@@ -729,6 +988,9 @@ impl<B: BlockT> ChainSync<B> {
 			if let PeerSyncState::AncestorSearch(_, _) = peer.state {
 				return false;
 			}
+			if self.is_state_syncing() {
+				return false;
+			}
 			if header.parent_hash() == &self.best_queued_hash || known_parent {
 				peer.common_number = number - One::one();
 			} else if known {
@@ -902,6 +1164,10 @@ impl<B: BlockT> ChainSync<B> {
 
 	// Select a range of NEW blocks to download from peer.
 	fn select_new_blocks(&mut self, who: PeerId) -> Option<(Range<NumberFor<B>>, message::BlockRequest<B>)> {
+		// blocks are not downloaded until warp sync or fast sync completes
+		if self.is_state_syncing() {
+			return None;
+		}
 		// when there are too many blocks in the queue => do not try to download new blocks
 		if self.queue_blocks.len() > MAX_IMPORTING_BLOCKS {
 			trace!(target: "sync", "Too many blocks in the queue.");
@@ -0,0 +1,192 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State download.
+//!
+//! The state of a target block is downloaded in ranges of keys. Every range comes with a proof
+//! that is checked against the state root of the target header, so the complete state is
+//! verified before the target block is imported without its ancestors.
+//!
+//! The target is either the last block proven by the warp sync, or, for the fast sync strategy,
+//! the best block of a peer whose header and justification are downloaded and verified first.
+//! Once the state is imported the regular sync takes over from there.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use log::debug;
+use network_libp2p::PeerId;
+use client::error::Error as ClientError;
+use runtime_primitives::{Justification, StorageOverlay, ChildrenStorageOverlay};
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
+use crate::chain::{Client, WarpSyncProvider};
+use crate::message;
+
+/// Downloaded state of the target block, ready to be imported.
+pub(crate) struct ImportState<B: BlockT> {
+	/// Header of the target block.
+	pub header: B::Header,
+	/// Justification of the target block.
+	pub justification: Option<Justification>,
+	/// Top-level storage.
+	pub top: StorageOverlay,
+	/// Child storage.
+	pub children: ChildrenStorageOverlay,
+}
+
+/// State download of a single block.
+pub(crate) struct StateSync<B: BlockT> {
+	header: B::Header,
+	justification: Option<Justification>,
+	top: StorageOverlay,
+	children: ChildrenStorageOverlay,
+	/// Last downloaded top-level key.
+	last_key: Vec<u8>,
+	/// Last downloaded key of the partially downloaded child trie at `last_key`.
+	last_child_key: Option<Vec<u8>>,
+}
+
+impl<B: BlockT> StateSync<B> {
+	/// Start downloading the state of the given block.
+	pub(crate) fn new(header: B::Header, justification: Option<Justification>) -> Self {
+		StateSync {
+			header,
+			justification,
+			top: Default::default(),
+			children: Default::default(),
+			last_key: Vec::new(),
+			last_child_key: None,
+		}
+	}
+
+	/// Returns the hash of the target block and the keys to request the next range from.
+	pub(crate) fn next_request(&self) -> (B::Hash, Vec<u8>, Option<Vec<u8>>) {
+		(self.header.hash(), self.last_key.clone(), self.last_child_key.clone())
+	}
+
+	/// Handle a state range response. Returns true once the complete state is downloaded, or an
+	/// error if the response is invalid.
+	pub(crate) fn on_response(&mut self, client: &Client<B>, response: message::StateResponse) -> Result<bool, ()> {
+		let len = response.entries.len()
+			+ response.children.iter().map(|(_, entries)| entries.len()).sum::<usize>();
+		if len == 0 && !response.complete {
+			return Err(());
+		}
+
+		let range = client.check_read_range_proof(
+			*self.header.state_root(),
+			response.proof,
+			&self.last_key,
+			self.last_child_key.as_ref().map(|key| &key[..]),
+			len,
+		).map_err(|e| debug!(target: "sync", "Invalid state range proof: {:?}", e))?;
+		if range.entries != response.entries
+			|| range.children != response.children
+			|| range.partial_child != response.partial_child
+			|| range.complete != response.complete
+		{
+			debug!(target: "sync", "State range doesn't match its proof");
+			return Err(());
+		}
+
+		if let Some((key, _)) = range.entries.last() {
+			self.last_key = key.clone();
+		}
+		self.last_child_key = match range.children.last() {
+			Some((_, entries)) if range.partial_child =>
+				Some(entries.last().map(|(key, _)| key.clone()).unwrap_or_default()),
+			_ => None,
+		};
+		self.top.extend(range.entries);
+		for (storage_key, entries) in range.children {
+			self.children.entry(storage_key).or_insert_with(Default::default).extend(entries);
+		}
+
+		Ok(range.complete)
+	}
+
+	/// Consume the downloaded state.
+	pub(crate) fn into_import_state(self) -> ImportState<B> {
+		let StateSync { header, justification, mut top, children, .. } = self;
+		// child trie roots are recomputed on import.
+		top.retain(|key, _| !children.contains_key(key));
+		ImportState { header, justification, top, children }
+	}
+}
+
+/// Fast sync state.
+///
+/// The target header is only accepted if it is a child of our finalized block, or if it comes
+/// with a justification by the authority set of our finalized block. Otherwise any peer could
+/// make us import an arbitrary state.
+pub(crate) struct FastSync<B: BlockT> {
+	provider: Arc<dyn WarpSyncProvider<B>>,
+	/// Our finalized block.
+	finalized_hash: B::Hash,
+	/// Encoded authority set that finalizes blocks after `finalized_hash`.
+	authorities: Vec<u8>,
+	/// Peers that were unable to serve our requests.
+	unusable_peers: HashSet<PeerId>,
+}
+
+impl<B: BlockT> FastSync<B> {
+	/// Start a new fast sync from the genesis block, which must be our finalized block.
+	pub(crate) fn new(provider: Arc<dyn WarpSyncProvider<B>>, finalized_hash: B::Hash) -> Result<Self, ClientError> {
+		let authorities = provider.initial_authorities()?;
+		Ok(FastSync {
+			provider,
+			finalized_hash,
+			authorities,
+			unusable_peers: HashSet::new(),
+		})
+	}
+
+	/// Returns true if we shouldn't send requests to the given peer.
+	pub(crate) fn is_unusable(&self, who: &PeerId) -> bool {
+		self.unusable_peers.contains(who)
+	}
+
+	/// Note that the given peer is unable to serve our requests.
+	pub(crate) fn note_unusable(&mut self, who: PeerId) {
+		self.unusable_peers.insert(who);
+	}
+
+	/// Handle the target header response. Returns the state download of the target, or an error
+	/// if the response is invalid or the target can't be proven to be finalized.
+	pub(crate) fn on_header(
+		&self,
+		hash: B::Hash,
+		header: Option<B::Header>,
+		justification: Option<Justification>,
+	) -> Result<StateSync<B>, ()> {
+		let header = match header {
+			Some(header) => header,
+			None => return Err(()),
+		};
+		if header.hash() != hash {
+			return Err(());
+		}
+
+		if *header.parent_hash() != self.finalized_hash {
+			let justification = justification.as_ref()
+				.ok_or_else(|| debug!(target: "sync", "Fast sync target {} is not justified", hash))?;
+			self.provider.verify_justification(&header, justification, &self.authorities)
+				.map_err(|e| debug!(target: "sync", "Invalid fast sync target justification: {:?}", e))?;
+		}
+
+		debug!(target: "sync", "Fast sync target is #{} ({})", header.number(), hash);
+		Ok(StateSync::new(header, justification))
+	}
+}
@@ -0,0 +1,112 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Warp sync proofs download.
+//!
+//! A node that starts at genesis downloads proofs of authority set changes up to a recent
+//! finalized block. The proofs are verified starting from the genesis authorities, so the last
+//! proven block can be trusted as a target for the state download, after which the regular sync
+//! takes over from there.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use log::{debug, trace};
+use network_libp2p::PeerId;
+use client::error::Error as ClientError;
+use runtime_primitives::Justification;
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
+use crate::chain::{WarpSyncProvider, WarpSyncProgress};
+
+/// Warp sync state.
+pub(crate) struct WarpSync<B: BlockT> {
+	provider: Arc<dyn WarpSyncProvider<B>>,
+	genesis_hash: B::Hash,
+	/// Encoded authority set that finalizes blocks after `last_hash`.
+	authorities: Vec<u8>,
+	/// Last block proven to be finalized.
+	last_hash: B::Hash,
+	/// Last proven block, along with its justification.
+	target: Option<(B::Header, Justification)>,
+	/// Peers that were unable to serve our requests.
+	unusable_peers: HashSet<PeerId>,
+}
+
+impl<B: BlockT> WarpSync<B> {
+	/// Start a new warp sync from the genesis block.
+	pub(crate) fn new(provider: Arc<dyn WarpSyncProvider<B>>, genesis_hash: B::Hash) -> Result<Self, ClientError> {
+		let authorities = provider.initial_authorities()?;
+		Ok(WarpSync {
+			provider,
+			genesis_hash,
+			authorities,
+			last_hash: genesis_hash,
+			target: None,
+			unusable_peers: HashSet::new(),
+		})
+	}
+
+	/// Restart the warp sync from the genesis block, e.g. after failing to import the state.
+	pub(crate) fn restart(&mut self) -> Result<(), ClientError> {
+		let unusable_peers = ::std::mem::replace(&mut self.unusable_peers, HashSet::new());
+		*self = WarpSync::new(self.provider.clone(), self.genesis_hash)?;
+		self.unusable_peers = unusable_peers;
+		Ok(())
+	}
+
+	/// Returns true if we shouldn't send requests to the given peer.
+	pub(crate) fn is_unusable(&self, who: &PeerId) -> bool {
+		self.unusable_peers.contains(who)
+	}
+
+	/// Note that the given peer is unable to serve our requests.
+	pub(crate) fn note_unusable(&mut self, who: PeerId) {
+		self.unusable_peers.insert(who);
+	}
+
+	/// Returns the block the next warp sync proof should start at.
+	pub(crate) fn next_request(&self) -> B::Hash {
+		self.last_hash
+	}
+
+	/// Handle a warp sync proof response. Returns the proven target block once the proofs reach
+	/// the latest finalized block, or an error if the response is invalid.
+	pub(crate) fn on_warp_proof(
+		&mut self,
+		proof: Option<Vec<u8>>,
+	) -> Result<Option<(B::Header, Justification)>, ()> {
+		let target = match proof {
+			Some(proof) => {
+				let (authorities, progress) = self.provider.verify(&proof, self.authorities.clone())
+					.map_err(|e| debug!(target: "sync", "Invalid warp sync proof: {:?}", e))?;
+				self.authorities = authorities;
+				match progress {
+					WarpSyncProgress::Partial(header, justification) => {
+						trace!(target: "sync", "Warp sync proven up to #{}", header.number());
+						self.last_hash = header.hash();
+						self.target = Some((header, justification));
+						return Ok(None);
+					},
+					WarpSyncProgress::Complete(header, justification) => (header, justification),
+				}
+			},
+			// nothing newer to prove, the last proven block is the target.
+			None => self.target.take().ok_or(())?,
+		};
+
+		debug!(target: "sync", "Warp sync proofs complete at #{} ({})", target.0.number(), target.0.hash());
+		Ok(Some(target))
+	}
+}
@@ -26,7 +26,7 @@ use std::sync::Arc;
 
 use crate::AlwaysBadChecker;
 use log::trace;
-use crate::chain::FinalityProofProvider;
+use crate::chain::{FinalityProofProvider, WarpSyncProvider};
 use client::{self, ClientInfo, BlockchainEvents, FinalityNotifications};
 use client::{in_mem::Backend as InMemoryBackend, error::Result as ClientResult};
 use client::block_builder::BlockBuilder;
@@ -766,6 +766,11 @@ pub trait TestNetFactory: Sized {
 		None
 	}
 
+	/// Get warp sync proof provider (if supported).
+	fn make_warp_sync_provider(&self, _client: PeersClient) -> Option<Arc<WarpSyncProvider<Block>>> {
+		None
+	}
+
 	fn default_config() -> ProtocolConfig {
 		ProtocolConfig::default()
 	}
@@ -961,6 +966,7 @@ pub trait TestNetFactory: Sized {
 			config.clone(),
 			client.clone(),
 			Arc::new(AlwaysBadChecker),
+			self.make_warp_sync_provider(PeersClient::Full(client.clone())),
 			specialization,
 		).unwrap();
 
@@ -1015,6 +1021,7 @@ pub trait TestNetFactory: Sized {
 			config,
 			client.clone(),
 			Arc::new(AlwaysBadChecker),
+			self.make_warp_sync_provider(PeersClient::Light(client.clone())),
 			specialization,
 		).unwrap();
 
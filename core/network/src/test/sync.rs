@@ -15,6 +15,7 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use client::{backend::Backend, blockchain::HeaderBackend};
+use crate::chain::WarpSyncProgress;
 use crate::config::Roles;
 use crate::message;
 use consensus::BlockOrigin;
@@ -485,3 +486,197 @@ fn light_peer_imports_header_from_announce() {
 	let known_stale_hash = net.peer(0).push_blocks_at(BlockId::Number(0), 1, true);
 	import_with_announce(&mut net, known_stale_hash);
 }
+
+/// Proves justified blocks after the requested one, at most two per proof.
+struct TestWarpSyncProvider(Arc<PeersFullClient>);
+
+impl WarpSyncProvider<Block> for TestWarpSyncProvider {
+	fn generate(&self, begin: H256) -> ClientResult<Option<Vec<u8>>> {
+		use parity_codec::Encode;
+
+		let begin = self.0.header(&BlockId::Hash(begin))?
+			.ok_or_else(|| client::error::Error::UnknownBlock(format!("{}", begin)))?;
+		let finalized = self.0.info()?.chain.finalized_number;
+		let mut fragments = Vec::new();
+		let mut is_finished = true;
+		for number in (*begin.number() + 1)..=finalized {
+			if let Some(justification) = self.0.justification(&BlockId::Number(number))? {
+				if fragments.len() == 2 {
+					is_finished = false;
+					break;
+				}
+				let header = self.0.header(&BlockId::Number(number))?.expect("block is finalized; qed");
+				fragments.push((header, justification));
+			}
+		}
+
+		if fragments.is_empty() {
+			return Ok(None);
+		}
+		Ok(Some((fragments, is_finished).encode()))
+	}
+
+	fn initial_authorities(&self) -> ClientResult<Vec<u8>> {
+		Ok(Vec::new())
+	}
+
+	fn verify(&self, proof: &[u8], authorities: Vec<u8>) -> ClientResult<(Vec<u8>, WarpSyncProgress<Block>)> {
+		use parity_codec::Decode;
+
+		let (fragments, is_finished): (Vec<(<Block as BlockT>::Header, Justification)>, bool) =
+			Decode::decode(&mut &proof[..]).ok_or(client::error::Error::JustificationDecode)?;
+		for (header, justification) in &fragments {
+			if *justification != header.hash().as_bytes().to_vec() {
+				return Err(client::error::Error::BadJustification(format!("{}", header.hash())));
+			}
+		}
+
+		let (header, justification) = fragments.into_iter().last()
+			.ok_or_else(|| client::error::Error::from("Empty warp sync proof"))?;
+		let progress = if is_finished {
+			WarpSyncProgress::Complete(header, justification)
+		} else {
+			WarpSyncProgress::Partial(header, justification)
+		};
+		Ok((authorities, progress))
+	}
+
+	fn verify_justification(
+		&self,
+		header: &<Block as BlockT>::Header,
+		justification: &Justification,
+		_authorities: &[u8],
+	) -> ClientResult<()> {
+		if *justification != header.hash().as_bytes().to_vec() {
+			return Err(client::error::Error::BadJustification(format!("{}", header.hash())));
+		}
+		Ok(())
+	}
+}
+
+struct WarpSyncTestNet(TestNet);
+
+impl TestNetFactory for WarpSyncTestNet {
+	type Specialization = DummySpecialization;
+	type Verifier = PassThroughVerifier;
+	type PeerData = ();
+
+	fn from_config(config: &ProtocolConfig) -> Self {
+		WarpSyncTestNet(TestNet::from_config(config))
+	}
+
+	fn make_verifier(&self, client: PeersClient, config: &ProtocolConfig) -> Arc<Self::Verifier> {
+		self.0.make_verifier(client, config)
+	}
+
+	fn peer(&self, i: usize) -> &Peer<Self::PeerData, Self::Specialization> {
+		self.0.peer(i)
+	}
+
+	fn peers(&self) -> &Vec<Arc<Peer<Self::PeerData, Self::Specialization>>> {
+		self.0.peers()
+	}
+
+	fn mut_peers<F: FnOnce(&mut Vec<Arc<Peer<Self::PeerData, Self::Specialization>>>)>(&mut self, closure: F) {
+		self.0.mut_peers(closure)
+	}
+
+	fn started(&self) -> bool {
+		self.0.started()
+	}
+
+	fn set_started(&mut self, new: bool) {
+		self.0.set_started(new)
+	}
+
+	fn make_warp_sync_provider(&self, client: PeersClient) -> Option<Arc<WarpSyncProvider<Block>>> {
+		client.as_full().map(|client| Arc::new(TestWarpSyncProvider(client)) as _)
+	}
+}
+
+#[test]
+fn warp_sync_skips_to_finalized_state() {
+	let _ = ::env_logger::try_init();
+	let mut net = WarpSyncTestNet::new(1);
+	net.peer(0).push_blocks(10, false);
+	for number in &[3, 6, 8] {
+		let hash = net.peer(0).client().header(&BlockId::Number(*number)).unwrap().unwrap().hash();
+		net.peer(0).client().finalize_block(BlockId::Hash(hash), Some(hash.as_bytes().to_vec()), true).unwrap();
+	}
+
+	let mut config = ProtocolConfig::default();
+	config.warp_sync = true;
+	net.add_full_peer(&config);
+	net.sync();
+
+	let target = net.peer(0).client().header(&BlockId::Number(8)).unwrap().unwrap();
+	let info0 = net.peer(0).client().info().unwrap().chain;
+	let info1 = net.peer(1).client().info().unwrap().chain;
+	assert_eq!(info1.best_number, 10);
+	assert_eq!(info1.best_hash, info0.best_hash);
+	assert_eq!(info1.finalized_hash, target.hash());
+	assert_eq!(
+		net.peer(1).client().justification(&BlockId::Number(8)).unwrap(),
+		Some(target.hash().as_bytes().to_vec()),
+	);
+	// blocks before the warp sync target are not downloaded.
+	assert!(net.peer(1).client().header(&BlockId::Number(5)).unwrap().is_none());
+}
+
+#[test]
+fn warp_sync_is_not_used_when_disabled() {
+	let _ = ::env_logger::try_init();
+	let mut net = WarpSyncTestNet::new(2);
+	net.peer(0).push_blocks(5, false);
+	let hash = net.peer(0).client().header(&BlockId::Number(4)).unwrap().unwrap().hash();
+	net.peer(0).client().finalize_block(BlockId::Hash(hash), Some(hash.as_bytes().to_vec()), true).unwrap();
+	net.sync();
+
+	assert_eq!(net.peer(1).client().info().unwrap().chain.best_number, 5);
+	assert!(net.peer(1).client().header(&BlockId::Number(1)).unwrap().is_some());
+}
+
+#[test]
+fn fast_sync_downloads_state_of_best_block() {
+	let _ = ::env_logger::try_init();
+	let mut net = WarpSyncTestNet::new(1);
+	net.peer(0).push_blocks(10, false);
+	let hash = net.peer(0).client().info().unwrap().chain.best_hash;
+	net.peer(0).client().finalize_block(BlockId::Hash(hash), Some(hash.as_bytes().to_vec()), true).unwrap();
+
+	let mut config = ProtocolConfig::default();
+	config.fast_sync = true;
+	net.add_full_peer(&config);
+	net.sync();
+
+	let info0 = net.peer(0).client().info().unwrap().chain;
+	let info1 = net.peer(1).client().info().unwrap().chain;
+	assert_eq!(info1.best_number, 10);
+	assert_eq!(info1.best_hash, info0.best_hash);
+	let code = primitives::storage::StorageKey(b":code".to_vec());
+	assert_eq!(
+		net.peer(1).client().storage(&BlockId::Number(10), &code).unwrap(),
+		net.peer(0).client().storage(&BlockId::Number(10), &code).unwrap(),
+	);
+	// blocks before the fast sync target are not downloaded.
+	assert!(net.peer(1).client().header(&BlockId::Number(5)).unwrap().is_none());
+}
+
+#[test]
+fn fast_sync_rejects_unjustified_target() {
+	let _ = ::env_logger::try_init();
+	let mut net = WarpSyncTestNet::new(1);
+	net.peer(0).push_blocks(10, false);
+	// the best block of the peer comes with a justification that doesn't finalize it.
+	let hash = net.peer(0).client().info().unwrap().chain.best_hash;
+	net.peer(0).client().finalize_block(BlockId::Hash(hash), Some(vec![42; 32]), true).unwrap();
+
+	let mut config = ProtocolConfig::default();
+	config.fast_sync = true;
+	net.add_full_peer(&config);
+	net.sync();
+
+	// the fake target is not imported.
+	assert_eq!(net.peer(1).client().info().unwrap().chain.best_number, 0);
+	assert!(net.peer(1).client().header(&BlockId::Hash(hash)).unwrap().is_none());
+}
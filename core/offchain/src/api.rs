@@ -14,181 +14,297 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::sync::Arc;
+use std::{convert::TryFrom, sync::Arc};
 use futures::{Stream, Future, sync::mpsc};
+use keystore::Store as Keystore;
 use log::{info, debug, warn, error};
-use parity_codec::Decode;
+use parity_codec::{Encode, Decode};
 use primitives::offchain::{
 	Timestamp, HttpRequestId, HttpRequestStatus, HttpError,
 	Externalities as OffchainExt,
 	CryptoKind, CryptoKeyId,
+	OffchainStorage,
 };
+use primitives::{ed25519, sr25519, Pair, crypto::{KeyTypeId, key_types}};
 use runtime_primitives::{
 	generic::BlockId,
 	traits::{self, Extrinsic},
 };
 use transaction_pool::txpool::{Pool, ChainApi};
+use crate::AuthorityKeyProvider;
+
+mod http;
+mod timestamp;
 
 /// A message between the offchain extension and the processing thread.
 enum ExtMessage {
 	SubmitExtrinsic(Vec<u8>),
 }
 
+/// Prefix of the local storage entries set by the runtime.
+const STORAGE_PREFIX: &[u8] = b"storage";
+/// Prefix of the local storage entries referencing the generated crypto keys.
+const KEYS_PREFIX: &[u8] = b"keys";
+/// Local storage key of the next free `CryptoKeyId`.
+const NEXT_ID: &[u8] = b"crypto_key_id";
+
+/// A key usable by the offchain workers.
+///
+/// The secret is held in the keystore: only the public key of the keys generated by
+/// `new_crypto_key` is persisted in the local storage.
+#[derive(Encode, Decode)]
+struct Key {
+	key_type: KeyTypeId,
+	kind: u32,
+	public: Vec<u8>,
+}
+
+impl Key {
+	fn verify(&self, msg: &[u8], signature: &[u8]) -> Result<bool, ()> {
+		Ok(match CryptoKind::try_from(self.kind)? {
+			CryptoKind::Sr25519 => sr25519::Pair::verify_weak(signature, msg, &self.public),
+			CryptoKind::Ed25519 => ed25519::Pair::verify_weak(signature, msg, &self.public),
+		})
+	}
+}
+
 /// Asynchronous offchain API.
 ///
 /// NOTE this is done to prevent recursive calls into the runtime (which are not supported currently).
-pub(crate) struct AsyncApi(mpsc::UnboundedSender<ExtMessage>);
+pub(crate) struct AsyncApi<Storage, KeyProvider> {
+	sender: mpsc::UnboundedSender<ExtMessage>,
+	db: Storage,
+	keystore: Arc<Keystore>,
+	key_provider: KeyProvider,
+	http: http::HttpApi,
+}
+
+impl<Storage: OffchainStorage, KeyProvider: AuthorityKeyProvider> AsyncApi<Storage, KeyProvider> {
+	/// Allocates a fresh `CryptoKeyId`.
+	///
+	/// The counter lives in the local storage and is bumped with compare-and-set, so that
+	/// concurrently running offchain workers never hand out the same id twice.
+	fn next_key_id(&mut self) -> Result<CryptoKeyId, ()> {
+		loop {
+			let current = self.db.get(KEYS_PREFIX, NEXT_ID);
+			let id = current.as_ref()
+				.and_then(|v| u16::decode(&mut &**v))
+				.unwrap_or_default();
+			let next = id.checked_add(1).ok_or_else(|| {
+				error!("Overflow in offchain worker crypto key ID assignment");
+			})?;
 
-fn unavailable_yet<R: Default>(name: &str) -> R {
-	error!("This {:?} API is not available for offchain workers yet. Follow
-		   https://github.com/paritytech/substrate/issues/1458 for details", name);
-	Default::default()
+			if self.db.compare_and_set(KEYS_PREFIX, NEXT_ID, current.as_ref().map(|v| &**v), &next.encode()) {
+				return Ok(CryptoKeyId(id))
+			}
+		}
+	}
+
+	/// Returns the key with given id, or the current authority key if `key` is `None`.
+	fn read_key(&self, key: Option<CryptoKeyId>) -> Result<Key, ()> {
+		match key {
+			None => self.key_provider.authority_key()
+				.map(|pair| Key {
+					key_type: key_types::ED25519,
+					kind: CryptoKind::Ed25519 as u32,
+					public: pair.public().as_ref().to_vec(),
+				})
+				.ok_or(()),
+			Some(key) => self.db.get(KEYS_PREFIX, &key.0.encode())
+				.and_then(|v| Key::decode(&mut &*v))
+				.ok_or(()),
+		}
+	}
 }
 
-impl OffchainExt for AsyncApi {
+impl<Storage, KeyProvider> OffchainExt for AsyncApi<Storage, KeyProvider> where
+	Storage: OffchainStorage,
+	KeyProvider: AuthorityKeyProvider,
+{
 	fn submit_transaction(&mut self, ext: Vec<u8>) -> Result<(), ()> {
-		self.0.unbounded_send(ExtMessage::SubmitExtrinsic(ext))
+		self.sender
+			.unbounded_send(ExtMessage::SubmitExtrinsic(ext))
 			.map(|_| ())
 			.map_err(|_| ())
 	}
 
-	fn new_crypto_key(&mut self, _crypto: CryptoKind) -> Result<CryptoKeyId, ()> {
-		unavailable_yet::<()>("new_crypto_key");
-		Err(())
+	fn new_crypto_key(&mut self, crypto: CryptoKind) -> Result<CryptoKeyId, ()> {
+		let public = match crypto {
+			CryptoKind::Sr25519 => self.keystore.generate_by_type::<sr25519::Pair>(key_types::OFFCHAIN)
+				.map(|pair| pair.public().as_ref().to_vec()),
+			CryptoKind::Ed25519 => self.keystore.generate_by_type::<ed25519::Pair>(key_types::OFFCHAIN)
+				.map(|pair| pair.public().as_ref().to_vec()),
+		}.map_err(|e| error!("Unable to generate offchain worker key: {}", e))?;
+
+		let id = self.next_key_id()?;
+		let key = Key {
+			key_type: key_types::OFFCHAIN,
+			kind: crypto as u32,
+			public,
+		};
+		self.db.set(KEYS_PREFIX, &id.0.encode(), &key.encode());
+
+		Ok(id)
 	}
 
-	fn encrypt(&mut self, _key: Option<CryptoKeyId>, _data: &[u8]) -> Result<Vec<u8>, ()> {
-		unavailable_yet::<()>("encrypt");
-		Err(())
+	fn encrypt(&mut self, key: Option<CryptoKeyId>, data: &[u8]) -> Result<Vec<u8>, ()> {
+		let key = self.read_key(key)?;
+
+		self.keystore.encrypt_with(key.key_type, &key.public, data)
+			.map_err(|e| warn!("Unable to encrypt with offchain worker key: {}", e))
 	}
 
-	fn decrypt(&mut self, _key: Option<CryptoKeyId>, _data: &[u8]) -> Result<Vec<u8>, ()> {
-		unavailable_yet::<()>("decrypt");
-		Err(())
+	fn decrypt(&mut self, key: Option<CryptoKeyId>, data: &[u8]) -> Result<Vec<u8>, ()> {
+		let key = self.read_key(key)?;
+
+		self.keystore.decrypt_with(key.key_type, &key.public, data)
+			.map_err(|e| debug!("Unable to decrypt with offchain worker key: {}", e))
 	}
 
-	fn sign(&mut self, _key: Option<CryptoKeyId>, _data: &[u8]) -> Result<Vec<u8>, ()> {
-		unavailable_yet::<()>("sign");
-		Err(())
+	fn sign(&mut self, key: Option<CryptoKeyId>, data: &[u8]) -> Result<Vec<u8>, ()> {
+		let key = self.read_key(key)?;
+
+		self.keystore.sign_with(key.key_type, &key.public, data)
+			.map_err(|e| warn!("Unable to sign with offchain worker key: {}", e))
 	}
 
-	fn verify(&mut self, _key: Option<CryptoKeyId>, _msg: &[u8], _signature: &[u8]) -> Result<bool, ()> {
-		unavailable_yet::<()>("verify");
-		Err(())
+	fn verify(&mut self, key: Option<CryptoKeyId>, msg: &[u8], signature: &[u8]) -> Result<bool, ()> {
+		let key = self.read_key(key)?;
+
+		key.verify(msg, signature)
 	}
 
 	fn timestamp(&mut self) -> Timestamp {
-		unavailable_yet("timestamp")
+		timestamp::now()
 	}
 
-	fn sleep_until(&mut self, _deadline: Timestamp) {
-		unavailable_yet::<()>("sleep_until")
+	fn sleep_until(&mut self, deadline: Timestamp) {
+		// Note that `timestamp_from_now` saturates at zero for deadlines in the past.
+		std::thread::sleep(timestamp::timestamp_from_now(deadline));
 	}
 
 	fn random_seed(&mut self) -> [u8; 32] {
-		unavailable_yet("random_seed")
+		rand::random()
 	}
 
-	fn local_storage_set(&mut self, _key: &[u8], _value: &[u8]) {
-		unavailable_yet("local_storage_set")
+	fn local_storage_set(&mut self, key: &[u8], value: &[u8]) {
+		self.db.set(STORAGE_PREFIX, key, value);
 	}
 
-	fn local_storage_compare_and_set(&mut self, _key: &[u8], _old_value: &[u8], _new_value: &[u8]) {
-		unavailable_yet("local_storage_compare_and_set")
+	fn local_storage_compare_and_set(&mut self, key: &[u8], old_value: &[u8], new_value: &[u8]) {
+		self.db.compare_and_set(STORAGE_PREFIX, key, Some(old_value), new_value);
 	}
 
-	fn local_storage_get(&mut self, _key: &[u8]) -> Option<Vec<u8>> {
-		unavailable_yet("local_storage_get")
+	fn local_storage_get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+		self.db.get(STORAGE_PREFIX, key)
 	}
 
 	fn http_request_start(
 		&mut self,
-		_method: &str,
-		_uri: &str,
+		method: &str,
+		uri: &str,
 		_meta: &[u8]
 	) -> Result<HttpRequestId, ()> {
-		unavailable_yet::<()>("http_request_start");
-		Err(())
+		self.http.request_start(method, uri)
 	}
 
 	fn http_request_add_header(
 		&mut self,
-		_request_id: HttpRequestId,
-		_name: &str,
-		_value: &str
+		request_id: HttpRequestId,
+		name: &str,
+		value: &str
 	) -> Result<(), ()> {
-		unavailable_yet::<()>("http_request_add_header");
-		Err(())
+		self.http.request_add_header(request_id, name, value)
 	}
 
 	fn http_request_write_body(
 		&mut self,
-		_request_id: HttpRequestId,
-		_chunk: &[u8],
-		_deadline: Option<Timestamp>
+		request_id: HttpRequestId,
+		chunk: &[u8],
+		deadline: Option<Timestamp>
 	) -> Result<(), HttpError> {
-		unavailable_yet::<()>("http_request_write_body");
-		Err(HttpError::IoError)
+		self.http.request_write_body(request_id, chunk, deadline)
 	}
 
 	fn http_response_wait(
 		&mut self,
 		ids: &[HttpRequestId],
-		_deadline: Option<Timestamp>
+		deadline: Option<Timestamp>
 	) -> Vec<HttpRequestStatus> {
-		unavailable_yet::<()>("http_response_wait");
-		ids.iter().map(|_| HttpRequestStatus::Unknown).collect()
+		self.http.response_wait(ids, deadline)
 	}
 
 	fn http_response_headers(
 		&mut self,
-		_request_id: HttpRequestId
+		request_id: HttpRequestId
 	) -> Vec<(Vec<u8>, Vec<u8>)> {
-		unavailable_yet("http_response_headers")
+		self.http.response_headers(request_id)
 	}
 
 	fn http_response_read_body(
 		&mut self,
-		_request_id: HttpRequestId,
-		_buffer: &mut [u8],
-		_deadline: Option<Timestamp>
+		request_id: HttpRequestId,
+		buffer: &mut [u8],
+		deadline: Option<Timestamp>
 	) -> Result<usize, HttpError> {
-		unavailable_yet::<()>("http_response_read_body");
-		Err(HttpError::IoError)
+		self.http.response_read_body(request_id, buffer, deadline)
 	}
 }
 
-/// Offchain extensions implementation API
-pub(crate) struct Api<A: ChainApi> {
-	receiver: Option<mpsc::UnboundedReceiver<ExtMessage>>,
-	transaction_pool: Arc<Pool<A>>,
-	at: BlockId<A::Block>,
-}
-
-impl<A: ChainApi> Api<A> {
-	pub fn new(
+impl<Storage, KeyProvider> AsyncApi<Storage, KeyProvider> {
+	/// Creates new Offchain extensions API implementation an the asynchronous processing part.
+	pub fn new<A: ChainApi>(
 		transaction_pool: Arc<Pool<A>>,
+		db: Storage,
+		keystore: Arc<Keystore>,
+		key_provider: KeyProvider,
 		at: BlockId<A::Block>,
-	) -> (AsyncApi, Self) {
-		let (tx, rx) = mpsc::unbounded();
+	) -> (Self, Api<A>) {
+		let (sender, rx) = mpsc::unbounded();
+		let (http_api, http_worker) = http::http();
+
 		let api = Self {
+			sender,
+			db,
+			keystore,
+			key_provider,
+			http: http_api,
+		};
+
+		let async_api = Api {
 			receiver: Some(rx),
 			transaction_pool,
 			at,
+			http: Some(http_worker),
 		};
-		(AsyncApi(tx), api)
+
+		(api, async_api)
 	}
+}
+
+/// Offchain extensions implementation API
+pub(crate) struct Api<A: ChainApi> {
+	receiver: Option<mpsc::UnboundedReceiver<ExtMessage>>,
+	transaction_pool: Arc<Pool<A>>,
+	at: BlockId<A::Block>,
+	/// Everything HTTP-related is handled by a different struct.
+	http: Option<http::HttpWorker>,
+}
 
+impl<A: ChainApi> Api<A> {
 	/// Run a processing task for the API
 	pub fn process(mut self) -> impl Future<Item=(), Error=()> {
 		let receiver = self.receiver.take().expect("Take invoked only once.");
+		let http = self.http.take().expect("Take invoked only once.");
 
-		receiver.for_each(move |msg| {
+		let extrinsics = receiver.for_each(move |msg| {
 			match msg {
 				ExtMessage::SubmitExtrinsic(ext) => self.submit_extrinsic(ext),
 			}
 			Ok(())
-		})
+		});
+
+		extrinsics.join(http).map(|_| ())
 	}
 
 	fn submit_extrinsic(&mut self, ext: Vec<u8>) {
@@ -209,3 +325,155 @@ impl<A: ChainApi> Api<A> {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::convert::TryInto;
+	use primitives::offchain::{Duration, InMemOffchainStorage};
+	use crate::tests::TestProvider;
+
+	fn offchain_api(keystore: Keystore, key: Option<ed25519::Pair>) -> AsyncApi<InMemOffchainStorage, TestProvider> {
+		let _ = env_logger::try_init();
+		let client = Arc::new(test_client::new());
+		let pool = Arc::new(
+			Pool::new(Default::default(), transaction_pool::ChainApi::new(client.clone()))
+		);
+		let db = InMemOffchainStorage::default();
+
+		AsyncApi::new(pool, db, Arc::new(keystore), TestProvider(key), BlockId::Number(0)).0
+	}
+
+	#[test]
+	fn should_get_timestamp() {
+		let mut api = offchain_api(Keystore::new_in_memory(), None);
+
+		// Get timestamp from std.
+		let now = std::time::SystemTime::now();
+		let d: u64 = now.duration_since(std::time::SystemTime::UNIX_EPOCH).unwrap().as_millis()
+			.try_into().unwrap();
+
+		// Get timestamp from offchain api.
+		let timestamp = api.timestamp();
+
+		// Compare.
+		assert!(timestamp.unix_millis() > 0);
+		assert!(timestamp.unix_millis() >= d);
+	}
+
+	#[test]
+	fn should_sleep() {
+		let mut api = offchain_api(Keystore::new_in_memory(), None);
+
+		// Arrange.
+		let now = api.timestamp();
+		let delta = Duration::from_millis(100);
+		let deadline = now.add(delta);
+
+		// Act.
+		api.sleep_until(deadline);
+		let new_now = api.timestamp();
+
+		// Assert.
+		// The diff could be more than the sleep duration.
+		assert!(new_now.unix_millis() - 100 >= now.unix_millis());
+	}
+
+	#[test]
+	fn should_set_and_get_local_storage() {
+		let mut api = offchain_api(Keystore::new_in_memory(), None);
+		let key = b"test";
+
+		assert_eq!(api.local_storage_get(key), None);
+		api.local_storage_set(key, b"value");
+		assert_eq!(api.local_storage_get(key), Some(b"value".to_vec()));
+
+		// the runtime storage is not mixed up with the generated keys
+		assert_eq!(api.db.get(KEYS_PREFIX, key), None);
+	}
+
+	#[test]
+	fn should_compare_and_set_local_storage() {
+		let mut api = offchain_api(Keystore::new_in_memory(), None);
+		let key = b"test";
+		api.local_storage_set(key, b"value");
+
+		api.local_storage_compare_and_set(key, b"val", b"xxx");
+		assert_eq!(api.local_storage_get(key), Some(b"value".to_vec()));
+
+		api.local_storage_compare_and_set(key, b"value", b"xxx");
+		assert_eq!(api.local_storage_get(key), Some(b"xxx".to_vec()));
+	}
+
+	#[test]
+	fn should_generate_keys_and_sign() {
+		let mut api = offchain_api(Keystore::new_in_memory(), None);
+		let msg = b"Hello world!";
+
+		let sr = api.new_crypto_key(CryptoKind::Sr25519).unwrap();
+		let ed = api.new_crypto_key(CryptoKind::Ed25519).unwrap();
+		assert_ne!(sr, ed);
+
+		for key in &[sr, ed] {
+			let signature = api.sign(Some(*key), msg).unwrap();
+			assert_eq!(signature.len(), 64);
+			assert_eq!(api.verify(Some(*key), msg, &signature), Ok(true));
+			assert_eq!(api.verify(Some(*key), b"Other message", &signature), Ok(false));
+		}
+
+		// signatures are not valid for the other key
+		let signature = api.sign(Some(sr), msg).unwrap();
+		assert_eq!(api.verify(Some(ed), msg, &signature), Ok(false));
+
+		// unknown keys are rejected
+		assert_eq!(api.sign(Some(CryptoKeyId(1_000)), msg), Err(()));
+
+		// only the public keys are kept in the local storage
+		let key = api.db.get(KEYS_PREFIX, &sr.0.encode()).unwrap();
+		assert_eq!(Key::decode(&mut &*key).unwrap().public.len(), 32);
+	}
+
+	#[test]
+	fn should_encrypt_and_decrypt() {
+		let mut api = offchain_api(Keystore::new_in_memory(), None);
+		let msg = b"Hello world!";
+
+		let sr = api.new_crypto_key(CryptoKind::Sr25519).unwrap();
+		let ed = api.new_crypto_key(CryptoKind::Ed25519).unwrap();
+
+		let encrypted = api.encrypt(Some(sr), msg).unwrap();
+		assert_eq!(api.decrypt(Some(sr), &encrypted), Ok(msg.to_vec()));
+		assert_eq!(api.decrypt(Some(ed), &encrypted), Err(()));
+		assert_eq!(api.decrypt(Some(sr), b"garbage"), Err(()));
+	}
+
+	#[test]
+	fn should_use_authority_key_if_available() {
+		let msg = b"Hello world!";
+		let mut api = offchain_api(Keystore::new_in_memory(), None);
+		assert_eq!(api.sign(None, msg), Err(()));
+		assert_eq!(api.encrypt(None, msg), Err(()));
+
+		let keystore = Keystore::new_in_memory();
+		let pair: ed25519::Pair = keystore.generate_from_seed_by_type(
+			key_types::ED25519,
+			"0x3d97c819d68f9bafa7d6e79cb991eebcd77d966c5334c0b94d9e1fa7ad0869dc",
+		).unwrap();
+		let mut api = offchain_api(keystore, Some(pair.clone()));
+		let signature = api.sign(None, msg).unwrap();
+		assert!(ed25519::Pair::verify_weak(&signature, msg, pair.public()));
+		assert_eq!(api.verify(None, msg, &signature), Ok(true));
+
+		let encrypted = api.encrypt(None, msg).unwrap();
+		assert_eq!(api.decrypt(None, &encrypted), Ok(msg.to_vec()));
+	}
+
+	#[test]
+	fn should_get_random_seed() {
+		let mut api = offchain_api(Keystore::new_in_memory(), None);
+		let seed = api.random_seed();
+		// Lower probability of this happening is negligible.
+		assert_ne!(seed, [0; 32]);
+		assert_ne!(seed, api.random_seed());
+	}
+}
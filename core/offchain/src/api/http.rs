@@ -0,0 +1,712 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module is composed of two structs: [`HttpApi`] and [`HttpWorker`]. Calling the [`http`]
+//! function returns a pair of [`HttpApi`] and [`HttpWorker`] that share some state.
+//!
+//! The [`HttpApi`] is (indirectly) passed to the runtime when calling an offchain worker, while
+//! the [`HttpWorker`] must be processed in the background. The [`HttpApi`] mimics the API of the
+//! HTTP-related methods available to offchain workers.
+//!
+//! The reason for this design is driven by the fact that HTTP requests should continue running
+//! (i.e.: the socket should continue being processed) in the background even if the runtime isn't
+//! actively calling any function.
+
+use crate::api::timestamp;
+use futures::{prelude::*, executor, future, stream::Fuse, sync::mpsc, task};
+use hyper::{Body, Chunk, Client as HyperClient, client::HttpConnector};
+use log::{warn, error};
+use primitives::offchain::{HttpRequestId, Timestamp, HttpRequestStatus, HttpError};
+use std::{collections::BTreeMap, io::{Cursor, Read}, sync::Arc, thread};
+
+/// Creates a pair of [`HttpApi`] and [`HttpWorker`].
+pub fn http() -> (HttpApi, HttpWorker) {
+	let (to_worker, from_api) = mpsc::unbounded();
+	let (to_api, from_worker) = mpsc::unbounded();
+
+	let api = HttpApi {
+		to_worker,
+		from_worker: from_worker.fuse(),
+		// We start with a random ID for the first HTTP request, to prevent mischievous people from
+		// writing runtime code with hardcoded IDs.
+		next_id: HttpRequestId(rand::random::<u16>() % 2000),
+		requests: BTreeMap::new(),
+	};
+
+	let worker = HttpWorker {
+		to_api,
+		from_api,
+		http_client: HyperClient::new(),
+		requests: Vec::new(),
+	};
+
+	(api, worker)
+}
+
+/// Provides HTTP capabilities.
+///
+/// Since this struct is a helper for offchain workers, its API is mimicking the API provided
+/// to offchain workers.
+pub struct HttpApi {
+	/// Used to sends messages to the worker.
+	to_worker: mpsc::UnboundedSender<ApiToWorker>,
+	/// Used to receive messages from the worker.
+	/// We use a `Fuse` in order to have an extra protection against panicking.
+	from_worker: Fuse<mpsc::UnboundedReceiver<WorkerToApi>>,
+	/// Id to assign to the next HTTP request that is started.
+	next_id: HttpRequestId,
+	/// List of HTTP requests in preparation or in progress.
+	requests: BTreeMap<HttpRequestId, HttpApiRequest>,
+}
+
+/// One active request within `HttpApi`.
+enum HttpApiRequest {
+	/// The request object is being constructed locally and not started yet.
+	NotDispatched(hyper::Request<Body>, hyper::body::Sender),
+	/// The request has been dispatched and we're in the process of sending out the body (if the
+	/// field is `Some`) or waiting for a response (if the field is `None`).
+	Dispatched(Option<hyper::body::Sender>),
+	/// Received a response.
+	Response(HttpApiRequestRp),
+	/// A request has been dispatched but the worker notified us of an error. We report this
+	/// failure to the user as an `IoError` and remove the request from the list as soon as
+	/// possible.
+	Fail(hyper::Error),
+}
+
+/// A request within `HttpApi` that has received a response.
+struct HttpApiRequestRp {
+	/// Status code of the response.
+	status_code: hyper::StatusCode,
+	/// Headers of the response.
+	headers: hyper::HeaderMap,
+	/// Body of the response, as a channel of `Chunk` objects.
+	/// While the code is designed to drop the `Receiver` once it ends, we wrap it within a
+	/// `Fuse` in order to be extra precautious about panics.
+	body: Fuse<mpsc::Receiver<Result<Chunk, hyper::Error>>>,
+	/// Chunk that has been extracted from the channel and that is currently being read.
+	/// Reading data from the response should read from this field in priority.
+	current_read_chunk: Option<Cursor<Chunk>>,
+}
+
+impl HttpApi {
+	/// Mimicks the corresponding method in the offchain API.
+	pub fn request_start(
+		&mut self,
+		method: &str,
+		uri: &str
+	) -> Result<HttpRequestId, ()> {
+		// Start by building the prototype of the request.
+		// We do this first so that we don't touch anything in `self` if building the prototype
+		// fails.
+		let (body_sender, body) = hyper::Body::channel();
+		let mut request = hyper::Request::new(body);
+		*request.method_mut() = hyper::Method::from_bytes(method.as_bytes()).map_err(|_| ())?;
+		*request.uri_mut() = uri.parse::<hyper::Uri>().map_err(|_| ())?;
+
+		let new_id = self.next_id;
+		debug_assert!(!self.requests.contains_key(&new_id));
+		match self.next_id.0.checked_add(1) {
+			Some(new_id) => self.next_id.0 = new_id,
+			None => {
+				error!("Overflow in offchain worker HTTP request ID assignment");
+				return Err(());
+			}
+		};
+		self.requests.insert(new_id, HttpApiRequest::NotDispatched(request, body_sender));
+
+		Ok(new_id)
+	}
+
+	/// Mimicks the corresponding method in the offchain API.
+	pub fn request_add_header(
+		&mut self,
+		request_id: HttpRequestId,
+		name: &str,
+		value: &str
+	) -> Result<(), ()> {
+		let request = match self.requests.get_mut(&request_id) {
+			Some(&mut HttpApiRequest::NotDispatched(ref mut rq, _)) => rq,
+			_ => return Err(())
+		};
+
+		let name = hyper::header::HeaderName::from_bytes(name.as_bytes()).map_err(|_| ())?;
+		let value = hyper::header::HeaderValue::from_str(value).map_err(|_| ())?;
+		// Note that we're always appending headers and never replacing old values.
+		// We assume here that the user knows what they're doing.
+		request.headers_mut().append(name, value);
+		Ok(())
+	}
+
+	/// Mimicks the corresponding method in the offchain API.
+	pub fn request_write_body(
+		&mut self,
+		request_id: HttpRequestId,
+		chunk: &[u8],
+		deadline: Option<Timestamp>
+	) -> Result<(), HttpError> {
+		// Extract the request from the list.
+		// Don't forget to add it back if necessary when returning.
+		let mut request = match self.requests.remove(&request_id) {
+			None => return Err(HttpError::IoError),
+			Some(r) => r,
+		};
+
+		loop {
+			request = match request {
+				HttpApiRequest::NotDispatched(request, sender) => {
+					// If the request is not dispatched yet, dispatch it and loop again.
+					let _ = self.to_worker.unbounded_send(ApiToWorker::Dispatch {
+						id: request_id,
+						request
+					});
+					HttpApiRequest::Dispatched(Some(sender))
+				}
+
+				HttpApiRequest::Dispatched(Some(mut sender)) => {
+					// Writing an empty chunk finalises the body of the request.
+					if chunk.is_empty() {
+						self.requests.insert(request_id, HttpApiRequest::Dispatched(None));
+						return Ok(())
+					}
+
+					match block_until(future::poll_fn(|| sender.poll_ready()), deadline) {
+						Some(Ok(())) => {},
+						Some(Err(_)) => {
+							// The body channel has been closed by the other side, which means
+							// that the request is over. The worker will tell us why.
+							self.requests.insert(request_id, HttpApiRequest::Dispatched(None));
+							return Err(HttpError::IoError)
+						},
+						None => {
+							self.requests.insert(request_id, HttpApiRequest::Dispatched(Some(sender)));
+							return Err(HttpError::DeadlineReached)
+						},
+					}
+
+					let result = sender.send_data(Chunk::from(chunk.to_vec()))
+						.map_err(|_| HttpError::IoError);
+					self.requests.insert(request_id, HttpApiRequest::Dispatched(Some(sender)));
+					return result
+				}
+
+				HttpApiRequest::Fail(_) =>
+					// If the request has already failed, return without putting back the request
+					// in the list.
+					return Err(HttpError::IoError),
+
+				v @ HttpApiRequest::Dispatched(None) |
+				v @ HttpApiRequest::Response(_) => {
+					// We have already finished sending this body.
+					self.requests.insert(request_id, v);
+					return Err(HttpError::IoError)
+				}
+			}
+		}
+	}
+
+	/// Mimicks the corresponding method in the offchain API.
+	pub fn response_wait(
+		&mut self,
+		ids: &[HttpRequestId],
+		deadline: Option<Timestamp>
+	) -> Vec<HttpRequestStatus> {
+		// First of all, dispatch all the non-dispatched requests and drop all senders so that the
+		// user can't write anymore data.
+		for id in ids {
+			match self.requests.get_mut(id) {
+				Some(HttpApiRequest::NotDispatched(_, _)) => {}
+				Some(HttpApiRequest::Dispatched(sending_body)) => {
+					*sending_body = None;
+					continue;
+				}
+				_ => continue
+			};
+
+			let (request, _sender) = match self.requests.remove(id) {
+				Some(HttpApiRequest::NotDispatched(rq, s)) => (rq, s),
+				_ => unreachable!("we checked for NotDispatched above; qed")
+			};
+
+			let _ = self.to_worker.unbounded_send(ApiToWorker::Dispatch {
+				id: *id,
+				request
+			});
+
+			// We also destroy the sender in order to forbid writing more data.
+			self.requests.insert(*id, HttpApiRequest::Dispatched(None));
+		}
+
+		loop {
+			// Within that loop, first try to see if we have all the elements for a response.
+			// This includes the situation where the deadline is reached.
+			{
+				let mut output = Vec::with_capacity(ids.len());
+				let mut must_wait_more = false;
+				for id in ids {
+					output.push(match self.requests.get(id) {
+						None => HttpRequestStatus::Unknown,
+						Some(HttpApiRequest::NotDispatched(_, _)) =>
+							unreachable!("we replaced all the NotDispatched with Dispatched earlier; qed"),
+						Some(HttpApiRequest::Dispatched(_)) => {
+							must_wait_more = true;
+							HttpRequestStatus::DeadlineReached
+						},
+						// The host couldn't complete the request, which from the point of view of
+						// the runtime is equivalent to the request having been terminated.
+						Some(HttpApiRequest::Fail(_)) => HttpRequestStatus::Timeout,
+						Some(HttpApiRequest::Response(HttpApiRequestRp { status_code, .. })) =>
+							HttpRequestStatus::Finished(status_code.as_u16()),
+					});
+				}
+				debug_assert_eq!(output.len(), ids.len());
+
+				// Are we ready to call `return`?
+				let is_done = if !must_wait_more {
+					true
+				} else {
+					timestamp::deadline_to_duration(deadline)
+						.map(|d| d.as_millis() == 0)
+						.unwrap_or(false)
+				};
+
+				if is_done {
+					// Requests in "fail" mode are purged before returning.
+					for n in (0..ids.len()).rev() {
+						if let Some(HttpApiRequest::Fail(error)) = self.requests.get(&ids[n]) {
+							warn!("Offchain worker HTTP request {:?} failed: {}", ids[n], error);
+							self.requests.remove(&ids[n]);
+						}
+					}
+					return output
+				}
+			}
+
+			// Grab next message from the worker.
+			let next_message = match block_until(future::poll_fn(|| self.from_worker.poll()), deadline) {
+				Some(Ok(msg)) => msg,
+				Some(Err(())) => None,
+				// Deadline reached; the output will be computed at the next iteration.
+				None => continue,
+			};
+
+			match next_message {
+				Some(WorkerToApi::Response { id, status_code, headers, body }) =>
+					match self.requests.remove(&id) {
+						Some(HttpApiRequest::Dispatched(_)) => {
+							self.requests.insert(id, HttpApiRequest::Response(HttpApiRequestRp {
+								status_code,
+								headers,
+								body: body.fuse(),
+								current_read_chunk: None,
+							}));
+						}
+						None => {}	// can happen if we detected an IO error when sending the body
+						_ => error!("State mismatch between the API and worker"),
+					}
+
+				Some(WorkerToApi::Fail { id, error }) =>
+					match self.requests.remove(&id) {
+						Some(HttpApiRequest::Dispatched(_)) => {
+							self.requests.insert(id, HttpApiRequest::Fail(error));
+						}
+						None => {}	// can happen if we detected an IO error when sending the body
+						_ => error!("State mismatch between the API and worker"),
+					}
+
+				None => {
+					error!("Worker has crashed");
+					return ids.iter().map(|_| HttpRequestStatus::Unknown).collect()
+				}
+			}
+		}
+	}
+
+	/// Mimicks the corresponding method in the offchain API.
+	pub fn response_headers(
+		&mut self,
+		request_id: HttpRequestId
+	) -> Vec<(Vec<u8>, Vec<u8>)> {
+		match self.requests.get(&request_id) {
+			Some(HttpApiRequest::Response(HttpApiRequestRp { headers, .. })) =>
+				headers
+					.iter()
+					.map(|(name, value)| (name.as_str().as_bytes().to_owned(), value.as_bytes().to_owned()))
+					.collect(),
+			_ => Vec::new()
+		}
+	}
+
+	/// Mimicks the corresponding method in the offchain API.
+	pub fn response_read_body(
+		&mut self,
+		request_id: HttpRequestId,
+		buffer: &mut [u8],
+		deadline: Option<Timestamp>
+	) -> Result<usize, HttpError> {
+		// Do an implicit wait on the request.
+		let _ = self.response_wait(&[request_id], deadline);
+
+		// Remove the request from the list and handle situations where the request is invalid or
+		// in the wrong state.
+		let mut response = match self.requests.remove(&request_id) {
+			Some(HttpApiRequest::Response(r)) => r,
+			// Because we called `response_wait` above, we know that the deadline has been reached
+			// and we still haven't received a response.
+			Some(rq @ HttpApiRequest::Dispatched(_)) => {
+				self.requests.insert(request_id, rq);
+				return Err(HttpError::DeadlineReached)
+			},
+			// The request has failed.
+			Some(HttpApiRequest::Fail { .. }) =>
+				return Err(HttpError::IoError),
+			// Request hasn't been dispatched yet; reading the body is invalid.
+			Some(rq) => {
+				self.requests.insert(request_id, rq);
+				return Err(HttpError::IoError)
+			}
+			None => return Err(HttpError::IoError)
+		};
+
+		loop {
+			// First read from `current_read_chunk`.
+			if let Some(mut current_read_chunk) = response.current_read_chunk.take() {
+				match current_read_chunk.read(buffer) {
+					Ok(0) => {}
+					Ok(n) => {
+						self.requests.insert(request_id, HttpApiRequest::Response(HttpApiRequestRp {
+							current_read_chunk: Some(current_read_chunk),
+							.. response
+						}));
+						return Ok(n)
+					},
+					Err(err) => {
+						// This code should never be reached unless there's a logic error somewhere.
+						error!("Failed to read from current read chunk: {:?}", err);
+						return Err(HttpError::IoError)
+					}
+				}
+			}
+
+			// If we reach here, that means the `current_read_chunk` is empty and needs to be
+			// filled with a new chunk from `body`. We block on `body` until either a chunk
+			// arrives or the deadline is reached.
+			match block_until(future::poll_fn(|| response.body.poll()), deadline) {
+				Some(Ok(Some(Ok(chunk)))) => response.current_read_chunk = Some(Cursor::new(chunk)),
+				Some(Ok(Some(Err(hyper_err)))) => {
+					warn!("Error while reading the body of an offchain worker HTTP request: {}", hyper_err);
+					return Err(HttpError::IoError)
+				},
+				// End of the body. The request is removed from the list as per the spec.
+				Some(Ok(None)) | Some(Err(())) => return Ok(0),
+				None => {
+					self.requests.insert(request_id, HttpApiRequest::Response(response));
+					return Err(HttpError::DeadlineReached)
+				}
+			}
+		}
+	}
+}
+
+/// Message send from the API to the worker.
+enum ApiToWorker {
+	/// Dispatches a new HTTP request.
+	Dispatch {
+		/// ID to send back when the response comes back.
+		id: HttpRequestId,
+		/// Request to start executing.
+		request: hyper::Request<Body>,
+	}
+}
+
+/// Message send from the worker to the API.
+enum WorkerToApi {
+	/// A request has succeeded.
+	Response {
+		/// The ID that was passed to the worker.
+		id: HttpRequestId,
+		/// Status code of the response.
+		status_code: hyper::StatusCode,
+		/// Headers of the response.
+		headers: hyper::HeaderMap,
+		/// Body of the response, as a channel of `Chunk` objects.
+		/// We send the body back through a channel instead of returning the hyper `Body` object
+		/// because we don't want the `HttpApi` to have to drive the reading.
+		/// Instead, reading an item from the channel will notify the worker task, which will push
+		/// the next item.
+		body: mpsc::Receiver<Result<Chunk, hyper::Error>>,
+	},
+	/// A request has failed because of an error.
+	Fail {
+		/// The ID that was passed to the worker.
+		id: HttpRequestId,
+		/// Error that happened.
+		error: hyper::Error,
+	},
+}
+
+/// Must be continuously polled for the [`HttpApi`] to properly work.
+pub struct HttpWorker {
+	/// Used to sends messages to the `HttpApi`.
+	to_api: mpsc::UnboundedSender<WorkerToApi>,
+	/// Used to receive messages from the `HttpApi`.
+	from_api: mpsc::UnboundedReceiver<ApiToWorker>,
+	/// The engine that runs HTTP requests.
+	http_client: HyperClient<HttpConnector, Body>,
+	/// HTTP requests that are being worked on by the engine.
+	requests: Vec<(HttpRequestId, HttpWorkerRequest)>,
+}
+
+/// HTTP request being processed by the worker.
+enum HttpWorkerRequest {
+	/// Request has been dispatched and is waiting for a response.
+	Dispatched(hyper::client::ResponseFuture),
+	/// Reading the body of the response and sending it to the channel.
+	ReadBody {
+		/// Body to read `Chunk`s from.
+		body: Body,
+		/// Where to send the chunks.
+		tx: mpsc::Sender<Result<Chunk, hyper::Error>>,
+	},
+}
+
+impl Future for HttpWorker {
+	type Item = ();
+	type Error = ();
+
+	fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+		// Reminder: this is continuously run in the background.
+
+		// Start by checking for messages coming from the `HttpApi`.
+		let mut api_is_gone = false;
+		loop {
+			match self.from_api.poll() {
+				Ok(Async::Ready(Some(ApiToWorker::Dispatch { id, request }))) => {
+					let future = self.http_client.request(request);
+					debug_assert!(self.requests.iter().all(|(i, _)| *i != id));
+					self.requests.push((id, HttpWorkerRequest::Dispatched(future)));
+				},
+				Ok(Async::NotReady) => break,
+				Ok(Async::Ready(None)) | Err(()) => {
+					api_is_gone = true;
+					break
+				},
+			}
+		}
+
+		// Then advance every request that is in progress.
+		for n in (0..self.requests.len()).rev() {
+			let (id, request) = self.requests.swap_remove(n);
+			match request {
+				HttpWorkerRequest::Dispatched(mut future) => {
+					// Check for an HTTP response from the Internet.
+					let response = match future.poll() {
+						Ok(Async::NotReady) => {
+							self.requests.push((id, HttpWorkerRequest::Dispatched(future)));
+							continue
+						},
+						Ok(Async::Ready(response)) => response,
+						Err(error) => {
+							let _ = self.to_api.unbounded_send(WorkerToApi::Fail { id, error });
+							continue;		// don't insert the request back
+						}
+					};
+
+					// We received a response! Decompose it into its parts.
+					let (head, body) = response.into_parts();
+					let (status_code, headers) = (head.status, head.headers);
+
+					let (body_tx, body_rx) = mpsc::channel(3);
+					let _ = self.to_api.unbounded_send(WorkerToApi::Response {
+						id,
+						status_code,
+						headers,
+						body: body_rx,
+					});
+
+					self.requests.push((id, HttpWorkerRequest::ReadBody { body, tx: body_tx }));
+					// Make sure that the body gets polled as well.
+					task::current().notify();
+				}
+
+				HttpWorkerRequest::ReadBody { mut body, mut tx } => {
+					// Before reading from the HTTP response, check that `tx` is ready to accept
+					// a new chunk.
+					match tx.poll_ready() {
+						Ok(Async::Ready(())) => {},
+						Ok(Async::NotReady) => {
+							self.requests.push((id, HttpWorkerRequest::ReadBody { body, tx }));
+							continue
+						},
+						// The `Receiver` has been dropped by the API, so we simply drop the body.
+						Err(_) => continue,
+					}
+
+					match body.poll() {
+						Ok(Async::Ready(Some(chunk))) => {
+							let _ = tx.start_send(Ok(chunk));
+							self.requests.push((id, HttpWorkerRequest::ReadBody { body, tx }));
+							task::current().notify();
+						},
+						Ok(Async::Ready(None)) => {},		// EOF; dropping `tx` closes the channel.
+						Ok(Async::NotReady) => {
+							self.requests.push((id, HttpWorkerRequest::ReadBody { body, tx }));
+						},
+						Err(err) => {
+							let _ = tx.start_send(Err(err));
+						},
+					}
+				}
+			}
+		}
+
+		if api_is_gone && self.requests.is_empty() {
+			Ok(Async::Ready(()))
+		} else {
+			Ok(Async::NotReady)
+		}
+	}
+}
+
+/// Wakes up the blocked thread when the polled future gets notified.
+struct ThreadNotify(thread::Thread);
+
+impl executor::Notify for ThreadNotify {
+	fn notify(&self, _id: usize) {
+		self.0.unpark()
+	}
+}
+
+/// Polls `future` on the current thread until it finishes or the `deadline` is reached.
+///
+/// Returns `None` if the deadline has been reached first. Passing `None` as deadline
+/// blocks forever.
+fn block_until<F: Future>(
+	future: F,
+	deadline: Option<Timestamp>,
+) -> Option<Result<F::Item, F::Error>> {
+	let notify = Arc::new(ThreadNotify(thread::current()));
+	let mut future = executor::spawn(future);
+
+	loop {
+		match future.poll_future_notify(&notify, 0) {
+			Ok(Async::Ready(item)) => return Some(Ok(item)),
+			Err(err) => return Some(Err(err)),
+			Ok(Async::NotReady) => {},
+		}
+
+		match timestamp::deadline_to_duration(deadline) {
+			None => thread::park(),
+			Some(ref duration) if duration.as_millis() == 0 => return None,
+			Some(duration) => thread::park_timeout(duration),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::http;
+	use crate::api::timestamp;
+	use futures::prelude::*;
+	use primitives::offchain::{HttpError, HttpRequestStatus, Duration};
+
+	// Returns an `HttpApi` whose worker is spawned on `runtime` together with a local HTTP
+	// server answering every request with "Hello World!". Returns the server port too.
+	fn build_api_server(runtime: &mut tokio::runtime::Runtime) -> (super::HttpApi, u16) {
+		let (api, worker) = http();
+		runtime.spawn(worker);
+
+		let addr = ([127, 0, 0, 1], 0).into();
+		let server = hyper::Server::bind(&addr)
+			.serve(|| hyper::service::service_fn_ok(|_| {
+				hyper::Response::new(hyper::Body::from("Hello World!"))
+			}));
+		let port = server.local_addr().port();
+		runtime.spawn(server.map_err(|_| ()));
+
+		(api, port)
+	}
+
+	#[test]
+	fn basic_localhost() {
+		let deadline = timestamp::now().add(Duration::from_millis(10_000));
+		let mut runtime = tokio::runtime::Runtime::new().unwrap();
+		let (mut api, port) = build_api_server(&mut runtime);
+
+		let id = api.request_start("POST", &format!("http://localhost:{}", port)).unwrap();
+		api.request_add_header(id, "Content-Type", "text/plain").unwrap();
+		api.request_write_body(id, &[], Some(deadline)).unwrap();
+		match api.response_wait(&[id], Some(deadline))[0] {
+			HttpRequestStatus::Finished(200) => {},
+			v => panic!("Connecting to localhost failed: {:?}", v)
+		}
+
+		let headers = api.response_headers(id);
+		assert!(headers.iter().any(|(h, _)| h.eq_ignore_ascii_case(b"Date")));
+
+		let mut buf = vec![0; 2048];
+		let n = api.response_read_body(id, &mut buf, Some(deadline)).unwrap();
+		assert_eq!(&buf[..n], b"Hello World!");
+		assert_eq!(api.response_read_body(id, &mut buf, Some(deadline)).unwrap(), 0);
+		assert_eq!(api.response_read_body(id, &mut buf, Some(deadline)), Err(HttpError::IoError));
+	}
+
+	#[test]
+	fn request_write_body_invalid_call() {
+		let deadline = timestamp::now().add(Duration::from_millis(10_000));
+		let mut runtime = tokio::runtime::Runtime::new().unwrap();
+		let (mut api, port) = build_api_server(&mut runtime);
+
+		let id = api.request_start("POST", &format!("http://localhost:{}", port)).unwrap();
+		api.request_write_body(id, &[1, 2, 3, 4], None).unwrap();
+		api.request_write_body(id, &[], None).unwrap();
+		assert_eq!(api.request_write_body(id, &[], None), Err(HttpError::IoError));
+		assert_eq!(api.request_write_body(id, &[1, 2, 3, 4], None), Err(HttpError::IoError));
+		assert_eq!(api.request_add_header(id, "Foo", "Bar"), Err(()));
+
+		match api.response_wait(&[id], Some(deadline))[0] {
+			HttpRequestStatus::Finished(200) => {},
+			v => panic!("Connecting to localhost failed: {:?}", v)
+		}
+		assert_eq!(api.request_write_body(id, &[], None), Err(HttpError::IoError));
+	}
+
+	#[test]
+	fn unknown_and_invalid_requests() {
+		let mut runtime = tokio::runtime::Runtime::new().unwrap();
+		let (mut api, _) = build_api_server(&mut runtime);
+
+		assert!(api.request_start("\0", "http://localhost").is_err());
+		assert!(api.request_start("GET", "not a uri").is_err());
+
+		let unknown = primitives::offchain::HttpRequestId(0xffff);
+		assert_eq!(api.response_wait(&[unknown], None), vec![HttpRequestStatus::Unknown]);
+		assert!(api.response_headers(unknown).is_empty());
+		assert_eq!(api.request_add_header(unknown, "Foo", "Bar"), Err(()));
+	}
+
+	#[test]
+	fn connection_refused_is_reported() {
+		let deadline = timestamp::now().add(Duration::from_millis(10_000));
+		let mut runtime = tokio::runtime::Runtime::new().unwrap();
+		let (mut api, _) = build_api_server(&mut runtime);
+
+		// Port 1 is reserved and nothing should be listening there.
+		let id = api.request_start("GET", "http://127.0.0.1:1").unwrap();
+		assert_eq!(api.response_wait(&[id], Some(deadline)), vec![HttpRequestStatus::Timeout]);
+		assert_eq!(api.response_wait(&[id], Some(deadline)), vec![HttpRequestStatus::Unknown]);
+	}
+}
@@ -36,11 +36,17 @@
 use std::{
 	marker::PhantomData,
 	sync::Arc,
+	thread,
 };
 
 use client::runtime_api::ApiExt;
-use log::{debug, warn};
-use primitives::ExecutionContext;
+use keystore::Store as Keystore;
+use log::{debug, error, warn};
+use primitives::{
+	ExecutionContext,
+	ed25519,
+	offchain::OffchainStorage,
+};
 use runtime_primitives::{
 	generic::BlockId,
 	traits::{self, ProvideRuntimeApi},
@@ -54,34 +60,60 @@ pub mod testing;
 
 pub use offchain_primitives::OffchainWorkerApi;
 
+/// Provides currently configured authority key.
+///
+/// The key is used by offchain workers to sign and verify data when no
+/// explicit `CryptoKeyId` is given.
+pub trait AuthorityKeyProvider: Clone + Send + 'static {
+	/// Returns currently configured authority key.
+	fn authority_key(&self) -> Option<ed25519::Pair>;
+}
+
 /// An offchain workers manager.
 #[derive(Debug)]
-pub struct OffchainWorkers<C, Block: traits::Block> {
+pub struct OffchainWorkers<C, S, KP, Block: traits::Block> {
 	client: Arc<C>,
+	db: S,
+	authority_key: KP,
+	keystore: Arc<Keystore>,
 	executor: TaskExecutor,
 	_block: PhantomData<Block>,
 }
 
-impl<C, Block: traits::Block> OffchainWorkers<C, Block> {
+impl<C, S, KP, Block: traits::Block> OffchainWorkers<C, S, KP, Block> {
 	/// Creates new `OffchainWorkers`.
+	///
+	/// Keys generated by the workers are held in `keystore`, `db` only references them.
 	pub fn new(
 		client: Arc<C>,
+		db: S,
+		authority_key: KP,
+		keystore: Arc<Keystore>,
 		executor: TaskExecutor,
 	) -> Self {
 		Self {
 			client,
+			db,
+			authority_key,
+			keystore,
 			executor,
 			_block: PhantomData,
 		}
 	}
 }
 
-impl<C, Block> OffchainWorkers<C, Block> where
+impl<C, S, KP, Block> OffchainWorkers<C, S, KP, Block> where
 	Block: traits::Block,
-	C: ProvideRuntimeApi,
+	C: ProvideRuntimeApi + Send + Sync + 'static,
 	C::Api: OffchainWorkerApi<Block>,
+	S: OffchainStorage + 'static,
+	KP: AuthorityKeyProvider,
 {
 	/// Start the offchain workers after given block.
+	///
+	/// The runtime is called on a dedicated thread, since offchain workers may block while
+	/// sleeping or waiting for HTTP responses. The requests themselves are driven by a task
+	/// spawned on the executor.
 	pub fn on_block_imported<A>(
 		&self,
 		number: &<Block::Header as traits::Header>::Number,
@@ -95,12 +127,33 @@ impl<C, Block> OffchainWorkers<C, Block> where
 		debug!("Checking offchain workers at {:?}: {:?}", at, has_api);
 
 		if has_api.unwrap_or(false) {
-			let (api, runner) = api::Api::new(pool.clone(), at.clone());
+			let (api, runner) = api::AsyncApi::new(
+				pool.clone(),
+				self.db.clone(),
+				self.keystore.clone(),
+				self.authority_key.clone(),
+				at.clone(),
+			);
 			self.executor.spawn(runner.process());
 
-			debug!("Running offchain workers at {:?}", at);
-			let api = Box::new(api);
-			runtime.offchain_worker_with_context(&at, ExecutionContext::OffchainWorker(api), *number).unwrap();
+			debug!("Spawning offchain workers at {:?}", at);
+			let client = self.client.clone();
+			let number = *number;
+			let spawned = thread::Builder::new()
+				.name(format!("offchain-worker-{:?}", at))
+				.spawn(move || {
+					let runtime = client.runtime_api();
+					let api = Box::new(api);
+					debug!("Running offchain workers at {:?}", at);
+					let run = runtime.offchain_worker_with_context(&at, ExecutionContext::OffchainWorker(api), number);
+					if let Err(e) = run {
+						error!("Error running offchain workers at {:?}: {:?}", at, e);
+					}
+				});
+
+			if let Err(e) = spawned {
+				warn!("Unable to spawn offchain workers thread: {:?}", e);
+			}
 		}
 	}
 }
@@ -109,6 +162,16 @@ impl<C, Block> OffchainWorkers<C, Block> where
 mod tests {
 	use super::*;
 	use futures::Future;
+	use primitives::offchain::InMemOffchainStorage;
+
+	#[derive(Clone)]
+	pub(crate) struct TestProvider(pub Option<ed25519::Pair>);
+
+	impl AuthorityKeyProvider for TestProvider {
+		fn authority_key(&self) -> Option<ed25519::Pair> {
+			self.0.clone()
+		}
+	}
 
 	#[test]
 	fn should_call_into_runtime_and_produce_extrinsic() {
@@ -119,7 +182,14 @@ mod tests {
 		let pool = Arc::new(Pool::new(Default::default(), ::transaction_pool::ChainApi::new(client.clone())));
 
 		// when
-		let offchain = OffchainWorkers::new(client, runtime.executor());
+		let db = InMemOffchainStorage::default();
+		let offchain = OffchainWorkers::new(
+			client,
+			db,
+			TestProvider(None),
+			Arc::new(Keystore::new_in_memory()),
+			runtime.executor(),
+		);
 		offchain.on_block_imported(&0u64, &pool);
 
 		// then
@@ -29,6 +29,8 @@ use primitives::offchain::{
 	Timestamp,
 	CryptoKind,
 	CryptoKeyId,
+	OffchainStorage,
+	InMemOffchainStorage,
 };
 
 /// Pending request.
@@ -61,6 +63,10 @@ pub struct PendingRequest {
 pub struct State {
 	/// A list of pending requests.
 	pub requests: BTreeMap<RequestId, PendingRequest>,
+	/// Local storage
+	pub local_storage: InMemOffchainStorage,
+	/// Current timestamp, advanced by `sleep_until`.
+	pub timestamp: Timestamp,
 }
 
 impl State {
@@ -118,32 +124,34 @@ impl offchain::Externalities for TestOffchainExt {
 	}
 
 	fn timestamp(&mut self) -> Timestamp {
-		unimplemented!("not needed in tests so far")
+		self.0.read().timestamp
 	}
 
-	fn sleep_until(&mut self, _deadline: Timestamp) {
-		unimplemented!("not needed in tests so far")
+	fn sleep_until(&mut self, deadline: Timestamp) {
+		let mut state = self.0.write();
+		// Time never goes backwards, even if the deadline is in the past.
+		state.timestamp = std::cmp::max(state.timestamp, deadline);
 	}
 
 	fn random_seed(&mut self) -> [u8; 32] {
 		unimplemented!("not needed in tests so far")
 	}
 
-	fn local_storage_set(&mut self, _key: &[u8], _value: &[u8]) {
-		unimplemented!("not needed in tests so far")
+	fn local_storage_set(&mut self, key: &[u8], value: &[u8]) {
+		self.0.write().local_storage.set(b"", key, value);
 	}
 
 	fn local_storage_compare_and_set(
 		&mut self,
-		_key: &[u8],
-		_old_value: &[u8],
-		_new_value: &[u8]
+		key: &[u8],
+		old_value: &[u8],
+		new_value: &[u8]
 	) {
-		unimplemented!("not needed in tests so far")
+		self.0.write().local_storage.compare_and_set(b"", key, Some(old_value), new_value);
 	}
 
-	fn local_storage_get(&mut self, _key: &[u8]) -> Option<Vec<u8>> {
-		unimplemented!("not needed in tests so far")
+	fn local_storage_get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+		self.0.read().local_storage.get(b"", key)
 	}
 
 	fn http_request_start(&mut self, method: &str, uri: &str, meta: &[u8]) -> Result<RequestId, ()> {
@@ -35,6 +35,28 @@ pub const DEV_ADDRESS: &str = "5DfhGyQdFobKM8NsWvEeAKk5EQQgYe9AydgJ7rMB6E1EqRzV"
 #[derive(Debug)]
 pub enum Infallible {}
 
+/// An identifier for a type of cryptographic key, i.e. the purpose a key is used for.
+pub type KeyTypeId = [u8; 4];
+
+/// Known key types. This also functions as a registry of key types, new ones should be added
+/// here to avoid collisions.
+pub mod key_types {
+	use super::KeyTypeId;
+
+	/// Key type for the generic ed25519 key of a node, e.g. its authority key.
+	pub const ED25519: KeyTypeId = *b"ed25";
+	/// Key type for the generic sr25519 key of a node.
+	pub const SR25519: KeyTypeId = *b"sr25";
+	/// Key type for the Aura consensus engine.
+	pub const AURA: KeyTypeId = *b"aura";
+	/// Key type for the BABE consensus engine.
+	pub const BABE: KeyTypeId = *b"babe";
+	/// Key type for the GRANDPA finality gadget.
+	pub const GRANDPA: KeyTypeId = *b"gran";
+	/// Key type for the keys generated by the offchain workers.
+	pub const OFFCHAIN: KeyTypeId = *b"offc";
+}
+
 /// The length of the junction identifier. Note that this is also referred to as the
 /// `CHAIN_CODE_LENGTH` in the context of Schnorrkel.
 #[cfg(feature = "std")]
@@ -407,6 +429,28 @@ pub trait Pair: Sized + 'static {
 	}
 }
 
+/// Something signing messages with the secret key of a public key, e.g. the key pair itself or
+/// a keystore holding it.
+#[cfg(feature = "std")]
+pub trait Sign<P: Pair>: Send + Sync {
+	/// Public key of the signing key.
+	fn signing_key(&self) -> P::Public;
+
+	/// Sign a message. `None` if the secret key isn't available anymore.
+	fn sign_message(&self, message: &[u8]) -> Option<P::Signature>;
+}
+
+#[cfg(feature = "std")]
+impl<P: Pair + Send + Sync> Sign<P> for P {
+	fn signing_key(&self) -> P::Public {
+		self.public()
+	}
+
+	fn sign_message(&self, message: &[u8]) -> Option<P::Signature> {
+		Some(self.sign(message))
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::DeriveJunction;
@@ -171,6 +171,64 @@ impl Timestamp {
 	}
 }
 
+/// Offchain DB persistent (non-fork-aware) storage.
+#[cfg(feature = "std")]
+pub trait OffchainStorage: Clone + Send + Sync {
+	/// Persist a value in storage under given key and prefix.
+	fn set(&mut self, prefix: &[u8], key: &[u8], value: &[u8]);
+
+	/// Retrieve a value from storage under given key and prefix.
+	fn get(&self, prefix: &[u8], key: &[u8]) -> Option<Vec<u8>>;
+
+	/// Replace the value in storage if given old_value matches the current one,
+	/// `None` meaning that the value must not be set yet.
+	///
+	/// Returns `true` if the value has been set, `false` otherwise.
+	fn compare_and_set(
+		&mut self,
+		prefix: &[u8],
+		key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool;
+}
+
+/// In-memory storage for offchain workers.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct InMemOffchainStorage {
+	storage: std::sync::Arc<parking_lot::RwLock<std::collections::HashMap<Vec<u8>, Vec<u8>>>>,
+}
+
+#[cfg(feature = "std")]
+impl OffchainStorage for InMemOffchainStorage {
+	fn set(&mut self, prefix: &[u8], key: &[u8], value: &[u8]) {
+		let key = prefix.iter().chain(key).cloned().collect();
+		self.storage.write().insert(key, value.to_vec());
+	}
+
+	fn get(&self, prefix: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+		let key: Vec<u8> = prefix.iter().chain(key).cloned().collect();
+		self.storage.read().get(&key).cloned()
+	}
+
+	fn compare_and_set(
+		&mut self,
+		prefix: &[u8],
+		key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool {
+		let key: Vec<u8> = prefix.iter().chain(key).cloned().collect();
+		let mut storage = self.storage.write();
+		let is_set = storage.get(&key).map(|v| &**v) == old_value;
+		if is_set {
+			storage.insert(key, new_value.to_vec());
+		}
+		is_set
+	}
+}
+
 /// An extended externalities for offchain workers.
 pub trait Externalities {
 	/// Submit transaction.
@@ -407,4 +465,20 @@ mod tests {
 		assert_eq!(t.sub(Duration::from_millis(10)), Timestamp(0));
 		assert_eq!(t.diff(&Timestamp(3)), Duration(2));
 	}
+
+	#[test]
+	fn in_mem_storage_compare_and_set() {
+		let mut storage = InMemOffchainStorage::default();
+		storage.set(b"prefix", b"key", b"old");
+
+		assert!(!storage.compare_and_set(b"prefix", b"key", Some(&b"wrong"[..]), b"new"));
+		assert!(!storage.compare_and_set(b"prefix", b"key", None, b"new"));
+		assert_eq!(storage.get(b"prefix", b"key"), Some(b"old".to_vec()));
+		assert!(storage.compare_and_set(b"prefix", b"key", Some(&b"old"[..]), b"new"));
+		assert_eq!(storage.get(b"prefix", b"key"), Some(b"new".to_vec()));
+		assert_eq!(storage.get(b"other", b"key"), None);
+
+		assert!(storage.compare_and_set(b"other", b"key", None, b"new"));
+		assert_eq!(storage.get(b"other", b"key"), Some(b"new".to_vec()));
+	}
 }
@@ -18,10 +18,15 @@
 
 #[warn(missing_docs)]
 
+mod middleware;
+
 pub use substrate_rpc as apis;
+pub use middleware::{RpcMetrics, RpcMiddleware};
 
 use std::io;
-use log::error;
+use std::net::SocketAddr;
+use std::path::Path;
+use log::{error, warn};
 use sr_primitives::{traits::{Block as BlockT, NumberFor}, generic::SignedBlock};
 
 /// Maximal payload accepted by RPC servers.
@@ -30,10 +35,46 @@ const MAX_PAYLOAD: usize = 15 * 1024 * 1024;
 /// Default maximum number of connections for WS RPC servers.
 const WS_MAX_CONNECTIONS: usize = 100;
 
+/// Default maximum number of subscriptions of each WS or IPC RPC connection.
+const MAX_SUBSCRIPTIONS: usize = 1024;
+
 type Metadata = apis::metadata::Metadata;
-type RpcHandler = pubsub::PubSubHandler<Metadata>;
+type RpcHandler = pubsub::PubSubHandler<Metadata, RpcMiddleware>;
 pub type HttpServer = http::Server;
 pub type WsServer = ws::Server;
+pub type IpcServer = ipc::Server;
+
+/// Additional RPC methods served next to the default APIs, e.g. the ones of runtime modules.
+pub type RpcExtension = Vec<(String, jsonrpc_core::RemoteProcedure<Metadata>)>;
+
+/// Which RPC methods a server exposes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcMethods {
+	/// Expose unsafe methods only on servers listening on a loopback interface.
+	Safe,
+	/// Expose every method, including the unsafe ones, on every server.
+	Unsafe,
+}
+
+impl Default for RpcMethods {
+	fn default() -> Self {
+		RpcMethods::Safe
+	}
+}
+
+impl RpcMethods {
+	/// Returns true if the unsafe methods must be denied on a server listening on `addr`.
+	///
+	/// The HTTP and WS transports don't expose the address of the peer, so the policy is
+	/// decided for the whole server: only a server listening on a loopback interface is
+	/// guaranteed to serve local clients exclusively.
+	pub fn deny_unsafe(&self, addr: &SocketAddr) -> bool {
+		match self {
+			RpcMethods::Safe => !addr.ip().is_loopback(),
+			RpcMethods::Unsafe => false,
+		}
+	}
+}
 
 /// Construct rpc `IoHandler`
 pub fn rpc_handler<Block: BlockT, ExHash, S, C, A, Y>(
@@ -41,6 +82,7 @@ pub fn rpc_handler<Block: BlockT, ExHash, S, C, A, Y>(
 	chain: C,
 	author: A,
 	system: Y,
+	extension: RpcExtension,
 ) -> RpcHandler where
 	Block: BlockT + 'static,
 	ExHash: Send + Sync + 'static + sr_primitives::Serialize + sr_primitives::DeserializeOwned,
@@ -49,21 +91,57 @@ pub fn rpc_handler<Block: BlockT, ExHash, S, C, A, Y>(
 	A: apis::author::AuthorApi<ExHash, Block::Hash, Metadata=Metadata>,
 	Y: apis::system::SystemApi<Block::Hash, NumberFor<Block>>,
 {
-	let mut io = pubsub::PubSubHandler::default();
-	io.extend_with(state.to_delegate());
-	io.extend_with(chain.to_delegate());
-	io.extend_with(author.to_delegate());
-	io.extend_with(system.to_delegate());
+	let methods = state.to_delegate().into_iter()
+		.chain(chain.to_delegate())
+		.chain(author.to_delegate())
+		.chain(system.to_delegate())
+		.chain(extension)
+		.collect::<Vec<_>>();
+	let metrics = RpcMetrics::new(methods.iter().map(|(name, _)| name.clone()));
+
+	let mut io = pubsub::PubSubHandler::new(
+		jsonrpc_core::MetaIoHandler::with_middleware(RpcMiddleware::new(metrics))
+	);
+	io.extend_with(methods);
 	io
 }
 
+/// Replace the unsafe methods of the handler with ones returning an error, if the policy
+/// denies them on a server listening on `addr`.
+fn deny_unsafe(addr: &SocketAddr, methods: RpcMethods, io: &mut RpcHandler) {
+	if !methods.deny_unsafe(addr) {
+		return;
+	}
+
+	warn!("Unsafe RPC methods are not exposed on {}, use `--rpc-methods Unsafe` to expose them", addr);
+	for method in apis::UNSAFE_METHODS {
+		io.add_method(method, |_| -> jsonrpc_core::Result<jsonrpc_core::Value> {
+			Err(jsonrpc_core::Error {
+				code: jsonrpc_core::ErrorCode::MethodNotFound,
+				message: "Method not found".into(),
+				data: Some("Unsafe methods are not exposed on this interface".into()),
+			})
+		});
+	}
+}
+
 /// Start HTTP server listening on given address.
+///
+/// `max_calls_per_second` limits the calls accepted by the server as a whole, since HTTP
+/// requests aren't bound to a connection.
 pub fn start_http(
-	addr: &std::net::SocketAddr,
+	addr: &SocketAddr,
+	max_calls_per_second: Option<u32>,
 	cors: Option<&Vec<String>>,
-	io: RpcHandler,
+	methods: RpcMethods,
+	mut io: RpcHandler,
 ) -> io::Result<http::Server> {
-	http::ServerBuilder::new(io)
+	deny_unsafe(addr, methods, &mut io);
+
+	let meta = Metadata::with_rate_limit(max_calls_per_second);
+	http::ServerBuilder::with_meta_extractor(io, move |_: &http::hyper::Request<http::hyper::Body>| {
+		meta.clone()
+	})
 		.threads(4)
 		.health_api(("/health", "system_health"))
 		.rest_api(if cors.is_some() {
@@ -77,13 +155,26 @@ pub fn start_http(
 }
 
 /// Start WS server listening on given address.
+///
+/// `max_subscriptions` and `max_calls_per_second` limit each connection.
 pub fn start_ws(
-	addr: &std::net::SocketAddr,
+	addr: &SocketAddr,
 	max_connections: Option<usize>,
+	max_subscriptions: Option<usize>,
+	max_calls_per_second: Option<u32>,
 	cors: Option<&Vec<String>>,
-	io: RpcHandler,
+	methods: RpcMethods,
+	mut io: RpcHandler,
 ) -> io::Result<ws::Server> {
-	ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| Metadata::new(context.sender()))
+	deny_unsafe(addr, methods, &mut io);
+
+	let limits = apis::metadata::ConnectionLimits {
+		max_subscriptions: Some(max_subscriptions.unwrap_or(MAX_SUBSCRIPTIONS)),
+		max_calls_per_second,
+	};
+	ws::ServerBuilder::with_meta_extractor(io, move |context: &ws::RequestContext| {
+		Metadata::with_limits(context.sender(), limits)
+	})
 		.max_payload(MAX_PAYLOAD)
 		.max_connections(max_connections.unwrap_or(WS_MAX_CONNECTIONS))
 		.allowed_origins(map_cors(cors))
@@ -98,8 +189,170 @@ pub fn start_ws(
 		})
 }
 
+/// Start IPC server listening on given path.
+///
+/// Access to the server is controlled by the permissions of the socket file, which is only
+/// accessible by its owner on unix, so every method is exposed. `max_subscriptions` and
+/// `max_calls_per_second` limit each connection.
+pub fn start_ipc(
+	path: &Path,
+	max_subscriptions: Option<usize>,
+	max_calls_per_second: Option<u32>,
+	io: RpcHandler,
+) -> io::Result<ipc::Server> {
+	let endpoint = path.to_str().ok_or_else(|| io::Error::new(
+		io::ErrorKind::InvalidInput,
+		format!("IPC socket path {} is not valid UTF-8", path.display()),
+	))?;
+
+	let limits = apis::metadata::ConnectionLimits {
+		max_subscriptions: Some(max_subscriptions.unwrap_or(MAX_SUBSCRIPTIONS)),
+		max_calls_per_second,
+	};
+	let server = ipc::ServerBuilder::with_meta_extractor(io, move |context: &ipc::RequestContext| {
+		Metadata::with_limits(context.sender.clone(), limits)
+	})
+		.start(endpoint)?;
+	restrict_to_owner(path)?;
+	Ok(server)
+}
+
+/// Make the IPC socket file accessible by its owner only.
+#[cfg(unix)]
+fn restrict_to_owner(path: &Path) -> io::Result<()> {
+	use std::os::unix::fs::PermissionsExt;
+	std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+fn restrict_to_owner(_path: &Path) -> io::Result<()> {
+	Ok(())
+}
+
 fn map_cors<T: for<'a> From<&'a str>>(
 	cors: Option<&Vec<String>>
 ) -> http::DomainsValidation<T> {
 	cors.map(|x| x.iter().map(AsRef::as_ref).map(Into::into).collect::<Vec<_>>()).into()
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::Value;
+
+	fn handler() -> RpcHandler {
+		let mut io = pubsub::PubSubHandler::new(
+			jsonrpc_core::MetaIoHandler::with_middleware(RpcMiddleware::new(RpcMetrics::new(Vec::new())))
+		);
+		io.add_method("test_ping", |_| Ok(Value::String("pong".into())));
+		for method in apis::UNSAFE_METHODS {
+			io.add_method(method, |_| Ok(Value::Bool(true)));
+		}
+		io
+	}
+
+	fn call(io: &RpcHandler, method: &str, meta: Metadata) -> String {
+		let request = format!(r#"{{"jsonrpc":"2.0","method":"{}","params":[],"id":1}}"#, method);
+		io.handle_request_sync(&request, meta).unwrap()
+	}
+
+	#[test]
+	fn unsafe_methods_are_denied_on_public_interfaces() {
+		let local = "127.0.0.1:9933".parse().unwrap();
+		let public = "0.0.0.0:9933".parse().unwrap();
+		assert!(!RpcMethods::Safe.deny_unsafe(&local));
+		assert!(!RpcMethods::Safe.deny_unsafe(&"[::1]:9933".parse().unwrap()));
+		assert!(RpcMethods::Safe.deny_unsafe(&public));
+		assert!(!RpcMethods::Unsafe.deny_unsafe(&public));
+
+		let mut io = handler();
+		deny_unsafe(&local, RpcMethods::Safe, &mut io);
+		assert_eq!(call(&io, "system_addReservedPeer", Default::default()), r#"{"jsonrpc":"2.0","result":true,"id":1}"#);
+
+		let mut io = handler();
+		deny_unsafe(&public, RpcMethods::Safe, &mut io);
+		for method in apis::UNSAFE_METHODS {
+			assert!(call(&io, method, Default::default()).contains("Unsafe methods are not exposed on this interface"));
+		}
+		assert_eq!(call(&io, "test_ping", Default::default()), r#"{"jsonrpc":"2.0","result":"pong","id":1}"#);
+	}
+
+	#[cfg(unix)]
+	fn ipc_handler() -> RpcHandler {
+		use futures::Future;
+
+		let mut io = handler();
+		io.add_subscription(
+			"test_hello",
+			("test_subscribe", |_: jsonrpc_core::Params, _: Metadata, subscriber: pubsub::Subscriber| {
+				let sink = subscriber.assign_id(pubsub::SubscriptionId::Number(5)).unwrap();
+				std::thread::spawn(move || {
+					let _ = sink.notify(jsonrpc_core::Params::Array(vec![Value::String("hello".into())])).wait();
+				});
+			}),
+			("test_unsubscribe", |_: pubsub::SubscriptionId, _: Option<Metadata>| -> jsonrpc_core::Result<Value> {
+				Ok(Value::Bool(true))
+			}),
+		);
+		io
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn ipc_serves_calls_and_subscriptions() {
+		use std::io::{BufRead, BufReader, Write};
+		use std::os::unix::{fs::PermissionsExt, net::UnixStream};
+
+		let dir = tempdir::TempDir::new("substrate-rpc-ipc").unwrap();
+		let path = dir.path().join("rpc.ipc");
+		let _server = start_ipc(&path, None, Some(3), ipc_handler()).unwrap();
+		assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+		let connect = || {
+			let stream = UnixStream::connect(&path).unwrap();
+			let responses = BufReader::new(stream.try_clone().unwrap()).lines().map(Result::unwrap);
+			(stream, responses)
+		};
+		let ping = |id: u32| format!("{{\"jsonrpc\":\"2.0\",\"method\":\"test_ping\",\"params\":[],\"id\":{}}}\n", id);
+
+		let (mut stream, mut responses) = connect();
+		stream.write_all(ping(1).as_bytes()).unwrap();
+		assert_eq!(responses.next().unwrap(), r#"{"jsonrpc":"2.0","result":"pong","id":1}"#);
+
+		stream.write_all(b"{\"jsonrpc\":\"2.0\",\"method\":\"test_subscribe\",\"params\":[],\"id\":2}\n").unwrap();
+		let (first, second) = (responses.next().unwrap(), responses.next().unwrap());
+		let (response, notification) = if first.contains("\"id\":2") { (first, second) } else { (second, first) };
+		assert_eq!(response, r#"{"jsonrpc":"2.0","result":5,"id":2}"#);
+		assert_eq!(notification, r#"{"jsonrpc":"2.0","method":"test_hello","params":["hello"]}"#);
+
+		// The rate limit applies to each connection.
+		stream.write_all(ping(3).as_bytes()).unwrap();
+		assert!(responses.next().unwrap().contains("pong"));
+		stream.write_all(ping(4).as_bytes()).unwrap();
+		assert!(responses.next().unwrap().contains("\"code\":6002"));
+
+		let (mut stream, mut responses) = connect();
+		stream.write_all(ping(5).as_bytes()).unwrap();
+		assert_eq!(responses.next().unwrap(), r#"{"jsonrpc":"2.0","result":"pong","id":5}"#);
+	}
+
+	#[test]
+	fn calls_exceeding_rate_limit_are_rejected() {
+		let io = handler();
+		let meta = Metadata::with_rate_limit(Some(2));
+
+		assert_eq!(call(&io, "test_ping", meta.clone()), r#"{"jsonrpc":"2.0","result":"pong","id":1}"#);
+		let batch = r#"[
+			{"jsonrpc":"2.0","method":"test_ping","params":[],"id":2},
+			{"jsonrpc":"2.0","method":"test_ping","params":[]},
+			{"jsonrpc":"2.0","method":"test_ping","params":[],"id":3}
+		]"#;
+		let response = io.handle_request_sync(batch, meta.clone()).unwrap();
+		assert_eq!(response.matches("\"code\":6002").count(), 2);
+		assert!(!response.contains("pong"));
+
+		// Rejected calls don't count towards the limit.
+		assert!(call(&io, "test_ping", meta.clone()).contains("pong"));
+		assert!(call(&io, "test_ping", meta).contains("\"code\":6002"));
+	}
+}
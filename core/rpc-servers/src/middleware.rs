@@ -0,0 +1,120 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC middleware.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use futures::future::{self, Either};
+use jsonrpc_core::{Call, FutureResponse, Middleware, Output, Request, Response};
+use substrate_metrics::{CounterVec, register_counter_vec};
+use substrate_rpc::metadata::{self, Metadata};
+
+/// Label of the calls of methods that aren't served.
+const UNKNOWN_METHOD: &str = "unknown";
+
+/// Counts the calls of each RPC method.
+pub struct RpcMetrics {
+	calls: Arc<CounterVec>,
+	/// Names of the served methods. Calls of other methods are counted together, so that
+	/// clients can't grow the registry with arbitrary method names.
+	methods: HashSet<String>,
+}
+
+impl RpcMetrics {
+	/// Create a new counter of the calls of the given methods, exporting the counts in the
+	/// global metrics registry.
+	pub fn new(methods: impl IntoIterator<Item=String>) -> Self {
+		RpcMetrics {
+			calls: register_counter_vec("substrate_rpc_calls_total", "Number of RPC calls", "method"),
+			methods: methods.into_iter().collect(),
+		}
+	}
+
+	fn note_call(&self, call: &Call) {
+		let method = match call {
+			Call::MethodCall(call) => &call.method,
+			Call::Notification(notification) => &notification.method,
+			_ => return,
+		};
+
+		if self.methods.contains(method) {
+			self.calls.inc(method);
+		} else {
+			self.calls.inc(UNKNOWN_METHOD);
+		}
+	}
+}
+
+/// Middleware counting the calls of each RPC method and rejecting the calls exceeding the
+/// rate limit of the connection.
+pub struct RpcMiddleware {
+	metrics: RpcMetrics,
+}
+
+impl RpcMiddleware {
+	/// Create a new middleware, counting the calls with the given metrics.
+	pub fn new(metrics: RpcMetrics) -> Self {
+		RpcMiddleware {
+			metrics,
+		}
+	}
+}
+
+/// Failure output for a call rejected by the rate limit, `None` for notifications.
+fn rate_limited(call: &Call) -> Option<Output> {
+	match call {
+		Call::MethodCall(call) => Some(Output::from(
+			Err(metadata::rate_limit_exceeded()),
+			call.id.clone(),
+			call.jsonrpc,
+		)),
+		_ => None,
+	}
+}
+
+impl Middleware<Metadata> for RpcMiddleware {
+	type Future = FutureResponse;
+
+	fn on_request<F, X>(&self, request: Request, meta: Metadata, next: F) -> Either<Self::Future, X> where
+		F: FnOnce(Request, Metadata) -> X + Send,
+		X: futures::Future<Item=Option<Response>, Error=()> + Send + 'static,
+	{
+		let calls = match request {
+			Request::Single(ref call) => {
+				self.metrics.note_call(call);
+				1
+			},
+			Request::Batch(ref calls) => {
+				calls.iter().for_each(|call| self.metrics.note_call(call));
+				calls.len()
+			},
+		};
+
+		if !meta.note_calls(calls) {
+			let response = match request {
+				Request::Single(ref call) => rate_limited(call).map(Response::Single),
+				Request::Batch(ref calls) => {
+					let outputs = calls.iter().filter_map(rate_limited).collect::<Vec<_>>();
+					if outputs.is_empty() { None } else { Some(Response::Batch(outputs)) }
+				},
+			};
+			return Either::A(Box::new(future::ok(response)));
+		}
+
+		Either::B(next(request, meta))
+	}
+}
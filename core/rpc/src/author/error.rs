@@ -72,6 +72,8 @@ const POOL_TOO_LOW_PRIORITY: i64 = POOL_INVALID_TX + 4;
 const POOL_CYCLE_DETECTED: i64 = POOL_INVALID_TX + 5;
 /// The transaction was not included to the pool because of the limits.
 const POOL_IMMEDIATELY_DROPPED: i64 = POOL_INVALID_TX + 6;
+/// The sender already has too many transactions in the pool.
+const POOL_TOO_MANY_FROM_SENDER: i64 = POOL_INVALID_TX + 7;
 
 impl From<Error> for rpc::Error {
 	fn from(e: Error) -> Self {
@@ -123,6 +125,11 @@ impl From<Error> for rpc::Error {
 				message: "Immediately Dropped" .into(),
 				data: Some("The transaction couldn't enter the pool because of the limit".into()),
 			},
+			Error::Pool(PoolError::TooManyFromSender) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(POOL_TOO_MANY_FROM_SENDER),
+				message: "Too Many Transactions From Sender".into(),
+				data: Some("The sender already has the maximal number of transactions in the pool".into()),
+			},
 			e => errors::internal(e),
 		}
 	}
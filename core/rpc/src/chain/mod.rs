@@ -22,11 +22,13 @@ use log::warn;
 use client::{self, Client, BlockchainEvents};
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
-use primitives::{H256, Blake2Hasher};
+use parity_codec::Encode;
+use primitives::{H256, Blake2Hasher, Bytes};
 use crate::rpc::Result as RpcResult;
 use crate::rpc::futures::{stream, Future, Sink, Stream};
 use runtime_primitives::generic::{BlockId, SignedBlock};
 use runtime_primitives::traits::{Block as BlockT, Header, NumberFor};
+use serde::{Serialize, Deserialize};
 
 use crate::subscriptions::Subscriptions;
 
@@ -37,6 +39,18 @@ mod number;
 
 use self::error::Result;
 
+/// An extrinsic included in a canonical block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtrinsicLookup<Hash> {
+	/// Hash of the block that includes the extrinsic.
+	pub block_hash: Hash,
+	/// Index of the extrinsic in the block.
+	pub index: u32,
+	/// The encoded extrinsic.
+	pub extrinsic: Bytes,
+}
+
 /// Substrate blockchain API
 #[rpc]
 pub trait ChainApi<Number, Hash, Header, SignedBlock> {
@@ -61,6 +75,13 @@ pub trait ChainApi<Number, Hash, Header, SignedBlock> {
 	#[rpc(name = "chain_getFinalizedHead", alias("chain_getFinalisedHead"))]
 	fn finalized_head(&self) -> Result<Hash>;
 
+	/// Get the canonical block that includes the extrinsic with given hash, along with
+	/// the extrinsic itself.
+	///
+	/// Returns `None` if the extrinsic is unknown or if the node doesn't index extrinsics.
+	#[rpc(name = "chain_getExtrinsicByHash")]
+	fn extrinsic_by_hash(&self, hash: Hash) -> Result<Option<ExtrinsicLookup<Hash>>>;
+
 	/// New head subscription
 	#[pubsub(
 		subscription = "chain_newHead",
@@ -131,6 +152,7 @@ impl<B, E, Block, RA> Chain<B, E, Block, RA> where
 
 	fn subscribe_headers<F, G, S, ERR>(
 		&self,
+		metadata: &crate::metadata::Metadata,
 		subscriber: Subscriber<Block::Header>,
 		best_block_hash: G,
 		stream: F,
@@ -140,7 +162,7 @@ impl<B, E, Block, RA> Chain<B, E, Block, RA> where
 		ERR: ::std::fmt::Debug,
 		S: Stream<Item=Block::Header, Error=ERR> + Send + 'static,
 	{
-		self.subscriptions.add(subscriber, |sink| {
+		self.subscriptions.add(metadata, subscriber, |sink| {
 			// send current head right at the start.
 			let header = best_block_hash()
 				.and_then(|hash| self.header(hash.into()))
@@ -197,8 +219,24 @@ impl<B, E, Block, RA> ChainApi<NumberFor<Block>, Block::Hash, Block::Header, Sig
 		Ok(self.client.info()?.chain.finalized_hash)
 	}
 
-	fn subscribe_new_head(&self, _metadata: Self::Metadata, subscriber: Subscriber<Block::Header>) {
+	fn extrinsic_by_hash(&self, hash: Block::Hash) -> Result<Option<ExtrinsicLookup<Block::Hash>>> {
+		let (block_hash, index) = match self.client.transaction_index(&hash)? {
+			Some(entry) => entry,
+			None => return Ok(None),
+		};
+		let extrinsic = self.client.body(&BlockId::Hash(block_hash))?
+			.and_then(|body| body.into_iter().nth(index as usize));
+
+		Ok(extrinsic.map(|extrinsic| ExtrinsicLookup {
+			block_hash,
+			index,
+			extrinsic: extrinsic.encode().into(),
+		}))
+	}
+
+	fn subscribe_new_head(&self, metadata: Self::Metadata, subscriber: Subscriber<Block::Header>) {
 		self.subscribe_headers(
+			&metadata,
 			subscriber,
 			|| self.block_hash(None.into()),
 			|| self.client.import_notification_stream()
@@ -211,8 +249,9 @@ impl<B, E, Block, RA> ChainApi<NumberFor<Block>, Block::Hash, Block::Header, Sig
 		Ok(self.subscriptions.cancel(id))
 	}
 
-	fn subscribe_finalized_heads(&self, _meta: Self::Metadata, subscriber: Subscriber<Block::Header>) {
+	fn subscribe_finalized_heads(&self, meta: Self::Metadata, subscriber: Subscriber<Block::Header>) {
 		self.subscribe_headers(
+			&meta,
 			subscriber,
 			|| Ok(Some(self.client.info()?.chain.finalized_hash)),
 			|| self.client.finality_notification_stream()
@@ -16,8 +16,9 @@
 
 use super::*;
 use assert_matches::assert_matches;
-use test_client::{self, TestClient};
-use test_client::runtime::{H256, Block, Header};
+use test_client::{self, TestClient, AccountKeyring, BlockBuilderExt};
+use test_client::runtime::{self, H256, Block, Header};
+use runtime_primitives::traits::{BlakeTwo256, Hash as HashT};
 use consensus::BlockOrigin;
 
 #[test]
@@ -188,6 +189,41 @@ fn should_return_finalized_hash() {
 	);
 }
 
+#[test]
+fn should_return_extrinsic_by_hash() {
+	let core = ::tokio::runtime::Runtime::new().unwrap();
+	let remote = core.executor();
+
+	let client = Chain {
+		client: Arc::new(test_client::new()),
+		subscriptions: Subscriptions::new(remote),
+	};
+
+	let mut builder = client.client.new_block(Default::default()).unwrap();
+	builder.push_transfer(runtime::Transfer {
+		from: AccountKeyring::Alice.into(),
+		to: AccountKeyring::Ferdie.into(),
+		amount: 42,
+		nonce: 0,
+	}).unwrap();
+	let block = builder.bake().unwrap();
+	let block_hash = block.hash();
+	let extrinsic = block.extrinsics[0].clone();
+	let extrinsic_hash = BlakeTwo256::hash_of(&extrinsic);
+
+	assert_matches!(client.extrinsic_by_hash(extrinsic_hash), Ok(None));
+
+	client.client.import(BlockOrigin::Own, block).unwrap();
+	assert_eq!(
+		client.extrinsic_by_hash(extrinsic_hash).unwrap(),
+		Some(ExtrinsicLookup {
+			block_hash,
+			index: 0,
+			extrinsic: extrinsic.encode().into(),
+		}),
+	);
+}
+
 #[test]
 fn should_notify_about_latest_block() {
 	let mut core = ::tokio::runtime::Runtime::new().unwrap();
@@ -0,0 +1,145 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Substrate contracts API.
+//!
+//! Only available on nodes whose runtime includes the contract module and implements its runtime
+//! API.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use client::blockchain::HeaderBackend;
+use contract_rpc_runtime_api::{ContractExecResult, ContractsApi as ContractsRuntimeApi};
+use jsonrpc_derive::rpc;
+use parity_codec::Codec;
+use primitives::Bytes;
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{Block as BlockT, ProvideRuntimeApi};
+use serde::{Serialize, Deserialize};
+
+mod error;
+#[cfg(test)]
+mod tests;
+
+use self::error::Result;
+
+/// A call to a contract to be executed without submitting a transaction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallRequest<AccountId, Balance> {
+	/// The account the call is made from.
+	pub origin: AccountId,
+	/// The contract to call.
+	pub dest: AccountId,
+	/// The value transferred to the contract.
+	pub value: Balance,
+	/// The maximum amount of gas the call may consume. Capped at the block gas limit.
+	pub gas_limit: u64,
+	/// The input data passed to the contract.
+	pub input_data: Bytes,
+}
+
+/// The outcome of a contract call executed without submitting a transaction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallResult {
+	/// Whether the call reverted. No output is returned and no events are emitted in that case.
+	pub reverted: bool,
+	/// The reason the call reverted, if it did.
+	pub revert_reason: Option<String>,
+	/// The output data returned by the contract.
+	pub output: Bytes,
+	/// The amount of gas consumed by the call.
+	pub gas_consumed: u64,
+	/// The encoded runtime events emitted by the call.
+	pub events: Vec<Bytes>,
+}
+
+impl From<ContractExecResult> for CallResult {
+	fn from(result: ContractExecResult) -> Self {
+		CallResult {
+			reverted: result.revert_reason.is_some(),
+			revert_reason: result.revert_reason.map(|reason| String::from_utf8_lossy(&reason).into_owned()),
+			output: result.output.into(),
+			gas_consumed: result.gas_consumed,
+			events: result.events.into_iter().map(Into::into).collect(),
+		}
+	}
+}
+
+/// Substrate contracts API
+#[rpc]
+pub trait ContractsApi<BlockHash, AccountId, Balance> {
+	/// Executes a call to a contract at a block's state.
+	///
+	/// The call is executed locally without submitting a transaction: no gas is paid for and
+	/// all changes made by the call are discarded. The gas limit of the call is capped at the
+	/// block gas limit.
+	#[rpc(name = "contract_call")]
+	fn call(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		hash: Option<BlockHash>
+	) -> Result<CallResult>;
+}
+
+/// Contracts API.
+pub struct Contracts<C, Block> {
+	/// Substrate client.
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> Contracts<C, Block> {
+	/// Create new Contracts API RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Self {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, Block, AccountId, Balance> ContractsApi<Block::Hash, AccountId, Balance> for Contracts<C, Block> where
+	Block: BlockT + 'static,
+	C: ProvideRuntimeApi + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: ContractsRuntimeApi<Block, AccountId, Balance>,
+	AccountId: Codec,
+	Balance: Codec,
+{
+	fn call(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		hash: Option<Block::Hash>
+	) -> Result<CallResult> {
+		let hash = match hash {
+			Some(hash) => hash,
+			None => self.client.info()?.best_hash,
+		};
+		let CallRequest { origin, dest, value, gas_limit, input_data } = call_request;
+
+		let result = self.client.runtime_api().call(
+			&BlockId::Hash(hash),
+			origin,
+			dest,
+			value,
+			gas_limit,
+			input_data.0,
+		)?;
+
+		Ok(result.into())
+	}
+}
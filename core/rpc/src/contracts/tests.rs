@@ -0,0 +1,121 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+use client::error::Result as ClientResult;
+use primitives::{H256, NativeOrEncoded, ExecutionContext};
+use test_client::runtime::Block;
+use crate::testing::{self, RuntimeApi};
+
+type Call = (u64, u64, u64, u64, Vec<u8>);
+
+/// A client with a runtime that records contract calls and returns a fixed result.
+type TestClient = testing::TestClient<Call, ContractExecResult>;
+
+impl ContractsRuntimeApi<Block, u64, u64> for RuntimeApi<Call, ContractExecResult> {
+	fn ContractsApi_call_runtime_api_impl(
+		&self,
+		at: &BlockId<Block>,
+		_: ExecutionContext,
+		params: Option<Call>,
+		_: Vec<u8>,
+	) -> ClientResult<NativeOrEncoded<ContractExecResult>> {
+		let params = params.expect("contract calls are always made natively in tests; qed");
+		self.inner.record_call(at, params)
+	}
+}
+
+fn call_request() -> CallRequest<u64, u64> {
+	CallRequest {
+		origin: 1,
+		dest: 2,
+		value: 10,
+		gas_limit: 100_000,
+		input_data: vec![1, 2, 3].into(),
+	}
+}
+
+#[test]
+fn should_call_contract_at_best_block() {
+	let client = Arc::new(TestClient {
+		best_hash: H256::repeat_byte(1),
+		result: ContractExecResult {
+			revert_reason: None,
+			output: vec![4, 5],
+			gas_consumed: 42,
+			events: vec![vec![6]],
+		},
+		..Default::default()
+	});
+	let api = Contracts::new(client.clone());
+
+	assert_eq!(
+		ContractsApi::<H256, u64, u64>::call(&api, call_request(), None).unwrap(),
+		CallResult {
+			reverted: false,
+			revert_reason: None,
+			output: vec![4, 5].into(),
+			gas_consumed: 42,
+			events: vec![vec![6].into()],
+		},
+	);
+	assert_eq!(
+		*client.calls.lock(),
+		vec![(BlockId::Hash(H256::repeat_byte(1)), (1, 2, 10, 100_000, vec![1, 2, 3]))],
+	);
+}
+
+#[test]
+fn should_call_contract_at_given_block() {
+	let client = Arc::new(TestClient {
+		best_hash: H256::repeat_byte(1),
+		result: ContractExecResult {
+			revert_reason: Some(b"contract trapped during execution".to_vec()),
+			gas_consumed: 100_000,
+			..Default::default()
+		},
+		..Default::default()
+	});
+	let api = Contracts::new(client.clone());
+
+	let result = ContractsApi::<H256, u64, u64>::call(&api, call_request(), Some(H256::repeat_byte(2))).unwrap();
+	assert!(result.reverted);
+	assert_eq!(result.revert_reason, Some("contract trapped during execution".into()));
+	assert_eq!(result.gas_consumed, 100_000);
+	assert_eq!(client.calls.lock()[0].0, BlockId::Hash(H256::repeat_byte(2)));
+}
+
+#[test]
+fn call_result_serialization_works() {
+	let result = CallResult {
+		reverted: false,
+		revert_reason: None,
+		output: vec![1, 2].into(),
+		gas_consumed: 5,
+		events: vec![vec![3].into()],
+	};
+
+	assert_eq!(
+		serde_json::to_string(&result).unwrap(),
+		r#"{"reverted":false,"revertReason":null,"output":"0x0102","gasConsumed":5,"events":["0x03"]}"#,
+	);
+
+	let request: CallRequest<u64, u64> = serde_json::from_str(
+		r#"{"origin":1,"dest":2,"value":10,"gasLimit":100000,"inputData":"0x010203"}"#
+	).unwrap();
+	assert_eq!(request, call_request());
+}
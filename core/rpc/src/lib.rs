@@ -21,12 +21,27 @@
 mod errors;
 mod helpers;
 mod subscriptions;
+#[cfg(test)]
+mod testing;
 
 pub use subscriptions::Subscriptions;
 
+/// Methods that change the state of the node and must only be exposed to trusted clients.
+pub const UNSAFE_METHODS: &[&str] = &[
+	"author_removeExtrinsic",
+	"contract_call",
+	"engine_createBlock",
+	"state_traceBlock",
+	"system_addReservedPeer",
+	"system_removeReservedPeer",
+	"system_setReservedOnly",
+];
+
 pub mod author;
 pub mod chain;
+pub mod contracts;
 pub mod metadata;
+pub mod payment;
 pub mod state;
 pub mod system;
 
@@ -15,10 +15,87 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 //! RPC Metadata
-use std::sync::Arc;
+use std::sync::{Arc, atomic::{self, AtomicUsize}};
+use std::time::{Duration, Instant};
 
 use jsonrpc_pubsub::{Session, PubSubMetadata};
-use crate::rpc::futures::sync::mpsc;
+use parking_lot::Mutex;
+use crate::rpc::{self, futures::sync::mpsc};
+
+/// Base code for errors caused by exceeding the limits of a connection.
+const BASE_ERROR: i64 = 6000;
+/// Connection holds the maximum number of subscriptions.
+const TOO_MANY_SUBSCRIPTIONS: i64 = BASE_ERROR + 1;
+/// Connection exceeded its rate limit.
+const RATE_LIMIT_EXCEEDED: i64 = BASE_ERROR + 2;
+
+/// Error returned for calls rejected by the rate limit of a connection.
+pub fn rate_limit_exceeded() -> rpc::Error {
+	rpc::Error {
+		code: rpc::ErrorCode::ServerError(RATE_LIMIT_EXCEEDED),
+		message: "Too many calls on this connection, try again later".into(),
+		data: None,
+	}
+}
+
+/// Error returned for subscriptions rejected because the connection holds too many of them.
+pub(crate) fn too_many_subscriptions() -> rpc::Error {
+	rpc::Error {
+		code: rpc::ErrorCode::ServerError(TOO_MANY_SUBSCRIPTIONS),
+		message: "Too many subscriptions on this connection".into(),
+		data: None,
+	}
+}
+
+/// Limits applied to a single connection.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionLimits {
+	/// Maximum number of active subscriptions. `None` if unlimited.
+	pub max_subscriptions: Option<usize>,
+	/// Maximum number of calls per second. `None` if unlimited.
+	pub max_calls_per_second: Option<u32>,
+}
+
+/// Calls made during the current one-second window.
+#[derive(Debug)]
+struct RateLimit {
+	max_calls: u32,
+	window_start: Instant,
+	calls: u32,
+}
+
+impl RateLimit {
+	fn new(max_calls: Option<u32>) -> Option<Arc<Mutex<Self>>> {
+		max_calls.map(|max_calls| Arc::new(Mutex::new(RateLimit {
+			max_calls,
+			window_start: Instant::now(),
+			calls: 0,
+		})))
+	}
+
+	fn note_calls(&mut self, calls: u32) -> bool {
+		let now = Instant::now();
+		if now.duration_since(self.window_start) >= Duration::from_secs(1) {
+			self.window_start = now;
+			self.calls = 0;
+		}
+
+		if self.calls.saturating_add(calls) > self.max_calls {
+			return false;
+		}
+		self.calls += calls;
+		true
+	}
+}
+
+/// Active subscription of a connection, released when dropped.
+pub(crate) struct SubscriptionPermit(Arc<AtomicUsize>);
+
+impl Drop for SubscriptionPermit {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, atomic::Ordering::AcqRel);
+	}
+}
 
 /// RPC Metadata.
 ///
@@ -28,6 +105,9 @@ use crate::rpc::futures::sync::mpsc;
 #[derive(Default, Clone)]
 pub struct Metadata {
 	session: Option<Arc<Session>>,
+	max_subscriptions: Option<usize>,
+	subscriptions: Arc<AtomicUsize>,
+	rate_limit: Option<Arc<Mutex<RateLimit>>>,
 }
 
 impl crate::rpc::Metadata for Metadata {}
@@ -40,8 +120,51 @@ impl PubSubMetadata for Metadata {
 impl Metadata {
 	/// Create new `Metadata` with session (Pub/Sub) support.
 	pub fn new(transport: mpsc::Sender<String>) -> Self {
+		Self::with_limits(transport, Default::default())
+	}
+
+	/// Create new `Metadata` with session (Pub/Sub) support, enforcing the given limits on
+	/// the connection.
+	pub fn with_limits(transport: mpsc::Sender<String>, limits: ConnectionLimits) -> Self {
 		Metadata {
 			session: Some(Arc::new(Session::new(transport))),
+			max_subscriptions: limits.max_subscriptions,
+			rate_limit: RateLimit::new(limits.max_calls_per_second),
+			..Default::default()
+		}
+	}
+
+	/// Create new `Metadata` without session, enforcing the given number of calls per second
+	/// on every request made with a clone of it.
+	///
+	/// Used by transports without persistent connections, e.g. HTTP, where the rate limit
+	/// applies to the server as a whole.
+	pub fn with_rate_limit(max_calls_per_second: Option<u32>) -> Self {
+		Metadata {
+			rate_limit: RateLimit::new(max_calls_per_second),
+			..Default::default()
+		}
+	}
+
+	/// Note that the given number of calls were made on the connection.
+	///
+	/// Returns false if the calls exceed the rate limit and must be rejected.
+	pub fn note_calls(&self, calls: usize) -> bool {
+		match self.rate_limit {
+			Some(ref rate_limit) => rate_limit.lock().note_calls(calls.min(u32::max_value() as usize) as u32),
+			None => true,
+		}
+	}
+
+	/// Reserve a new subscription on the connection.
+	///
+	/// Returns `None` if the connection already holds the maximum number of subscriptions.
+	pub(crate) fn reserve_subscription(&self) -> Option<SubscriptionPermit> {
+		let previous = self.subscriptions.fetch_add(1, atomic::Ordering::AcqRel);
+		let permit = SubscriptionPermit(self.subscriptions.clone());
+		match self.max_subscriptions {
+			Some(max) if previous >= max => None,
+			_ => Some(permit),
 		}
 	}
 
@@ -52,3 +175,51 @@ impl Metadata {
 		(rx, Self::new(tx))
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn subscriptions_are_limited() {
+		let (tx, _rx) = mpsc::channel(1);
+		let meta = Metadata::with_limits(tx, ConnectionLimits {
+			max_subscriptions: Some(2),
+			max_calls_per_second: None,
+		});
+
+		let first = meta.reserve_subscription().unwrap();
+		let _second = meta.reserve_subscription().unwrap();
+		assert!(meta.reserve_subscription().is_none());
+
+		drop(first);
+		assert!(meta.reserve_subscription().is_some());
+	}
+
+	#[test]
+	fn calls_are_rate_limited() {
+		let (tx, _rx) = mpsc::channel(1);
+		let meta = Metadata::with_limits(tx, ConnectionLimits {
+			max_subscriptions: None,
+			max_calls_per_second: Some(3),
+		});
+
+		assert!(meta.note_calls(2));
+		assert!(meta.note_calls(1));
+		assert!(!meta.note_calls(1));
+
+		let (_rx, unlimited) = Metadata::new_test();
+		assert!((0..100).all(|_| unlimited.note_calls(1)));
+	}
+
+	#[test]
+	fn rate_limit_is_shared_by_clones() {
+		let meta = Metadata::with_rate_limit(Some(2));
+		let other = meta.clone();
+
+		assert!(meta.note_calls(1));
+		assert!(other.note_calls(1));
+		assert!(!meta.note_calls(1));
+		assert!(!other.note_calls(1));
+	}
+}
@@ -0,0 +1,80 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+//! Substrate transaction payment API.
+//!
+//! Only available on nodes whose runtime implements the transaction payment runtime API.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use balances_rpc_runtime_api::TransactionPaymentApi as TransactionPaymentRuntimeApi;
+use client::blockchain::HeaderBackend;
+use jsonrpc_derive::rpc;
+use parity_codec::{Codec, Decode};
+use primitives::Bytes;
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{Block as BlockT, ProvideRuntimeApi};
+
+mod error;
+#[cfg(test)]
+mod tests;
+
+use self::error::{Error, Result};
+
+pub use balances_rpc_runtime_api::FeeInfo;
+
+/// Substrate transaction payment API
+#[rpc]
+pub trait PaymentApi<BlockHash, Balance> {
+	/// Returns the fee the given encoded extrinsic would pay if it was included in a block
+	/// built on top of the given block.
+	#[rpc(name = "payment_queryInfo")]
+	fn query_info(&self, extrinsic: Bytes, hash: Option<BlockHash>) -> Result<FeeInfo<Balance>>;
+}
+
+/// Transaction payment API.
+pub struct Payment<C, Block> {
+	/// Substrate client.
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> Payment<C, Block> {
+	/// Create new Payment API RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Self {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, Block, Balance> PaymentApi<Block::Hash, Balance> for Payment<C, Block> where
+	Block: BlockT + 'static,
+	C: ProvideRuntimeApi + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: TransactionPaymentRuntimeApi<Block, Balance>,
+	Balance: Codec,
+{
+	fn query_info(&self, extrinsic: Bytes, hash: Option<Block::Hash>) -> Result<FeeInfo<Balance>> {
+		let hash = match hash {
+			Some(hash) => hash,
+			None => self.client.info()?.best_hash,
+		};
+		let len = extrinsic.len() as u32;
+		let uxt = Decode::decode(&mut &extrinsic[..]).ok_or(Error::BadFormat)?;
+
+		Ok(self.client.runtime_api().query_info(&BlockId::Hash(hash), uxt, len)?)
+	}
+}
@@ -0,0 +1,106 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+use assert_matches::assert_matches;
+use client::error::Result as ClientResult;
+use parity_codec::Encode;
+use primitives::{H256, NativeOrEncoded, ExecutionContext};
+use test_client::runtime::{Block, Extrinsic};
+use crate::testing::{self, RuntimeApi};
+
+/// A client with a runtime that records fee queries and returns a fixed result.
+type TestClient = testing::TestClient<(Extrinsic, u32), FeeInfo<u64>>;
+
+impl TransactionPaymentRuntimeApi<Block, u64> for RuntimeApi<(Extrinsic, u32), FeeInfo<u64>> {
+	fn TransactionPaymentApi_query_info_runtime_api_impl(
+		&self,
+		at: &BlockId<Block>,
+		_: ExecutionContext,
+		params: Option<(Extrinsic, u32)>,
+		_: Vec<u8>,
+	) -> ClientResult<NativeOrEncoded<FeeInfo<u64>>> {
+		let params = params.expect("fee queries are always made natively in tests; qed");
+		self.inner.record_call(at, params)
+	}
+}
+
+fn fee_info() -> FeeInfo<u64> {
+	FeeInfo {
+		weight: 5,
+		base_fee: 10,
+		weight_fee: 15,
+		total_fee: 25,
+	}
+}
+
+#[test]
+fn should_query_info_at_best_block() {
+	let client = Arc::new(TestClient {
+		best_hash: H256::repeat_byte(1),
+		result: fee_info(),
+		..Default::default()
+	});
+	let api = Payment::new(client.clone());
+	let uxt = Extrinsic::IncludeData(vec![1, 2, 3]);
+	let encoded = uxt.encode();
+
+	assert_eq!(
+		PaymentApi::<H256, u64>::query_info(&api, encoded.clone().into(), None).unwrap(),
+		fee_info(),
+	);
+	assert_eq!(
+		*client.calls.lock(),
+		vec![(BlockId::Hash(H256::repeat_byte(1)), (uxt, encoded.len() as u32))],
+	);
+}
+
+#[test]
+fn should_query_info_at_given_block() {
+	let client = Arc::new(TestClient {
+		best_hash: H256::repeat_byte(1),
+		..Default::default()
+	});
+	let api = Payment::new(client.clone());
+	let encoded = Extrinsic::IncludeData(vec![1]).encode();
+
+	assert_eq!(
+		PaymentApi::<H256, u64>::query_info(&api, encoded.into(), Some(H256::repeat_byte(2))).unwrap(),
+		FeeInfo::default(),
+	);
+	assert_eq!(client.calls.lock()[0].0, BlockId::Hash(H256::repeat_byte(2)));
+}
+
+#[test]
+fn should_reject_invalid_extrinsic() {
+	let client = Arc::new(TestClient::default());
+	let api = Payment::new(client.clone());
+
+	assert_matches!(
+		PaymentApi::<H256, u64>::query_info(&api, vec![0xff, 0xff].into(), None),
+		Err(Error::BadFormat)
+	);
+	assert!(client.calls.lock().is_empty());
+}
+
+#[test]
+fn fee_info_serialization_works() {
+	assert_eq!(
+		serde_json::to_string(&fee_info()).unwrap(),
+		r#"{"weight":5,"baseFee":10,"weightFee":15,"totalFee":25}"#,
+	);
+}
@@ -37,6 +37,7 @@ use runtime_primitives::traits::{
 	SaturatedConversion
 };
 use runtime_version::RuntimeVersion;
+use serde::{Serialize, Deserialize};
 use state_machine::{self, ExecutionStrategy};
 
 use crate::subscriptions::Subscriptions;
@@ -47,6 +48,96 @@ mod tests;
 
 use self::error::Result;
 
+/// Maximum number of storage entries returned by a single paged storage query.
+const STORAGE_PAGED_MAX_COUNT: u32 = 1000;
+
+/// Maximum number of keys proven by a single read proof query.
+const READ_PROOF_MAX_KEYS: u32 = 1000;
+
+/// Maximum size in bytes of the keys and values of the storage accesses returned by a single
+/// block trace.
+const TRACE_MAX_SIZE: usize = 16 * 1024 * 1024;
+
+/// Storage read proof of a set of keys at a block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadProof<Hash> {
+	/// Block hash used to generate the proof.
+	pub at: Hash,
+	/// Trie nodes of the proof, in no particular order.
+	pub proof: Vec<Bytes>,
+}
+
+/// Kind of a storage access made while executing a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageAccessKind {
+	/// Value read.
+	Read,
+	/// Hash of the value read.
+	ReadHash,
+	/// Existence of the key checked.
+	Exists,
+	/// Value written or deleted.
+	Write,
+	/// Every key starting with the key deleted.
+	ClearPrefix,
+	/// Child trie deleted. The key is empty.
+	KillChild,
+}
+
+/// Storage access made while executing a block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageAccess {
+	/// Index of the extrinsic being applied, `None` outside of extrinsics.
+	pub extrinsic_index: Option<u32>,
+	/// Storage key of the child trie, `None` for the top-level storage.
+	pub child_storage_key: Option<StorageKey>,
+	/// Accessed key.
+	pub key: StorageKey,
+	/// Kind of access.
+	pub kind: StorageAccessKind,
+	/// Value read or written, or the hash of the value read. `None` if the key doesn't exist
+	/// or has been deleted.
+	pub value: Option<StorageData>,
+	/// Whether the key exists, for existence checks.
+	pub exists: Option<bool>,
+}
+
+impl From<client::StorageAccess> for StorageAccess {
+	fn from(access: client::StorageAccess) -> Self {
+		let (kind, value, exists) = match access.kind {
+			client::StorageAccessKind::Read(value) => (StorageAccessKind::Read, value, None),
+			client::StorageAccessKind::ReadHash(hash) => (StorageAccessKind::ReadHash, hash, None),
+			client::StorageAccessKind::Exists(exists) => (StorageAccessKind::Exists, None, Some(exists)),
+			client::StorageAccessKind::Write(value) => (StorageAccessKind::Write, value, None),
+			client::StorageAccessKind::ClearPrefix => (StorageAccessKind::ClearPrefix, None, None),
+			client::StorageAccessKind::KillChild => (StorageAccessKind::KillChild, None, None),
+		};
+		StorageAccess {
+			extrinsic_index: access.extrinsic_index,
+			child_storage_key: access.child_storage_key.map(StorageKey),
+			key: StorageKey(access.key),
+			kind,
+			value: value.map(StorageData),
+			exists,
+		}
+	}
+}
+
+/// Storage accesses made while executing a block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTrace<Hash> {
+	/// Hash of the executed block.
+	pub block: Hash,
+	/// Storage accesses, in execution order.
+	pub storage: Vec<StorageAccess>,
+	/// Whether the last accesses have been dropped because the trace is too large.
+	pub truncated: bool,
+}
+
 /// Substrate state API
 #[rpc]
 pub trait StateApi<Hash> {
@@ -61,6 +152,27 @@ pub trait StateApi<Hash> {
 	#[rpc(name = "state_getKeys")]
 	fn storage_keys(&self, prefix: StorageKey, hash: Option<Hash>) -> Result<Vec<StorageKey>>;
 
+	/// Returns at most `count` keys with prefix, in key order, starting after `start_key` if given.
+	#[rpc(name = "state_getKeysPaged")]
+	fn storage_keys_paged(
+		&self,
+		prefix: StorageKey,
+		count: u32,
+		start_key: Option<StorageKey>,
+		hash: Option<Hash>,
+	) -> Result<Vec<StorageKey>>;
+
+	/// Returns at most `count` storage entries with key prefix, in key order, starting after
+	/// `start_key` if given.
+	#[rpc(name = "state_getStoragePaged")]
+	fn storage_paged(
+		&self,
+		prefix: StorageKey,
+		count: u32,
+		start_key: Option<StorageKey>,
+		hash: Option<Hash>,
+	) -> Result<Vec<(StorageKey, StorageData)>>;
+
 	/// Returns a storage entry at a specific block's state.
 	#[rpc(name = "state_getStorage", alias("state_getStorageAt"))]
 	fn storage(&self, key: StorageKey, hash: Option<Hash>) -> Result<Option<StorageData>>;
@@ -107,6 +219,33 @@ pub trait StateApi<Hash> {
 		hash: Option<Hash>
 	) -> Result<Option<u64>>;
 
+	/// Returns a proof of the storage entries of the given keys at a block's state.
+	///
+	/// The proof can be checked against the state root of the block, e.g. with
+	/// `state_machine::read_keys_proof_check`. At most 1000 keys are proven at once.
+	#[rpc(name = "state_getReadProof")]
+	fn read_proof(&self, keys: Vec<StorageKey>, hash: Option<Hash>) -> Result<ReadProof<Hash>>;
+
+	/// Returns a proof of the child storage entries of the given keys at a block's state.
+	///
+	/// The proof can be checked against the state root of the block, e.g. with
+	/// `state_machine::read_child_keys_proof_check`. At most 1000 keys are proven at once.
+	#[rpc(name = "state_getChildReadProof")]
+	fn child_read_proof(
+		&self,
+		child_storage_key: StorageKey,
+		keys: Vec<StorageKey>,
+		hash: Option<Hash>
+	) -> Result<ReadProof<Hash>>;
+
+	/// Re-executes a block on the state of its parent and returns every storage access made,
+	/// optionally only the ones to keys (or child tries) starting with `key_filter`. The trace
+	/// is truncated once its keys and values reach 16 MiB.
+	///
+	/// This method is unsafe and must only be exposed to trusted clients.
+	#[rpc(name = "state_traceBlock")]
+	fn trace_block(&self, hash: Hash, key_filter: Option<StorageKey>) -> Result<BlockTrace<Hash>>;
+
 	/// Returns the runtime metadata as an opaque blob.
 	#[rpc(name = "state_getMetadata")]
 	fn metadata(&self, hash: Option<Hash>) -> Result<Bytes>;
@@ -354,6 +493,32 @@ impl<B, E, Block, RA> StateApi<Block::Hash> for State<B, E, Block, RA> where
 		Ok(self.client.storage_keys(&BlockId::Hash(block), &key_prefix)?)
 	}
 
+	fn storage_keys_paged(
+		&self,
+		key_prefix: StorageKey,
+		count: u32,
+		start_key: Option<StorageKey>,
+		block: Option<Block::Hash>,
+	) -> Result<Vec<StorageKey>> {
+		check_paged_count(count)?;
+		let block = self.unwrap_or_best(block)?;
+		trace!(target: "rpc", "Querying {} storage keys at {:?}", count, block);
+		Ok(self.client.storage_keys_paged(&BlockId::Hash(block), &key_prefix, start_key.as_ref(), count as usize)?)
+	}
+
+	fn storage_paged(
+		&self,
+		key_prefix: StorageKey,
+		count: u32,
+		start_key: Option<StorageKey>,
+		block: Option<Block::Hash>,
+	) -> Result<Vec<(StorageKey, StorageData)>> {
+		check_paged_count(count)?;
+		let block = self.unwrap_or_best(block)?;
+		trace!(target: "rpc", "Querying {} storage entries at {:?}", count, block);
+		Ok(self.client.storage_pairs_paged(&BlockId::Hash(block), &key_prefix, start_key.as_ref(), count as usize)?)
+	}
+
 	fn storage(&self, key: StorageKey, block: Option<Block::Hash>) -> Result<Option<StorageData>> {
 		let block = self.unwrap_or_best(block)?;
 		trace!(target: "rpc", "Querying storage at {:?} for key {}", block, HexDisplay::from(&key.0));
@@ -413,6 +578,49 @@ impl<B, E, Block, RA> StateApi<Block::Hash> for State<B, E, Block, RA> where
 		Ok(self.child_storage(child_storage_key, key, block)?.map(|x| x.0.len() as u64))
 	}
 
+	fn read_proof(&self, keys: Vec<StorageKey>, block: Option<Block::Hash>) -> Result<ReadProof<Block::Hash>> {
+		check_read_proof_keys(keys.len())?;
+		let block = self.unwrap_or_best(block)?;
+		trace!(target: "rpc", "Proving {} storage keys at {:?}", keys.len(), block);
+		let keys = keys.into_iter().map(|key| key.0).collect::<Vec<_>>();
+		let proof = self.client.read_proof_keys(&BlockId::Hash(block), &keys)?;
+		Ok(ReadProof {
+			at: block,
+			proof: proof.into_iter().map(Bytes).collect(),
+		})
+	}
+
+	fn child_read_proof(
+		&self,
+		child_storage_key: StorageKey,
+		keys: Vec<StorageKey>,
+		block: Option<Block::Hash>
+	) -> Result<ReadProof<Block::Hash>> {
+		check_read_proof_keys(keys.len())?;
+		let block = self.unwrap_or_best(block)?;
+		trace!(target: "rpc", "Proving {} child storage keys at {:?}", keys.len(), block);
+		let keys = keys.into_iter().map(|key| key.0).collect::<Vec<_>>();
+		let proof = self.client.read_child_proof_keys(&BlockId::Hash(block), &child_storage_key.0, &keys)?;
+		Ok(ReadProof {
+			at: block,
+			proof: proof.into_iter().map(Bytes).collect(),
+		})
+	}
+
+	fn trace_block(&self, block: Block::Hash, key_filter: Option<StorageKey>) -> Result<BlockTrace<Block::Hash>> {
+		trace!(target: "rpc", "Tracing block {:?}", block);
+		let trace = self.client.trace_block(
+			&BlockId::Hash(block),
+			key_filter.as_ref().map(|prefix| &prefix.0[..]),
+			TRACE_MAX_SIZE,
+		)?;
+		Ok(BlockTrace {
+			block,
+			storage: trace.accesses.into_iter().map(Into::into).collect(),
+			truncated: trace.truncated,
+		})
+	}
+
 	fn metadata(&self, block: Option<Block::Hash>) -> Result<Bytes> {
 		let block = self.unwrap_or_best(block)?;
 		self.client.runtime_api().metadata(&BlockId::Hash(block)).map(Into::into).map_err(Into::into)
@@ -433,7 +641,7 @@ impl<B, E, Block, RA> StateApi<Block::Hash> for State<B, E, Block, RA> where
 
 	fn subscribe_storage(
 		&self,
-		_meta: Self::Metadata,
+		meta: Self::Metadata,
 		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
 		keys: Option<Vec<StorageKey>>
 	) {
@@ -460,7 +668,7 @@ impl<B, E, Block, RA> StateApi<Block::Hash> for State<B, E, Block, RA> where
 				vec![Ok(Ok(StorageChangeSet { block, changes }))]
 			}).unwrap_or_default());
 
-		self.subscriptions.add(subscriber, |sink| {
+		self.subscriptions.add(&meta, subscriber, |sink| {
 			let stream = stream
 				.map_err(|e| warn!("Error creating storage notification stream: {:?}", e))
 				.map(|(block, changes)| Ok(StorageChangeSet {
@@ -485,7 +693,7 @@ impl<B, E, Block, RA> StateApi<Block::Hash> for State<B, E, Block, RA> where
 		Ok(self.client.runtime_version_at(&BlockId::Hash(at))?)
 	}
 
-	fn subscribe_runtime_version(&self, _meta: Self::Metadata, subscriber: Subscriber<RuntimeVersion>) {
+	fn subscribe_runtime_version(&self, meta: Self::Metadata, subscriber: Subscriber<RuntimeVersion>) {
 		let stream = match self.client.storage_changes_notification_stream(
 				Some(&[StorageKey(storage::well_known_keys::CODE.to_vec())])
 		) {
@@ -496,7 +704,7 @@ impl<B, E, Block, RA> StateApi<Block::Hash> for State<B, E, Block, RA> where
 			}
 		};
 
-		self.subscriptions.add(subscriber, |sink| {
+		self.subscriptions.add(&meta, subscriber, |sink| {
 			let version = self.runtime_version(None.into())
 				.map_err(Into::into);
 
@@ -556,6 +764,26 @@ pub(crate) fn split_range(size: usize, middle: Option<usize>) -> (Range<usize>,
 	(range1, range2)
 }
 
+fn check_paged_count(count: u32) -> Result<()> {
+	if count > STORAGE_PAGED_MAX_COUNT {
+		return Err(error::Error::InvalidCount {
+			value: count,
+			max: STORAGE_PAGED_MAX_COUNT,
+		});
+	}
+	Ok(())
+}
+
+fn check_read_proof_keys(count: usize) -> Result<()> {
+	if count > READ_PROOF_MAX_KEYS as usize {
+		return Err(error::Error::InvalidCount {
+			value: count.min(u32::max_value() as usize) as u32,
+			max: READ_PROOF_MAX_KEYS,
+		});
+	}
+	Ok(())
+}
+
 fn invalid_block_range<H: Header>(from: Option<&H>, to: Option<&H>, reason: String) -> error::Error {
 	let to_string = |x: Option<&H>| match x {
 		None => "unknown hash".into(),
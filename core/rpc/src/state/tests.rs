@@ -19,6 +19,7 @@ use self::error::Error;
 
 use assert_matches::assert_matches;
 use consensus::BlockOrigin;
+use parity_codec::{Encode, Decode};
 use primitives::storage::well_known_keys;
 use sr_io::blake2_256;
 use test_client::{self, runtime, AccountKeyring, TestClient, BlockBuilderExt, LocalExecutor};
@@ -47,6 +48,127 @@ fn should_return_storage() {
 	);
 }
 
+#[test]
+fn should_return_storage_paged() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let genesis_hash = client.genesis_hash();
+	let client = State::new(client, Subscriptions::new(core.executor()));
+	let prefix = StorageKey(Vec::new());
+
+	let mut all_keys = client.storage_keys(prefix.clone(), None).unwrap();
+	all_keys.sort();
+	assert!(all_keys.len() > 2);
+
+	let mut paged_keys = Vec::new();
+	let mut start_key = None;
+	loop {
+		let page = client.storage_keys_paged(prefix.clone(), 2, start_key, Some(genesis_hash)).unwrap();
+		assert!(page.len() <= 2);
+		start_key = match page.last() {
+			Some(key) => Some(key.clone()),
+			None => break,
+		};
+		paged_keys.extend(page);
+	}
+	assert_eq!(paged_keys, all_keys);
+
+	let entries = client.storage_paged(prefix.clone(), 2, Some(all_keys[0].clone()), None).unwrap();
+	assert_eq!(entries.len(), 2);
+	for (key, value) in entries {
+		assert_eq!(client.storage(key, None).unwrap(), Some(value));
+	}
+
+	assert_matches!(
+		client.storage_keys_paged(prefix, STORAGE_PAGED_MAX_COUNT + 1, None, None),
+		Err(Error::InvalidCount { .. })
+	);
+}
+
+#[test]
+fn should_return_verifiable_read_proof() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let genesis_hash = client.genesis_hash();
+	let state_root = *client.header(&BlockId::Hash(genesis_hash)).unwrap().unwrap().state_root();
+	let api = State::new(client, Subscriptions::new(core.executor()));
+	let keys = vec![StorageKey(b":code".to_vec()), StorageKey(b"missing".to_vec())];
+
+	let read_proof = api.read_proof(keys.clone(), None).unwrap();
+	assert_eq!(read_proof.at, genesis_hash);
+
+	let proof = read_proof.proof.into_iter().map(|node| node.0).collect();
+	let keys = keys.into_iter().map(|key| key.0).collect::<Vec<_>>();
+	let values = state_machine::read_keys_proof_check::<Blake2Hasher, _>(state_root, proof, &keys).unwrap();
+	assert_eq!(values[0].1.as_ref().map(|code| code.len()), Some(LocalExecutor::native_equivalent().len()));
+	assert_eq!(values[1], (b"missing".to_vec(), None));
+
+	let too_many = (0..READ_PROOF_MAX_KEYS + 1).map(|i| StorageKey(i.encode())).collect();
+	assert_matches!(api.read_proof(too_many, None), Err(Error::InvalidCount { .. }));
+}
+
+#[test]
+fn should_return_verifiable_child_read_proof() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let genesis_hash = client.genesis_hash();
+	let state_root = *client.header(&BlockId::Hash(genesis_hash)).unwrap().unwrap().state_root();
+	let api = State::new(client, Subscriptions::new(core.executor()));
+	let child_key = StorageKey(well_known_keys::CHILD_STORAGE_KEY_PREFIX.iter().chain(b"test").cloned().collect());
+	let keys = vec![StorageKey(b"key".to_vec()), StorageKey(b"missing".to_vec())];
+
+	let read_proof = api.child_read_proof(child_key.clone(), keys.clone(), None).unwrap();
+	assert_eq!(read_proof.at, genesis_hash);
+
+	let proof = read_proof.proof.into_iter().map(|node| node.0).collect();
+	let keys = keys.into_iter().map(|key| key.0).collect::<Vec<_>>();
+	let values = state_machine::read_child_keys_proof_check::<Blake2Hasher, _>(
+		state_root,
+		proof,
+		&child_key.0,
+		&keys,
+	).unwrap();
+	assert_eq!(values, vec![(b"key".to_vec(), Some(vec![42])), (b"missing".to_vec(), None)]);
+
+	let too_many = (0..READ_PROOF_MAX_KEYS + 1).map(|i| StorageKey(i.encode())).collect();
+	assert_matches!(api.child_read_proof(child_key, too_many, None), Err(Error::InvalidCount { .. }));
+}
+
+#[test]
+fn should_trace_block_storage_accesses() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_transfer(runtime::Transfer {
+		from: AccountKeyring::Alice.into(),
+		to: AccountKeyring::Ferdie.into(),
+		amount: 42,
+		nonce: 0,
+	}).unwrap();
+	let block = builder.bake().unwrap();
+	let block_hash = block.header.hash();
+	client.import(BlockOrigin::Own, block).unwrap();
+	let api = State::new(client, Subscriptions::new(core.executor()));
+	let alice_balance_key = StorageKey(
+		blake2_256(&test_runtime::system::balance_of_key(AccountKeyring::Alice.into())).to_vec()
+	);
+
+	let trace = api.trace_block(block_hash, Some(alice_balance_key.clone())).unwrap();
+	assert_eq!(trace.block, block_hash);
+	assert!(!trace.truncated);
+	assert!(trace.storage.iter().all(|access| access.key == alice_balance_key));
+
+	let read = trace.storage.iter().find(|access| access.kind == StorageAccessKind::Read).unwrap();
+	let write = trace.storage.iter().rev().find(|access| access.kind == StorageAccessKind::Write).unwrap();
+	let balance = |access: &StorageAccess| -> u64 {
+		Decode::decode(&mut &access.value.as_ref().unwrap().0[..]).unwrap()
+	};
+	assert_eq!(balance(write), balance(read) - 42);
+
+	assert!(api.trace_block(block_hash, None).unwrap().storage.len() > trace.storage.len());
+	assert_matches!(api.trace_block(H256::repeat_byte(1), None), Err(Error::Client(_)));
+}
+
 #[test]
 fn should_return_child_storage() {
 	let core = tokio::runtime::Runtime::new().unwrap();
@@ -22,6 +22,7 @@ use jsonrpc_pubsub::{SubscriptionId, typed::{Sink, Subscriber}};
 use parking_lot::Mutex;
 use crate::rpc::futures::sync::oneshot;
 use crate::rpc::futures::{Future, future};
+use crate::metadata::{self, Metadata};
 use tokio::runtime::TaskExecutor;
 
 type Id = u64;
@@ -69,21 +70,34 @@ impl Subscriptions {
 
 	/// Creates new subscription for given subscriber.
 	///
-	/// Second parameter is a function that converts Subscriber sink into a future.
+	/// Second parameter is the metadata of the connection, the subscriber is rejected if the
+	/// connection already holds the maximum number of subscriptions.
+	/// Third parameter is a function that converts Subscriber sink into a future.
 	/// This future will be driven to completion bu underlying event loop
 	/// or will be cancelled in case #cancel is invoked.
-	pub fn add<T, E, G, R, F>(&self, subscriber: Subscriber<T, E>, into_future: G) where
+	pub fn add<T, E, G, R, F>(&self, metadata: &Metadata, subscriber: Subscriber<T, E>, into_future: G) where
 		G: FnOnce(Sink<T, E>) -> R,
 		R: future::IntoFuture<Future=F, Item=(), Error=()>,
 		F: future::Future<Item=(), Error=()> + Send + 'static,
 	{
+		let permit = match metadata.reserve_subscription() {
+			Some(permit) => permit,
+			None => {
+				let _ = subscriber.reject(metadata::too_many_subscriptions());
+				return;
+			},
+		};
+
 		let id = self.next_id.next_id();
 		if let Ok(sink) = subscriber.assign_id(id.into()) {
 			let (tx, rx) = oneshot::channel();
 			let future = into_future(sink)
 				.into_future()
 				.select(rx.map_err(|e| warn!("Error timeing out: {:?}", e)))
-				.then(|_| Ok(()));
+				.then(move |_| {
+					drop(permit);
+					Ok(())
+				});
 
 			self.active_subscriptions.lock().insert(id, tx);
 			self.executor.spawn(future);
@@ -28,6 +28,9 @@ pub enum Error {
 	/// Provided block range couldn't be resolved to a list of blocks.
 	#[display(fmt = "Node is not fully functional: {}", _0)]
 	NotHealthy(Health),
+	/// Peer argument is malformatted.
+	#[display(fmt = "Peer argument is malformatted: {}", _0)]
+	MalformattedPeerArg(String),
 }
 
 impl std::error::Error for Error {}
@@ -43,6 +46,11 @@ impl From<Error> for rpc::Error {
 				message: format!("{}", e),
 				data: serde_json::to_value(h).ok(),
 			},
+			Error::MalformattedPeerArg(ref details) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 2),
+				message: format!("{}", e),
+				data: Some(details.clone().into()),
+			},
 		}
 	}
 }
@@ -27,7 +27,7 @@ use jsonrpc_derive::rpc;
 use network;
 use runtime_primitives::traits::{self, Header as HeaderT};
 
-use self::error::Result;
+use self::error::{Error, Result};
 pub use self::helpers::{Properties, SystemInfo, Health, PeerInfo};
 
 /// Substrate system RPC API
@@ -67,12 +67,31 @@ pub trait SystemApi<Hash, Number> {
 	// TODO: make this stable and move structs https://github.com/paritytech/substrate/issues/1890
 	#[rpc(name = "system_networkState")]
 	fn system_network_state(&self) -> Result<network::NetworkState>;
+
+	/// Adds a reserved peer, given as a multiaddr ending with `/p2p/<peer id>`.
+	///
+	/// This method is unsafe and must only be exposed to trusted clients.
+	#[rpc(name = "system_addReservedPeer")]
+	fn system_add_reserved_peer(&self, peer: String) -> Result<()>;
+
+	/// Removes a reserved peer, given as a multiaddr ending with `/p2p/<peer id>`.
+	///
+	/// This method is unsafe and must only be exposed to trusted clients.
+	#[rpc(name = "system_removeReservedPeer")]
+	fn system_remove_reserved_peer(&self, peer: String) -> Result<()>;
+
+	/// Sets whether only reserved peers are allowed to connect.
+	///
+	/// This method is unsafe and must only be exposed to trusted clients.
+	#[rpc(name = "system_setReservedOnly")]
+	fn system_set_reserved_only(&self, reserved_only: bool) -> Result<()>;
 }
 
 /// System API implementation
 pub struct System<B: traits::Block> {
 	info: SystemInfo,
 	sync: Arc<network::SyncProvider<B>>,
+	network: Arc<network::ManageNetwork + Send + Sync>,
 	should_have_peers: bool,
 }
 
@@ -81,12 +100,14 @@ impl<B: traits::Block> System<B> {
 	pub fn new(
 		info: SystemInfo,
 		sync: Arc<network::SyncProvider<B>>,
+		network: Arc<network::ManageNetwork + Send + Sync>,
 		should_have_peers: bool,
 	) -> Self {
 		System {
 			info,
 			should_have_peers,
 			sync,
+			network,
 		}
 	}
 }
@@ -129,4 +150,24 @@ impl<B: traits::Block> SystemApi<B::Hash, <B::Header as HeaderT>::Number> for Sy
 	fn system_network_state(&self) -> Result<network::NetworkState> {
 		Ok(self.sync.network_state())
 	}
+
+	fn system_add_reserved_peer(&self, peer: String) -> Result<()> {
+		self.network.add_reserved_peer(peer).map_err(Error::MalformattedPeerArg)
+	}
+
+	fn system_remove_reserved_peer(&self, peer: String) -> Result<()> {
+		let (peer_id, _) = network::parse_str_addr(&peer)
+			.map_err(|e| Error::MalformattedPeerArg(format!("{:?}", e)))?;
+		self.network.remove_reserved_peer(peer_id);
+		Ok(())
+	}
+
+	fn system_set_reserved_only(&self, reserved_only: bool) -> Result<()> {
+		if reserved_only {
+			self.network.deny_unreserved_peers();
+		} else {
+			self.network.accept_unreserved_peers();
+		}
+		Ok(())
+	}
 }
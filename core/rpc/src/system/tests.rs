@@ -21,6 +21,7 @@ use network::config::Roles;
 use test_client::runtime::Block;
 use assert_matches::assert_matches;
 use futures::sync::mpsc;
+use parking_lot::Mutex;
 
 struct Status {
 	pub peers: usize,
@@ -78,9 +79,33 @@ impl network::SyncProvider<Block> for Status {
 		self.is_syncing
 	}
 }
+#[derive(Default)]
+struct Network {
+	reserved_peers: Mutex<Vec<PeerId>>,
+	reserved_only: Mutex<bool>,
+}
 
+impl network::ManageNetwork for Network {
+	fn accept_unreserved_peers(&self) {
+		*self.reserved_only.lock() = false;
+	}
 
-fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
+	fn deny_unreserved_peers(&self) {
+		*self.reserved_only.lock() = true;
+	}
+
+	fn remove_reserved_peer(&self, peer: PeerId) {
+		self.reserved_peers.lock().retain(|p| *p != peer);
+	}
+
+	fn add_reserved_peer(&self, peer: String) -> ::std::result::Result<(), String> {
+		let (peer_id, _) = network::parse_str_addr(&peer).map_err(|e| format!("{:?}", e))?;
+		self.reserved_peers.lock().push(peer_id);
+		Ok(())
+	}
+}
+
+fn api_with_network<T: Into<Option<Status>>>(sync: T, network: Arc<Network>) -> System<Block> {
 	let status = sync.into().unwrap_or_default();
 	let should_have_peers = !status.is_dev;
 	System::new(SystemInfo {
@@ -88,7 +113,11 @@ fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
 		impl_version: "0.2.0".into(),
 		chain_name: "testchain".into(),
 		properties: Default::default(),
-	}, Arc::new(status), should_have_peers)
+	}, Arc::new(status), network, should_have_peers)
+}
+
+fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
+	api_with_network(sync, Default::default())
 }
 
 #[test]
@@ -213,3 +242,48 @@ fn system_network_state() {
 		}
 	);
 }
+
+#[test]
+fn system_reserved_peers() {
+	let network = Arc::new(Network::default());
+	let api = api_with_network(None, network.clone());
+	let peer_id = PeerId::random();
+	let peer = format!("/ip4/127.0.0.1/tcp/30333/p2p/{}", peer_id.to_base58());
+
+	api.system_add_reserved_peer(peer.clone()).unwrap();
+	assert_eq!(*network.reserved_peers.lock(), vec![peer_id]);
+
+	api.system_remove_reserved_peer(peer).unwrap();
+	assert!(network.reserved_peers.lock().is_empty());
+
+	assert_matches!(
+		api.system_add_reserved_peer("/ip4/127.0.0.1/tcp/30333".into()),
+		Err(Error::MalformattedPeerArg(_))
+	);
+	assert_matches!(
+		api.system_remove_reserved_peer("not a multiaddr".into()),
+		Err(Error::MalformattedPeerArg(_))
+	);
+}
+
+#[test]
+fn system_set_reserved_only() {
+	let network = Arc::new(Network::default());
+	let api = api_with_network(None, network.clone());
+
+	api.system_set_reserved_only(true).unwrap();
+	assert!(*network.reserved_only.lock());
+
+	api.system_set_reserved_only(false).unwrap();
+	assert!(!*network.reserved_only.lock());
+}
+
+#[test]
+fn reserved_peer_methods_are_unsafe() {
+	let methods = api(None).to_delegate().into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+
+	for method in &["system_addReservedPeer", "system_removeReservedPeer", "system_setReservedOnly"] {
+		assert!(methods.iter().any(|name| name == method), "{} is not served", method);
+		assert!(crate::UNSAFE_METHODS.contains(method), "{} is not denied on public interfaces", method);
+	}
+}
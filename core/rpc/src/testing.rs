@@ -0,0 +1,177 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A mock client for testing the RPC extensions backed by a runtime API.
+//!
+//! The client records the calls made to its runtime API and answers all of them with the same
+//! result. Tests implement the runtime API under test for `RuntimeApi`.
+
+use std::sync::Arc;
+
+use client::{blockchain::{self, HeaderBackend}, error::Result as ClientResult};
+use client::runtime_api::{Core, RuntimeVersion, ApiExt};
+use parking_lot::Mutex;
+use primitives::{H256, NativeOrEncoded, ExecutionContext};
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{ApiRef, Block as BlockT, NumberFor, Header as HeaderT, ProvideRuntimeApi};
+use test_client::runtime::Block;
+
+/// A client with a runtime that records the parameters of runtime API calls and returns a
+/// fixed result.
+pub struct TestClient<Params, Output> {
+	/// The hash returned as the best block.
+	pub best_hash: H256,
+	/// The block and parameters of each call made to the runtime API.
+	pub calls: Arc<Mutex<Vec<(BlockId<Block>, Params)>>>,
+	/// The result of every call made to the runtime API.
+	pub result: Output,
+}
+
+impl<Params, Output: Default> Default for TestClient<Params, Output> {
+	fn default() -> Self {
+		TestClient {
+			best_hash: Default::default(),
+			calls: Default::default(),
+			result: Default::default(),
+		}
+	}
+}
+
+impl<Params, Output: Clone> Clone for TestClient<Params, Output> {
+	fn clone(&self) -> Self {
+		TestClient {
+			best_hash: self.best_hash,
+			calls: self.calls.clone(),
+			result: self.result.clone(),
+		}
+	}
+}
+
+impl<Params, Output: Clone> TestClient<Params, Output> {
+	/// Record a call to the runtime API and return the result.
+	pub fn record_call(&self, at: &BlockId<Block>, params: Params) -> ClientResult<NativeOrEncoded<Output>> {
+		self.calls.lock().push((at.clone(), params));
+		Ok(NativeOrEncoded::Native(self.result.clone()))
+	}
+}
+
+/// The runtime API of `TestClient`.
+pub struct RuntimeApi<Params, Output> {
+	/// The client the runtime API belongs to.
+	pub inner: TestClient<Params, Output>,
+}
+
+impl<Params, Output: Clone> ProvideRuntimeApi for TestClient<Params, Output> {
+	type Api = RuntimeApi<Params, Output>;
+
+	fn runtime_api<'a>(&'a self) -> ApiRef<'a, Self::Api> {
+		RuntimeApi { inner: self.clone() }.into()
+	}
+}
+
+impl<Params, Output> HeaderBackend<Block> for TestClient<Params, Output> where
+	Params: Send + Sync,
+	Output: Send + Sync,
+{
+	fn header(&self, _: BlockId<Block>) -> ClientResult<Option<<Block as BlockT>::Header>> {
+		unimplemented!("Not required for testing!")
+	}
+
+	fn info(&self) -> ClientResult<blockchain::Info<Block>> {
+		Ok(blockchain::Info {
+			best_hash: self.best_hash,
+			best_number: 1,
+			genesis_hash: Default::default(),
+			finalized_hash: Default::default(),
+			finalized_number: 0,
+		})
+	}
+
+	fn status(&self, _: BlockId<Block>) -> ClientResult<blockchain::BlockStatus> {
+		unimplemented!("Not required for testing!")
+	}
+
+	fn number(&self, _: H256) -> ClientResult<Option<<<Block as BlockT>::Header as HeaderT>::Number>> {
+		unimplemented!("Not required for testing!")
+	}
+
+	fn hash(&self, _: NumberFor<Block>) -> ClientResult<Option<H256>> {
+		unimplemented!("Not required for testing!")
+	}
+}
+
+impl<Params, Output> Core<Block> for RuntimeApi<Params, Output> {
+	fn Core_version_runtime_api_impl(
+		&self,
+		_: &BlockId<Block>,
+		_: ExecutionContext,
+		_: Option<()>,
+		_: Vec<u8>,
+	) -> ClientResult<NativeOrEncoded<RuntimeVersion>> {
+		unimplemented!("Not required for testing!")
+	}
+
+	fn Core_execute_block_runtime_api_impl(
+		&self,
+		_: &BlockId<Block>,
+		_: ExecutionContext,
+		_: Option<(Block)>,
+		_: Vec<u8>,
+	) -> ClientResult<NativeOrEncoded<()>> {
+		unimplemented!("Not required for testing!")
+	}
+
+	fn Core_initialize_block_runtime_api_impl(
+		&self,
+		_: &BlockId<Block>,
+		_: ExecutionContext,
+		_: Option<&<Block as BlockT>::Header>,
+		_: Vec<u8>,
+	) -> ClientResult<NativeOrEncoded<()>> {
+		unimplemented!("Not required for testing!")
+	}
+
+	fn Core_authorities_runtime_api_impl(
+		&self,
+		_: &BlockId<Block>,
+		_: ExecutionContext,
+		_: Option<()>,
+		_: Vec<u8>,
+	) -> ClientResult<NativeOrEncoded<Vec<primitives::sr25519::Public>>> {
+		unimplemented!("Not required for testing!")
+	}
+}
+
+impl<Params, Output> ApiExt<Block> for RuntimeApi<Params, Output> {
+	fn map_api_result<F: FnOnce(&Self) -> std::result::Result<R, E>, R, E>(
+		&self,
+		_: F
+	) -> std::result::Result<R, E> {
+		unimplemented!("Not required for testing!")
+	}
+
+	fn runtime_version_at(&self, _: &BlockId<Block>) -> ClientResult<RuntimeVersion> {
+		unimplemented!("Not required for testing!")
+	}
+
+	fn record_proof(&mut self) {
+		unimplemented!("Not required for testing!")
+	}
+
+	fn extract_proof(&mut self) -> Option<Vec<Vec<u8>>> {
+		unimplemented!("Not required for testing!")
+	}
+}
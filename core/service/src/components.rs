@@ -16,24 +16,26 @@
 
 //! Substrate service components.
 
-use std::{sync::Arc, net::SocketAddr, ops::Deref, ops::DerefMut};
+use std::{sync::Arc, net::SocketAddr, ops::Deref, ops::DerefMut, path::PathBuf};
 use serde::{Serialize, de::DeserializeOwned};
 use tokio::runtime::TaskExecutor;
 use crate::chain_spec::ChainSpec;
 use client_db;
 use client::{self, Client, runtime_api};
-use crate::{error, Service, maybe_start_server};
+use crate::{error, Service, AuthorityKeyProvider, maybe_start_server};
 use consensus_common::{import_queue::ImportQueue, SelectChain};
-use network::{self, OnDemand, FinalityProofProvider};
+use network::{self, OnDemand, FinalityProofProvider, WarpSyncProvider};
 use substrate_executor::{NativeExecutor, NativeExecutionDispatch};
 use transaction_pool::txpool::{self, Options as TransactionPoolOptions, Pool as TransactionPool};
 use runtime_primitives::{
-	BuildStorage, traits::{Block as BlockT, Header as HeaderT, ProvideRuntimeApi}, generic::BlockId
+	BuildStorage, traits::{Block as BlockT, Header as HeaderT, ProvideRuntimeApi, Extrinsic as ExtrinsicT},
+	generic::BlockId,
 };
 use crate::config::Configuration;
 use primitives::{Blake2Hasher, H256};
 use rpc::{self, apis::system::SystemInfo};
 use parking_lot::Mutex;
+use log::warn;
 
 // Type aliases.
 // These exist mainly to avoid typing `<F as Factory>::Foo` all over the code.
@@ -120,6 +122,9 @@ pub type ComponentClient<C> = Client<
 /// Block type for `Components`
 pub type ComponentBlock<C> = <<C as Components>::Factory as ServiceFactory>::Block;
 
+/// Block hash type for `Components`
+pub type ComponentBlockHash<C> = <ComponentBlock<C> as BlockT>::Hash;
+
 /// Extrinsic hash type for `Components`
 pub type ComponentExHash<C> = <<C as Components>::TransactionPoolApi as txpool::ChainApi>::Hash;
 
@@ -129,6 +134,19 @@ pub type ComponentExtrinsic<C> = <ComponentBlock<C> as BlockT>::Extrinsic;
 /// Extrinsic pool API type for `Components`.
 pub type PoolApi<C> = <C as Components>::TransactionPoolApi;
 
+/// Offchain workers local storage type for `Components`.
+pub type ComponentOffchainStorage<C> = <
+	<C as Components>::Backend as client::backend::Backend<ComponentBlock<C>, Blake2Hasher>
+>::OffchainStorage;
+
+/// Offchain workers manager type for `Components`.
+pub type ComponentOffchainWorkers<C> = offchain::OffchainWorkers<
+	ComponentClient<C>,
+	ComponentOffchainStorage<C>,
+	AuthorityKeyProvider,
+	ComponentBlock<C>,
+>;
+
 /// A set of traits for the runtime genesis config.
 pub trait RuntimeGenesis: Serialize + DeserializeOwned + BuildStorage {}
 impl<T: Serialize + DeserializeOwned + BuildStorage> RuntimeGenesis for T {}
@@ -140,14 +158,23 @@ pub trait StartRPC<C: Components> {
 	fn start_rpc(
 		client: Arc<ComponentClient<C>>,
 		network: Arc<network::SyncProvider<ComponentBlock<C>>>,
+		network_manager: Arc<network::ManageNetwork + Send + Sync>,
 		should_have_peers: bool,
 		system_info: SystemInfo,
 		rpc_http: Option<SocketAddr>,
 		rpc_ws: Option<SocketAddr>,
+		rpc_ipc: Option<PathBuf>,
+		rpc_http_rate_limit: Option<u32>,
 		rpc_ws_max_connections: Option<usize>,
+		rpc_ws_max_subscriptions: Option<usize>,
+		rpc_ws_rate_limit: Option<u32>,
+		rpc_ipc_max_subscriptions: Option<usize>,
+		rpc_ipc_rate_limit: Option<u32>,
 		rpc_cors: Option<Vec<String>>,
+		rpc_methods: rpc::RpcMethods,
 		task_executor: TaskExecutor,
 		transaction_pool: Arc<TransactionPool<C::TransactionPoolApi>>,
+		rpc_extension: rpc::RpcExtension,
 	) -> error::Result<Self::ServersHandle>;
 }
 
@@ -155,19 +182,32 @@ impl<C: Components> StartRPC<Self> for C where
 	ComponentClient<C>: ProvideRuntimeApi,
 	<ComponentClient<C> as ProvideRuntimeApi>::Api: runtime_api::Metadata<ComponentBlock<C>>,
 {
-	type ServersHandle = (Option<rpc::HttpServer>, Option<Mutex<rpc::WsServer>>);
+	type ServersHandle = (
+		Option<rpc::HttpServer>,
+		Option<Mutex<rpc::WsServer>>,
+		Option<Mutex<rpc::IpcServer>>,
+	);
 
 	fn start_rpc(
 		client: Arc<ComponentClient<C>>,
 		network: Arc<network::SyncProvider<ComponentBlock<C>>>,
+		network_manager: Arc<network::ManageNetwork + Send + Sync>,
 		should_have_peers: bool,
 		rpc_system_info: SystemInfo,
 		rpc_http: Option<SocketAddr>,
 		rpc_ws: Option<SocketAddr>,
+		rpc_ipc: Option<PathBuf>,
+		rpc_http_rate_limit: Option<u32>,
 		rpc_ws_max_connections: Option<usize>,
+		rpc_ws_max_subscriptions: Option<usize>,
+		rpc_ws_rate_limit: Option<u32>,
+		rpc_ipc_max_subscriptions: Option<usize>,
+		rpc_ipc_rate_limit: Option<u32>,
 		rpc_cors: Option<Vec<String>>,
+		rpc_methods: rpc::RpcMethods,
 		task_executor: TaskExecutor,
 		transaction_pool: Arc<TransactionPool<C::TransactionPoolApi>>,
+		rpc_extension: rpc::RpcExtension,
 	) -> error::Result<Self::ServersHandle> {
 		let handler = || {
 			let client = client.clone();
@@ -178,30 +218,49 @@ impl<C: Components> StartRPC<Self> for C where
 				client.clone(), transaction_pool.clone(), subscriptions
 			);
 			let system = rpc::apis::system::System::new(
-				rpc_system_info.clone(), network.clone(), should_have_peers
+				rpc_system_info.clone(), network.clone(), network_manager.clone(), should_have_peers
 			);
 			rpc::rpc_handler::<ComponentBlock<C>, ComponentExHash<C>, _, _, _, _>(
 				state,
 				chain,
 				author,
 				system,
+				rpc_extension.clone(),
 			)
 		};
 
 		Ok((
 			maybe_start_server(
 				rpc_http,
-				|address| rpc::start_http(address, rpc_cors.as_ref(), handler()),
+				|address| rpc::start_http(
+					address,
+					rpc_http_rate_limit,
+					rpc_cors.as_ref(),
+					rpc_methods,
+					handler(),
+				),
 			)?,
 			maybe_start_server(
 				rpc_ws,
 				|address| rpc::start_ws(
 					address,
 					rpc_ws_max_connections,
+					rpc_ws_max_subscriptions,
+					rpc_ws_rate_limit,
 					rpc_cors.as_ref(),
+					rpc_methods,
 					handler(),
 				),
 			)?.map(Mutex::new),
+			match rpc_ipc {
+				Some(ref path) => Some(Mutex::new(rpc::start_ipc(
+					path,
+					rpc_ipc_max_subscriptions,
+					rpc_ipc_rate_limit,
+					handler(),
+				)?)),
+				None => None,
+			},
 		))
 	}
 }
@@ -212,6 +271,8 @@ pub trait MaintainTransactionPool<C: Components> {
 		id: &BlockId<ComponentBlock<C>>,
 		client: &ComponentClient<C>,
 		transaction_pool: &TransactionPool<C::TransactionPoolApi>,
+		retracted: &[ComponentBlockHash<C>],
+		enacted: &[ComponentBlockHash<C>],
 	) -> error::Result<()>;
 }
 
@@ -219,6 +280,8 @@ fn maintain_transaction_pool<Api, Backend, Block, Executor, PoolApi>(
 	id: &BlockId<Block>,
 	client: &Client<Backend, Executor, Block, Api>,
 	transaction_pool: &TransactionPool<PoolApi>,
+	retracted: &[Block::Hash],
+	enacted: &[Block::Hash],
 ) -> error::Result<()> where
 	Block: BlockT<Hash = <Blake2Hasher as ::primitives::Hasher>::Out>,
 	Backend: client::backend::Backend<Block, Blake2Hasher>,
@@ -227,15 +290,37 @@ fn maintain_transaction_pool<Api, Backend, Block, Executor, PoolApi>(
 	Executor: client::CallExecutor<Block, Blake2Hasher>,
 	PoolApi: txpool::ChainApi<Hash = Block::Hash, Block = Block>,
 {
+	// Put transactions from retracted blocks back into the pool.
+	for hash in retracted {
+		if let Some(block) = client.block(&BlockId::hash(*hash))? {
+			let extrinsics = block.block.extrinsics().iter()
+				.filter(|xt| xt.is_signed().unwrap_or(true))
+				.cloned();
+			if let Err(e) = transaction_pool.resubmit_at(id, extrinsics) {
+				warn!("Error re-submitting transactions from retracted block {:?}: {:?}", hash, e);
+			}
+		}
+	}
+
 	// Avoid calling into runtime if there is nothing to prune from the pool anyway.
 	if transaction_pool.status().is_empty() {
 		return Ok(())
 	}
 
-	if let Some(block) = client.block(id)? {
-		let parent_id = BlockId::hash(*block.block.header().parent_hash());
-		let extrinsics = block.block.extrinsics();
-		transaction_pool.prune(id, &parent_id, extrinsics).map_err(|e| format!("{:?}", e))?;
+	// Prune transactions included in the blocks that joined the best chain together
+	// with the imported one, and then in the imported block itself.
+	let enacted = enacted.iter().map(|hash| BlockId::hash(*hash));
+	for id in enacted.chain(std::iter::once(*id)) {
+		if let Some(block) = client.block(&id)? {
+			let parent_id = BlockId::hash(*block.block.header().parent_hash());
+			let extrinsics = block.block.extrinsics();
+			transaction_pool.prune(&id, &parent_id, extrinsics).map_err(|e| format!("{:?}", e))?;
+		}
+	}
+
+	// After a re-org some of the ready transactions might not be valid anymore.
+	if !retracted.is_empty() {
+		transaction_pool.revalidate_ready(id).map_err(|e| format!("{:?}", e))?;
 	}
 
 	Ok(())
@@ -249,15 +334,17 @@ impl<C: Components> MaintainTransactionPool<Self> for C where
 		id: &BlockId<ComponentBlock<C>>,
 		client: &ComponentClient<C>,
 		transaction_pool: &TransactionPool<C::TransactionPoolApi>,
+		retracted: &[ComponentBlockHash<C>],
+		enacted: &[ComponentBlockHash<C>],
 	) -> error::Result<()> {
-		maintain_transaction_pool(id, client, transaction_pool)
+		maintain_transaction_pool(id, client, transaction_pool, retracted, enacted)
 	}
 }
 
 pub trait OffchainWorker<C: Components> {
 	fn offchain_workers(
 		number: &FactoryBlockNumber<C::Factory>,
-		offchain: &offchain::OffchainWorkers<ComponentClient<C>, ComponentBlock<C>>,
+		offchain: &ComponentOffchainWorkers<C>,
 		pool: &Arc<TransactionPool<C::TransactionPoolApi>>,
 	) -> error::Result<()>;
 }
@@ -265,10 +352,11 @@ pub trait OffchainWorker<C: Components> {
 impl<C: Components> OffchainWorker<Self> for C where
 	ComponentClient<C>: ProvideRuntimeApi,
 	<ComponentClient<C> as ProvideRuntimeApi>::Api: offchain::OffchainWorkerApi<ComponentBlock<C>>,
+	ComponentOffchainStorage<C>: 'static,
 {
 	fn offchain_workers(
 		number: &FactoryBlockNumber<C::Factory>,
-		offchain: &offchain::OffchainWorkers<ComponentClient<C>, ComponentBlock<C>>,
+		offchain: &ComponentOffchainWorkers<C>,
 		pool: &Arc<TransactionPool<C::TransactionPoolApi>>,
 	) -> error::Result<()> {
 		Ok(offchain.on_block_imported(number, pool))
@@ -341,6 +429,26 @@ pub trait ServiceFactory: 'static + Sized {
 		client: Arc<FullClient<Self>>
 	) -> Result<Option<Arc<FinalityProofProvider<Self::Block>>>, error::Error>;
 
+	/// Build warp sync proof provider for serving and verifying warp sync proofs on full node.
+	///
+	/// By default warp sync is not supported.
+	fn build_warp_sync_provider(
+		_client: Arc<FullClient<Self>>
+	) -> Result<Option<Arc<WarpSyncProvider<Self::Block>>>, error::Error> {
+		Ok(None)
+	}
+
+	/// Build runtime-specific RPC methods served by the full node.
+	///
+	/// By default no methods are added.
+	fn build_rpc_extension(
+		_config: &FactoryFullConfiguration<Self>,
+		_client: Arc<FullClient<Self>>,
+		_subscriptions: rpc::apis::Subscriptions,
+	) -> rpc::RpcExtension {
+		Vec::new()
+	}
+
 	/// Build the Fork Choice algorithm for full client
 	fn build_select_chain(
 		config: &mut FactoryFullConfiguration<Self>,
@@ -437,6 +545,18 @@ pub trait Components: Sized + 'static {
 		client: Arc<ComponentClient<Self>>
 	) -> Result<Option<Arc<FinalityProofProvider<<Self::Factory as ServiceFactory>::Block>>>, error::Error>;
 
+	/// Warp sync proof provider for serving network requests and verifying warp sync proofs.
+	fn build_warp_sync_provider(
+		client: Arc<ComponentClient<Self>>
+	) -> Result<Option<Arc<WarpSyncProvider<<Self::Factory as ServiceFactory>::Block>>>, error::Error>;
+
+	/// Runtime-specific RPC methods.
+	fn build_rpc_extension(
+		config: &FactoryFullConfiguration<Self::Factory>,
+		client: Arc<ComponentClient<Self>>,
+		subscriptions: rpc::apis::Subscriptions,
+	) -> rpc::RpcExtension;
+
 	/// Build fork choice selector
 	fn build_select_chain(
 		config: &mut FactoryFullConfiguration<Self::Factory>,
@@ -501,6 +621,7 @@ impl<Factory: ServiceFactory> Components for FullComponents<Factory> {
 			state_cache_size: config.state_cache_size,
 			path: config.database_path.as_str().into(),
 			pruning: config.pruning.clone(),
+			transaction_index: config.transaction_index,
 		};
 		Ok((Arc::new(client_db::new_client(
 			db_settings,
@@ -539,6 +660,20 @@ impl<Factory: ServiceFactory> Components for FullComponents<Factory> {
 	) -> Result<Option<Arc<FinalityProofProvider<<Self::Factory as ServiceFactory>::Block>>>, error::Error> {
 		Factory::build_finality_proof_provider(client)
 	}
+
+	fn build_warp_sync_provider(
+		client: Arc<ComponentClient<Self>>
+	) -> Result<Option<Arc<WarpSyncProvider<<Self::Factory as ServiceFactory>::Block>>>, error::Error> {
+		Factory::build_warp_sync_provider(client)
+	}
+
+	fn build_rpc_extension(
+		config: &FactoryFullConfiguration<Self::Factory>,
+		client: Arc<ComponentClient<Self>>,
+		subscriptions: rpc::apis::Subscriptions,
+	) -> rpc::RpcExtension {
+		Factory::build_rpc_extension(config, client, subscriptions)
+	}
 }
 
 /// A struct that implement `Components` for the light client.
@@ -593,6 +728,7 @@ impl<Factory: ServiceFactory> Components for LightComponents<Factory> {
 			state_cache_size: config.state_cache_size,
 			path: config.database_path.as_str().into(),
 			pruning: config.pruning.clone(),
+			transaction_index: false,
 		};
 		let db_storage = client_db::light::LightStorage::new(db_settings)?;
 		let light_blockchain = client::light::new_light_blockchain(db_storage);
@@ -622,6 +758,20 @@ impl<Factory: ServiceFactory> Components for LightComponents<Factory> {
 	) -> Result<Option<Arc<FinalityProofProvider<<Self::Factory as ServiceFactory>::Block>>>, error::Error> {
 		Ok(None)
 	}
+
+	fn build_warp_sync_provider(
+		_client: Arc<ComponentClient<Self>>
+	) -> Result<Option<Arc<WarpSyncProvider<<Self::Factory as ServiceFactory>::Block>>>, error::Error> {
+		Ok(None)
+	}
+
+	fn build_rpc_extension(
+		_config: &FactoryFullConfiguration<Self::Factory>,
+		_client: Arc<ComponentClient<Self>>,
+		_subscriptions: rpc::apis::Subscriptions,
+	) -> rpc::RpcExtension {
+		Vec::new()
+	}
 	fn build_select_chain(
 		_config: &mut FactoryFullConfiguration<Self::Factory>,
 		_client: Arc<ComponentClient<Self>>
@@ -667,10 +817,74 @@ mod tests {
 			&id,
 			&client,
 			&pool,
+			&[],
+			&[],
 		).unwrap();
 
 		// then
 		assert_eq!(pool.status().ready, 0);
 		assert_eq!(pool.status().future, 0);
 	}
+
+	#[test]
+	fn should_add_reverted_transactions_to_the_pool() {
+		use client::BlockchainEvents;
+		use futures::Stream;
+
+		let client = Arc::new(substrate_test_client::new());
+		let pool = TransactionPool::new(Default::default(), ::transaction_pool::ChainApi::new(client.clone()));
+		let mut notifications = client.import_notification_stream().wait();
+		let transfer = |from: AccountKeyring| Transfer {
+			amount: 5,
+			nonce: 0,
+			from: from.into(),
+			to: Default::default(),
+		}.into_signed_tx();
+		let (alice, bob) = (transfer(AccountKeyring::Alice), transfer(AccountKeyring::Bob));
+		let genesis = client.genesis_hash();
+		let mut maintain = || {
+			let notification = notifications.next().unwrap().unwrap();
+			maintain_transaction_pool(
+				&BlockId::hash(notification.hash),
+				&client,
+				&pool,
+				&notification.retracted,
+				&notification.enacted,
+			).unwrap();
+			notification
+		};
+
+		// import `a1` containing alice's transfer
+		pool.submit_one(&BlockId::hash(genesis), alice.clone()).unwrap();
+		let mut builder = client.new_block_at(&BlockId::hash(genesis), Default::default()).unwrap();
+		builder.push(alice.clone()).unwrap();
+		let a1 = builder.bake().unwrap();
+		let a1_hash = a1.header().hash();
+		client.import(BlockOrigin::Own, a1).unwrap();
+		maintain();
+		assert_eq!(pool.status().ready, 0);
+
+		// import `b1` containing bob's transfer on a fork
+		let mut builder = client.new_block_at(&BlockId::hash(genesis), Default::default()).unwrap();
+		builder.push(bob.clone()).unwrap();
+		let b1 = builder.bake().unwrap();
+		let b1_hash = b1.header().hash();
+		client.import(BlockOrigin::Own, b1).unwrap();
+		assert!(!maintain().is_new_best);
+
+		// bob's transfer is still valid on top of `a1`
+		pool.submit_one(&BlockId::hash(a1_hash), bob.clone()).unwrap();
+		assert_eq!(pool.status().ready, 1);
+
+		// import `b2`, making the fork the best chain
+		let b2 = client.new_block_at(&BlockId::hash(b1_hash), Default::default()).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, b2).unwrap();
+		let notification = maintain();
+		assert_eq!(notification.retracted, vec![a1_hash]);
+		assert_eq!(notification.enacted, vec![b1_hash]);
+
+		// then alice's transfer is back in the pool and bob's is pruned
+		assert_eq!(pool.ready().map(|tx| tx.data.clone()).collect::<Vec<_>>(), vec![alice]);
+		assert_eq!(pool.status().future, 0);
+	}
 }
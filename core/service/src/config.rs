@@ -16,7 +16,8 @@
 
 //! Service configuration.
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, path::PathBuf};
+use rpc::RpcMethods;
 use transaction_pool;
 use crate::chain_spec::ChainSpec;
 pub use client::ExecutionStrategies;
@@ -27,6 +28,15 @@ use serde::{Serialize, de::DeserializeOwned};
 use target_info::Target;
 use tel::TelemetryEndpoints;
 
+/// How a development node authors blocks instead of running the consensus engines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sealing {
+	/// A block is authored on each `engine_createBlock` RPC call.
+	Manual,
+	/// A block is authored and finalized as soon as transactions enter the pool.
+	Instant,
+}
+
 /// Service configuration.
 #[derive(Clone)]
 pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
@@ -40,8 +50,14 @@ pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
 	pub roles: Roles,
 	/// Extrinsic pool configuration.
 	pub transaction_pool: transaction_pool::txpool::Options,
+	/// Persist the extrinsic pool contents across restarts.
+	pub transaction_pool_journal: bool,
 	/// Network configuration.
 	pub network: NetworkConfiguration,
+	/// Warp sync to a recent finalized block when starting from genesis.
+	pub warp_sync: bool,
+	/// Download the state of a recent block when starting from genesis.
+	pub fast_sync: bool,
 	/// Path to key files.
 	pub keystore_path: String,
 	/// Path to the database.
@@ -52,6 +68,8 @@ pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
 	pub state_cache_size: usize,
 	/// Pruning settings.
 	pub pruning: PruningMode,
+	/// Maintain an index of the extrinsics of canonical blocks by extrinsic hash.
+	pub transaction_index: bool,
 	/// Additional key seeds.
 	pub keys: Vec<String>,
 	/// Chain configuration.
@@ -66,10 +84,26 @@ pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
 	pub rpc_http: Option<SocketAddr>,
 	/// RPC over Websockets binding address. `None` if disabled.
 	pub rpc_ws: Option<SocketAddr>,
+	/// Maximum number of calls per second on the HTTP RPC server. `None` if unlimited.
+	pub rpc_http_rate_limit: Option<u32>,
 	/// Maximum number of connections for WebSockets RPC server. `None` if default.
 	pub rpc_ws_max_connections: Option<usize>,
+	/// Maximum number of subscriptions of each WebSockets RPC connection. `None` if default.
+	pub rpc_ws_max_subscriptions: Option<usize>,
+	/// Maximum number of calls per second on each WebSockets RPC connection. `None` if unlimited.
+	pub rpc_ws_rate_limit: Option<u32>,
+	/// Which RPC methods are exposed.
+	pub rpc_methods: RpcMethods,
 	/// CORS settings for HTTP & WS servers. `None` if all origins are allowed.
 	pub rpc_cors: Option<Vec<String>>,
+	/// Path of the RPC over IPC socket. `None` if disabled.
+	pub rpc_ipc: Option<PathBuf>,
+	/// Maximum number of subscriptions of each IPC RPC connection. `None` if default.
+	pub rpc_ipc_max_subscriptions: Option<usize>,
+	/// Maximum number of calls per second on each IPC RPC connection. `None` if unlimited.
+	pub rpc_ipc_rate_limit: Option<u32>,
+	/// Prometheus metrics server binding address. `None` if disabled.
+	pub prometheus_endpoint: Option<SocketAddr>,
 	/// Telemetry service URL. `None` if disabled.
 	pub telemetry_endpoints: Option<TelemetryEndpoints>,
 	/// The default number of 64KB pages to allocate for Wasm execution
@@ -80,6 +114,8 @@ pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
 	pub force_authoring: bool,
 	/// Disable GRANDPA when running in validator mode
 	pub disable_grandpa: bool,
+	/// Author blocks on request instead of running the consensus engines.
+	pub sealing: Option<Sealing>,
 	/// Node keystore's password
 	pub password: String,
 }
@@ -95,7 +131,10 @@ impl<C: Default, G: Serialize + DeserializeOwned + BuildStorage> Configuration<C
 			name: Default::default(),
 			roles: Roles::FULL,
 			transaction_pool: Default::default(),
+			transaction_pool_journal: false,
 			network: Default::default(),
+			warp_sync: false,
+			fast_sync: false,
 			keystore_path: Default::default(),
 			database_path: Default::default(),
 			database_cache_size: Default::default(),
@@ -103,16 +142,26 @@ impl<C: Default, G: Serialize + DeserializeOwned + BuildStorage> Configuration<C
 			keys: Default::default(),
 			custom: Default::default(),
 			pruning: PruningMode::default(),
+			transaction_index: true,
 			execution_strategies: Default::default(),
 			rpc_http: None,
 			rpc_ws: None,
+			rpc_ipc: None,
+			rpc_ipc_max_subscriptions: None,
+			rpc_ipc_rate_limit: None,
+			rpc_http_rate_limit: None,
 			rpc_ws_max_connections: None,
+			rpc_ws_max_subscriptions: None,
+			rpc_ws_rate_limit: None,
+			rpc_methods: Default::default(),
 			rpc_cors: Some(vec![]),
+			prometheus_endpoint: None,
 			telemetry_endpoints: None,
 			default_heap_pages: None,
 			offchain_worker: Default::default(),
 			force_authoring: false,
 			disable_grandpa: false,
+			sealing: None,
 			password: "".to_string(),
 		};
 		configuration.network.boot_nodes = configuration.chain_spec.boot_nodes().to_vec();
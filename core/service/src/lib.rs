@@ -31,20 +31,20 @@ use std::collections::HashMap;
 use futures::sync::mpsc;
 use parking_lot::Mutex;
 
-use client::BlockchainEvents;
+use client::{BlockchainEvents, backend::Backend as _};
 use exit_future::Signal;
 use futures::prelude::*;
 use keystore::Store as Keystore;
 use log::{info, warn, debug};
 use parity_codec::{Encode, Decode};
-use primitives::Pair;
+use primitives::{Pair, ed25519, crypto::{key_types, Sign}};
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::{Header, SaturatedConversion};
 use substrate_executor::NativeExecutor;
 use tel::{telemetry, SUBSTRATE_INFO};
 
 pub use self::error::Error;
-pub use config::{Configuration, Roles, PruningMode};
+pub use config::{Configuration, Roles, PruningMode, Sealing};
 pub use chain_spec::{ChainSpec, Properties};
 pub use transaction_pool::txpool::{
 	self, Pool as TransactionPool, Options as TransactionPoolOptions, ChainApi, IntoPoolError
@@ -57,13 +57,15 @@ pub use components::{ServiceFactory, FullBackend, FullExecutor, LightBackend,
 	ComponentBlock, FullClient, LightClient, FullComponents, LightComponents,
 	CodeExecutor, NetworkService, FactoryChainSpec, FactoryBlock,
 	FactoryFullConfiguration, RuntimeGenesis, FactoryGenesis,
-	ComponentExHash, ComponentExtrinsic, FactoryExtrinsic
+	ComponentExHash, ComponentExtrinsic, FactoryExtrinsic, ComponentOffchainWorkers,
 };
 use components::{StartRPC, MaintainTransactionPool, OffchainWorker};
 #[doc(hidden)]
 pub use std::{ops::Deref, result::Result, sync::Arc};
 #[doc(hidden)]
-pub use network::{FinalityProofProvider, OnDemand};
+pub use network::{FinalityProofProvider, WarpSyncProvider, OnDemand};
+#[doc(hidden)]
+pub use rpc::{RpcExtension, RpcMethods, apis::Subscriptions as RpcSubscriptions};
 #[doc(hidden)]
 pub use tokio::runtime::TaskExecutor;
 
@@ -75,14 +77,14 @@ pub struct Service<Components: components::Components> {
 	select_chain: Option<<Components as components::Components>::SelectChain>,
 	network: Option<Arc<components::NetworkService<Components::Factory>>>,
 	transaction_pool: Arc<TransactionPool<Components::TransactionPoolApi>>,
-	keystore: Keystore,
+	keystore: Arc<Keystore>,
 	exit: ::exit_future::Exit,
 	signal: Option<Signal>,
 	/// Configuration of this Service
 	pub config: FactoryFullConfiguration<Components::Factory>,
 	_rpc: Box<::std::any::Any + Send + Sync>,
 	_telemetry: Option<Arc<tel::Telemetry>>,
-	_offchain_workers: Option<Arc<offchain::OffchainWorkers<ComponentClient<Components>, ComponentBlock<Components>>>>,
+	_offchain_workers: Option<Arc<ComponentOffchainWorkers<Components>>>,
 	_telemetry_on_connect_sinks: Arc<Mutex<Vec<mpsc::UnboundedSender<()>>>>,
 }
 
@@ -129,18 +131,29 @@ impl<Components: components::Components> Service<Components> {
 		// Create client
 		let executor = NativeExecutor::new(config.default_heap_pages);
 
-		let mut keystore = Keystore::open(config.keystore_path.as_str().into())?;
+		// Keys are only written to disk encrypted with a password. Authorities need a persistent
+		// key, unless one is given on the command line.
+		let keystore = if !config.password.is_empty() {
+			Keystore::open(config.keystore_path.as_str().into(), &config.password)?
+		} else if config.roles == Roles::AUTHORITY && config.keys.is_empty() {
+			return Err(error::Error::Other(
+				"A keystore password is required to run as an authority; \
+				use `-i` or `--password-filename`".into()
+			));
+		} else {
+			Keystore::new_in_memory()
+		};
 
 		// This is meant to be for testing only
 		// FIXME #1063 remove this
 		for seed in &config.keys {
-			keystore.generate_from_seed(seed)?;
+			keystore.generate_from_seed_by_type::<ed25519::Pair>(key_types::ED25519, seed)?;
 		}
 		// Keep the public key for telemetry
-		let public_key = match keystore.contents()?.get(0) {
+		let public_key = match keystore.public_keys_by_type::<ed25519::Pair>(key_types::ED25519)?.get(0) {
 			Some(public_key) => public_key.clone(),
 			None => {
-				let key = keystore.generate(&config.password)?;
+				let key: ed25519::Pair = keystore.generate_by_type(key_types::ED25519)?;
 				let public_key = key.public();
 				info!("Generated a new keypair: {:?}", public_key);
 
@@ -148,6 +161,8 @@ impl<Components: components::Components> Service<Components> {
 			}
 		};
 
+		let keystore = Arc::new(keystore);
+
 		let (client, on_demand) = Components::build_client(&config, executor)?;
 		let select_chain = Components::build_select_chain(&mut config, client.clone())?;
 		let import_queue = Box::new(Components::build_import_queue(
@@ -156,6 +171,7 @@ impl<Components: components::Components> Service<Components> {
 			select_chain.clone(),
 		)?);
 		let finality_proof_provider = Components::build_finality_proof_provider(client.clone())?;
+		let warp_sync_provider = Components::build_warp_sync_provider(client.clone())?;
 		let chain_info = client.info()?.chain;
 
 		let version = config.full_version();
@@ -169,6 +185,12 @@ impl<Components: components::Components> Service<Components> {
 		let transaction_pool = Arc::new(
 			Components::build_transaction_pool(config.transaction_pool.clone(), client.clone())?
 		);
+		if config.transaction_pool_journal {
+			let best = BlockId::hash(chain_info.best_hash);
+			if let Err(e) = transaction_pool::journal::restore(&*client, &*transaction_pool, &best) {
+				warn!("Unable to restore transaction pool journal: {:?}", e);
+			}
+		}
 		let transaction_pool_adapter = Arc::new(TransactionPoolAdapter::<Components> {
 			imports_external_transactions: !config.roles.is_light(),
 			pool: transaction_pool.clone(),
@@ -193,6 +215,9 @@ impl<Components: components::Components> Service<Components> {
 			network_config: config.network.clone(),
 			chain: client.clone(),
 			finality_proof_provider,
+			warp_sync_provider,
+			warp_sync: config.warp_sync,
+			fast_sync: config.fast_sync,
 			on_demand,
 			transaction_pool: transaction_pool_adapter.clone() as _,
 			import_queue,
@@ -209,13 +234,25 @@ impl<Components: components::Components> Service<Components> {
 			.select(exit.clone())
 			.then(|_| Ok(())));
 
-		let offchain_workers =  if config.offchain_worker {
-			Some(Arc::new(offchain::OffchainWorkers::new(
-				client.clone(),
-				task_executor.clone(),
-			)))
-		} else {
-			None
+		let offchain_storage = client.backend().offchain_storage();
+		let offchain_workers = match (config.offchain_worker, offchain_storage) {
+			(true, Some(db)) => {
+				Some(Arc::new(offchain::OffchainWorkers::new(
+					client.clone(),
+					db,
+					AuthorityKeyProvider {
+						roles: config.roles,
+						keystore: keystore.clone(),
+					},
+					keystore.clone(),
+					task_executor.clone(),
+				)))
+			},
+			(true, None) => {
+				log::warn!("Offchain workers disabled, due to lack of offchain storage support in backend.");
+				None
+			},
+			_ => None,
 		};
 
 		{
@@ -224,11 +261,17 @@ impl<Components: components::Components> Service<Components> {
 			let txpool = Arc::downgrade(&transaction_pool);
 			let wclient = Arc::downgrade(&client);
 			let offchain = offchain_workers.as_ref().map(Arc::downgrade);
+			let best_height = metrics::register_gauge("substrate_block_height_best", "Height of the best block");
+			best_height.set(chain_info.best_number.saturated_into::<u64>());
 
 			let events = client.import_notification_stream()
 				.for_each(move |notification| {
 					let number = *notification.header.number();
 
+					if notification.is_new_best {
+						best_height.set(number.saturated_into::<u64>());
+					}
+
 					if let Some(network) = network.upgrade() {
 						network.on_block_imported(notification.hash, notification.header);
 					}
@@ -238,7 +281,11 @@ impl<Components: components::Components> Service<Components> {
 							&BlockId::hash(notification.hash),
 							&*client,
 							&*txpool,
+							&notification.retracted,
+							&notification.enacted,
 						).map_err(|e| warn!("Pool error processing new block: {:?}", e))?;
+
+						transaction_pool::metrics::report(&*txpool);
 					}
 
 					if let (Some(txpool), Some(offchain)) = (txpool.upgrade(), offchain.as_ref().and_then(|o| o.upgrade())) {
@@ -259,6 +306,11 @@ impl<Components: components::Components> Service<Components> {
 		{
 			// finality notifications
 			let network = Arc::downgrade(&network);
+			let finalized_height = metrics::register_gauge(
+				"substrate_block_height_finalized",
+				"Height of the last finalized block",
+			);
+			finalized_height.set(chain_info.finalized_number.saturated_into::<u64>());
 
 			// A utility stream that drops all ready items and only returns the last one.
 			// This is used to only keep the last finality notification and avoid
@@ -291,6 +343,7 @@ impl<Components: components::Components> Service<Components> {
 
 			let events = MostRecentNotification(client.finality_notification_stream().fuse())
 				.for_each(move |notification| {
+					finalized_height.set(notification.header.number().saturated_into::<u64>());
 					if let Some(network) = network.upgrade() {
 						network.on_block_finalized(notification.hash, notification.header);
 					}
@@ -305,11 +358,15 @@ impl<Components: components::Components> Service<Components> {
 		{
 			// extrinsic notifications
 			let network = Arc::downgrade(&network);
+			let txpool = Arc::downgrade(&transaction_pool);
 			let events = transaction_pool.import_notification_stream()
 				.for_each(move |_| {
 					if let Some(network) = network.upgrade() {
 						network.trigger_repropagate();
 					}
+					if let Some(txpool) = txpool.upgrade() {
+						transaction_pool::metrics::report(&*txpool);
+					}
 					Ok(())
 				})
 				.select(exit.clone())
@@ -318,6 +375,26 @@ impl<Components: components::Components> Service<Components> {
 			task_executor.spawn(events);
 		}
 
+		if config.transaction_pool_journal {
+			// extrinsics journal
+			match transaction_pool::journal::Journal::new(client.clone(), transaction_pool.clone()) {
+				Ok(mut journal) => {
+					let events = transaction_pool.extrinsic_notification_stream()
+						.for_each(move |xt| {
+							if let Err(e) = journal.append(&xt) {
+								warn!("Unable to write transaction pool journal: {:?}", e);
+							}
+							Ok(())
+						})
+						.select(exit.clone())
+						.then(|_| Ok(()));
+
+					task_executor.spawn(events);
+				},
+				Err(e) => warn!("Unable to open transaction pool journal: {:?}", e),
+			}
+		}
+
 
 		// RPC
 		let system_info = rpc::apis::system::SystemInfo {
@@ -326,19 +403,41 @@ impl<Components: components::Components> Service<Components> {
 			impl_version: config.impl_version.into(),
 			properties: config.chain_spec.properties(),
 		};
+		let rpc_extension = Components::build_rpc_extension(
+			&config,
+			client.clone(),
+			rpc::apis::Subscriptions::new(task_executor.clone()),
+		);
 		let rpc = Components::RuntimeServices::start_rpc(
 			client.clone(),
 			network.clone(),
+			network.clone(),
 			has_bootnodes,
 			system_info,
 			config.rpc_http,
 			config.rpc_ws,
+			config.rpc_ipc.clone(),
+			config.rpc_http_rate_limit,
 			config.rpc_ws_max_connections,
+			config.rpc_ws_max_subscriptions,
+			config.rpc_ws_rate_limit,
+			config.rpc_ipc_max_subscriptions,
+			config.rpc_ipc_rate_limit,
 			config.rpc_cors.clone(),
+			config.rpc_methods,
 			task_executor.clone(),
 			transaction_pool.clone(),
+			rpc_extension,
 		)?;
 
+		// Prometheus metrics
+		if let Some(address) = config.prometheus_endpoint {
+			let server = metrics::init_prometheus(address)?;
+			task_executor.spawn(server
+				.select(exit.clone())
+				.then(|_| Ok(())));
+		}
+
 		let telemetry_connection_sinks: Arc<Mutex<Vec<mpsc::UnboundedSender<()>>>> = Default::default();
 
 		// Telemetry
@@ -388,17 +487,24 @@ impl<Components: components::Components> Service<Components> {
 		})
 	}
 
+	/// give the authority signing key, if we are an authority and have a key. The key pair
+	/// stays in the keystore.
+	pub fn authority_signer(&self) -> Option<Arc<dyn Sign<ed25519::Pair>>> {
+		if self.config.roles != Roles::AUTHORITY { return None }
+		let public = self.keystore.public_keys_by_type::<ed25519::Pair>(key_types::ED25519).ok()?
+			.into_iter()
+			.next()?;
+		Some(Arc::new(keystore::LocalKey::new(self.keystore.clone(), key_types::ED25519, public)))
+	}
+
 	/// give the authority key, if we are an authority and have a key
 	pub fn authority_key(&self) -> Option<primitives::ed25519::Pair> {
-		if self.config.roles != Roles::AUTHORITY { return None }
-		let keystore = &self.keystore;
-		if let Ok(Some(Ok(key))) =  keystore.contents().map(|keys| keys.get(0)
-				.map(|k| keystore.load(k, &self.config.password)))
-		{
-			Some(key)
-		} else {
-			None
-		}
+		use offchain::AuthorityKeyProvider as _;
+
+		AuthorityKeyProvider {
+			roles: self.config.roles,
+			keystore: self.keystore.clone(),
+		}.authority_key()
 	}
 
 	/// return a shared instance of Telemetry (if enabled)
@@ -470,6 +576,27 @@ fn maybe_start_server<T, F>(address: Option<SocketAddr>, start: F) -> Result<Opt
 	})
 }
 
+/// A provider of the authority key stored in the node keystore.
+#[derive(Clone)]
+pub struct AuthorityKeyProvider {
+	roles: Roles,
+	keystore: Arc<Keystore>,
+}
+
+impl offchain::AuthorityKeyProvider for AuthorityKeyProvider {
+	fn authority_key(&self) -> Option<primitives::ed25519::Pair> {
+		if self.roles != Roles::AUTHORITY { return None }
+		let keystore = &self.keystore;
+		if let Ok(Some(Ok(key))) = keystore.public_keys_by_type::<ed25519::Pair>(key_types::ED25519)
+			.map(|keys| keys.get(0).map(|k| keystore.key_pair_by_type::<ed25519::Pair>(k, key_types::ED25519)))
+		{
+			Some(key)
+		} else {
+			None
+		}
+	}
+}
+
 /// Transaction pool adapter.
 pub struct TransactionPoolAdapter<C: Components> {
 	imports_external_transactions: bool,
@@ -616,7 +743,7 @@ impl<C: Components> network::TransactionPool<ComponentExHash<C>, ComponentBlock<
 /// 			{ |config, executor| <FullComponents<Factory>>::new(config, executor) },
 /// 		// Setup as Consensus Authority (if the role and key are given)
 /// 		AuthoritySetup = {
-/// 			|service: Self::FullService, executor: TaskExecutor, key: Option<Arc<ed25519::Pair>>| {
+/// 			|service: Self::FullService, executor: TaskExecutor, key: Option<Arc<dyn Sign<ed25519::Pair>>>| {
 /// 				Ok(service)
 /// 			}},
 /// 		LightService = LightComponents<Self>
@@ -632,6 +759,11 @@ impl<C: Components> network::TransactionPool<ComponentExHash<C>, ComponentBlock<
 /// 		FinalityProofProvider = { |client: Arc<FullClient<Self>>| {
 /// 				Ok(Some(Arc::new(grandpa::FinalityProofProvider::new(client.clone(), client)) as _))
 /// 			}},
+/// 		// `WarpSyncProvider` and `RpcExtension` are optional; without them warp sync is not
+/// 		// supported and no runtime-specific RPC methods are served.
+/// 		RpcExtension = { |config: &FactoryFullConfiguration<Self>, client: Arc<FullClient<Self>>, subscriptions| {
+/// 				Vec::new()
+/// 			}},
 /// 	}
 /// }
 /// ```
@@ -658,6 +790,8 @@ macro_rules! construct_service_factory {
 			SelectChain = $select_chain:ty
 				{ $( $select_chain_init:tt )* },
 			FinalityProofProvider = { $( $finality_proof_provider_init:tt )* },
+			$( WarpSyncProvider = { $( $warp_sync_provider_init:tt )* }, )?
+			$( RpcExtension = { $( $rpc_extension_init:tt )* }, )?
 		}
 	) => {
 		$( #[$attr] )*
@@ -729,6 +863,24 @@ macro_rules! construct_service_factory {
 				( $( $finality_proof_provider_init )* ) (client)
 			}
 
+			$(
+				fn build_warp_sync_provider(
+					client: Arc<$crate::FullClient<Self>>
+				) -> Result<Option<Arc<$crate::WarpSyncProvider<Self::Block>>>, $crate::Error> {
+					( $( $warp_sync_provider_init )* ) (client)
+				}
+			)?
+
+			$(
+				fn build_rpc_extension(
+					config: &$crate::FactoryFullConfiguration<Self>,
+					client: Arc<$crate::FullClient<Self>>,
+					subscriptions: $crate::RpcSubscriptions,
+				) -> $crate::RpcExtension {
+					( $( $rpc_extension_init )* ) (config, client, subscriptions)
+				}
+			)?
+
 			fn new_light(
 				config: $crate::FactoryFullConfiguration<Self>,
 				executor: $crate::TaskExecutor
@@ -743,7 +895,7 @@ macro_rules! construct_service_factory {
 			) -> Result<Self::FullService, $crate::Error>
 			{
 				( $( $full_service_init )* ) (config, executor.clone()).and_then(|service| {
-					let key = (&service).authority_key().map(Arc::new);
+					let key = (&service).authority_signer();
 					($( $authority_setup )*)(service, executor, key)
 				})
 			}
@@ -114,6 +114,7 @@ fn node_config<F: ServiceFactory> (
 		database_cache_size: None,
 		state_cache_size: 16777216,
 		pruning: Default::default(),
+		transaction_index: true,
 		keys: keys,
 		chain_spec: (*spec).clone(),
 		custom: Default::default(),
@@ -121,13 +122,25 @@ fn node_config<F: ServiceFactory> (
 		execution_strategies: Default::default(),
 		rpc_http: None,
 		rpc_ws: None,
+		rpc_ipc: None,
+		rpc_http_rate_limit: None,
 		rpc_ws_max_connections: None,
+		rpc_ws_max_subscriptions: None,
+		rpc_ws_rate_limit: None,
+		rpc_ipc_max_subscriptions: None,
+		rpc_ipc_rate_limit: None,
 		rpc_cors: None,
+		rpc_methods: Default::default(),
+		prometheus_endpoint: None,
 		telemetry_endpoints: None,
 		default_heap_pages: None,
 		offchain_worker: false,
+		transaction_pool_journal: false,
+		warp_sync: false,
+		fast_sync: false,
 		force_authoring: false,
 		disable_grandpa: false,
+		sealing: None,
 		password: "".to_string(),
 	}
 }
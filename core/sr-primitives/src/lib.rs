@@ -41,6 +41,7 @@ use traits::{SaturatedConversion, UniqueSaturatedInto};
 
 pub mod generic;
 pub mod transaction_validity;
+pub mod weights;
 
 /// A message indicating an invalid signature in extrinsic.
 pub const BAD_SIGNATURE: &str = "bad signature in extrinsic";
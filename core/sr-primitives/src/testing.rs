@@ -21,6 +21,7 @@ use std::{fmt::Debug, ops::Deref, fmt};
 use crate::codec::{Codec, Encode, Decode};
 use crate::traits::{self, Checkable, Applyable, BlakeTwo256, Convert};
 use crate::generic::DigestItem as GenDigestItem;
+use crate::weights::{Weighable, Weight};
 pub use substrate_primitives::H256;
 use substrate_primitives::U256;
 use substrate_primitives::sr25519::{Public as AuthorityId, Signature as AuthoritySignature};
@@ -239,3 +240,8 @@ impl<Call> Applyable for TestXt<Call> where
 		(self.2, self.0)
 	}
 }
+impl<Call: Weighable> Weighable for TestXt<Call> {
+	fn weight(&self, len: usize) -> Weight {
+		self.2.weight(len)
+	}
+}
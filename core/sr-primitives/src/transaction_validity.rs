@@ -65,6 +65,11 @@ pub enum TransactionValidity {
 		/// including in blocks that are authored on the current node, but will
 		/// never be sent to other peers.
 		propagate: bool,
+		/// Encoded account that signed the transaction (if any).
+		///
+		/// The account is resolved by the runtime, so that the pool can limit the number
+		/// of transactions from a single sender regardless of how it was addressed.
+		sender: Option<Vec<u8>>,
 	},
 	/// Transaction validity can't be determined.
 	Unknown(i8),
@@ -80,9 +85,10 @@ impl Decode for TransactionValidity {
 				let provides = Vec::decode(value)?;
 				let longevity = TransactionLongevity::decode(value)?;
 				let propagate = bool::decode(value).unwrap_or(true);
+				let sender = Option::decode(value).unwrap_or(None);
 
 				Some(TransactionValidity::Valid {
-					priority, requires, provides, longevity, propagate,
+					priority, requires, provides, longevity, propagate, sender,
 				})
 			},
 			2 => Some(TransactionValidity::Unknown(i8::decode(value)?)),
@@ -107,6 +113,7 @@ mod tests {
 			provides: vec![vec![4, 5, 6]],
 			longevity: 42,
 			propagate: true,
+			sender: None,
 		}));
 	}
 
@@ -118,12 +125,13 @@ mod tests {
 			provides: vec![vec![4, 5, 6]],
 			longevity: 42,
 			propagate: false,
+			sender: Some(vec![7, 8]),
 		};
 
 		let encoded = v.encode();
 		assert_eq!(
 			encoded,
-			vec![1, 5, 0, 0, 0, 0, 0, 0, 0, 4, 16, 1, 2, 3, 4, 4, 12, 4, 5, 6, 42, 0, 0, 0, 0, 0, 0, 0, 0]
+			vec![1, 5, 0, 0, 0, 0, 0, 0, 0, 4, 16, 1, 2, 3, 4, 4, 12, 4, 5, 6, 42, 0, 0, 0, 0, 0, 0, 0, 0, 1, 8, 7, 8]
 		);
 
 		// decode back
@@ -0,0 +1,79 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Dispatch weights.
+//!
+//! Every dispatchable function declares a weight: an estimate of the resources its execution
+//! consumes. The weight of an extrinsic determines the fee paid for it and how much of the
+//! block's capacity it uses up.
+
+/// Numeric range of a weight.
+pub type Weight = u32;
+
+/// Maximum total weight of the extrinsics of a block.
+pub const MAX_TRANSACTIONS_WEIGHT: Weight = 4 * 1024 * 1024;
+
+/// Something that has a weight, given the length of the extrinsic it is encoded in.
+pub trait Weighable {
+	/// Weight of `self`, included in an extrinsic of `len` bytes.
+	fn weight(&self, len: usize) -> Weight;
+}
+
+/// Weight declared for a dispatchable function.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum TransactionWeight {
+	/// A base weight plus a weight per byte of the encoded extrinsic.
+	Basic(Weight, Weight),
+	/// Uses up the whole capacity of a block.
+	Max,
+	/// Doesn't use any of the capacity of a block.
+	Free,
+}
+
+impl Weighable for TransactionWeight {
+	fn weight(&self, len: usize) -> Weight {
+		match self {
+			TransactionWeight::Basic(base, per_byte) => {
+				let len = if len > Weight::max_value() as usize { Weight::max_value() } else { len as Weight };
+				base.saturating_add(per_byte.saturating_mul(len))
+			},
+			TransactionWeight::Max => MAX_TRANSACTIONS_WEIGHT,
+			TransactionWeight::Free => 0,
+		}
+	}
+}
+
+impl Default for TransactionWeight {
+	/// The weight of functions that don't declare one: the length of the extrinsic in bytes.
+	fn default() -> Self {
+		TransactionWeight::Basic(0, 1)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn transaction_weights_work() {
+		assert_eq!(TransactionWeight::default().weight(100), 100);
+		assert_eq!(TransactionWeight::Basic(10, 2).weight(100), 210);
+		assert_eq!(TransactionWeight::Basic(10, 2).weight(usize::max_value()), Weight::max_value());
+		assert_eq!(TransactionWeight::Max.weight(0), MAX_TRANSACTIONS_WEIGHT);
+		assert_eq!(TransactionWeight::Free.weight(100), 0);
+	}
+}
@@ -205,6 +205,15 @@ impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 		}
 	}
 
+	pub fn insert_base_block<E: fmt::Debug>(&mut self, hash: &BlockHash, number: u64, parent_hash: &BlockHash, changeset: ChangeSet<Key>) -> Result<CommitSet<Key>, Error<E>> {
+		match self.mode {
+			PruningMode::ArchiveAll => self.insert_block(hash, number, parent_hash, changeset),
+			PruningMode::Constrained(_) | PruningMode::ArchiveCanonical => {
+				self.non_canonical.insert_base(hash, number, parent_hash, changeset)
+			}
+		}
+	}
+
 	pub fn canonicalize_block<E: fmt::Debug>(&mut self, hash: &BlockHash) -> Result<CommitSet<Key>, Error<E>> {
 		let mut commit = match self.mode {
 			PruningMode::ArchiveAll => {
@@ -335,6 +344,12 @@ impl<BlockHash: Hash, Key: Hash> StateDb<BlockHash, Key> {
 		self.db.write().insert_block(hash, number, parent_hash, changeset)
 	}
 
+	/// Add a block with unknown ancestry. It becomes the base of the non-canonical overlay,
+	/// so no other non-canonical blocks may be present.
+	pub fn insert_base_block<E: fmt::Debug>(&self, hash: &BlockHash, number: u64, parent_hash: &BlockHash, changeset: ChangeSet<Key>) -> Result<CommitSet<Key>, Error<E>> {
+		self.db.write().insert_base_block(hash, number, parent_hash, changeset)
+	}
+
 	/// Finalize a previously inserted block.
 	pub fn canonicalize_block<E: fmt::Debug>(&self, hash: &BlockHash) -> Result<CommitSet<Key>, Error<E>> {
 		self.db.write().canonicalize_block(hash)
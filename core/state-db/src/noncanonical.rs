@@ -230,6 +230,20 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 		Ok(commit)
 	}
 
+	/// Insert a block with unknown ancestry, making it the base of the overlay. The parent is
+	/// assumed to be canonicalized. Requires the overlay to be empty.
+	pub fn insert_base<E: fmt::Debug>(&mut self, hash: &BlockHash, number: u64, parent_hash: &BlockHash, changeset: ChangeSet<Key>) -> Result<CommitSet<Key>, Error<E>> {
+		if number == 0
+			|| !self.levels.is_empty()
+			|| !self.pending_insertions.is_empty()
+			|| !self.pending_canonicalizations.is_empty()
+		{
+			return Err(Error::InvalidBlock);
+		}
+		self.last_canonicalized = None;
+		self.insert(hash, number, parent_hash, changeset)
+	}
+
 	fn discard_journals(&self, level_index: usize, discarded_journals: &mut Vec<Vec<u8>>, hash: &BlockHash) {
 		if let Some(level) = self.levels.get(level_index) {
 			level.iter().for_each(|overlay| {
@@ -511,6 +525,28 @@ mod tests {
 		assert_eq!(overlay.last_canonicalized, overlay2.last_canonicalized);
 	}
 
+	#[test]
+	fn insert_base_after_canonicalize() {
+		let h1 = H256::random();
+		let h2 = H256::random();
+		let h3 = H256::random();
+		let mut db = make_db(&[1, 2]);
+		let mut overlay = NonCanonicalOverlay::<H256, H256>::new(&db).unwrap();
+		db.commit(&overlay.insert::<io::Error>(&h1, 1, &H256::default(), make_changeset(&[3], &[])).unwrap());
+		assert!(overlay.insert_base::<io::Error>(&h3, 10, &h2, ChangeSet::default()).is_err());
+		db.commit(&overlay.canonicalize::<io::Error>(&h1).unwrap());
+		overlay.apply_pending();
+		assert!(overlay.insert::<io::Error>(&h3, 10, &h2, ChangeSet::default()).is_err());
+
+		db.commit(&overlay.insert_base::<io::Error>(&h3, 10, &h2, make_changeset(&[4], &[])).unwrap());
+		overlay.apply_pending();
+		assert_eq!(overlay.last_canonicalized, Some((h2, 9)));
+		db.commit(&overlay.canonicalize::<io::Error>(&h3).unwrap());
+		overlay.apply_pending();
+		assert_eq!(overlay.last_canonicalized, Some((h3, 10)));
+		assert!(db.data_eq(&make_db(&[1, 2, 3, 4])));
+	}
+
 	#[test]
 	fn insert_canonicalize_two() {
 		let h1 = H256::random();
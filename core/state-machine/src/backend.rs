@@ -94,6 +94,44 @@ pub trait Backend<H: Hasher> {
 		all
 	}
 
+	/// Get at most `count` key/value pairs with keys starting with `prefix`, in key order.
+	/// If `start_key` is given, only keys strictly greater than it are returned.
+	fn paged_pairs(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error> {
+		let mut keys = self.keys(prefix);
+		keys.sort();
+		let mut pairs = Vec::new();
+		for key in keys.into_iter().filter(|key| start_key.map_or(true, |start| &key[..] > start)) {
+			if pairs.len() >= count {
+				break;
+			}
+			if let Some(value) = self.storage(&key)? {
+				pairs.push((key, value));
+			}
+		}
+		Ok(pairs)
+	}
+
+	/// Get at most `count` keys starting with `prefix`, in key order, without reading their
+	/// values. If `start_key` is given, only keys strictly greater than it are returned.
+	fn paged_keys(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<Vec<u8>>, Self::Error> {
+		let mut keys = self.keys(prefix);
+		keys.sort();
+		Ok(keys.into_iter()
+			.filter(|key| start_key.map_or(true, |start| &key[..] > start))
+			.take(count)
+			.collect())
+	}
+
 	/// Get all keys of child storage with given prefix
 	fn child_keys(&self, child_storage_key: &[u8], prefix: &[u8]) -> Vec<Vec<u8>> {
 		let mut all = Vec::new();
@@ -358,6 +396,40 @@ impl<H: Hasher> Backend<H> for InMemory<H> {
 			.collect()
 	}
 
+	fn paged_pairs(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error> {
+		let mut pairs: Vec<_> = self.inner.get(&None)
+			.into_iter()
+			.flat_map(|map| map.iter())
+			.filter(|(k, _)| k.starts_with(prefix) && start_key.map_or(true, |start| &k[..] > start))
+			.map(|(k, v)| (k.clone(), v.clone()))
+			.collect();
+		pairs.sort();
+		pairs.truncate(count);
+		Ok(pairs)
+	}
+
+	fn paged_keys(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<Vec<u8>>, Self::Error> {
+		let mut keys: Vec<_> = self.inner.get(&None)
+			.into_iter()
+			.flat_map(|map| map.keys())
+			.filter(|k| k.starts_with(prefix) && start_key.map_or(true, |start| &k[..] > start))
+			.cloned()
+			.collect();
+		keys.sort();
+		keys.truncate(count);
+		Ok(keys)
+	}
+
 	fn child_keys(&self, storage_key: &[u8], prefix: &[u8]) -> Vec<Vec<u8>> {
 		self.inner.get(&Some(storage_key.to_vec()))
 			.into_iter()
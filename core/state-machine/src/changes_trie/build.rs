@@ -218,6 +218,7 @@ mod test {
 				}),
 			].into_iter().collect(),
 			changes_trie_config: Some(Configuration { digest_interval: 4, digest_levels: 2 }),
+			trace: None,
 		};
 
 		(backend, storage, changes)
@@ -21,6 +21,7 @@ use log::warn;
 use crate::backend::Backend;
 use crate::changes_trie::{Storage as ChangesTrieStorage, compute_changes_trie_root};
 use crate::{Externalities, OverlayedChanges, ChildStorageKey};
+use crate::trace::StorageAccessKind;
 use hash_db::Hasher;
 use primitives::offchain;
 use primitives::storage::well_known_keys::is_child_storage_key;
@@ -138,6 +139,18 @@ where
 		self.storage_transaction = None;
 	}
 
+	/// Read a value without tracing the access.
+	fn read_storage(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.overlay.storage(key).map(|x| x.map(|x| x.to_vec())).unwrap_or_else(||
+			self.backend.storage(key).expect(EXT_NOT_ALLOWED_TO_FAIL))
+	}
+
+	/// Read a child storage value without tracing the access.
+	fn read_child_storage(&self, storage_key: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+		self.overlay.child_storage(storage_key, key).map(|x| x.map(|x| x.to_vec())).unwrap_or_else(||
+			self.backend.child_storage(storage_key, key).expect(EXT_NOT_ALLOWED_TO_FAIL))
+	}
+
 }
 
 #[cfg(test)]
@@ -174,14 +187,17 @@ where
 {
 	fn storage(&self, key: &[u8]) -> Option<Vec<u8>> {
 		let _guard = panic_handler::AbortGuard::new(true);
-		self.overlay.storage(key).map(|x| x.map(|x| x.to_vec())).unwrap_or_else(||
-			self.backend.storage(key).expect(EXT_NOT_ALLOWED_TO_FAIL))
+		let value = self.read_storage(key);
+		self.overlay.trace_access(None, key, || StorageAccessKind::Read(value.clone()));
+		value
 	}
 
 	fn storage_hash(&self, key: &[u8]) -> Option<H::Out> {
 		let _guard = panic_handler::AbortGuard::new(true);
-		self.overlay.storage(key).map(|x| x.map(|x| H::hash(x))).unwrap_or_else(||
-			self.backend.storage_hash(key).expect(EXT_NOT_ALLOWED_TO_FAIL))
+		let hash = self.overlay.storage(key).map(|x| x.map(|x| H::hash(x))).unwrap_or_else(||
+			self.backend.storage_hash(key).expect(EXT_NOT_ALLOWED_TO_FAIL));
+		self.overlay.trace_access(None, key, || StorageAccessKind::ReadHash(hash.as_ref().map(|hash| hash.as_ref().to_vec())));
+		hash
 	}
 
 	fn original_storage(&self, key: &[u8]) -> Option<Vec<u8>> {
@@ -196,25 +212,29 @@ where
 
 	fn child_storage(&self, storage_key: ChildStorageKey<H>, key: &[u8]) -> Option<Vec<u8>> {
 		let _guard = panic_handler::AbortGuard::new(true);
-		self.overlay.child_storage(storage_key.as_ref(), key).map(|x| x.map(|x| x.to_vec())).unwrap_or_else(||
-			self.backend.child_storage(storage_key.as_ref(), key).expect(EXT_NOT_ALLOWED_TO_FAIL))
+		let value = self.read_child_storage(storage_key.as_ref(), key);
+		self.overlay.trace_access(Some(storage_key.as_ref()), key, || StorageAccessKind::Read(value.clone()));
+		value
 	}
 
 	fn exists_storage(&self, key: &[u8]) -> bool {
 		let _guard = panic_handler::AbortGuard::new(true);
-		match self.overlay.storage(key) {
+		let exists = match self.overlay.storage(key) {
 			Some(x) => x.is_some(),
 			_ => self.backend.exists_storage(key).expect(EXT_NOT_ALLOWED_TO_FAIL),
-		}
+		};
+		self.overlay.trace_access(None, key, || StorageAccessKind::Exists(exists));
+		exists
 	}
 
 	fn exists_child_storage(&self, storage_key: ChildStorageKey<H>, key: &[u8]) -> bool {
 		let _guard = panic_handler::AbortGuard::new(true);
-
-		match self.overlay.child_storage(storage_key.as_ref(), key) {
+		let exists = match self.overlay.child_storage(storage_key.as_ref(), key) {
 			Some(x) => x.is_some(),
 			_ => self.backend.exists_child_storage(storage_key.as_ref(), key).expect(EXT_NOT_ALLOWED_TO_FAIL),
-		}
+		};
+		self.overlay.trace_access(Some(storage_key.as_ref()), key, || StorageAccessKind::Exists(exists));
+		exists
 	}
 
 	fn place_storage(&mut self, key: Vec<u8>, value: Option<Vec<u8>>) {
@@ -224,6 +244,7 @@ where
 			return;
 		}
 
+		self.overlay.trace_access(None, &key, || StorageAccessKind::Write(value.clone()));
 		self.mark_dirty();
 		self.overlay.set_storage(key, value);
 	}
@@ -231,6 +252,7 @@ where
 	fn place_child_storage(&mut self, storage_key: ChildStorageKey<H>, key: Vec<u8>, value: Option<Vec<u8>>) {
 		let _guard = panic_handler::AbortGuard::new(true);
 
+		self.overlay.trace_access(Some(storage_key.as_ref()), &key, || StorageAccessKind::Write(value.clone()));
 		self.mark_dirty();
 		self.overlay.set_child_storage(storage_key.into_owned(), key, value);
 	}
@@ -238,6 +260,7 @@ where
 	fn kill_child_storage(&mut self, storage_key: ChildStorageKey<H>) {
 		let _guard = panic_handler::AbortGuard::new(true);
 
+		self.overlay.trace_access(Some(storage_key.as_ref()), &[], || StorageAccessKind::KillChild);
 		self.mark_dirty();
 		self.overlay.clear_child_storage(storage_key.as_ref());
 		self.backend.for_keys_in_child_storage(storage_key.as_ref(), |key| {
@@ -252,6 +275,7 @@ where
 			return;
 		}
 
+		self.overlay.trace_access(None, prefix, || StorageAccessKind::ClearPrefix);
 		self.mark_dirty();
 		self.overlay.clear_prefix(prefix);
 		self.backend.for_keys_with_prefix(prefix, |key| {
@@ -381,6 +405,7 @@ mod tests {
 				digest_interval: 0,
 				digest_levels: 0,
 			}),
+			trace: None,
 		}
 	}
 
@@ -422,4 +447,78 @@ mod tests {
 		assert_eq!(ext.storage_changes_root(Default::default()).unwrap(),
 			Some(hex!("bcf494e41e29a15c9ae5caa053fe3cb8b446ee3e02a254efbdec7a19235b76e4").into()));
 	}
+
+	#[test]
+	fn storage_accesses_are_traced() {
+		use crate::trace::StorageAccess;
+
+		let mut overlay = prepare_overlay_with_changes();
+		overlay.enable_tracing(None, usize::max_value());
+		let backend = TestBackend::default();
+		let child_storage_key = b":child_storage:default:child".to_vec();
+		{
+			let mut ext = TestExt::new(&mut overlay, &backend, None, None);
+			assert_eq!(ext.storage(&[1]), Some(vec![100]));
+			assert_eq!(ext.storage_hash(&[1]), Some(Blake2Hasher::hash(&[100])));
+			assert!(!ext.exists_storage(&[2]));
+			ext.set_storage(vec![2], vec![200]);
+			ext.clear_storage(&[1]);
+			ext.clear_prefix(&[3]);
+			ext.kill_child_storage(ChildStorageKey::from_slice(&child_storage_key).unwrap());
+		}
+
+		let access = |key: &[u8], kind| StorageAccess {
+			extrinsic_index: Some(3),
+			child_storage_key: None,
+			key: key.to_vec(),
+			kind,
+		};
+		assert_eq!(overlay.take_trace().accesses, vec![
+			access(&[1], StorageAccessKind::Read(Some(vec![100]))),
+			access(&[1], StorageAccessKind::ReadHash(Some(Blake2Hasher::hash(&[100]).as_ref().to_vec()))),
+			access(&[2], StorageAccessKind::Exists(false)),
+			access(&[2], StorageAccessKind::Write(Some(vec![200]))),
+			access(&[1], StorageAccessKind::Write(None)),
+			access(&[3], StorageAccessKind::ClearPrefix),
+			StorageAccess {
+				child_storage_key: Some(child_storage_key),
+				..access(&[], StorageAccessKind::KillChild)
+			},
+		]);
+		assert_eq!(overlay.take_trace(), Default::default());
+	}
+
+	#[test]
+	fn storage_trace_is_filtered_and_limited() {
+		let mut overlay = prepare_overlay_with_changes();
+		overlay.enable_tracing(Some(vec![1]), 7);
+		let backend = TestBackend::default();
+		{
+			let mut ext = TestExt::new(&mut overlay, &backend, None, None);
+			ext.set_storage(vec![1, 1], vec![11, 11]);
+			ext.set_storage(vec![2], vec![20]);
+			ext.set_storage(vec![1, 2], vec![12]);
+			ext.set_storage(vec![1, 3], vec![13]);
+			ext.set_storage(vec![1], vec![10]);
+		}
+
+		let trace = overlay.take_trace();
+		assert_eq!(trace.accesses.iter().map(|access| access.key.clone()).collect::<Vec<_>>(), vec![
+			vec![1, 1],
+			vec![1, 2],
+		]);
+		assert!(trace.truncated);
+	}
+
+	#[test]
+	fn storage_accesses_are_not_traced_by_default() {
+		let mut overlay = prepare_overlay_with_changes();
+		let backend = TestBackend::default();
+		{
+			let ext = TestExt::new(&mut overlay, &backend, None, None);
+			assert_eq!(ext.storage(&[1]), Some(vec![100]));
+		}
+
+		assert_eq!(overlay.take_trace(), Default::default());
+	}
 }
@@ -36,6 +36,7 @@ mod overlayed_changes;
 mod proving_backend;
 mod trie_backend;
 mod trie_backend_essence;
+mod trace;
 
 use overlayed_changes::OverlayedChangeSet;
 pub use trie::{TrieMut, TrieDBMut, DBValue, MemoryDB};
@@ -53,6 +54,7 @@ pub use changes_trie::{
 	oldest_non_pruned_trie as oldest_non_pruned_changes_trie
 };
 pub use overlayed_changes::OverlayedChanges;
+pub use trace::{StorageAccess, StorageAccessKind, StorageTrace};
 pub use proving_backend::{
 	create_proof_check_backend, create_proof_check_backend_storage,
 	Recorder as ProofRecorder, ProvingBackend,
@@ -60,6 +62,24 @@ pub use proving_backend::{
 pub use trie_backend_essence::{TrieBackendStorage, Storage};
 pub use trie_backend::TrieBackend;
 
+/// Ordered range of the storage.
+///
+/// Both top-level and child trie entries count towards the size limit of a range, so the
+/// contents of a large child trie are split across several ranges.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StorageRange {
+	/// Top-level key-value pairs, ordered by key.
+	pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+	/// Contents of the child tries whose storage keys are in `entries`, or of the child trie the
+	/// range was started in.
+	pub children: Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)>,
+	/// True if the contents of the last child trie in `children` are incomplete. The next range
+	/// continues it after its last key.
+	pub partial_child: bool,
+	/// True if there are no more keys after the range.
+	pub complete: bool,
+}
+
 /// A wrapper around a child storage key.
 ///
 /// This wrapper ensures that the child storage key is correct and properly used.  It is
@@ -793,6 +813,32 @@ where
 	prove_read_on_trie_backend(&trie_backend, key)
 }
 
+/// Generate a single storage read proof for all given keys.
+pub fn prove_read_keys<B, H, I>(
+	backend: B,
+	keys: I,
+) -> Result<Vec<Vec<u8>>, Box<Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+	H::Out: Ord,
+	I: IntoIterator,
+	I::Item: AsRef<[u8]>,
+{
+	let trie_backend = backend.try_into_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<Error>)?;
+	let proving_backend = proving_backend::ProvingBackend::<_, H>::new(&trie_backend);
+	for key in keys {
+		proving_backend.storage(key.as_ref()).map_err(|e| Box::new(e) as Box<Error>)?;
+	}
+
+	// nodes shared between the keys are only included once.
+	let mut proof = proving_backend.extract_proof();
+	proof.sort();
+	proof.dedup();
+	Ok(proof)
+}
+
 /// Generate child storage read proof.
 pub fn prove_child_read<B, H>(
 	backend: B,
@@ -810,6 +856,34 @@ where
 }
 
 
+/// Generate a single child storage read proof for all given keys of the child trie at
+/// `storage_key`.
+pub fn prove_child_read_keys<B, H, I>(
+	backend: B,
+	storage_key: &[u8],
+	keys: I,
+) -> Result<Vec<Vec<u8>>, Box<Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+	H::Out: Ord,
+	I: IntoIterator,
+	I::Item: AsRef<[u8]>,
+{
+	let trie_backend = backend.try_into_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<Error>)?;
+	let proving_backend = proving_backend::ProvingBackend::<_, H>::new(&trie_backend);
+	for key in keys {
+		proving_backend.child_storage(storage_key, key.as_ref()).map_err(|e| Box::new(e) as Box<Error>)?;
+	}
+
+	// nodes shared between the keys are only included once.
+	let mut proof = proving_backend.extract_proof();
+	proof.sort();
+	proof.dedup();
+	Ok(proof)
+}
+
 /// Generate storage read proof on pre-created trie backend.
 pub fn prove_read_on_trie_backend<S, H>(
 	trie_backend: &TrieBackend<S, H>,
@@ -856,6 +930,30 @@ where
 	read_proof_check_on_proving_backend(&proving_backend, key)
 }
 
+/// Check storage read proof of multiple keys, generated by `prove_read_keys` call.
+///
+/// Returns the value of every given key, or an error if the proof doesn't contain all nodes
+/// required to read any of them.
+pub fn read_keys_proof_check<H, I>(
+	root: H::Out,
+	proof: Vec<Vec<u8>>,
+	keys: I,
+) -> Result<Vec<(Vec<u8>, Option<Vec<u8>>)>, Box<Error>>
+where
+	H: Hasher,
+	H::Out: Ord,
+	I: IntoIterator,
+	I::Item: AsRef<[u8]>,
+{
+	let proving_backend = create_proof_check_backend::<H>(root, proof)?;
+	keys.into_iter()
+		.map(|key| {
+			let value = read_proof_check_on_proving_backend(&proving_backend, key.as_ref())?;
+			Ok((key.as_ref().to_vec(), value))
+		})
+		.collect()
+}
+
 /// Check child storage read proof, generated by `prove_child_read` call.
 pub fn read_child_proof_check<H>(
 	root: H::Out,
@@ -872,6 +970,31 @@ where
 }
 
 
+/// Check child storage read proof of multiple keys, generated by `prove_child_read_keys` call.
+///
+/// Returns the value of every given key of the child trie, or an error if the proof doesn't
+/// contain all nodes required to read any of them.
+pub fn read_child_keys_proof_check<H, I>(
+	root: H::Out,
+	proof: Vec<Vec<u8>>,
+	storage_key: &[u8],
+	keys: I,
+) -> Result<Vec<(Vec<u8>, Option<Vec<u8>>)>, Box<Error>>
+where
+	H: Hasher,
+	H::Out: Ord,
+	I: IntoIterator,
+	I::Item: AsRef<[u8]>,
+{
+	let proving_backend = create_proof_check_backend::<H>(root, proof)?;
+	keys.into_iter()
+		.map(|key| {
+			let value = read_child_proof_check_on_proving_backend(&proving_backend, storage_key, key.as_ref())?;
+			Ok((key.as_ref().to_vec(), value))
+		})
+		.collect()
+}
+
 /// Check storage read proof on pre-created proving backend.
 pub fn read_proof_check_on_proving_backend<H>(
 	proving_backend: &TrieBackend<MemoryDB<H>, H>,
@@ -897,6 +1020,63 @@ where
 	proving_backend.child_storage(storage_key, key).map_err(|e| Box::new(e) as Box<Error>)
 }
 
+/// Generate storage range proof.
+///
+/// See `TrieBackendEssence::storage_range` for the meaning of the parameters.
+pub fn prove_range_read<B, H>(
+	backend: B,
+	start: &[u8],
+	child_start: Option<&[u8]>,
+	max_entries: usize,
+) -> Result<(StorageRange, Vec<Vec<u8>>), Box<Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+	H::Out: Ord
+{
+	let trie_backend = backend.try_into_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<Error>)?;
+	prove_range_read_on_trie_backend(&trie_backend, start, child_start, max_entries)
+}
+
+/// Generate storage range proof on pre-created trie backend.
+pub fn prove_range_read_on_trie_backend<S, H>(
+	trie_backend: &TrieBackend<S, H>,
+	start: &[u8],
+	child_start: Option<&[u8]>,
+	max_entries: usize,
+) -> Result<(StorageRange, Vec<Vec<u8>>), Box<Error>>
+where
+	S: trie_backend_essence::TrieBackendStorage<H>,
+	H: Hasher,
+	H::Out: Ord
+{
+	let proving_backend = proving_backend::ProvingBackend::<_, H>::new(trie_backend);
+	let result = proving_backend.storage_range(start, child_start, max_entries)
+		.map_err(|e| Box::new(e) as Box<Error>)?;
+	Ok((result, proving_backend.extract_proof()))
+}
+
+/// Check storage range proof, generated by `prove_range_read` call.
+///
+/// Returns the range that is read from the proof, which must be compared to the range
+/// returned by the prover.
+pub fn read_range_proof_check<H>(
+	root: H::Out,
+	proof: Vec<Vec<u8>>,
+	start: &[u8],
+	child_start: Option<&[u8]>,
+	max_entries: usize,
+) -> Result<StorageRange, Box<Error>>
+where
+	H: Hasher,
+	H::Out: Ord
+{
+	let proving_backend = create_proof_check_backend::<H>(root, proof)?;
+	proving_backend.essence().storage_range(start, child_start, max_entries)
+		.map_err(|e| Box::new(e) as Box<Error>)
+}
+
 /// Sets overlayed changes' changes trie configuration. Returns error if configuration
 /// differs from previous OR config decode has failed.
 pub(crate) fn set_changes_trie_config(overlay: &mut OverlayedChanges, config: Option<Vec<u8>>, final_check: bool) -> Result<(), Box<Error>> {
@@ -1213,6 +1393,129 @@ mod tests {
 		assert_eq!(local_result2, None);
 	}
 
+	#[test]
+	fn prove_read_keys_and_proof_check_works() {
+		// fetch read proof of several keys from 'remote' full node
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let keys = vec![b"value1".to_vec(), b"value2".to_vec(), b"missing".to_vec()];
+		let remote_proof = prove_read_keys(remote_backend, &keys).unwrap();
+		// check proof locally
+		let local_result = read_keys_proof_check::<Blake2Hasher, _>(
+			remote_root,
+			remote_proof.clone(),
+			&keys,
+		).unwrap();
+		assert_eq!(local_result, vec![
+			(b"value1".to_vec(), Some(vec![42])),
+			(b"value2".to_vec(), Some(vec![24])),
+			(b"missing".to_vec(), None),
+		]);
+		// keys that were not proven can't be read
+		assert!(read_keys_proof_check::<Blake2Hasher, _>(remote_root, remote_proof, &[vec![0xffu8]]).is_err());
+	}
+
+	#[test]
+	fn prove_child_read_keys_and_proof_check_works() {
+		// fetch read proof of several child keys from 'remote' full node
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let child_key = b":child_storage:default:sub1";
+		let keys = vec![b"value3".to_vec(), b"value4".to_vec(), b"value2".to_vec()];
+		let remote_proof = prove_child_read_keys(remote_backend, child_key, &keys).unwrap();
+		// check proof locally
+		let local_result = read_child_keys_proof_check::<Blake2Hasher, _>(
+			remote_root,
+			remote_proof.clone(),
+			child_key,
+			&keys,
+		).unwrap();
+		assert_eq!(local_result, vec![
+			(b"value3".to_vec(), Some(vec![142])),
+			(b"value4".to_vec(), Some(vec![124])),
+			(b"value2".to_vec(), None),
+		]);
+		// keys of the top-level trie were not proven
+		assert!(read_keys_proof_check::<Blake2Hasher, _>(remote_root, remote_proof, &[b"value2".to_vec()]).is_err());
+	}
+
+	#[test]
+	fn prove_range_read_and_proof_check_works() {
+		// fetch range proof from 'remote' full node
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let (remote_range, remote_proof) = prove_range_read(remote_backend, &[], None, 4).unwrap();
+		assert_eq!(
+			remote_range.entries.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>(),
+			vec![b":child_storage:default:sub1".to_vec(), b":code".to_vec()],
+		);
+		// child trie entries count towards the limit.
+		assert_eq!(remote_range.children, vec![(
+			b":child_storage:default:sub1".to_vec(),
+			vec![(b"value3".to_vec(), vec![142]), (b"value4".to_vec(), vec![124])],
+		)]);
+		assert!(!remote_range.partial_child);
+		assert!(!remote_range.complete);
+		// check proof locally
+		let local_range = read_range_proof_check::<Blake2Hasher>(
+			remote_root,
+			remote_proof.clone(),
+			&[],
+			None,
+			4,
+		).unwrap();
+		assert_eq!(local_range, remote_range);
+		// proof doesn't cover keys after the range
+		assert!(read_range_proof_check::<Blake2Hasher>(remote_root, remote_proof, &[], None, 10).is_err());
+
+		// the last range is complete
+		let remote_backend = trie_backend::tests::test_trie();
+		let (remote_range, remote_proof) = prove_range_read(remote_backend, &[250], None, 10).unwrap();
+		assert_eq!(remote_range.entries, (251u8..255).map(|i| (vec![i], vec![i])).collect::<Vec<_>>());
+		assert!(remote_range.complete);
+		let local_range = read_range_proof_check::<Blake2Hasher>(
+			remote_root,
+			remote_proof,
+			&[250],
+			None,
+			10,
+		).unwrap();
+		assert_eq!(local_range, remote_range);
+	}
+
+	#[test]
+	fn prove_range_read_pages_through_child_tries() {
+		let child = b":child_storage:default:sub1".to_vec();
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+
+		// the child trie doesn't fit into the range.
+		let (remote_range, remote_proof) = prove_range_read(remote_backend, &[], None, 2).unwrap();
+		assert_eq!(remote_range.entries.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>(), vec![child.clone()]);
+		assert_eq!(remote_range.children, vec![(child.clone(), vec![(b"value3".to_vec(), vec![142])])]);
+		assert!(remote_range.partial_child);
+		assert!(!remote_range.complete);
+		let local_range = read_range_proof_check::<Blake2Hasher>(remote_root, remote_proof, &[], None, 2).unwrap();
+		assert_eq!(local_range, remote_range);
+
+		// the next range continues with the rest of the child trie.
+		let remote_backend = trie_backend::tests::test_trie();
+		let (remote_range, remote_proof) = prove_range_read(remote_backend, &child, Some(b"value3"), 2).unwrap();
+		assert!(remote_range.entries.is_empty() || remote_range.entries[0].0 > child);
+		assert_eq!(remote_range.children, vec![(child.clone(), vec![(b"value4".to_vec(), vec![124])])]);
+		assert!(!remote_range.partial_child);
+		assert_eq!(remote_range.entries.len(), 1);
+		assert_eq!(remote_range.entries[0].0, b":code".to_vec());
+		let local_range = read_range_proof_check::<Blake2Hasher>(
+			remote_root,
+			remote_proof,
+			&child,
+			Some(b"value3"),
+			2,
+		).unwrap();
+		assert_eq!(local_range, remote_range);
+	}
+
 	#[test]
 	fn cannot_change_changes_trie_config() {
 		assert!(new(
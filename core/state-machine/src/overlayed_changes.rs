@@ -17,9 +17,11 @@
 //! The overlayed changes to state.
 
 #[cfg(test)] use std::iter::FromIterator;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use parity_codec::Decode;
 use crate::changes_trie::{NO_EXTRINSIC_INDEX, Configuration as ChangesTrieConfig};
+use crate::trace::{StorageAccessKind, StorageTrace, Tracer};
 use primitives::storage::well_known_keys::EXTRINSIC_INDEX;
 
 /// The overlayed changes to state to be queried on top of the backend.
@@ -35,6 +37,8 @@ pub struct OverlayedChanges {
 	/// Changes trie configuration. None by default, but could be installed by the
 	/// runtime if it supports change tries.
 	pub(crate) changes_trie_config: Option<ChangesTrieConfig>,
+	/// Recorder of the storage accesses made by the externalities. None unless tracing is enabled.
+	pub(crate) trace: Option<RefCell<Tracer>>,
 }
 
 /// The storage value, used inside OverlayedChanges.
@@ -270,6 +274,41 @@ impl OverlayedChanges {
 		});
 	}
 
+	/// Start recording the storage accesses made through the externalities to keys (or child
+	/// tries) starting with `key_prefix`, until the recorded keys and values reach `max_size`
+	/// bytes.
+	pub fn enable_tracing(&mut self, key_prefix: Option<Vec<u8>>, max_size: usize) {
+		self.trace = Some(RefCell::new(Tracer::new(key_prefix, max_size)));
+	}
+
+	/// Take the storage accesses recorded so far.
+	pub fn take_trace(&mut self) -> StorageTrace {
+		self.trace.as_ref().map(|trace| trace.borrow_mut().take()).unwrap_or_default()
+	}
+
+	/// Record a storage access if tracing is enabled. The kind of access is only built if the
+	/// access is recorded.
+	pub(crate) fn trace_access<F: FnOnce() -> StorageAccessKind>(
+		&self,
+		child_storage_key: Option<&[u8]>,
+		key: &[u8],
+		kind: F,
+	) {
+		if let Some(ref trace) = self.trace {
+			if !trace.borrow().is_traced(child_storage_key, key) {
+				return;
+			}
+
+			let kind = kind();
+			trace.borrow_mut().record(
+				self.storage(EXTRINSIC_INDEX).and_then(|idx| idx),
+				child_storage_key,
+				key,
+				kind,
+			);
+		}
+	}
+
 	/// Returns current extrinsic index to use in changes trie construction.
 	/// None is returned if it is not set or changes trie config is not set.
 	/// Persistent value (from the backend) can be ignored because runtime must
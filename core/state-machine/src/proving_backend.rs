@@ -27,7 +27,7 @@ use trie::{
 pub use trie::Recorder;
 use crate::trie_backend::TrieBackend;
 use crate::trie_backend_essence::{Ephemeral, TrieBackendEssence, TrieBackendStorage};
-use crate::{Error, ExecutionError, Backend};
+use crate::{Error, ExecutionError, Backend, StorageRange};
 
 /// Patricia trie-based backend essence which also tracks all touched storage trie values.
 /// These can be sent to remote node and used as a proof of execution.
@@ -112,6 +112,22 @@ impl<'a, S: 'a + TrieBackendStorage<H>, H: 'a + Hasher> ProvingBackend<'a, S, H>
 		}
 	}
 
+	/// Read a storage range, recording all touched trie nodes.
+	pub fn storage_range(
+		&self,
+		start: &[u8],
+		child_start: Option<&[u8]>,
+		max_entries: usize,
+	) -> Result<StorageRange, String> {
+		self.backend.essence().storage_range_with(
+			start,
+			child_start,
+			max_entries,
+			Some(&mut *self.proof_recorder.try_borrow_mut()
+				.expect("only fails when already borrowed; storage_range() is non-reentrant; qed")),
+		)
+	}
+
 	/// Consume the backend, extracting the gathered proof in lexicographical order
 	/// by value.
 	pub fn extract_proof(self) -> Vec<Vec<u8>> {
@@ -170,6 +186,24 @@ impl<'a, S, H> Backend<H> for ProvingBackend<'a, S, H>
 		self.backend.child_keys(child_storage_key, prefix)
 	}
 
+	fn paged_pairs(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error> {
+		self.backend.paged_pairs(prefix, start_key, count)
+	}
+
+	fn paged_keys(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<Vec<u8>>, Self::Error> {
+		self.backend.paged_keys(prefix, start_key, count)
+	}
+
 	fn storage_root<I>(&self, delta: I) -> (H::Out, Self::Transaction)
 		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
 	{
@@ -0,0 +1,142 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage access tracing.
+//!
+//! When tracing is enabled on the `OverlayedChanges`, the externalities record every storage
+//! access made by the runtime, along with the index of the extrinsic being applied.
+
+use std::mem;
+use parity_codec::Decode;
+
+/// Kind of a traced storage access.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageAccessKind {
+	/// Value read, `None` if the key doesn't exist.
+	Read(Option<Vec<u8>>),
+	/// Hash of the value read, `None` if the key doesn't exist.
+	ReadHash(Option<Vec<u8>>),
+	/// Existence of the key checked.
+	Exists(bool),
+	/// Value written, `None` if the key has been deleted.
+	Write(Option<Vec<u8>>),
+	/// Every key starting with the traced key has been deleted.
+	ClearPrefix,
+	/// The child trie has been deleted. The traced key is empty.
+	KillChild,
+}
+
+impl StorageAccessKind {
+	fn size(&self) -> usize {
+		match self {
+			StorageAccessKind::Read(value)
+				| StorageAccessKind::ReadHash(value)
+				| StorageAccessKind::Write(value) => value.as_ref().map_or(0, Vec::len),
+			StorageAccessKind::Exists(_)
+				| StorageAccessKind::ClearPrefix
+				| StorageAccessKind::KillChild => 0,
+		}
+	}
+}
+
+/// A traced storage access.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageAccess {
+	/// Index of the extrinsic being applied, `None` outside of extrinsics.
+	pub extrinsic_index: Option<u32>,
+	/// Storage key of the child trie, `None` for the top-level storage.
+	pub child_storage_key: Option<Vec<u8>>,
+	/// Accessed key.
+	pub key: Vec<u8>,
+	/// Kind of access.
+	pub kind: StorageAccessKind,
+}
+
+/// Storage accesses recorded by the externalities.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StorageTrace {
+	/// Recorded accesses, in execution order.
+	pub accesses: Vec<StorageAccess>,
+	/// Whether accesses have been dropped because the trace reached its maximum size.
+	pub truncated: bool,
+}
+
+/// Recorder of the storage accesses to keys (or child tries) starting with a prefix, until
+/// the keys and values recorded reach a maximum size.
+#[derive(Debug, Clone)]
+pub(crate) struct Tracer {
+	key_prefix: Option<Vec<u8>>,
+	max_size: usize,
+	size: usize,
+	trace: StorageTrace,
+	/// Encoded extrinsic index of the last recorded access and its decoded value.
+	extrinsic_index: (Option<Vec<u8>>, Option<u32>),
+}
+
+impl Tracer {
+	pub(crate) fn new(key_prefix: Option<Vec<u8>>, max_size: usize) -> Self {
+		Tracer {
+			key_prefix,
+			max_size,
+			size: 0,
+			trace: Default::default(),
+			extrinsic_index: (None, None),
+		}
+	}
+
+	/// Whether an access to the given key would be recorded.
+	pub(crate) fn is_traced(&self, child_storage_key: Option<&[u8]>, key: &[u8]) -> bool {
+		!self.trace.truncated && self.key_prefix.as_ref().map_or(true, |prefix| key.starts_with(prefix)
+			|| child_storage_key.map_or(false, |child_storage_key| child_storage_key.starts_with(prefix)))
+	}
+
+	/// Record an access made while the extrinsic index had the given encoding.
+	pub(crate) fn record(
+		&mut self,
+		encoded_extrinsic_index: Option<&[u8]>,
+		child_storage_key: Option<&[u8]>,
+		key: &[u8],
+		kind: StorageAccessKind,
+	) {
+		let size = child_storage_key.map_or(0, <[u8]>::len) + key.len() + kind.size();
+		if self.size.saturating_add(size) > self.max_size {
+			self.trace.truncated = true;
+			return;
+		}
+		self.size += size;
+
+		// The index only changes between extrinsics, so it is decoded once per extrinsic.
+		if self.extrinsic_index.0.as_ref().map(AsRef::as_ref) != encoded_extrinsic_index {
+			self.extrinsic_index = (
+				encoded_extrinsic_index.map(<[u8]>::to_vec),
+				encoded_extrinsic_index.and_then(|idx| Decode::decode(&mut &*idx)),
+			);
+		}
+
+		self.trace.accesses.push(StorageAccess {
+			extrinsic_index: self.extrinsic_index.1,
+			child_storage_key: child_storage_key.map(<[u8]>::to_vec),
+			key: key.to_vec(),
+			kind,
+		});
+	}
+
+	/// Take the accesses recorded so far.
+	pub(crate) fn take(&mut self) -> StorageTrace {
+		self.size = 0;
+		mem::replace(&mut self.trace, Default::default())
+	}
+}
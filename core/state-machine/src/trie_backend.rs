@@ -125,6 +125,24 @@ impl<S: TrieBackendStorage<H>, H: Hasher> Backend<H> for TrieBackend<S, H> where
 		collect_all().map_err(|e| debug!(target: "trie", "Error extracting trie keys: {}", e)).unwrap_or_default()
 	}
 
+	fn paged_pairs(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Self::Error> {
+		self.essence.paged_pairs(prefix, start_key, count)
+	}
+
+	fn paged_keys(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<Vec<u8>>, Self::Error> {
+		self.essence.paged_keys(prefix, start_key, count)
+	}
+
 	fn storage_root<I>(&self, delta: I) -> (H::Out, S::Overlay)
 		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
 	{
@@ -190,6 +208,7 @@ pub mod tests {
 	use primitives::{Blake2Hasher, H256};
 	use parity_codec::Encode;
 	use trie::{TrieMut, TrieDBMut, PrefixedMemoryDB};
+	use crate::backend::InMemory;
 	use super::*;
 
 	fn test_db() -> (PrefixedMemoryDB<Blake2Hasher>, H256) {
@@ -277,4 +296,48 @@ pub mod tests {
 		expected.insert(b"value2".to_vec());
 		assert_eq!(seen, expected);
 	}
+
+	#[test]
+	fn paged_pairs_works() {
+		let trie = test_trie();
+		let in_memory: InMemory<Blake2Hasher> = trie.pairs().into_iter()
+			.map(|(k, v)| (None, k, Some(v)))
+			.collect::<Vec<_>>()
+			.into();
+
+		assert_eq!(
+			trie.paged_pairs(b"value", None, 1).unwrap(),
+			vec![(b"value1".to_vec(), vec![42])],
+		);
+		assert_eq!(
+			trie.paged_pairs(b"value", Some(b"value1"), 10).unwrap(),
+			vec![(b"value2".to_vec(), vec![24])],
+		);
+		assert_eq!(
+			trie.paged_pairs(b"value", Some(b"value2"), 10).unwrap(),
+			vec![],
+		);
+		assert_eq!(
+			trie.paged_pairs(b"", Some(b":code"), 2).unwrap(),
+			vec![(b"key".to_vec(), b"value".to_vec()), (b"value1".to_vec(), vec![42])],
+		);
+		for (prefix, start) in vec![
+			(&b"value"[..], None),
+			(&b""[..], Some(&b":"[..])),
+			(&b""[..], Some(&[200u8][..])),
+		] {
+			assert_eq!(
+				trie.paged_pairs(prefix, start, 3).unwrap(),
+				in_memory.paged_pairs(prefix, start, 3).unwrap(),
+			);
+			assert_eq!(
+				trie.paged_keys(prefix, start, 3).unwrap(),
+				in_memory.paged_keys(prefix, start, 3).unwrap(),
+			);
+		}
+		assert_eq!(
+			trie.paged_keys(b"value", None, 10).unwrap(),
+			vec![b"value1".to_vec(), b"value2".to_vec()],
+		);
+	}
 }
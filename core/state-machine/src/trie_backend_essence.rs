@@ -21,8 +21,10 @@ use std::ops::Deref;
 use std::sync::Arc;
 use log::{debug, warn};
 use hash_db::{self, Hasher};
-use trie::{TrieDB, Trie, MemoryDB, PrefixedMemoryDB, DBValue, TrieError, default_child_trie_root, read_trie_value, read_child_trie_value, for_keys_in_child_trie};
+use trie::{TrieDB, Trie, MemoryDB, PrefixedMemoryDB, DBValue, TrieError, Recorder, default_child_trie_root, read_trie_value, read_trie_value_with, read_child_trie_value, for_keys_in_child_trie, read_trie_range, read_trie_keys_range, record_trie_range};
+use primitives::storage::well_known_keys::is_child_storage_key;
 use crate::backend::Consolidate;
+use crate::StorageRange;
 
 /// Patricia trie-based storage trait.
 pub trait Storage<H: Hasher>: Send + Sync {
@@ -140,6 +142,165 @@ impl<S: TrieBackendStorage<H>, H: Hasher> TrieBackendEssence<S, H> {
 			debug!(target: "trie", "Error while iterating by prefix: {}", e);
 		}
 	}
+
+	/// Get at most `count` key/value pairs with keys starting with `prefix`, in key order.
+	/// If `start_key` is given, only keys strictly greater than it are returned.
+	pub fn paged_pairs(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, String> {
+		let mut read_overlay = S::Overlay::default();
+		let eph = Ephemeral {
+			storage: &self.storage,
+			overlay: &mut read_overlay,
+		};
+		let map_e = |e| format!("Trie iteration error: {}", e);
+
+		// ranges only contain keys strictly greater than their start, so the prefix itself is
+		// looked up separately.
+		let (start, first) = match start_key {
+			Some(start) if start >= prefix => (start, None),
+			_ => (prefix, read_trie_value::<H, _>(&eph, &self.root, prefix).map_err(map_e)?),
+		};
+		let (pairs, _) = read_trie_range::<H, _>(&eph, &self.root, start, count).map_err(map_e)?;
+
+		Ok(first.map(|value| (prefix.to_vec(), value)).into_iter()
+			.chain(pairs.into_iter().take_while(|(key, _)| key.starts_with(prefix)))
+			.take(count)
+			.collect())
+	}
+
+	/// Get at most `count` keys starting with `prefix`, in key order, without collecting their
+	/// values. If `start_key` is given, only keys strictly greater than it are returned.
+	pub fn paged_keys(
+		&self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<Vec<u8>>, String> {
+		let mut read_overlay = S::Overlay::default();
+		let eph = Ephemeral {
+			storage: &self.storage,
+			overlay: &mut read_overlay,
+		};
+		let map_e = |e| format!("Trie iteration error: {}", e);
+
+		let (start, first) = match start_key {
+			Some(start) if start >= prefix => (start, None),
+			_ => (prefix, read_trie_value::<H, _>(&eph, &self.root, prefix).map_err(map_e)?),
+		};
+		let (keys, _) = read_trie_keys_range::<H, _>(&eph, &self.root, start, count).map_err(map_e)?;
+
+		Ok(first.map(|_| prefix.to_vec()).into_iter()
+			.chain(keys.into_iter().take_while(|key| key.starts_with(prefix)))
+			.take(count)
+			.collect())
+	}
+
+	/// Read a range of the storage with at most `max_entries` entries, top-level and child trie
+	/// entries alike.
+	///
+	/// The range starts with the top-level keys strictly greater than `start`, each child trie
+	/// being read right after its root. If `child_start` is given, `start` is the storage key of
+	/// a partially read child trie, and the range starts with its keys strictly greater than
+	/// `child_start`.
+	pub fn storage_range(
+		&self,
+		start: &[u8],
+		child_start: Option<&[u8]>,
+		max_entries: usize,
+	) -> Result<StorageRange, String> {
+		self.storage_range_with(start, child_start, max_entries, None)
+	}
+
+	/// Read a storage range as `storage_range` does, optionally recording all touched trie nodes.
+	pub(crate) fn storage_range_with(
+		&self,
+		start: &[u8],
+		child_start: Option<&[u8]>,
+		max_entries: usize,
+		mut recorder: Option<&mut Recorder<H::Out>>,
+	) -> Result<StorageRange, String> {
+		let mut read_overlay = S::Overlay::default();
+		let eph = Ephemeral {
+			storage: &self.storage,
+			overlay: &mut read_overlay,
+		};
+
+		let map_e = |e| format!("Trie range error: {}", e);
+		let child_root = |storage_key: &[u8], child_root: &[u8]| {
+			let mut root = H::Out::default();
+			if !is_child_storage_key(storage_key) || root.as_ref().len() != child_root.len() {
+				return Err(format!("Invalid child trie root at {:?}", storage_key));
+			}
+			root.as_mut().copy_from_slice(child_root);
+			Ok(root)
+		};
+
+		// root of the partially read child trie the range starts in.
+		let start_child_root = match child_start {
+			Some(_) => {
+				let value = match recorder {
+					Some(ref mut recorder) => read_trie_value_with::<H, _, _>(&eph, &self.root, start, &mut **recorder),
+					None => read_trie_value::<H, _>(&eph, &self.root, start),
+				}.map_err(map_e)?;
+				match value {
+					Some(value) => Some(child_root(start, &value)?),
+					None => return Err(format!("Missing child trie root at {:?}", start)),
+				}
+			},
+			None => None,
+		};
+
+		let mut read_range = |root: &H::Out, start: &[u8], max_entries: usize| match recorder {
+			Some(ref mut recorder) =>
+				record_trie_range::<H, _>(&eph, root, start, max_entries, &mut **recorder).map_err(map_e),
+			None => read_trie_range::<H, _>(&eph, root, start, max_entries).map_err(map_e),
+		};
+
+		let mut budget = max_entries;
+		let mut children = Vec::new();
+
+		if let (Some(root), Some(child_start)) = (start_child_root, child_start) {
+			let (child_entries, child_complete) = read_range(&root, child_start, budget)?;
+			budget -= child_entries.len();
+			children.push((start.to_vec(), child_entries));
+			if !child_complete {
+				return Ok(StorageRange { entries: Vec::new(), children, partial_child: true, complete: false });
+			}
+		}
+
+		let (top, top_complete) = read_range(&self.root, start, budget)?;
+		let top_len = top.len();
+		let mut entries = Vec::with_capacity(top_len);
+		for (storage_key, value) in top {
+			budget -= 1;
+			let child = if is_child_storage_key(&storage_key) {
+				let root = child_root(&storage_key, &value)?;
+				let (child_entries, child_complete) = read_range(&root, &[], budget)?;
+				budget -= child_entries.len();
+				Some((child_entries, child_complete))
+			} else {
+				None
+			};
+
+			entries.push((storage_key.clone(), value));
+			if let Some((child_entries, child_complete)) = child {
+				children.push((storage_key, child_entries));
+				if !child_complete {
+					return Ok(StorageRange { entries, children, partial_child: true, complete: false });
+				}
+			}
+			if budget == 0 {
+				break;
+			}
+		}
+
+		let complete = top_complete && entries.len() == top_len;
+		Ok(StorageRange { entries, children, partial_child: false, complete })
+	}
 }
 
 pub(crate) struct Ephemeral<'a, S: 'a + TrieBackendStorage<H>, H: 'a + Hasher> {
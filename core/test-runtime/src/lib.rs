@@ -100,6 +100,8 @@ pub enum Extrinsic {
 	AuthoritiesChange(Vec<AuthorityId>),
 	Transfer(Transfer, AccountSignature),
 	IncludeData(Vec<u8>),
+	ReportEquivocation(EquivocationReport),
+	ReportBabeEquivocation(consensus_babe::EquivocationReport),
 }
 
 #[cfg(feature = "std")]
@@ -123,16 +125,20 @@ impl BlindCheckable for Extrinsic {
 				}
 			},
 			Extrinsic::IncludeData(_) => Err(runtime_primitives::BAD_SIGNATURE),
+			// the reports are checked when they are executed.
+			Extrinsic::ReportEquivocation(report) => Ok(Extrinsic::ReportEquivocation(report)),
+			Extrinsic::ReportBabeEquivocation(report) => Ok(Extrinsic::ReportBabeEquivocation(report)),
 		}
 	}
 }
 
 impl ExtrinsicT for Extrinsic {
 	fn is_signed(&self) -> Option<bool> {
-		if let Extrinsic::IncludeData(_) = *self {
-			Some(false)
-		} else {
-			Some(true)
+		match *self {
+			Extrinsic::IncludeData(_)
+				| Extrinsic::ReportEquivocation(_)
+				| Extrinsic::ReportBabeEquivocation(_) => Some(false),
+			_ => Some(true),
 		}
 	}
 }
@@ -154,6 +160,8 @@ pub type AuthorityId = <AuthoritySignature as Verify>::Signer;
 pub type AccountSignature = sr25519::Signature;
 /// An identifier for an account on this system.
 pub type AccountId = <AccountSignature as Verify>::Signer;
+/// A report of an authority that sealed two different blocks for the same slot.
+pub type EquivocationReport = consensus_aura::EquivocationReport<AuthorityId, AuthoritySignature>;
 /// A simple hash type for all our hashing.
 pub type Hash = H256;
 /// The block number type used in this runtime.
@@ -373,6 +381,7 @@ cfg_if! {
 							provides: vec![data],
 							longevity: 1,
 							propagate: false,
+							sender: None,
 						};
 					}
 
@@ -463,12 +472,35 @@ cfg_if! {
 				fn slot_duration() -> u64 { 1 }
 			}
 
+			impl consensus_aura::AuraEquivocationApi<Block> for Runtime {
+				fn construct_equivocation_report_extrinsic(report: Vec<u8>) -> Option<Extrinsic> {
+					Decode::decode(&mut &report[..]).map(Extrinsic::ReportEquivocation)
+				}
+			}
+
+			impl consensus_babe::BabeEquivocationApi<Block> for Runtime {
+				fn construct_equivocation_report_extrinsic(report: Vec<u8>) -> Option<Extrinsic> {
+					Decode::decode(&mut &report[..]).map(Extrinsic::ReportBabeEquivocation)
+				}
+			}
+
 			impl consensus_babe::BabeApi<Block> for Runtime {
 				fn startup_data() -> consensus_babe::BabeConfiguration {
 					consensus_babe::BabeConfiguration {
 						slot_duration: 1,
 						expected_block_time: 1,
 						threshold: std::u64::MAX,
+						epoch_length: 6,
+						genesis_authorities: system::authorities(),
+						randomness: [0; 32],
+						secondary_slots: true,
+					}
+				}
+
+				fn next_epoch() -> consensus_babe::NextEpochDescriptor {
+					consensus_babe::NextEpochDescriptor {
+						authorities: system::authorities(),
+						randomness: [0; 32],
 					}
 				}
 			}
@@ -521,6 +553,7 @@ cfg_if! {
 							provides: vec![data],
 							longevity: 1,
 							propagate: false,
+							sender: None,
 						};
 					}
 
@@ -615,12 +648,35 @@ cfg_if! {
 				fn slot_duration() -> u64 { 1 }
 			}
 
+			impl consensus_aura::AuraEquivocationApi<Block> for Runtime {
+				fn construct_equivocation_report_extrinsic(report: Vec<u8>) -> Option<Extrinsic> {
+					Decode::decode(&mut &report[..]).map(Extrinsic::ReportEquivocation)
+				}
+			}
+
+			impl consensus_babe::BabeEquivocationApi<Block> for Runtime {
+				fn construct_equivocation_report_extrinsic(report: Vec<u8>) -> Option<Extrinsic> {
+					Decode::decode(&mut &report[..]).map(Extrinsic::ReportBabeEquivocation)
+				}
+			}
+
 			impl consensus_babe::BabeApi<Block> for Runtime {
 				fn startup_data() -> consensus_babe::BabeConfiguration {
 					consensus_babe::BabeConfiguration {
 						slot_duration: 1,
 						expected_block_time: 1,
 						threshold: core::u64::MAX,
+						epoch_length: 6,
+						genesis_authorities: system::authorities(),
+						randomness: [0; 32],
+						secondary_slots: true,
+					}
+				}
+
+				fn next_epoch() -> consensus_babe::NextEpochDescriptor {
+					consensus_babe::NextEpochDescriptor {
+						authorities: system::authorities(),
+						randomness: [0; 32],
 					}
 				}
 			}
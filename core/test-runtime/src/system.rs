@@ -25,7 +25,9 @@ use runtime_primitives::traits::{Hash as HashT, BlakeTwo256, Digest as DigestT,
 use runtime_primitives::generic;
 use runtime_primitives::{ApplyError, ApplyOutcome, ApplyResult, transaction_validity::TransactionValidity};
 use parity_codec::{KeyedVec, Encode};
-use super::{AccountId, BlockNumber, Extrinsic, Transfer, H256 as Hash, Block, Header, Digest};
+use super::{
+	AccountId, BlockNumber, Extrinsic, Transfer, H256 as Hash, Block, Header, Digest,
+};
 use primitives::{Blake2Hasher, storage::well_known_keys};
 use primitives::sr25519::Public as AuthorityId;
 
@@ -39,6 +41,7 @@ storage_items! {
 	ParentHash: b"sys:pha" => required Hash;
 	NewAuthorities: b"sys:new_auth" => Vec<AuthorityId>;
 	StorageDigest: b"sys:digest" => Digest;
+	ReportedOffences: b"sys:offences" => map [ Vec<u8> => bool ];
 }
 
 pub fn balance_of_key(who: AccountId) -> Vec<u8> {
@@ -154,6 +157,20 @@ impl executive::ExecuteBlock<Block> for BlockExecutor {
 /// Execute a transaction outside of the block execution function.
 /// This doesn't attempt to validate anything regarding the block.
 pub fn validate_transaction(utx: Extrinsic) -> TransactionValidity {
+	if let Some((offender, slot, proven)) = equivocation_report(&utx) {
+		return match check_equivocation_report(offender, slot, proven) {
+			Ok(offence) => TransactionValidity::Valid {
+				priority: u64::max_value(),
+				requires: Vec::new(),
+				provides: vec![offence],
+				longevity: u64::max_value(),
+				propagate: true,
+				sender: None,
+			},
+			Err(e) => TransactionValidity::Invalid(e as i8),
+		};
+	}
+
 	if check_signature(&utx).is_err() {
 		return TransactionValidity::Invalid(ApplyError::BadSignature as i8);
 	}
@@ -191,6 +208,7 @@ pub fn validate_transaction(utx: Extrinsic) -> TransactionValidity {
 		provides,
 		longevity: 64,
 		propagate: true,
+		sender: Some(tx.from.encode()),
 	}
 }
 
@@ -249,7 +267,45 @@ fn execute_transaction_backend(utx: &Extrinsic) -> ApplyResult {
 		Extrinsic::Transfer(ref transfer, _) => execute_transfer_backend(transfer),
 		Extrinsic::AuthoritiesChange(ref new_auth) => execute_new_authorities_backend(new_auth),
 		Extrinsic::IncludeData(_) => Ok(ApplyOutcome::Success),
+		Extrinsic::ReportEquivocation(ref report) =>
+			execute_equivocation_report_backend(&report.offender, report.slot, report.check::<BlakeTwo256>()),
+		Extrinsic::ReportBabeEquivocation(ref report) =>
+			execute_equivocation_report_backend(&report.offender, report.slot, report.check::<BlakeTwo256>()),
+	}
+}
+
+/// The offender, the slot and whether the proof is valid, if the extrinsic reports an equivocation.
+fn equivocation_report(utx: &Extrinsic) -> Option<(&AuthorityId, u64, bool)> {
+	match utx {
+		Extrinsic::ReportEquivocation(report) =>
+			Some((&report.offender, report.slot, report.check::<BlakeTwo256>())),
+		Extrinsic::ReportBabeEquivocation(report) =>
+			Some((&report.offender, report.slot, report.check::<BlakeTwo256>())),
+		_ => None,
+	}
+}
+
+/// Check the report against the current authorities, returning the identifier of the offence.
+fn check_equivocation_report(offender: &AuthorityId, slot: u64, proven: bool) -> Result<Vec<u8>, ApplyError> {
+	if !authorities().contains(offender) || !proven {
+		return Err(ApplyError::BadSignature);
 	}
+
+	let offence = (offender, slot).encode();
+	if ReportedOffences::exists(&offence) {
+		return Err(ApplyError::Stale);
+	}
+
+	Ok(offence)
+}
+
+fn execute_equivocation_report_backend(offender: &AuthorityId, slot: u64, proven: bool) -> ApplyResult {
+	let offence = check_equivocation_report(offender, slot, proven)?;
+	ReportedOffences::insert(offence, true);
+
+	// slash the whole balance of the offender.
+	storage::hashed::put(&blake2_256, &balance_of_key(offender.clone()), &0u64);
+	Ok(ApplyOutcome::Success)
 }
 
 fn execute_transfer_backend(tx: &Transfer) -> ApplyResult {
@@ -19,7 +19,7 @@
 //! For a more full-featured pool, have a look at the `pool` module.
 
 use std::{
-	collections::HashSet,
+	collections::{HashMap, HashSet},
 	fmt,
 	hash,
 	sync::Arc,
@@ -102,6 +102,8 @@ pub struct Transaction<Hash, Extrinsic> {
 	pub provides: Vec<Tag>,
 	/// Should that transaction be propagated.
 	pub propagate: bool,
+	/// Encoded account that signed the transaction (if known).
+	pub sender: Option<Vec<u8>>,
 }
 
 impl<Hash, Extrinsic> Transaction<Hash, Extrinsic> {
@@ -167,6 +169,12 @@ pub struct BasePool<Hash: hash::Hash + Eq, Ex> {
 	/// transactions to future in case they were just stuck in verification.
 	recently_pruned: [HashSet<Tag>; RECENTLY_PRUNED_TAGS],
 	recently_pruned_index: usize,
+	/// Limits of transactions coming from a single sender.
+	sender_limit: SenderLimit,
+	/// Number of ready transactions per sender.
+	ready_senders: SenderCounts,
+	/// Number of future transactions per sender.
+	future_senders: SenderCounts,
 }
 
 impl<Hash: hash::Hash + Eq, Ex> Default for BasePool<Hash, Ex> {
@@ -176,6 +184,23 @@ impl<Hash: hash::Hash + Eq, Ex> Default for BasePool<Hash, Ex> {
 			ready: Default::default(),
 			recently_pruned: Default::default(),
 			recently_pruned_index: 0,
+			sender_limit: Default::default(),
+			ready_senders: Default::default(),
+			future_senders: Default::default(),
+		}
+	}
+}
+
+impl<Hash: hash::Hash + Eq, Ex> BasePool<Hash, Ex> {
+	/// Create new pool with given per-sender limits.
+	///
+	/// `min_priority_bump` is the minimal increase of priority (in percent) required
+	/// for a transaction to replace transactions providing the same tags.
+	pub fn new(sender_limit: SenderLimit, min_priority_bump: u64) -> Self {
+		BasePool {
+			ready: ReadyTransactions::with_min_priority_bump(min_priority_bump),
+			sender_limit,
+			..Default::default()
 		}
 	}
 }
@@ -206,7 +231,15 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 
 		// If all tags are not satisfied import to future.
 		if !tx.is_ready() {
+			if let Some(ref sender) = tx.transaction.sender {
+				if self.future_senders.get(sender) >= self.sender_limit.future {
+					debug!(target: "txpool", "[{:?}] Sender limit reached ({})", tx.transaction.hash, self.sender_limit.future);
+					return Err(error::Error::TooManyFromSender)
+				}
+			}
+
 			let hash = tx.transaction.hash.clone();
+			self.future_senders.add(&tx.transaction);
 			self.future.import(tx);
 			return Ok(Imported::Future { hash });
 		}
@@ -214,6 +247,39 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 		self.import_to_ready(tx)
 	}
 
+	/// Makes sure that the sender doesn't exceed the number of ready transactions
+	/// after the transaction is imported to the ready queue.
+	///
+	/// Ready transactions that are going to be replaced by the new one are not counted.
+	fn check_ready_sender_limit(&self, tx: &Transaction<Hash, Ex>) -> error::Result<()> {
+		let sender = match tx.sender {
+			Some(ref sender) => sender,
+			None => return Ok(()),
+		};
+
+		let count = self.ready_senders.get(sender);
+		if count < self.sender_limit.ready {
+			return Ok(())
+		}
+
+		let replaced = tx.provides
+			.iter()
+			.filter_map(|tag| self.ready.provided_tags().get(tag))
+			.cloned()
+			.collect::<HashSet<_>>();
+		let replaced = self.ready.by_hash(&replaced.into_iter().collect::<Vec<_>>())
+			.into_iter()
+			.filter(|tx| tx.as_ref().and_then(|tx| tx.sender.as_ref()) == Some(sender))
+			.count();
+
+		if count - replaced >= self.sender_limit.ready {
+			debug!(target: "txpool", "[{:?}] Sender limit reached ({})", tx.hash, self.sender_limit.ready);
+			return Err(error::Error::TooManyFromSender)
+		}
+
+		Ok(())
+	}
+
 	/// Imports transaction to ready queue.
 	///
 	/// NOTE the transaction has to have all requirements satisfied.
@@ -234,15 +300,26 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 			};
 
 			// find transactions in Future that it unlocks
-			to_import.append(&mut self.future.satisfy_tags(&tx.transaction.provides));
+			let mut unlocked = self.future.satisfy_tags(&tx.transaction.provides);
+			for tx in &unlocked {
+				self.future_senders.remove(&tx.transaction);
+			}
+			to_import.append(&mut unlocked);
 
-			// import this transaction
+			// import this transaction, promoted transactions are subject to the sender limit as well.
 			let current_hash = tx.transaction.hash.clone();
-			match self.ready.import(tx) {
+			let sender = tx.transaction.sender.clone();
+			let imported = self.check_ready_sender_limit(&tx.transaction)
+				.and_then(|_| self.ready.import(tx));
+			match imported {
 				Ok(mut replaced) => {
 					if !first {
 						promoted.push(current_hash);
 					}
+					self.ready_senders.add_sender(sender);
+					for tx in &replaced {
+						self.ready_senders.remove(tx);
+					}
 					// The transactions were removed from the ready pool. We might attempt to re-import them.
 					removed.append(&mut replaced);
 				},
@@ -265,7 +342,9 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 		if removed.iter().any(|tx| tx.hash == hash) {
 			// We still need to remove all transactions that we promoted
 			// since they depend on each other and will never get to the best iterator.
-			self.ready.remove_invalid(&promoted);
+			for tx in self.ready.remove_invalid(&promoted) {
+				self.ready_senders.remove(&tx);
+			}
 
 			debug!(target: "txpool", "[{:?}] Cycle detected, bailing.", hash);
 			return Err(error::Error::CycleDetected)
@@ -366,7 +445,14 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 	/// and you don't want them to be stored in the pool use `prune_tags` method.
 	pub fn remove_invalid(&mut self, hashes: &[Hash]) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = self.ready.remove_invalid(hashes);
-		removed.extend(self.future.remove(hashes));
+		for tx in &removed {
+			self.ready_senders.remove(tx);
+		}
+		let future = self.future.remove(hashes);
+		for tx in &future {
+			self.future_senders.remove(tx);
+		}
+		removed.extend(future);
 		removed
 	}
 
@@ -385,9 +471,17 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 
 		for tag in tags {
 			// make sure to promote any future transactions that could be unlocked
-			to_import.append(&mut self.future.satisfy_tags(::std::iter::once(&tag)));
+			let mut unlocked = self.future.satisfy_tags(::std::iter::once(&tag));
+			for tx in &unlocked {
+				self.future_senders.remove(&tx.transaction);
+			}
+			to_import.append(&mut unlocked);
 			// and actually prune transactions in ready queue
-			pruned.append(&mut self.ready.prune_tags(tag.clone()));
+			let mut removed = self.ready.prune_tags(tag.clone());
+			for tx in &removed {
+				self.ready_senders.remove(tx);
+			}
+			pruned.append(&mut removed);
 			// store the tags for next submission
 			recently_pruned.insert(tag);
 		}
@@ -459,9 +553,70 @@ impl Limit {
 	}
 }
 
+/// Limits of transactions coming from a single sender.
+#[derive(Debug, Clone)]
+pub struct SenderLimit {
+	/// Maximal number of ready transactions from a single sender.
+	pub ready: usize,
+	/// Maximal number of future transactions from a single sender.
+	pub future: usize,
+}
+
+impl Default for SenderLimit {
+	fn default() -> Self {
+		SenderLimit {
+			ready: usize::max_value(),
+			future: usize::max_value(),
+		}
+	}
+}
+
+/// Number of transactions in a queue per sender.
+#[derive(Debug, Default)]
+struct SenderCounts(HashMap<Vec<u8>, usize>);
+
+impl SenderCounts {
+	/// Returns number of transactions from given sender.
+	fn get(&self, sender: &[u8]) -> usize {
+		self.0.get(sender).cloned().unwrap_or(0)
+	}
+
+	/// Notes a transaction entering the queue.
+	fn add<Hash, Ex>(&mut self, tx: &Transaction<Hash, Ex>) {
+		self.add_sender(tx.sender.clone())
+	}
+
+	/// Notes a transaction from given sender entering the queue.
+	fn add_sender(&mut self, sender: Option<Vec<u8>>) {
+		if let Some(sender) = sender {
+			*self.0.entry(sender).or_insert(0) += 1;
+		}
+	}
+
+	/// Notes a transaction leaving the queue.
+	fn remove<Hash, Ex>(&mut self, tx: &Transaction<Hash, Ex>) {
+		let sender = match tx.sender {
+			Some(ref sender) => sender,
+			None => return,
+		};
+
+		let is_last = match self.0.get_mut(sender) {
+			Some(count) => {
+				*count -= 1;
+				*count == 0
+			},
+			None => false,
+		};
+		if is_last {
+			self.0.remove(sender);
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use assert_matches::assert_matches;
 
 	type Hash = u64;
 
@@ -484,6 +639,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![1]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 
 		// then
@@ -506,6 +662,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![1]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![1u8],
@@ -516,6 +673,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![1]],
 			propagate: true,
+			sender: None,
 		}).unwrap_err();
 
 		// then
@@ -539,6 +697,7 @@ mod tests {
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
@@ -551,6 +710,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![0]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 
 		// then
@@ -573,6 +733,7 @@ mod tests {
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -583,6 +744,7 @@ mod tests {
 			requires: vec![vec![2]],
 			provides: vec![],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![2u8],
@@ -593,6 +755,7 @@ mod tests {
 			requires: vec![vec![1]],
 			provides: vec![vec![3], vec![2]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![4u8],
@@ -603,6 +766,7 @@ mod tests {
 			requires: vec![vec![3], vec![4]],
 			provides: vec![],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
@@ -616,6 +780,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![0], vec![4]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 
 		// then
@@ -648,6 +813,7 @@ mod tests {
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -658,6 +824,7 @@ mod tests {
 			requires: vec![vec![1]],
 			provides: vec![vec![2]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
@@ -672,6 +839,7 @@ mod tests {
 			requires: vec![vec![2]],
 			provides: vec![vec![0]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 
 		// then
@@ -692,6 +860,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![0]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		let mut it = pool.ready().into_iter().map(|tx| tx.data[0]);
 		assert_eq!(it.next(), Some(4));
@@ -720,6 +889,7 @@ mod tests {
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -730,6 +900,7 @@ mod tests {
 			requires: vec![vec![1]],
 			provides: vec![vec![2]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
@@ -744,6 +915,7 @@ mod tests {
 			requires: vec![vec![2]],
 			provides: vec![vec![0]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 
 		// then
@@ -764,6 +936,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![0]],
 			propagate: true,
+			sender: None,
 		}).unwrap_err();
 		let mut it = pool.ready().into_iter().map(|tx| tx.data[0]);
 		assert_eq!(it.next(), None);
@@ -788,6 +961,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![0], vec![4]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![1u8],
@@ -798,6 +972,7 @@ mod tests {
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -808,6 +983,7 @@ mod tests {
 			requires: vec![vec![2]],
 			provides: vec![],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![2u8],
@@ -818,6 +994,7 @@ mod tests {
 			requires: vec![vec![1]],
 			provides: vec![vec![3], vec![2]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![4u8],
@@ -828,6 +1005,7 @@ mod tests {
 			requires: vec![vec![3], vec![4]],
 			provides: vec![],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		// future
 		pool.import(Transaction {
@@ -839,6 +1017,7 @@ mod tests {
 			requires: vec![vec![11]],
 			provides: vec![],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 5);
 		assert_eq!(pool.future.len(), 1);
@@ -865,6 +1044,7 @@ mod tests {
 			requires: vec![vec![0]],
 			provides: vec![vec![100]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		// ready
 		pool.import(Transaction {
@@ -876,6 +1056,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![1]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![2u8],
@@ -886,6 +1067,7 @@ mod tests {
 			requires: vec![vec![2]],
 			provides: vec![vec![3]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -896,6 +1078,7 @@ mod tests {
 			requires: vec![vec![1]],
 			provides: vec![vec![2]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![4u8],
@@ -906,6 +1089,7 @@ mod tests {
 			requires: vec![vec![3], vec![2]],
 			provides: vec![vec![4]],
 			propagate: true,
+			sender: None,
 		}).unwrap();
 
 		assert_eq!(pool.ready().count(), 4);
@@ -929,6 +1113,77 @@ mod tests {
 		assert_eq!(pool.ready().count(), 3);
 	}
 
+	fn sender_tx(hash: u64, requires: Vec<Tag>, provides: Vec<Tag>, sender: u8) -> Transaction<Hash, Vec<u8>> {
+		Transaction {
+			data: vec![hash as u8],
+			bytes: 1,
+			hash,
+			priority: 5u64,
+			valid_till: 64u64,
+			requires,
+			provides,
+			propagate: true,
+			sender: Some(vec![sender]),
+		}
+	}
+
+	#[test]
+	fn should_enforce_per_sender_limits() {
+		// given
+		let mut pool = BasePool::new(SenderLimit { ready: 2, future: 1 }, 0);
+		let tx = sender_tx;
+
+		// when
+		pool.import(tx(1, vec![], vec![vec![1]], 1)).unwrap();
+		pool.import(tx(2, vec![vec![1]], vec![vec![2]], 1)).unwrap();
+		pool.import(tx(3, vec![vec![5]], vec![vec![6]], 1)).unwrap();
+
+		// then
+		// ready limit reached
+		assert_matches!(
+			pool.import(tx(4, vec![vec![2]], vec![vec![3]], 1)),
+			Err(error::Error::TooManyFromSender)
+		);
+		// future limit reached
+		assert_matches!(
+			pool.import(tx(5, vec![vec![7]], vec![vec![8]], 1)),
+			Err(error::Error::TooManyFromSender)
+		);
+		// replacement is still possible
+		let mut replacement = tx(6, vec![vec![1]], vec![vec![2]], 1);
+		replacement.priority = 10;
+		pool.import(replacement).unwrap();
+		// other senders are not affected
+		pool.import(tx(7, vec![], vec![vec![10]], 2)).unwrap();
+		assert_eq!(pool.ready().count(), 3);
+		assert_eq!(pool.future.len(), 1);
+	}
+
+	#[test]
+	fn should_enforce_per_sender_limits_on_promotion() {
+		// given
+		let mut pool = BasePool::new(SenderLimit { ready: 1, future: 1 }, 0);
+		let tx = sender_tx;
+		pool.import(tx(1, vec![], vec![vec![1]], 1)).unwrap();
+		pool.import(tx(2, vec![vec![2]], vec![vec![3]], 1)).unwrap();
+		assert_eq!(pool.future.len(), 1);
+
+		// when
+		let imported = pool.import(tx(3, vec![], vec![vec![2]], 2)).unwrap();
+
+		// then
+		assert_matches!(imported, Imported::Ready { ref promoted, ref failed, .. } if promoted.is_empty() && failed == &vec![2]);
+		assert_eq!(pool.ready().count(), 2);
+		assert_eq!(pool.future.len(), 0);
+
+		// pruning frees the limit
+		pool.prune_tags(vec![vec![1]]);
+		pool.import(tx(4, vec![vec![2]], vec![vec![3]], 1)).unwrap();
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3, 4]);
+		assert_eq!(pool.ready_senders.get(&[1]), 1);
+		assert_eq!(pool.future_senders.get(&[1]), 0);
+	}
+
 	#[test]
 	fn transaction_debug() {
 		assert_eq!(
@@ -941,6 +1196,7 @@ mod tests {
 				requires: vec![vec![3], vec![2]],
 				provides: vec![vec![4]],
 				propagate: true,
+				sender: None,
 			}),
 			"Transaction { \
 hash: 4, priority: 1000, valid_till: 64, bytes: 1, propagate: true, \
@@ -959,6 +1215,7 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 				requires: vec![vec![3], vec![2]],
 				provides: vec![vec![4]],
 				propagate: true,
+				sender: None,
 		}.is_propagateable(), true);
 
 		assert_eq!(Transaction {
@@ -970,6 +1227,7 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 				requires: vec![vec![3], vec![2]],
 				provides: vec![vec![4]],
 				propagate: false,
+				sender: None,
 		}.is_propagateable(), false);
 	}
 }
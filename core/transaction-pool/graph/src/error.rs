@@ -44,6 +44,9 @@ pub enum Error {
 		/// Transaction entering the pool.
 		new: Priority
 	},
+	/// The sender already has too many transactions in the pool.
+	#[display(fmt="Too many transactions from the sender")]
+	TooManyFromSender,
 	/// Deps cycle etected and we couldn't import transaction.
 	#[display(fmt="Cycle Detected")]
 	CycleDetected,
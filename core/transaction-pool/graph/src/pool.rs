@@ -37,7 +37,7 @@ use sr_primitives::{
 	transaction_validity::{TransactionValidity, TransactionTag as Tag},
 };
 
-pub use crate::base_pool::Limit;
+pub use crate::base_pool::{Limit, SenderLimit};
 
 /// Modification notification event stream type;
 pub type EventStream = mpsc::UnboundedReceiver<()>;
@@ -82,6 +82,10 @@ pub struct Options {
 	pub ready: Limit,
 	/// Future queue limits.
 	pub future: Limit,
+	/// Limits of transactions coming from a single sender.
+	pub per_sender: SenderLimit,
+	/// Minimal priority increase (in percent) required to replace a transaction.
+	pub min_priority_bump: u64,
 }
 
 impl Default for Options {
@@ -95,6 +99,8 @@ impl Default for Options {
 				count: 128,
 				total_bytes: 1 * 1024 * 1024,
 			},
+			per_sender: Default::default(),
+			min_priority_bump: 0,
 		}
 	}
 }
@@ -109,6 +115,7 @@ pub struct Pool<B: ChainApi> {
 		ExtrinsicFor<B>,
 	>>,
 	import_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<()>>>,
+	extrinsic_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<ExtrinsicFor<B>>>>,
 	rotator: PoolRotator<ExHash<B>>,
 }
 
@@ -116,6 +123,22 @@ impl<B: ChainApi> Pool<B> {
 	/// Imports a bunch of unverified extrinsics to the pool
 	pub fn submit_at<T>(&self, at: &BlockId<B::Block>, xts: T) -> Result<Vec<Result<ExHash<B>, B::Error>>, B::Error> where
 		T: IntoIterator<Item=ExtrinsicFor<B>>
+	{
+		self.import_at(at, xts, false)
+	}
+
+	/// Re-imports extrinsics from blocks that were retracted from the best chain.
+	///
+	/// Unlike `submit_at` this ignores temporary bans, since the extrinsics were most
+	/// likely banned exactly because they were included in the retracted blocks.
+	pub fn resubmit_at<T>(&self, at: &BlockId<B::Block>, xts: T) -> Result<Vec<Result<ExHash<B>, B::Error>>, B::Error> where
+		T: IntoIterator<Item=ExtrinsicFor<B>>
+	{
+		self.import_at(at, xts, true)
+	}
+
+	fn import_at<T>(&self, at: &BlockId<B::Block>, xts: T, ignore_bans: bool) -> Result<Vec<Result<ExHash<B>, B::Error>>, B::Error> where
+		T: IntoIterator<Item=ExtrinsicFor<B>>
 	{
 		let block_number = self.api.block_id_to_number(at)?
 			.ok_or_else(|| error::Error::InvalidBlockId(format!("{:?}", at)).into())?;
@@ -124,12 +147,12 @@ impl<B: ChainApi> Pool<B> {
 			.into_iter()
 			.map(|xt| -> Result<_, B::Error> {
 				let (hash, bytes) = self.api.hash_and_length(&xt);
-				if self.rotator.is_banned(&hash) {
+				if !ignore_bans && self.rotator.is_banned(&hash) {
 					return Err(error::Error::TemporarilyBanned.into())
 				}
 
 				match self.api.validate_transaction(at, xt.clone())? {
-					TransactionValidity::Valid { priority, requires, provides, longevity, propagate } => {
+					TransactionValidity::Valid { priority, requires, provides, longevity, propagate, sender } => {
 						Ok(base::Transaction {
 							data: xt,
 							bytes,
@@ -138,6 +161,7 @@ impl<B: ChainApi> Pool<B> {
 							requires,
 							provides,
 							propagate,
+							sender,
 							valid_till: block_number
 								.saturated_into::<u64>()
 								.saturating_add(longevity),
@@ -153,11 +177,14 @@ impl<B: ChainApi> Pool<B> {
 				}
 			})
 			.map(|tx| {
-				let imported = self.pool.write().import(tx?)?;
+				let tx = tx?;
+				let xt = tx.data.clone();
+				let imported = self.pool.write().import(tx)?;
 
 				if let base::Imported::Ready { .. } = imported {
 					self.import_notification_sinks.lock().retain(|sink| sink.unbounded_send(()).is_ok());
 				}
+				self.extrinsic_notification_sinks.lock().retain(|sink| sink.unbounded_send(xt.clone()).is_ok());
 
 				let mut listener = self.listener.write();
 				fire_events(&mut *listener, &imported);
@@ -363,14 +390,37 @@ impl<B: ChainApi> Pool<B> {
 		Ok(())
 	}
 
+	/// Revalidates all ready transactions at given block.
+	///
+	/// Transactions that are no longer valid (for instance after a re-org)
+	/// are removed from the pool together with their dependencies.
+	pub fn revalidate_ready(&self, at: &BlockId<B::Block>) -> Result<(), B::Error> {
+		let invalid = self.ready()
+			.filter(|tx| match self.api.validate_transaction(at, tx.data.clone()) {
+				Ok(TransactionValidity::Invalid(_)) => true,
+				Ok(_) => false,
+				Err(e) => {
+					debug!(target: "txpool", "Error revalidating transaction {:?}: {:?}", tx.hash, e);
+					false
+				},
+			})
+			.map(|tx| tx.hash.clone())
+			.collect::<Vec<_>>();
+
+		self.remove_invalid(&invalid);
+
+		Ok(())
+	}
+
 	/// Create a new transaction pool.
 	pub fn new(options: Options, api: B) -> Self {
 		Pool {
 			api,
+			pool: RwLock::new(base::BasePool::new(options.per_sender.clone(), options.min_priority_bump)),
 			options,
 			listener: Default::default(),
-			pool: Default::default(),
 			import_notification_sinks: Default::default(),
+			extrinsic_notification_sinks: Default::default(),
 			rotator: Default::default(),
 		}
 	}
@@ -382,6 +432,13 @@ impl<B: ChainApi> Pool<B> {
 		stream
 	}
 
+	/// Return a stream of extrinsics imported to the pool (to either of the queues).
+	pub fn extrinsic_notification_stream(&self) -> mpsc::UnboundedReceiver<ExtrinsicFor<B>> {
+		let (sink, stream) = mpsc::unbounded();
+		self.extrinsic_notification_sinks.lock().push(sink);
+		stream
+	}
+
 	/// Invoked when extrinsics are broadcasted.
 	pub fn on_broadcasted(&self, propagated: HashMap<ExHash<B>, Vec<String>>) {
 		let mut listener = self.listener.write();
@@ -411,6 +468,18 @@ impl<B: ChainApi> Pool<B> {
 		self.pool.read().ready()
 	}
 
+	/// Returns extrinsics of all transactions in the pool.
+	///
+	/// Ready transactions are returned first (in the same order as `ready`),
+	/// followed by the transactions from the future queue.
+	pub fn all_extrinsics(&self) -> Vec<ExtrinsicFor<B>> {
+		let pool = self.pool.read();
+		pool.ready()
+			.map(|tx| tx.data.clone())
+			.chain(pool.futures().map(|tx| tx.data.clone()))
+			.collect()
+	}
+
 	/// Returns pool status.
 	pub fn status(&self) -> base::Status {
 		self.pool.read().status()
@@ -492,6 +561,7 @@ mod tests {
 					provides: vec![vec![nonce as u8]],
 					longevity: 3,
 					propagate: true,
+					sender: None,
 				})
 			}
 		}
@@ -569,6 +639,53 @@ mod tests {
 		assert_matches!(res.unwrap_err(), error::Error::TemporarilyBanned);
 	}
 
+	#[test]
+	fn should_resubmit_banned_transaction() {
+		// given
+		let pool = pool();
+		let uxt = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		});
+		pool.rotator.ban(&time::Instant::now(), vec![pool.hash_of(&uxt)]);
+
+		// when
+		let res = pool.resubmit_at(&BlockId::Number(0), vec![uxt]).unwrap();
+
+		// then
+		assert_eq!(res.len(), 1);
+		assert!(res[0].is_ok());
+		assert_eq!(pool.status().ready, 1);
+	}
+
+	#[test]
+	fn should_remove_invalid_transactions_when_revalidating() {
+		// given
+		let pool = pool();
+		let hash1 = pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		})).unwrap();
+		let hash2 = pool.submit_one(&BlockId::Number(1), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(2)),
+			to: AccountId::from_h256(H256::from_low_u64_be(1)),
+			amount: 5,
+			nonce: 1,
+		})).unwrap();
+		assert_eq!(pool.status().ready, 2);
+
+		// when
+		pool.revalidate_ready(&BlockId::Number(1)).unwrap();
+
+		// then
+		assert_eq!(pool.ready().map(|v| v.hash).collect::<Vec<_>>(), vec![hash2]);
+		assert!(pool.rotator.is_banned(&hash1));
+	}
+
 	#[test]
 	fn should_notify_about_pool_events() {
 		let stream = {
@@ -673,6 +790,7 @@ mod tests {
 		let pool = Pool::new(Options {
 			ready: limit.clone(),
 			future: limit.clone(),
+			..Default::default()
 		}, TestApi::default());
 
 		let hash1 = pool.submit_one(&BlockId::Number(0), uxt(Transfer {
@@ -707,6 +825,7 @@ mod tests {
 		let pool = Pool::new(Options {
 			ready: limit.clone(),
 			future: limit.clone(),
+			..Default::default()
 		}, TestApi::default());
 
 		// when
@@ -864,6 +983,7 @@ mod tests {
 			let pool = Pool::new(Options {
 				ready: limit.clone(),
 				future: limit.clone(),
+				..Default::default()
 			}, TestApi::default());
 
 			let xt = uxt(Transfer {
@@ -114,6 +114,8 @@ pub struct ReadyTransactions<Hash: hash::Hash + Eq, Ex> {
 	ready: Arc<RwLock<HashMap<Hash, ReadyTx<Hash, Ex>>>>,
 	/// Best transactions that are ready to be included to the block without any other previous transaction.
 	best: BTreeSet<TransactionRef<Hash, Ex>>,
+	/// Minimal priority increase (in percent) required to replace existing transactions.
+	min_priority_bump: u64,
 }
 
 impl<Hash: hash::Hash + Eq, Ex> Default for ReadyTransactions<Hash, Ex> {
@@ -123,6 +125,17 @@ impl<Hash: hash::Hash + Eq, Ex> Default for ReadyTransactions<Hash, Ex> {
 			provided_tags: Default::default(),
 			ready: Default::default(),
 			best: Default::default(),
+			min_priority_bump: 0,
+		}
+	}
+}
+
+impl<Hash: hash::Hash + Eq, Ex> ReadyTransactions<Hash, Ex> {
+	/// Create new queue requiring given priority increase (in percent) for replacements.
+	pub fn with_min_priority_bump(min_priority_bump: u64) -> Self {
+		ReadyTransactions {
+			min_priority_bump,
+			..Default::default()
 		}
 	}
 }
@@ -374,7 +387,9 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 			};
 
 			// bail - the transaction has too low priority to replace the old ones
-			if old_priority >= tx.priority {
+			let required_priority = old_priority
+				.saturating_add(old_priority.saturating_mul(self.min_priority_bump) / 100);
+			if old_priority >= tx.priority || required_priority > tx.priority {
 				return Err(error::Error::TooLowPriority { old: old_priority, new: tx.priority })
 			}
 
@@ -489,6 +504,7 @@ fn remove_item<T: PartialEq>(vec: &mut Vec<T>, item: &T) {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use assert_matches::assert_matches;
 
 	fn tx(id: u8) -> Transaction<u64, Vec<u8>> {
 		Transaction {
@@ -500,6 +516,7 @@ mod tests {
 			requires: vec![vec![1], vec![2]],
 			provides: vec![vec![3], vec![4]],
 			propagate: true,
+			sender: None,
 		}
 	}
 
@@ -535,6 +552,34 @@ mod tests {
 		assert_eq!(ready.get().count(), 1);
 	}
 
+	#[test]
+	fn should_require_minimal_priority_bump_to_replace() {
+		// given
+		let mut ready = ReadyTransactions::with_min_priority_bump(10);
+		let mut tx1 = tx(1);
+		tx1.requires.clear();
+		tx1.priority = 100;
+		let mut tx2 = tx(2);
+		tx2.requires.clear();
+		tx2.priority = 109;
+		let x = WaitingTransaction::new(tx1, &ready.provided_tags(), &[]);
+		ready.import(x).unwrap();
+
+		// when
+		let x = WaitingTransaction::new(tx2.clone(), &ready.provided_tags(), &[]);
+		let err = ready.import(x).unwrap_err();
+		assert_matches!(err, error::Error::TooLowPriority { old: 100, new: 109 });
+
+		tx2.priority = 110;
+		let x = WaitingTransaction::new(tx2, &ready.provided_tags(), &[]);
+		let replaced = ready.import(x).unwrap();
+
+		// then
+		assert_eq!(replaced.len(), 1);
+		assert_eq!(replaced[0].hash, 1);
+		assert_eq!(ready.get().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2]);
+	}
+
 
 	#[test]
 	fn should_return_best_transactions_in_correct_order() {
@@ -560,6 +605,7 @@ mod tests {
 			requires: vec![tx1.provides[0].clone()],
 			provides: vec![],
 			propagate: true,
+			sender: None,
 		};
 
 		// when
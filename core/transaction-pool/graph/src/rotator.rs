@@ -121,6 +121,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![],
 			propagate: true,
+			sender: None,
 		};
 
 		(hash, tx)
@@ -187,6 +188,7 @@ mod tests {
 				requires: vec![],
 				provides: vec![],
 				propagate: true,
+				sender: None,
 			}
 		}
 
@@ -0,0 +1,265 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction pool journal.
+//!
+//! Stores extrinsics imported to the pool in the auxiliary storage of the client,
+//! so that they can be re-submitted after the node restarts.
+//!
+//! Extrinsics are appended to the journal as they are imported. Extrinsics that leave
+//! the pool are not removed, instead the journal is compacted (rewritten with the current
+//! content of the pool) once it grows considerably larger than the pool itself.
+
+use std::sync::Arc;
+
+use client::backend::AuxStore;
+use log::{debug, info, warn};
+use parity_codec::{Encode, Decode};
+use sr_primitives::generic::BlockId;
+use txpool::{ChainApi, ExtrinsicFor, Pool};
+
+use crate::error;
+
+/// Auxiliary storage key of the number of journal entries.
+const JOURNAL_LEN_KEY: &[u8] = b"txpool_journal_len";
+/// Prefix of auxiliary storage keys of the journal entries.
+const JOURNAL_ENTRY_PREFIX: &[u8] = b"txpool_journal_entry";
+/// Journals with less entries are never compacted.
+const MIN_COMPACTION_LEN: u64 = 1024;
+
+fn entry_key(index: u64) -> Vec<u8> {
+	let mut key = JOURNAL_ENTRY_PREFIX.to_vec();
+	index.using_encoded(|i| key.extend_from_slice(i));
+	key
+}
+
+fn read_len<A: AuxStore>(aux: &A) -> error::Result<u64> {
+	Ok(aux.get_aux(JOURNAL_LEN_KEY)?
+		.and_then(|len| u64::decode(&mut &len[..]))
+		.unwrap_or(0))
+}
+
+/// Replaces `len` entries of the journal with the extrinsics currently in the pool.
+///
+/// Ready extrinsics are stored first (in the order they would be included
+/// in a block), followed by the ones from the future queue.
+/// Returns the new number of entries.
+fn compact<A: AuxStore, B: ChainApi>(aux: &A, pool: &Pool<B>, len: u64) -> error::Result<u64> {
+	let extrinsics = pool.all_extrinsics();
+	let new_len = extrinsics.len() as u64;
+	debug!(target: "txpool", "Compacting the journal from {} to {} extrinsics", len, new_len);
+
+	let entries = extrinsics.iter()
+		.enumerate()
+		.map(|(index, xt)| (entry_key(index as u64), xt.encode()))
+		.chain(::std::iter::once((JOURNAL_LEN_KEY.to_vec(), new_len.encode())))
+		.collect::<Vec<_>>();
+	let removed = (new_len..len).map(entry_key).collect::<Vec<_>>();
+
+	aux.insert_aux(
+		&entries.iter().map(|(k, v)| (&k[..], &v[..])).collect::<Vec<_>>(),
+		&removed.iter().map(|k| &k[..]).collect::<Vec<_>>(),
+	)?;
+
+	Ok(new_len)
+}
+
+/// Appends extrinsics imported to the pool to the journal.
+pub struct Journal<A, B: ChainApi> {
+	aux: Arc<A>,
+	pool: Arc<Pool<B>>,
+	len: u64,
+}
+
+impl<A: AuxStore, B: ChainApi> Journal<A, B> {
+	/// Opens the journal kept in given auxiliary storage.
+	pub fn new(aux: Arc<A>, pool: Arc<Pool<B>>) -> error::Result<Self> {
+		let len = read_len(&*aux)?;
+		Ok(Journal { aux, pool, len })
+	}
+
+	/// Appends an extrinsic that was imported to the pool.
+	pub fn append(&mut self, xt: &ExtrinsicFor<B>) -> error::Result<()> {
+		let status = self.pool.status();
+		let pool_len = (status.ready + status.future) as u64;
+		if self.len >= MIN_COMPACTION_LEN && self.len >= pool_len.saturating_mul(2) {
+			// the extrinsic is part of the pool already (unless it was pruned in the meantime)
+			self.len = compact(&*self.aux, &self.pool, self.len)?;
+			return Ok(())
+		}
+
+		let key = entry_key(self.len);
+		let len = (self.len + 1).encode();
+		self.aux.insert_aux(&[(&key[..], &xt.encode()[..]), (JOURNAL_LEN_KEY, &len[..])], &[])?;
+		self.len += 1;
+
+		Ok(())
+	}
+}
+
+/// Re-submits all extrinsics from the journal to the pool at given block.
+///
+/// Extrinsics that are no longer valid (e.g. were already included in the chain)
+/// are dropped. The journal is compacted afterwards, so it only contains the
+/// re-imported extrinsics.
+/// Returns the number of extrinsics that were successfuly re-imported.
+pub fn restore<A: AuxStore, B: ChainApi>(
+	aux: &A,
+	pool: &Pool<B>,
+	at: &BlockId<B::Block>,
+) -> error::Result<usize> {
+	let len = read_len(aux)?;
+	if len == 0 {
+		return Ok(0)
+	}
+
+	let mut extrinsics: Vec<ExtrinsicFor<B>> = Vec::with_capacity(len as usize);
+	for index in 0..len {
+		match aux.get_aux(&entry_key(index))?.and_then(|xt| Decode::decode(&mut &xt[..])) {
+			Some(xt) => extrinsics.push(xt),
+			None => warn!(target: "txpool", "Unable to decode transaction pool journal entry {}, discarding.", index),
+		}
+	}
+
+	let total = extrinsics.len();
+	let results = match pool.submit_at(at, extrinsics) {
+		Ok(results) => results,
+		Err(e) => {
+			warn!(target: "txpool", "Unable to re-submit extrinsics from the journal: {:?}", e);
+			Vec::new()
+		},
+	};
+
+	let mut imported = 0;
+	for result in results {
+		match result {
+			Ok(_) => imported += 1,
+			Err(e) => debug!(target: "txpool", "Dropping extrinsic from the journal: {:?}", e),
+		}
+	}
+
+	if imported < total {
+		info!(
+			target: "txpool",
+			"Dropped {} invalid or stale extrinsics from the transaction pool journal",
+			total - imported,
+		);
+	}
+	info!(target: "txpool", "Restored {} extrinsics from the transaction pool journal", imported);
+
+	compact(aux, pool, len)?;
+
+	Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sr_primitives::traits::{Block as BlockT, Header as HeaderT};
+	use test_client::{
+		TestClient, AccountKeyring::*, consensus::BlockOrigin,
+		runtime::{AccountId, Transfer},
+	};
+	use crate::ChainApi as FullChainApi;
+
+	fn transfer(nonce: u64) -> test_client::runtime::Extrinsic {
+		Transfer {
+			from: Alice.into(),
+			to: AccountId::default(),
+			nonce,
+			amount: 1,
+		}.into_signed_tx()
+	}
+
+	#[test]
+	fn should_restore_pool_from_the_journal() {
+		let client = Arc::new(test_client::new());
+		let at = BlockId::hash(client.genesis_hash());
+
+		// given
+		let pool = Arc::new(Pool::new(Default::default(), FullChainApi::new(client.clone())));
+		let mut journal = Journal::new(client.clone(), pool.clone()).unwrap();
+		for xt in vec![transfer(0), transfer(1), transfer(3)] {
+			pool.submit_one(&at, xt.clone()).unwrap();
+			journal.append(&xt).unwrap();
+		}
+		assert_eq!(pool.status().ready, 2);
+		assert_eq!(pool.status().future, 1);
+		assert_eq!(read_len(&*client).unwrap(), 3);
+
+		// when
+		let pool = Pool::new(Default::default(), FullChainApi::new(client.clone()));
+		let imported = restore(&*client, &pool, &at).unwrap();
+
+		// then
+		assert_eq!(imported, 3);
+		assert_eq!(pool.status().ready, 2);
+		assert_eq!(pool.status().future, 1);
+		assert_eq!(read_len(&*client).unwrap(), 3);
+	}
+
+	#[test]
+	fn should_drop_stale_extrinsics_from_the_journal() {
+		let client = Arc::new(test_client::new());
+		let genesis = BlockId::hash(client.genesis_hash());
+
+		// given
+		let pool = Arc::new(Pool::new(Default::default(), FullChainApi::new(client.clone())));
+		let mut journal = Journal::new(client.clone(), pool.clone()).unwrap();
+		for xt in vec![transfer(0), transfer(1)] {
+			pool.submit_one(&genesis, xt.clone()).unwrap();
+			journal.append(&xt).unwrap();
+		}
+
+		// the first transfer gets included in a block
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push(transfer(0)).unwrap();
+		let block = builder.bake().unwrap();
+		let best = BlockId::hash(block.header().hash());
+		client.import(BlockOrigin::Own, block).unwrap();
+
+		// when
+		let pool = Pool::new(Default::default(), FullChainApi::new(client.clone()));
+		let imported = restore(&*client, &pool, &best).unwrap();
+
+		// then
+		assert_eq!(imported, 1);
+		assert_eq!(pool.ready().map(|tx| tx.data.transfer().nonce).collect::<Vec<_>>(), vec![1]);
+		assert_eq!(read_len(&*client).unwrap(), 1);
+		assert_eq!(client.get_aux(&entry_key(1)).unwrap(), None);
+	}
+
+	#[test]
+	fn should_compact_the_journal() {
+		let client = Arc::new(test_client::new());
+		let at = BlockId::hash(client.genesis_hash());
+		let pool = Arc::new(Pool::new(Default::default(), FullChainApi::new(client.clone())));
+		let mut journal = Journal::new(client.clone(), pool.clone()).unwrap();
+
+		// given
+		journal.len = MIN_COMPACTION_LEN;
+		pool.submit_one(&at, transfer(0)).unwrap();
+
+		// when
+		journal.append(&transfer(0)).unwrap();
+
+		// then
+		assert_eq!(journal.len, 1);
+		assert_eq!(read_len(&*client).unwrap(), 1);
+		assert_eq!(client.get_aux(&entry_key(0)).unwrap(), Some(transfer(0).encode()));
+		assert_eq!(client.get_aux(&entry_key(1)).unwrap(), None);
+	}
+}
@@ -24,6 +24,8 @@ mod api;
 mod tests;
 
 pub mod error;
+pub mod journal;
+pub mod metrics;
 
 pub use api::ChainApi;
 pub use txpool;
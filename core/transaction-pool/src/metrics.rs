@@ -0,0 +1,39 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction pool metrics.
+
+use std::sync::Arc;
+use lazy_static::lazy_static;
+use substrate_metrics::{Gauge, register_gauge};
+
+lazy_static! {
+	static ref READY: Arc<Gauge> = register_gauge(
+		"substrate_transaction_pool_ready",
+		"Number of transactions in the ready queue",
+	);
+	static ref FUTURE: Arc<Gauge> = register_gauge(
+		"substrate_transaction_pool_future",
+		"Number of transactions in the future queue",
+	);
+}
+
+/// Export the current sizes of the ready and future queues of the pool.
+pub fn report<A: txpool::ChainApi>(pool: &txpool::Pool<A>) {
+	let status = pool.status();
+	READY.set(status.ready as u64);
+	FUTURE.set(status.future as u64);
+}
@@ -54,6 +54,7 @@ impl txpool::ChainApi for TestApi {
 			provides,
 			longevity: 64,
 			propagate: true,
+			sender: Some(uxt.transfer().from.encode()),
 		})
 	}
 
@@ -173,3 +174,57 @@ fn should_ban_invalid_transactions() {
 	// then
 	pool.submit_one(&BlockId::number(0), uxt.clone()).unwrap_err();
 }
+
+#[test]
+fn should_resubmit_transactions_from_retracted_blocks() {
+	use std::sync::Arc;
+	use futures::Stream;
+	use client::BlockchainEvents;
+	use sr_primitives::traits::{Block as BlockT, Header as HeaderT};
+	use test_client::{TestClient, consensus::BlockOrigin};
+
+	let client = Arc::new(test_client::new());
+	let pool = Pool::new(Default::default(), ChainApi::new(client.clone()));
+	let notifications = client.import_notification_stream();
+	let genesis = client.genesis_hash();
+	let transfer = Transfer {
+		from: Alice.into(),
+		to: AccountId::default(),
+		nonce: 0,
+		amount: 5,
+	}.into_signed_tx();
+
+	// import `a1` containing the transfer and prune it from the pool
+	pool.submit_one(&BlockId::hash(genesis), transfer.clone()).unwrap();
+	let mut builder = client.new_block_at(&BlockId::hash(genesis), Default::default()).unwrap();
+	builder.push(transfer.clone()).unwrap();
+	let a1 = builder.bake().unwrap();
+	let a1_hash = a1.header().hash();
+	client.import(BlockOrigin::Own, a1.clone()).unwrap();
+	pool.prune(&BlockId::hash(a1_hash), &BlockId::hash(genesis), a1.extrinsics()).unwrap();
+	assert_eq!(pool.status().ready, 0);
+
+	// import a longer fork `b1 -> b2` that doesn't contain the transfer
+	let b1 = client.new_block_at(&BlockId::hash(genesis), Default::default()).unwrap().bake().unwrap();
+	let b1_hash = b1.header().hash();
+	client.import(BlockOrigin::Own, b1).unwrap();
+	let b2 = client.new_block_at(&BlockId::hash(b1_hash), Default::default()).unwrap().bake().unwrap();
+	let b2_hash = b2.header().hash();
+	client.import(BlockOrigin::Own, b2).unwrap();
+
+	let notifications = notifications.wait().take(3).collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(notifications[1].retracted, Vec::<Hash>::new());
+	assert!(notifications[2].is_new_best);
+	assert_eq!(notifications[2].retracted, vec![a1_hash]);
+	assert_eq!(notifications[2].enacted, vec![b1_hash]);
+
+	// the transfer is banned since it was recently included in a block
+	let at = BlockId::hash(b2_hash);
+	assert!(pool.submit_one(&at, transfer.clone()).is_err());
+
+	// but re-submission from the retracted block succeeds
+	let retracted = client.block(&BlockId::hash(a1_hash)).unwrap().unwrap();
+	let results = pool.resubmit_at(&at, retracted.block.extrinsics().iter().cloned()).unwrap();
+	assert!(results[0].is_ok());
+	assert_eq!(pool.status().ready, 1);
+}
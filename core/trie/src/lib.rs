@@ -245,6 +245,47 @@ pub fn record_all_keys<H: Hasher, DB>(
 	Ok(())
 }
 
+/// Read at most `max_entries` key-value pairs with keys strictly greater than `start`, in
+/// key order. The returned flag is true if there are no more keys after the returned ones.
+pub fn read_trie_range<H: Hasher, DB>(
+	db: &DB,
+	root: &H::Out,
+	start: &[u8],
+	max_entries: usize,
+) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool), Box<TrieError<H::Out>>> where
+	DB: hash_db::HashDBRef<H, trie_db::DBValue>
+{
+	trie_range::<H, DB, _>(db, root, start, max_entries, None, |key, value| (key, value.to_vec()))
+}
+
+/// Read at most `max_entries` keys strictly greater than `start`, in key order, without
+/// collecting their values. The returned flag is true if there are no more keys after the
+/// returned ones.
+pub fn read_trie_keys_range<H: Hasher, DB>(
+	db: &DB,
+	root: &H::Out,
+	start: &[u8],
+	max_entries: usize,
+) -> Result<(Vec<Vec<u8>>, bool), Box<TrieError<H::Out>>> where
+	DB: hash_db::HashDBRef<H, trie_db::DBValue>
+{
+	trie_range::<H, DB, _>(db, root, start, max_entries, None, |key, _| key)
+}
+
+/// Read a range of the trie as `read_trie_range` does, recording all trie nodes that are
+/// required to read the same range from the recorded nodes only.
+pub fn record_trie_range<H: Hasher, DB>(
+	db: &DB,
+	root: &H::Out,
+	start: &[u8],
+	max_entries: usize,
+	recorder: &mut Recorder<H::Out>,
+) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool), Box<TrieError<H::Out>>> where
+	DB: hash_db::HashDBRef<H, trie_db::DBValue>
+{
+	trie_range::<H, DB, _>(db, root, start, max_entries, Some(recorder), |key, value| (key, value.to_vec()))
+}
+
 /// Read a value from the child trie.
 pub fn read_child_trie_value<H: Hasher, DB>(
 	_storage_key: &[u8],
@@ -290,6 +331,48 @@ const EXTENSION_NODE_THRESHOLD: u8 = EXTENSION_NODE_BIG - EXTENSION_NODE_OFFSET;
 const LEAF_NODE_SMALL_MAX: u8 = LEAF_NODE_BIG - 1;
 const EXTENSION_NODE_SMALL_MAX: u8 = EXTENSION_NODE_BIG - 1;
 
+fn trie_range<H: Hasher, DB, T>(
+	db: &DB,
+	root: &H::Out,
+	start: &[u8],
+	max_entries: usize,
+	mut recorder: Option<&mut Recorder<H::Out>>,
+	entry: impl Fn(Vec<u8>, DBValue) -> T,
+) -> Result<(Vec<T>, bool), Box<TrieError<H::Out>>> where
+	DB: hash_db::HashDBRef<H, trie_db::DBValue>
+{
+	let trie = TrieDB::<H>::new(&*db, root)?;
+
+	// there's currently no API like iter_with()
+	// => lookup the start key and every visited key using get_with, which
+	// touches the same nodes as the iterator does
+	if let Some(ref mut recorder) = recorder {
+		trie.get_with(start, &mut **recorder)?;
+	}
+
+	let mut iter = trie.iter()?;
+	iter.seek(start)?;
+
+	let mut entries = Vec::new();
+	for x in iter {
+		let (key, value) = x?;
+		if &key[..] <= start {
+			continue;
+		}
+
+		if let Some(ref mut recorder) = recorder {
+			trie.get_with(&key, &mut **recorder)?;
+		}
+
+		if entries.len() == max_entries {
+			return Ok((entries, false));
+		}
+		entries.push(entry(key, value));
+	}
+
+	Ok((entries, true))
+}
+
 fn take<'a>(input: &mut &'a[u8], count: usize) -> Option<&'a[u8]> {
 	if input.len() < count {
 		return None
@@ -611,4 +694,61 @@ mod tests {
 
 		assert_eq!(pairs, iter_pairs);
 	}
+
+	#[test]
+	fn range_read_works() {
+		let pairs = vec![
+			(b"alfa".to_vec(), vec![1]),
+			(b"bravo".to_vec(), vec![2]),
+			(b"bravo2".to_vec(), vec![3]),
+			(b"charlie".to_vec(), vec![4]),
+			(b"delta".to_vec(), vec![5]),
+		];
+
+		let mut mdb = MemoryDB::default();
+		let mut root = Default::default();
+		let _ = populate_trie(&mut mdb, &mut root, &pairs);
+
+		assert_eq!(
+			read_trie_range::<Blake2Hasher, _>(&mdb, &root, &[], 2).unwrap(),
+			(pairs[..2].to_vec(), false),
+		);
+		assert_eq!(
+			read_trie_range::<Blake2Hasher, _>(&mdb, &root, b"bravo", 2).unwrap(),
+			(pairs[2..4].to_vec(), false),
+		);
+		assert_eq!(
+			read_trie_range::<Blake2Hasher, _>(&mdb, &root, b"bz", 2).unwrap(),
+			(pairs[3..].to_vec(), true),
+		);
+		assert_eq!(
+			read_trie_range::<Blake2Hasher, _>(&mdb, &root, b"delta", 2).unwrap(),
+			(Vec::new(), true),
+		);
+		assert_eq!(
+			read_trie_keys_range::<Blake2Hasher, _>(&mdb, &root, b"bravo", 2).unwrap(),
+			(vec![b"bravo2".to_vec(), b"charlie".to_vec()], false),
+		);
+	}
+
+	#[test]
+	fn recorded_range_can_be_read_again() {
+		let pairs = (0u8..64).map(|i| (vec![i, i.wrapping_mul(7)], vec![i])).collect::<Vec<_>>();
+
+		let mut mdb = MemoryDB::default();
+		let mut root = Default::default();
+		let _ = populate_trie(&mut mdb, &mut root, &pairs);
+
+		let start = [17u8, 255];
+		let mut recorder = Recorder::new();
+		let range = record_trie_range::<Blake2Hasher, _>(&mdb, &root, &start, 10, &mut recorder).unwrap();
+		assert_eq!(range, (pairs[18..28].to_vec(), false));
+
+		let mut proof_db = MemoryDB::<Blake2Hasher>::default();
+		for node in recorder.drain() {
+			proof_db.insert(&[], &node.data);
+		}
+		assert_eq!(read_trie_range::<Blake2Hasher, _>(&proof_db, &root, &start, 10).unwrap(), range);
+		assert!(read_trie_range::<Blake2Hasher, _>(&proof_db, &root, &start, 20).is_err());
+	}
 }
@@ -156,6 +156,100 @@ impl<H, N, V> ForkTree<H, N, V> where
 		self.node_iter().map(|node| (&node.hash, &node.number, &node.data))
 	}
 
+	/// Find the deepest node in the tree that is either the given block or one
+	/// of its ancestors, and return it. The given function `is_descendent_of`
+	/// should return `true` if the second hash (target) is a descendent of the
+	/// first hash (base).
+	pub fn find_ancestor_or_self<F, E>(
+		&self,
+		hash: &H,
+		number: &N,
+		is_descendent_of: &F,
+	) -> Result<Option<(&H, &N, &V)>, Error<E>>
+		where E: std::error::Error,
+			  F: Fn(&H, &H) -> Result<bool, E>,
+	{
+		let mut found = None;
+		let mut candidates = &self.roots;
+
+		'descend: loop {
+			for node in candidates.iter() {
+				if node.number <= *number && (node.hash == *hash || is_descendent_of(&node.hash, hash)?) {
+					found = Some(node);
+					candidates = &node.children;
+					continue 'descend;
+				}
+			}
+
+			break;
+		}
+
+		Ok(found.map(|node| (&node.hash, &node.number, &node.data)))
+	}
+
+	/// Prune the nodes that can't be on the chain of the given block, once it is
+	/// finalized. The deepest node that is either the block or one of its ancestors
+	/// becomes the only root, and only its children descending from the block are kept.
+	/// Returns whether any node was pruned. The given function `is_descendent_of` should
+	/// return `true` if the second hash (target) is a descendent of the first hash (base).
+	pub fn prune<F, E>(
+		&mut self,
+		hash: &H,
+		number: &N,
+		is_descendent_of: &F,
+	) -> Result<bool, Error<E>>
+		where E: std::error::Error,
+			  F: Fn(&H, &H) -> Result<bool, E>,
+	{
+		let mut path = Vec::new();
+		let mut candidates = &self.roots;
+
+		'descend: loop {
+			for (i, node) in candidates.iter().enumerate() {
+				if node.number <= *number && (node.hash == *hash || is_descendent_of(&node.hash, hash)?) {
+					path.push(i);
+					candidates = &node.children;
+					continue 'descend;
+				}
+			}
+
+			break;
+		}
+
+		let mut changed = path.len() > 1 || (!path.is_empty() && self.roots.len() > 1);
+		let mut kept = None;
+		for i in path {
+			kept = Some(match kept.take() {
+				None => self.roots.swap_remove(i),
+				Some(mut parent) => parent.children.swap_remove(i),
+			});
+		}
+
+		let candidates = match kept {
+			Some(ref mut node) => std::mem::replace(&mut node.children, Vec::new()),
+			None => std::mem::replace(&mut self.roots, Vec::new()),
+		};
+
+		let mut retained = Vec::new();
+		for node in candidates {
+			if node.number > *number && is_descendent_of(hash, &node.hash)? {
+				retained.push(node);
+			} else {
+				changed = true;
+			}
+		}
+
+		self.roots = match kept {
+			Some(mut node) => {
+				node.children = retained;
+				vec![node]
+			},
+			None => retained,
+		};
+
+		Ok(changed)
+	}
+
 	/// Finalize a root in the tree and return it, return `None` in case no root
 	/// with the given hash exists. All other roots are pruned, and the children
 	/// of the finalized node become the new roots.
@@ -788,6 +882,74 @@ mod test {
 		assert_eq!(tree.roots().count(), 0);
 	}
 
+	#[test]
+	fn find_ancestor_or_self_works() {
+		let (tree, is_descendent_of) = test_fork_tree();
+
+		assert_eq!(
+			tree.find_ancestor_or_self(&"H", &3, &is_descendent_of),
+			Ok(Some((&"H", &3, &()))),
+		);
+
+		assert_eq!(
+			tree.find_ancestor_or_self(&"X", &10, &is_descendent_of),
+			Ok(None),
+		);
+
+		let mut tree = ForkTree::new();
+		tree.import("A", 1, (), &is_descendent_of).unwrap();
+		tree.import("B", 2, (), &is_descendent_of).unwrap();
+		tree.import("F", 2, (), &is_descendent_of).unwrap();
+
+		// the deepest node is returned for blocks which aren't in the tree.
+		assert_eq!(
+			tree.find_ancestor_or_self(&"D", &4, &is_descendent_of),
+			Ok(Some((&"B", &2, &()))),
+		);
+
+		assert_eq!(
+			tree.find_ancestor_or_self(&"I", &4, &is_descendent_of),
+			Ok(Some((&"F", &2, &()))),
+		);
+
+		assert_eq!(
+			tree.find_ancestor_or_self(&"K", &3, &is_descendent_of),
+			Ok(Some((&"A", &1, &()))),
+		);
+	}
+
+	#[test]
+	fn prune_works() {
+		let (mut tree, is_descendent_of) = test_fork_tree();
+
+		// the nodes are kept from the deepest ancestor of the block on.
+		assert_eq!(tree.prune(&"F", &2, &is_descendent_of), Ok(true));
+		assert_eq!(
+			tree.iter().map(|(h, ..)| *h).collect::<Vec<_>>(),
+			vec!["F", "H", "I", "G"],
+		);
+
+		// pruning again at the same block doesn't change anything.
+		assert_eq!(tree.prune(&"F", &2, &is_descendent_of), Ok(false));
+
+		// the competing branches starting at or before the block are pruned.
+		assert_eq!(tree.prune(&"I", &4, &is_descendent_of), Ok(true));
+		assert_eq!(
+			tree.iter().map(|(h, ..)| *h).collect::<Vec<_>>(),
+			vec!["I"],
+		);
+
+		// without any ancestor in the tree, only the descendents of the block are kept.
+		let (mut tree, is_descendent_of) = test_fork_tree();
+		tree.finalize_root(&"A");
+		assert_eq!(tree.prune(&"0", &1, &is_descendent_of), Ok(false));
+		assert_eq!(tree.prune(&"J", &2, &is_descendent_of), Ok(true));
+		assert_eq!(
+			tree.iter().map(|(h, ..)| *h).collect::<Vec<_>>(),
+			vec!["J", "K"],
+		);
+	}
+
 	#[test]
 	fn iter_iterates_in_preorder() {
 		let (tree, ..) = test_fork_tree();
@@ -143,6 +143,7 @@ impl consensus::Trait for Runtime {
 	// The aura module handles offline-reports internally
 	// rather than using an explicit report system.
 	type InherentOfflineReport = ();
+	type MisbehaviorReport = ();
 	/// The ubiquitous log type.
 	type Log = Log;
 }
@@ -105,7 +105,7 @@ fn testnet_genesis(initial_authorities: Vec<AuthorityId>, endowed_accounts: Vec<
 		}),
 		balances: Some(BalancesConfig {
 			transaction_base_fee: 1,
-			transaction_byte_fee: 0,
+			transaction_weight_fee: 0,
 			existential_deposit: 500,
 			transfer_fee: 0,
 			creation_fee: 0,
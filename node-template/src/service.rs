@@ -15,7 +15,7 @@ use substrate_service::{
 use basic_authorship::ProposerFactory;
 use consensus::{import_queue, start_aura, AuraImportQueue, SlotDuration, NothingExtra};
 use substrate_client::{self as client, LongestChain};
-use primitives::{ed25519::Pair, Pair as PairT};
+use primitives::{ed25519::Pair, crypto::Sign};
 use inherents::InherentDataProviders;
 use network::construct_simple_protocol;
 use substrate_executor::native_executor_instance;
@@ -65,9 +65,9 @@ construct_service_factory! {
 				FullComponents::<Factory>::new(config, executor)
 			},
 		AuthoritySetup = {
-			|service: Self::FullService, executor: TaskExecutor, key: Option<Arc<Pair>>| {
+			|service: Self::FullService, executor: TaskExecutor, key: Option<Arc<dyn Sign<Pair>>>| {
 				if let Some(key) = key {
-					info!("Using authority key {}", key.public());
+					info!("Using authority key {}", key.signing_key());
 					let proposer = Arc::new(ProposerFactory {
 						client: service.client(),
 						transaction_pool: service.transaction_pool(),
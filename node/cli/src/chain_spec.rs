@@ -86,7 +86,7 @@ fn staging_testnet_config_genesis() -> GenesisConfig {
 		system: None,
 		balances: Some(BalancesConfig {
 			transaction_base_fee: 1 * CENTS,
-			transaction_byte_fee: 10 * MILLICENTS,
+			transaction_weight_fee: 10 * MILLICENTS,
 			balances: endowed_accounts.iter().cloned()
 				.map(|k| (k, ENDOWMENT))
 				.chain(initial_authorities.iter().map(|x| (x.0.clone(), STASH)))
@@ -160,7 +160,7 @@ fn staging_testnet_config_genesis() -> GenesisConfig {
 			surcharge_reward: 150,
 			tombstone_deposit: 16,
 			transaction_base_fee: 1 * CENTS,
-			transaction_byte_fee: 10 * MILLICENTS,
+			transaction_weight_fee: 10 * MILLICENTS,
 			transfer_fee: 1 * CENTS,
 			creation_fee: 1 * CENTS,
 			contract_fee: 1 * CENTS,
@@ -253,7 +253,7 @@ pub fn testnet_genesis(
 		surcharge_reward: 150,
 		tombstone_deposit: 16,
 		transaction_base_fee: 1,
-		transaction_byte_fee: 0,
+		transaction_weight_fee: 0,
 		transfer_fee: 0,
 		creation_fee: 0,
 		contract_fee: 21,
@@ -278,7 +278,7 @@ pub fn testnet_genesis(
 		}),
 		balances: Some(BalancesConfig {
 			transaction_base_fee: 1,
-			transaction_byte_fee: 0,
+			transaction_weight_fee: 0,
 			existential_deposit: 500,
 			transfer_fee: 0,
 			creation_fee: 0,
@@ -21,24 +21,32 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use client::{self, LongestChain};
-use consensus::{import_queue, start_aura, AuraImportQueue, SlotDuration, NothingExtra};
+use client::{self, LongestChain, BlockchainEvents};
+use consensus::{
+	import_queue, start_aura, submit_equivocation_reports, AuraImportQueue, SlotDuration, NothingExtra,
+};
+use futures::{Future, Stream, future::Either, sync::mpsc};
 use grandpa::{self, FinalityProofProvider as GrandpaFinalityProofProvider};
+use grandpa_rpc::{Grandpa, GrandpaApi};
+use manual_seal::{EngineCommand, ManualSeal, ManualSealApi};
 use node_executor;
-use primitives::{Pair as PairT, ed25519};
-use node_primitives::Block;
+use primitives::{ed25519, crypto::Sign};
+use node_primitives::{Block, AccountId, Balance, Hash};
+use sr_primitives::generic::BlockId;
 use node_runtime::{GenesisConfig, RuntimeApi};
 use substrate_service::{
 	FactoryFullConfiguration, LightComponents, FullComponents, FullBackend,
 	FullClient, LightClient, LightBackend, FullExecutor, LightExecutor, TaskExecutor,
-	error::{Error as ServiceError},
+	RpcSubscriptions, Sealing, error::{Error as ServiceError},
 };
 use transaction_pool::{self, txpool::{Pool as TransactionPool}};
 use inherents::InherentDataProviders;
 use network::construct_simple_protocol;
 use substrate_service::construct_service_factory;
-use log::info;
+use log::{info, warn};
 use substrate_service::TelemetryOnConnect;
+use substrate_rpc::contracts::{Contracts, ContractsApi};
+use substrate_rpc::payment::{Payment, PaymentApi};
 
 construct_simple_protocol! {
 	/// Demo protocol attachment for substrate.
@@ -50,6 +58,11 @@ pub struct NodeConfig<F: substrate_service::ServiceFactory> {
 	/// grandpa connection to import block
 	// FIXME #1134 rather than putting this on the config, let's have an actual intermediate setup state
 	pub grandpa_import_setup: Option<(Arc<grandpa::BlockImportForService<F>>, grandpa::LinkHalfForService<F>)>,
+	/// Commands sent to the manual seal engine by the `engine_createBlock` RPC.
+	manual_seal_commands: Option<(
+		mpsc::UnboundedSender<EngineCommand<Hash>>,
+		mpsc::UnboundedReceiver<EngineCommand<Hash>>,
+	)>,
 	inherent_data_providers: InherentDataProviders,
 }
 
@@ -57,6 +70,7 @@ impl<F> Default for NodeConfig<F> where F: substrate_service::ServiceFactory {
 	fn default() -> NodeConfig<F> {
 		NodeConfig {
 			grandpa_import_setup: None,
+			manual_seal_commands: None,
 			inherent_data_providers: InherentDataProviders::new(),
 		}
 	}
@@ -78,12 +92,69 @@ construct_service_factory! {
 			{ |config: FactoryFullConfiguration<Self>, executor: TaskExecutor|
 				FullComponents::<Factory>::new(config, executor) },
 		AuthoritySetup = {
-			|mut service: Self::FullService, executor: TaskExecutor, local_key: Option<Arc<ed25519::Pair>>| {
+			|mut service: Self::FullService, executor: TaskExecutor, local_key: Option<Arc<dyn Sign<ed25519::Pair>>>| {
 				let (block_import, link_half) = service.config.custom.grandpa_import_setup.take()
 					.expect("Link Half and Block Import are present for Full Services or setup failed before. qed");
 
+				// submit the equivocations detected during block import and by GRANDPA to the chain.
+				let client = service.client();
+				let transaction_pool = service.transaction_pool();
+				let report_equivocations = client.import_notification_stream()
+					.filter(|notification| notification.is_new_best)
+					.for_each(move |notification| {
+						let at = BlockId::hash(notification.hash);
+						if let Err(e) = submit_equivocation_reports(&*client, &*transaction_pool, &at) {
+							warn!("Unable to submit equivocation reports: {:?}", e);
+						}
+						if let Err(e) = grandpa::submit_equivocation_reports(&*client, &*transaction_pool, &at) {
+							warn!("Unable to submit GRANDPA equivocation reports: {:?}", e);
+						}
+						Ok(())
+					});
+				executor.spawn(report_equivocations.select(service.on_exit()).then(|_| Ok(())));
+
+				// development chains authoring blocks on request don't run the consensus engines.
+				if let Some(sealing) = service.config.sealing {
+					let proposer = substrate_basic_authorship::ProposerFactory {
+						client: service.client(),
+						transaction_pool: service.transaction_pool(),
+					};
+					let select_chain = service.select_chain()
+						.ok_or(ServiceError::SelectChainRequired)?;
+					let inherent_data_providers = service.config.custom.inherent_data_providers.clone();
+
+					let sealing = match sealing {
+						Sealing::Manual => {
+							let (_, commands) = service.config.custom.manual_seal_commands.take()
+								.expect("Manual seal commands are set up with the import queue. qed");
+							info!("Running manual seal engine, blocks are authored with `engine_createBlock`");
+							Either::A(manual_seal::run_manual_seal(
+								block_import,
+								proposer,
+								select_chain,
+								commands,
+								inherent_data_providers,
+							))
+						},
+						Sealing::Instant => {
+							info!("Running instant seal engine, blocks are authored on new transactions");
+							Either::B(manual_seal::run_instant_seal(
+								block_import,
+								proposer,
+								select_chain,
+								service.transaction_pool(),
+								inherent_data_providers,
+								true,
+							))
+						},
+					};
+					executor.spawn(sealing.select(service.on_exit()).then(|_| Ok(())));
+
+					return Ok(service);
+				}
+
 				if let Some(ref key) = local_key {
-					info!("Using authority key {}", key.public());
+					info!("Using authority key {}", key.signing_key());
 					let proposer = Arc::new(substrate_basic_authorship::ProposerFactory {
 						client: service.client(),
 						transaction_pool: service.transaction_pool(),
@@ -105,7 +176,7 @@ construct_service_factory! {
 						service.config.force_authoring,
 					)?);
 
-					info!("Running Grandpa session as Authority {}", key.public());
+					info!("Running Grandpa session as Authority {}", key.signing_key());
 				}
 
 				let local_key = if service.config.disable_grandpa {
@@ -165,6 +236,9 @@ construct_service_factory! {
 				let justification_import = block_import.clone();
 
 				config.custom.grandpa_import_setup = Some((block_import.clone(), link_half));
+				if config.sealing == Some(Sealing::Manual) {
+					config.custom.manual_seal_commands = Some(mpsc::unbounded());
+				}
 
 				import_queue::<_, _, _, ed25519::Pair>(
 					slot_duration,
@@ -214,6 +288,42 @@ construct_service_factory! {
 		FinalityProofProvider = { |client: Arc<FullClient<Self>>| {
 			Ok(Some(Arc::new(GrandpaFinalityProofProvider::new(client.clone(), client)) as _))
 		}},
+		WarpSyncProvider = { |client: Arc<FullClient<Self>>| {
+			let checker = grandpa::ExecutorAuthoritySetChecker::new(
+				node_executor::NativeExecutor::<node_executor::Executor>::new(None),
+			);
+			Ok(Some(Arc::new(grandpa::WarpSyncProofProvider::new(
+				client.clone(),
+				client,
+				Arc::new(checker),
+			)) as _))
+		}},
+		RpcExtension = {
+			|config: &FactoryFullConfiguration<Self>, client: Arc<FullClient<Self>>, subscriptions: RpcSubscriptions| {
+				let contracts = Contracts::new(client.clone());
+				let payment = Payment::new(client);
+				let mut extension: substrate_service::RpcExtension =
+					ContractsApi::<_, AccountId, Balance>::to_delegate(contracts).into_iter()
+						.chain(PaymentApi::<_, Balance>::to_delegate(payment))
+						.collect();
+
+				if let Some((ref commands, _)) = config.custom.manual_seal_commands {
+					let manual_seal = ManualSeal::new(commands.clone());
+					extension.extend(ManualSealApi::to_delegate(manual_seal));
+				}
+
+				if let Some((_, ref link_half)) = config.custom.grandpa_import_setup {
+					let grandpa = Grandpa::new(
+						Arc::new(link_half.round_state()),
+						link_half.justification_subscribers(),
+						subscriptions,
+					);
+					extension.extend(GrandpaApi::to_delegate(grandpa));
+				}
+
+				extension
+			}
+		},
 	}
 }
 
@@ -33,22 +33,27 @@ use client::{
 };
 use runtime_primitives::{ApplyResult, generic, create_runtime_str};
 use runtime_primitives::transaction_validity::TransactionValidity;
+use runtime_primitives::weights::Weighable;
 use runtime_primitives::traits::{
 	BlakeTwo256, Block as BlockT, DigestFor, NumberFor, StaticLookup, AuthorityIdFor, Convert,
 };
 use version::RuntimeVersion;
+use parity_codec::{Encode, Decode};
 use council::{motions as council_motions, voting as council_voting};
 #[cfg(feature = "std")]
 use council::seats as council_seats;
 #[cfg(any(feature = "std", test))]
 use version::NativeVersion;
 use substrate_primitives::OpaqueMetadata;
+use contract_rpc_runtime_api::ContractExecResult;
+use balances_rpc_runtime_api::FeeInfo;
 
 #[cfg(any(feature = "std", test))]
 pub use runtime_primitives::BuildStorage;
 pub use consensus::Call as ConsensusCall;
 pub use timestamp::Call as TimestampCall;
 pub use balances::Call as BalancesCall;
+pub use grandpa::Call as GrandpaCall;
 pub use runtime_primitives::{Permill, Perbill};
 pub use support::StorageValue;
 pub use staking::StakerStatus;
@@ -58,8 +63,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("node"),
 	impl_name: create_runtime_str!("substrate-node"),
 	authoring_version: 10,
-	spec_version: 89,
-	impl_version: 89,
+	spec_version: 98,
+	impl_version: 98,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -128,6 +133,7 @@ impl consensus::Trait for Runtime {
 	// The Aura module handles offline-reports internally
 	// rather than using an explicit report system.
 	type InherentOfflineReport = ();
+	type MisbehaviorReport = aura::StakingEquivocationSlasher<Runtime, AuthoritySignature>;
 }
 
 impl timestamp::Trait for Runtime {
@@ -181,13 +187,24 @@ impl treasury::Trait for Runtime {
 	type ProposalRejection = ();
 }
 
+/// Charges the calls dispatched by contracts the fee `Balances` charges for transactions of the
+/// same weight, so that it matches the one reported by the transaction payment runtime API.
+pub struct DispatchFeeComputor;
+impl contract::ComputeDispatchFee<Call, Balance> for DispatchFeeComputor {
+	fn compute_dispatch_fee(call: &Call) -> Balance {
+		let weight = call.weight(call.using_encoded(|encoded| encoded.len()));
+		let (base_fee, weight_fee) = Balances::transaction_fee(weight);
+		base_fee.saturating_add(weight_fee)
+	}
+}
+
 impl contract::Trait for Runtime {
 	type Currency = Balances;
 	type Call = Call;
 	type Event = Event;
 	type Gas = u64;
 	type DetermineContractAddress = contract::SimpleAddressDeterminator<Runtime>;
-	type ComputeDispatchFee = contract::DefaultDispatchFeeComputor<Runtime>;
+	type ComputeDispatchFee = DispatchFeeComputor;
 	type TrieIdGenerator = contract::TrieIdFromParentCounter<Runtime>;
 	type GasPayment = ();
 }
@@ -199,6 +216,8 @@ impl sudo::Trait for Runtime {
 
 impl grandpa::Trait for Runtime {
 	type SessionKey = AuthorityId;
+	type Signature = AuthoritySignature;
+	type OnEquivocation = grandpa::StakingEquivocationSlasher<Runtime>;
 	type Log = Log;
 	type Event = Event;
 }
@@ -216,7 +235,7 @@ construct_runtime!(
 		System: system::{default, Log(ChangesTrieRoot)},
 		Aura: aura::{Module, Inherent(Timestamp)},
 		Timestamp: timestamp::{Module, Call, Storage, Config<T>, Inherent},
-		Consensus: consensus::{Module, Call, Storage, Config<T>, Log(AuthoritiesChange), Inherent},
+		Consensus: consensus::{Module, Call, Storage, Config<T>, Log(AuthoritiesChange), Inherent, ValidateUnsigned},
 		Indices: indices,
 		Balances: balances,
 		Session: session,
@@ -227,7 +246,7 @@ construct_runtime!(
 		CouncilMotions: council_motions::{Module, Call, Storage, Event<T>, Origin},
 		CouncilSeats: council_seats::{Config<T>},
 		FinalityTracker: finality_tracker::{Module, Call, Inherent},
-		Grandpa: grandpa::{Module, Call, Storage, Config<T>, Log(), Event<T>},
+		Grandpa: grandpa::{Module, Call, Storage, Config<T>, Log(), Event<T>, ValidateUnsigned},
 		Treasury: treasury,
 		Contract: contract::{Module, Call, Storage, Config<T>, Event<T>},
 		Sudo: sudo,
@@ -350,9 +369,63 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl consensus_aura::AuraEquivocationApi<Block> for Runtime {
+		fn construct_equivocation_report_extrinsic(report: Vec<u8>) -> Option<UncheckedExtrinsic> {
+			let report = Decode::decode(&mut &report[..])?;
+			Some(UncheckedExtrinsic::new_unsigned(Call::Consensus(ConsensusCall::report_misbehavior(report))))
+		}
+	}
+
+	impl fg_primitives::GrandpaEquivocationApi<Block> for Runtime {
+		fn construct_equivocation_report_extrinsic(report: Vec<u8>) -> Option<UncheckedExtrinsic> {
+			let report = Decode::decode(&mut &report[..])?;
+			Some(UncheckedExtrinsic::new_unsigned(Call::Grandpa(GrandpaCall::report_misbehavior(report))))
+		}
+	}
+
 	impl consensus_authorities::AuthoritiesApi<Block> for Runtime {
 		fn authorities() -> Vec<AuthorityIdFor<Block>> {
 			Consensus::authorities()
 		}
 	}
+
+	impl contract_rpc_runtime_api::ContractsApi<Block, AccountId, Balance> for Runtime {
+		fn call(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: u64,
+			input_data: Vec<u8>,
+		) -> ContractExecResult {
+			let outcome = Contract::bare_call(origin, dest, value, gas_limit, input_data);
+			let (output, revert_reason) = match outcome.result {
+				Ok(output) => (output, None),
+				Err(reason) => (Vec::new(), Some(reason.as_bytes().to_vec())),
+			};
+			ContractExecResult {
+				revert_reason,
+				output,
+				gas_consumed: outcome.gas_consumed,
+				events: outcome.events.iter().map(Encode::encode).collect(),
+			}
+		}
+	}
+
+	impl balances_rpc_runtime_api::TransactionPaymentApi<Block, Balance> for Runtime {
+		fn query_info(uxt: <Block as BlockT>::Extrinsic, len: u32) -> FeeInfo<Balance> {
+			let weight = uxt.function.weight(len as usize);
+			// unsigned extrinsics don't pay any fee.
+			if uxt.signature.is_none() {
+				return FeeInfo { weight, ..Default::default() };
+			}
+
+			let (base_fee, weight_fee) = Balances::transaction_fee(weight);
+			FeeInfo {
+				weight,
+				base_fee,
+				weight_fee,
+				total_fee: base_fee.saturating_add(weight_fee),
+			}
+		}
+	}
 }
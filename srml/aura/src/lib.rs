@@ -21,19 +21,22 @@
 //!
 //! ## Overview
 //!
-//! The Aura module extends Aura consensus by managing offline reporting.
+//! The Aura module extends Aura consensus by managing offline reporting and by verifying
+//! reports of authorities that sealed two different blocks for the same slot.
 //!
 //! ## Interface
 //!
 //! ### Public Functions
 //!
 //! - `slot_duration` - Determine the Aura slot-duration based on the Timestamp module configuration.
+//! - `check_equivocation_report` - Verify a report of an equivocating authority.
 //!
 //! ## Related Modules
 //!
 //! - [Staking](../srml_staking/index.html): The Staking module is called in Aura to enforce slashing
 //!  if validators miss a certain number of slots (see the [`StakingSlasher`](./struct.StakingSlasher.html)
-//!  struct and associated method).
+//!  struct and associated method). Equivocating authorities are slashed via the
+//!  [`StakingEquivocationSlasher`](./struct.StakingEquivocationSlasher.html) struct.
 //! - [Timestamp](../srml_timestamp/index.html): The Timestamp module is used in Aura to track
 //! consensus rounds (via `slots`).
 //! - [Consensus](../srml_consensus/index.html): The Consensus module does not relate directly to Aura,
@@ -52,13 +55,14 @@ pub use timestamp;
 
 use rstd::{result, prelude::*};
 use srml_support::storage::StorageValue;
-use srml_support::{decl_storage, decl_module};
-use primitives::traits::{SaturatedConversion, Saturating, Zero, One};
+use srml_support::{decl_storage, decl_module, Parameter};
+use primitives::traits::{SaturatedConversion, Saturating, Zero, One, Verify};
 use timestamp::OnTimestampSet;
 #[cfg(feature = "std")]
 use timestamp::TimestampInherentData;
 use parity_codec::{Encode, Decode};
 use inherents::{RuntimeString, InherentIdentifier, InherentData, ProvideInherent, MakeFatalError};
+use aura_primitives::EquivocationReport;
 #[cfg(feature = "std")]
 use inherents::{InherentDataProviders, ProvideInherentData};
 
@@ -198,6 +202,25 @@ impl<T: Trait> Module<T> {
 		<timestamp::Module<T>>::minimum_period().saturating_mul(2.into())
 	}
 
+	/// Verify a report of an equivocating authority against the current authorities.
+	///
+	/// Returns the identifier of the offence, i.e. the offender and the slot.
+	pub fn check_equivocation_report<S>(
+		report: &EquivocationReport<T::SessionKey, S>,
+	) -> result::Result<Vec<u8>, &'static str> where
+		T: consensus::Trait,
+		S: Verify<Signer = T::SessionKey>,
+	{
+		if !<consensus::Module<T>>::authorities().contains(&report.offender) {
+			return Err("Reported offender is not an authority");
+		}
+		if !report.check::<T::Hashing>() {
+			return Err("Invalid equivocation proof");
+		}
+
+		Ok((&report.offender, report.slot).encode())
+	}
+
 	fn on_timestamp_set<H: HandleReport>(now: T::Moment, slot_duration: T::Moment) {
 		let last = Self::last();
 		<Self as Store>::LastTimestamp::put(now.clone());
@@ -247,6 +270,29 @@ impl<T: staking::Trait + Trait> HandleReport for StakingSlasher<T> {
 	}
 }
 
+/// A type for verifying reports of equivocating authorities and slashing
+/// them via the staking module.
+///
+/// `S` is the signature type used to seal blocks.
+pub struct StakingEquivocationSlasher<T, S>(::rstd::marker::PhantomData<(T, S)>);
+
+impl<T, S> consensus::HandleMisbehaviorReport for StakingEquivocationSlasher<T, S> where
+	T: staking::Trait + Trait,
+	S: Verify<Signer = T::SessionKey> + Parameter,
+{
+	type Report = EquivocationReport<T::SessionKey, S>;
+
+	fn check_report(report: &Self::Report) -> result::Result<Vec<u8>, &'static str> {
+		<Module<T>>::check_equivocation_report(report)
+	}
+
+	fn handle_report(report: Self::Report) {
+		if let Some(v) = session::Module::<T>::key_owner(&report.offender) {
+			staking::Module::<T>::on_equivocating_validator(v);
+		}
+	}
+}
+
 impl<T: Trait> ProvideInherent for Module<T> {
 	type Call = timestamp::Call<T>;
 	type Error = MakeFatalError<RuntimeString>;
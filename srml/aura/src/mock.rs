@@ -36,6 +36,7 @@ impl consensus::Trait for Test {
 	type Log = DigestItem;
 	type SessionKey = UintAuthorityId;
 	type InherentOfflineReport = ();
+	type MisbehaviorReport = ();
 }
 
 impl system::Trait for Test {
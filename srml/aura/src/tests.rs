@@ -20,9 +20,12 @@
 
 use lazy_static::lazy_static;
 use crate::mock::{System, Aura, new_test_ext};
-use primitives::traits::Header;
+use primitives::{generic, traits::{Header, BlakeTwo256, Hash, Lazy, Verify}, testing::UintAuthorityId};
 use runtime_io::with_externalities;
 use parking_lot::Mutex;
+use parity_codec::{Encode, Decode};
+use substrate_primitives::H256;
+use aura_primitives::{EquivocationReport, AURA_ENGINE_ID};
 use crate::{AuraReport, HandleReport};
 
 #[test]
@@ -90,3 +93,69 @@ fn aura_reports_offline() {
 		assert_eq!(SLASH_COUNTS.lock().as_slice(), &[0, 0, 1, 1]);
 	});
 }
+
+/// A signature that is valid for the given authority and message.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+struct TestSignature(u64, Vec<u8>);
+
+impl Verify for TestSignature {
+	type Signer = UintAuthorityId;
+
+	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &UintAuthorityId) -> bool {
+		self.0 == signer.0 && msg.get() == &self.1[..]
+	}
+}
+
+fn sealed_pre_header(author: u64, number: u64, slot: u64) -> (Vec<u8>, TestSignature) {
+	let mut header = generic::Header::<u64, BlakeTwo256, generic::DigestItem<H256, u64, ()>>::new(
+		number,
+		Default::default(),
+		Default::default(),
+		Default::default(),
+		Default::default(),
+	);
+	header.digest_mut().push(generic::DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode()));
+
+	let encoded = header.encode();
+	let signature = TestSignature(author, BlakeTwo256::hash(&encoded).as_ref().to_vec());
+	(encoded, signature)
+}
+
+fn equivocation_report(offender: u64, fst: (u64, u64), snd: (u64, u64)) -> EquivocationReport<UintAuthorityId, TestSignature> {
+	let (first_header, first_signature) = sealed_pre_header(offender, fst.0, fst.1);
+	let (second_header, second_signature) = sealed_pre_header(offender, snd.0, snd.1);
+
+	EquivocationReport {
+		offender: UintAuthorityId(offender),
+		slot: fst.1,
+		first_header,
+		first_signature,
+		second_header,
+		second_signature,
+	}
+}
+
+#[test]
+fn equivocation_reports_are_checked() {
+	with_externalities(&mut new_test_ext(vec![0, 1, 2, 3]), || {
+		let report = equivocation_report(2, (1, 7), (2, 7));
+		assert_eq!(Aura::check_equivocation_report(&report), Ok((UintAuthorityId(2), 7u64).encode()));
+
+		// the offender must be a current authority.
+		let report = equivocation_report(5, (1, 7), (2, 7));
+		assert!(Aura::check_equivocation_report(&report).is_err());
+
+		// both headers must be for the same slot.
+		let report = equivocation_report(2, (1, 7), (2, 8));
+		assert!(Aura::check_equivocation_report(&report).is_err());
+
+		// both headers must be sealed by the offender.
+		let mut report = equivocation_report(2, (1, 7), (2, 7));
+		report.second_signature.0 = 3;
+		assert!(Aura::check_equivocation_report(&report).is_err());
+
+		// the same header twice is no equivocation.
+		let report = equivocation_report(2, (1, 7), (1, 7));
+		assert!(Aura::check_equivocation_report(&report).is_err());
+	});
+}
@@ -15,6 +15,15 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Consensus extension module for BABE consensus.
+//!
+//! The module keeps track of the BABE epochs. The VRF outputs of the primary slot claims
+//! made during an epoch are collected and, once the epoch is over, hashed into the
+//! randomness of the epoch after the next one. The authorities follow the same schedule:
+//! the ones set by a session change, via [`SyncedAuthorities`](./struct.SyncedAuthorities.html),
+//! are announced when the next epoch starts and take over the epoch after.
+//!
+//! It also verifies reports of authorities that sealed two different blocks for the same
+//! slot, which are slashed via the [`StakingEquivocationSlasher`](./struct.StakingEquivocationSlasher.html).
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(unsafe_code, warnings)]
@@ -23,14 +32,20 @@ pub use timestamp;
 use rstd::{result, prelude::*};
 use srml_support::{decl_storage, decl_module};
 use timestamp::{OnTimestampSet, Trait};
-use primitives::traits::{SaturatedConversion, Saturating};
+use primitives::traits::{SaturatedConversion, Saturating, Digest, DigestItem};
 #[cfg(feature = "std")]
 use timestamp::TimestampInherentData;
-use parity_codec::Decode;
+use parity_codec::{Encode, Decode};
+use babe_primitives::{
+	AuthorityId, BABE_ENGINE_ID, EquivocationReport, NextEpochDescriptor, RawBabePreDigest, VRF_OUTPUT_LENGTH,
+};
 use inherents::{RuntimeString, InherentIdentifier, InherentData, ProvideInherent, MakeFatalError};
 #[cfg(feature = "std")]
 use inherents::{InherentDataProviders, ProvideInherentData};
 
+mod mock;
+mod tests;
+
 /// The BABE inherent identifier.
 pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"babeslot";
 
@@ -107,11 +122,45 @@ decl_storage! {
 	trait Store for Module<T: Trait> as Babe {
 		// The last timestamp.
 		LastTimestamp get(last): T::Moment;
+
+		/// The length of the epochs, in slots.
+		pub EpochDuration get(epoch_duration) config(): u64;
+
+		/// The authorities of the current epoch.
+		pub Authorities get(authorities) config(): Vec<AuthorityId>;
+
+		/// The authorities of the next epoch, announced when the current one started, if
+		/// they differ from the current ones.
+		NextAuthorities: Option<Vec<AuthorityId>>;
+
+		/// The authorities set by the last session change, to be announced when the next
+		/// epoch starts.
+		PendingAuthorities: Option<Vec<AuthorityId>>;
+
+		/// The index of the current epoch.
+		pub EpochIndex get(epoch_index): u64;
+
+		/// The first slot of the current epoch, set by the first block of the chain.
+		pub EpochStartSlot get(epoch_start_slot): Option<u64>;
+
+		/// The randomness of the current epoch.
+		pub Randomness get(randomness) config(): [u8; VRF_OUTPUT_LENGTH];
+
+		/// The randomness of the next epoch.
+		NextRandomness build(|config: &GenesisConfig<T>| config.randomness): [u8; VRF_OUTPUT_LENGTH];
+
+		/// The VRF outputs collected during the current epoch, making up the randomness
+		/// of the epoch after the next one.
+		UnderConstruction: Vec<[u8; VRF_OUTPUT_LENGTH]>;
 	}
 }
 
 decl_module! {
-	pub struct Module<T: Trait> for enum Call where origin: T::Origin { }
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn on_initialize() {
+			Self::do_initialize();
+		}
+	}
 }
 
 impl<T: Trait> Module<T> {
@@ -121,6 +170,140 @@ impl<T: Trait> Module<T> {
 		// the majority of their slot.
 		<timestamp::Module<T>>::minimum_period().saturating_mul(2.into())
 	}
+
+	/// The data of the next epoch, as announced to the client in the first block of
+	/// each epoch.
+	pub fn next_epoch() -> NextEpochDescriptor {
+		NextEpochDescriptor {
+			authorities: <NextAuthorities<T>>::get().unwrap_or_else(Self::authorities),
+			randomness: <NextRandomness<T>>::get(),
+		}
+	}
+
+	/// Verify a report of an equivocating authority against the authorities of the
+	/// current epoch.
+	///
+	/// Returns the identifier of the offence, i.e. the offender and the slot.
+	pub fn check_equivocation_report(report: &EquivocationReport) -> result::Result<Vec<u8>, &'static str> {
+		if !Self::authorities().contains(&report.offender) {
+			return Err("Reported offender is not an authority");
+		}
+		if !report.check::<T::Hashing>() {
+			return Err("Invalid equivocation proof");
+		}
+
+		Ok((&report.offender, report.slot).encode())
+	}
+
+	fn do_initialize() {
+		let pre_digest = <system::Module<T>>::digest()
+			.logs()
+			.iter()
+			.filter_map(|s| s.as_pre_runtime())
+			.filter_map(|(id, mut data)| if id == BABE_ENGINE_ID {
+				RawBabePreDigest::decode(&mut data)
+			} else {
+				None
+			})
+			.next();
+
+		let pre_digest = match pre_digest {
+			Some(pre_digest) => pre_digest,
+			None => return,
+		};
+
+		let slot_num = pre_digest.slot_num();
+		match Self::epoch_start_slot() {
+			// the first block of the chain starts the first epoch.
+			None => <EpochStartSlot<T>>::put(slot_num),
+			Some(start_slot) => {
+				let duration = Self::epoch_duration();
+				if duration > 0 && slot_num >= start_slot + duration {
+					// epochs without any block are skipped.
+					let skipped_epochs = (slot_num - start_slot) / duration;
+					Self::change_epoch(skipped_epochs, start_slot + skipped_epochs * duration);
+				}
+			}
+		}
+
+		if let Some(vrf_output) = pre_digest.vrf_output() {
+			<UnderConstruction<T>>::mutate(|outputs| outputs.push(*vrf_output));
+		}
+	}
+
+	fn change_epoch(skipped_epochs: u64, start_slot: u64) {
+		let epoch_index = Self::epoch_index() + skipped_epochs;
+		<EpochIndex<T>>::put(epoch_index);
+		<EpochStartSlot<T>>::put(start_slot);
+
+		// the authorities announced when the previous epoch started take over, and the
+		// ones of the last session change are announced.
+		if let Some(authorities) = <NextAuthorities<T>>::take() {
+			<Authorities<T>>::put(authorities);
+		}
+		if let Some(authorities) = <PendingAuthorities<T>>::take() {
+			<NextAuthorities<T>>::put(authorities);
+		}
+
+		// the randomness of the next epoch was announced when the current one started,
+		// the one of the epoch after is made of the outputs of the previous epoch.
+		let randomness = <NextRandomness<T>>::get();
+		<Randomness<T>>::put(randomness);
+		let next_randomness = compute_randomness(randomness, epoch_index, <UnderConstruction<T>>::take());
+		<NextRandomness<T>>::put(next_randomness);
+	}
+}
+
+/// Helper for authorities being synchronized with the general session authorities.
+///
+/// The authorities set by a session change are announced when the next epoch starts,
+/// and take over the epoch after.
+pub struct SyncedAuthorities<T>(::rstd::marker::PhantomData<T>);
+
+impl<X, T> session::OnSessionChange<X> for SyncedAuthorities<T> where
+	T: Trait + consensus::Trait<SessionKey = AuthorityId>,
+{
+	fn on_session_change(_: X, _: bool) {
+		<PendingAuthorities<T>>::put(<consensus::Module<T>>::authorities());
+	}
+}
+
+/// A type for verifying reports of equivocating authorities and slashing
+/// them via the staking module.
+///
+/// The session keys of the runtime must be the BABE authority keys.
+pub struct StakingEquivocationSlasher<T>(::rstd::marker::PhantomData<T>);
+
+impl<T> consensus::HandleMisbehaviorReport for StakingEquivocationSlasher<T> where
+	T: staking::Trait + consensus::Trait<SessionKey = AuthorityId>,
+{
+	type Report = EquivocationReport;
+
+	fn check_report(report: &Self::Report) -> result::Result<Vec<u8>, &'static str> {
+		<Module<T>>::check_equivocation_report(report)
+	}
+
+	fn handle_report(report: Self::Report) {
+		if let Some(v) = session::Module::<T>::key_owner(&report.offender) {
+			staking::Module::<T>::on_equivocating_validator(v);
+		}
+	}
+}
+
+/// Hash the VRF outputs of an epoch into the randomness of a future epoch.
+fn compute_randomness(
+	last_epoch_randomness: [u8; VRF_OUTPUT_LENGTH],
+	epoch_index: u64,
+	vrf_outputs: Vec<[u8; VRF_OUTPUT_LENGTH]>,
+) -> [u8; VRF_OUTPUT_LENGTH] {
+	let mut s = Vec::with_capacity(VRF_OUTPUT_LENGTH * (vrf_outputs.len() + 1) + 8);
+	s.extend_from_slice(&last_epoch_randomness);
+	s.extend_from_slice(&epoch_index.to_le_bytes());
+	for vrf_output in vrf_outputs {
+		s.extend_from_slice(&vrf_output);
+	}
+
+	runtime_io::blake2_256(&s)
 }
 
 impl<T: Trait> OnTimestampSet<T::Moment> for Module<T> {
@@ -0,0 +1,97 @@
+// Copyright 2018-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Test utilities
+
+#![cfg(test)]
+
+use primitives::{BuildStorage, traits::IdentityLookup, testing::{Digest, DigestItem, Header}};
+use srml_support::impl_outer_origin;
+use runtime_io;
+use parity_codec::Encode;
+use substrate_primitives::{H256, Blake2Hasher};
+use babe_primitives::AuthorityId;
+use crate::{GenesisConfig, Module};
+
+impl_outer_origin!{
+	pub enum Origin for Test {}
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+impl system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = ::primitives::traits::BlakeTwo256;
+	type Digest = Digest;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type Log = DigestItem;
+}
+
+impl timestamp::Trait for Test {
+	type Moment = u64;
+	type OnTimestampSet = Babe;
+}
+
+impl consensus::Trait for Test {
+	type Log = ConsensusLog;
+	type SessionKey = AuthorityId;
+	type InherentOfflineReport = ();
+	type MisbehaviorReport = ();
+}
+
+/// Log of the consensus module. The BABE authorities don't convert to the authorities of
+/// the test digest, so the log is kept opaque.
+pub struct ConsensusLog(DigestItem);
+
+impl From<consensus::Log<Test>> for ConsensusLog {
+	fn from(log: consensus::Log<Test>) -> Self {
+		ConsensusLog(DigestItem::Other(log.encode()))
+	}
+}
+
+impl From<ConsensusLog> for DigestItem {
+	fn from(log: ConsensusLog) -> Self {
+		log.0
+	}
+}
+
+/// The randomness of the first epoch.
+pub const GENESIS_RANDOMNESS: [u8; 32] = [42; 32];
+
+pub fn new_test_ext(authorities: Vec<AuthorityId>) -> runtime_io::TestExternalities<Blake2Hasher> {
+	let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+	t.extend(timestamp::GenesisConfig::<Test>{
+		minimum_period: 1,
+	}.build_storage().unwrap().0);
+	t.extend(GenesisConfig::<Test>{
+		epoch_duration: 10,
+		authorities,
+		randomness: GENESIS_RANDOMNESS,
+		_genesis_phantom_data: Default::default(),
+	}.build_storage().unwrap().0);
+	t.into()
+}
+
+pub type System = system::Module<Test>;
+pub type Babe = Module<Test>;
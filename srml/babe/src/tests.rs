@@ -0,0 +1,203 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the module.
+
+#![cfg(test)]
+
+use crate::mock::{Babe, System, Test, GENESIS_RANDOMNESS, new_test_ext};
+use crate::{compute_randomness, NextRandomness, SyncedAuthorities, UnderConstruction};
+use primitives::{generic, testing::{Digest, DigestItem}};
+use primitives::traits::{Header, BlakeTwo256, Digest as DigestT, Hash, OnInitialize};
+use runtime_io::with_externalities;
+use parity_codec::Encode;
+use session::OnSessionChange;
+use srml_support::StorageValue;
+use substrate_primitives::{H256, Pair, sr25519};
+use babe_primitives::{
+	AuthorityId, AuthoritySignature, EquivocationReport, RawBabePreDigest, BABE_ENGINE_ID, VRF_PROOF_LENGTH,
+};
+
+fn pair(seed: u8) -> sr25519::Pair {
+	sr25519::Pair::from_seed(&[seed; 32])
+}
+
+fn primary_pre_digest(slot_num: u64, vrf_output: [u8; 32]) -> RawBabePreDigest {
+	RawBabePreDigest::Primary {
+		vrf_output,
+		proof: [0; VRF_PROOF_LENGTH],
+		author: pair(1).public(),
+		slot_num,
+	}
+}
+
+fn secondary_pre_digest(slot_num: u64) -> RawBabePreDigest {
+	RawBabePreDigest::Secondary { author: pair(1).public(), slot_num }
+}
+
+/// Initialize the block `number`, authored with the given pre-digest.
+fn initialize_block(number: u64, pre_digest: RawBabePreDigest) {
+	let mut digest = Digest::default();
+	digest.push(DigestItem::PreRuntime(BABE_ENGINE_ID, pre_digest.encode()));
+	System::initialize(&number, &Default::default(), &Default::default(), &digest);
+	Babe::on_initialize(number);
+}
+
+fn sealed_pre_header(author: &sr25519::Pair, number: u64, slot_num: u64) -> (Vec<u8>, AuthoritySignature) {
+	let mut header = generic::Header::<u64, BlakeTwo256, generic::DigestItem<H256, AuthorityId, ()>>::new(
+		number,
+		Default::default(),
+		Default::default(),
+		Default::default(),
+		Default::default(),
+	);
+	let pre_digest = RawBabePreDigest::Secondary { author: author.public(), slot_num };
+	header.digest_mut().push(generic::DigestItem::PreRuntime(BABE_ENGINE_ID, pre_digest.encode()));
+
+	let encoded = header.encode();
+	let signature = author.sign(BlakeTwo256::hash(&encoded).as_ref());
+	(encoded, signature)
+}
+
+fn equivocation_report(offender: &sr25519::Pair, fst: (u64, u64), snd: (u64, u64)) -> EquivocationReport {
+	let (first_header, first_signature) = sealed_pre_header(offender, fst.0, fst.1);
+	let (second_header, second_signature) = sealed_pre_header(offender, snd.0, snd.1);
+
+	EquivocationReport {
+		offender: offender.public(),
+		slot: fst.1,
+		first_header,
+		first_signature,
+		second_header,
+		second_signature,
+	}
+}
+
+#[test]
+fn equivocation_reports_are_checked() {
+	with_externalities(&mut new_test_ext(vec![pair(1).public(), pair(2).public()]), || {
+		let report = equivocation_report(&pair(2), (1, 7), (2, 7));
+		assert_eq!(Babe::check_equivocation_report(&report), Ok((pair(2).public(), 7u64).encode()));
+
+		// the offender must be a current authority.
+		let report = equivocation_report(&pair(3), (1, 7), (2, 7));
+		assert!(Babe::check_equivocation_report(&report).is_err());
+
+		// both headers must be for the same slot.
+		let report = equivocation_report(&pair(2), (1, 7), (2, 8));
+		assert!(Babe::check_equivocation_report(&report).is_err());
+
+		// both headers must be sealed by the offender.
+		let mut report = equivocation_report(&pair(2), (1, 7), (2, 7));
+		report.second_signature = sealed_pre_header(&pair(1), 2, 7).1;
+		assert!(Babe::check_equivocation_report(&report).is_err());
+
+		// the same header twice is no equivocation.
+		let report = equivocation_report(&pair(2), (1, 7), (1, 7));
+		assert!(Babe::check_equivocation_report(&report).is_err());
+	});
+}
+
+#[test]
+fn first_block_starts_first_epoch() {
+	with_externalities(&mut new_test_ext(vec![pair(1).public()]), || {
+		assert_eq!(Babe::epoch_start_slot(), None);
+
+		initialize_block(1, secondary_pre_digest(3));
+		assert_eq!(Babe::epoch_start_slot(), Some(3));
+		assert_eq!(Babe::epoch_index(), 0);
+
+		// the next epoch keeps the randomness of the first one.
+		assert_eq!(Babe::randomness(), GENESIS_RANDOMNESS);
+		assert_eq!(Babe::next_epoch().randomness, GENESIS_RANDOMNESS);
+		assert_eq!(Babe::next_epoch().authorities, vec![pair(1).public()]);
+	});
+}
+
+#[test]
+fn vrf_outputs_of_primary_slots_are_collected() {
+	with_externalities(&mut new_test_ext(vec![pair(1).public()]), || {
+		initialize_block(1, primary_pre_digest(0, [1; 32]));
+		initialize_block(2, secondary_pre_digest(1));
+		initialize_block(3, primary_pre_digest(2, [2; 32]));
+
+		assert_eq!(<UnderConstruction<Test>>::get(), vec![[1; 32], [2; 32]]);
+	});
+}
+
+#[test]
+fn randomness_is_rotated_on_epoch_change() {
+	with_externalities(&mut new_test_ext(vec![pair(1).public()]), || {
+		initialize_block(1, primary_pre_digest(0, [1; 32]));
+		initialize_block(2, primary_pre_digest(9, [2; 32]));
+		assert_eq!(Babe::epoch_index(), 0);
+
+		// the randomness announced at the start of the first epoch is enacted, and the
+		// outputs of the first epoch make up the randomness announced for the third one.
+		initialize_block(3, primary_pre_digest(10, [3; 32]));
+		let third_randomness = compute_randomness(GENESIS_RANDOMNESS, 1, vec![[1; 32], [2; 32]]);
+		assert_eq!((Babe::epoch_index(), Babe::epoch_start_slot()), (1, Some(10)));
+		assert_eq!(Babe::randomness(), GENESIS_RANDOMNESS);
+		assert_eq!(Babe::next_epoch().randomness, third_randomness);
+		assert_eq!(<UnderConstruction<Test>>::get(), vec![[3; 32]]);
+
+		initialize_block(4, secondary_pre_digest(20));
+		assert_eq!((Babe::epoch_index(), Babe::epoch_start_slot()), (2, Some(20)));
+		assert_eq!(Babe::randomness(), third_randomness);
+		assert_eq!(
+			<NextRandomness<Test>>::get(),
+			compute_randomness(third_randomness, 2, vec![[3; 32]]),
+		);
+		assert!(<UnderConstruction<Test>>::get().is_empty());
+	});
+}
+
+#[test]
+fn skipped_epochs_enact_announced_data() {
+	with_externalities(&mut new_test_ext(vec![pair(1).public()]), || {
+		initialize_block(1, primary_pre_digest(0, [1; 32]));
+
+		// no block was authored during the second and third epochs.
+		initialize_block(2, secondary_pre_digest(35));
+		assert_eq!((Babe::epoch_index(), Babe::epoch_start_slot()), (3, Some(30)));
+		assert_eq!(Babe::randomness(), GENESIS_RANDOMNESS);
+		assert_eq!(
+			Babe::next_epoch().randomness,
+			compute_randomness(GENESIS_RANDOMNESS, 3, vec![[1; 32]]),
+		);
+	});
+}
+
+#[test]
+fn authorities_of_session_changes_take_over_after_next_epoch() {
+	with_externalities(&mut new_test_ext(vec![pair(1).public()]), || {
+		initialize_block(1, secondary_pre_digest(0));
+
+		consensus::Module::<Test>::set_authorities(&[pair(2).public()]);
+		SyncedAuthorities::<Test>::on_session_change(0u64, false);
+		assert_eq!(Babe::next_epoch().authorities, vec![pair(1).public()]);
+
+		// the new authorities are announced when the next epoch starts...
+		initialize_block(2, secondary_pre_digest(10));
+		assert_eq!(Babe::authorities(), vec![pair(1).public()]);
+		assert_eq!(Babe::next_epoch().authorities, vec![pair(2).public()]);
+
+		// ...and enacted the epoch after.
+		initialize_block(3, secondary_pre_digest(20));
+		assert_eq!(Babe::authorities(), vec![pair(2).public()]);
+		assert_eq!(Babe::next_epoch().authorities, vec![pair(2).public()]);
+	});
+}
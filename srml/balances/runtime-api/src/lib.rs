@@ -0,0 +1,52 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definition required by the transaction payment RPC extensions.
+//!
+//! The API allows clients to query the fee an extrinsic would pay before submitting it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+use client::decl_runtime_apis;
+use parity_codec::{Codec, Encode, Decode};
+use runtime_primitives::traits::Block as BlockT;
+use runtime_primitives::weights::Weight;
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+
+/// Fee an extrinsic would pay, along with its breakdown.
+#[derive(Clone, Default, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct FeeInfo<Balance> {
+	/// The weight of the extrinsic.
+	pub weight: Weight,
+	/// The fixed part of the fee, paid by every transaction.
+	pub base_fee: Balance,
+	/// The part of the fee proportional to the weight of the extrinsic.
+	pub weight_fee: Balance,
+	/// The total fee. Unsigned extrinsics don't pay any fee.
+	pub total_fee: Balance,
+}
+
+decl_runtime_apis! {
+	/// The API to query the fee of extrinsics.
+	pub trait TransactionPaymentApi<Balance: Codec> {
+		/// Get the fee the given extrinsic would pay, given its encoded length `len`.
+		fn query_info(uxt: <Block as BlockT>::Extrinsic, len: u32) -> FeeInfo<Balance>;
+	}
+}
@@ -154,7 +154,7 @@ use srml_support::traits::{
 	WithdrawReason, WithdrawReasons, LockIdentifier, LockableCurrency, ExistenceRequirement,
 	Imbalance, SignedImbalance, ReservableCurrency
 };
-use srml_support::dispatch::Result;
+use srml_support::dispatch::{Result, Weight};
 use primitives::traits::{
 	Zero, SimpleArithmetic, StaticLookup, Member, CheckedAdd, CheckedSub,
 	MaybeSerializeDebug, Saturating
@@ -275,8 +275,9 @@ decl_storage! {
 		pub CreationFee get(creation_fee) config(): T::Balance;
 		/// The fee to be paid for making a transaction; the base.
 		pub TransactionBaseFee get(transaction_base_fee) config(): T::Balance;
-		/// The fee to be paid for making a transaction; the per-byte portion.
-		pub TransactionByteFee get(transaction_byte_fee) config(): T::Balance;
+		/// The fee to be paid for making a transaction; the per-weight-unit portion. Calls
+		/// that don't declare a weight weigh their encoded length in bytes.
+		pub TransactionWeightFee get(transaction_weight_fee) config(): T::Balance;
 
 		/// Information regarding the vesting of a given account.
 		pub Vesting get(vesting) build(|config: &GenesisConfig<T, I>| {
@@ -390,6 +391,13 @@ impl<T: Trait<I>, I: Instance> Module<T, I> {
 		}
 	}
 
+	/// Get the fee paid for a transaction of the given `weight`, as its base part and its
+	/// weight-proportional part.
+	pub fn transaction_fee(weight: Weight) -> (T::Balance, T::Balance) {
+		let weight_fee = Self::transaction_weight_fee().saturating_mul(T::Balance::from(weight));
+		(Self::transaction_base_fee(), weight_fee)
+	}
+
 	// PRIVATE MUTABLES
 
 	/// Set the reserved balance of an account to some new value. Will enforce `ExistentialDeposit`
@@ -1016,9 +1024,9 @@ where
 }
 
 impl<T: Trait<I>, I: Instance> MakePayment<T::AccountId> for Module<T, I> {
-	fn make_payment(transactor: &T::AccountId, encoded_len: usize) -> Result {
-		let encoded_len = T::Balance::from(encoded_len as u32);
-		let transaction_fee = Self::transaction_base_fee() + Self::transaction_byte_fee() * encoded_len;
+	fn make_payment(transactor: &T::AccountId, weight: Weight) -> Result {
+		let (base_fee, weight_fee) = Self::transaction_fee(weight);
+		let transaction_fee = base_fee.saturating_add(weight_fee);
 		let imbalance = Self::withdraw(
 			transactor,
 			transaction_fee,
@@ -618,3 +618,39 @@ fn extra_balance_should_transfer() {
 		}
 	);
 }
+
+#[test]
+fn transaction_fee_is_split_into_base_and_weight_fee() {
+	with_externalities(
+		&mut ExtBuilder::default()
+			.existential_deposit(10)
+			.monied(true)
+			.transaction_fees(10, 2)
+			.build(),
+		|| {
+			assert_eq!(Balances::transaction_fee(0), (10, 0));
+			assert_eq!(Balances::transaction_fee(25), (10, 50));
+
+			assert_ok!(<Balances as MakePayment<_>>::make_payment(&1, 25));
+			assert_eq!(Balances::free_balance(&1), 100 - 10 - 50);
+		}
+	);
+}
+
+#[test]
+fn transaction_fee_saturates() {
+	with_externalities(
+		&mut ExtBuilder::default()
+			.existential_deposit(10)
+			.monied(true)
+			.transaction_fees(10, u64::max_value())
+			.build(),
+		|| {
+			assert_eq!(Balances::transaction_fee(2), (10, u64::max_value()));
+			assert_noop!(
+				<Balances as MakePayment<_>>::make_payment(&1, 2),
+				"too few free funds in account"
+			);
+		}
+	);
+}
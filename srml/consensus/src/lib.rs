@@ -29,7 +29,8 @@
 //!
 //! ### Dispatchable Functions
 //!
-//! - `report_misbehavior` - Report some misbehavior. The origin of this call must be signed.
+//! - `report_misbehavior` - Report some misbehavior of an authority. The report is verified by the runtime
+//!  and the offender is punished at most once per offence. The origin of this call must be unsigned.
 //! - `note_offline` - Note that the previous block's validator missed its opportunity to propose a block.
 //!  The origin of this call must be an inherent.
 //! - `remark` - Make some on-chain remark. The origin of this call must be signed.
@@ -43,6 +44,8 @@
 //! - `set_authorities` - Set the current set of authorities' session keys.
 //! - `set_authority_count` - Set the total number of authorities.
 //! - `set_authority` - Set a single authority by index.
+//! - `note_new_session` - Start recording the offences of a new session, forgetting the ones that are
+//!  out of the reporting window.
 //!
 //! ## Usage
 //!
@@ -131,10 +134,15 @@ use serde::Serialize;
 use rstd::prelude::*;
 use parity_codec as codec;
 use codec::{Encode, Decode};
-use srml_support::{storage, Parameter, decl_storage, decl_module};
-use srml_support::storage::StorageValue;
+use srml_support::{storage, Parameter, decl_storage, decl_module, ensure};
+use srml_support::storage::{StorageValue, StorageMap};
 use srml_support::storage::unhashed::StorageVec;
-use primitives::traits::{MaybeSerializeDebug, Member};
+use srml_support::dispatch::TransactionWeight;
+use primitives::traits::{MaybeSerializeDebug, Member, ValidateUnsigned};
+use primitives::ApplyError;
+use primitives::transaction_validity::{
+	TransactionValidity, TransactionPriority, TransactionLongevity,
+};
 use substrate_primitives::storage::well_known_keys;
 use system::{ensure_signed, ensure_none};
 use inherents::{
@@ -153,6 +161,11 @@ pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"offlrep0";
 /// The error type used by this inherent.
 pub type InherentError = RuntimeString;
 
+/// The priority of misbehavior reports in the transaction pool.
+///
+/// Above the priority of regular extrinsics, which is their encoded length.
+pub const MISBEHAVIOR_REPORT_PRIORITY: TransactionPriority = 1 << 32;
+
 struct AuthorityStorageVec<S: codec::Codec + Default>(rstd::marker::PhantomData<S>);
 impl<S: codec::Codec + Default> StorageVec for AuthorityStorageVec<S> {
 	type Item = S;
@@ -196,6 +209,31 @@ impl InherentOfflineReport for () {
 	}
 }
 
+/// Verifies and handles reports of misbehaving authorities.
+pub trait HandleMisbehaviorReport {
+	/// The report data type submitted to the runtime.
+	type Report: codec::Codec + Parameter;
+
+	/// Check that the report proves misbehavior of an authority.
+	///
+	/// Returns an identifier of the offence, made of the offender and the time of
+	/// the offence (e.g. its slot), which makes sure that the same offence is only
+	/// punished once.
+	fn check_report(report: &Self::Report) -> Result<Vec<u8>, &'static str>;
+
+	/// Punish the offender of a report that has passed `check_report`.
+	fn handle_report(report: Self::Report);
+}
+
+impl HandleMisbehaviorReport for () {
+	type Report = Vec<u8>;
+
+	fn check_report(_: &Vec<u8>) -> Result<Vec<u8>, &'static str> {
+		Err("Misbehavior reports not supported")
+	}
+	fn handle_report(_: Vec<u8>) { }
+}
+
 /// A variant of the `OfflineReport` that is useful for instant-finality blocks.
 ///
 /// This assumes blocks are only finalized.
@@ -263,6 +301,9 @@ pub trait Trait: system::Trait {
 	/// Defines the offline-report type of the trait.
 	/// Set to `()` if offline-reports aren't needed for this runtime.
 	type InherentOfflineReport: InherentOfflineReport;
+	/// Defines how misbehavior reports are verified and handled.
+	/// Set to `()` if misbehavior reports aren't supported by this runtime.
+	type MisbehaviorReport: HandleMisbehaviorReport;
 }
 
 decl_storage! {
@@ -270,6 +311,10 @@ decl_storage! {
 		// Actual authorities set at the block execution start. Is `Some` iff
 		// the set has been changed.
 		OriginalAuthorities: Option<Vec<T::SessionKey>>;
+		/// Offences that have already been reported and punished.
+		ReportedOffences get(is_offence_reported): map Vec<u8> => bool;
+		/// The offences reported in each session of the reporting window, oldest first.
+		RecentOffences get(recent_offences): Vec<Vec<Vec<u8>>>;
 	}
 	add_extra_genesis {
 		config(authorities): Vec<T::SessionKey>;
@@ -292,11 +337,22 @@ decl_storage! {
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		/// Report some misbehavior.
-		fn report_misbehavior(origin, _report: Vec<u8>) {
-			ensure_signed(origin)?;
+		fn report_misbehavior(origin, report: <T::MisbehaviorReport as HandleMisbehaviorReport>::Report) {
+			ensure_none(origin)?;
+
+			let offence = T::MisbehaviorReport::check_report(&report)?;
+			ensure!(!Self::is_offence_reported(&offence), "Offence has already been reported");
+
+			<ReportedOffences<T>>::insert(&offence, true);
+			<RecentOffences<T>>::mutate(|recent| match recent.last_mut() {
+				Some(current) => current.push(offence),
+				None => recent.push(vec![offence]),
+			});
+			T::MisbehaviorReport::handle_report(report);
 		}
 
 		/// Note that the previous block's validator missed its opportunity to propose a block.
+		#[weight = TransactionWeight::Free]
 		fn note_offline(origin, offline: <T::InherentOfflineReport as InherentOfflineReport>::Inherent) {
 			ensure_none(origin)?;
 
@@ -314,6 +370,7 @@ decl_module! {
 		}
 
 		/// Set the new code.
+		#[weight = TransactionWeight::Max]
 		pub fn set_code(new: Vec<u8>) {
 			storage::unhashed::put_raw(well_known_keys::CODE, &new);
 		}
@@ -376,6 +433,22 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Start recording the offences of a new session, forgetting the ones reported more
+	/// than `window` sessions ago (counting the new one).
+	///
+	/// Called by `rotate_session` only.
+	pub fn note_new_session(window: u32) {
+		let mut recent = <RecentOffences<T>>::get();
+		recent.push(Vec::new());
+
+		let expired = recent.len().saturating_sub(window as usize);
+		for offence in recent.drain(..expired).flatten() {
+			<ReportedOffences<T>>::remove(offence);
+		}
+
+		<RecentOffences<T>>::put(recent);
+	}
+
 	/// Save original authorities set.
 	fn save_original_authorities(current_authorities: Option<Vec<T::SessionKey>>) {
 		if OriginalAuthorities::<T>::get().is_some() {
@@ -393,6 +466,29 @@ impl<T: Trait> Module<T> {
 	}
 }
 
+/// Misbehavior reports are unsigned extrinsics, their validity is established by
+/// checking the report itself.
+impl<T: Trait> ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		match call {
+			Call::report_misbehavior(report) => match T::MisbehaviorReport::check_report(report) {
+				Ok(ref offence) if !Self::is_offence_reported(offence) => TransactionValidity::Valid {
+					priority: MISBEHAVIOR_REPORT_PRIORITY,
+					requires: Vec::new(),
+					provides: vec![(&b"misbehavior"[..], offence).encode()],
+					longevity: TransactionLongevity::max_value(),
+					propagate: true,
+					sender: None,
+				},
+				_ => TransactionValidity::Invalid(ApplyError::BadSignature as i8),
+			},
+			_ => TransactionValidity::Invalid(ApplyError::BadSignature as i8),
+		}
+	}
+}
+
 /// Implementing `ProvideInherent` enables this module to create and check inherents.
 impl<T: Trait> ProvideInherent for Module<T> {
 	/// The call type of the module.
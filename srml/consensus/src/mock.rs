@@ -18,11 +18,35 @@
 
 #![cfg(test)]
 
+use std::cell::RefCell;
 use primitives::{BuildStorage, traits::IdentityLookup, testing::{Digest, DigestItem, Header, UintAuthorityId}};
 use srml_support::impl_outer_origin;
 use runtime_io;
+use parity_codec::Encode;
 use substrate_primitives::{H256, Blake2Hasher};
-use crate::{GenesisConfig, Trait, Module};
+use crate::{GenesisConfig, Trait, Module, HandleMisbehaviorReport};
+
+thread_local! {
+	pub static PUNISHED: RefCell<Vec<u64>> = RefCell::new(Vec::new());
+}
+
+/// Reports `(offender, slot)`, valid for any current authority.
+pub struct TestMisbehaviorReport;
+impl HandleMisbehaviorReport for TestMisbehaviorReport {
+	type Report = (u64, u64);
+
+	fn check_report(report: &(u64, u64)) -> Result<Vec<u8>, &'static str> {
+		if Consensus::authorities().contains(&UintAuthorityId(report.0)) {
+			Ok(report.encode())
+		} else {
+			Err("not an authority")
+		}
+	}
+
+	fn handle_report(report: (u64, u64)) {
+		PUNISHED.with(|p| p.borrow_mut().push(report.0));
+	}
+}
 
 impl_outer_origin!{
 	pub enum Origin for Test {}
@@ -35,6 +59,7 @@ impl Trait for Test {
 	type Log = DigestItem;
 	type SessionKey = UintAuthorityId;
 	type InherentOfflineReport = crate::InstantFinalityReportVec<()>;
+	type MisbehaviorReport = TestMisbehaviorReport;
 }
 impl system::Trait for Test {
 	type Origin = Origin;
@@ -18,9 +18,13 @@
 
 #![cfg(test)]
 
-use primitives::{generic, testing::{self, UintAuthorityId}, traits::OnFinalize};
+use primitives::{generic, testing::{self, UintAuthorityId}, traits::{OnFinalize, ValidateUnsigned}};
+use primitives::transaction_validity::TransactionValidity;
+use parity_codec::Encode;
 use runtime_io::with_externalities;
-use crate::mock::{Consensus, System, new_test_ext};
+use srml_support::{assert_ok, assert_noop};
+use crate::mock::{Consensus, System, Origin, PUNISHED, new_test_ext};
+use crate::{Call, MISBEHAVIOR_REPORT_PRIORITY};
 use inherents::{InherentData, ProvideInherent};
 
 #[test]
@@ -129,3 +133,58 @@ fn set_and_kill_storage_work() {
 		);
 	});
 }
+
+#[test]
+fn misbehavior_is_punished_once() {
+	with_externalities(&mut new_test_ext(vec![1, 2, 3]), || {
+		assert_noop!(Consensus::report_misbehavior(Origin::signed(1), (2, 10)), "bad origin: expected to be no origin");
+		assert_noop!(Consensus::report_misbehavior(Origin::NONE, (4, 10)), "not an authority");
+
+		assert_ok!(Consensus::report_misbehavior(Origin::NONE, (2, 10)));
+		assert_noop!(Consensus::report_misbehavior(Origin::NONE, (2, 10)), "Offence has already been reported");
+		assert_ok!(Consensus::report_misbehavior(Origin::NONE, (2, 11)));
+
+		assert_eq!(PUNISHED.with(|p| p.borrow().clone()), vec![2, 2]);
+	});
+}
+
+#[test]
+fn misbehavior_reports_are_validated() {
+	with_externalities(&mut new_test_ext(vec![1, 2, 3]), || {
+		let is_valid = |report| match Consensus::validate_unsigned(&Call::report_misbehavior(report)) {
+			TransactionValidity::Valid { .. } => true,
+			_ => false,
+		};
+
+		assert!(is_valid((2, 10)));
+		assert!(!is_valid((4, 10)));
+
+		match Consensus::validate_unsigned(&Call::report_misbehavior((2, 10))) {
+			TransactionValidity::Valid { priority, provides, .. } => {
+				assert_eq!(priority, MISBEHAVIOR_REPORT_PRIORITY);
+				assert_eq!(provides, vec![(&b"misbehavior"[..], (2u64, 10u64).encode()).encode()]);
+			},
+			_ => panic!("report should be valid"),
+		}
+
+		assert_ok!(Consensus::report_misbehavior(Origin::NONE, (2, 10)));
+		assert!(!is_valid((2, 10)));
+	});
+}
+
+#[test]
+fn reported_offences_are_pruned_after_the_reporting_window() {
+	with_externalities(&mut new_test_ext(vec![1, 2, 3]), || {
+		let offence = |slot: u64| (2u64, slot).encode();
+
+		assert_ok!(Consensus::report_misbehavior(Origin::NONE, (2, 10)));
+		Consensus::note_new_session(2);
+		assert!(Consensus::is_offence_reported(&offence(10)));
+
+		assert_ok!(Consensus::report_misbehavior(Origin::NONE, (2, 11)));
+		Consensus::note_new_session(2);
+		assert!(!Consensus::is_offence_reported(&offence(10)));
+		assert!(Consensus::is_offence_reported(&offence(11)));
+		assert_eq!(Consensus::recent_offences(), vec![vec![offence(11)], vec![]]);
+	});
+}
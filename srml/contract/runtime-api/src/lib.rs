@@ -0,0 +1,59 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definition required by the contract RPC extensions.
+//!
+//! The API allows executing a contract call against the state of a block without submitting a
+//! transaction, e.g. to read the state of a contract or to estimate the gas a call needs.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+use client::decl_runtime_apis;
+use parity_codec::{Codec, Encode, Decode};
+use rstd::vec::Vec;
+
+/// Result of a contract call executed by the runtime API.
+#[derive(Clone, Default, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ContractExecResult {
+	/// The reason the call reverted, if it did. Nothing is returned and no events are emitted in
+	/// that case.
+	pub revert_reason: Option<Vec<u8>>,
+	/// The output data returned by the contract.
+	pub output: Vec<u8>,
+	/// The amount of gas consumed by the call.
+	pub gas_consumed: u64,
+	/// The encoded runtime events emitted by the call.
+	pub events: Vec<Vec<u8>>,
+}
+
+decl_runtime_apis! {
+	/// The API to dry-run contract calls.
+	pub trait ContractsApi<AccountId: Codec, Balance: Codec> {
+		/// Perform a call from a specified account to a given contract.
+		///
+		/// No gas is paid for and all changes made by the call are discarded. The gas limit is
+		/// capped at the block gas limit.
+		fn call(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: u64,
+			input_data: Vec<u8>,
+		) -> ContractExecResult;
+	}
+}
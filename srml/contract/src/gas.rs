@@ -88,7 +88,6 @@ pub struct GasMeter<T: Trait> {
 	tokens: Vec<ErasedToken>,
 }
 impl<T: Trait> GasMeter<T> {
-	#[cfg(test)]
 	pub fn with_limit(gas_limit: T::Gas, gas_price: BalanceOf<T>) -> GasMeter<T> {
 		GasMeter {
 			limit: gas_limit,
@@ -184,7 +183,7 @@ impl<T: Trait> GasMeter<T> {
 	}
 
 	/// Returns how much gas was spent.
-	fn spent(&self) -> T::Gas {
+	pub fn spent(&self) -> T::Gas {
 		self.limit - self.gas_left
 	}
 
@@ -63,6 +63,11 @@
 //! This creates a new smart contract account and calls its contract deploy handler to initialize the contract.
 //! * `call` - Makes a call to an account, optionally transferring some balance.
 //!
+//! ### Public functions
+//!
+//! * `bare_call` - Executes a call to an account without paying for gas or persisting any changes. Used
+//! by the runtime API to read the state of contracts and to estimate gas.
+//!
 //! ## Usage
 //!
 //! The Contract module is a work in progress. The following examples show how this Contract module can be
@@ -98,9 +103,9 @@ use rstd::{prelude::*, marker::PhantomData, convert::TryFrom};
 use parity_codec::{Codec, Encode, Decode};
 use runtime_io::blake2_256;
 use runtime_primitives::traits::{
-	Hash, SimpleArithmetic, Bounded, StaticLookup, Zero, MaybeSerializeDebug, Member
+	Hash, SimpleArithmetic, Bounded, StaticLookup, Zero, MaybeSerializeDebug, Member, Saturating
 };
-use srml_support::dispatch::{Result, Dispatchable};
+use srml_support::dispatch::{Result, Dispatchable, Weighable};
 use srml_support::{
 	Parameter, StorageMap, StorageValue, decl_module, decl_event, decl_storage, storage::child
 };
@@ -281,7 +286,7 @@ pub trait Trait: timestamp::Trait {
 	type Currency: Currency<Self::AccountId>;
 
 	/// The outer call dispatch type.
-	type Call: Parameter + Dispatchable<Origin=<Self as system::Trait>::Origin>;
+	type Call: Parameter + Dispatchable<Origin=<Self as system::Trait>::Origin> + Weighable;
 
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
@@ -329,14 +334,16 @@ where
 }
 
 /// The default dispatch fee computor computes the fee in the same way that
-/// the implementation of `MakePayment` for the Balances module does.
+/// the implementation of `MakePayment` for the Balances module does, i.e.
+/// proportional to the weight of the call.
 pub struct DefaultDispatchFeeComputor<T: Trait>(PhantomData<T>);
 impl<T: Trait> ComputeDispatchFee<T::Call, BalanceOf<T>> for DefaultDispatchFeeComputor<T> {
 	fn compute_dispatch_fee(call: &T::Call) -> BalanceOf<T> {
-		let encoded_len = call.using_encoded(|encoded| encoded.len() as u32);
+		let encoded_len = call.using_encoded(|encoded| encoded.len());
+		let weight = call.weight(encoded_len);
 		let base_fee = <Module<T>>::transaction_base_fee();
-		let byte_fee = <Module<T>>::transaction_byte_fee();
-		base_fee + byte_fee * encoded_len.into()
+		let weight_fee = <Module<T>>::transaction_weight_fee();
+		base_fee.saturating_add(weight_fee.saturating_mul(weight.into()))
 	}
 }
 
@@ -615,6 +622,57 @@ decl_module! {
 	}
 }
 
+/// Outcome of a call executed with `bare_call`.
+pub struct CallOutcome<T: Trait> {
+	/// The output data returned by the contract, or the reason the call reverted.
+	pub result: rstd::result::Result<Vec<u8>, &'static str>,
+	/// The amount of gas consumed by the call.
+	pub gas_consumed: T::Gas,
+	/// The events the call would have deposited. Empty if the call reverted.
+	pub events: Vec<<T as Trait>::Event>,
+}
+
+impl<T: Trait> Module<T> {
+	/// Perform a call to a specified contract.
+	///
+	/// This function is similar to `Self::call`, but doesn't perform any address lookups, doesn't
+	/// pay for the gas and doesn't persist any changes, deposit any events or dispatch any calls.
+	/// It is meant to be used by the runtime API to dry-run a call against the current state.
+	///
+	/// The gas limit is capped at the block gas limit, since no call can consume more than that.
+	pub fn bare_call(
+		origin: T::AccountId,
+		dest: T::AccountId,
+		value: BalanceOf<T>,
+		gas_limit: T::Gas,
+		input_data: Vec<u8>,
+	) -> CallOutcome<T> {
+		let gas_limit = rstd::cmp::min(gas_limit, Self::block_gas_limit());
+		let mut gas_meter = gas::GasMeter::with_limit(gas_limit, Self::gas_price());
+
+		let cfg = Config::preload();
+		let vm = crate::wasm::WasmVm::new(&cfg.schedule);
+		let loader = crate::wasm::WasmLoader::new(&cfg.schedule);
+		let mut ctx = ExecutionContext::top_level(origin, &cfg, &vm, &loader);
+
+		let result = ctx.call(dest, value, &mut gas_meter, &input_data, exec::EmptyOutputBuf::new())
+			.map(|receipt| receipt.output_data);
+		let events = if result.is_ok() {
+			ctx.events.into_iter()
+				.map(|indexed_event| <T as Trait>::Event::from(indexed_event.event))
+				.collect()
+		} else {
+			Vec::new()
+		};
+
+		CallOutcome {
+			result,
+			gas_consumed: gas_meter.spent(),
+			events,
+		}
+	}
+}
+
 decl_event! {
 	pub enum Event<T>
 	where
@@ -674,8 +732,8 @@ decl_storage! {
 		CreationFee get(creation_fee) config(): BalanceOf<T>;
 		/// The fee to be paid for making a transaction; the base.
 		TransactionBaseFee get(transaction_base_fee) config(): BalanceOf<T>;
-		/// The fee to be paid for making a transaction; the per-byte portion.
-		TransactionByteFee get(transaction_byte_fee) config(): BalanceOf<T>;
+		/// The fee to be paid for making a transaction; the per-weight-unit portion.
+		TransactionWeightFee get(transaction_weight_fee) config(): BalanceOf<T>;
 		/// The fee required to create a contract instance.
 		ContractFee get(contract_fee) config(): BalanceOf<T> = 21.into();
 		/// The base fee charged for calling into a contract.
@@ -96,6 +96,7 @@ impl consensus::Trait for Test {
 	type Log = DigestItem;
 	type SessionKey = UintAuthorityId;
 	type InherentOfflineReport = ();
+	type MisbehaviorReport = ();
 }
 impl Trait for Test {
 	type Currency = Balances;
@@ -198,7 +199,7 @@ impl ExtBuilder {
 		t.extend(
 			balances::GenesisConfig::<Test> {
 				transaction_base_fee: 0,
-				transaction_byte_fee: 0,
+				transaction_weight_fee: 0,
 				balances: vec![],
 				existential_deposit: self.existential_deposit,
 				transfer_fee: self.transfer_fee,
@@ -218,7 +219,7 @@ impl ExtBuilder {
 				surcharge_reward: 150,
 				tombstone_deposit: 16,
 				transaction_base_fee: 2,
-				transaction_byte_fee: 6,
+				transaction_weight_fee: 6,
 				transfer_fee: self.transfer_fee,
 				creation_fee: self.creation_fee,
 				contract_fee: 21,
@@ -408,6 +409,97 @@ fn instantiate_and_call_and_deposit_event() {
 	);
 }
 
+#[test]
+fn bare_call_returns_output_without_persisting_changes() {
+	let wasm = wabt::wat2wasm(CODE_RETURN_FROM_START_FN).unwrap();
+
+	with_externalities(
+		&mut ExtBuilder::default().existential_deposit(100).build(),
+		|| {
+			Balances::deposit_creating(&ALICE, 1_000_000);
+
+			assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+			assert_ok!(Contract::create(
+				Origin::signed(ALICE),
+				100,
+				100_000,
+				HASH_RETURN_FROM_START_FN.into(),
+				vec![],
+			));
+
+			let events = System::events();
+			let alice_balance = Balances::free_balance(&ALICE);
+
+			let outcome = Contract::bare_call(ALICE, BOB, 10, 100_000, vec![]);
+
+			assert_eq!(outcome.result, Ok(vec![1, 2, 3, 4]));
+			assert!(outcome.gas_consumed > 0);
+			assert_eq!(outcome.events, vec![
+				MetaEvent::contract(RawEvent::Transfer(ALICE, BOB, 10)),
+				MetaEvent::contract(RawEvent::Contract(BOB, vec![1, 2, 3, 4])),
+			]);
+
+			// Nothing is persisted and no gas is paid for.
+			assert_eq!(System::events(), events);
+			assert_eq!(Balances::free_balance(&ALICE), alice_balance);
+		},
+	);
+}
+
+#[test]
+fn bare_call_reports_revert() {
+	let wasm = wabt::wat2wasm(CODE_RETURN_FROM_START_FN).unwrap();
+
+	with_externalities(
+		&mut ExtBuilder::default().existential_deposit(100).build(),
+		|| {
+			Balances::deposit_creating(&ALICE, 1_000_000);
+
+			assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+			assert_ok!(Contract::create(
+				Origin::signed(ALICE),
+				100,
+				100_000,
+				HASH_RETURN_FROM_START_FN.into(),
+				vec![],
+			));
+
+			// Not enough gas to even pay the base call fee.
+			let outcome = Contract::bare_call(ALICE, BOB, 0, 1, vec![]);
+
+			assert_eq!(outcome.result, Err("not enough gas to pay base call fee"));
+			assert_eq!(outcome.gas_consumed, 1);
+			assert!(outcome.events.is_empty());
+		},
+	);
+}
+
+#[test]
+fn bare_call_caps_gas_limit_at_block_gas_limit() {
+	let wasm = wabt::wat2wasm(CODE_RETURN_FROM_START_FN).unwrap();
+
+	with_externalities(
+		&mut ExtBuilder::default().existential_deposit(100).block_gas_limit(100_000).build(),
+		|| {
+			Balances::deposit_creating(&ALICE, 1_000_000);
+
+			assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+			assert_ok!(Contract::create(
+				Origin::signed(ALICE),
+				100,
+				100_000,
+				HASH_RETURN_FROM_START_FN.into(),
+				vec![],
+			));
+
+			let outcome = Contract::bare_call(ALICE, BOB, 10, u64::max_value(), vec![]);
+
+			assert_eq!(outcome.result, Ok(vec![1, 2, 3, 4]));
+			assert!(outcome.gas_consumed <= 100_000);
+		},
+	);
+}
+
 const CODE_DISPATCH_CALL: &str = r#"
 (module
 	(import "env" "ext_dispatch_call" (func $ext_dispatch_call (param i32 i32)))
@@ -86,10 +86,12 @@ use srml_support::{Dispatchable, traits::MakePayment};
 use parity_codec::{Codec, Encode};
 use system::extrinsics_root;
 use primitives::{ApplyOutcome, ApplyError};
+use primitives::weights::Weighable;
 use primitives::transaction_validity::{TransactionValidity, TransactionPriority, TransactionLongevity};
 
 mod internal {
 	pub const MAX_TRANSACTIONS_SIZE: u32 = 4 * 1024 * 1024;
+	pub use primitives::weights::MAX_TRANSACTIONS_WEIGHT;
 
 	pub enum ApplyError {
 		BadSignature(&'static str),
@@ -125,7 +127,7 @@ impl<
 > ExecuteBlock<Block> for Executive<System, Block, Context, Payment, UnsignedValidator, AllModules>
 where
 	Block::Extrinsic: Checkable<Context> + Codec,
-	<Block::Extrinsic as Checkable<Context>>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId>,
+	<Block::Extrinsic as Checkable<Context>>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId> + Weighable,
 	<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call: Dispatchable,
 	<<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call as Dispatchable>::Origin: From<Option<System::AccountId>>,
 	UnsignedValidator: ValidateUnsigned<Call=<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call>
@@ -145,7 +147,7 @@ impl<
 > Executive<System, Block, Context, Payment, UnsignedValidator, AllModules>
 where
 	Block::Extrinsic: Checkable<Context> + Codec,
-	<Block::Extrinsic as Checkable<Context>>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId>,
+	<Block::Extrinsic as Checkable<Context>>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId> + Weighable,
 	<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call: Dispatchable,
 	<<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call as Dispatchable>::Origin: From<Option<System::AccountId>>,
 	UnsignedValidator: ValidateUnsigned<Call=<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call>
@@ -253,6 +255,12 @@ where
 			return Err(internal::ApplyError::FullBlock);
 		}
 
+		// Check the weight of the block if that extrinsic is applied.
+		let weight = xt.weight(encoded_len);
+		if <system::Module<System>>::all_extrinsics_weight().saturating_add(weight) > internal::MAX_TRANSACTIONS_WEIGHT {
+			return Err(internal::ApplyError::FullBlock);
+		}
+
 		if let (Some(sender), Some(index)) = (xt.sender(), xt.index()) {
 			// check index
 			let expected_index = <system::Module<System>>::account_nonce(sender);
@@ -261,7 +269,7 @@ where
 			) }
 
 			// pay any fees
-			Payment::make_payment(sender, encoded_len).map_err(|_| internal::ApplyError::CantPay)?;
+			Payment::make_payment(sender, weight).map_err(|_| internal::ApplyError::CantPay)?;
 
 			// AUDIT: Under no circumstances may this function panic from here onwards.
 			// FIXME: ensure this at compile-time (such as by not defining a panic function, forcing
@@ -279,7 +287,7 @@ where
 		// Decode parameters and dispatch
 		let (f, s) = xt.deconstruct();
 		let r = f.dispatch(s.into());
-		<system::Module<System>>::note_applied_extrinsic(&r, encoded_len as u32);
+		<system::Module<System>>::note_applied_extrinsic(&r, encoded_len as u32, weight);
 
 		r.map(|_| internal::ApplyOutcome::Success).or_else(|e| match e {
 			primitives::BLOCK_FULL => Err(internal::ApplyError::FullBlock),
@@ -318,6 +326,7 @@ where
 		const UNKNOWN_ERROR: i8 = -127;
 		const MISSING_SENDER: i8 = -20;
 		const INVALID_INDEX: i8 = -10;
+		const EXCEEDS_BLOCK_WEIGHT: i8 = -30;
 
 		let encoded_len = uxt.encode().len();
 
@@ -332,10 +341,16 @@ where
 			Err(_) => return TransactionValidity::Invalid(UNKNOWN_ERROR),
 		};
 
+		// a transaction heavier than a whole block can never be included.
+		let weight = xt.weight(encoded_len);
+		if weight > internal::MAX_TRANSACTIONS_WEIGHT {
+			return TransactionValidity::Invalid(EXCEEDS_BLOCK_WEIGHT)
+		}
+
 		match (xt.sender(), xt.index()) {
 			(Some(sender), Some(index)) => {
 				// pay any fees
-				if Payment::make_payment(sender, encoded_len).is_err() {
+				if Payment::make_payment(sender, weight).is_err() {
 					return TransactionValidity::Invalid(ApplyError::CantPay as i8)
 				}
 
@@ -347,6 +362,7 @@ where
 
 				let index = *index;
 				let provides = vec![(sender, index).encode()];
+				let encoded_sender = sender.encode();
 				let requires = if expected_index < index {
 					vec![(sender, index - One::one()).encode()]
 				} else {
@@ -359,6 +375,7 @@ where
 					provides,
 					longevity: TransactionLongevity::max_value(),
 					propagate: true,
+					sender: Some(encoded_sender),
 				}
 			},
 			(None, None) => UnsignedValidator::validate_unsigned(&xt.deconstruct().0),
@@ -434,6 +451,7 @@ mod tests {
 					provides: vec![],
 					longevity: std::u64::MAX,
 					propagate: false,
+					sender: None,
 				},
 				_ => TransactionValidity::Invalid(0),
 			}
@@ -448,7 +466,7 @@ mod tests {
 		let mut t = system::GenesisConfig::<Runtime>::default().build_storage().unwrap().0;
 		t.extend(balances::GenesisConfig::<Runtime> {
 			transaction_base_fee: 10,
-			transaction_byte_fee: 0,
+			transaction_weight_fee: 0,
 			balances: vec![(1, 111)],
 			existential_deposit: 0,
 			transfer_fee: 0,
@@ -551,10 +569,43 @@ mod tests {
 				if should_fail {
 					assert!(res.is_err());
 					assert_eq!(<system::Module<Runtime>>::all_extrinsics_len(), 28);
+					assert_eq!(<system::Module<Runtime>>::all_extrinsics_weight(), 28);
 					assert_eq!(<system::Module<Runtime>>::extrinsic_index(), Some(1));
 				} else {
 					assert!(res.is_ok());
 					assert_eq!(<system::Module<Runtime>>::all_extrinsics_len(), 56);
+					assert_eq!(<system::Module<Runtime>>::all_extrinsics_weight(), 56);
+					assert_eq!(<system::Module<Runtime>>::extrinsic_index(), Some(2));
+				}
+			});
+		};
+
+		run_test(false);
+		run_test(true);
+	}
+
+	#[test]
+	fn block_weight_limit_enforced() {
+		let run_test = |should_fail: bool| {
+			let mut t = new_test_ext();
+			let xt = primitives::testing::TestXt(Some(1), 0, Call::transfer(33, 69));
+			let weight = xt.weight(xt.encode().len());
+			let used = internal::MAX_TRANSACTIONS_WEIGHT - weight + if should_fail { 1 } else { 0 };
+			with_externalities(&mut t, || {
+				Executive::initialize_block(&Header::new(1, H256::default(), H256::default(), [69u8; 32].into(), Digest::default()));
+				// use up the weight of the block without using up its length.
+				<system::Module<Runtime>>::note_applied_extrinsic(&Ok(()), 0, used);
+
+				let res = Executive::apply_extrinsic(xt);
+
+				assert_eq!(<system::Module<Runtime>>::all_extrinsics_len(), if should_fail { 0 } else { 28 });
+				if should_fail {
+					assert_eq!(res, Err(ApplyError::FullBlock));
+					assert_eq!(<system::Module<Runtime>>::all_extrinsics_weight(), used);
+					assert_eq!(<system::Module<Runtime>>::extrinsic_index(), Some(1));
+				} else {
+					assert!(res.is_ok());
+					assert_eq!(<system::Module<Runtime>>::all_extrinsics_weight(), internal::MAX_TRANSACTIONS_WEIGHT);
 					assert_eq!(<system::Module<Runtime>>::extrinsic_index(), Some(2));
 				}
 			});
@@ -573,6 +624,7 @@ mod tests {
 			provides: vec![],
 			longevity: 18446744073709551615,
 			propagate: false,
+			sender: None,
 		};
 		let mut t = new_test_ext();
 
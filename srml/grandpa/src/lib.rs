@@ -19,8 +19,12 @@
 //! This manages the GRANDPA authority set ready for the native code.
 //! These authorities are only for GRANDPA finality, not for consensus overall.
 //!
-//! In the future, it will also handle misbehavior reports, and on-chain
-//! finality notifications.
+//! It also handles reports of equivocating authorities, i.e. authorities that
+//! cast two different prevotes or precommits in the same round. The reports are
+//! unsigned extrinsics which are checked against the current authority set and
+//! passed on to `Trait::OnEquivocation` (e.g. the `StakingEquivocationSlasher`).
+//!
+//! In the future, it will also handle on-chain finality notifications.
 //!
 //! For full integration with GRANDPA, the `GrandpaApi` should be implemented.
 //! The necessary items are re-exported via the `fg_primitives` crate.
@@ -32,17 +36,21 @@ pub use substrate_finality_grandpa_primitives as fg_primitives;
 
 #[cfg(feature = "std")]
 use serde::Serialize;
-use rstd::prelude::*;
+use rstd::{prelude::*, result};
 use parity_codec as codec;
 use codec::{Encode, Decode};
-use fg_primitives::ScheduledChange;
-use srml_support::{Parameter, decl_event, decl_storage, decl_module};
+use fg_primitives::{ScheduledChange, EquivocationProof};
+use srml_support::{Parameter, decl_event, decl_storage, decl_module, ensure};
 use srml_support::dispatch::Result;
-use srml_support::storage::StorageValue;
+use srml_support::storage::{StorageValue, StorageMap};
 use srml_support::storage::unhashed::StorageVec;
-use primitives::traits::CurrentHeight;
+use primitives::ApplyError;
+use primitives::traits::{CurrentHeight, ValidateUnsigned, Verify};
+use primitives::transaction_validity::{
+	TransactionValidity, TransactionPriority, TransactionLongevity,
+};
 use substrate_primitives::ed25519;
-use system::ensure_signed;
+use system::ensure_none;
 use primitives::traits::MaybeSerializeDebug;
 use ed25519::Public as AuthorityId;
 
@@ -124,6 +132,24 @@ impl<N, SessionKey> GrandpaChangeSignal<N> for RawLog<N, SessionKey>
 	}
 }
 
+/// Handler for GRANDPA authorities that have been proven to equivocate.
+pub trait OnEquivocation<SessionKey> {
+	/// Punish the given authority. This is called at most once per offence.
+	fn on_equivocation(offender: &SessionKey);
+}
+
+impl<SessionKey> OnEquivocation<SessionKey> for () {
+	fn on_equivocation(_: &SessionKey) {}
+}
+
+/// The equivocation proof type accepted by the module.
+pub type EquivocationProofFor<T> = EquivocationProof<
+	<T as system::Trait>::Hash,
+	<T as system::Trait>::BlockNumber,
+	<T as Trait>::SessionKey,
+	<T as Trait>::Signature,
+>;
+
 pub trait Trait: system::Trait {
 	/// Type for all log entries of this module.
 	type Log: From<Log<Self>> + Into<system::DigestItemOf<Self>>;
@@ -131,6 +157,12 @@ pub trait Trait: system::Trait {
 	/// The session key type used by authorities.
 	type SessionKey: Parameter + Default + MaybeSerializeDebug;
 
+	/// The signature type of GRANDPA votes.
+	type Signature: Verify<Signer = Self::SessionKey> + Parameter;
+
+	/// Handler for authorities that have equivocated.
+	type OnEquivocation: OnEquivocation<Self::SessionKey>;
+
 	/// The event type of this module.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
@@ -180,6 +212,8 @@ decl_event!(
 	pub enum Event<T> where <T as Trait>::SessionKey {
 		/// New authority set has been applied.
 		NewAuthorities(Vec<(SessionKey, u64)>),
+		/// An authority has been reported for equivocating.
+		Equivocation(SessionKey),
 	}
 );
 
@@ -189,6 +223,11 @@ decl_storage! {
 		PendingChange get(pending_change): Option<StoredPendingChange<T::BlockNumber, T::SessionKey>>;
 		// next block number where we can force a change.
 		NextForced get(next_forced): Option<T::BlockNumber>;
+		// The id of the current authority set, incremented on every applied change.
+		// Unknown on chains that started before it was tracked, until set by `set_current_set_id`.
+		CurrentSetId get(current_set_id) build(|_| 0u64): Option<u64>;
+		// Offences that have already been reported, to avoid punishing twice.
+		ReportedOffences get(is_offence_reported): map Vec<u8> => bool;
 	}
 	add_extra_genesis {
 		config(authorities): Vec<(T::SessionKey, u64)>;
@@ -215,10 +254,27 @@ decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event<T>() = default;
 
-		/// Report some misbehavior.
-		fn report_misbehavior(origin, _report: Vec<u8>) {
-			ensure_signed(origin)?;
-			// FIXME: https://github.com/paritytech/substrate/issues/1112
+		/// Report an authority for equivocating in the current authority set.
+		///
+		/// The origin must be unsigned. Each offence is only punished once.
+		fn report_misbehavior(origin, report: EquivocationProofFor<T>) {
+			ensure_none(origin)?;
+
+			let offence = Self::check_equivocation_proof(&report)?;
+			ensure!(!Self::is_offence_reported(&offence), "Offence has already been reported");
+
+			<ReportedOffences<T>>::insert(offence, true);
+			T::OnEquivocation::on_equivocation(&report.offender);
+			Self::deposit_event(RawEvent::Equivocation(report.offender));
+		}
+
+		/// Set the id of the current authority set on chains that started before
+		/// it was tracked. Equivocation reports are rejected until it is known.
+		///
+		/// The dispatch origin for this call is `root`.
+		fn set_current_set_id(#[compact] set_id: u64) {
+			ensure!(Self::current_set_id().is_none(), "The current set id is already known");
+			<CurrentSetId<T>>::put(set_id);
 		}
 
 		fn on_finalize(block_number: T::BlockNumber) {
@@ -244,6 +300,9 @@ decl_module! {
 					);
 					<AuthorityStorageVec<T::SessionKey>>::set_items(pending_change.next_authorities);
 					<PendingChange<T>>::kill();
+					<CurrentSetId<T>>::mutate(|set_id| if let Some(set_id) = set_id.as_mut() {
+						*set_id += 1;
+					});
 				}
 			}
 		}
@@ -301,12 +360,51 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Check an equivocation proof against the current authority set.
+	///
+	/// Returns the encoded offence, which identifies the offender and the
+	/// round they equivocated in.
+	pub fn check_equivocation_proof(proof: &EquivocationProofFor<T>) -> result::Result<Vec<u8>, &'static str> {
+		let current_set_id = Self::current_set_id().ok_or("The current authority set id is unknown")?;
+		ensure!(proof.set_id == current_set_id, "Equivocation proof is for a different authority set");
+		ensure!(
+			Self::grandpa_authorities().iter().any(|(id, _)| *id == proof.offender),
+			"Offender is not a GRANDPA authority"
+		);
+		ensure!(proof.check(), "Invalid equivocation proof");
+
+		Ok((proof.set_id, proof.round, &proof.offender).encode())
+	}
+
 	/// Deposit one of this module's logs.
 	fn deposit_log(log: Log<T>) {
 		<system::Module<T>>::deposit_log(<T as Trait>::Log::from(log).into());
 	}
 }
 
+/// Equivocation reports are unsigned extrinsics, their validity is established
+/// by checking the proof itself.
+impl<T: Trait> ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		match call {
+			Call::report_misbehavior(report) => match Self::check_equivocation_proof(report) {
+				Ok(ref offence) if !Self::is_offence_reported(offence) => TransactionValidity::Valid {
+					priority: TransactionPriority::max_value(),
+					requires: Vec::new(),
+					provides: vec![offence.clone()],
+					longevity: TransactionLongevity::max_value(),
+					propagate: true,
+					sender: None,
+				},
+				_ => TransactionValidity::Invalid(ApplyError::BadSignature as i8),
+			},
+			_ => TransactionValidity::Invalid(ApplyError::BadSignature as i8),
+		}
+	}
+}
+
 impl<T: Trait> Module<T> where AuthorityId: core::convert::From<<T as Trait>::SessionKey> {
 	/// See if the digest contains any standard scheduled change.
 	pub fn scrape_digest_change(log: &Log<T>)
@@ -378,3 +476,18 @@ impl<T> finality_tracker::OnFinalizationStalled<T::BlockNumber> for SyncedAuthor
 		let _ = <Module<T>>::schedule_change(next_authorities, further_wait, Some(median));
 	}
 }
+
+/// A type for slashing equivocating GRANDPA authorities via the staking module.
+///
+/// The offender's session key is mapped to its validator by the session module.
+pub struct StakingEquivocationSlasher<T>(::rstd::marker::PhantomData<T>);
+
+impl<T> OnEquivocation<<T as Trait>::SessionKey> for StakingEquivocationSlasher<T> where
+	T: Trait + staking::Trait + consensus::Trait<SessionKey=<T as Trait>::SessionKey>,
+{
+	fn on_equivocation(offender: &<T as Trait>::SessionKey) {
+		if let Some(v) = <session::Module<T>>::key_owner(offender) {
+			<staking::Module<T>>::on_equivocating_validator(v);
+		}
+	}
+}
@@ -18,13 +18,14 @@
 
 #![cfg(test)]
 
-use primitives::{BuildStorage, traits::IdentityLookup, testing::{Digest, DigestItem, Header}};
+use std::cell::RefCell;
+use primitives::{BuildStorage, traits::{IdentityLookup, Lazy, Verify}, testing::{Digest, DigestItem, Header}};
 use primitives::generic::DigestItem as GenDigestItem;
 use runtime_io;
 use srml_support::{impl_outer_origin, impl_outer_event};
 use substrate_primitives::{H256, Blake2Hasher};
 use parity_codec::{Encode, Decode};
-use crate::{GenesisConfig, Trait, Module, RawLog};
+use crate::{GenesisConfig, Trait, Module, RawLog, OnEquivocation};
 
 impl_outer_origin!{
 	pub enum Origin for Test {}
@@ -36,12 +37,37 @@ impl From<RawLog<u64, u64>> for DigestItem {
 	}
 }
 
+/// A signature of the given authority over the given message.
+#[derive(Clone, PartialEq, Eq, Debug, Decode, Encode)]
+pub struct TestSignature(pub u64, pub Vec<u8>);
+
+impl Verify for TestSignature {
+	type Signer = u64;
+
+	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &u64) -> bool {
+		*signer == self.0 && msg.get() == &self.1[..]
+	}
+}
+
+thread_local! {
+	pub static EQUIVOCATORS: RefCell<Vec<u64>> = RefCell::new(Vec::new());
+}
+
+pub struct TestOnEquivocation;
+impl OnEquivocation<u64> for TestOnEquivocation {
+	fn on_equivocation(offender: &u64) {
+		EQUIVOCATORS.with(|e| e.borrow_mut().push(*offender));
+	}
+}
+
 // Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
 #[derive(Clone, PartialEq, Eq, Debug, Decode, Encode)]
 pub struct Test;
 impl Trait for Test {
 	type Log = DigestItem;
 	type SessionKey = u64;
+	type Signature = TestSignature;
+	type OnEquivocation = TestOnEquivocation;
 	type Event = TestEvent;
 }
 impl system::Trait for Test {
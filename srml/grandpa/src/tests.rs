@@ -21,7 +21,9 @@
 use primitives::{testing, traits::OnFinalize};
 use primitives::traits::Header;
 use runtime_io::with_externalities;
-use crate::mock::{Grandpa, System, new_test_ext};
+use crate::mock::{Grandpa, System, Origin, Test, TestSignature, EQUIVOCATORS, new_test_ext};
+use fg_primitives::EquivocatedVote;
+use substrate_primitives::H256;
 use system::{EventRecord, Phase};
 use crate::{RawLog, RawEvent};
 use codec::{Decode, Encode};
@@ -42,6 +44,7 @@ fn authorities_change_logged() {
 				RawLog::AuthoritiesChangeSignal(0, vec![(4, 1), (5, 1), (6, 1)]).into(),
 			],
 		});
+		assert_eq!(Grandpa::current_set_id(), Some(1));
 
 		assert_eq!(System::events(), vec![
 			EventRecord {
@@ -198,3 +201,119 @@ fn dispatch_forced_change() {
 		let _ = header;
 	});
 }
+
+fn signed_vote(authority: u64, vote: EquivocatedVote<H256, u64>, round: u64, set_id: u64)
+	-> (EquivocatedVote<H256, u64>, TestSignature)
+{
+	let signature = TestSignature(authority, (&vote, round, set_id).encode());
+	(vote, signature)
+}
+
+fn equivocation_proof(offender: u64, round: u64, set_id: u64) -> EquivocationProofFor<Test> {
+	EquivocationProof {
+		set_id,
+		round,
+		offender,
+		first: signed_vote(offender, EquivocatedVote::Prevote(H256::repeat_byte(1), 1), round, set_id),
+		second: signed_vote(offender, EquivocatedVote::Prevote(H256::repeat_byte(2), 1), round, set_id),
+	}
+}
+
+#[test]
+fn equivocation_proofs_are_checked() {
+	with_externalities(&mut new_test_ext(vec![(1, 1), (2, 1), (3, 1)]), || {
+		assert!(Grandpa::check_equivocation_proof(&equivocation_proof(1, 1, 0)).is_ok());
+
+		assert_eq!(
+			Grandpa::check_equivocation_proof(&equivocation_proof(1, 1, 1)),
+			Err("Equivocation proof is for a different authority set"),
+		);
+		assert_eq!(
+			Grandpa::check_equivocation_proof(&equivocation_proof(4, 1, 0)),
+			Err("Offender is not a GRANDPA authority"),
+		);
+
+		let mut proof = equivocation_proof(1, 1, 0);
+		proof.second = proof.first.clone();
+		assert_eq!(Grandpa::check_equivocation_proof(&proof), Err("Invalid equivocation proof"));
+
+		let mut proof = equivocation_proof(1, 1, 0);
+		let payload = (&proof.second.0, 1u64, 0u64).encode();
+		proof.second.1 = TestSignature(2, payload);
+		assert_eq!(Grandpa::check_equivocation_proof(&proof), Err("Invalid equivocation proof"));
+
+		// unsigned transaction validation
+		assert!(match Grandpa::validate_unsigned(&Call::report_misbehavior(equivocation_proof(1, 1, 0))) {
+			TransactionValidity::Valid { .. } => true,
+			_ => false,
+		});
+		assert!(match Grandpa::validate_unsigned(&Call::report_misbehavior(equivocation_proof(4, 1, 0))) {
+			TransactionValidity::Invalid(_) => true,
+			_ => false,
+		});
+	});
+}
+
+#[test]
+fn equivocation_is_punished_once() {
+	with_externalities(&mut new_test_ext(vec![(1, 1), (2, 1), (3, 1)]), || {
+		System::initialize(&1, &Default::default(), &Default::default(), &Default::default());
+
+		assert_eq!(
+			Grandpa::report_misbehavior(Origin::signed(2), equivocation_proof(1, 1, 0)),
+			Err("bad origin: expected to be no origin"),
+		);
+
+		assert_eq!(Grandpa::report_misbehavior(Origin::NONE, equivocation_proof(1, 1, 0)), Ok(()));
+		assert_eq!(
+			Grandpa::report_misbehavior(Origin::NONE, equivocation_proof(1, 1, 0)),
+			Err("Offence has already been reported"),
+		);
+		assert!(match Grandpa::validate_unsigned(&Call::report_misbehavior(equivocation_proof(1, 1, 0))) {
+			TransactionValidity::Invalid(_) => true,
+			_ => false,
+		});
+
+		// a different round is a different offence.
+		assert_eq!(Grandpa::report_misbehavior(Origin::NONE, equivocation_proof(1, 2, 0)), Ok(()));
+
+		assert_eq!(EQUIVOCATORS.with(|e| e.borrow().clone()), vec![1, 1]);
+		assert_eq!(System::events(), vec![
+			EventRecord {
+				phase: Phase::ApplyExtrinsic(0),
+				event: RawEvent::Equivocation(1).into(),
+				topics: vec![],
+			},
+			EventRecord {
+				phase: Phase::ApplyExtrinsic(0),
+				event: RawEvent::Equivocation(1).into(),
+				topics: vec![],
+			},
+		]);
+	});
+}
+
+#[test]
+fn current_set_id_can_be_set_once_when_unknown() {
+	with_externalities(&mut new_test_ext(vec![(1, 1), (2, 1), (3, 1)]), || {
+		assert_eq!(Grandpa::current_set_id(), Some(0));
+		assert_eq!(Grandpa::set_current_set_id(5), Err("The current set id is already known"));
+
+		// a chain that started before the set id was tracked.
+		<CurrentSetId<Test>>::kill();
+		assert_eq!(
+			Grandpa::check_equivocation_proof(&equivocation_proof(1, 1, 0)),
+			Err("The current authority set id is unknown"),
+		);
+
+		// applied changes don't make up a set id.
+		System::initialize(&1, &Default::default(), &Default::default(), &Default::default());
+		Grandpa::schedule_change(vec![(1, 1), (2, 1)], 0, None).unwrap();
+		Grandpa::on_finalize(1);
+		assert_eq!(Grandpa::current_set_id(), None);
+
+		assert_eq!(Grandpa::set_current_set_id(5), Ok(()));
+		assert_eq!(Grandpa::current_set_id(), Some(5));
+		assert!(Grandpa::check_equivocation_proof(&equivocation_proof(1, 1, 5)).is_ok());
+	});
+}
@@ -122,6 +122,11 @@ use srml_support::{dispatch::Result, traits::OnFreeBalanceZero};
 use system::ensure_signed;
 use rstd::ops::Mul;
 
+/// Number of sessions, counting the current one, during which misbehavior of their validators
+/// can still be reported: the owners of their session keys and the reported offences are kept
+/// for that long.
+pub const REPORTING_WINDOW: u32 = 3;
+
 /// A session has changed.
 pub trait OnSessionChange<T> {
 	/// Session has changed.
@@ -218,12 +223,31 @@ decl_storage! {
 		}): map T::AccountId => Option<T::SessionKey>;
 		/// The next session length.
 		NextSessionLength: Option<T::BlockNumber>;
+		/// The validator owning a session key of one of the sessions in the reporting window.
+		pub KeyOwner get(key_owner) build(|config: &GenesisConfig<T>| {
+			genesis_key_owners(config)
+		}): map T::SessionKey => Option<T::AccountId>;
+		/// The session keys of each session in the reporting window, oldest first.
+		SessionKeys build(|config: &GenesisConfig<T>| {
+			vec![genesis_key_owners(config).into_iter().map(|(key, _)| key).collect::<Vec<_>>()]
+		}): Vec<Vec<T::SessionKey>>;
 	}
 	add_extra_genesis {
 		config(keys): Vec<(T::AccountId, T::SessionKey)>;
 	}
 }
 
+#[cfg(feature = "std")]
+fn genesis_key_owners<T: Trait>(config: &GenesisConfig<T>) -> Vec<(T::SessionKey, T::AccountId)> {
+	config.validators.iter().filter_map(|v| {
+		config.keys.iter()
+			.find(|(who, _)| who == v)
+			.map(|(_, key)| key.clone())
+			.or_else(|| T::ConvertAccountIdToSessionKey::convert(v.clone()))
+			.map(|key| (key, v.clone()))
+	}).collect()
+}
+
 impl<T: Trait> Module<T> {
 	/// The current number of validators.
 	pub fn validator_count() -> u32 {
@@ -292,14 +316,35 @@ impl<T: Trait> Module<T> {
 		// Update any changes in session keys.
 		let v = Self::validators();
 		<consensus::Module<T>>::set_authority_count(v.len() as u32);
+		let mut keys = Vec::with_capacity(v.len());
 		for (i, v) in v.into_iter().enumerate() {
-			<consensus::Module<T>>::set_authority(
-				i as u32,
-				&<NextKeyFor<T>>::get(&v)
-					.or_else(|| T::ConvertAccountIdToSessionKey::convert(v))
-					.unwrap_or_default()
-			);
+			let key = <NextKeyFor<T>>::get(&v)
+				.or_else(|| T::ConvertAccountIdToSessionKey::convert(v.clone()))
+				.unwrap_or_default();
+			<consensus::Module<T>>::set_authority(i as u32, &key);
+			<KeyOwner<T>>::insert(&key, v);
+			keys.push(key);
 		};
+
+		Self::note_session_keys(keys);
+		<consensus::Module<T>>::note_new_session(REPORTING_WINDOW);
+	}
+
+	/// Note the keys of a new session, forgetting the owners of the keys which weren't used
+	/// by any session of the reporting window.
+	fn note_session_keys(keys: Vec<T::SessionKey>) {
+		let mut sessions = <SessionKeys<T>>::get();
+		sessions.push(keys);
+
+		let expired = sessions.len().saturating_sub(REPORTING_WINDOW as usize);
+		let expired = sessions.drain(..expired).flatten().collect::<Vec<_>>();
+		for key in expired {
+			if !sessions.iter().any(|keys| keys.contains(&key)) {
+				<KeyOwner<T>>::remove(&key);
+			}
+		}
+
+		<SessionKeys<T>>::put(sessions);
 	}
 
 	/// Get the time that should elapse over a session if everything is working perfectly.
@@ -358,6 +403,7 @@ mod tests {
 		type Log = DigestItem;
 		type SessionKey = UintAuthorityId;
 		type InherentOfflineReport = ();
+		type MisbehaviorReport = ();
 	}
 	impl system::Trait for Test {
 		type Origin = Origin;
@@ -409,6 +455,7 @@ mod tests {
 			assert_eq!(Consensus::authorities(), vec![UintAuthorityId(1), UintAuthorityId(2), UintAuthorityId(3)]);
 			assert_eq!(Session::length(), 2);
 			assert_eq!(Session::validators(), vec![1, 2, 3]);
+			assert_eq!(Session::key_owner(UintAuthorityId(1)), Some(1));
 		});
 	}
 
@@ -420,12 +467,16 @@ mod tests {
 			Session::check_rotate_session(1);
 			assert_eq!(Session::validators(), vec![1, 2]);
 			assert_eq!(Consensus::authorities(), vec![UintAuthorityId(1), UintAuthorityId(2)]);
+			assert_eq!(Session::key_owner(UintAuthorityId(2)), Some(2));
+			// the key of validator 3 is still known within the reporting window.
+			assert_eq!(Session::key_owner(UintAuthorityId(3)), Some(3));
 
 			NEXT_VALIDATORS.with(|v| *v.borrow_mut() = vec![1, 2, 4]);
 			assert_ok!(Session::force_new_session(false));
 			Session::check_rotate_session(2);
 			assert_eq!(Session::validators(), vec![1, 2, 4]);
 			assert_eq!(Consensus::authorities(), vec![UintAuthorityId(1), UintAuthorityId(2), UintAuthorityId(4)]);
+			assert_eq!(Session::key_owner(UintAuthorityId(4)), Some(4));
 
 			NEXT_VALIDATORS.with(|v| *v.borrow_mut() = vec![1, 2, 3]);
 			assert_ok!(Session::force_new_session(false));
@@ -544,6 +595,29 @@ mod tests {
 			System::set_block_number(4);
 			Session::check_rotate_session(4);
 			assert_eq!(Consensus::authorities(), vec![UintAuthorityId(1), UintAuthorityId(5), UintAuthorityId(3)]);
+			assert_eq!(Session::key_owner(UintAuthorityId(5)), Some(2));
+			assert_eq!(Session::key_owner(UintAuthorityId(2)), Some(2));
+		});
+	}
+
+	#[test]
+	fn key_owners_are_kept_for_the_reporting_window() {
+		with_externalities(&mut new_test_ext(), || {
+			NEXT_VALIDATORS.with(|v| *v.borrow_mut() = vec![1, 2]);
+			for i in 1..u64::from(REPORTING_WINDOW) {
+				System::set_block_number(i);
+				assert_ok!(Session::force_new_session(false));
+				Session::check_rotate_session(i);
+				assert_eq!(Session::key_owner(UintAuthorityId(3)), Some(3));
+			}
+
+			let i = u64::from(REPORTING_WINDOW);
+			System::set_block_number(i);
+			assert_ok!(Session::force_new_session(false));
+			Session::check_rotate_session(i);
+			assert_eq!(Session::key_owner(UintAuthorityId(3)), None);
+			assert_eq!(Session::key_owner(UintAuthorityId(1)), Some(1));
+			assert_eq!(Session::key_owner(UintAuthorityId(2)), Some(2));
 		});
 	}
 }
@@ -204,6 +204,11 @@
 //! capped at their total stake (NOTE: This cap should never come into force in a correctly implemented,
 //! non-corrupted, well-configured system).
 //!
+//! A validator that is proven to have equivocated (e.g. authored two different blocks for the same slot)
+//! is reported via [`on_equivocating_validator`](./struct.Module.html#method.on_equivocating_validator).
+//! There is no grace period for equivocations: the validator is immediately slashed by
+//! [`EquivocationSlash`](./struct.Module.html#method.equivocation_slash) of its `total` `Exposure` and unstaked.
+//!
 //! ### Additional Fund Management Operations
 //!
 //! Any funds already placed into stash can be the target of the following operations:
@@ -448,6 +453,8 @@ decl_storage! {
 		pub OfflineSlash get(offline_slash) config(): Perbill = Perbill::from_millionths(1000);
 		/// Number of instances of offline reports before slashing begins for validators.
 		pub OfflineSlashGrace get(offline_slash_grace) config(): u32;
+		/// Slash, per validator that is taken when they are found to be equivocating.
+		pub EquivocationSlash get(equivocation_slash): Perbill = Perbill::from_percent(10);
 		/// The length of the bonding duration in eras.
 		pub BondingDuration get(bonding_duration) config(): T::BlockNumber = 12.into();
 
@@ -759,6 +766,11 @@ decl_module! {
 			<OfflineSlashGrace<T>>::put(new);
 		}
 
+		/// Set the slash taken from equivocating validators.
+		fn set_equivocation_slash(new: Perbill) {
+			<EquivocationSlash<T>>::put(new);
+		}
+
 		/// Set the validators who cannot be slashed (if any).
 		fn set_invulnerables(validators: Vec<T::AccountId>) {
 			<Invulnerables<T>>::put(validators);
@@ -775,6 +787,8 @@ decl_event!(
 		OfflineWarning(AccountId, u32),
 		/// One validator (and its nominators) has been slashed by the given amount.
 		OfflineSlash(AccountId, Balance),
+		/// One validator (and its nominators) has been slashed by the given amount for equivocating.
+		EquivocationSlash(AccountId, Balance),
 	}
 );
 
@@ -1114,6 +1128,28 @@ impl<T: Trait> Module<T> {
 			Self::deposit_event(event);
 		}
 	}
+
+	/// Call when a validator is proven to have equivocated. The validator is
+	/// slashed and removed from the validator candidates.
+	///
+	/// NOTE: This is called with the controller (not the stash) account id.
+	pub fn on_equivocating_validator(controller: T::AccountId) {
+		if let Some(l) = Self::ledger(&controller) {
+			let stash = l.stash;
+
+			// Early exit if validator is invulnerable.
+			if Self::invulnerables().contains(&stash) {
+				return
+			}
+
+			let slash = Self::equivocation_slash() * Self::stakers(&stash).total;
+			Self::slash_validator(&stash, slash);
+			<Validators<T>>::remove(&stash);
+			let _ = Self::apply_force_new_era(false);
+
+			Self::deposit_event(RawEvent::EquivocationSlash(stash, slash));
+		}
+	}
 }
 
 impl<T: Trait> OnSessionChange<T::Moment> for Module<T> {
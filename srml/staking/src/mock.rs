@@ -50,6 +50,7 @@ impl consensus::Trait for Test {
 	type Log = DigestItem;
 	type SessionKey = UintAuthorityId;
 	type InherentOfflineReport = ();
+	type MisbehaviorReport = ();
 }
 impl system::Trait for Test {
 	type Origin = Origin;
@@ -193,7 +194,7 @@ impl ExtBuilder {
 					(101, 2000 * balance_factor),
 			],
 			transaction_base_fee: 0,
-			transaction_byte_fee: 0,
+			transaction_weight_fee: 0,
 			existential_deposit: self.existential_deposit,
 			transfer_fee: 0,
 			creation_fee: 0,
@@ -221,6 +221,30 @@ fn offline_should_slash_and_kick() {
 	});
 }
 
+#[test]
+fn equivocation_should_slash_and_kick_immediately() {
+	// Test that an equivocating validator gets slashed and kicked without any grace
+	with_externalities(&mut ExtBuilder::default().build(), || {
+		let _ = Balances::make_free_balance_be(&11, 1000);
+		assert!(<Validators<Test>>::exists(&11));
+		// Set a grace period, which should not apply to equivocations
+		assert_ok!(Staking::set_offline_slash_grace(7));
+
+		Staking::on_equivocating_validator(10);
+
+		// Confirm balance has been reduced by equivocation_slash() * amount_at_stake.
+		let slash = Staking::equivocation_slash() * Staking::stakers(11).total;
+		assert!(slash > 0);
+		assert_eq!(Balances::free_balance(&11), 1000 - slash);
+		// Equivocations do not count towards offline reports
+		assert_eq!(Staking::slash_count(&11), 0);
+		// Confirm account 10 has been removed as a validator
+		assert!(!<Validators<Test>>::exists(&11));
+		// A new era is forced due to slashing
+		assert!(Staking::forcing_new_era().is_some());
+	});
+}
+
 #[test]
 fn offline_grace_should_delay_slashing() {
 	// Tests that with grace, slashing is delayed
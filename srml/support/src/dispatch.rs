@@ -23,6 +23,7 @@ pub use std::fmt;
 pub use crate::rstd::result;
 pub use crate::codec::{Codec, Decode, Encode, Input, Output, HasCompact, EncodeAsRef};
 pub use srml_metadata::{FunctionMetadata, DecodeDifferent, DecodeDifferentArray, FunctionArgumentMetadata};
+pub use crate::runtime_primitives::weights::{Weighable, Weight, TransactionWeight};
 
 /// A type that cannot be instantiated.
 pub enum Never {}
@@ -155,6 +156,33 @@ impl<T> Parameter for T where T: Codec + Clone + Eq {}
 /// # fn main() {}
 /// ```
 ///
+/// ### Weight Example
+///
+/// Every function has a weight, which determines the fee paid for calling it and how much of the
+/// block's capacity the call uses up. It is declared with the `weight` attribute, taking a
+/// [`TransactionWeight`](../sr_primitives/weights/enum.TransactionWeight.html), which may come
+/// before or after the function's doc comments. Functions without the attribute weigh the length
+/// of the extrinsic in bytes.
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate srml_support;
+/// # use srml_support::dispatch::{Result, TransactionWeight};
+/// # use srml_system::{self as system, Trait, ensure_signed};
+/// decl_module! {
+/// 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+///
+/// 		/// A function that does a lot of work.
+/// 		#[weight = TransactionWeight::Basic(10_000, 10)]
+/// 		fn my_heavy_function(origin) -> Result {
+///				// Your implementation
+/// 			Ok(())
+/// 		}
+///		}
+/// }
+/// # fn main() {}
+/// ```
+///
 /// ## Multiple Module Instances Example
 ///
 /// A Substrate module can be built such that multiple instances of the same module can be used within a single
@@ -402,6 +430,34 @@ macro_rules! decl_module {
 			$($rest)*
 		);
 	};
+	// the weight may precede the docs; move it after them.
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path $(= $module_default_instance:path)?)?>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $deposit_event:tt )* }
+		{ $( $on_initialize:tt )* }
+		{ $( $on_finalize:tt )* }
+		{ $( $offchain:tt )* }
+		[ $($t:tt)* ]
+		#[weight = $weight:expr]
+		$(#[doc = $doc_attr:tt])+
+		$($rest:tt)*
+	) => {
+		$crate::decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<$trait_instance: $trait_name$(<I>, $instance: $instantiable $(= $module_default_instance)?)?>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $deposit_event )* }
+			{ $( $on_initialize )* }
+			{ $( $on_finalize )* }
+			{ $( $offchain )* }
+			[ $($t)* ]
+			$(#[doc = $doc_attr])*
+			#[weight = $weight]
+			$($rest)*
+		);
+	};
 	(@normalize
 		$(#[$attr:meta])*
 		pub struct $mod_type:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path $(= $module_default_instance:path)?)?>
@@ -412,6 +468,7 @@ macro_rules! decl_module {
 		{ $( $offchain:tt )* }
 		[ $($t:tt)* ]
 		$(#[doc = $doc_attr:tt])*
+		$(#[weight = $weight:expr])?
 		$fn_vis:vis fn $fn_name:ident(
 			$origin:ident $(, $(#[$codec_attr:ident])* $param_name:ident : $param:ty)*
 		) $( -> $result:ty )* { $( $impl:tt )* }
@@ -432,6 +489,7 @@ macro_rules! decl_module {
 					$origin $( , $(#[$codec_attr])* $param_name : $param )*
 				) $( -> $result )* { $( $impl )* }
 				{ $($instance: $instantiable)? }
+				{ $( $weight )? }
 			]
 			$($rest)*
 		);
@@ -446,6 +504,7 @@ macro_rules! decl_module {
 		{ $( $offchain:tt )* }
 		[ $($t:tt)* ]
 		$(#[doc = $doc_attr:tt])*
+		$(#[weight = $weight:expr])?
 		$fn_vis:vis fn $fn_name:ident(
 			$origin:ident : T::Origin $(, $(#[$codec_attr:ident])* $param_name:ident : $param:ty)*
 		) $( -> $result:ty )* { $( $impl:tt )* }
@@ -467,6 +526,7 @@ macro_rules! decl_module {
 		{ $( $offchain:tt )* }
 		[ $($t:tt)* ]
 		$(#[doc = $doc_attr:tt])*
+		$(#[weight = $weight:expr])?
 		$fn_vis:vis fn $fn_name:ident(
 			origin : $origin:ty $(, $(#[$codec_attr:ident])* $param_name:ident : $param:ty)*
 		) $( -> $result:ty )* { $( $impl:tt )* }
@@ -488,6 +548,7 @@ macro_rules! decl_module {
 		{ $( $offchain:tt )* }
 		[ $($t:tt)* ]
 		$(#[doc = $doc_attr:tt])*
+		$(#[weight = $weight:expr])?
 		$fn_vis:vis fn $fn_name:ident(
 			$( $(#[$codec_attr:ident])* $param_name:ident : $param:ty),*
 		) $( -> $result:ty )* { $( $impl:tt )* }
@@ -508,6 +569,7 @@ macro_rules! decl_module {
 					root $( , $(#[$codec_attr])* $param_name : $param )*
 				) $( -> $result )* { $( $impl )* }
 				{ $($instance: $instantiable)? }
+				{ $( $weight )? }
 			]
 			$($rest)*
 		);
@@ -554,6 +616,14 @@ macro_rules! decl_module {
 		<$mod_type<$trait_instance $(, $instance)?>>::$fn_name( $origin $(, $param_name )* )
 	};
 
+	// Weight of a function: the declared one or the default.
+	(@weight) => {
+		$crate::dispatch::TransactionWeight::default()
+	};
+	(@weight $weight:expr) => {
+		$weight
+	};
+
 	// no `deposit_event` function wanted
 	(@impl_deposit_event
 		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, I: $instantiable:path)?>;
@@ -872,6 +942,7 @@ macro_rules! decl_module {
 					$from:ident $( , $(#[$codec_attr:ident])* $param_name:ident : $param:ty)*
 				) $( -> $result:ty )* { $( $impl:tt )* }
 				{ $($fn_instance:ident: $fn_instantiable:path)? }
+				{ $( $weight:expr )? }
 			)*
 		}
 		{ $( $deposit_event:tt )* }
@@ -1030,6 +1101,21 @@ macro_rules! decl_module {
 				}
 			}
 		}
+		impl<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?> $crate::dispatch::Weighable
+			for $call_type<$trait_instance $(, $instance)?>
+		{
+			fn weight(&self, _len: usize) -> $crate::dispatch::Weight {
+				match self {
+					$(
+						$call_type::$fn_name(..) => $crate::dispatch::Weighable::weight(
+							&$crate::decl_module!(@weight $( $weight )?),
+							_len,
+						),
+					)*
+					$call_type::__PhantomItem(_, _) => { unreachable!("__PhantomItem should never be used.") },
+				}
+			}
+		}
 		impl<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?> $crate::dispatch::Callable
 			for $mod_type<$trait_instance $(, $instance)?>
 		{
@@ -1083,6 +1169,15 @@ macro_rules! impl_outer_dispatch {
 				}
 			}
 		}
+		impl $crate::dispatch::Weighable for $call_type {
+			fn weight(&self, len: usize) -> $crate::dispatch::Weight {
+				match self {
+					$(
+						$call_type::$camelcase(call) => $crate::dispatch::Weighable::weight(call, len),
+					)*
+				}
+			}
+		}
 		$(
 			impl $crate::dispatch::IsSubType<$camelcase> for $call_type {
 				fn is_aux_sub_type(&self) -> Option<&<$camelcase as $crate::dispatch::Callable>::Call> {
@@ -1251,6 +1346,7 @@ macro_rules! __check_reserved_fn_name {
 mod tests {
 	use super::*;
 	use crate::runtime_primitives::traits::{OnInitialize, OnFinalize};
+	use crate::runtime_primitives::weights::MAX_TRANSACTIONS_WEIGHT;
 
 	pub trait Trait {
 		type Origin;
@@ -1269,8 +1365,11 @@ mod tests {
 		pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 			/// Hi, this is a comment.
 			fn aux_0(_origin) -> Result { unreachable!() }
+			#[weight = TransactionWeight::Basic(10, 0)]
+			/// The weight may also precede the docs.
 			fn aux_1(_origin, #[compact] _data: u32) -> Result { unreachable!() }
 			fn aux_2(_origin, _data: i32, _data2: String) -> Result { unreachable!() }
+			#[weight = TransactionWeight::Max]
 			fn aux_3() -> Result { unreachable!() }
 			fn aux_4(_data: i32) -> Result { unreachable!() }
 			fn aux_5(_origin, _data: i32, #[compact] _data2: u32) -> Result { unreachable!() }
@@ -1297,7 +1396,9 @@ mod tests {
 							ty: DecodeDifferent::Encode("Compact<u32>")
 						}
 					]),
-					documentation: DecodeDifferent::Encode(&[]),
+					documentation: DecodeDifferent::Encode(&[
+						" The weight may also precede the docs."
+					]),
 				},
 				FunctionMetadata {
 					name: DecodeDifferent::Encode("aux_2"),
@@ -1385,6 +1486,15 @@ mod tests {
 		assert_eq!(decoded, call);
 	}
 
+	#[test]
+	fn weight_should_attach_to_call_enum() {
+		// declared weights.
+		assert_eq!(Call::<TraitImpl>::aux_1(1).weight(100), 10);
+		assert_eq!(Call::<TraitImpl>::aux_3().weight(100), MAX_TRANSACTIONS_WEIGHT);
+		// default weight is the length of the extrinsic.
+		assert_eq!(Call::<TraitImpl>::aux_0().weight(100), 100);
+	}
+
 	#[test]
 	#[should_panic(expected = "on_initialize")]
 	fn on_initialize_should_work() {
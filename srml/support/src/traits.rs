@@ -18,6 +18,7 @@
 
 use crate::rstd::result;
 use crate::codec::{Codec, Encode, Decode};
+use crate::runtime_primitives::weights::Weight;
 use crate::runtime_primitives::traits::{
 	MaybeSerializeDebug, SimpleArithmetic
 };
@@ -104,13 +105,13 @@ pub enum UpdateBalanceOutcome {
 ///
 /// It operates over a single generic `AccountId` type.
 pub trait MakePayment<AccountId> {
-	/// Make transaction payment from `who` for an extrinsic of encoded length
-	/// `encoded_len` bytes. Return `Ok` iff the payment was successful.
-	fn make_payment(who: &AccountId, encoded_len: usize) -> Result<(), &'static str>;
+	/// Make transaction payment from `who` for an extrinsic of the given `weight`.
+	/// Return `Ok` iff the payment was successful.
+	fn make_payment(who: &AccountId, weight: Weight) -> Result<(), &'static str>;
 }
 
 impl<T> MakePayment<T> for () {
-	fn make_payment(_: &T, _: usize) -> Result<(), &'static str> { Ok(()) }
+	fn make_payment(_: &T, _: Weight) -> Result<(), &'static str> { Ok(()) }
 }
 
 /// Handler for when some currency "account" decreased in balance for
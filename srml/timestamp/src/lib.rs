@@ -95,6 +95,7 @@ use parity_codec::Decode;
 use inherents::ProvideInherentData;
 use srml_support::{StorageValue, Parameter, decl_storage, decl_module};
 use srml_support::for_each_tuple;
+use srml_support::dispatch::TransactionWeight;
 use runtime_primitives::traits::{SimpleArithmetic, Zero, SaturatedConversion};
 use system::ensure_none;
 use inherents::{RuntimeString, InherentIdentifier, ProvideInherent, IsFatalError, InherentData};
@@ -220,6 +221,7 @@ decl_module! {
 		/// The timestamp should be greater than the previous one by the amount specified by `minimum_period`.
 		///
 		/// The dispatch origin for this call must be `Inherent`.
+		#[weight = TransactionWeight::Free]
 		fn set(origin, #[compact] now: T::Moment) {
 			ensure_none(origin)?;
 			assert!(!<Self as Store>::DidUpdate::exists(), "Timestamp must be updated only once in the block");